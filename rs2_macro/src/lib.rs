@@ -8,7 +8,8 @@ use syn::{
 };
 
 /// Convert a list of OpCode definitions (R, I, J type) into varying
-/// parts of associated machinery.
+/// parts of associated machinery: a decoder, a disassembler, and the list
+/// of instruction names.
 ///
 /// Defining an instruction as `(NAME, fn, op, delay)`,
 /// this takes 2 parameters:
@@ -31,6 +32,9 @@ pub fn mips_ops(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 	let parsed_input = parse_macro_input!(input as Expr);
 	let mut r_type_matches_tokens = None;
 	let mut ij_type_matches_tokens = None;
+	let mut r_type_disassembly_tokens = None;
+	let mut ij_type_disassembly_tokens = None;
+	let mut names = vec![];
 
 	match parsed_input {
 		Expr::Array(outer_list) => {
@@ -40,10 +44,14 @@ pub fn mips_ops(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 				if let Expr::Array(instruction_list) = el {
 					match i {
 						0 => {
-							r_type_matches_tokens = Some(r_type_matches(instruction_list));
+							let (matches, disassembly) = r_type_matches(instruction_list, &mut names);
+							r_type_matches_tokens = Some(matches);
+							r_type_disassembly_tokens = Some(disassembly);
 						},
 						1 => {
-							ij_type_matches_tokens = Some(ij_type_matches(instruction_list));
+							let (matches, disassembly) = ij_type_matches(instruction_list, &mut names);
+							ij_type_matches_tokens = Some(matches);
+							ij_type_disassembly_tokens = Some(disassembly);
 						},
 						_ => panic!("There is no additional instruction family here to handle..."),
 					}
@@ -53,6 +61,8 @@ pub fn mips_ops(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 		_ => panic!("Params given to ops macro were not in a list!"),
 	};
 
+	let reserved = reserved_instruction_tokens();
+
 	let op_fn = quote!{
 		/// Convert a 32-bit instruction into an [`OpCode`](pipeline/struct.OpCode.html), for later queueing/execution.
 		///
@@ -78,22 +88,61 @@ pub fn mips_ops(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 				// I, J instructions
 				#ij_type_matches_tokens
 
-				_ => debug!(
-					"Unknown opcode {:06b}: data {:026b}.",
-					raw_opcode,
-					(instruction << 6) >> 6,
-				),
+				_ => {
+					debug!(
+						"Unknown opcode {:06b}: data {:026b}.",
+						raw_opcode,
+						(instruction << 6) >> 6,
+					);
+					#reserved
+				},
 			}
 
 			out
 		}
+
+		/// Render a 32-bit instruction as assembly, resolving branch targets
+		/// against `pc` (the instruction's address) where it is known.
+		pub fn disassemble(instruction: u32, pc: Option<u32>) -> String {
+			if instruction == 0 {
+				return "nop".to_string();
+			}
+
+			let raw_opcode = instruction.get_opcode();
+			let opcode = crate::isa::mips::Opcode::from_u8(raw_opcode);
+
+			match opcode {
+				// R/switched instructions
+				#r_type_disassembly_tokens
+
+				// I, J instructions
+				#ij_type_disassembly_tokens
+
+				_ => crate::isa::mips::disassembly::undecodable(instruction),
+			}
+		}
+
+		/// Family and name of every instruction, as given to the disassembler.
+		pub const INSTRUCTION_NAMES: &[(&str, &str)] = &[#(#names),*];
 	};
 
 	proc_macro::TokenStream::from(op_fn)
 }
 
-fn r_type_matches(instructions: &ExprArray) -> proc_macro2::TokenStream {
+/// Fill out `out` so that it raises a Reserved Instruction exception
+/// (see `crate::core::ops::reserved_instruction`) when executed.
+fn reserved_instruction_tokens() -> proc_macro2::TokenStream {
+	quote!{
+		out.action = crate::core::ops::reserved_instruction as crate::core::pipeline::EEAction;
+		out.delay = 1;
+		out.requirements = crate::core::constants::requirements::ALU.fuse_registers(Default::default());
+	}
+}
+
+fn r_type_matches(instructions: &ExprArray, names: &mut Vec<proc_macro2::TokenStream>) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
 	let mut match_parts = vec![];
+	let mut disassembly_parts = vec![];
+	let reserved = reserved_instruction_tokens();
 
 	for family in instructions.elems.clone().iter_mut() {
 		if let Expr::Tuple(ref mut family_data) = family {
@@ -105,10 +154,11 @@ fn r_type_matches(instructions: &ExprArray) -> proc_macro2::TokenStream {
 			let op_name = family_elems.pop().unwrap().into_value();
 			let op_code = family_elems.pop().unwrap().into_value();
 
-			let r_type_matches_tokens = if let Expr::Array(instruction_list) = funcs {
-				Some(individual_r_type_matches(&instruction_list))
+			let (r_type_matches_tokens, r_type_disassembly_tokens) = if let Expr::Array(instruction_list) = funcs {
+				let (matches, disassembly) = individual_r_type_matches(&instruction_list, &op_name, names);
+				(Some(matches), Some(disassembly))
 			} else {
-				None
+				(None, None)
 			};
 
 			match_parts.push(quote!{
@@ -118,23 +168,38 @@ fn r_type_matches(instructions: &ExprArray) -> proc_macro2::TokenStream {
 
 					match #op_codec(instruction) {
 						#r_type_matches_tokens
-						_ => debug!(
-							"Unknown {}-type instruction {:06b}: data {:020b}.",
-							#op_name,
-							raw_func,
-							(instruction << 6) >> 12,
-						),
+						_ => {
+							debug!(
+								"Unknown {}-type instruction {:06b}: data {:020b}.",
+								#op_name,
+								raw_func,
+								(instruction << 6) >> 12,
+							);
+							#reserved
+						},
 					}
 				},
 			});
+
+			disassembly_parts.push(quote!{
+				Some(#op_code) => match #op_codec(instruction) {
+					#r_type_disassembly_tokens
+					_ => crate::isa::mips::disassembly::undecodable(instruction),
+				},
+			});
 		}
 	}
 
-	quote!{#(#match_parts)*}
+	(quote!{#(#match_parts)*}, quote!{#(#disassembly_parts)*})
 }
 
-fn individual_r_type_matches(instructions: &ExprArray) -> proc_macro2::TokenStream {
+fn individual_r_type_matches(
+	instructions: &ExprArray,
+	family_name: &Expr,
+	names: &mut Vec<proc_macro2::TokenStream>,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
 	let mut match_parts = vec![];
+	let mut disassembly_parts = vec![];
 
 	for instruction in instructions.elems.clone().iter_mut() {
 		if let Expr::Tuple(ref mut instruction_data) = instruction {
@@ -163,14 +228,25 @@ fn individual_r_type_matches(instructions: &ExprArray) -> proc_macro2::TokenStre
 					out.requirements = requirements;
 				},
 			});
+
+			names.push(quote!{(#family_name, stringify!(#op_name))});
+			disassembly_parts.push(quote!{
+				Some(#func_code) => crate::isa::mips::disassembly::render(
+					#family_name,
+					stringify!(#op_name),
+					instruction,
+					pc,
+				),
+			});
 		}
 	}
 
-	quote!{#(#match_parts)*}
+	(quote!{#(#match_parts)*}, quote!{#(#disassembly_parts)*})
 }
 
-fn ij_type_matches(instructions: &ExprArray) -> proc_macro2::TokenStream {
+fn ij_type_matches(instructions: &ExprArray, names: &mut Vec<proc_macro2::TokenStream>) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
 	let mut match_parts = vec![];
+	let mut disassembly_parts = vec![];
 	for instruction in instructions.elems.clone().iter_mut() {
 		if let Expr::Tuple(ref mut instruction_data) = instruction {
 			let elems = &mut instruction_data.elems;
@@ -196,8 +272,18 @@ fn ij_type_matches(instructions: &ExprArray) -> proc_macro2::TokenStream {
 					out.requirements = requirements;
 				},
 			});
+
+			names.push(quote!{("", stringify!(#op_name))});
+			disassembly_parts.push(quote!{
+				Some(#func_code) => crate::isa::mips::disassembly::render(
+					"",
+					stringify!(#op_name),
+					instruction,
+					pc,
+				),
+			});
 		}
 	}
 
-	quote!{#(#match_parts)*}
+	(quote!{#(#match_parts)*}, quote!{#(#disassembly_parts)*})
 }
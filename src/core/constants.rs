@@ -11,6 +11,10 @@ pub const COP0_REGISTER_WIDTH: usize = 32;
 pub const COP0_REGISTER_WIDTH_BYTES: usize = COP0_REGISTER_WIDTH / 8;
 pub const COP0_REGISTER_FILE_SIZE: usize = REGISTER_COUNT * COP0_REGISTER_WIDTH_BYTES;
 
+pub const COP1_REGISTER_WIDTH: usize = 32;
+pub const COP1_REGISTER_WIDTH_BYTES: usize = COP1_REGISTER_WIDTH / 8;
+pub const COP1_REGISTER_FILE_SIZE: usize = REGISTER_COUNT * COP1_REGISTER_WIDTH_BYTES;
+
 /// Implementation numbers and their relation to PRId are discussed in the
 /// *EE Core User's Manual 6.0*, pp.77.
 pub const EE_IMPL: u32  = 0x2e;
@@ -19,6 +23,12 @@ pub const IOP_IMPL: u32 = 0x00;
 pub const EE_PRID: u32 = EE_IMPL << 8;
 pub const IOP_PRID: u32 = IOP_IMPL << 8;
 
+/// Implementation and revision numbers of the FPU, as reported in FCR0.
+pub const FPU_IMPL: u32 = 0x2e;
+pub const FPU_REV: u32 = 0x30;
+
+pub const EE_FCR0: u32 = (FPU_IMPL << 8) | FPU_REV;
+
 pub mod timings {
 	//! Timings relating to operations and instructions.
 	//! These are informed by the *EE Core User's Manual 6.0*,
@@ -45,6 +55,10 @@ pub mod timings {
 	pub const FLOAT_RSQRT_DELAY: u8 = 14;
 	pub const FLOAT_MADD_DELAY: u8 = 4;
 	pub const FLOAT_LWC1_DELAY: u8 = 2;
+
+	// COP2 (VU0 macro mode) is not covered by the above, and follows VU0's own latencies.
+	pub const VECTOR_MOVE_DELAY: u8 = 1;
+	pub const VECTOR_OPERATE_DELAY: u8 = 4;
 }
 
 pub mod requirements {
@@ -80,10 +94,10 @@ pub mod requirements {
 	/// Pipeline requirement for COP1 (FPU) Operations.
 	pub const COP1_OPERATE: Requirement<Pipe> = Requirement::Joint(Pipe::COP1_OPERATE);
 
-	/// Pipeline requirement for COP1 (VU) Loads/Stores.
+	/// Pipeline requirement for COP2 (VU) Loads/Stores.
 	pub const COP2_MOVE: Requirement<Pipe> = Requirement::Joint(Pipe::COP2_MOVE);
 
-	/// Pipeline requirement for COP0 (VU) Operations.
+	/// Pipeline requirement for COP2 (VU) Operations.
 	pub const COP2_OPERATE: Requirement<Pipe> = Requirement::Joint(Pipe::COP2_OPERATE);
 
 	/// Pipeline requirement for Arithmetic, Shift, Logical, Trap, Syscall, Break.
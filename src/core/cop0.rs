@@ -194,6 +194,14 @@ impl Status {
 			PrivilegeLevel::Kernel(ExceptionLevel::NoException)
 		}
 	}
+
+	/// Whether interrupts may currently be taken: `IE` and `EIE` must be set,
+	/// and the core must not already be handling an exception.
+	#[inline]
+	pub fn accepts_interrupts(self) -> bool {
+		self.contains(Self::INTERRUPT_ENABLE | Self::ENABLE_IE)
+			&& !self.intersects(Self::EXCEPTION_LEVEL | Self::ERROR_LEVEL)
+	}
 }
 
 bitflags!{
@@ -213,11 +221,11 @@ pub struct Cause: u32 {
 		| Self::EXCEPTION_CODE_L1_B3.bits
 		| Self::EXCEPTION_CODE_L1_B4.bits;
 
-	/// Set when an I[0] interrupt is pending.
-	const PENDING_INTERRUPT_I1    = 0b0000_0000_0000_0000_0000_0100_0000_0000;
+	/// Set when an Int[0] (INTC) interrupt is pending.
+	const PENDING_INTERRUPT_I0    = 0b0000_0000_0000_0000_0000_0100_0000_0000;
 
-	/// Set when an I[1] interrupt is pending.
-	const PENDING_INTERRUPT_I0    = 0b0000_0000_0000_0000_0000_1000_0000_0000;
+	/// Set when an Int[1] (DMAC) interrupt is pending.
+	const PENDING_INTERRUPT_I1    = 0b0000_0000_0000_0000_0000_1000_0000_0000;
 
 	/// Set when a timer interrupt is pending.
 	const PENDING_INTERRUPT_TIMER = 0b0000_0000_0000_0000_1000_0000_0000_0000;
@@ -336,6 +344,12 @@ pub fn get_writable_bitmask(index: u8) -> u32 {
 
 pub const WIRED_DEFAULT: u32 = 0;
 
+/// Set in Index by TLBP when no TLB line matches.
+pub const INDEX_PROBE_FAILURE: u32 = 0x8000_0000;
+
+/// The bits of Index which select a TLB line.
+pub const INDEX_MASK: u32 = 0x3f;
+
 pub const RANDOM_DEFAULT: u32 = RANDOM_MAX;
 
 // Note: these are the way they are because the tlb size is 48.
@@ -0,0 +1,117 @@
+use bitflags::bitflags;
+use enum_primitive::*;
+use super::fpu::FpuFlags;
+
+enum_from_primitive!{
+/// Names of the COP1 (FPU) control registers, accessed via `CFC1` and `CTC1`.
+/// These are defined within the *EE Core User's Manual 6.0*, pp.158.
+#[derive(Debug, PartialEq)]
+pub enum ControlRegister {
+	/// Implementation/Revision register. Read-only.
+	Implementation = 0,
+
+	/// Control/Status register.
+	ControlStatus = 31,
+}
+}
+
+bitflags!{
+/// Flags contained within COP1's control/status register (FCR31).
+/// These are defined within the *EE Core User's Manual 6.0*, pp.159.
+pub struct Fcr31: u32 {
+	/// Sticky underflow flag: set alongside
+	/// [`UNDERFLOW`](#associatedconstant.UNDERFLOW), cleared only by software.
+	const STICKY_UNDERFLOW = 0b0000_0000_0000_0000_0000_0000_0000_1000;
+
+	/// Sticky overflow flag: set alongside
+	/// [`OVERFLOW`](#associatedconstant.OVERFLOW), cleared only by software.
+	const STICKY_OVERFLOW  = 0b0000_0000_0000_0000_0000_0000_0001_0000;
+
+	/// Sticky divide-by-zero flag: set alongside
+	/// [`DIVIDE_BY_ZERO`](#associatedconstant.DIVIDE_BY_ZERO), cleared only by software.
+	const STICKY_DIVIDE    = 0b0000_0000_0000_0000_0000_0000_0010_0000;
+
+	/// Sticky invalid operation flag: set alongside
+	/// [`INVALID`](#associatedconstant.INVALID), cleared only by software.
+	const STICKY_INVALID   = 0b0000_0000_0000_0000_0000_0000_0100_0000;
+
+	/// Set when the last operation underflowed.
+	const UNDERFLOW        = 0b0000_0000_0000_0000_0100_0000_0000_0000;
+
+	/// Set when the last operation overflowed.
+	const OVERFLOW         = 0b0000_0000_0000_0000_1000_0000_0000_0000;
+
+	/// Set when the last division had a zero divisor.
+	const DIVIDE_BY_ZERO   = 0b0000_0000_0000_0001_0000_0000_0000_0000;
+
+	/// Set when the last operation was invalid (e.g., `0/0`, `sqrt(-x)`).
+	const INVALID          = 0b0000_0000_0000_0010_0000_0000_0000_0000;
+
+	/// Condition bit, set by `C.cond.S` and consumed by `BC1T`/`BC1F`.
+	const CONDITION        = 0b0000_0000_1000_0000_0000_0000_0000_0000;
+
+	/// Bits which always read as `1`.
+	const FIXED_ONES       = 0b0000_0001_0000_0000_0000_0000_0000_0001;
+
+	/// All non-sticky exception flags.
+	const CAUSE = Self::UNDERFLOW.bits
+		| Self::OVERFLOW.bits
+		| Self::DIVIDE_BY_ZERO.bits
+		| Self::INVALID.bits;
+
+	/// Flags updated by arithmetic (add, subtract, multiply).
+	const ARITHMETIC_CAUSE = Self::UNDERFLOW.bits
+		| Self::OVERFLOW.bits;
+
+	/// All sticky exception flags.
+	const STICKY = Self::STICKY_UNDERFLOW.bits
+		| Self::STICKY_OVERFLOW.bits
+		| Self::STICKY_DIVIDE.bits
+		| Self::STICKY_INVALID.bits;
+
+	/// All bits which may be changed by `CTC1`.
+	const WRITABLE = Self::CAUSE.bits
+		| Self::STICKY.bits
+		| Self::CONDITION.bits;
+}
+}
+
+impl From<FpuFlags> for Fcr31 {
+	/// Convert raised flags into their cause and sticky bits.
+	fn from(flags: FpuFlags) -> Self {
+		let mut out = Self::empty();
+
+		if flags.contains(FpuFlags::UNDERFLOW) {
+			out |= Self::UNDERFLOW | Self::STICKY_UNDERFLOW;
+		}
+
+		if flags.contains(FpuFlags::OVERFLOW) {
+			out |= Self::OVERFLOW | Self::STICKY_OVERFLOW;
+		}
+
+		if flags.contains(FpuFlags::DIVIDE_BY_ZERO) {
+			out |= Self::DIVIDE_BY_ZERO | Self::STICKY_DIVIDE;
+		}
+
+		if flags.contains(FpuFlags::INVALID) {
+			out |= Self::INVALID | Self::STICKY_INVALID;
+		}
+
+		out
+	}
+}
+
+impl Default for Fcr31 {
+	fn default() -> Self {
+		Self::FIXED_ONES
+	}
+}
+
+/// Used to write-protect certain bits/fields of the FPU control registers
+/// which should (from the perspecive of running code) be immutable.
+pub fn get_writable_bitmask(index: u8) -> u32 {
+	match ControlRegister::from_u8(index) {
+		Some(ControlRegister::ControlStatus) => Fcr31::WRITABLE.bits(),
+		_ => 0,
+	}
+}
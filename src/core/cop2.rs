@@ -0,0 +1,84 @@
+use bitflags::bitflags;
+use enum_primitive::*;
+
+/// Number of VI registers, which take the first COP2 control register indices.
+pub const VI_COUNT: u8 = 16;
+
+enum_from_primitive!{
+/// Names of the COP2 (VU0) control registers beyond VI0--VI15,
+/// accessed via `CFC2` and `CTC2`.
+#[derive(Debug, PartialEq)]
+pub enum ControlRegister {
+	/// Status flags. Only the sticky flags are writable.
+	Status = 16,
+
+	/// MAC flags. Read-only.
+	Mac = 17,
+
+	/// Clipping flags.
+	Clipping = 18,
+
+	R = 20,
+	I = 21,
+	Q = 22,
+
+	/// Address of VU0's next microinstruction, in doublewords. Read-only.
+	Tpc = 26,
+
+	/// Microprogram address used by `VCALLMSR`, in doublewords.
+	Cmsar0 = 27,
+
+	/// Break and reset control of both VUs. See [`Fbrst`](struct.Fbrst.html).
+	Fbrst = 28,
+
+	/// Execution state of both VUs. Read-only. See [`VpuStat`](struct.VpuStat.html).
+	VpuStat = 29,
+
+	/// Writing an address (in doublewords) starts a VU1 microprogram there.
+	Cmsar1 = 31,
+}
+}
+
+/// Shift from each VU0 bit of [`Fbrst`](struct.Fbrst.html) and
+/// [`VpuStat`](struct.VpuStat.html) to its VU1 twin.
+pub const VU1_SHIFT: u32 = 8;
+
+bitflags!{
+/// Bits of FBRST, for VU0. VU1's sit [`VU1_SHIFT`](constant.VU1_SHIFT.html) higher.
+pub struct Fbrst: u32 {
+	/// Stop the running microprogram. Reads as `0`.
+	const FORCE_BREAK  = 0b0001;
+
+	/// Stop the running microprogram and clear the flags. Reads as `0`.
+	const RESET        = 0b0010;
+
+	/// Allow the `D` bit to stop microprograms.
+	const DEBUG_ENABLE = 0b0100;
+
+	/// Allow the `T` bit to stop microprograms.
+	const TRACE_ENABLE = 0b1000;
+
+	/// Bits held, for both VUs.
+	const HELD = Self::DEBUG_ENABLE.bits
+		| Self::TRACE_ENABLE.bits
+		| (Self::DEBUG_ENABLE.bits << VU1_SHIFT)
+		| (Self::TRACE_ENABLE.bits << VU1_SHIFT);
+}
+}
+
+impl Default for Fbrst {
+	fn default() -> Self {
+		Self::empty()
+	}
+}
+
+bitflags!{
+/// Bits of VPU-STAT, for VU0. VU1's sit [`VU1_SHIFT`](constant.VU1_SHIFT.html) higher.
+pub struct VpuStat: u32 {
+	/// A microprogram is running.
+	const BUSY                = 0b0001;
+
+	/// The last microprogram was stopped by `FBRST`.
+	const FORCE_BREAK_STOPPED = 0b1000;
+}
+}
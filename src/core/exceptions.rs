@@ -15,8 +15,11 @@ pub mod vectors {
 use enum_primitive::*;
 use super::{
 	cop0::{
+		self,
 		Cause,
 		Config,
+		Context,
+		EntryHi,
 		Register,
 		Status,
 	},
@@ -147,11 +150,15 @@ fn bad_p_addr(cpu: &mut EECore, addr: u32) {
 
 #[inline]
 fn fill_ctx_entryhi(cpu: &mut EECore, addr: u32) {
-	// Fill out COntext Register w/ 19 hi-order bits
-	// and on page table address.
+	// Fill out Context Register w/ 19 hi-order bits (BadVPN2),
+	// keeping the page table address (PTEBase).
+	let vpn2 = addr >> 13;
+	let context = cpu.read_cop0_direct(Register::Context as u8);
+	cpu.write_cop0_direct(Register::Context as u8, cop0::context_from_parts(context.get_pte_base(), vpn2));
 
 	// Fill out EntryHi using addr and current ASID.
-	// unimplemented!()
+	let asid = cpu.read_cop0_direct(Register::EntryHi as u8).get_asid();
+	cpu.write_cop0_direct(Register::EntryHi as u8, cop0::entry_hi_from_parts(vpn2, asid));
 }
 
 enum_from_primitive!{
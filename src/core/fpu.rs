@@ -0,0 +1,571 @@
+//! Soft-float implementation of the EE's single-precision arithmetic.
+//!
+//! The EE FPU does not follow IEEE 754. As described in the
+//! *EE Core User's Manual 6.0*, pp.155--157:
+//! * There are no infinities or NaNs: an exponent of `255` is an ordinary
+//!   exponent, so the largest magnitude is `0x7fff_ffff` (*Fmax*).
+//! * Results which overflow are clamped to ±Fmax, raising `O`.
+//! * Denormal operands are read as zero (keeping their sign), and results which
+//!   would be denormal are flushed to zero, raising `U`.
+//! * All results are rounded toward zero.
+//!
+//! Every operation here works on raw bit patterns, computes the exact result
+//! in integer space, and truncates it exactly once, so results do not depend
+//! on the host's float environment.
+
+use bitflags::bitflags;
+use std::cmp::Ordering;
+
+/// Largest representable magnitude.
+pub const FMAX: u32 = 0x7fff_ffff;
+
+pub const SIGN_BIT: u32 = 0x8000_0000;
+
+const EXPONENT_MASK: u32 = 0x7f80_0000;
+const MANTISSA_MASK: u32 = 0x007f_ffff;
+const IMPLICIT_BIT: u32 = 0x0080_0000;
+const MANTISSA_BITS: i32 = 23;
+const EXPONENT_BIAS: i32 = 127;
+const MAX_BIASED_EXPONENT: i32 = 255;
+
+/// Extra precision carried while aligning operands for addition.
+const ADD_GUARD_BITS: u32 = 32;
+
+/// Extra precision carried by the dividend during division.
+const DIV_GUARD_BITS: u32 = 40;
+
+bitflags!{
+/// Exceptional conditions raised by a single operation.
+///
+/// Callers map these onto their own flag registers (e.g., FCR31).
+#[derive(Default)]
+pub struct FpuFlags: u8 {
+	const OVERFLOW       = 0b0001;
+	const UNDERFLOW      = 0b0010;
+	const DIVIDE_BY_ZERO = 0b0100;
+	const INVALID        = 0b1000;
+}
+}
+
+/// Bit pattern of a result, and the flags its computation raised.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FpuResult {
+	pub value: u32,
+	pub flags: FpuFlags,
+}
+
+impl From<u32> for FpuResult {
+	fn from(value: u32) -> Self {
+		Self::exact(value)
+	}
+}
+
+impl FpuResult {
+	#[inline]
+	fn exact(value: u32) -> Self {
+		Self {
+			value,
+			flags: FpuFlags::empty(),
+		}
+	}
+
+	#[inline]
+	fn with_flags(value: u32, flags: FpuFlags) -> Self {
+		Self {
+			value,
+			flags,
+		}
+	}
+}
+
+/// Expanded form of a float: `mantissa * 2^(exponent - 23)`.
+///
+/// Zero (and denormal) values have a mantissa of `0`.
+#[derive(Clone, Copy, Debug)]
+struct Unpacked {
+	sign: bool,
+	exponent: i32,
+	mantissa: u32,
+}
+
+impl Unpacked {
+	#[inline]
+	fn is_zero(&self) -> bool {
+		self.mantissa == 0
+	}
+}
+
+#[inline]
+fn unpack(value: u32) -> Unpacked {
+	let sign = value & SIGN_BIT != 0;
+	let biased = ((value & EXPONENT_MASK) >> MANTISSA_BITS) as i32;
+
+	if biased == 0 {
+		// Denormals are read as zero.
+		Unpacked {
+			sign,
+			exponent: 0,
+			mantissa: 0,
+		}
+	} else {
+		Unpacked {
+			sign,
+			exponent: biased - EXPONENT_BIAS,
+			mantissa: (value & MANTISSA_MASK) | IMPLICIT_BIT,
+		}
+	}
+}
+
+#[inline]
+fn signed_zero(sign: bool) -> u32 {
+	if sign { SIGN_BIT } else { 0 }
+}
+
+#[inline]
+fn signed_fmax(sign: bool) -> u32 {
+	signed_zero(sign) | FMAX
+}
+
+/// Truncate `mantissa * 2^(exponent - point)` into a float, clamping or
+/// flushing the result if it falls outside the representable range.
+///
+/// An exact zero is always positive.
+fn truncate_pack(sign: bool, exponent: i32, mantissa: u128, point: i32) -> FpuResult {
+	if mantissa == 0 {
+		return FpuResult::exact(0);
+	}
+
+	let leading_bit = 127 - mantissa.leading_zeros() as i32;
+	let biased = exponent - point + leading_bit + EXPONENT_BIAS;
+
+	let mantissa = if leading_bit >= MANTISSA_BITS {
+		mantissa >> (leading_bit - MANTISSA_BITS)
+	} else {
+		mantissa << (MANTISSA_BITS - leading_bit)
+	} as u32;
+
+	if biased > MAX_BIASED_EXPONENT {
+		FpuResult::with_flags(signed_fmax(sign), FpuFlags::OVERFLOW)
+	} else if biased < 1 {
+		FpuResult::with_flags(signed_zero(sign), FpuFlags::UNDERFLOW)
+	} else {
+		FpuResult::exact(
+			signed_zero(sign)
+				| ((biased as u32) << MANTISSA_BITS)
+				| (mantissa & MANTISSA_MASK)
+		)
+	}
+}
+
+/// Shift right, folding any discarded bits into the LSB.
+#[inline]
+fn shift_right_sticky(value: u128, amount: u32) -> u128 {
+	if amount >= 128 {
+		(value != 0) as u128
+	} else {
+		let out = value >> amount;
+		out | ((out << amount != value) as u128)
+	}
+}
+
+/// Largest `r` such that `r * r <= value`.
+fn isqrt(value: u128) -> u128 {
+	let mut remainder = value;
+	let mut root = 0u128;
+	let mut bit = 1u128 << 126;
+
+	while bit > value {
+		bit >>= 2;
+	}
+
+	while bit != 0 {
+		if remainder >= root + bit {
+			remainder -= root + bit;
+			root = (root >> 1) + bit;
+		} else {
+			root >>= 1;
+		}
+		bit >>= 2;
+	}
+
+	root
+}
+
+/// Return `value` with denormals flushed to (signed) zero.
+#[inline]
+pub fn flush(value: u32) -> u32 {
+	if value & EXPONENT_MASK == 0 {
+		value & SIGN_BIT
+	} else {
+		value
+	}
+}
+
+/// Ordering key for comparisons: there are no NaNs, and `-0 == +0`.
+#[inline]
+fn ordering_key(value: u32) -> i64 {
+	let value = flush(value);
+	let magnitude = i64::from(value & !SIGN_BIT);
+
+	if value & SIGN_BIT != 0 {
+		-magnitude
+	} else {
+		magnitude
+	}
+}
+
+pub fn compare(a: u32, b: u32) -> Ordering {
+	ordering_key(a).cmp(&ordering_key(b))
+}
+
+pub fn abs(a: u32) -> u32 {
+	a & !SIGN_BIT
+}
+
+pub fn neg(a: u32) -> u32 {
+	a ^ SIGN_BIT
+}
+
+pub fn max(a: u32, b: u32) -> u32 {
+	if compare(a, b) == Ordering::Less { b } else { a }
+}
+
+pub fn min(a: u32, b: u32) -> u32 {
+	if compare(a, b) == Ordering::Greater { b } else { a }
+}
+
+pub fn add(a: u32, b: u32) -> FpuResult {
+	let x = unpack(a);
+	let y = unpack(b);
+
+	match (x.is_zero(), y.is_zero()) {
+		(true, true) => return FpuResult::exact(signed_zero(x.sign && y.sign)),
+		(true, false) => return FpuResult::exact(b),
+		(false, true) => return FpuResult::exact(a),
+		_ => {},
+	}
+
+	let (big, small) = if x.exponent >= y.exponent { (x, y) } else { (y, x) };
+	let distance = (big.exponent - small.exponent) as u32;
+
+	let big_m = u128::from(big.mantissa) << ADD_GUARD_BITS;
+	let small_m = shift_right_sticky(u128::from(small.mantissa) << ADD_GUARD_BITS, distance);
+	let point = MANTISSA_BITS + ADD_GUARD_BITS as i32;
+
+	if big.sign == small.sign {
+		truncate_pack(big.sign, big.exponent, big_m + small_m, point)
+	} else if big_m >= small_m {
+		truncate_pack(big.sign, big.exponent, big_m - small_m, point)
+	} else {
+		truncate_pack(small.sign, big.exponent, small_m - big_m, point)
+	}
+}
+
+pub fn sub(a: u32, b: u32) -> FpuResult {
+	add(a, neg(b))
+}
+
+pub fn mul(a: u32, b: u32) -> FpuResult {
+	let x = unpack(a);
+	let y = unpack(b);
+	let sign = x.sign != y.sign;
+
+	if x.is_zero() || y.is_zero() {
+		return FpuResult::exact(signed_zero(sign));
+	}
+
+	truncate_pack(
+		sign,
+		x.exponent + y.exponent,
+		u128::from(x.mantissa) * u128::from(y.mantissa),
+		2 * MANTISSA_BITS,
+	)
+}
+
+pub fn div(a: u32, b: u32) -> FpuResult {
+	let x = unpack(a);
+	let y = unpack(b);
+	let sign = x.sign != y.sign;
+
+	if y.is_zero() {
+		let flags = if x.is_zero() {
+			FpuFlags::INVALID
+		} else {
+			FpuFlags::DIVIDE_BY_ZERO
+		};
+
+		return FpuResult::with_flags(signed_fmax(sign), flags);
+	}
+
+	if x.is_zero() {
+		return FpuResult::exact(signed_zero(sign));
+	}
+
+	let quotient = (u128::from(x.mantissa) << DIV_GUARD_BITS) / u128::from(y.mantissa);
+
+	truncate_pack(sign, x.exponent - y.exponent, quotient, DIV_GUARD_BITS as i32)
+}
+
+/// Square root of `|a|`: negative operands raise `I`.
+pub fn sqrt(a: u32) -> FpuResult {
+	let x = unpack(a);
+
+	if x.is_zero() {
+		return FpuResult::exact(signed_zero(x.sign));
+	}
+
+	let flags = if x.sign {
+		FpuFlags::INVALID
+	} else {
+		FpuFlags::empty()
+	};
+
+	// Scale the operand so that its exponent is even, leaving
+	// enough bits below the point for a 24-bit root.
+	let exponent = x.exponent - MANTISSA_BITS;
+	let shift = 40 + (exponent - 40).rem_euclid(2);
+	let root = isqrt(u128::from(x.mantissa) << shift);
+
+	let mut out = truncate_pack(false, (exponent - shift) / 2, root, 0);
+	out.flags |= flags;
+	out
+}
+
+/// Compute `a / sqrt(|b|)` with a single truncation.
+///
+/// A zero divisor raises `D`, and a negative divisor raises `I`.
+pub fn rsqrt(a: u32, b: u32) -> FpuResult {
+	let x = unpack(a);
+	let y = unpack(b);
+	let sign = x.sign != y.sign;
+
+	if y.is_zero() {
+		return FpuResult::with_flags(signed_fmax(sign), FpuFlags::DIVIDE_BY_ZERO);
+	}
+
+	let flags = if y.sign {
+		FpuFlags::INVALID
+	} else {
+		FpuFlags::empty()
+	};
+
+	if x.is_zero() {
+		return FpuResult::with_flags(signed_zero(x.sign), flags);
+	}
+
+	// floor(n / sqrt(m)) == isqrt(floor(n^2 / m)).
+	let numerator_shift = 32;
+	let exponent = y.exponent - MANTISSA_BITS;
+	let denominator_shift = exponent.rem_euclid(2);
+
+	let numerator = u128::from(x.mantissa) << numerator_shift;
+	let denominator = u128::from(y.mantissa) << denominator_shift;
+	let quotient = isqrt((numerator * numerator) / denominator);
+
+	let out_exponent = x.exponent - MANTISSA_BITS - numerator_shift
+		- (exponent - denominator_shift) / 2;
+
+	let mut out = truncate_pack(x.sign, out_exponent, quotient, 0);
+	out.flags |= flags;
+	out
+}
+
+/// Convert a 32-bit integer to a float, rounding toward zero.
+pub fn from_i32(value: i32) -> u32 {
+	let sign = value < 0;
+	let magnitude = u128::from(value.unsigned_abs());
+
+	truncate_pack(sign, 0, magnitude, 0).value
+}
+
+/// Convert a float to a 32-bit integer, rounding toward zero.
+///
+/// Out-of-range values saturate.
+pub fn to_i32(value: u32) -> i32 {
+	let x = unpack(value);
+
+	if x.is_zero() || x.exponent < 0 {
+		0
+	} else if x.exponent >= 31 {
+		if x.sign { i32::MIN } else { i32::MAX }
+	} else {
+		let magnitude = if x.exponent <= MANTISSA_BITS {
+			x.mantissa >> (MANTISSA_BITS - x.exponent)
+		} else {
+			x.mantissa << (x.exponent - MANTISSA_BITS)
+		} as i32;
+
+		if x.sign { -magnitude } else { magnitude }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const ONE: u32 = 0x3f80_0000;
+	const TWO: u32 = 0x4000_0000;
+	const THREE: u32 = 0x4040_0000;
+	const FOUR: u32 = 0x4080_0000;
+	const HALF: u32 = 0x3f00_0000;
+	const NEG_ONE: u32 = 0xbf80_0000;
+	const NEG_ZERO: u32 = SIGN_BIT;
+	const ONE_THIRD_RTZ: u32 = 0x3eaa_aaaa;
+	const BELOW_ONE: u32 = 0x3f7f_ffff;
+	const SMALLEST_NORMAL: u32 = 0x0080_0000;
+	const DENORMAL: u32 = 0x0040_0000;
+	const IEEE_INF: u32 = 0x7f80_0000;
+	const IEEE_NAN: u32 = 0x7fc0_0000;
+	const NONE: FpuFlags = FpuFlags::empty();
+	const O: FpuFlags = FpuFlags::OVERFLOW;
+	const U: FpuFlags = FpuFlags::UNDERFLOW;
+	const D: FpuFlags = FpuFlags::DIVIDE_BY_ZERO;
+	const I: FpuFlags = FpuFlags::INVALID;
+
+	fn check_binary(name: &str, op: fn(u32, u32) -> FpuResult, cases: &[(u32, u32, u32, FpuFlags)]) {
+		for (i, &(a, b, value, flags)) in cases.iter().enumerate() {
+			assert_eq!(
+				op(a, b),
+				FpuResult { value, flags },
+				"{} case {}: {:08x}, {:08x}", name, i, a, b,
+			);
+		}
+	}
+
+	#[test]
+	fn add_table() {
+		check_binary("add", add, &[
+			(ONE, TWO, THREE, NONE),
+			(ONE, NEG_ONE, 0, NONE),
+			(NEG_ZERO, NEG_ZERO, NEG_ZERO, NONE),
+			(NEG_ZERO, 0, 0, NONE),
+			// Exponent 255 is an ordinary number.
+			(IEEE_INF, 0, IEEE_INF, NONE),
+			(IEEE_NAN, NEG_ZERO, IEEE_NAN, NONE),
+			// Clamping.
+			(FMAX, FMAX, FMAX, O),
+			(FMAX | SIGN_BIT, FMAX | SIGN_BIT, FMAX | SIGN_BIT, O),
+			(0x7f00_0000, 0x7f00_0000, 0x7f80_0000, NONE),
+			// Denormal operands read as zero.
+			(DENORMAL, ONE, ONE, NONE),
+			(DENORMAL, DENORMAL, 0, NONE),
+			// Flushing.
+			(SMALLEST_NORMAL | 1, SMALLEST_NORMAL | SIGN_BIT, 0, U),
+			// Truncation: 1 - tiny rounds toward zero.
+			(ONE, 0x9f80_0000, BELOW_ONE, NONE),
+			// ...and 1 + tiny does not round up.
+			(ONE, 0x1f80_0000, ONE, NONE),
+		]);
+	}
+
+	#[test]
+	fn sub_table() {
+		check_binary("sub", sub, &[
+			(THREE, ONE, TWO, NONE),
+			(ONE, ONE, 0, NONE),
+			(FMAX | SIGN_BIT, FMAX, FMAX | SIGN_BIT, O),
+			(SMALLEST_NORMAL | 1, SMALLEST_NORMAL, 0, U),
+		]);
+	}
+
+	#[test]
+	fn mul_table() {
+		check_binary("mul", mul, &[
+			(TWO, THREE, 0x40c0_0000, NONE),
+			(NEG_ONE, 0, NEG_ZERO, NONE),
+			(NEG_ONE, DENORMAL, NEG_ZERO, NONE),
+			(FMAX, TWO, FMAX, O),
+			(FMAX, NEG_ONE, FMAX | SIGN_BIT, NONE),
+			(SMALLEST_NORMAL, HALF, 0, U),
+			(SMALLEST_NORMAL | SIGN_BIT, HALF, NEG_ZERO, U),
+			// 1.99999988 * 1.99999988 = 3.99999952...: truncated.
+			(0x3fff_ffff, 0x3fff_ffff, 0x407f_fffe, NONE),
+		]);
+	}
+
+	#[test]
+	fn div_table() {
+		check_binary("div", div, &[
+			(ONE, THREE, ONE_THIRD_RTZ, NONE),
+			(FOUR, TWO, TWO, NONE),
+			(ONE, 0, FMAX, D),
+			(NEG_ONE, 0, FMAX | SIGN_BIT, D),
+			(ONE, NEG_ZERO, FMAX | SIGN_BIT, D),
+			(0, 0, FMAX, I),
+			(ONE, DENORMAL, FMAX, D),
+			(0, ONE, 0, NONE),
+			(FMAX, HALF, FMAX, O),
+			(SMALLEST_NORMAL, TWO, 0, U),
+		]);
+	}
+
+	#[test]
+	fn sqrt_table() {
+		let cases = [
+			(FOUR, TWO, NONE),
+			(0x4110_0000, THREE, NONE),
+			(0, 0, NONE),
+			(NEG_ZERO, NEG_ZERO, NONE),
+			(DENORMAL, 0, NONE),
+			// Negative operands use their magnitude.
+			(0xc080_0000, TWO, I),
+			// sqrt(2), truncated.
+			(TWO, 0x3fb5_04f3, NONE),
+		];
+
+		for (i, &(a, value, flags)) in cases.iter().enumerate() {
+			assert_eq!(sqrt(a), FpuResult { value, flags }, "sqrt case {}", i);
+		}
+	}
+
+	#[test]
+	fn rsqrt_table() {
+		check_binary("rsqrt", rsqrt, &[
+			(ONE, FOUR, HALF, NONE),
+			(THREE, FOUR, 0x3fc0_0000, NONE),
+			(ONE, 0xc080_0000, HALF, I),
+			(ONE, 0, FMAX, D),
+			(0, FOUR, 0, NONE),
+			// 1 / sqrt(2), truncated.
+			(ONE, TWO, 0x3f35_04f3, NONE),
+		]);
+	}
+
+	#[test]
+	fn compare_ignores_zero_sign_and_denormals() {
+		assert_eq!(compare(0, NEG_ZERO), Ordering::Equal);
+		assert_eq!(compare(DENORMAL, 0), Ordering::Equal);
+		assert_eq!(compare(NEG_ONE, ONE), Ordering::Less);
+		assert_eq!(compare(IEEE_NAN, FMAX), Ordering::Less);
+		assert_eq!(compare(FMAX | SIGN_BIT, NEG_ONE), Ordering::Less);
+	}
+
+	#[test]
+	fn conversion_table() {
+		for &(int, float) in [
+			(0, 0),
+			(1, ONE),
+			(-1, NEG_ONE),
+			(3, THREE),
+			// 2^24 + 1 is not representable: truncated.
+			(16_777_217, 0x4b80_0000),
+			(i32::MAX, 0x4eff_ffff),
+			(i32::MIN, 0xcf00_0000),
+		].iter() {
+			assert_eq!(from_i32(int), float, "from {}", int);
+		}
+
+		for &(float, int) in [
+			(0x3fff_ffff, 1),
+			(0xbfff_ffff, -1),
+			(HALF, 0),
+			(DENORMAL, 0),
+			(0x4eff_ffff, 0x7fff_ff80),
+			(FMAX, i32::MAX),
+			(FMAX | SIGN_BIT, i32::MIN),
+			(0xcf00_0000, i32::MIN),
+		].iter() {
+			assert_eq!(to_i32(float), int, "to {:08x}", float);
+		}
+	}
+}
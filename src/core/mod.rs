@@ -1,6 +1,9 @@
 pub mod constants;
 pub mod cop0;
+pub mod cop1;
+pub mod cop2;
 pub mod exceptions;
+pub mod fpu;
 pub mod mode;
 pub mod ops;
 pub mod pipeline;
@@ -12,8 +15,28 @@ use byteorder::{
 	ByteOrder,
 };
 use crate::{
+	dmac::{
+		self,
+		ChannelId,
+		Dmac,
+	},
+	gif::{
+		self,
+		Gif,
+	},
+	gs::{
+		self,
+		Gs,
+		VBlank,
+	},
+	intc::{
+		self,
+		Intc,
+		Interrupt,
+	},
 	isa::mips::Capability,
 	memory::{
+		bus::BusAccess,
 		constants::*,
 		mmu::{
 			self,
@@ -22,9 +45,25 @@ use crate::{
 		},
 		Memory,
 	},
+	timer::{
+		self,
+		Timers,
+	},
+	vif::{
+		self,
+		Vif,
+	},
+	vu::{
+		self,
+		VectorUnit,
+	},
 };
 use constants::*;
 use cop0::*;
+use cop1::{
+	ControlRegister,
+	Fcr31,
+};
 use enum_primitive::*;
 use exceptions::{
 	L1Exception,
@@ -33,18 +72,33 @@ use exceptions::{
 use mode::PrivilegeLevel;
 use pipeline::*;
 use std::{
+	cell::RefCell,
 	cmp::Reverse,
 	collections::BinaryHeap,
+	rc::Rc,
 };
 
 pub struct EECore {
 	pub register_file: [u8; REGISTER_FILE_SIZE],
 	pub cop0_register_file: [u8; COP0_REGISTER_FILE_SIZE],
+	pub cop1_register_file: [u8; COP1_REGISTER_FILE_SIZE],
 	pub hi: [u8; REGISTER_WIDTH_BYTES],
 	pub lo: [u8; REGISTER_WIDTH_BYTES],
 	pub sa_register: u32,
 	pub pc_register: u32,
 
+	/// FPU implementation/revision register (FCR0).
+	pub fcr0: u32,
+	/// FPU control/status register (FCR31).
+	pub fcr31: u32,
+	/// FPU accumulator, used by the `*A.S` and `MADD`/`MSUB` families.
+	pub fpu_accumulator: u32,
+
+	/// Microprogram address used by `VCALLMSR`, in doublewords (CMSAR0).
+	pub cmsar0: u16,
+	/// VU break/reset control (FBRST), of which only the enable bits are held.
+	pub fbrst: cop2::Fbrst,
+
 	pub memory: Memory,
 	pub mmu: Mmu,
 
@@ -59,6 +113,44 @@ pub struct EECore {
 	/// Whether dual issue of instructions is enabled or disabled.
 	pub dual_issue: bool,
 
+	/// Whether undecodable instructions should halt the core
+	/// (after logging them), as well as raising an exception.
+	pub strict: bool,
+
+	/// Set when execution has stopped and should be handed over to the debugger.
+	pub halted: bool,
+
+	/// External CPCOND0 input, tested by the `BC0x` branches.
+	///
+	/// Driven by the DMAC to signal DMA completion, updated each cycle.
+	pub cpcond0: bool,
+
+	/// Interrupt controller, which drives the `Int[0]` line (`Cause.IP2`).
+	pub intc: Rc<RefCell<Intc>>,
+
+	/// The four EE timers, which count alongside [`clock`](#structfield.clock).
+	pub timers: Rc<RefCell<Timers>>,
+
+	/// DMA controller, which drives `Int[1]` and `CPCOND0`.
+	pub dmac: Rc<RefCell<Dmac>>,
+
+	/// GIF, fed by DMAC channel 2 (PATH3).
+	pub gif: Rc<RefCell<Gif>>,
+
+	/// GS, fed by the GIF, which also drives VBLANK.
+	pub gs: Rc<RefCell<Gs>>,
+
+	/// The vector units, whose memories are fed by the VIFs.
+	pub vu0: Rc<RefCell<VectorUnit>>,
+	pub vu1: Rc<RefCell<VectorUnit>>,
+
+	/// VIF0 and VIF1, fed by DMAC channels 0 and 1.
+	pub vif0: Rc<RefCell<Vif>>,
+	pub vif1: Rc<RefCell<Vif>>,
+
+	/// External `Int[1]` line (`Cause.IP3`), driven by the DMAC and updated each cycle.
+	pub int1: bool,
+
 	/// Registers and physical pipes 
 	pub usable_parts: Capability,
 
@@ -67,32 +159,97 @@ pub struct EECore {
 	waiting_asyncs: BinaryHeap<Reverse<LiveAction>>,
 
 	excepted_this_cycle: bool,
+
+	/// Set when the current instruction moved the PC itself, without a delay slot.
+	jumped: bool,
+
+	/// Address of the branch whose delay slot is currently executing, if any.
+	delay_slot_branch: Option<u32>,
 }
 
 impl EECore {
 	/// Create a new instance of an EE Core Processor, including any necessary state (pipelines, register file, etc.)
 	pub fn new() -> Self {
+		let intc = Rc::new(RefCell::new(Intc::default()));
+		let timers = Rc::new(RefCell::new(Timers::default()));
+		let dmac = Rc::new(RefCell::new(Dmac::default()));
+		let gif = Rc::new(RefCell::new(Gif::default()));
+		let gs = Rc::new(RefCell::new(Gs::default()));
+		let vu0 = Rc::new(RefCell::new(VectorUnit::vu0()));
+		let vu1 = Rc::new(RefCell::new(VectorUnit::vu1()));
+		let vif0 = Rc::new(RefCell::new(Vif::vif0(vu0.clone())));
+		let vif1 = Rc::new(RefCell::new(Vif::vif1(vu1.clone(), gif.clone())));
+		dmac.borrow_mut().attach(ChannelId::Vif0, vif0.clone());
+		dmac.borrow_mut().attach(ChannelId::Vif1, vif1.clone());
+		dmac.borrow_mut().attach(ChannelId::Gif, gif.clone());
+		gif.borrow_mut().connect(gs.clone());
+		vu1.borrow_mut().connect(gif.clone());
+		vu0.borrow_mut().map_vu1(vu1.clone());
+
+		let mut memory = Memory::new(vec![0;4]);
+		memory.bus.map("Timers", timer::TIMERS_PHYSICAL, timer::TIMERS_SIZE, timers.clone());
+		memory.bus.map("GIF", gif::GIF_PHYSICAL, gif::GIF_SIZE, gif.clone());
+		memory.bus.map("GIF FIFO", gif::GIF_FIFO_PHYSICAL, gif::GIF_FIFO_SIZE, gif.clone());
+		memory.bus.map("DMAC", dmac::DMAC_PHYSICAL, dmac::DMAC_SIZE, dmac.clone());
+		memory.bus.map("INTC", intc::INTC_PHYSICAL, intc::INTC_SIZE, intc.clone());
+		memory.bus.map("GS", GS_PRIV_REGISTERS_PHYSICAL, gs::GS_PRIV_SIZE, gs.clone());
+		memory.bus.map("VIF0", vif::VIF0_PHYSICAL, vif::VIF_SIZE, vif0.clone());
+		memory.bus.map("VIF1", vif::VIF1_PHYSICAL, vif::VIF_SIZE, vif1.clone());
+		memory.bus.map("VIF0 FIFO", vif::VIF0_FIFO_PHYSICAL, vif::VIF_FIFO_SIZE, vif0.clone());
+		memory.bus.map("VIF1 FIFO", vif::VIF1_FIFO_PHYSICAL, vif::VIF_FIFO_SIZE, vif1.clone());
+		memory.bus.map("VU0 code", VU0_CODE_PHYSICAL, vu::VU0_MEMORY_SIZE, vu0.clone());
+		memory.bus.map("VU0 data", VU0_DATA_PHYSICAL, vu::VU0_MEMORY_SIZE, vu0.clone());
+		memory.bus.map("VU1 code", VU1_CODE_PHYSICAL, vu::VU1_MEMORY_SIZE, vu1.clone());
+		memory.bus.map("VU1 data", VU1_DATA_PHYSICAL, vu::VU1_MEMORY_SIZE, vu1.clone());
+
 		Self {
 			register_file: [0u8; REGISTER_FILE_SIZE],
 			cop0_register_file: [0u8; COP0_REGISTER_FILE_SIZE],
+			cop1_register_file: [0u8; COP1_REGISTER_FILE_SIZE],
 			hi: [0u8; REGISTER_WIDTH_BYTES],
 			lo: [0u8; REGISTER_WIDTH_BYTES],
 			sa_register: 0,
 			pc_register: BIOS_START as u32,
 
-			memory: Memory::new(vec![0;4]),
+			fcr0: EE_FCR0,
+			fcr31: Fcr31::default().bits(),
+			fpu_accumulator: 0,
+			cmsar0: 0,
+			fbrst: Default::default(),
+
+			memory,
 			mmu: Default::default(),
 
 			branch_delay_slot_active: None,
 
 			dual_issue: false,
 
+			strict: false,
+			halted: false,
+
+			cpcond0: false,
+
+			intc,
+			timers,
+			dmac,
+			gif,
+			gs,
+			vu0,
+			vu1,
+			vif0,
+			vif1,
+			int1: false,
+
 			usable_parts: Capability::all(),
 
 			clock: 0,
 			waiting_asyncs: BinaryHeap::with_capacity(6), // 6 Physical pipes.
 
 			excepted_this_cycle: false,
+
+			jumped: false,
+
+			delay_slot_branch: None,
 		}
 	}
 
@@ -105,16 +262,18 @@ impl EECore {
 	/// Note, register R0 will always return 0.
 	pub fn read_register(&self, index: u8) -> u64 {
 		trace!("Reading from register {}", index);
-		let floor = ((index as usize) * REGISTER_WIDTH_BYTES) + HALF_REGISTER_WIDTH_BYTES;
+		let floor = (index as usize) * REGISTER_WIDTH_BYTES;
 		LittleEndian::read_u64(&self.register_file[floor..])
 	}
 
 	/// Write a value to the specified register.
 	/// Writes to R0 will have NO effect.
+	///
+	/// Only the lower 64 bits are changed: the upper half is preserved.
 	pub fn write_register(&mut self, index: u8, value: u64) -> Option<()> {
 		trace!("Writing value {} to register {}", value, index);
 		if index != 0 {
-			let floor = ((index as usize) * REGISTER_WIDTH_BYTES) + HALF_REGISTER_WIDTH_BYTES;
+			let floor = (index as usize) * REGISTER_WIDTH_BYTES;
 			LittleEndian::write_u64(&mut self.register_file[floor..], value);
 			Some(())
 		} else {
@@ -122,6 +281,27 @@ impl EECore {
 		}
 	}
 
+	/// Reads the full 128-bit value of the specified register.
+	/// Note, register R0 will always return 0.
+	pub fn read_register_wide(&self, index: u8) -> u128 {
+		trace!("Reading (wide) from register {}", index);
+		let floor = (index as usize) * REGISTER_WIDTH_BYTES;
+		LittleEndian::read_u128(&self.register_file[floor..])
+	}
+
+	/// Write a full 128-bit value to the specified register.
+	/// Writes to R0 will have NO effect.
+	pub fn write_register_wide(&mut self, index: u8, value: u128) -> Option<()> {
+		trace!("Writing (wide) value {} to register {}", value, index);
+		if index != 0 {
+			let floor = (index as usize) * REGISTER_WIDTH_BYTES;
+			LittleEndian::write_u128(&mut self.register_file[floor..], value);
+			Some(())
+		} else {
+			None
+		}
+	}
+
 	/// Reads the value of the HI register.
 	pub fn read_hi(&self) -> u64 {
 		trace!("Reading from HI");
@@ -136,29 +316,57 @@ impl EECore {
 		LittleEndian::write_u64(&mut self.hi[..], value);
 	}
 
-	/// Reads half of the HI register,
-	/// where `index` is `0` or `1`.
+	/// Reads the upper 64 bits of the HI register (HI1).
+	pub fn read_hi1(&self) -> u64 {
+		trace!("Reading from HI1");
+
+		LittleEndian::read_u64(&self.hi[HALF_REGISTER_WIDTH_BYTES..])
+	}
+
+	/// Write a value to the upper 64 bits of the HI register (HI1).
+	pub fn write_hi1(&mut self, value: u64) {
+		trace!("Writing value {} to HI1", value);
+
+		LittleEndian::write_u64(&mut self.hi[HALF_REGISTER_WIDTH_BYTES..], value);
+	}
+
+	/// Reads the full 128-bit value of the HI register.
+	pub fn read_hi_wide(&self) -> u128 {
+		trace!("Reading (wide) from HI");
+
+		LittleEndian::read_u128(&self.hi[..])
+	}
+
+	/// Write a full 128-bit value to the HI register.
+	pub fn write_hi_wide(&mut self, value: u128) {
+		trace!("Writing (wide) value {} to HI", value);
+
+		LittleEndian::write_u128(&mut self.hi[..], value);
+	}
+
+	/// Reads the low word of HI (`index` `0`)
+	/// or HI1 (`index` `1`).
 	pub fn read_hi_half(&self, index: u8) -> u32 {
 		trace!("Reading from HI{}", index);
 
 		let offset = if index == 0 {
-			HALF_REGISTER_WIDTH_BYTES
-		} else {
 			0
+		} else {
+			HALF_REGISTER_WIDTH_BYTES
 		};
 
 		LittleEndian::read_u32(&self.hi[offset..])
 	}
 
-	/// Write a value to half of the HI register,
-	/// where `index` is `0` or `1`.
+	/// Write a value to the low word of HI (`index` `0`)
+	/// or HI1 (`index` `1`), leaving the upper word untouched.
 	pub fn write_hi_half(&mut self, index: u8, value: u32) {
 		trace!("Writing value {} to HI{}", value, index);
 
 		let offset = if index == 0 {
-			HALF_REGISTER_WIDTH_BYTES
-		} else {
 			0
+		} else {
+			HALF_REGISTER_WIDTH_BYTES
 		};
 
 		LittleEndian::write_u32(&mut self.hi[offset..], value);
@@ -178,29 +386,57 @@ impl EECore {
 		LittleEndian::write_u64(&mut self.lo[..], value);
 	}
 
-	/// Reads half of the LO register,
-	/// where `index` is `0` or `1`.
+	/// Reads the upper 64 bits of the LO register (LO1).
+	pub fn read_lo1(&self) -> u64 {
+		trace!("Reading from LO1");
+
+		LittleEndian::read_u64(&self.lo[HALF_REGISTER_WIDTH_BYTES..])
+	}
+
+	/// Write a value to the upper 64 bits of the LO register (LO1).
+	pub fn write_lo1(&mut self, value: u64) {
+		trace!("Writing value {} to LO1", value);
+
+		LittleEndian::write_u64(&mut self.lo[HALF_REGISTER_WIDTH_BYTES..], value);
+	}
+
+	/// Reads the full 128-bit value of the LO register.
+	pub fn read_lo_wide(&self) -> u128 {
+		trace!("Reading (wide) from LO");
+
+		LittleEndian::read_u128(&self.lo[..])
+	}
+
+	/// Write a full 128-bit value to the LO register.
+	pub fn write_lo_wide(&mut self, value: u128) {
+		trace!("Writing (wide) value {} to LO", value);
+
+		LittleEndian::write_u128(&mut self.lo[..], value);
+	}
+
+	/// Reads the low word of LO (`index` `0`)
+	/// or LO1 (`index` `1`).
 	pub fn read_lo_half(&self, index: u8) -> u32 {
 		trace!("Reading from LO{}", index);
 
 		let offset = if index == 0 {
-			HALF_REGISTER_WIDTH_BYTES
-		} else {
 			0
+		} else {
+			HALF_REGISTER_WIDTH_BYTES
 		};
 
 		LittleEndian::read_u32(&self.lo[offset..])
 	}
 
-	/// Write a value to half of the LO register,
-	/// where `index` is `0` or `1`.
+	/// Write a value to the low word of LO (`index` `0`)
+	/// or LO1 (`index` `1`), leaving the upper word untouched.
 	pub fn write_lo_half(&mut self, index: u8, value: u32) {
 		trace!("Writing value {} to LO{}", value, index);
 
 		let offset = if index == 0 {
-			HALF_REGISTER_WIDTH_BYTES
-		} else {
 			0
+		} else {
+			HALF_REGISTER_WIDTH_BYTES
 		};
 
 		LittleEndian::write_u32(&mut self.lo[offset..], value);
@@ -246,16 +482,158 @@ impl EECore {
 
 		match Register::from_u8(index) {
 			Some(Register::Config) => self.update_config(value),
-			Some(Register::Index) => self.mmu.index = value as u8,
+			Some(Register::Index) => self.mmu.index = (value & INDEX_MASK) as u8,
+			Some(Register::EntryHi) => self.mmu.asid = value.get_asid(),
 			Some(Register::PageMask) => self.mmu.page_mask = value,
 			Some(Register::Wired) => {
 				self.mmu.wired = value as u8;
 				self.write_cop0_direct(Register::Random as u8, RANDOM_DEFAULT);
 			},
+			Some(Register::Compare) => {
+				// Acknowledges the timer interrupt.
+				let cause = self.read_cop0_direct(Register::Cause as u8);
+				self.write_cop0_direct(
+					Register::Cause as u8,
+					cause & !Cause::PENDING_INTERRUPT_TIMER.bits(),
+				);
+			},
 			_ => {},
 		}
 	}
 
+	/// Reads the raw bits of the specified FPU register (COP1).
+	pub fn read_cop1(&self, index: u8) -> u32 {
+		trace!("Reading from FPR {}", index);
+		let floor = (index as usize) * COP1_REGISTER_WIDTH_BYTES;
+		LittleEndian::read_u32(&self.cop1_register_file[floor..])
+	}
+
+	/// Write raw bits to the specified FPU register (COP1).
+	pub fn write_cop1(&mut self, index: u8, value: u32) {
+		trace!("Writing value {:08x} to FPR {}", value, index);
+		let floor = (index as usize) * COP1_REGISTER_WIDTH_BYTES;
+		LittleEndian::write_u32(&mut self.cop1_register_file[floor..], value);
+	}
+
+	/// Reads the specified FPU control register (COP1).
+	///
+	/// Reserved registers read as `0`.
+	pub fn read_cop1_control(&self, index: u8) -> u32 {
+		match ControlRegister::from_u8(index) {
+			Some(ControlRegister::Implementation) => self.fcr0,
+			Some(ControlRegister::ControlStatus) => self.fcr31,
+			None => 0,
+		}
+	}
+
+	/// Write a value to the specified FPU control register (COP1).
+	///
+	/// Read-only bits and registers are left untouched.
+	pub fn write_cop1_control(&mut self, index: u8, value: u32) {
+		let bmask = cop1::get_writable_bitmask(index);
+		trace!("Writing value {:08x} to FCR {}", value, index);
+
+		if let Some(ControlRegister::ControlStatus) = ControlRegister::from_u8(index) {
+			self.fcr31 = (value & bmask) | (self.fcr31 & !bmask);
+		}
+	}
+
+	/// Reads the flags held in FCR31.
+	#[inline]
+	pub fn read_fpu_status(&self) -> Fcr31 {
+		Fcr31::from_bits_truncate(self.fcr31)
+	}
+
+	/// Replace the flags held in FCR31.
+	#[inline]
+	pub fn write_fpu_status(&mut self, status: Fcr31) {
+		self.fcr31 = (status | Fcr31::FIXED_ONES).bits();
+	}
+
+	/// Reads the 128-bit value of VU0's VF register `index` (COP2), `x` lowest.
+	pub fn read_cop2(&self, index: u8) -> u128 {
+		trace!("Reading from VF {}", index);
+		self.vu0.borrow().vf[index as usize].iter().rev()
+			.fold(0, |value, &field| (value << 32) | u128::from(field))
+	}
+
+	/// Write a 128-bit value to VU0's VF register `index` (COP2), `x` lowest.
+	/// Writes to VF0 will have NO effect.
+	pub fn write_cop2(&mut self, index: u8, value: u128) {
+		trace!("Writing value {:032x} to VF {}", value, index);
+		if index != 0 {
+			let mut vu0 = self.vu0.borrow_mut();
+			for (field, word) in vu0.vf[index as usize].iter_mut().enumerate() {
+				*word = (value >> (field * 32)) as u32;
+			}
+		}
+	}
+
+	/// Reads the specified VU0 control register (COP2): VI0--VI15, then the
+	/// special registers.
+	///
+	/// Reserved registers read as `0`.
+	pub fn read_cop2_control(&self, index: u8) -> u32 {
+		match cop2::ControlRegister::from_u8(index) {
+			Some(cop2::ControlRegister::Cmsar0) => u32::from(self.cmsar0),
+			Some(cop2::ControlRegister::Fbrst) => self.fbrst.bits(),
+			Some(cop2::ControlRegister::VpuStat) => self.read_vpu_stat(),
+			Some(cop2::ControlRegister::Cmsar1) => 0,
+			_ => self.vu0.borrow().read_control(index),
+		}
+	}
+
+	/// Write a value to the specified VU0 control register (COP2).
+	///
+	/// Read-only bits and registers are left untouched.
+	pub fn write_cop2_control(&mut self, index: u8, value: u32) {
+		trace!("Writing value {:08x} to VU0 control register {}", value, index);
+
+		match cop2::ControlRegister::from_u8(index) {
+			Some(cop2::ControlRegister::Cmsar0) => self.cmsar0 = value as u16,
+			Some(cop2::ControlRegister::Fbrst) => self.write_fbrst(value),
+			Some(cop2::ControlRegister::VpuStat) => {},
+			Some(cop2::ControlRegister::Cmsar1) => self.vu1.borrow_mut().start((value & 0xffff) * 8),
+			_ => self.vu0.borrow_mut().write_control(index, value),
+		}
+	}
+
+	/// The execution state of both VUs, as held in VPU-STAT.
+	pub fn read_vpu_stat(&self) -> u32 {
+		let state = |vu: &VectorUnit| {
+			let mut out = cop2::VpuStat::empty();
+			out.set(cop2::VpuStat::BUSY, vu.running);
+			out.set(cop2::VpuStat::FORCE_BREAK_STOPPED, vu.force_broken);
+			out.bits()
+		};
+
+		state(&self.vu0.borrow()) | (state(&self.vu1.borrow()) << cop2::VU1_SHIFT)
+	}
+
+	/// Break or reset either VU, as writing FBRST does.
+	fn write_fbrst(&mut self, value: u32) {
+		for (vu, shift) in [(&self.vu0, 0), (&self.vu1, cop2::VU1_SHIFT)] {
+			let request = cop2::Fbrst::from_bits_truncate(value >> shift);
+			let mut vu = vu.borrow_mut();
+
+			if request.contains(cop2::Fbrst::RESET) {
+				vu.reset();
+			} else if request.contains(cop2::Fbrst::FORCE_BREAK) {
+				vu.force_break();
+			}
+		}
+
+		self.fbrst = cop2::Fbrst::from_bits_truncate(value) & cop2::Fbrst::HELD;
+	}
+
+	/// Issue the current instruction again next cycle, as an interlock does.
+	///
+	/// In a branch delay slot, the branch is issued again too.
+	pub fn stall(&mut self) {
+		let pc = self.delay_slot_branch.unwrap_or(self.pc_register);
+		self.jump_immediate(pc);
+	}
+
 	fn update_config(&mut self, value: u32) {
 		let config = Config::from_bits_truncate(value);
 
@@ -277,19 +655,83 @@ impl EECore {
 	pub fn read_memory(&mut self, v_addr: u32, size: usize) -> Option<&[u8]> {
 		if self.access_virtual_address(v_addr, true) {
 			let p_addr = self.translate_virtual_address(v_addr, true);
-			p_addr.map(move |real_p| self.memory.read(real_p, size))
+			p_addr.and_then(move |real_p| self.memory.read(real_p, size))
 		} else {
 			None
 		}
 	}
 
-	pub fn read_memory_mut(&mut self, v_addr: u32, size: usize) -> Option<&mut [u8]> {
-		if self.access_virtual_address(v_addr, false) {
-			let p_addr = self.translate_virtual_address(v_addr, false);
-			p_addr.map(move |real_p| self.memory.read_mut(real_p, size))
-		} else {
-			None
+	/// Load a value from memory or a mapped device, as a load instruction does.
+	///
+	/// Raises a bus error if nothing responds to the translated address.
+	pub fn load<T: BusAccess>(&mut self, v_addr: u32) -> Option<T> {
+		if !self.access_virtual_address(v_addr, true) {
+			return None;
+		}
+
+		let p_addr = self.translate_virtual_address(v_addr, true)?;
+		let out = self.memory.load(p_addr);
+
+		if out.is_none() {
+			self.throw_l1_exception(L1Exception::BusErrorLoadStore(p_addr.raw()));
+		}
+
+		out
+	}
+
+	/// Store a value to memory or a mapped device, as a store instruction does.
+	///
+	/// Raises a bus error if nothing responds to the translated address.
+	pub fn store<T: BusAccess>(&mut self, v_addr: u32, value: T) {
+		if !self.access_virtual_address(v_addr, false) {
+			return;
+		}
+
+		if let Some(p_addr) = self.translate_virtual_address(v_addr, false) {
+			if self.memory.store(p_addr, value).is_none() {
+				self.throw_l1_exception(L1Exception::BusErrorLoadStore(p_addr.raw()));
+			}
+		}
+	}
+
+	/// Merge part of a value into the enclosing aligned `T`, as unaligned stores do.
+	///
+	/// `v_addr` is translated once with store access, so any exception reports it
+	/// as given. Raises a bus error if nothing responds to the aligned address.
+	pub fn store_merge<T: BusAccess>(&mut self, v_addr: u32, merge: impl FnOnce(T) -> T) {
+		if !self.access_virtual_address(v_addr, false) {
+			return;
+		}
+
+		if let Some(p_addr) = self.translate_virtual_address(v_addr, false) {
+			let aligned = p_addr.aligned(T::SIZE);
+			let memory = &mut self.memory;
+			let done = memory.load(aligned)
+				.and_then(|old| memory.store(aligned, merge(old)));
+
+			if done.is_none() {
+				self.throw_l1_exception(L1Exception::BusErrorLoadStore(aligned.raw()));
+			}
+		}
+	}
+
+	/// Fetch the instruction at `v_addr`.
+	///
+	/// Instructions may only be fetched from plain memory: anything else is a bus error.
+	pub fn fetch_instruction(&mut self, v_addr: u32) -> Option<u32> {
+		if !self.access_virtual_address(v_addr, true) {
+			return None;
+		}
+
+		let p_addr = self.translate_virtual_address(v_addr, true)?;
+		let out = self.memory.read(p_addr, OPCODE_LENGTH_BYTES)
+			.map(LittleEndian::read_u32);
+
+		if out.is_none() {
+			self.throw_l1_exception(L1Exception::BusErrorFetch(p_addr.raw()));
 		}
+
+		out
 	}
 
 	pub fn write_memory(&mut self, v_addr: u32, data: &[u8]) {
@@ -341,31 +783,83 @@ impl EECore {
 	///
 	/// This attempts to fetch and issue two instructions from memory.
 	pub fn cycle(&mut self) {
-		// Timer interrupt.
-		// FIXME: does this happen befpre or after execution?
+		self.clock = self.clock.wrapping_add(1);
+		self.timers.borrow_mut().step(1, &mut self.intc.borrow_mut());
+
+		{
+			let mut dmac = self.dmac.borrow_mut();
+			dmac.step(1, &mut self.memory);
+			self.int1 = dmac.interrupt_pending();
+			self.cpcond0 = dmac.cpcond0();
+		}
+
+		{
+			let mut gs = self.gs.borrow_mut();
+			let mut intc = self.intc.borrow_mut();
+
+			match gs.step(1) {
+				Some(VBlank::Start) => {
+					intc.raise(Interrupt::VBlankStart);
+					self.timers.borrow_mut().set_vblank(true);
+				},
+				Some(VBlank::End) => {
+					intc.raise(Interrupt::VBlankEnd);
+					self.timers.borrow_mut().set_vblank(false);
+				},
+				None => {},
+			}
+			self.timers.borrow_mut().set_hblank(gs.hblank(), &mut intc);
+
+			if gs.take_interrupt() {
+				intc.raise(Interrupt::Gs);
+			}
+		}
+
+		self.vu0.borrow_mut().cycle();
+		self.vu1.borrow_mut().cycle();
+
+		// VIFs stalled on the VU or GIF pick up where they left off.
+		for (vif, interrupt) in [(&self.vif0, Interrupt::Vif0), (&self.vif1, Interrupt::Vif1)] {
+			let mut vif = vif.borrow_mut();
+			vif.process();
+
+			if vif.take_interrupt() {
+				self.intc.borrow_mut().raise(interrupt);
+			}
+		}
+
+		// Timer interrupt: latched in Cause until Compare is next written.
 		let count = self.read_cop0_direct(Register::Count as u8).wrapping_add(1);
 		self.write_cop0_direct(Register::Count as u8, count);
 		if count == self.read_cop0_direct(Register::Compare as u8) {
-			// FIXME: magic number
-			// FIXME: needs to be masked.
-			self.throw_l1_exception(L1Exception::Interrupt(7))
+			let cause = self.read_cop0_direct(Register::Cause as u8);
+			self.write_cop0_direct(
+				Register::Cause as u8,
+				cause | Cause::PENDING_INTERRUPT_TIMER.bits(),
+			);
 		}
 
-		let count = self.clock.wrapping_add(1);
+		if let Some(line) = self.pending_interrupt() {
+			self.throw_l1_exception(L1Exception::Interrupt(line));
+			self.excepted_this_cycle = false;
+			return;
+		}
 
 		let dual_issue = self.dual_issue;
-		let might_jump = self.branch_delay_slot_active.is_some();
 
 		let pc = self.pc_register;
 		trace!("PC: {:08x}", self.pc_register);
 
-		let ops = self.read_memory(pc, 2 * OPCODE_LENGTH_BYTES).unwrap();
-
-		let i1 = LittleEndian::read_u32(&ops);
-		let i2 = LittleEndian::read_u32(&ops[OPCODE_LENGTH_BYTES..]);
+		let i1 = match self.fetch_instruction(pc) {
+			Some(i1) => i1,
+			None => {
+				self.excepted_this_cycle = false;
+				return;
+			},
+		};
 
 		let p1 = ops::process_instruction(i1);
-		trace!("Decoded: {:?}", p1);
+		trace!("Decoded: {}: {:?}", ops::disassemble(i1, Some(pc)), p1);
 		self.execute(p1);
 
 		trace!("Where?: {:?}", p1.pipeline_fits(&self.usable_parts));
@@ -386,16 +880,13 @@ impl EECore {
 
 		if dual_issue {
 			trace!("PC: {:08x}", self.pc_register);
-			let i2 = if might_jump {
-				LittleEndian::read_u32(self.read_memory(self.pc_register, OPCODE_LENGTH_BYTES).unwrap())
-			} else {
-				i2
-			};
-
-			let p2 = ops::process_instruction(i2);
-			trace!("Decoded 2: {:?}", p2);
-			self.execute(p2);
-			trace!("Where?: {:?}", p2.pipeline_fits(&self.usable_parts));
+			let pc = self.pc_register;
+			if let Some(i2) = self.fetch_instruction(pc) {
+				let p2 = ops::process_instruction(i2);
+				trace!("Decoded 2: {}: {:?}", ops::disassemble(i2, Some(pc)), p2);
+				self.execute(p2);
+				trace!("Where?: {:?}", p2.pipeline_fits(&self.usable_parts));
+			}
 		}
 
 		self.excepted_this_cycle = false;
@@ -410,6 +901,7 @@ impl EECore {
 
 	pub fn execute(&mut self, instruction: OpCode) {
 		let branch_result = if let Some(op) = self.branch_delay_slot_active.take() {
+			self.delay_slot_branch = Some(self.pc_register.wrapping_sub(OPCODE_LENGTH_BYTES as u32));
 			(op.action)(self, &op)
 		} else {
 			BranchResult::empty()
@@ -421,12 +913,36 @@ impl EECore {
 			trace!("Nullified...");
 		}
 
-		if !(branch_result.contains(BranchResult::BRANCHED) || self.excepted_this_cycle) {
+		self.delay_slot_branch = None;
+		let jumped = std::mem::replace(&mut self.jumped, false);
+
+		if !(branch_result.contains(BranchResult::BRANCHED) || self.excepted_this_cycle || jumped) {
 			self.pc_register = self.pc_register.wrapping_add(OPCODE_LENGTH_BYTES as u32);
 		}
 	}
 
-	pub fn in_exception(&mut self) -> bool {
+	/// Sample the external interrupt lines into `Cause.IP`, and return the
+	/// lowest interrupt line which should be taken before the next instruction.
+	///
+	/// Interrupts are level-triggered, and are not taken while the next instruction
+	/// sits in a branch delay slot.
+	pub fn pending_interrupt(&mut self) -> Option<u8> {
+		let mut cause = Cause::from_bits_truncate(self.read_cop0_direct(Register::Cause as u8));
+		cause.set(Cause::PENDING_INTERRUPT_I0, self.intc.borrow().pending());
+		cause.set(Cause::PENDING_INTERRUPT_I1, self.int1);
+		self.write_cop0_direct(Register::Cause as u8, cause.bits());
+
+		let status = Status::from_bits_truncate(self.read_cop0_direct(Register::Status as u8));
+		let unmasked = cause.bits() & (status & Status::INTERRUPT_MASK).bits();
+
+		if unmasked == 0 || !status.accepts_interrupts() || self.branch_delay_slot_active.is_some() {
+			None
+		} else {
+			Some((unmasked.trailing_zeros() - 8) as u8)
+		}
+	}
+
+	pub fn in_exception(&self) -> bool {
 		self.get_current_privilege().is_in_exception()
 	}
 
@@ -456,12 +972,12 @@ impl EECore {
 		if !status.contains(Status::EXCEPTION_LEVEL) {
 			status.insert(Status::EXCEPTION_LEVEL);
 
-			let saved_addr = if self.branch_delay_slot_active.is_none() {
+			let saved_addr = if let Some(branch_addr) = self.delay_slot_branch {
 				cause.insert(Cause::BRANCH_DELAY_1);
-				self.pc_register
+				branch_addr
 			} else {
 				cause.remove(Cause::BRANCH_DELAY_1);
-				self.pc_register - OPCODE_LENGTH_BYTES as u32
+				self.pc_register
 			};
 
 			trace!("placing 0x{:08x} in EPC", saved_addr);
@@ -494,12 +1010,12 @@ impl EECore {
 		if !status.contains(Status::ERROR_LEVEL) {
 			status.insert(Status::ERROR_LEVEL);
 
-			let saved_addr = if self.branch_delay_slot_active.is_none() {
+			let saved_addr = if let Some(branch_addr) = self.delay_slot_branch {
 				cause.insert(Cause::BRANCH_DELAY_2);
-				self.pc_register
+				branch_addr
 			} else {
 				cause.remove(Cause::BRANCH_DELAY_2);
-				self.pc_register - OPCODE_LENGTH_BYTES as u32
+				self.pc_register
 			};
 
 			self.write_cop0_direct(Register::ErrorEPC as u8, saved_addr);
@@ -514,6 +1030,13 @@ impl EECore {
 		self.write_cop0_direct(Register::Status as u8, status.bits());
 	}
 
+	/// Moves the PC to `target` with no delay slot, as `ERET` does.
+	#[inline]
+	pub fn jump_immediate(&mut self, target: u32) {
+		self.pc_register = target;
+		self.jumped = true;
+	}
+
 	#[inline]
 	pub fn branch(&mut self, op: &OpCode, new_action: BranchAction, temp: u32) {
 		let _ = self.branch_delay_slot_active.replace(BranchOpCode::new(
@@ -523,6 +1046,16 @@ impl EECore {
 		));
 	}
 
+	/// Whether instructions for coprocessor `cop` may currently be issued.
+	///
+	/// COP0 is always usable in kernel mode.
+	pub fn coprocessor_usable(&self, cop: u8) -> bool {
+		let status = Status::from_bits_truncate(self.read_cop0_direct(Register::Status as u8));
+		let cu_bit = Status::from_bits_truncate(Status::COP0_USABLE.bits() << cop);
+
+		status.contains(cu_bit) || (cop == 0 && status.privilege_level().is_kernel())
+	}
+
 	pub fn get_current_privilege(&self) -> PrivilegeLevel {
 		Status::from_bits_truncate(
 			self.read_cop0_direct(Register::Status as u8)
@@ -8,6 +8,82 @@ use crate::{
 	utils::*,
 };
 
+/// Signed 32x32 -> 64-bit product of `rs` and `rt`.
+#[inline]
+fn signed_product(cpu: &EECore, data: &OpCode) -> u64 {
+	let lhs = i64::from(cpu.read_register(data.ri_get_source()) as i32);
+	let rhs = i64::from(cpu.read_register(data.ri_get_target()) as i32);
+	(lhs * rhs) as u64
+}
+
+/// Unsigned 32x32 -> 64-bit product of `rs` and `rt`.
+#[inline]
+fn unsigned_product(cpu: &EECore, data: &OpCode) -> u64 {
+	let lhs = u64::from(cpu.read_register(data.ri_get_source()) as u32);
+	let rhs = u64::from(cpu.read_register(data.ri_get_target()) as u32);
+	lhs * rhs
+}
+
+/// The 64-bit accumulator held in the low words of HI/LO for multiplier `pipe`.
+#[inline]
+fn accumulator(cpu: &EECore, pipe: u8) -> u64 {
+	(u64::from(cpu.read_hi_half(pipe)) << 32) | u64::from(cpu.read_lo_half(pipe))
+}
+
+#[inline]
+fn write_hi_lo(cpu: &mut EECore, pipe: u8, hi: u64, lo: u64) {
+	if pipe == 0 {
+		cpu.write_hi(hi);
+		cpu.write_lo(lo);
+	} else {
+		cpu.write_hi1(hi);
+		cpu.write_lo1(lo);
+	}
+}
+
+/// Splits a 64-bit result across HI/LO of multiplier `pipe` (each word sign-extended),
+/// and copies LO into `rd`.
+fn write_product(cpu: &mut EECore, data: &OpCode, pipe: u8, result: u64) {
+	let lo_part = (result as u32).s_ext();
+	write_hi_lo(cpu, pipe, ((result >> 32) as u32).s_ext(), lo_part);
+
+	// EE-core specific modification (RRR).
+	let dest = data.r_get_destination();
+	if dest != 0 {
+		cpu.write_register(dest, lo_part);
+	}
+}
+
+fn divide(cpu: &mut EECore, data: &OpCode, pipe: u8) {
+	let lhs = cpu.read_register(data.ri_get_source()) as i32;
+	let rhs = cpu.read_register(data.ri_get_target()) as i32;
+
+	if rhs == 0 {
+		return;
+	}
+
+	// may need some very... specific mods.
+
+	let quotient = lhs.wrapping_div(rhs).s_ext();
+	let remainder = lhs.wrapping_rem(rhs).s_ext();
+
+	write_hi_lo(cpu, pipe, remainder, quotient);
+}
+
+fn divide_unsigned(cpu: &mut EECore, data: &OpCode, pipe: u8) {
+	let lhs = cpu.read_register(data.ri_get_source()) as u32;
+	let rhs = cpu.read_register(data.ri_get_target()) as u32;
+
+	if rhs == 0 {
+		return;
+	}
+
+	let quotient = (lhs / rhs).s_ext();
+	let remainder = (lhs % rhs).s_ext();
+
+	write_hi_lo(cpu, pipe, remainder, quotient);
+}
+
 pub fn add(cpu: &mut EECore, data: &OpCode) {
 	// NOTE: do this work in signed space of proper size,
 	// then convert to unsigned at end.
@@ -75,6 +151,45 @@ pub fn andi(cpu: &mut EECore, data: &OpCode) {
 	);
 }
 
+pub fn dadd(cpu: &mut EECore, data: &OpCode) {
+	// rs + rt -> rd, trapping on 64-bit signed overflow.
+	let lhs = cpu.read_register(data.ri_get_source()) as i64;
+	let rhs = cpu.read_register(data.ri_get_target()) as i64;
+
+	if let Some(sum) = lhs.checked_add(rhs) {
+		cpu.write_register(
+			data.r_get_destination(),
+			sum as u64,
+		);
+	} else {
+		cpu.throw_l1_exception(L1Exception::Overflow);
+	}
+}
+
+pub fn daddi(cpu: &mut EECore, data: &OpCode) {
+	let lhs = cpu.read_register(data.ri_get_source()) as i64;
+	let rhs = i64::from(data.i_get_immediate_signed());
+
+	if let Some(sum) = lhs.checked_add(rhs) {
+		cpu.write_register(
+			data.ri_get_target(),
+			sum as u64,
+		);
+	} else {
+		cpu.throw_l1_exception(L1Exception::Overflow);
+	}
+}
+
+pub fn daddiu(cpu: &mut EECore, data: &OpCode) {
+	let lhs = cpu.read_register(data.ri_get_source());
+	let rhs: u64 = data.i_get_immediate_signed().s_ext();
+
+	cpu.write_register(
+		data.ri_get_target(),
+		lhs.wrapping_add(rhs),
+	);
+}
+
 pub fn daddu(cpu: &mut EECore, data: &OpCode) {
 	// rs + rt -> rd
 	let lhs = cpu.read_register(data.ri_get_source());
@@ -86,36 +201,133 @@ pub fn daddu(cpu: &mut EECore, data: &OpCode) {
 	);
 }
 
-pub fn div(cpu: &mut EECore, data: &OpCode) {
-	let lhs = cpu.read_register(data.ri_get_source()) as i32;
-	let rhs = cpu.read_register(data.ri_get_target()) as i32;
+pub fn dsll(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register(
+		data.r_get_destination(),
+		cpu.read_register(data.ri_get_target()) << data.r_get_shift_amount(),
+	);
+}
 
-	if rhs == 0 {
-		return;
+pub fn dsll32(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register(
+		data.r_get_destination(),
+		cpu.read_register(data.ri_get_target()) << (data.r_get_shift_amount() + 32),
+	);
+}
+
+pub fn dsllv(cpu: &mut EECore, data: &OpCode) {
+	let shift = cpu.read_register(data.ri_get_source()) & 0b11_1111;
+	cpu.write_register(
+		data.r_get_destination(),
+		cpu.read_register(data.ri_get_target()) << shift,
+	);
+}
+
+pub fn dsra(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register(
+		data.r_get_destination(),
+		(cpu.read_register(data.ri_get_target()) as i64 >> data.r_get_shift_amount()) as u64,
+	);
+}
+
+pub fn dsra32(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register(
+		data.r_get_destination(),
+		(cpu.read_register(data.ri_get_target()) as i64 >> (data.r_get_shift_amount() + 32)) as u64,
+	);
+}
+
+pub fn dsrav(cpu: &mut EECore, data: &OpCode) {
+	let shift = cpu.read_register(data.ri_get_source()) & 0b11_1111;
+	cpu.write_register(
+		data.r_get_destination(),
+		(cpu.read_register(data.ri_get_target()) as i64 >> shift) as u64,
+	);
+}
+
+pub fn dsrl(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register(
+		data.r_get_destination(),
+		cpu.read_register(data.ri_get_target()) >> data.r_get_shift_amount(),
+	);
+}
+
+pub fn dsrl32(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register(
+		data.r_get_destination(),
+		cpu.read_register(data.ri_get_target()) >> (data.r_get_shift_amount() + 32),
+	);
+}
+
+pub fn dsrlv(cpu: &mut EECore, data: &OpCode) {
+	let shift = cpu.read_register(data.ri_get_source()) & 0b11_1111;
+	cpu.write_register(
+		data.r_get_destination(),
+		cpu.read_register(data.ri_get_target()) >> shift,
+	);
+}
+
+pub fn dsub(cpu: &mut EECore, data: &OpCode) {
+	// rs - rt -> rd, trapping on 64-bit signed overflow.
+	let lhs = cpu.read_register(data.ri_get_source()) as i64;
+	let rhs = cpu.read_register(data.ri_get_target()) as i64;
+
+	if let Some(diff) = lhs.checked_sub(rhs) {
+		cpu.write_register(
+			data.r_get_destination(),
+			diff as u64,
+		);
+	} else {
+		cpu.throw_l1_exception(L1Exception::Overflow);
 	}
+}
 
-	// may need some very... specific mods.
+pub fn dsubu(cpu: &mut EECore, data: &OpCode) {
+	let lhs = cpu.read_register(data.ri_get_source());
+	let rhs = cpu.read_register(data.ri_get_target());
 
-	let quotient = lhs.wrapping_div(rhs).s_ext();
-	let remainder = lhs.wrapping_rem(rhs).s_ext();
+	cpu.write_register(
+		data.r_get_destination(),
+		lhs.wrapping_sub(rhs),
+	);
+}
 
-	cpu.write_hi(remainder);
-	cpu.write_lo(quotient);
+pub fn div(cpu: &mut EECore, data: &OpCode) {
+	divide(cpu, data, 0);
+}
+
+pub fn div1(cpu: &mut EECore, data: &OpCode) {
+	divide(cpu, data, 1);
 }
 
 pub fn divu(cpu: &mut EECore, data: &OpCode) {
-	let lhs = cpu.read_register(data.ri_get_source()) as u32;
-	let rhs = cpu.read_register(data.ri_get_target()) as u32;
+	divide_unsigned(cpu, data, 0);
+}
 
-	if rhs == 0 {
-		return;
-	}
+pub fn divu1(cpu: &mut EECore, data: &OpCode) {
+	divide_unsigned(cpu, data, 1);
+}
 
-	let quotient = (lhs / rhs).s_ext();
-	let remainder = (lhs % rhs).s_ext();
+pub fn madd(cpu: &mut EECore, data: &OpCode) {
+	// (HI, LO) <- (HI, LO) + rs * rt, in signed space; rd <- LO.
+	let result = accumulator(cpu, 0).wrapping_add(signed_product(cpu, data));
+	write_product(cpu, data, 0, result);
+}
+
+pub fn madd1(cpu: &mut EECore, data: &OpCode) {
+	let result = accumulator(cpu, 1).wrapping_add(signed_product(cpu, data));
+	write_product(cpu, data, 1, result);
+}
+
+pub fn maddu(cpu: &mut EECore, data: &OpCode) {
+	// (HI, LO) <- (HI, LO) + rs * rt, in unsigned space; rd <- LO.
+	let result = accumulator(cpu, 0).wrapping_add(unsigned_product(cpu, data));
+	write_product(cpu, data, 0, result);
+}
 
-	cpu.write_hi(remainder);
-	cpu.write_lo(quotient);
+pub fn maddu1(cpu: &mut EECore, data: &OpCode) {
+	let result = accumulator(cpu, 1).wrapping_add(unsigned_product(cpu, data));
+	write_product(cpu, data, 1, result);
 }
 
 pub fn movn(cpu: &mut EECore, data: &OpCode) {
@@ -128,22 +340,47 @@ pub fn movn(cpu: &mut EECore, data: &OpCode) {
 	}
 }
 
+pub fn movz(cpu: &mut EECore, data: &OpCode) {
+	// if rt == 0, then rd <- rs
+	if cpu.read_register(data.ri_get_target()) == 0 {
+		cpu.write_register(
+			data.r_get_destination(),
+			cpu.read_register(data.ri_get_source()),
+		);
+	}
+}
+
 pub fn mult(cpu: &mut EECore, data: &OpCode) {
 	// multiply rs and rt in signed space.
 	// result will be 64-bit. Place into hi and lo (sign-extended).
-	let lhs = cpu.read_register(data.ri_get_source()) as i64;
-	let rhs = cpu.read_register(data.ri_get_target()) as i64;
-	let result = lhs.wrapping_mul(rhs);
+	let result = signed_product(cpu, data);
+	write_product(cpu, data, 0, result);
+}
 
-	cpu.write_hi((result >> 32) as u64);
-	let lo_part = (result as i32).s_ext();
-	cpu.write_lo(lo_part);
+pub fn mult1(cpu: &mut EECore, data: &OpCode) {
+	// As MULT, but on the I1 multiplier (HI1/LO1).
+	let result = signed_product(cpu, data);
+	write_product(cpu, data, 1, result);
+}
 
-	// EE-core specific modification (RRR).
-	let dest = data.r_get_destination();
-	if dest != 0 {
-		cpu.write_register(dest, lo_part);
-	}
+pub fn multu(cpu: &mut EECore, data: &OpCode) {
+	// multiply rs and rt in unsigned space.
+	// result will be 64-bit. Place into hi and lo (sign-extended).
+	let result = unsigned_product(cpu, data);
+	write_product(cpu, data, 0, result);
+}
+
+pub fn multu1(cpu: &mut EECore, data: &OpCode) {
+	// As MULTU, but on the I1 multiplier (HI1/LO1).
+	let result = unsigned_product(cpu, data);
+	write_product(cpu, data, 1, result);
+}
+
+pub fn nor(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register(
+		data.r_get_destination(),
+		!(cpu.read_register(data.ri_get_source()) | cpu.read_register(data.ri_get_target())),
+	);
 }
 
 pub fn or(cpu: &mut EECore, data: &OpCode) {
@@ -169,6 +406,14 @@ pub fn sll(cpu: &mut EECore, data: &OpCode) {
 	);
 }
 
+pub fn sllv(cpu: &mut EECore, data: &OpCode) {
+	let shift = cpu.read_register(data.ri_get_source()) & 0b1_1111;
+	cpu.write_register(
+		data.r_get_destination(),
+		((cpu.read_register(data.ri_get_target()) as u32) << shift).s_ext(),
+	);
+}
+
 pub fn slt(cpu: &mut EECore, data: &OpCode) {
 	let lhs = cpu.read_register(data.ri_get_source()) as i64;
 	let rhs = cpu.read_register(data.ri_get_target()) as i64;
@@ -212,6 +457,14 @@ pub fn sra(cpu: &mut EECore, data: &OpCode) {
 	);
 }
 
+pub fn srav(cpu: &mut EECore, data: &OpCode) {
+	let shift = cpu.read_register(data.ri_get_source()) & 0b1_1111;
+	cpu.write_register(
+		data.r_get_destination(),
+		(cpu.read_register(data.ri_get_target()) as i32 >> shift).s_ext(),
+	);
+}
+
 pub fn srl(cpu: &mut EECore, data: &OpCode) {
 	cpu.write_register(
 		data.r_get_destination(),
@@ -219,13 +472,51 @@ pub fn srl(cpu: &mut EECore, data: &OpCode) {
 	);
 }
 
+pub fn srlv(cpu: &mut EECore, data: &OpCode) {
+	let shift = cpu.read_register(data.ri_get_source()) & 0b1_1111;
+	cpu.write_register(
+		data.r_get_destination(),
+		(cpu.read_register(data.ri_get_target()) as u32 >> shift).s_ext(),
+	);
+}
+
+pub fn sub(cpu: &mut EECore, data: &OpCode) {
+	let lhs = cpu.read_register(data.ri_get_source()) as i32;
+	let rhs = cpu.read_register(data.ri_get_target()) as i32;
+
+	if let Some(diff) = lhs.checked_sub(rhs) {
+		cpu.write_register(
+			data.r_get_destination(),
+			diff.s_ext(),
+		);
+	} else {
+		cpu.throw_l1_exception(L1Exception::Overflow);
+	}
+}
+
 pub fn subu(cpu: &mut EECore, data: &OpCode) {
 	let lhs = cpu.read_register(data.ri_get_source()) as u32;
 	let rhs = cpu.read_register(data.ri_get_target()) as u32;
 	cpu.write_register(
 		data.r_get_destination(),
-		(lhs - rhs).s_ext(),
-	);	
+		lhs.wrapping_sub(rhs).s_ext(),
+	);
+}
+
+pub fn xor(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register(
+		data.r_get_destination(),
+		cpu.read_register(data.ri_get_source()) ^ cpu.read_register(data.ri_get_target()),
+	);
+}
+
+pub fn xori(cpu: &mut EECore, data: &OpCode) {
+	// rt <- rs ^ zero-ext(imm)
+	let extd_imm = data.i_get_immediate() as u64;
+	cpu.write_register(
+		data.ri_get_target(),
+		cpu.read_register(data.ri_get_source()) ^ extd_imm,
+	);
 }
 
 #[cfg(test)]
@@ -235,7 +526,7 @@ mod tests {
 		core::ops,
 		isa::mips::{
 			self,
-			ee::{CacheFunction, Cop0Function, Cop1Function},
+			ee::{CacheFunction, Cop0Function, Cop1Function, MmiBaseFunction},
 			Function as MipsFunction,
 			Instruction,
 			Opcode as MipsOpcode,
@@ -956,4 +1247,308 @@ mod tests {
 
 		assert_eq!(test_ee.read_register(3), i1 - i2);
 	}
+
+	fn run_register_op(function: MipsFunction, rs: u64, rt: u64, sa: u8) -> EECore {
+		// Apply `function` to registers 1 and 2, storing the result in register 3.
+		let mut test_ee = EECore::new();
+		test_ee.write_register(1, rs);
+		test_ee.write_register(2, rt);
+
+		let instruction = mips::build_op_register(function, 1, 2, 3, sa);
+		test_ee.execute(ops::process_instruction(instruction));
+
+		test_ee
+	}
+
+	fn run_mmi_op(test_ee: &mut EECore, function: MmiBaseFunction, rs: u64, rt: u64) {
+		// Apply MMI-encoded `function` to registers 1 and 2, with register 3 as `rd`.
+		test_ee.write_register(1, rs);
+		test_ee.write_register(2, rt);
+
+		let instruction = mips::build_op_register_custom(MipsOpcode::Mmi, function as u8, 1, 2, 3, 0);
+		test_ee.execute(ops::process_instruction(instruction));
+	}
+
+	fn run_immediate_op(opcode: MipsOpcode, rs: u64, imm: u16) -> EECore {
+		// Apply `opcode` to register 1 and `imm`, storing the result in register 2.
+		let mut test_ee = EECore::new();
+		test_ee.write_register(1, rs);
+
+		let instruction = mips::build_op_immediate(opcode, 1, 2, imm);
+		test_ee.execute(ops::process_instruction(instruction));
+
+		test_ee
+	}
+
+	#[test]
+	fn subu_wraps() {
+		let test_ee = run_register_op(MipsFunction::SubU, 1, 2, 0);
+		assert_eq!(test_ee.read_register(3), u64::MAX);
+	}
+
+	#[test]
+	fn basic_sub() {
+		let test_ee = run_register_op(MipsFunction::Sub, 19, 36, 0);
+		assert_eq!(test_ee.read_register(3) as i64, -17);
+	}
+
+	#[test]
+	fn sub_overflow_exception() {
+		let test_ee = run_register_op(MipsFunction::Sub, i32::MIN as u64, 1, 0);
+
+		assert!(test_ee.in_exception());
+		assert_eq!(test_ee.read_register(3), 0);
+	}
+
+	#[test]
+	fn basic_dadd() {
+		let test_ee = run_register_op(MipsFunction::DAdd, 0x1_0000_0000, (-1i64) as u64, 0);
+		assert_eq!(test_ee.read_register(3), 0xffff_ffff);
+	}
+
+	#[test]
+	fn dadd_overflow_exception() {
+		let test_ee = run_register_op(MipsFunction::DAdd, i64::MAX as u64, 1, 0);
+
+		assert!(test_ee.in_exception());
+		assert_eq!(test_ee.read_register(3), 0);
+	}
+
+	#[test]
+	fn basic_dsub() {
+		let test_ee = run_register_op(MipsFunction::DSub, 0x1_0000_0000, 1, 0);
+		assert_eq!(test_ee.read_register(3), 0xffff_ffff);
+	}
+
+	#[test]
+	fn dsub_overflow_exception() {
+		let test_ee = run_register_op(MipsFunction::DSub, i64::MIN as u64, 1, 0);
+
+		assert!(test_ee.in_exception());
+		assert_eq!(test_ee.read_register(3), 0);
+	}
+
+	#[test]
+	fn basic_dsubu() {
+		let test_ee = run_register_op(MipsFunction::DSubU, 0, 1, 0);
+		assert_eq!(test_ee.read_register(3), u64::MAX);
+	}
+
+	#[test]
+	fn basic_daddi() {
+		let test_ee = run_immediate_op(MipsOpcode::DAddI, 0x1_0000_0000, (-1i16) as u16);
+		assert_eq!(test_ee.read_register(2), 0xffff_ffff);
+	}
+
+	#[test]
+	fn daddi_overflow_exception() {
+		let test_ee = run_immediate_op(MipsOpcode::DAddI, i64::MAX as u64, 1);
+
+		assert!(test_ee.in_exception());
+		assert_eq!(test_ee.read_register(2), 0);
+	}
+
+	#[test]
+	fn daddiu_wraps() {
+		let test_ee = run_immediate_op(MipsOpcode::DAddIU, u64::MAX, 2);
+
+		assert!(!test_ee.in_exception());
+		assert_eq!(test_ee.read_register(2), 1);
+	}
+
+	#[test]
+	fn basic_xor_nor() {
+		let xor_ee = run_register_op(MipsFunction::Xor, 0b1100, 0b1010, 0);
+		let nor_ee = run_register_op(MipsFunction::Nor, 0b1100, 0b1010, 0);
+
+		assert_eq!(xor_ee.read_register(3), 0b0110);
+		assert_eq!(nor_ee.read_register(3), !0b1110);
+	}
+
+	#[test]
+	fn xori_zero_extends() {
+		let test_ee = run_immediate_op(MipsOpcode::XorI, 0xffff_0000_0000_1234, 0xffff);
+		assert_eq!(test_ee.read_register(2), 0xffff_0000_0000_edcb);
+	}
+
+	#[test]
+	fn basic_movz() {
+		let moving_ee = run_register_op(MipsFunction::MovZ, 1234, 0, 0);
+		let staying_ee = run_register_op(MipsFunction::MovZ, 1234, 1, 0);
+
+		assert_eq!(moving_ee.read_register(3), 1234);
+		assert_eq!(staying_ee.read_register(3), 0);
+	}
+
+	#[test]
+	fn basic_multu() {
+		let test_ee = run_register_op(MipsFunction::MultU, u32::MAX.s_ext(), 2, 0);
+		let mult_result = u64::from(u32::MAX) * 2;
+
+		assert_eq!(test_ee.read_hi(), ((mult_result >> 32) as u32).s_ext());
+		assert_eq!(test_ee.read_lo(), (mult_result as u32).s_ext());
+		assert_eq!(test_ee.read_register(3), (mult_result as u32).s_ext());
+	}
+
+	#[test]
+	fn mult_ignores_upper_operand_bits() {
+		// Only the low words of rs and rt take part in a multiply.
+		let test_ee = run_register_op(MipsFunction::Mult, 0x1_0000_0003, 0xffff_ffff_ffff_fffe, 0);
+
+		assert_eq!(test_ee.read_lo(), (-6i32).s_ext());
+		assert_eq!(test_ee.read_hi(), u64::MAX);
+		assert_eq!(test_ee.read_register(3), (-6i32).s_ext());
+	}
+
+	#[test]
+	fn pipe_one_multiplies() {
+		let mut test_ee = EECore::new();
+		test_ee.write_hi(0x1111);
+		test_ee.write_lo(0x2222);
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MULT1, (-3i32).s_ext(), 0x4000_0000);
+
+		// -3 * 2^30 = 0xffff_ffff_4000_0000
+		assert_eq!(test_ee.read_hi1(), u64::MAX);
+		assert_eq!(test_ee.read_lo1(), 0x4000_0000);
+		assert_eq!(test_ee.read_register(3), 0x4000_0000);
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MULTU1, u32::MAX.s_ext(), 2);
+
+		assert_eq!(test_ee.read_hi1(), 1);
+		assert_eq!(test_ee.read_lo1(), 0xffff_ffff_ffff_fffe);
+
+		// The I0 multiplier's results are untouched.
+		assert_eq!(test_ee.read_hi(), 0x1111);
+		assert_eq!(test_ee.read_lo(), 0x2222);
+	}
+
+	#[test]
+	fn interleaved_pipe_multiplies() {
+		let mut test_ee = EECore::new();
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MULT1, 7, 6);
+		test_ee.execute(ops::process_instruction(mips::build_op_register(MipsFunction::Mult, 1, 2, 0, 0)));
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MULT1, 5, 5);
+
+		assert_eq!(test_ee.read_lo(), 42);
+		assert_eq!(test_ee.read_lo1(), 25);
+	}
+
+	#[test]
+	fn basic_madd() {
+		let mut test_ee = EECore::new();
+		test_ee.write_hi(0);
+		test_ee.write_lo(0xffff_ffff);
+
+		// The accumulator is {HI[31:0], LO[31:0]}, so this carries into HI.
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MADD, 1, 1);
+
+		assert_eq!(test_ee.read_hi(), 1);
+		assert_eq!(test_ee.read_lo(), 0);
+		assert_eq!(test_ee.read_register(3), 0);
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MADD, (-2i32).s_ext(), 0x8000_0000);
+
+		// 0x1_0000_0000 + (-2 * -2^31) = 0x2_0000_0000
+		assert_eq!(test_ee.read_hi(), 2);
+		assert_eq!(test_ee.read_lo(), 0);
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MADD, (-1i32).s_ext(), 0x2_0000_0003);
+
+		// 0x2_0000_0000 - 3, with each word sign-extended.
+		assert_eq!(test_ee.read_hi(), 1);
+		assert_eq!(test_ee.read_lo(), (-3i32).s_ext());
+	}
+
+	#[test]
+	fn basic_maddu() {
+		let mut test_ee = EECore::new();
+		test_ee.write_hi(0xffff_ffff_8000_0000);
+		test_ee.write_lo(0);
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MADDU, u32::MAX.s_ext(), 2);
+
+		// 0x8000_0000_0000_0000 + 0x1_ffff_fffe
+		assert_eq!(test_ee.read_hi(), 0xffff_ffff_8000_0001);
+		assert_eq!(test_ee.read_lo(), 0xffff_ffff_ffff_fffe);
+		assert_eq!(test_ee.read_register(3), 0xffff_ffff_ffff_fffe);
+	}
+
+	#[test]
+	fn pipe_one_madd() {
+		let mut test_ee = EECore::new();
+		test_ee.write_hi1(3);
+		test_ee.write_lo1(4);
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MADD1, (-1i32).s_ext(), 5);
+
+		assert_eq!(test_ee.read_hi1(), 2);
+		assert_eq!(test_ee.read_lo1(), u64::MAX);
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::MADDU1, 1, 1);
+
+		assert_eq!(test_ee.read_hi1(), 3);
+		assert_eq!(test_ee.read_lo1(), 0);
+		assert_eq!(test_ee.read_hi(), 0);
+		assert_eq!(test_ee.read_lo(), 0);
+	}
+
+	#[test]
+	fn pipe_one_divides() {
+		let mut test_ee = EECore::new();
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::DIV1, (-200i32).s_ext(), (-6i32).s_ext());
+
+		assert_eq!(test_ee.read_lo1() as i32, -200 / -6);
+		assert_eq!(test_ee.read_hi1() as i32, -200 % -6);
+
+		run_mmi_op(&mut test_ee, MmiBaseFunction::DIVU1, u32::MAX.s_ext(), 5);
+
+		assert_eq!(test_ee.read_lo1(), u64::from(u32::MAX / 5));
+		assert_eq!(test_ee.read_hi1(), 0);
+
+		// Division by zero leaves HI1/LO1 alone.
+		run_mmi_op(&mut test_ee, MmiBaseFunction::DIV1, 1, 0);
+
+		assert!(!test_ee.in_exception());
+		assert_eq!(test_ee.read_lo1(), u64::from(u32::MAX / 5));
+		assert_eq!(test_ee.read_hi(), 0);
+		assert_eq!(test_ee.read_lo(), 0);
+	}
+
+	#[test]
+	fn variable_word_shifts() {
+		// Only the lowest 5 bits of rs are used.
+		let sllv_ee = run_register_op(MipsFunction::SLLV, 32 + 4, 0x0800_0001, 0);
+		let srlv_ee = run_register_op(MipsFunction::SRLV, 4, 0xffff_ffff_8000_0000, 0);
+		let srav_ee = run_register_op(MipsFunction::SRAV, 4, 0xffff_ffff_8000_0000, 0);
+
+		assert_eq!(sllv_ee.read_register(3), 0xffff_ffff_8000_0010);
+		assert_eq!(srlv_ee.read_register(3), 0x0800_0000);
+		assert_eq!(srav_ee.read_register(3), 0xffff_ffff_f800_0000);
+	}
+
+	#[test]
+	fn doubleword_shifts() {
+		let input = 0x8000_0000_0000_0010;
+
+		assert_eq!(run_register_op(MipsFunction::DSLL, 0, input, 4).read_register(3), 0x100);
+		assert_eq!(run_register_op(MipsFunction::DSRL, 0, input, 4).read_register(3), 0x0800_0000_0000_0001);
+		assert_eq!(run_register_op(MipsFunction::DSRA, 0, input, 4).read_register(3), 0xf800_0000_0000_0001);
+
+		assert_eq!(run_register_op(MipsFunction::DSLL32, 0, input, 4).read_register(3), 0x100_0000_0000);
+		assert_eq!(run_register_op(MipsFunction::DSRL32, 0, input, 4).read_register(3), 0x0800_0000);
+		assert_eq!(run_register_op(MipsFunction::DSRA32, 0, input, 4).read_register(3), 0xffff_ffff_f800_0000);
+	}
+
+	#[test]
+	fn variable_doubleword_shifts() {
+		// Only the lowest 6 bits of rs are used.
+		let input = 0x8000_0000_0000_0010;
+
+		assert_eq!(run_register_op(MipsFunction::DSLLV, 64 + 36, input, 0).read_register(3), 0x100_0000_0000);
+		assert_eq!(run_register_op(MipsFunction::DSRLV, 36, input, 0).read_register(3), 0x0800_0000);
+		assert_eq!(run_register_op(MipsFunction::DSRAV, 36, input, 0).read_register(3), 0xffff_ffff_f800_0000);
+	}
 }
\ No newline at end of file
@@ -30,31 +30,83 @@ pub fn bgez(cpu: &mut EECore, data: &OpCode) {
 	cpu.branch(data, inner_bne as BranchAction, cond as u32);
 }
 
+pub fn bgezal(cpu: &mut EECore, data: &OpCode) {
+	// As BGEZ, storing PC after BD-slot in R31 regardless of outcome.
+	let cond = cpu.read_register(data.ri_get_source()) as i64 >= 0;
+	link(cpu);
+	cpu.branch(data, inner_bne as BranchAction, cond as u32);
+}
+
+pub fn bgezall(cpu: &mut EECore, data: &OpCode) {
+	// As BGEZL, storing PC after BD-slot in R31 regardless of outcome.
+	let cond = cpu.read_register(data.ri_get_source()) as i64 >= 0;
+	link(cpu);
+	cpu.branch(data, inner_bnel as BranchAction, cond as u32);
+}
+
+pub fn bgezl(cpu: &mut EECore, data: &OpCode) {
+	// If GPR[rs]>=0, then apply offset to current PC as in BNEL.
+	let cond = cpu.read_register(data.ri_get_source()) as i64 >= 0;
+	cpu.branch(data, inner_bnel as BranchAction, cond as u32);
+}
+
 pub fn bgtz(cpu: &mut EECore, data: &OpCode) {
 	// If GPR[rs]>0, then apply offset to current PC as in BNE.
 	let cond = cpu.read_register(data.ri_get_source()) as i64 > 0;
 	cpu.branch(data, inner_bne as BranchAction, cond as u32);
 }
 
+pub fn bgtzl(cpu: &mut EECore, data: &OpCode) {
+	// If GPR[rs]>0, then apply offset to current PC as in BNEL.
+	let cond = cpu.read_register(data.ri_get_source()) as i64 > 0;
+	cpu.branch(data, inner_bnel as BranchAction, cond as u32);
+}
+
 pub fn blez(cpu: &mut EECore, data: &OpCode) {
 	// If GPR[rs]<=0, then apply offset to current PC as in BNE.
 	let cond = cpu.read_register(data.ri_get_source()) as i64 <= 0;
 	cpu.branch(data, inner_bne as BranchAction, cond as u32);
 }
 
+pub fn blezl(cpu: &mut EECore, data: &OpCode) {
+	// If GPR[rs]<=0, then apply offset to current PC as in BNEL.
+	let cond = cpu.read_register(data.ri_get_source()) as i64 <= 0;
+	cpu.branch(data, inner_bnel as BranchAction, cond as u32);
+}
+
 pub fn bltz(cpu: &mut EECore, data: &OpCode) {
 	// If GPR[rs]<0, then apply offset to current PC as in BNE.
 	let cond = (cpu.read_register(data.ri_get_source()) as i64) < 0;
 	cpu.branch(data, inner_bne as BranchAction, cond as u32);
 }
 
+pub fn bltzal(cpu: &mut EECore, data: &OpCode) {
+	// As BLTZ, storing PC after BD-slot in R31 regardless of outcome.
+	let cond = (cpu.read_register(data.ri_get_source()) as i64) < 0;
+	link(cpu);
+	cpu.branch(data, inner_bne as BranchAction, cond as u32);
+}
+
+pub fn bltzall(cpu: &mut EECore, data: &OpCode) {
+	// As BLTZL, storing PC after BD-slot in R31 regardless of outcome.
+	let cond = (cpu.read_register(data.ri_get_source()) as i64) < 0;
+	link(cpu);
+	cpu.branch(data, inner_bnel as BranchAction, cond as u32);
+}
+
+pub fn bltzl(cpu: &mut EECore, data: &OpCode) {
+	// If GPR[rs]<0, then apply offset to current PC as in BNEL.
+	let cond = (cpu.read_register(data.ri_get_source()) as i64) < 0;
+	cpu.branch(data, inner_bnel as BranchAction, cond as u32);
+}
+
 pub fn bne(cpu: &mut EECore, data: &OpCode) {
 	// Compute condition here.
 	let cond = cpu.read_register(data.ri_get_source()) != cpu.read_register(data.ri_get_target());
 	cpu.branch(data, inner_bne as BranchAction, cond as u32);
 }
 
-fn inner_bne(cpu: &mut EECore, data: &BranchOpCode) -> BranchResult {
+pub(super) fn inner_bne(cpu: &mut EECore, data: &BranchOpCode) -> BranchResult {
 	// Add immediate to current PC value.
 	if data.temp != 0 {
 		cpu.pc_register = v_addr_with_offset_branch(cpu, data);
@@ -70,7 +122,7 @@ pub fn bnel(cpu: &mut EECore, data: &OpCode) {
 	cpu.branch(data, inner_bnel as BranchAction, cond as u32);
 }
 
-fn inner_bnel(cpu: &mut EECore, data: &BranchOpCode) -> BranchResult {
+pub(super) fn inner_bnel(cpu: &mut EECore, data: &BranchOpCode) -> BranchResult {
 	// Add immediate to current PC value.
 	if data.temp != 0 {
 		cpu.pc_register = v_addr_with_offset_branch(cpu, data);
@@ -96,10 +148,7 @@ fn inner_j(cpu: &mut EECore, data: &BranchOpCode) -> BranchResult {
 
 pub fn jal(cpu: &mut EECore, data: &OpCode) {
 	// Store PC after BD-slot in R31.
-	cpu.write_register(
-		31,
-		u64::from(cpu.pc_register.wrapping_add((OPCODE_LENGTH_BYTES * 2) as u32))
-	);
+	link(cpu);
 
 	cpu.branch(data, inner_j as BranchAction, 0);
 }
@@ -135,6 +184,18 @@ fn inner_jr(cpu: &mut EECore, data: &BranchOpCode) -> BranchResult {
 	BranchResult::BRANCHED
 }
 
+#[inline]
+fn link(cpu: &mut EECore) {
+	cpu.write_register(
+		31,
+		u64::from(cpu.pc_register.wrapping_add((OPCODE_LENGTH_BYTES * 2) as u32))
+	);
+}
+
+pub fn syscall(cpu: &mut EECore, _data: &OpCode) {
+	cpu.throw_l1_exception(L1Exception::Systemcall);
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -155,6 +216,7 @@ mod tests {
 		},
 		memory::constants::*,
 	};
+	use crate::core::cop0::Register;
 
 	#[test]
 	fn jump_not_instant() {
@@ -446,6 +508,115 @@ mod tests {
 		assert_eq!(jumping_ee.read_register(4), proof_of_delay.z_ext());
 	}
 
+	fn run_likely(instruction: u32, rs_value: i64) -> EECore {
+		// Run a branch with a delay slot which writes to R4.
+		let program = instructions_to_bytes(&[
+			instruction,
+			mips::build_op_immediate(MipsOpcode::OrI, 0, 4, 0xa123),
+		]);
+
+		let mut test_ee = EECore::new();
+		test_ee.write_register(1, rs_value as u64);
+		install_and_run_program(&mut test_ee, program);
+
+		test_ee
+	}
+
+	fn assert_likely(instruction: u32, jumping_value: i64, staying_value: i64) {
+		let jump_target = BIOS_START + 4 + (0xf0 << 2);
+
+		let jumping_ee = run_likely(instruction, jumping_value);
+		let staying_ee = run_likely(instruction, staying_value);
+
+		assert_eq!(jumping_ee.pc_register, jump_target);
+		assert_eq!(jumping_ee.read_register(4), 0xa123);
+
+		assert_eq!(staying_ee.pc_register, BIOS_START + 8);
+		assert_eq!(staying_ee.read_register(4), 0);
+	}
+
+	#[test]
+	fn basic_bgezl_nullifies() {
+		let instruction = mips::build_op_immediate(MipsOpcode::RegImm, 1, RegImmFunction::BGEZL as u8, 0xf0);
+		assert_likely(instruction, 0, -1);
+	}
+
+	#[test]
+	fn basic_bltzl_nullifies() {
+		let instruction = mips::build_op_immediate(MipsOpcode::RegImm, 1, RegImmFunction::BLTZL as u8, 0xf0);
+		assert_likely(instruction, -1, 0);
+	}
+
+	#[test]
+	fn basic_bgtzl_nullifies() {
+		let instruction = mips::build_op_immediate(MipsOpcode::BGTZL, 1, 0, 0xf0);
+		assert_likely(instruction, 1, 0);
+	}
+
+	#[test]
+	fn basic_blezl_nullifies() {
+		let instruction = mips::build_op_immediate(MipsOpcode::BLEZL, 1, 0, 0xf0);
+		assert_likely(instruction, 0, 1);
+	}
+
+	#[test]
+	fn bgezal_always_links() {
+		let instruction = mips::build_op_immediate(MipsOpcode::RegImm, 1, RegImmFunction::BGEZAL as u8, 0xf0);
+		let jump_target = BIOS_START + 4 + (0xf0 << 2);
+
+		let jumping_ee = run_likely(instruction, 5);
+		let staying_ee = run_likely(instruction, -5);
+
+		assert_eq!(jumping_ee.pc_register, jump_target);
+		assert_eq!(staying_ee.pc_register, BIOS_START + 8);
+
+		// Delay slot is executed either way.
+		assert_eq!(staying_ee.read_register(4), 0xa123);
+
+		assert_eq!(jumping_ee.read_register(31) as u32, BIOS_START + 8);
+		assert_eq!(staying_ee.read_register(31) as u32, BIOS_START + 8);
+	}
+
+	#[test]
+	fn bltzall_links_and_nullifies() {
+		let instruction = mips::build_op_immediate(MipsOpcode::RegImm, 1, RegImmFunction::BLTZALL as u8, 0xf0);
+		assert_likely(instruction, -5, 5);
+
+		let staying_ee = run_likely(instruction, 5);
+		assert_eq!(staying_ee.read_register(31) as u32, BIOS_START + 8);
+	}
+
+	#[test]
+	fn basic_bltzal() {
+		let instruction = mips::build_op_immediate(MipsOpcode::RegImm, 1, RegImmFunction::BLTZAL as u8, 0xf0);
+		let jump_target = BIOS_START + 4 + (0xf0 << 2);
+
+		let jumping_ee = run_likely(instruction, -5);
+
+		assert_eq!(jumping_ee.pc_register, jump_target);
+		assert_eq!(jumping_ee.read_register(31) as u32, BIOS_START + 8);
+	}
+
+	#[test]
+	fn basic_bgezall() {
+		let instruction = mips::build_op_immediate(MipsOpcode::RegImm, 1, RegImmFunction::BGEZALL as u8, 0xf0);
+		assert_likely(instruction, 0, -5);
+	}
+
+	#[test]
+	fn syscall_raises_exception() {
+		let mut test_ee = EECore::new();
+
+		let instruction = mips::build_op_register(MipsFunction::Syscall, 0, 0, 0, 0);
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert!(test_ee.in_exception());
+		assert_eq!(
+			(test_ee.read_cop0(Register::Cause as u8) >> 2) & 0b1_1111,
+			u32::from(u8::from(L1Exception::Systemcall)),
+		);
+	}
+
 	#[test]
 	fn basic_jump() {
 		// Execute a jump instruction and a NOP. PC changes to new target.
@@ -1,10 +1,12 @@
 pub mod cache;
 
+use super::branch;
 use crate::{
 	core::{
 		cop0::{
 			Register,
 			Status,
+			INDEX_PROBE_FAILURE,
 		},
 		exceptions::L1Exception,
 		pipeline::*,
@@ -31,6 +33,90 @@ fn cop0_usable(cpu: &mut EECore) -> bool {
 	valid
 }
 
+pub fn bc0f(cpu: &mut EECore, data: &OpCode) {
+	if !cop0_usable(cpu) {
+		return;
+	}
+
+	let cond = !cpu.cpcond0;
+	cpu.branch(data, branch::inner_bne as BranchAction, cond as u32);
+}
+
+pub fn bc0fl(cpu: &mut EECore, data: &OpCode) {
+	if !cop0_usable(cpu) {
+		return;
+	}
+
+	let cond = !cpu.cpcond0;
+	cpu.branch(data, branch::inner_bnel as BranchAction, cond as u32);
+}
+
+pub fn bc0t(cpu: &mut EECore, data: &OpCode) {
+	if !cop0_usable(cpu) {
+		return;
+	}
+
+	let cond = cpu.cpcond0;
+	cpu.branch(data, branch::inner_bne as BranchAction, cond as u32);
+}
+
+pub fn bc0tl(cpu: &mut EECore, data: &OpCode) {
+	if !cop0_usable(cpu) {
+		return;
+	}
+
+	let cond = cpu.cpcond0;
+	cpu.branch(data, branch::inner_bnel as BranchAction, cond as u32);
+}
+
+#[inline(always)]
+fn set_interrupt_enable(cpu: &mut EECore, enable: bool) {
+	// Outside kernel mode, EI/DI are only honoured when Status.EDI is set.
+	let mut status = Status::from_bits_truncate(cpu.read_cop0_direct(Register::Status as u8));
+
+	if status.contains(Status::ENABLE_EDI) || status.privilege_level().is_kernel() {
+		status.set(Status::ENABLE_IE, enable);
+		cpu.write_cop0_direct(Register::Status as u8, status.bits());
+	}
+}
+
+pub fn di(cpu: &mut EECore, _data: &OpCode) {
+	if !cop0_usable(cpu) {
+		return;
+	}
+
+	set_interrupt_enable(cpu, false);
+}
+
+pub fn ei(cpu: &mut EECore, _data: &OpCode) {
+	if !cop0_usable(cpu) {
+		return;
+	}
+
+	set_interrupt_enable(cpu, true);
+}
+
+pub fn eret(cpu: &mut EECore, _data: &OpCode) {
+	if !cop0_usable(cpu) {
+		return;
+	}
+
+	// Leave the innermost exception level, resuming at its saved PC.
+	// There is no delay slot.
+	let mut status = Status::from_bits_truncate(cpu.read_cop0_direct(Register::Status as u8));
+
+	let target = if status.contains(Status::ERROR_LEVEL) {
+		status.remove(Status::ERROR_LEVEL);
+		cpu.read_cop0_direct(Register::ErrorEPC as u8)
+	} else {
+		status.remove(Status::EXCEPTION_LEVEL);
+		cpu.read_cop0_direct(Register::EPC as u8)
+	};
+
+	cpu.write_cop0_direct(Register::Status as u8, status.bits());
+	cpu.jump_immediate(target);
+}
+
 pub fn mfc0(cpu: &mut EECore, data: &OpCode) {
 	if !cop0_usable(cpu) {
 		return;
@@ -51,6 +137,35 @@ pub fn mtc0(cpu: &mut EECore, data: &OpCode) {
 	cpu.write_cop0(data.r_get_destination(), v);
 }
 
+pub fn tlbp(cpu: &mut EECore, _data: &OpCode) {
+	if !cop0_usable(cpu) {
+		return;
+	}
+
+	// find the line matching EntryHi, or set Index.P on a miss.
+	let index = cpu.mmu.probe(cpu.read_cop0_direct(Register::EntryHi as u8))
+		.map_or(INDEX_PROBE_FAILURE, |i| i as u32);
+
+	cpu.write_cop0_direct(Register::Index as u8, index);
+}
+
+pub fn tlbr(cpu: &mut EECore, _data: &OpCode) {
+	if !cop0_usable(cpu) {
+		return;
+	}
+
+	// copy the line at Index back into the COP0 MMU registers.
+	let line = match cpu.mmu.tlb.lines.get(cpu.mmu.index as usize) {
+		Some(line) => *line,
+		None => return,
+	};
+
+	cpu.write_cop0_direct(Register::PageMask as u8, line.mask);
+	cpu.write_cop0_direct(Register::EntryHi as u8, line.entry_hi());
+	cpu.write_cop0_direct(Register::EntryLo0 as u8, line.entry_lo0());
+	cpu.write_cop0_direct(Register::EntryLo1 as u8, line.entry_lo1());
+}
+
 pub fn tlbwi(cpu: &mut EECore, _data: &OpCode) {
 	if !cop0_usable(cpu) {
 		return;
@@ -94,6 +209,10 @@ mod tests {
 			},
 			ops,
 		},
+		dmac::{
+			ChannelId,
+			D_PCR_PHYSICAL,
+		},
 		isa::mips::{
 			self,
 			ee::*,
@@ -266,4 +385,210 @@ mod tests {
 		assert!(forbidden_ee.in_exception());
 		assert!(!allowed_ee.in_exception());
 	}
-}
\ No newline at end of file
+
+	fn set_status(test_ee: &mut EECore, insert: Status, remove: Status) {
+		let mut status = Status::from_bits_truncate(test_ee.read_cop0_direct(Register::Status as u8));
+		status.insert(insert);
+		status.remove(remove);
+		test_ee.write_cop0_direct(Register::Status as u8, status.bits());
+	}
+
+	fn cop0_c0(function: C0Function) -> u32 {
+		mips::build_op_register_custom(MipsOpcode::Cop0, function as u8, C0, 0, 0, 0)
+	}
+
+	#[test]
+	fn syscall_eret_round_trip() {
+		let mut test_ee = EECore::new();
+
+		let syscall = mips::build_op_register(MipsFunction::Syscall, 0, 0, 0, 0);
+		test_ee.execute(ops::process_instruction(syscall));
+
+		let cause = cop0::Cause::from_bits_truncate(test_ee.read_cop0_direct(Register::Cause as u8));
+		assert!(test_ee.in_exception());
+		assert!(!cause.contains(cop0::Cause::BRANCH_DELAY_1));
+		assert_eq!(test_ee.read_cop0_direct(Register::EPC as u8), BIOS_START);
+
+		// Handlers step over the SYSCALL before returning.
+		test_ee.write_cop0_direct(Register::EPC as u8, BIOS_START + 4);
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::Eret)));
+
+		assert!(!test_ee.in_exception());
+		assert_eq!(test_ee.pc_register, BIOS_START + 4);
+	}
+
+	#[test]
+	fn eret_prefers_error_level() {
+		let mut test_ee = EECore::new();
+		set_status(&mut test_ee, Status::ERROR_LEVEL | Status::EXCEPTION_LEVEL, Status::empty());
+		test_ee.write_cop0_direct(Register::EPC as u8, 0x1000);
+		test_ee.write_cop0_direct(Register::ErrorEPC as u8, 0x2000);
+
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::Eret)));
+
+		let status = Status::from_bits_truncate(test_ee.read_cop0_direct(Register::Status as u8));
+		assert_eq!(test_ee.pc_register, 0x2000);
+		assert!(!status.contains(Status::ERROR_LEVEL));
+		assert!(status.contains(Status::EXCEPTION_LEVEL));
+
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::Eret)));
+
+		assert_eq!(test_ee.pc_register, 0x1000);
+		assert!(!test_ee.in_exception());
+	}
+
+	#[test]
+	fn exception_in_delay_slot_saves_branch() {
+		let mut test_ee = EECore::new();
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_immediate(MipsOpcode::BEq, 0, 0, 0x10),
+			mips::build_op_register(MipsFunction::Syscall, 0, 0, 0, 0),
+		]));
+
+		let cause = cop0::Cause::from_bits_truncate(test_ee.read_cop0_direct(Register::Cause as u8));
+		assert!(test_ee.in_exception());
+		assert!(cause.contains(cop0::Cause::BRANCH_DELAY_1));
+		assert_eq!(test_ee.read_cop0_direct(Register::EPC as u8), BIOS_START);
+	}
+
+	#[test]
+	fn ei_di_respect_edi() {
+		let interrupts_enabled = |test_ee: &EECore| Status::from_bits_truncate(
+			test_ee.read_cop0_direct(Register::Status as u8)
+		).contains(Status::ENABLE_IE);
+
+		// User mode, without EDI: no effect.
+		let mut test_ee = EECore::new();
+		set_status(&mut test_ee, Status::USER_MODE | Status::COP0_USABLE, Status::ENABLE_IE | Status::ENABLE_EDI);
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::EI)));
+		assert!(!interrupts_enabled(&test_ee));
+
+		// User mode, with EDI.
+		set_status(&mut test_ee, Status::ENABLE_EDI, Status::empty());
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::EI)));
+		assert!(interrupts_enabled(&test_ee));
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::DI)));
+		assert!(!interrupts_enabled(&test_ee));
+
+		// Kernel mode ignores EDI.
+		let mut test_ee = EECore::new();
+		set_status(&mut test_ee, Status::empty(), Status::ENABLE_IE | Status::ENABLE_EDI);
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::EI)));
+		assert!(interrupts_enabled(&test_ee));
+	}
+
+	#[test]
+	fn bc0_follows_cpcond0() {
+		let jump_offset: u16 = 0x00_f0;
+		let jump_target = BIOS_START + 4 + ((jump_offset as u32) << 2);
+
+		for (condition, branch, taken) in [
+			(true, BC0Function::BC0T, true),
+			(false, BC0Function::BC0T, false),
+			(true, BC0Function::BC0F, false),
+			(false, BC0Function::BC0F, true),
+			(true, BC0Function::BC0TL, true),
+			(false, BC0Function::BC0FL, true),
+		].iter() {
+			let mut test_ee = EECore::new();
+
+			// CPCOND0 stays clear while a channel selected by D_PCR has yet to complete.
+			let pcr: u32 = if *condition { 0 } else { 1 << (ChannelId::Gif as u32) };
+			test_ee.store(KSEG1_START + D_PCR_PHYSICAL, pcr);
+
+			install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+				mips::build_op_immediate(MipsOpcode::Cop0, BC0, *branch as u8, jump_offset),
+				NOP,
+			]));
+
+			let expected = if *taken { jump_target } else { BIOS_START + 8 };
+			assert_eq!(test_ee.pc_register, expected);
+		}
+	}
+
+	#[test]
+	fn bc0tl_nullifies() {
+		let mut test_ee = EECore::new();
+		test_ee.store(KSEG1_START + D_PCR_PHYSICAL, 1u32 << (ChannelId::Gif as u32));
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_immediate(MipsOpcode::Cop0, BC0, BC0Function::BC0TL as u8, 0x00_f0),
+			mips::build_op_immediate(MipsOpcode::OrI, 0, 4, 0xa123),
+		]));
+
+		assert_eq!(test_ee.pc_register, BIOS_START + 8);
+		assert_eq!(test_ee.read_register(4), 0);
+	}
+
+	#[test]
+	fn basic_tlbr() {
+		let mut test_ee = EECore::new();
+
+		let hi = cop0::entry_hi_from_parts(0x1234, 7);
+		let lo0 = cop0::entry_lo_from_parts(false, 0x450, 3, true, true, false);
+		let lo1 = cop0::entry_lo_from_parts(false, 0x460, 2, false, true, false);
+
+		test_ee.write_cop0(Register::Index as u8, 9);
+		test_ee.write_cop0(Register::PageMask as u8, 0b11 << 13);
+		test_ee.mmu.write_index(hi, lo0, lo1);
+
+		for register in [Register::EntryHi as u8, Register::EntryLo0 as u8, Register::EntryLo1 as u8, Register::PageMask as u8].iter() {
+			test_ee.write_cop0(*register, 0);
+		}
+
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::TlbR)));
+
+		assert_eq!(test_ee.read_cop0_direct(Register::EntryHi as u8), hi);
+		assert_eq!(test_ee.read_cop0_direct(Register::EntryLo0 as u8), lo0);
+		assert_eq!(test_ee.read_cop0_direct(Register::EntryLo1 as u8), lo1);
+		assert_eq!(test_ee.read_cop0_direct(Register::PageMask as u8), 0b11 << 13);
+	}
+
+	#[test]
+	fn tlb_index_beyond_tlb_is_ignored() {
+		let mut test_ee = EECore::new();
+
+		let hi = cop0::entry_hi_from_parts(0x1234, 7);
+		let lo = cop0::entry_lo_from_parts(false, 0x450, 3, true, true, false);
+
+		// Only the line-select field of Index is kept.
+		test_ee.write_cop0(Register::Index as u8, cop0::INDEX_PROBE_FAILURE | 5);
+		assert_eq!(test_ee.mmu.index, 5);
+
+		// Lines 48--63 don't exist: TLBWI and TLBR leave everything alone.
+		test_ee.write_cop0(Register::Index as u8, 63);
+		test_ee.write_cop0(Register::EntryHi as u8, hi);
+		test_ee.write_cop0(Register::EntryLo0 as u8, lo);
+		test_ee.write_cop0(Register::EntryLo1 as u8, lo);
+
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::TlbWI)));
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::TlbR)));
+
+		assert_eq!(test_ee.mmu.probe(hi), None);
+		assert_eq!(test_ee.read_cop0_direct(Register::EntryHi as u8), hi);
+		assert_eq!(test_ee.read_cop0_direct(Register::EntryLo0 as u8), lo);
+	}
+
+	#[test]
+	fn basic_tlbp() {
+		let mut test_ee = EECore::new();
+
+		let hi = cop0::entry_hi_from_parts(0x1234, 7);
+		let lo = cop0::entry_lo_from_parts(false, 0x450, 3, true, true, false);
+
+		test_ee.write_cop0(Register::Index as u8, 12);
+		test_ee.mmu.write_index(hi, lo, lo);
+
+		// Hit.
+		test_ee.write_cop0(Register::Index as u8, 0);
+		test_ee.write_cop0(Register::EntryHi as u8, hi);
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::TlbP)));
+		assert_eq!(test_ee.read_cop0_direct(Register::Index as u8), 12);
+
+		// Miss, through a different ASID.
+		test_ee.write_cop0(Register::EntryHi as u8, cop0::entry_hi_from_parts(0x1234, 8));
+		test_ee.execute(ops::process_instruction(cop0_c0(C0Function::TlbP)));
+		assert_eq!(test_ee.read_cop0_direct(Register::Index as u8) & cop0::INDEX_PROBE_FAILURE, cop0::INDEX_PROBE_FAILURE);
+	}
+}
@@ -1,9 +1,15 @@
-use byteorder::{
-	ByteOrder,
-	LittleEndian,
-};
 use crate::{
 	core::{
+		cop0::{
+			Register,
+			Status,
+		},
+		cop1::Fcr31,
+		fpu::{
+			self,
+			FpuFlags,
+			FpuResult,
+		},
 		exceptions::L1Exception,
 		pipeline::*,
 		EECore,
@@ -11,14 +17,362 @@ use crate::{
 	isa::mips::Instruction,
 	utils::*,
 };
-use std::mem::size_of;
+use std::cmp::Ordering;
+use super::branch;
+
+#[inline(always)]
+fn cop1_usable(cpu: &mut EECore) -> bool {
+	// Unlike COP0, kernel mode does not grant access to the FPU:
+	// Status.CU1 must always be set.
+	let status = Status::from_bits_truncate(cpu.read_cop0_direct(Register::Status as u8));
+	let valid = status.contains(Status::COP1_USABLE);
+
+	if !valid {
+		cpu.throw_l1_exception(L1Exception::CoprocessorUnusable(1));
+	}
+
+	valid
+}
+
+// COP1 arithmetic reuses the R-type fields as `fmt`, `ft`, `fs`, `fd`.
+
+#[inline(always)]
+fn fd(data: &OpCode) -> u8 {
+	data.r_get_shift_amount()
+}
+
+#[inline(always)]
+fn fs(data: &OpCode) -> u8 {
+	data.r_get_destination()
+}
+
+#[inline(always)]
+fn ft(data: &OpCode) -> u8 {
+	data.ri_get_target()
+}
+
+/// Record the flags raised by a soft-float operation in FCR31.
+///
+/// `cause` lists the flags which the operation is responsible for: these
+/// are cleared before any raised flags (and their sticky twins) are set.
+#[inline]
+fn update_flags(cpu: &mut EECore, cause: Fcr31, flags: FpuFlags) {
+	let mut status = cpu.read_fpu_status();
+	status.remove(cause);
+	status.insert(Fcr31::from(flags));
+	cpu.write_fpu_status(status);
+}
+
+/// Apply `op` to FPR[fs] and FPR[ft], storing the result in FPR[fd].
+#[inline]
+fn binary_op(cpu: &mut EECore, data: &OpCode, op: fn(u32, u32) -> FpuResult, cause: Fcr31) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let result = op(cpu.read_cop1(fs(data)), cpu.read_cop1(ft(data)));
+	cpu.write_cop1(fd(data), result.value);
+	update_flags(cpu, cause, result.flags);
+}
+
+/// Apply `op` to FPR[fs] and FPR[ft], storing the result in ACC.
+#[inline]
+fn binary_op_acc(cpu: &mut EECore, data: &OpCode, op: fn(u32, u32) -> FpuResult) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let result = op(cpu.read_cop1(fs(data)), cpu.read_cop1(ft(data)));
+	cpu.fpu_accumulator = result.value;
+	update_flags(cpu, Fcr31::ARITHMETIC_CAUSE, result.flags);
+}
+
+/// Compute `op(ACC, FPR[fs] * FPR[ft])`, where the product is truncated
+/// (and clamped) before being accumulated.
+#[inline]
+fn multiply_accumulate_inner(cpu: &mut EECore, data: &OpCode, op: fn(u32, u32) -> FpuResult) -> FpuResult {
+	let product = fpu::mul(cpu.read_cop1(fs(data)), cpu.read_cop1(ft(data)));
+	let mut result = op(cpu.fpu_accumulator, product.value);
+	result.flags |= product.flags;
+	result
+}
+
+/// Compute `op(ACC, FPR[fs] * FPR[ft])`, storing the result in FPR[fd].
+#[inline]
+fn multiply_accumulate(cpu: &mut EECore, data: &OpCode, op: fn(u32, u32) -> FpuResult) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let result = multiply_accumulate_inner(cpu, data, op);
+	cpu.write_cop1(fd(data), result.value);
+	update_flags(cpu, Fcr31::ARITHMETIC_CAUSE, result.flags);
+}
+
+/// Compute `op(ACC, FPR[fs] * FPR[ft])`, storing the result in ACC.
+#[inline]
+fn multiply_accumulate_acc(cpu: &mut EECore, data: &OpCode, op: fn(u32, u32) -> FpuResult) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let result = multiply_accumulate_inner(cpu, data, op);
+	cpu.fpu_accumulator = result.value;
+	update_flags(cpu, Fcr31::ARITHMETIC_CAUSE, result.flags);
+}
+
+/// Set FCR31.C according to `cond` applied to the ordering of FPR[fs] and FPR[ft].
+#[inline]
+fn compare(cpu: &mut EECore, data: &OpCode, cond: fn(Ordering) -> bool) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let order = fpu::compare(cpu.read_cop1(fs(data)), cpu.read_cop1(ft(data)));
+
+	let mut status = cpu.read_fpu_status();
+	status.set(Fcr31::CONDITION, cond(order));
+	cpu.write_fpu_status(status);
+}
+
+/// Apply a sign-manipulating `op` to FPR[fs], storing the result in FPR[fd].
+///
+/// These never raise flags, but clear `O` and `U`.
+#[inline]
+fn unary_sign_op(cpu: &mut EECore, data: &OpCode, op: fn(u32) -> u32) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let v = op(cpu.read_cop1(fs(data)));
+	cpu.write_cop1(fd(data), v);
+	update_flags(cpu, Fcr31::ARITHMETIC_CAUSE, FpuFlags::empty());
+}
+
+pub fn abs_s(cpu: &mut EECore, data: &OpCode) {
+	unary_sign_op(cpu, data, fpu::abs);
+}
+
+pub fn add_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op(cpu, data, fpu::add, Fcr31::ARITHMETIC_CAUSE);
+}
+
+pub fn adda_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op_acc(cpu, data, fpu::add);
+}
+
+pub fn bc1f(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let cond = !cpu.read_fpu_status().contains(Fcr31::CONDITION);
+	cpu.branch(data, branch::inner_bne as BranchAction, cond as u32);
+}
+
+pub fn bc1fl(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let cond = !cpu.read_fpu_status().contains(Fcr31::CONDITION);
+	cpu.branch(data, branch::inner_bnel as BranchAction, cond as u32);
+}
+
+pub fn bc1t(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let cond = cpu.read_fpu_status().contains(Fcr31::CONDITION);
+	cpu.branch(data, branch::inner_bne as BranchAction, cond as u32);
+}
+
+pub fn bc1tl(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let cond = cpu.read_fpu_status().contains(Fcr31::CONDITION);
+	cpu.branch(data, branch::inner_bnel as BranchAction, cond as u32);
+}
+
+pub fn c_eq_s(cpu: &mut EECore, data: &OpCode) {
+	compare(cpu, data, |order| order == Ordering::Equal);
+}
+
+pub fn c_f_s(cpu: &mut EECore, data: &OpCode) {
+	compare(cpu, data, |_order| false);
+}
+
+pub fn c_le_s(cpu: &mut EECore, data: &OpCode) {
+	compare(cpu, data, |order| order != Ordering::Greater);
+}
+
+pub fn c_lt_s(cpu: &mut EECore, data: &OpCode) {
+	compare(cpu, data, |order| order == Ordering::Less);
+}
+
+pub fn cfc1(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	// FCR[fs] -> GPR[rt], sign-extended.
+	let v = cpu.read_cop1_control(fs(data));
+	cpu.write_register(data.ri_get_target(), v.s_ext());
+}
+
+pub fn ctc1(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	// GPR[rt] -> FCR[fs]
+	let v = cpu.read_register(data.ri_get_target()) as u32;
+	cpu.write_cop1_control(fs(data), v);
+}
+
+pub fn cvt_s_w(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	// FPR[fs] holds a 32-bit integer.
+	let v = cpu.read_cop1(fs(data)) as i32;
+	cpu.write_cop1(fd(data), fpu::from_i32(v));
+}
+
+pub fn cvt_w_s(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	// Rounds toward zero, saturating out-of-range values.
+	let v = fpu::to_i32(cpu.read_cop1(fs(data)));
+	cpu.write_cop1(fd(data), v as u32);
+}
+
+pub fn div_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op(cpu, data, fpu::div, Fcr31::CAUSE);
+}
+
+pub fn lwc1(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let v_addr = v_addr_with_offset(cpu, data);
+
+	// FIXME: make size info part of address resolution.
+	if v_addr & 0b11 != 0 {
+		cpu.throw_l1_exception(L1Exception::AddressErrorFetchLoad(v_addr));
+		return;
+	}
+
+	let loc = cpu.load::<u32>(v_addr);
+
+	if let Some(loc) = loc {
+		cpu.write_cop1(ft(data), loc);
+	}
+}
+
+pub fn madd_s(cpu: &mut EECore, data: &OpCode) {
+	multiply_accumulate(cpu, data, fpu::add);
+}
+
+pub fn madda_s(cpu: &mut EECore, data: &OpCode) {
+	multiply_accumulate_acc(cpu, data, fpu::add);
+}
+
+pub fn max_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op(cpu, data, |a, b| FpuResult::from(fpu::max(a, b)), Fcr31::ARITHMETIC_CAUSE);
+}
+
+pub fn mfc1(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	// FPR[fs] -> GPR[rt], sign-extended.
+	let v = cpu.read_cop1(fs(data));
+	cpu.write_register(data.ri_get_target(), v.s_ext());
+}
+
+pub fn min_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op(cpu, data, |a, b| FpuResult::from(fpu::min(a, b)), Fcr31::ARITHMETIC_CAUSE);
+}
+
+pub fn mov_s(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	let v = cpu.read_cop1(fs(data));
+	cpu.write_cop1(fd(data), v);
+}
+
+pub fn msub_s(cpu: &mut EECore, data: &OpCode) {
+	multiply_accumulate(cpu, data, fpu::sub);
+}
+
+pub fn msuba_s(cpu: &mut EECore, data: &OpCode) {
+	multiply_accumulate_acc(cpu, data, fpu::sub);
+}
+
+pub fn mtc1(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	// 32 LSBs of GPR[rt] -> FPR[fs]
+	let v = cpu.read_register(data.ri_get_target()) as u32;
+	cpu.write_cop1(fs(data), v);
+}
+
+pub fn mul_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op(cpu, data, fpu::mul, Fcr31::ARITHMETIC_CAUSE);
+}
+
+pub fn mula_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op_acc(cpu, data, fpu::mul);
+}
+
+pub fn neg_s(cpu: &mut EECore, data: &OpCode) {
+	unary_sign_op(cpu, data, fpu::neg);
+}
+
+pub fn rsqrt_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op(cpu, data, fpu::rsqrt, Fcr31::CAUSE);
+}
+
+pub fn sqrt_s(cpu: &mut EECore, data: &OpCode) {
+	if !cop1_usable(cpu) {
+		return;
+	}
+
+	// NOTE: SQRT.S takes its operand from ft, not fs.
+	let result = fpu::sqrt(cpu.read_cop1(ft(data)));
+	cpu.write_cop1(fd(data), result.value);
+	update_flags(cpu, Fcr31::CAUSE, result.flags);
+}
+
+pub fn sub_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op(cpu, data, fpu::sub, Fcr31::ARITHMETIC_CAUSE);
+}
+
+pub fn suba_s(cpu: &mut EECore, data: &OpCode) {
+	binary_op_acc(cpu, data, fpu::sub);
+}
 
 pub fn swc1(cpu: &mut EECore, data: &OpCode) {
-	debug!("SWC1 not reading from Cop1 -- Writing zeroes!");
+	if !cop1_usable(cpu) {
+		return;
+	}
 
-	let to_store = 0 as u32;
-	let offset: u32 = data.i_get_immediate_signed().s_ext();
-	let v_addr = (cpu.read_register(data.ri_get_source()) as u32).wrapping_add(offset);
+	// mem[GPR[rs] + signed(imm)] <- FPR[ft]
+	let to_store = cpu.read_cop1(ft(data));
+	let v_addr = v_addr_with_offset(cpu, data);
 
 	// FIXME: make size info part of address resolution.
 	if v_addr & 0b11 != 0 {
@@ -26,17 +380,337 @@ pub fn swc1(cpu: &mut EECore, data: &OpCode) {
 		return;
 	}
 
-	if let Some(loc) = cpu.read_memory_mut(v_addr as u32, size_of::<u32>()) {
-		LittleEndian::write_u32(loc, to_store);
-	}
+	cpu.store(v_addr, to_store);
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::{
+		core::ops,
+		isa::mips::{
+			self,
+			ee::*,
+			Opcode as MipsOpcode,
+			NOP,
+		},
+		memory::constants::*,
+	};
+
+	fn fpu_ee() -> EECore {
+		let mut test_ee = EECore::new();
+
+		let mut status = Status::from_bits_truncate(test_ee.read_cop0_direct(Register::Status as u8));
+		status.insert(Status::COP1_USABLE);
+		test_ee.write_cop0_direct(Register::Status as u8, status.bits());
+
+		test_ee
+	}
+
+	fn build_op_s(function: C1SFunction, ft: u8, fs: u8, fd: u8) -> u32 {
+		mips::build_op_register_custom(MipsOpcode::Cop1, function as u8, FMT_S, ft, fs, fd)
+	}
+
+	fn run_s(test_ee: &mut EECore, function: C1SFunction, lhs: f32, rhs: f32) -> f32 {
+		test_ee.write_cop1(1, lhs.to_bits());
+		test_ee.write_cop1(2, rhs.to_bits());
+
+		test_ee.execute(ops::process_instruction(build_op_s(function, 2, 1, 3)));
+
+		f32::from_bits(test_ee.read_cop1(3))
+	}
+
+	#[test]
+	fn cop1_needs_enabled() {
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, 1234);
+
+		let instruction = mips::build_op_register_custom(MipsOpcode::Cop1, 0, MT1, 1, 2, 0);
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert!(test_ee.in_exception());
+		assert_eq!(test_ee.read_cop1(2), 0);
+	}
+
+	#[test]
+	fn basic_mtc1_mfc1() {
+		let mut test_ee = fpu_ee();
+		let value: u32 = 0xbf80_0000;
+
+		test_ee.write_register(1, value.z_ext());
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_register_custom(MipsOpcode::Cop1, 0, MT1, 1, 7, 0),
+			mips::build_op_register_custom(MipsOpcode::Cop1, 0, MF1, 2, 7, 0),
+		]));
+
+		assert_eq!(test_ee.read_cop1(7), value);
+		// MFC1 sign-extends.
+		assert_eq!(test_ee.read_register(2), value.s_ext());
+	}
+
+	#[test]
+	fn basic_cfc1() {
+		let mut test_ee = fpu_ee();
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_register_custom(MipsOpcode::Cop1, 0, CF1, 1, 0, 0),
+		]));
+
+		assert_eq!(test_ee.read_register(1), crate::core::constants::EE_FCR0.z_ext());
+	}
+
+	#[test]
+	fn ctc1_respects_writable_bits() {
+		let mut test_ee = fpu_ee();
+
+		test_ee.write_register(1, u64::MAX);
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_register_custom(MipsOpcode::Cop1, 0, CT1, 1, 31, 0),
+			mips::build_op_register_custom(MipsOpcode::Cop1, 0, CT1, 1, 0, 0),
+		]));
+
+		assert_eq!(test_ee.fcr31, (Fcr31::WRITABLE | Fcr31::FIXED_ONES).bits());
+		assert_eq!(test_ee.fcr0, crate::core::constants::EE_FCR0);
+	}
+
+	#[test]
+	fn basic_lwc1() {
+		let mut test_ee = fpu_ee();
+		let value: u32 = 0x4049_0fdb;
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_memory(KSEG1_START + 4, &value.to_le_bytes());
+
+		let instruction = mips::build_op_immediate(MipsOpcode::LWC1, 1, 5, 4);
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert_eq!(test_ee.read_cop1(5), value);
+	}
+
+	#[test]
+	fn lwc1_4_byte_aligned() {
+		let mut test_ee = fpu_ee();
+
+		test_ee.write_register(1, (KSEG1_START + 2).z_ext());
+
+		let instruction = mips::build_op_immediate(MipsOpcode::LWC1, 1, 5, 0);
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert!(test_ee.in_exception());
+	}
 
 	#[test]
 	fn basic_swc1() {
-		unimplemented!();
+		let mut test_ee = fpu_ee();
+		let value: u32 = 0x4049_0fdb;
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_cop1(5, value);
+
+		let instruction = mips::build_op_immediate(MipsOpcode::SWC1, 1, 5, 8);
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert_eq!(test_ee.load::<u32>(KSEG1_START + 8), Some(value));
+	}
+
+	#[test]
+	fn basic_add_s() {
+		let mut test_ee = fpu_ee();
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Add, 1.5, 2.25), 3.75);
+	}
+
+	#[test]
+	fn basic_sub_s() {
+		let mut test_ee = fpu_ee();
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Sub, 1.5, 2.25), -0.75);
+	}
+
+	#[test]
+	fn basic_mul_s() {
+		let mut test_ee = fpu_ee();
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Mul, 1.5, -4.0), -6.0);
+	}
+
+	#[test]
+	fn basic_div_s() {
+		let mut test_ee = fpu_ee();
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Div, 9.0, 2.0), 4.5);
+	}
+
+	#[test]
+	fn arithmetic_sets_cause_and_sticky_flags() {
+		let mut test_ee = fpu_ee();
+		// Fmax is a NaN pattern to the host, so compare bits.
+		let fmax = f32::from_bits(fpu::FMAX);
+
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Add, fmax, fmax).to_bits(), fpu::FMAX);
+		assert!(test_ee.read_fpu_status().contains(Fcr31::OVERFLOW | Fcr31::STICKY_OVERFLOW));
+
+		// The next arithmetic op clears O, but not SO.
+		run_s(&mut test_ee, C1SFunction::Add, 1.0, 1.0);
+		let status = test_ee.read_fpu_status();
+		assert!(!status.contains(Fcr31::OVERFLOW));
+		assert!(status.contains(Fcr31::STICKY_OVERFLOW));
+
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Div, -1.0, 0.0).to_bits(), fpu::FMAX | fpu::SIGN_BIT);
+		assert!(test_ee.read_fpu_status().contains(Fcr31::DIVIDE_BY_ZERO | Fcr31::STICKY_DIVIDE));
+
+		run_s(&mut test_ee, C1SFunction::Div, 0.0, 0.0);
+		let status = test_ee.read_fpu_status();
+		assert!(status.contains(Fcr31::INVALID | Fcr31::STICKY_INVALID | Fcr31::STICKY_DIVIDE));
+		assert!(!status.contains(Fcr31::DIVIDE_BY_ZERO));
+
+		// Division and square roots clear every cause flag, O and U included.
+		run_s(&mut test_ee, C1SFunction::Add, fmax, fmax);
+		run_s(&mut test_ee, C1SFunction::Sqrt, 0.0, 4.0);
+		let status = test_ee.read_fpu_status();
+		assert!(!status.intersects(Fcr31::CAUSE));
+		assert!(status.contains(Fcr31::STICKY_OVERFLOW | Fcr31::STICKY_INVALID));
+	}
+
+	#[test]
+	fn basic_max_min_s() {
+		let mut test_ee = fpu_ee();
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Max, -3.0, 2.0), 2.0);
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Min, -3.0, 2.0), -3.0);
+	}
+
+	#[test]
+	fn basic_sqrt_s() {
+		let mut test_ee = fpu_ee();
+
+		// SQRT.S reads ft.
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Sqrt, 0.0, 16.0), 4.0);
+	}
+
+	#[test]
+	fn basic_rsqrt_s() {
+		let mut test_ee = fpu_ee();
+		assert_eq!(run_s(&mut test_ee, C1SFunction::RSqrt, 3.0, 4.0), 1.5);
+	}
+
+	#[test]
+	fn basic_abs_neg_mov_s() {
+		let mut test_ee = fpu_ee();
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Abs, -3.5, 0.0), 3.5);
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Neg, -3.5, 0.0), 3.5);
+		assert_eq!(run_s(&mut test_ee, C1SFunction::Mov, -3.5, 0.0), -3.5);
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn basic_accumulator_ops() {
+		let mut test_ee = fpu_ee();
+
+		test_ee.write_cop1(1, 3.0f32.to_bits());
+		test_ee.write_cop1(2, 4.0f32.to_bits());
+
+		test_ee.execute(ops::process_instruction(build_op_s(C1SFunction::AddA, 2, 1, 0)));
+		assert_eq!(f32::from_bits(test_ee.fpu_accumulator), 7.0);
+
+		test_ee.execute(ops::process_instruction(build_op_s(C1SFunction::SubA, 2, 1, 0)));
+		assert_eq!(f32::from_bits(test_ee.fpu_accumulator), -1.0);
+
+		test_ee.execute(ops::process_instruction(build_op_s(C1SFunction::MulA, 2, 1, 0)));
+		assert_eq!(f32::from_bits(test_ee.fpu_accumulator), 12.0);
+
+		// ACC = 12 + 3*4
+		test_ee.execute(ops::process_instruction(build_op_s(C1SFunction::MAddA, 2, 1, 0)));
+		assert_eq!(f32::from_bits(test_ee.fpu_accumulator), 24.0);
+
+		// ACC = 24 - 3*4
+		test_ee.execute(ops::process_instruction(build_op_s(C1SFunction::MSubA, 2, 1, 0)));
+		assert_eq!(f32::from_bits(test_ee.fpu_accumulator), 12.0);
+	}
+
+	#[test]
+	fn basic_madd_msub_s() {
+		let mut test_ee = fpu_ee();
+
+		test_ee.fpu_accumulator = 10.0f32.to_bits();
+
+		assert_eq!(run_s(&mut test_ee, C1SFunction::MAdd, 2.0, 3.0), 16.0);
+		assert_eq!(run_s(&mut test_ee, C1SFunction::MSub, 2.0, 3.0), 4.0);
+
+		// ACC is left untouched.
+		assert_eq!(f32::from_bits(test_ee.fpu_accumulator), 10.0);
+	}
+
+	#[test]
+	fn basic_compare_s() {
+		let mut test_ee = fpu_ee();
+
+		let cases = [
+			(C1SFunction::CEq, 1.0, 1.0, true),
+			(C1SFunction::CEq, 1.0, 2.0, false),
+			(C1SFunction::CLt, 1.0, 2.0, true),
+			(C1SFunction::CLt, 2.0, 2.0, false),
+			(C1SFunction::CLe, 2.0, 2.0, true),
+			(C1SFunction::CLe, 3.0, 2.0, false),
+			(C1SFunction::CF, 2.0, 2.0, false),
+		];
+
+		for (function, lhs, rhs, expected) in cases.iter() {
+			let _ = run_s(&mut test_ee, *function, *lhs, *rhs);
+			assert_eq!(test_ee.read_fpu_status().contains(Fcr31::CONDITION), *expected);
+		}
+	}
+
+	#[test]
+	fn basic_cvt() {
+		let mut test_ee = fpu_ee();
+
+		test_ee.write_cop1(1, (-7i32) as u32);
+		let instruction = mips::build_op_register_custom(MipsOpcode::Cop1, CVT_S, FMT_W, 0, 1, 2);
+		test_ee.execute(ops::process_instruction(instruction));
+		assert_eq!(f32::from_bits(test_ee.read_cop1(2)), -7.0);
+
+		test_ee.write_cop1(1, (-7.9f32).to_bits());
+		test_ee.execute(ops::process_instruction(build_op_s(C1SFunction::CvtW, 0, 1, 2)));
+		assert_eq!(test_ee.read_cop1(2) as i32, -7);
+	}
+
+	#[test]
+	fn basic_bc1t_bc1f() {
+		let jump_offset: u16 = 0x00_f0;
+		let jump_target = BIOS_START + 4 + ((jump_offset as u32) << 2);
+
+		for (condition, branch, taken) in [
+			(true, BC1Function::BC1T, true),
+			(false, BC1Function::BC1T, false),
+			(true, BC1Function::BC1F, false),
+			(false, BC1Function::BC1F, true),
+		].iter() {
+			let mut test_ee = fpu_ee();
+
+			let mut status = test_ee.read_fpu_status();
+			status.set(Fcr31::CONDITION, *condition);
+			test_ee.write_fpu_status(status);
+
+			install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+				mips::build_op_immediate(MipsOpcode::Cop1, BC1, *branch as u8, jump_offset),
+				NOP,
+			]));
+
+			let expected = if *taken { jump_target } else { BIOS_START + 8 };
+			assert_eq!(test_ee.pc_register, expected);
+		}
+	}
+
+	#[test]
+	fn bc1tl_nullifies() {
+		let proof_of_delay = 0xa123;
+
+		let mut test_ee = fpu_ee();
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_immediate(MipsOpcode::Cop1, BC1, BC1Function::BC1TL as u8, 0x00_f0),
+			mips::build_op_immediate(MipsOpcode::OrI, 0, 4, proof_of_delay),
+		]));
+
+		assert_eq!(test_ee.pc_register, BIOS_START + 8);
+		assert_eq!(test_ee.read_register(4), 0);
+	}
+}
@@ -0,0 +1,434 @@
+use crate::{
+	core::{
+		cop0::{
+			Register,
+			Status,
+		},
+		exceptions::L1Exception,
+		pipeline::*,
+		EECore,
+	},
+	isa::mips::{
+		ee::INTERLOCK,
+		Instruction,
+	},
+	utils::*,
+};
+use super::branch;
+
+#[inline(always)]
+fn cop2_usable(cpu: &mut EECore) -> bool {
+	// As with COP1, Status.CU2 must always be set.
+	let status = Status::from_bits_truncate(cpu.read_cop0_direct(Register::Status as u8));
+	let valid = status.contains(Status::COP2_USABLE);
+
+	if !valid {
+		cpu.throw_l1_exception(L1Exception::CoprocessorUnusable(2));
+	}
+
+	valid
+}
+
+/// Whether an instruction which must wait for VU0's microprogram to end may
+/// go ahead, stalling it otherwise.
+#[inline]
+fn vu0_ready(cpu: &mut EECore, interlock: bool) -> bool {
+	let ready = !(interlock && vu0_busy(cpu));
+
+	if !ready {
+		trace!("COP2: waiting on VU0 microprogram");
+		cpu.stall();
+	}
+
+	ready
+}
+
+/// Whether a move with interlock bit `I` may go ahead.
+#[inline]
+fn move_ready(cpu: &mut EECore, data: &OpCode) -> bool {
+	cop2_usable(cpu) && vu0_ready(cpu, data.raw & INTERLOCK != 0)
+}
+
+/// CPCOND2, tested by the `BC2x` branches: set while VU0 runs a microprogram.
+#[inline]
+fn vu0_busy(cpu: &EECore) -> bool {
+	cpu.vu0.borrow().running
+}
+
+// COP2 moves reuse the R-type fields as `rt`, and `rd` for the VU0 register.
+
+#[inline(always)]
+fn rd(data: &OpCode) -> u8 {
+	data.r_get_destination()
+}
+
+pub fn bc2f(cpu: &mut EECore, data: &OpCode) {
+	if !cop2_usable(cpu) {
+		return;
+	}
+
+	let cond = !vu0_busy(cpu);
+	cpu.branch(data, branch::inner_bne as BranchAction, cond as u32);
+}
+
+pub fn bc2fl(cpu: &mut EECore, data: &OpCode) {
+	if !cop2_usable(cpu) {
+		return;
+	}
+
+	let cond = !vu0_busy(cpu);
+	cpu.branch(data, branch::inner_bnel as BranchAction, cond as u32);
+}
+
+pub fn bc2t(cpu: &mut EECore, data: &OpCode) {
+	if !cop2_usable(cpu) {
+		return;
+	}
+
+	let cond = vu0_busy(cpu);
+	cpu.branch(data, branch::inner_bne as BranchAction, cond as u32);
+}
+
+pub fn bc2tl(cpu: &mut EECore, data: &OpCode) {
+	if !cop2_usable(cpu) {
+		return;
+	}
+
+	let cond = vu0_busy(cpu);
+	cpu.branch(data, branch::inner_bnel as BranchAction, cond as u32);
+}
+
+pub fn cfc2(cpu: &mut EECore, data: &OpCode) {
+	if !move_ready(cpu, data) {
+		return;
+	}
+
+	// VI/control register[rd] -> GPR[rt], sign-extended.
+	let v = cpu.read_cop2_control(rd(data));
+	cpu.write_register(data.ri_get_target(), v.s_ext());
+}
+
+pub fn ctc2(cpu: &mut EECore, data: &OpCode) {
+	if !move_ready(cpu, data) {
+		return;
+	}
+
+	// GPR[rt] -> VI/control register[rd]
+	let v = cpu.read_register(data.ri_get_target()) as u32;
+	cpu.write_cop2_control(rd(data), v);
+}
+
+pub fn lqc2(cpu: &mut EECore, data: &OpCode) {
+	if !cop2_usable(cpu) {
+		return;
+	}
+
+	// As with LQ, the lowest 4 bits of the address are ignored.
+	let v_addr = v_addr_with_offset(cpu, data) & !0b1111;
+
+	if let Some(loc) = cpu.load::<u128>(v_addr) {
+		cpu.write_cop2(data.ri_get_target(), loc);
+	}
+}
+
+/// Any VU0 upper or lower operation, in macro mode.
+pub fn operate(cpu: &mut EECore, data: &OpCode) {
+	if !cop2_usable(cpu) || !vu0_ready(cpu, true) {
+		return;
+	}
+
+	cpu.vu0.borrow_mut().execute_macro(data.raw);
+}
+
+pub fn qmfc2(cpu: &mut EECore, data: &OpCode) {
+	if !move_ready(cpu, data) {
+		return;
+	}
+
+	// VF[rd] -> GPR[rt]
+	let v = cpu.read_cop2(rd(data));
+	cpu.write_register_wide(data.ri_get_target(), v);
+}
+
+pub fn qmtc2(cpu: &mut EECore, data: &OpCode) {
+	if !move_ready(cpu, data) {
+		return;
+	}
+
+	// GPR[rt] -> VF[rd]
+	let v = cpu.read_register_wide(data.ri_get_target());
+	cpu.write_cop2(rd(data), v);
+}
+
+pub fn sqc2(cpu: &mut EECore, data: &OpCode) {
+	if !cop2_usable(cpu) {
+		return;
+	}
+
+	// mem[GPR[rs] + signed(imm)] <- VF[ft]
+	let to_store = cpu.read_cop2(data.ri_get_target());
+	let v_addr = v_addr_with_offset(cpu, data) & !0b1111;
+
+	cpu.store(v_addr, to_store);
+}
+
+pub fn vcallms(cpu: &mut EECore, data: &OpCode) {
+	if !cop2_usable(cpu) || !vu0_ready(cpu, true) {
+		return;
+	}
+
+	// The microprogram address, in doublewords, sits in bits 20--6.
+	let address = (data.raw >> 6) & 0x7fff;
+	cpu.vu0.borrow_mut().start(address * 8);
+}
+
+pub fn vcallmsr(cpu: &mut EECore, _data: &OpCode) {
+	if !cop2_usable(cpu) || !vu0_ready(cpu, true) {
+		return;
+	}
+
+	let address = u32::from(cpu.cmsar0);
+	cpu.vu0.borrow_mut().start(address * 8);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::{
+			cop2::*,
+			ops,
+		},
+		isa::{
+			mips::{
+				self,
+				ee::*,
+				Opcode as MipsOpcode,
+			},
+			vu::{
+				build_lower,
+				build_lower_function,
+				build_lower_special,
+				build_macro,
+				build_upper,
+				LowerOpcode,
+				LOWER_NOP,
+				UPPER_E,
+				UPPER_NOP,
+			},
+		},
+		memory::constants::*,
+	};
+
+	const ONE: u32 = 0x3f80_0000;
+	const TWO: u32 = 0x4000_0000;
+	const THREE: u32 = 0x4040_0000;
+	const FOUR: u32 = 0x4080_0000;
+
+	fn vu0_ee() -> EECore {
+		let mut test_ee = EECore::new();
+
+		let mut status = Status::from_bits_truncate(test_ee.read_cop0_direct(Register::Status as u8));
+		status.insert(Status::COP2_USABLE);
+		test_ee.write_cop0_direct(Register::Status as u8, status.bits());
+
+		test_ee
+	}
+
+	fn build_move(family: u8, rt: u8, rd: u8, interlock: bool) -> u32 {
+		mips::build_op_register_custom(MipsOpcode::Cop2, interlock as u8, family, rt, rd, 0)
+	}
+
+	fn vector(fields: [u32; 4]) -> u128 {
+		fields.iter().rev().fold(0, |value, &field| (value << 32) | u128::from(field))
+	}
+
+	#[test]
+	fn cop2_needs_enabled() {
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register_wide(1, vector([ONE; 4]));
+		test_ee.execute(ops::process_instruction(build_move(QMT2, 1, 2, false)));
+
+		assert!(test_ee.in_exception());
+		assert_eq!(test_ee.read_cop2(2), 0);
+	}
+
+	#[test]
+	fn basic_qmtc2_qmfc2() {
+		let mut test_ee = vu0_ee();
+		let value = vector([ONE, TWO, THREE, FOUR]);
+
+		test_ee.write_register_wide(1, value);
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			build_move(QMT2, 1, 7, true),
+			build_move(QMF2, 2, 7, false),
+			// VF0 is constant.
+			build_move(QMT2, 1, 0, false),
+			build_move(QMF2, 3, 0, false),
+		]));
+
+		assert_eq!(test_ee.vu0.borrow().vf[7], [ONE, TWO, THREE, FOUR]);
+		assert_eq!(test_ee.read_register_wide(2), value);
+		assert_eq!(test_ee.read_register_wide(3), vector([0, 0, 0, ONE]));
+	}
+
+	#[test]
+	fn ctc2_cfc2_reach_vi_and_control_registers() {
+		let mut test_ee = vu0_ee();
+
+		test_ee.write_register(1, 0xffff_8001);
+		test_ee.vu0.borrow_mut().mac = 0x1234;
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			build_move(CT2, 1, 5, false),
+			build_move(CT2, 1, 0, false),
+			build_move(CT2, 1, ControlRegister::Status as u8, false),
+			build_move(CT2, 1, ControlRegister::Mac as u8, false),
+			build_move(CF2, 2, 5, false),
+			build_move(CF2, 3, 0, false),
+			build_move(CF2, 4, ControlRegister::Mac as u8, false),
+			build_move(CF2, 5, ControlRegister::Status as u8, true),
+		]));
+
+		assert_eq!(test_ee.read_register(2), 0x8001);
+		assert_eq!(test_ee.read_register(3), 0);
+		// MAC is read-only, and only the sticky status flags may be written.
+		assert_eq!(test_ee.read_register(4), 0x1234);
+		assert_eq!(test_ee.read_register(5), 0);
+	}
+
+	#[test]
+	fn lqc2_sqc2_ignore_low_address_bits() {
+		let mut test_ee = vu0_ee();
+		let value = vector([ONE, TWO, THREE, FOUR]);
+
+		test_ee.write_register(1, (KSEG1_START + 0x1003).z_ext());
+		test_ee.write_memory(KSEG1_START + 0x1000, &value.to_le_bytes());
+
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::LQC2, 1, 4, 0)));
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::SQC2, 1, 4, 0x10)));
+
+		assert_eq!(test_ee.vu0.borrow().vf[4], [ONE, TWO, THREE, FOUR]);
+		assert_eq!(test_ee.load::<u128>(KSEG1_START + 0x1010), Some(value));
+	}
+
+	#[test]
+	fn macro_upper_and_lower_operations() {
+		let mut test_ee = vu0_ee();
+
+		test_ee.write_register_wide(1, vector([ONE, TWO, THREE, FOUR]));
+		test_ee.write_register_wide(2, vector([ONE; 4]));
+		test_ee.write_register(3, 3);
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			build_move(QMT2, 1, 1, false),
+			build_move(QMT2, 2, 2, false),
+			build_move(CT2, 3, 1, false),
+			build_move(CT2, 3, 2, false),
+			// VADD.xyz VF3, VF1, VF2
+			build_macro(build_upper(0x28, 0b1110, 2, 1, 3)),
+			// VIADD VI3, VI1, VI2
+			build_macro(build_lower_function(0x30, 0, 2, 1, 3)),
+			// VDIV Q, VF3y, VF2x
+			build_macro(build_lower_special(0x38, 0b00_01, 2, 3)),
+			build_move(CF2, 4, 3, false),
+			build_move(CF2, 5, ControlRegister::Q as u8, false),
+		]));
+
+		assert_eq!(test_ee.vu0.borrow().vf[3], [TWO, THREE, FOUR, 0]);
+		assert_eq!(test_ee.read_register(4), 6);
+		assert_eq!(test_ee.read_register(5) as u32, THREE);
+		assert!(!test_ee.in_exception());
+	}
+
+	#[test]
+	fn vcallms_and_vcallmsr_start_vu0() {
+		let mut test_ee = vu0_ee();
+
+		for &address in &[0x20, 0x40] {
+			let mut vu0 = test_ee.vu0.borrow_mut();
+			vu0.write_micro(address, (u64::from(UPPER_NOP | UPPER_E) << 32) | u64::from(LOWER_NOP));
+			vu0.write_micro(address + 1, (u64::from(UPPER_NOP) << 32) | u64::from(LOWER_NOP));
+		}
+
+		test_ee.write_register(1, 0x40);
+
+		install_and_run_program_for(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_register_custom(MipsOpcode::Cop2, VCALLMS, CO2, 0, 0, 0x20),
+			build_move(CF2, 2, ControlRegister::Tpc as u8, true),
+			build_move(CT2, 1, ControlRegister::Cmsar0 as u8, false),
+			mips::build_op_register_custom(MipsOpcode::Cop2, VCALLMSR, CO2, 0, ControlRegister::Cmsar0 as u8, 0),
+			build_move(CF2, 3, ControlRegister::Tpc as u8, true),
+		]), 12);
+
+		// Each program ends after the pair following its E bit.
+		assert_eq!(test_ee.read_register(2), 0x22);
+		assert_eq!(test_ee.read_register(3), 0x42);
+	}
+
+	#[test]
+	fn bc2t_polls_until_vu0_ends() {
+		let mut test_ee = vu0_ee();
+
+		{
+			let mut vu0 = test_ee.vu0.borrow_mut();
+			for address in 0..8 {
+				let upper = if address == 6 { UPPER_NOP | UPPER_E } else { UPPER_NOP };
+				let lower = build_lower(LowerOpcode::IaddIu as u8, 0, 1, 1, 1);
+				vu0.write_micro(address, (u64::from(upper) << 32) | u64::from(lower));
+			}
+		}
+
+		install_and_run_program_for(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_register_custom(MipsOpcode::Cop2, VCALLMS, CO2, 0, 0, 0),
+			mips::build_op_immediate(MipsOpcode::Cop2, BC2, BC2Function::BC2T as u8, 0xffff),
+			mips::NOP,
+			build_move(CF2, 2, 1, false),
+			build_move(CF2, 3, ControlRegister::VpuStat as u8, false),
+		]), 30);
+
+		assert_eq!(test_ee.read_register(2), 8);
+		assert_eq!(test_ee.read_register(3), 0);
+	}
+
+	#[test]
+	fn fbrst_breaks_and_resets() {
+		let mut test_ee = vu0_ee();
+		let fbrst = ControlRegister::Fbrst as u8;
+		let vpu_stat = ControlRegister::VpuStat as u8;
+
+		// With nothing in micro memory, neither program would end.
+		test_ee.vu0.borrow_mut().start(0);
+		test_ee.vu1.borrow_mut().start(0);
+		test_ee.vu1.borrow_mut().status = 0xfff;
+		assert_eq!(test_ee.read_cop2_control(vpu_stat), (VpuStat::BUSY.bits() << VU1_SHIFT) | VpuStat::BUSY.bits());
+
+		let enables = Fbrst::DEBUG_ENABLE | Fbrst::TRACE_ENABLE;
+		test_ee.write_cop2_control(fbrst, (Fbrst::FORCE_BREAK | enables).bits());
+		assert_eq!(test_ee.read_cop2_control(vpu_stat), (VpuStat::BUSY.bits() << VU1_SHIFT) | VpuStat::FORCE_BREAK_STOPPED.bits());
+		assert_eq!(test_ee.read_cop2_control(fbrst), enables.bits());
+
+		test_ee.write_cop2_control(fbrst, Fbrst::RESET.bits() << VU1_SHIFT);
+		assert_eq!(test_ee.read_cop2_control(vpu_stat), VpuStat::FORCE_BREAK_STOPPED.bits());
+		assert_eq!(test_ee.vu1.borrow().status, 0);
+	}
+
+	#[test]
+	fn interlocked_moves_wait_for_vu0() {
+		let mut test_ee = vu0_ee();
+		let pc = test_ee.pc_register;
+
+		test_ee.write_register_wide(1, vector([ONE; 4]));
+		test_ee.vu0.borrow_mut().running = true;
+
+		test_ee.execute(ops::process_instruction(build_move(QMT2, 1, 1, true)));
+		assert_eq!(test_ee.pc_register, pc);
+		assert_eq!(test_ee.read_cop2(1), 0);
+
+		test_ee.execute(ops::process_instruction(build_move(QMT2, 1, 1, false)));
+		assert_eq!(test_ee.pc_register, pc + 4);
+		assert_eq!(test_ee.read_cop2(1), vector([ONE; 4]));
+	}
+}
@@ -1,7 +1,3 @@
-use byteorder::{
-	ByteOrder,
-	LittleEndian,
-};
 use crate::{
 	core::{
 		exceptions::L1Exception,
@@ -11,13 +7,11 @@ use crate::{
 	isa::mips::Instruction,
 	utils::*,
 };
-use std::mem::size_of;
 
 pub fn lb(cpu: &mut EECore, data: &OpCode) {
 	let v_addr = v_addr_with_offset(cpu, data);
 
-	let loc = cpu.read_memory(v_addr as u32, size_of::<u8>())
-		.map(|buf| buf[0]);
+	let loc = cpu.load::<u8>(v_addr as u32);
 
 	if let Some(loc) = loc {
 		cpu.write_register(data.ri_get_target(), loc.s_ext());
@@ -27,8 +21,7 @@ pub fn lb(cpu: &mut EECore, data: &OpCode) {
 pub fn lbu(cpu: &mut EECore, data: &OpCode) {
 	let v_addr = v_addr_with_offset(cpu, data);
 
-	let loc = cpu.read_memory(v_addr as u32, size_of::<u8>())
-		.map(|buf| buf[0]);
+	let loc = cpu.load::<u8>(v_addr as u32);
 
 	if let Some(loc) = loc {
 		cpu.write_register(data.ri_get_target(), loc.z_ext());
@@ -44,14 +37,59 @@ pub fn ld(cpu: &mut EECore, data: &OpCode) {
 		return;
 	}
 
-	let loc = cpu.read_memory(v_addr as u32, size_of::<u64>())
-		.map(LittleEndian::read_u64);
+	let loc = cpu.load::<u64>(v_addr as u32);
 
 	if let Some(loc) = loc {
 		cpu.write_register(data.ri_get_target(), loc);
 	}
 }
 
+pub fn ldl(cpu: &mut EECore, data: &OpCode) {
+	// Merge the bytes from v_addr down to the enclosing doubleword's
+	// boundary into the most significant bytes of GPR[rt].
+	let v_addr = v_addr_with_offset(cpu, data);
+	let shift = (0b111 - (v_addr & 0b111)) * 8;
+
+	let loc = cpu.load::<u64>(v_addr & !0b111);
+
+	if let Some(loc) = loc {
+		let keep = (1u64 << shift) - 1;
+		let old = cpu.read_register(data.ri_get_target());
+		cpu.write_register(data.ri_get_target(), (old & keep) | (loc << shift));
+	}
+}
+
+pub fn ldr(cpu: &mut EECore, data: &OpCode) {
+	// Merge the bytes from v_addr up to the enclosing doubleword's
+	// boundary into the least significant bytes of GPR[rt].
+	let v_addr = v_addr_with_offset(cpu, data);
+	let shift = (v_addr & 0b111) * 8;
+
+	let loc = cpu.load::<u64>(v_addr & !0b111);
+
+	if let Some(loc) = loc {
+		let keep = !(u64::MAX >> shift);
+		let old = cpu.read_register(data.ri_get_target());
+		cpu.write_register(data.ri_get_target(), (old & keep) | (loc >> shift));
+	}
+}
+
+pub fn lh(cpu: &mut EECore, data: &OpCode) {
+	let v_addr = v_addr_with_offset(cpu, data);
+
+	// FIXME: make size info part of address resolution.
+	if v_addr & 0b1 != 0 {
+		cpu.throw_l1_exception(L1Exception::AddressErrorFetchLoad(v_addr));
+		return;
+	}
+
+	let loc = cpu.load::<u16>(v_addr);
+
+	if let Some(loc) = loc {
+		cpu.write_register(data.ri_get_target(), loc.s_ext());
+	}
+}
+
 pub fn lhu(cpu: &mut EECore, data: &OpCode) {
 	let v_addr = v_addr_with_offset(cpu, data);
 
@@ -61,14 +99,24 @@ pub fn lhu(cpu: &mut EECore, data: &OpCode) {
 		return;
 	}
 
-	let loc = cpu.read_memory(v_addr as u32, size_of::<u16>())
-		.map(LittleEndian::read_u16);
+	let loc = cpu.load::<u16>(v_addr as u32);
 
 	if let Some(loc) = loc {
 		cpu.write_register(data.ri_get_target(), loc.z_ext());
 	}
 }
 
+pub fn lq(cpu: &mut EECore, data: &OpCode) {
+	// The lowest 4 bits of the address are ignored: LQ never raises an address error.
+	let v_addr = v_addr_with_offset(cpu, data) & !0b1111;
+
+	let loc = cpu.load::<u128>(v_addr);
+
+	if let Some(loc) = loc {
+		cpu.write_register_wide(data.ri_get_target(), loc);
+	}
+}
+
 pub fn lw(cpu: &mut EECore, data: &OpCode) {
 	let v_addr = v_addr_with_offset(cpu, data);
 
@@ -78,14 +126,68 @@ pub fn lw(cpu: &mut EECore, data: &OpCode) {
 		return;
 	}
 
-	let loc = cpu.read_memory(v_addr as u32, size_of::<u32>())
-		.map(LittleEndian::read_u32);
+	let loc = cpu.load::<u32>(v_addr as u32);
 
 	if let Some(loc) = loc {
 		cpu.write_register(data.ri_get_target(), loc.s_ext());
 	}
 }
 
+pub fn lwl(cpu: &mut EECore, data: &OpCode) {
+	// Merge the bytes from v_addr down to the enclosing word's
+	// boundary into the most significant bytes of GPR[rt] (as 32),
+	// then sign extend.
+	let v_addr = v_addr_with_offset(cpu, data);
+	let shift = (0b11 - (v_addr & 0b11)) * 8;
+
+	let loc = cpu.load::<u32>(v_addr & !0b11);
+
+	if let Some(loc) = loc {
+		let keep = (1u32 << shift) - 1;
+		let old = cpu.read_register(data.ri_get_target()) as u32;
+		cpu.write_register(data.ri_get_target(), ((old & keep) | (loc << shift)).s_ext());
+	}
+}
+
+pub fn lwr(cpu: &mut EECore, data: &OpCode) {
+	// Merge the bytes from v_addr up to the enclosing word's
+	// boundary into the least significant bytes of GPR[rt] (as 32).
+	let v_addr = v_addr_with_offset(cpu, data);
+	let shift = (v_addr & 0b11) * 8;
+
+	let loc = cpu.load::<u32>(v_addr & !0b11);
+
+	if let Some(loc) = loc {
+		let old = cpu.read_register(data.ri_get_target());
+
+		// A full word is sign extended, otherwise the upper half of GPR[rt] is untouched.
+		let new = if shift == 0 {
+			loc.s_ext()
+		} else {
+			let keep = !(u32::MAX >> shift);
+			(old & !0xffff_ffff) | u64::from((old as u32 & keep) | (loc >> shift))
+		};
+
+		cpu.write_register(data.ri_get_target(), new);
+	}
+}
+
+pub fn lwu(cpu: &mut EECore, data: &OpCode) {
+	let v_addr = v_addr_with_offset(cpu, data);
+
+	// FIXME: make size info part of address resolution.
+	if v_addr & 0b11 != 0 {
+		cpu.throw_l1_exception(L1Exception::AddressErrorFetchLoad(v_addr));
+		return;
+	}
+
+	let loc = cpu.load::<u32>(v_addr);
+
+	if let Some(loc) = loc {
+		cpu.write_register(data.ri_get_target(), loc.z_ext());
+	}
+}
+
 pub fn lui(cpu: &mut EECore, data: &OpCode) {
 	// load sign extended shifted value of immediate into rt.
 	let v: u64 = data.i_get_immediate().s_ext();
@@ -97,11 +199,26 @@ pub fn mfhi(cpu: &mut EECore, data: &OpCode) {
 	cpu.write_register(data.r_get_destination(), cpu.read_hi());
 }
 
+pub fn mfhi1(cpu: &mut EECore, data: &OpCode) {
+	// HI1 -> GPR[rd]
+	cpu.write_register(data.r_get_destination(), cpu.read_hi1());
+}
+
 pub fn mflo(cpu: &mut EECore, data: &OpCode) {
 	// LO -> GPR[rd]
 	cpu.write_register(data.r_get_destination(), cpu.read_lo());
 }
 
+pub fn mflo1(cpu: &mut EECore, data: &OpCode) {
+	// LO1 -> GPR[rd]
+	cpu.write_register(data.r_get_destination(), cpu.read_lo1());
+}
+
+pub fn mfsa(cpu: &mut EECore, data: &OpCode) {
+	// SA -> GPR[rd], in the same (opaque) form MTSA accepts.
+	cpu.write_register(data.r_get_destination(), u64::from(cpu.sa_register));
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -113,7 +230,7 @@ mod test {
 		core::ops,
 		isa::mips::{
 			self,
-			ee::{CacheFunction, Cop0Function, Cop1Function},
+			ee::{CacheFunction, Cop0Function, Cop1Function, MmiBaseFunction},
 			Function as MipsFunction,
 			Instruction,
 			Opcode as MipsOpcode,
@@ -122,6 +239,19 @@ mod test {
 		memory::constants::*,
 	};
 
+	fn ramp_memory(test_ee: &mut EECore) {
+		let bytes: Vec<u8> = (0xf0..=0xff).collect();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_memory(KSEG1_START, &bytes);
+	}
+
+	fn run_ops(test_ee: &mut EECore, instructions: &[u32]) {
+		for instruction in instructions {
+			test_ee.execute(ops::process_instruction(*instruction));
+		}
+	}
+
 	#[test]
 	fn basic_lb() {
 		let offset: i16 = 0;
@@ -170,6 +300,70 @@ mod test {
 		assert_eq!(test_ee.read_register(2), read_val);
 	}
 
+	#[test]
+	fn ldl_ldr_unaligned_doubleword() {
+		let mut test_ee = EECore::new();
+		ramp_memory(&mut test_ee);
+
+		run_ops(&mut test_ee, &[
+			mips::build_op_immediate(MipsOpcode::LDR, 1, 2, 3),
+			mips::build_op_immediate(MipsOpcode::LDL, 1, 2, 10),
+		]);
+
+		assert_eq!(test_ee.read_register(2), 0xfaf9_f8f7_f6f5_f4f3);
+	}
+
+	#[test]
+	fn ldl_merges_high_bytes() {
+		let mut test_ee = EECore::new();
+		ramp_memory(&mut test_ee);
+		test_ee.write_register(2, 0x1122_3344_5566_7788);
+
+		run_ops(&mut test_ee, &[mips::build_op_immediate(MipsOpcode::LDL, 1, 2, 2)]);
+
+		assert_eq!(test_ee.read_register(2), 0xf2f1_f044_5566_7788);
+	}
+
+	#[test]
+	fn ldr_merges_low_bytes() {
+		let mut test_ee = EECore::new();
+		ramp_memory(&mut test_ee);
+		test_ee.write_register(2, 0x1122_3344_5566_7788);
+
+		run_ops(&mut test_ee, &[mips::build_op_immediate(MipsOpcode::LDR, 1, 2, 5)]);
+
+		assert_eq!(test_ee.read_register(2), 0x1122_3344_55f7_f6f5);
+	}
+
+	#[test]
+	fn basic_lh() {
+		let offset: i16 = 0;
+		let read_val: u16 = 0xfade;
+
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_memory(KSEG1_START, &read_val.to_le_bytes());
+		let instruction = mips::build_op_immediate(MipsOpcode::LH, 1, 2, offset as u16);
+
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert_eq!(test_ee.read_register(2), read_val.s_ext());
+	}
+
+	#[test]
+	fn lh_2_byte_aligned() {
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		let instruction = mips::build_op_immediate(MipsOpcode::LH, 1, 2, 1);
+
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert!(test_ee.in_exception());
+		assert_eq!(test_ee.read_register(2), 0);
+	}
+
 	#[test]
 	fn basic_lhu() {
 		let offset: i16 = 0;
@@ -186,6 +380,39 @@ mod test {
 		assert_eq!(test_ee.read_register(2), read_val.z_ext());
 	}
 
+	#[test]
+	fn basic_lq() {
+		let offset: i16 = 0;
+		let read_val: u128 = 0x0f1e_2d3c_4b5a_6978_1234_5678_90ab_cdef;
+
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_memory(KSEG1_START, &read_val.to_le_bytes());
+		let instruction = mips::build_op_immediate(MipsOpcode::LQ, 1, 2, offset as u16);
+
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert_eq!(test_ee.read_register_wide(2), read_val);
+	}
+
+	#[test]
+	fn lq_ignores_low_address_bits() {
+		let offset: i16 = 7;
+		let read_val: u128 = 0x0f1e_2d3c_4b5a_6978_1234_5678_90ab_cdef;
+
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, (KSEG1_START + 8).z_ext());
+		test_ee.write_memory(KSEG1_START, &read_val.to_le_bytes());
+		let instruction = mips::build_op_immediate(MipsOpcode::LQ, 1, 2, offset as u16);
+
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert!(!test_ee.in_exception());
+		assert_eq!(test_ee.read_register_wide(2), read_val);
+	}
+
 	#[test]
 	fn basic_lw() {
 		let offset: i16 = 0;
@@ -202,6 +429,86 @@ mod test {
 		assert_eq!(test_ee.read_register(2), read_val.s_ext());
 	}
 
+	#[test]
+	fn lwl_lwr_unaligned_word() {
+		let mut test_ee = EECore::new();
+		ramp_memory(&mut test_ee);
+
+		run_ops(&mut test_ee, &[
+			mips::build_op_immediate(MipsOpcode::LWR, 1, 2, 1),
+			mips::build_op_immediate(MipsOpcode::LWL, 1, 2, 4),
+		]);
+
+		assert_eq!(test_ee.read_register(2), 0xf4f3_f2f1u32.s_ext());
+	}
+
+	#[test]
+	fn lwl_merges_high_bytes() {
+		let mut test_ee = EECore::new();
+		ramp_memory(&mut test_ee);
+		test_ee.write_register(2, 0x1122_3344_5566_7788);
+
+		run_ops(&mut test_ee, &[mips::build_op_immediate(MipsOpcode::LWL, 1, 2, 1)]);
+
+		assert_eq!(test_ee.read_register(2), 0xf1f0_7788u32.s_ext());
+	}
+
+	#[test]
+	fn lwr_merges_low_bytes() {
+		let mut test_ee = EECore::new();
+		ramp_memory(&mut test_ee);
+		test_ee.write_register(2, 0x1122_3344_5566_7788);
+
+		run_ops(&mut test_ee, &[mips::build_op_immediate(MipsOpcode::LWR, 1, 2, 1)]);
+
+		// The upper word of the register is left untouched.
+		assert_eq!(test_ee.read_register(2), 0x1122_3344_55f3_f2f1);
+
+		// ...unless an aligned word is loaded in full.
+		run_ops(&mut test_ee, &[mips::build_op_immediate(MipsOpcode::LWR, 1, 2, 4)]);
+		assert_eq!(test_ee.read_register(2), 0xf7f6_f5f4u32.s_ext());
+	}
+
+	#[test]
+	fn basic_lwu() {
+		let offset: i16 = 0;
+		let read_val: u32 = 0x90ab_cdef;
+
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_memory(KSEG1_START, &read_val.to_le_bytes());
+		let instruction = mips::build_op_immediate(MipsOpcode::LWU, 1, 2, offset as u16);
+
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert_eq!(test_ee.read_register(2), read_val.z_ext());
+	}
+
+	#[test]
+	fn lwu_4_byte_aligned() {
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		let instruction = mips::build_op_immediate(MipsOpcode::LWU, 1, 2, 2);
+
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert!(test_ee.in_exception());
+	}
+
+	#[test]
+	fn pref_is_nop() {
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, (KSEG1_START + 3).z_ext());
+		let instruction = mips::build_op_immediate(MipsOpcode::Pref, 1, 0, 0);
+
+		test_ee.execute(ops::process_instruction(instruction));
+
+		assert!(!test_ee.in_exception());
+	}
+
 	#[test]
 	fn basic_lui() {
 		// Place a 16-bit value into bits 32..16.
@@ -257,4 +564,17 @@ mod test {
 
 		assert_eq!(test_ee.read_register(1), lo);
 	}
+
+	#[test]
+	fn basic_mfhi1_mflo1() {
+		let mut test_ee = EECore::new();
+		test_ee.write_hi1(0x1234_5678_abcd_ef90);
+		test_ee.write_lo1(0x0fed_cba9_8765_4321);
+
+		test_ee.execute(ops::process_instruction(mips::build_op_register_custom(MipsOpcode::Mmi, MmiBaseFunction::MFHI1 as u8, 0, 0, 1, 0)));
+		test_ee.execute(ops::process_instruction(mips::build_op_register_custom(MipsOpcode::Mmi, MmiBaseFunction::MFLO1 as u8, 0, 0, 2, 0)));
+
+		assert_eq!(test_ee.read_register(1), 0x1234_5678_abcd_ef90);
+		assert_eq!(test_ee.read_register(2), 0x0fed_cba9_8765_4321);
+	}
 }
\ No newline at end of file
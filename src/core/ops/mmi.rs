@@ -0,0 +1,1500 @@
+//! Multimedia (128-bit) instructions: opcode `MMI` and its sub-families.
+//!
+//! These treat each GPR as a vector of bytes, halfwords, words or doublewords.
+//! Lane `0` is always the least-significant, and HI/LO are treated as 128-bit
+//! registers (their upper halves being HI1/LO1).
+
+use crate::{
+	core::{
+		pipeline::*,
+		EECore,
+	},
+	isa::mips::Instruction,
+	utils::*,
+};
+use std::mem::size_of;
+
+/// An unsigned integer type which may be packed into a 128-bit register.
+trait Lane: Copy {
+	const BITS: u32;
+	const COUNT: u32 = 128 / Self::BITS;
+
+	fn narrow(value: u128) -> Self;
+	fn widen(self) -> u128;
+}
+
+macro_rules! impl_lane {
+	($t:ty) => {
+		impl Lane for $t {
+			const BITS: u32 = (size_of::<$t>() * 8) as u32;
+
+			#[inline(always)]
+			fn narrow(value: u128) -> Self {
+				value as $t
+			}
+
+			#[inline(always)]
+			fn widen(self) -> u128 {
+				u128::from(self)
+			}
+		}
+	};
+}
+
+impl_lane!(u8);
+impl_lane!(u16);
+impl_lane!(u32);
+impl_lane!(u64);
+
+/// Extract lane `index` of `value`.
+#[inline(always)]
+fn lane<L: Lane>(value: u128, index: u32) -> L {
+	L::narrow(value >> (index * L::BITS))
+}
+
+/// Construct a register, lane by lane.
+#[inline(always)]
+fn build<L: Lane>(f: impl Fn(u32) -> L) -> u128 {
+	(0..L::COUNT).fold(0, |acc, i| acc | (f(i).widen() << (i * L::BITS)))
+}
+
+/// Apply `f` to each pair of lanes in `a` and `b`.
+#[inline(always)]
+fn map2<L: Lane>(a: u128, b: u128, f: impl Fn(L, L) -> L) -> u128 {
+	build(|i| f(lane(a, i), lane(b, i)))
+}
+
+/// Apply `f` to each lane in `a`.
+#[inline(always)]
+fn map1<L: Lane>(a: u128, f: impl Fn(L) -> L) -> u128 {
+	build(|i| f(lane(a, i)))
+}
+
+/// Reorder the lanes of `a`: lane `i` of the output is lane `order[i]` of `a`.
+#[inline(always)]
+fn permute<L: Lane>(a: u128, order: &[u32]) -> u128 {
+	build::<L>(|i| lane(a, order[i as usize]))
+}
+
+/// Interleave lanes from the lower (`from == 0`) or upper half
+/// of `rt` (even lanes) and `rs` (odd lanes).
+#[inline(always)]
+fn interleave<L: Lane>(rs: u128, rt: u128, from: u32) -> u128 {
+	build::<L>(|i| lane(if i % 2 == 0 { rt } else { rs }, from + i / 2))
+}
+
+/// Pack the even lanes of `rt` into the lower half, and the even lanes of
+/// `rs` into the upper half.
+#[inline(always)]
+fn pack<L: Lane>(rs: u128, rt: u128) -> u128 {
+	let half = L::COUNT / 2;
+	build::<L>(|i| if i < half {
+		lane(rt, 2 * i)
+	} else {
+		lane(rs, 2 * (i - half))
+	})
+}
+
+#[inline(always)]
+fn mask<L: Lane>(cond: bool) -> L {
+	L::narrow(if cond { u128::MAX } else { 0 })
+}
+
+/// Location of product `index` of a halfword multiply within HI/LO:
+/// whether it is held in HI, and in which word.
+///
+/// Products are placed in pairs, alternating between LO and HI.
+#[inline(always)]
+fn halfword_product_slot(index: u32) -> (bool, u32) {
+	((index / 2) % 2 == 1, (index / 4) * 2 + index % 2)
+}
+
+/// Apply `op` to GPR[rs] and GPR[rt], storing the result in GPR[rd].
+#[inline(always)]
+fn parallel_op(cpu: &mut EECore, data: &OpCode, op: impl Fn(u128, u128) -> u128) {
+	let rs = cpu.read_register_wide(data.ri_get_source());
+	let rt = cpu.read_register_wide(data.ri_get_target());
+	cpu.write_register_wide(data.r_get_destination(), op(rs, rt));
+}
+
+/// Apply `op` to GPR[rt], storing the result in GPR[rd].
+#[inline(always)]
+fn parallel_op_t(cpu: &mut EECore, data: &OpCode, op: impl Fn(u128) -> u128) {
+	let rt = cpu.read_register_wide(data.ri_get_target());
+	cpu.write_register_wide(data.r_get_destination(), op(rt));
+}
+
+/// Apply `op` to GPR[rt] and `sa`, storing the result in GPR[rd].
+#[inline(always)]
+fn parallel_shift(cpu: &mut EECore, data: &OpCode, op: impl Fn(u128, u32) -> u128) {
+	let sa = u32::from(data.r_get_shift_amount());
+	parallel_op_t(cpu, data, |rt| op(rt, sa));
+}
+
+/// Apply `op` to the even words of GPR[rs] and GPR[rt], and the
+/// matching 64-bit accumulator `HI.w[2i] || LO.w[2i]`.
+///
+/// The 64-bit result of each lane is written to GPR[rd],
+/// and split (sign-extended) between LO and HI.
+#[inline(always)]
+fn word_multiply(cpu: &mut EECore, data: &OpCode, op: impl Fn(u32, u32, u64) -> u64) {
+	let rs = cpu.read_register_wide(data.ri_get_source());
+	let rt = cpu.read_register_wide(data.ri_get_target());
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	let results = build::<u64>(|i| {
+		let acc = (u64::from(lane::<u32>(hi, 2 * i)) << 32) | u64::from(lane::<u32>(lo, 2 * i));
+		op(lane(rs, 2 * i), lane(rt, 2 * i), acc)
+	});
+
+	cpu.write_lo_wide(build::<u64>(|i| lane::<u32>(results, 2 * i).s_ext()));
+	cpu.write_hi_wide(build::<u64>(|i| lane::<u32>(results, 2 * i + 1).s_ext()));
+	cpu.write_register_wide(data.r_get_destination(), results);
+}
+
+/// Apply `op` to the product of each pair of signed halfwords of GPR[rs] and
+/// GPR[rt], and the matching accumulator in HI/LO (see [`halfword_product_slot`]).
+///
+/// Results are written back to HI/LO, and even results are written to GPR[rd].
+#[inline(always)]
+fn halfword_multiply(cpu: &mut EECore, data: &OpCode, op: impl Fn(i32, i32) -> i32) {
+	let rs = cpu.read_register_wide(data.ri_get_source());
+	let rt = cpu.read_register_wide(data.ri_get_target());
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	let mut hi_words = [0u32; 4];
+	let mut lo_words = [0u32; 4];
+	let mut results = [0u32; 8];
+
+	for (i, result) in results.iter_mut().enumerate() {
+		let i = i as u32;
+		let (in_hi, word) = halfword_product_slot(i);
+		let acc = lane::<u32>(if in_hi { hi } else { lo }, word) as i32;
+		let product = halfword_product(rs, rt, i);
+
+		*result = op(product, acc) as u32;
+
+		if in_hi {
+			hi_words[word as usize] = *result;
+		} else {
+			lo_words[word as usize] = *result;
+		}
+	}
+
+	cpu.write_hi_wide(build::<u32>(|i| hi_words[i as usize]));
+	cpu.write_lo_wide(build::<u32>(|i| lo_words[i as usize]));
+	cpu.write_register_wide(data.r_get_destination(), build::<u32>(|i| results[2 * i as usize]));
+}
+
+/// Combine adjacent halfword products as `op(odd, even)`, storing the
+/// results in the even words of LO (first of each pair) and HI (second),
+/// and in GPR[rd].
+///
+/// The odd words of HI/LO are undefined, and are left unchanged.
+#[inline(always)]
+fn halfword_horizontal(cpu: &mut EECore, data: &OpCode, op: impl Fn(i32, i32) -> i32) {
+	let rs = cpu.read_register_wide(data.ri_get_source());
+	let rt = cpu.read_register_wide(data.ri_get_target());
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	let sums = build::<u32>(|i| op(
+		halfword_product(rs, rt, 2 * i + 1),
+		halfword_product(rs, rt, 2 * i),
+	) as u32);
+
+	cpu.write_lo_wide(build::<u32>(|i| if i % 2 == 0 { lane(sums, i) } else { lane(lo, i) }));
+	cpu.write_hi_wide(build::<u32>(|i| if i % 2 == 0 { lane(sums, i + 1) } else { lane(hi, i) }));
+	cpu.write_register_wide(data.r_get_destination(), sums);
+}
+
+#[inline(always)]
+fn halfword_product(rs: u128, rt: u128, index: u32) -> i32 {
+	i32::from(lane::<u16>(rs, index) as i16) * i32::from(lane::<u16>(rt, index) as i16)
+}
+
+/// Divide the even words of GPR[rs] by those of GPR[rt], placing quotients
+/// in LO and remainders in HI (both sign-extended).
+///
+/// As with `DIV`, lanes with a zero divisor leave HI/LO unchanged.
+#[inline(always)]
+fn word_divide(cpu: &mut EECore, data: &OpCode, op: impl Fn(u32, u32) -> Option<(u32, u32)>) {
+	let rs = cpu.read_register_wide(data.ri_get_source());
+	let rt = cpu.read_register_wide(data.ri_get_target());
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	let results = [
+		op(lane(rs, 0), lane(rt, 0)),
+		op(lane(rs, 2), lane(rt, 2)),
+	];
+
+	cpu.write_lo_wide(build::<u64>(|i| results[i as usize]
+		.map(|(quotient, _)| quotient.s_ext())
+		.unwrap_or_else(|| lane(lo, i))));
+	cpu.write_hi_wide(build::<u64>(|i| results[i as usize]
+		.map(|(_, remainder)| remainder.s_ext())
+		.unwrap_or_else(|| lane(hi, i))));
+}
+
+/// Saturate a signed 32-bit value into a signed halfword.
+#[inline(always)]
+fn saturate_halfword(value: u32) -> u16 {
+	(value as i32).max(i32::from(i16::MIN)).min(i32::from(i16::MAX)) as u16
+}
+
+/// Count the leading bits which match the sign bit, excluding the sign bit.
+#[inline(always)]
+fn leading_sign_bits(value: u32) -> u32 {
+	let value = if (value as i32) < 0 { !value } else { value };
+	value.leading_zeros() - 1
+}
+
+pub fn pabsh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op_t(cpu, data, |rt| map1::<u16>(rt, |a| (a as i16).saturating_abs() as u16));
+}
+
+pub fn pabsw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op_t(cpu, data, |rt| map1::<u32>(rt, |a| (a as i32).saturating_abs() as u32));
+}
+
+pub fn paddb(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u8>(rs, rt, u8::wrapping_add));
+}
+
+pub fn paddh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, u16::wrapping_add));
+}
+
+pub fn paddsb(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u8>(rs, rt, |a, b| (a as i8).saturating_add(b as i8) as u8));
+}
+
+pub fn paddsh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, |a, b| (a as i16).saturating_add(b as i16) as u16));
+}
+
+pub fn paddsw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, |a, b| (a as i32).saturating_add(b as i32) as u32));
+}
+
+pub fn paddub(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u8>(rs, rt, u8::saturating_add));
+}
+
+pub fn padduh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, u16::saturating_add));
+}
+
+pub fn padduw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, u32::saturating_add));
+}
+
+pub fn paddw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, u32::wrapping_add));
+}
+
+pub fn padsbh(cpu: &mut EECore, data: &OpCode) {
+	// Lower 4 halfwords subtract, upper 4 add.
+	parallel_op(cpu, data, |rs, rt| build::<u16>(|i| {
+		let (a, b) = (lane::<u16>(rs, i), lane::<u16>(rt, i));
+		if i < 4 { a.wrapping_sub(b) } else { a.wrapping_add(b) }
+	}));
+}
+
+pub fn pand(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| rs & rt);
+}
+
+pub fn pceqb(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u8>(rs, rt, |a, b| mask(a == b)));
+}
+
+pub fn pceqh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, |a, b| mask(a == b)));
+}
+
+pub fn pceqw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, |a, b| mask(a == b)));
+}
+
+pub fn pcgtb(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u8>(rs, rt, |a, b| mask(a as i8 > b as i8)));
+}
+
+pub fn pcgth(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, |a, b| mask(a as i16 > b as i16)));
+}
+
+pub fn pcgtw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, |a, b| mask(a as i32 > b as i32)));
+}
+
+pub fn pcpyh(cpu: &mut EECore, data: &OpCode) {
+	// Broadcast the lowest halfword of each doubleword.
+	parallel_op_t(cpu, data, |rt| build::<u16>(|i| lane(rt, (i / 4) * 4)));
+}
+
+pub fn pcpyld(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| build::<u64>(|i| lane(if i == 0 { rt } else { rs }, 0)));
+}
+
+pub fn pcpyud(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| build::<u64>(|i| lane(if i == 0 { rs } else { rt }, 1)));
+}
+
+pub fn pdivbw(cpu: &mut EECore, data: &OpCode) {
+	// Each word of GPR[rs] is divided by the lowest halfword of GPR[rt].
+	let rs = cpu.read_register_wide(data.ri_get_source());
+	let rt = cpu.read_register_wide(data.ri_get_target());
+	let divisor = i32::from(lane::<u16>(rt, 0) as i16);
+
+	if divisor == 0 {
+		return;
+	}
+
+	cpu.write_lo_wide(map1::<u32>(rs, |a| (a as i32).wrapping_div(divisor) as u32));
+	cpu.write_hi_wide(map1::<u32>(rs, |a| (a as i32).wrapping_rem(divisor) as u32));
+}
+
+pub fn pdivuw(cpu: &mut EECore, data: &OpCode) {
+	word_divide(cpu, data, |a, b| Some((a.checked_div(b)?, a % b)));
+}
+
+pub fn pdivw(cpu: &mut EECore, data: &OpCode) {
+	word_divide(cpu, data, |a, b| if b == 0 {
+		None
+	} else {
+		let (a, b) = (a as i32, b as i32);
+		Some((a.wrapping_div(b) as u32, a.wrapping_rem(b) as u32))
+	});
+}
+
+pub fn pexch(cpu: &mut EECore, data: &OpCode) {
+	parallel_op_t(cpu, data, |rt| permute::<u16>(rt, &[0, 2, 1, 3, 4, 6, 5, 7]));
+}
+
+pub fn pexcw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op_t(cpu, data, |rt| permute::<u32>(rt, &[0, 2, 1, 3]));
+}
+
+pub fn pexeh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op_t(cpu, data, |rt| permute::<u16>(rt, &[2, 1, 0, 3, 6, 5, 4, 7]));
+}
+
+pub fn pexew(cpu: &mut EECore, data: &OpCode) {
+	parallel_op_t(cpu, data, |rt| permute::<u32>(rt, &[2, 1, 0, 3]));
+}
+
+pub fn pext5(cpu: &mut EECore, data: &OpCode) {
+	// 1-5-5-5 -> 8-8-8-8.
+	parallel_op_t(cpu, data, |rt| map1::<u32>(rt, |a| {
+		((a & 0x1f) << 3)
+			| (((a >> 5) & 0x1f) << 11)
+			| (((a >> 10) & 0x1f) << 19)
+			| (((a >> 15) & 0x1) << 31)
+	}));
+}
+
+pub fn pextlb(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| interleave::<u8>(rs, rt, 0));
+}
+
+pub fn pextlh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| interleave::<u16>(rs, rt, 0));
+}
+
+pub fn pextlw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| interleave::<u32>(rs, rt, 0));
+}
+
+pub fn pextub(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| interleave::<u8>(rs, rt, 8));
+}
+
+pub fn pextuh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| interleave::<u16>(rs, rt, 4));
+}
+
+pub fn pextuw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| interleave::<u32>(rs, rt, 2));
+}
+
+pub fn phmadh(cpu: &mut EECore, data: &OpCode) {
+	halfword_horizontal(cpu, data, i32::wrapping_add);
+}
+
+pub fn phmsbh(cpu: &mut EECore, data: &OpCode) {
+	halfword_horizontal(cpu, data, i32::wrapping_sub);
+}
+
+pub fn pinteh(cpu: &mut EECore, data: &OpCode) {
+	// Even halfwords of rt and rs, alternating.
+	parallel_op(cpu, data, |rs, rt| build::<u16>(|i| if i % 2 == 0 {
+		lane(rt, i)
+	} else {
+		lane(rs, i - 1)
+	}));
+}
+
+pub fn pinth(cpu: &mut EECore, data: &OpCode) {
+	// Lower halfwords of rt and upper halfwords of rs, alternating.
+	parallel_op(cpu, data, |rs, rt| build::<u16>(|i| if i % 2 == 0 {
+		lane(rt, i / 2)
+	} else {
+		lane(rs, 4 + i / 2)
+	}));
+}
+
+pub fn plzcw(cpu: &mut EECore, data: &OpCode) {
+	let rs = cpu.read_register(data.ri_get_source());
+	let lower = leading_sign_bits(rs as u32);
+	let upper = leading_sign_bits((rs >> 32) as u32);
+
+	cpu.write_register(data.r_get_destination(), (u64::from(upper) << 32) | u64::from(lower));
+}
+
+pub fn pmaddh(cpu: &mut EECore, data: &OpCode) {
+	halfword_multiply(cpu, data, i32::wrapping_add);
+}
+
+pub fn pmadduw(cpu: &mut EECore, data: &OpCode) {
+	word_multiply(cpu, data, |a, b, acc| acc.wrapping_add(u64::from(a) * u64::from(b)));
+}
+
+pub fn pmaddw(cpu: &mut EECore, data: &OpCode) {
+	word_multiply(cpu, data, |a, b, acc| {
+		let product = i64::from(a as i32) * i64::from(b as i32);
+		(acc as i64).wrapping_add(product) as u64
+	});
+}
+
+pub fn pmaxh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, |a, b| (a as i16).max(b as i16) as u16));
+}
+
+pub fn pmaxw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, |a, b| (a as i32).max(b as i32) as u32));
+}
+
+pub fn pmfhi(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register_wide(data.r_get_destination(), cpu.read_hi_wide());
+}
+
+pub fn pmfhl_lh(cpu: &mut EECore, data: &OpCode) {
+	// Even halfwords of each word, in the same order as `PMULTH` products.
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	cpu.write_register_wide(data.r_get_destination(), build::<u16>(|i| {
+		let (in_hi, word) = halfword_product_slot(i);
+		lane(if in_hi { hi } else { lo }, 2 * word)
+	}));
+}
+
+pub fn pmfhl_lw(cpu: &mut EECore, data: &OpCode) {
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	cpu.write_register_wide(data.r_get_destination(), build::<u32>(|i| {
+		lane(if i % 2 == 0 { lo } else { hi }, i & 0b10)
+	}));
+}
+
+pub fn pmfhl_sh(cpu: &mut EECore, data: &OpCode) {
+	// Each word, saturated to a halfword, in the same order as `PMULTH` products.
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	cpu.write_register_wide(data.r_get_destination(), build::<u16>(|i| {
+		let (in_hi, word) = halfword_product_slot(i);
+		saturate_halfword(lane(if in_hi { hi } else { lo }, word))
+	}));
+}
+
+pub fn pmfhl_slw(cpu: &mut EECore, data: &OpCode) {
+	// HI.w[2i] || LO.w[2i], saturated to a (sign-extended) word.
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	cpu.write_register_wide(data.r_get_destination(), build::<u64>(|i| {
+		let value = (u64::from(lane::<u32>(hi, 2 * i)) << 32) | u64::from(lane::<u32>(lo, 2 * i));
+		let value = (value as i64).max(i64::from(i32::MIN)).min(i64::from(i32::MAX));
+		value as u64
+	}));
+}
+
+pub fn pmfhl_uw(cpu: &mut EECore, data: &OpCode) {
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	cpu.write_register_wide(data.r_get_destination(), build::<u32>(|i| {
+		lane(if i % 2 == 0 { lo } else { hi }, (i & 0b10) + 1)
+	}));
+}
+
+pub fn pmflo(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_register_wide(data.r_get_destination(), cpu.read_lo_wide());
+}
+
+pub fn pminh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, |a, b| (a as i16).min(b as i16) as u16));
+}
+
+pub fn pminw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, |a, b| (a as i32).min(b as i32) as u32));
+}
+
+pub fn pmsubh(cpu: &mut EECore, data: &OpCode) {
+	halfword_multiply(cpu, data, |product, acc| acc.wrapping_sub(product));
+}
+
+pub fn pmsubw(cpu: &mut EECore, data: &OpCode) {
+	word_multiply(cpu, data, |a, b, acc| {
+		let product = i64::from(a as i32) * i64::from(b as i32);
+		(acc as i64).wrapping_sub(product) as u64
+	});
+}
+
+pub fn pmthi(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_hi_wide(cpu.read_register_wide(data.ri_get_source()));
+}
+
+pub fn pmthl_lw(cpu: &mut EECore, data: &OpCode) {
+	// Inverse of `PMFHL.LW`: odd words of HI/LO are unchanged.
+	let rs = cpu.read_register_wide(data.ri_get_source());
+	let hi = cpu.read_hi_wide();
+	let lo = cpu.read_lo_wide();
+
+	cpu.write_lo_wide(build::<u32>(|i| if i % 2 == 0 { lane(rs, i) } else { lane(lo, i) }));
+	cpu.write_hi_wide(build::<u32>(|i| if i % 2 == 0 { lane(rs, i + 1) } else { lane(hi, i) }));
+}
+
+pub fn pmtlo(cpu: &mut EECore, data: &OpCode) {
+	cpu.write_lo_wide(cpu.read_register_wide(data.ri_get_source()));
+}
+
+pub fn pmulth(cpu: &mut EECore, data: &OpCode) {
+	halfword_multiply(cpu, data, |product, _acc| product);
+}
+
+pub fn pmultuw(cpu: &mut EECore, data: &OpCode) {
+	word_multiply(cpu, data, |a, b, _acc| u64::from(a) * u64::from(b));
+}
+
+pub fn pmultw(cpu: &mut EECore, data: &OpCode) {
+	word_multiply(cpu, data, |a, b, _acc| (i64::from(a as i32) * i64::from(b as i32)) as u64);
+}
+
+pub fn pnor(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| !(rs | rt));
+}
+
+pub fn por(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| rs | rt);
+}
+
+pub fn ppac5(cpu: &mut EECore, data: &OpCode) {
+	// 8-8-8-8 -> 1-5-5-5.
+	parallel_op_t(cpu, data, |rt| map1::<u32>(rt, |a| {
+		((a >> 3) & 0x1f)
+			| (((a >> 11) & 0x1f) << 5)
+			| (((a >> 19) & 0x1f) << 10)
+			| (((a >> 31) & 0x1) << 15)
+	}));
+}
+
+pub fn ppacb(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, pack::<u8>);
+}
+
+pub fn ppach(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, pack::<u16>);
+}
+
+pub fn ppacw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, pack::<u32>);
+}
+
+pub fn prevh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op_t(cpu, data, |rt| permute::<u16>(rt, &[3, 2, 1, 0, 7, 6, 5, 4]));
+}
+
+pub fn prot3w(cpu: &mut EECore, data: &OpCode) {
+	parallel_op_t(cpu, data, |rt| permute::<u32>(rt, &[1, 2, 0, 3]));
+}
+
+pub fn psllh(cpu: &mut EECore, data: &OpCode) {
+	parallel_shift(cpu, data, |rt, sa| map1::<u16>(rt, |a| a << (sa & 0xf)));
+}
+
+pub fn psllvw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| build::<u64>(|i| {
+		let shift = lane::<u32>(rs, 2 * i) & 0x1f;
+		(lane::<u32>(rt, 2 * i) << shift).s_ext()
+	}));
+}
+
+pub fn psllw(cpu: &mut EECore, data: &OpCode) {
+	parallel_shift(cpu, data, |rt, sa| map1::<u32>(rt, |a| a << sa));
+}
+
+pub fn psrah(cpu: &mut EECore, data: &OpCode) {
+	parallel_shift(cpu, data, |rt, sa| map1::<u16>(rt, |a| ((a as i16) >> (sa & 0xf)) as u16));
+}
+
+pub fn psravw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| build::<u64>(|i| {
+		let shift = lane::<u32>(rs, 2 * i) & 0x1f;
+		((lane::<u32>(rt, 2 * i) as i32) >> shift).s_ext()
+	}));
+}
+
+pub fn psraw(cpu: &mut EECore, data: &OpCode) {
+	parallel_shift(cpu, data, |rt, sa| map1::<u32>(rt, |a| ((a as i32) >> sa) as u32));
+}
+
+pub fn psrlh(cpu: &mut EECore, data: &OpCode) {
+	parallel_shift(cpu, data, |rt, sa| map1::<u16>(rt, |a| a >> (sa & 0xf)));
+}
+
+pub fn psrlvw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| build::<u64>(|i| {
+		let shift = lane::<u32>(rs, 2 * i) & 0x1f;
+		(lane::<u32>(rt, 2 * i) >> shift).s_ext()
+	}));
+}
+
+pub fn psrlw(cpu: &mut EECore, data: &OpCode) {
+	parallel_shift(cpu, data, |rt, sa| map1::<u32>(rt, |a| a >> sa));
+}
+
+pub fn psubb(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u8>(rs, rt, u8::wrapping_sub));
+}
+
+pub fn psubh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, u16::wrapping_sub));
+}
+
+pub fn psubsb(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u8>(rs, rt, |a, b| (a as i8).saturating_sub(b as i8) as u8));
+}
+
+pub fn psubsh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, |a, b| (a as i16).saturating_sub(b as i16) as u16));
+}
+
+pub fn psubsw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, |a, b| (a as i32).saturating_sub(b as i32) as u32));
+}
+
+pub fn psubub(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u8>(rs, rt, u8::saturating_sub));
+}
+
+pub fn psubuh(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u16>(rs, rt, u16::saturating_sub));
+}
+
+pub fn psubuw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, u32::saturating_sub));
+}
+
+pub fn psubw(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| map2::<u32>(rs, rt, u32::wrapping_sub));
+}
+
+pub fn pxor(cpu: &mut EECore, data: &OpCode) {
+	parallel_op(cpu, data, |rs, rt| rs ^ rt);
+}
+
+pub fn qfsrv(cpu: &mut EECore, data: &OpCode) {
+	// (GPR[rs] || GPR[rt]) >> SA, where SA holds a shift amount in bits.
+	let shift = cpu.sa_register & 0x7f;
+
+	parallel_op(cpu, data, |rs, rt| if shift == 0 {
+		rt
+	} else {
+		(rt >> shift) | (rs << (128 - shift))
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::ops,
+		isa::mips::{
+			self,
+			ee::*,
+			Opcode as MipsOpcode,
+			RegImmFunction,
+		},
+	};
+
+	fn words(w: [u32; 4]) -> u128 {
+		build::<u32>(|i| w[i as usize])
+	}
+
+	fn halves(h: [u16; 8]) -> u128 {
+		build::<u16>(|i| h[i as usize])
+	}
+
+	fn dwords(d: [u64; 2]) -> u128 {
+		build::<u64>(|i| d[i as usize])
+	}
+
+	fn neg(v: i32) -> u32 {
+		v as u32
+	}
+
+	fn neg_h(v: i16) -> u16 {
+		v as u16
+	}
+
+	fn neg_d(v: i64) -> u64 {
+		v as u64
+	}
+
+	/// Bytes `base..base + 16`.
+	fn byte_ramp(base: u8) -> u128 {
+		build::<u8>(|i| base + i as u8)
+	}
+
+	fn run(test_ee: &mut EECore, function: u8, sa: u8, rs: u128, rt: u128) -> u128 {
+		test_ee.write_register_wide(1, rs);
+		test_ee.write_register_wide(2, rt);
+
+		let instruction = mips::build_op_register_custom(MipsOpcode::Mmi, function, 1, 2, 3, sa);
+		test_ee.execute(ops::process_instruction(instruction));
+
+		test_ee.read_register_wide(3)
+	}
+
+	fn run_base(function: MmiBaseFunction, sa: u8, rs: u128, rt: u128) -> u128 {
+		run(&mut EECore::new(), function as u8, sa, rs, rt)
+	}
+
+	fn run0(function: Mmi0Function, rs: u128, rt: u128) -> u128 {
+		run(&mut EECore::new(), MMI0, function as u8, rs, rt)
+	}
+
+	fn run1(function: Mmi1Function, rs: u128, rt: u128) -> u128 {
+		run(&mut EECore::new(), MMI1, function as u8, rs, rt)
+	}
+
+	fn run2(function: Mmi2Function, rs: u128, rt: u128) -> u128 {
+		run(&mut EECore::new(), MMI2, function as u8, rs, rt)
+	}
+
+	fn run3(function: Mmi3Function, rs: u128, rt: u128) -> u128 {
+		run(&mut EECore::new(), MMI3, function as u8, rs, rt)
+	}
+
+	fn word_a() -> u128 {
+		words([1, 0x7fff_ffff, 0x8000_0000, 0xffff_fffe])
+	}
+
+	fn word_b() -> u128 {
+		words([2, 1, 0xffff_ffff, 5])
+	}
+
+	fn half_a() -> u128 {
+		halves([1, 0x7fff, 0x8000, 0xfffe, 10, 20, 30, 40])
+	}
+
+	fn half_b() -> u128 {
+		halves([2, 1, 0xffff, 5, 10, 30, 20, 0])
+	}
+
+	fn byte_a() -> u128 {
+		u128::from_le_bytes([1, 0x7f, 0x80, 0xfe, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+	}
+
+	fn byte_b() -> u128 {
+		u128::from_le_bytes([2, 1, 0xff, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+	}
+
+	fn bytes_low(b: [u8; 4]) -> u128 {
+		u128::from(u32::from_le_bytes(b))
+	}
+
+	/// Bytes of `expected`, with all but the first 4 bytes equal to `rest`.
+	fn bytes_with_rest(b: [u8; 4], rest: u8) -> u128 {
+		let mut out = [rest; 16];
+		out[..4].copy_from_slice(&b);
+		u128::from_le_bytes(out)
+	}
+
+	fn ramp_words(base: u32) -> u128 {
+		words([base, base + 1, base + 2, base + 3])
+	}
+
+	fn ramp_halves(base: u16) -> u128 {
+		build::<u16>(|i| base + i as u16)
+	}
+
+	fn mul_ee(hi: u128, lo: u128) -> EECore {
+		let mut test_ee = EECore::new();
+		test_ee.write_hi_wide(hi);
+		test_ee.write_lo_wide(lo);
+		test_ee
+	}
+
+	#[test]
+	fn mmi_decode() {
+		use MmiFunction::*;
+
+		let cases = [
+			(MMI0, Mmi0Function::PAddW as u8, PAddW),
+			(MMI1, Mmi1Function::QFSRV as u8, QFSRV),
+			(MMI2, Mmi2Function::PCpyLD as u8, PCpyLD),
+			(MMI3, Mmi3Function::POr as u8, POr),
+			(MmiBaseFunction::PSLLH as u8, 5, PSLLH),
+			(MmiBaseFunction::PMFHL as u8, PMFHLFunction::SLW as u8, PMFHLSLW),
+			(MmiBaseFunction::PMTHL as u8, PMTHL_LW, PMTHLLW),
+		];
+
+		for (function, sa, expected) in cases.iter() {
+			let instruction = mips::build_op_register_custom(MipsOpcode::Mmi, *function, 1, 2, 3, *sa);
+			assert_eq!(MmiFunction::decode(instruction).as_ref(), Some(expected));
+		}
+
+		// Unassigned slots.
+		let bad_mmi0 = mips::build_op_register_custom(MipsOpcode::Mmi, MMI0, 1, 2, 3, 0b0_1011);
+		let bad_pmthl = mips::build_op_register_custom(MipsOpcode::Mmi, MmiBaseFunction::PMTHL as u8, 1, 2, 3, 1);
+		assert_eq!(MmiFunction::decode(bad_mmi0), None);
+		assert_eq!(MmiFunction::decode(bad_pmthl), None);
+	}
+
+	#[test]
+	fn basic_paddw() {
+		assert_eq!(run0(Mmi0Function::PAddW, word_a(), word_b()), words([3, 0x8000_0000, 0x7fff_ffff, 3]));
+	}
+
+	#[test]
+	fn basic_psubw() {
+		assert_eq!(run0(Mmi0Function::PSubW, word_a(), word_b()), words([neg(-1), 0x7fff_fffe, 0x8000_0001, neg(-7)]));
+	}
+
+	#[test]
+	fn basic_pcgtw() {
+		assert_eq!(run0(Mmi0Function::PCGTW, word_a(), word_b()), words([0, !0, 0, 0]));
+	}
+
+	#[test]
+	fn basic_pmaxw() {
+		assert_eq!(run0(Mmi0Function::PMaxW, word_a(), word_b()), words([2, 0x7fff_ffff, neg(-1), 5]));
+	}
+
+	#[test]
+	fn basic_paddh() {
+		assert_eq!(run0(Mmi0Function::PAddH, half_a(), half_b()), halves([3, 0x8000, 0x7fff, 3, 20, 50, 50, 40]));
+	}
+
+	#[test]
+	fn basic_psubh() {
+		assert_eq!(
+			run0(Mmi0Function::PSubH, half_a(), half_b()),
+			halves([neg_h(-1), 0x7ffe, 0x8001, neg_h(-7), 0, neg_h(-10), 10, 40]),
+		);
+	}
+
+	#[test]
+	fn basic_pcgth() {
+		assert_eq!(run0(Mmi0Function::PCGTH, half_a(), half_b()), halves([0, !0, 0, 0, 0, 0, !0, !0]));
+	}
+
+	#[test]
+	fn basic_pmaxh() {
+		assert_eq!(run0(Mmi0Function::PMaxH, half_a(), half_b()), halves([2, 0x7fff, neg_h(-1), 5, 10, 30, 30, 40]));
+	}
+
+	#[test]
+	fn basic_paddb() {
+		assert_eq!(run0(Mmi0Function::PAddB, byte_a(), byte_b()), bytes_low([3, 0x80, 0x7f, 3]));
+	}
+
+	#[test]
+	fn basic_psubb() {
+		assert_eq!(run0(Mmi0Function::PSubB, byte_a(), byte_b()), bytes_low([0xff, 0x7e, 0x81, 0xf9]));
+	}
+
+	#[test]
+	fn basic_pcgtb() {
+		assert_eq!(run0(Mmi0Function::PCGTB, byte_a(), byte_b()), bytes_low([0, 0xff, 0, 0]));
+	}
+
+	#[test]
+	fn basic_paddsw() {
+		assert_eq!(run0(Mmi0Function::PAddSW, word_a(), word_b()), words([3, 0x7fff_ffff, 0x8000_0000, 3]));
+	}
+
+	#[test]
+	fn basic_psubsw() {
+		assert_eq!(run0(Mmi0Function::PSubSW, word_a(), word_b()), words([neg(-1), 0x7fff_fffe, 0x8000_0001, neg(-7)]));
+
+		// Saturation in both directions.
+		let out = run0(Mmi0Function::PSubSW, words([0x7fff_ffff, 0x8000_0000, 0, 0]), words([neg(-1), 1, 0, 0]));
+		assert_eq!(out, words([0x7fff_ffff, 0x8000_0000, 0, 0]));
+	}
+
+	#[test]
+	fn basic_pextlw() {
+		assert_eq!(run0(Mmi0Function::PExtLW, ramp_words(0x10), ramp_words(0x20)), words([0x20, 0x10, 0x21, 0x11]));
+	}
+
+	#[test]
+	fn basic_ppacw() {
+		assert_eq!(run0(Mmi0Function::PPacW, ramp_words(0x10), ramp_words(0x20)), words([0x20, 0x22, 0x10, 0x12]));
+	}
+
+	#[test]
+	fn basic_paddsh() {
+		assert_eq!(run0(Mmi0Function::PAddSH, half_a(), half_b()), halves([3, 0x7fff, 0x8000, 3, 20, 50, 50, 40]));
+	}
+
+	#[test]
+	fn basic_psubsh() {
+		assert_eq!(
+			run0(Mmi0Function::PSubSH, half_a(), half_b()),
+			halves([neg_h(-1), 0x7ffe, 0x8001, neg_h(-7), 0, neg_h(-10), 10, 40]),
+		);
+
+		let out = run0(Mmi0Function::PSubSH, halves([0x8000, 0x7fff, 0, 0, 0, 0, 0, 0]), halves([1, neg_h(-1), 0, 0, 0, 0, 0, 0]));
+		assert_eq!(out, halves([0x8000, 0x7fff, 0, 0, 0, 0, 0, 0]));
+	}
+
+	#[test]
+	fn basic_pextlh() {
+		assert_eq!(
+			run0(Mmi0Function::PExtLH, ramp_halves(0x10), ramp_halves(0x20)),
+			halves([0x20, 0x10, 0x21, 0x11, 0x22, 0x12, 0x23, 0x13]),
+		);
+	}
+
+	#[test]
+	fn basic_ppach() {
+		assert_eq!(
+			run0(Mmi0Function::PPacH, ramp_halves(0x10), ramp_halves(0x20)),
+			halves([0x20, 0x22, 0x24, 0x26, 0x10, 0x12, 0x14, 0x16]),
+		);
+	}
+
+	#[test]
+	fn basic_paddsb() {
+		assert_eq!(run0(Mmi0Function::PAddSB, byte_a(), byte_b()), bytes_low([3, 0x7f, 0x80, 3]));
+	}
+
+	#[test]
+	fn basic_psubsb() {
+		assert_eq!(run0(Mmi0Function::PSubSB, byte_a(), byte_b()), bytes_low([0xff, 0x7e, 0x81, 0xf9]));
+
+		let out = run0(Mmi0Function::PSubSB, bytes_low([0x80, 0x7f, 0, 0]), bytes_low([1, 0xff, 0, 0]));
+		assert_eq!(out, bytes_low([0x80, 0x7f, 0, 0]));
+	}
+
+	#[test]
+	fn basic_pextlb() {
+		let expected = build::<u8>(|i| if i % 2 == 0 { 0x20 + (i / 2) as u8 } else { 0x10 + (i / 2) as u8 });
+		assert_eq!(run0(Mmi0Function::PExtLB, byte_ramp(0x10), byte_ramp(0x20)), expected);
+	}
+
+	#[test]
+	fn basic_ppacb() {
+		let expected = build::<u8>(|i| if i < 8 { 0x20 + 2 * i as u8 } else { 0x10 + 2 * (i - 8) as u8 });
+		assert_eq!(run0(Mmi0Function::PPacB, byte_ramp(0x10), byte_ramp(0x20)), expected);
+	}
+
+	#[test]
+	fn basic_pext5_ppac5() {
+		// A = 1, B = 21, G = 10, R = 31.
+		let packed = 0xd55f;
+		let unpacked = 0x80a8_50f8;
+
+		assert_eq!(run0(Mmi0Function::PExt5, 0, words([packed; 4])), words([unpacked; 4]));
+		assert_eq!(run0(Mmi0Function::PPac5, 0, words([unpacked; 4])), words([packed; 4]));
+	}
+
+	#[test]
+	fn basic_pabsw() {
+		assert_eq!(
+			run1(Mmi1Function::PAbsW, 0, words([neg(-5), 0x8000_0000, 7, 0])),
+			words([5, 0x7fff_ffff, 7, 0]),
+		);
+	}
+
+	#[test]
+	fn basic_pceqw() {
+		assert_eq!(run1(Mmi1Function::PCEqW, words([1, 2, 3, 4]), words([1, 0, 3, 0])), words([!0, 0, !0, 0]));
+	}
+
+	#[test]
+	fn basic_pminw() {
+		assert_eq!(run1(Mmi1Function::PMinW, word_a(), word_b()), words([1, 1, 0x8000_0000, neg(-2)]));
+	}
+
+	#[test]
+	fn basic_padsbh() {
+		assert_eq!(
+			run1(Mmi1Function::PAdSBH, half_a(), half_b()),
+			halves([neg_h(-1), 0x7ffe, 0x8001, neg_h(-7), 20, 50, 50, 40]),
+		);
+	}
+
+	#[test]
+	fn basic_pabsh() {
+		assert_eq!(
+			run1(Mmi1Function::PAbsH, 0, halves([neg_h(-5), 0x8000, 7, 0, 1, 2, 3, 4])),
+			halves([5, 0x7fff, 7, 0, 1, 2, 3, 4]),
+		);
+	}
+
+	#[test]
+	fn basic_pceqh() {
+		assert_eq!(run1(Mmi1Function::PCEqH, half_a(), half_b()), halves([0, 0, 0, 0, !0, 0, 0, 0]));
+	}
+
+	#[test]
+	fn basic_pminh() {
+		assert_eq!(run1(Mmi1Function::PMinH, half_a(), half_b()), halves([1, 1, 0x8000, neg_h(-2), 10, 20, 20, 0]));
+	}
+
+	#[test]
+	fn basic_pceqb() {
+		assert_eq!(run1(Mmi1Function::PCEqB, byte_a(), byte_b()), bytes_with_rest([0, 0, 0, 0], 0xff));
+	}
+
+	#[test]
+	fn basic_padduw() {
+		assert_eq!(run1(Mmi1Function::PAddUW, word_a(), word_b()), words([3, 0x8000_0000, !0, !0]));
+	}
+
+	#[test]
+	fn basic_psubuw() {
+		assert_eq!(run1(Mmi1Function::PSubUW, word_a(), word_b()), words([0, 0x7fff_fffe, 0, 0xffff_fff9]));
+	}
+
+	#[test]
+	fn basic_pextuw() {
+		assert_eq!(run1(Mmi1Function::PExtUW, ramp_words(0x10), ramp_words(0x20)), words([0x22, 0x12, 0x23, 0x13]));
+	}
+
+	#[test]
+	fn basic_padduh() {
+		assert_eq!(run1(Mmi1Function::PAddUH, half_a(), half_b()), halves([3, 0x8000, !0, !0, 20, 50, 50, 40]));
+	}
+
+	#[test]
+	fn basic_psubuh() {
+		assert_eq!(run1(Mmi1Function::PSubUH, half_a(), half_b()), halves([0, 0x7ffe, 0, 0xfff9, 0, 0, 10, 40]));
+	}
+
+	#[test]
+	fn basic_pextuh() {
+		assert_eq!(
+			run1(Mmi1Function::PExtUH, ramp_halves(0x10), ramp_halves(0x20)),
+			halves([0x24, 0x14, 0x25, 0x15, 0x26, 0x16, 0x27, 0x17]),
+		);
+	}
+
+	#[test]
+	fn basic_paddub() {
+		assert_eq!(run1(Mmi1Function::PAddUB, byte_a(), byte_b()), bytes_low([3, 0x80, 0xff, 0xff]));
+	}
+
+	#[test]
+	fn basic_psubub() {
+		assert_eq!(run1(Mmi1Function::PSubUB, byte_a(), byte_b()), bytes_low([0, 0x7e, 0, 0xf9]));
+	}
+
+	#[test]
+	fn basic_pextub() {
+		let expected = build::<u8>(|i| if i % 2 == 0 { 0x28 + (i / 2) as u8 } else { 0x18 + (i / 2) as u8 });
+		assert_eq!(run1(Mmi1Function::PExtUB, byte_ramp(0x10), byte_ramp(0x20)), expected);
+	}
+
+	#[test]
+	fn basic_qfsrv() {
+		let mut test_ee = EECore::new();
+
+		// SA holds a shift amount in bits.
+		test_ee.sa_register = 32;
+		let out = run(&mut test_ee, MMI1, Mmi1Function::QFSRV as u8, ramp_words(0x10), ramp_words(0x20));
+		assert_eq!(out, words([0x21, 0x22, 0x23, 0x10]));
+
+		test_ee.sa_register = 0;
+		let out = run(&mut test_ee, MMI1, Mmi1Function::QFSRV as u8, ramp_words(0x10), ramp_words(0x20));
+		assert_eq!(out, ramp_words(0x20));
+	}
+
+	#[test]
+	fn qfsrv_after_mtsab_mtsah() {
+		// The SDK's misaligned copies set SA from an address, then funnel-shift two quadwords.
+		let mut test_ee = EECore::new();
+		let (rs, rt) = (byte_ramp(0x10), byte_ramp(0x20));
+
+		test_ee.write_register(4, 0x7003);
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::RegImm, 4, RegImmFunction::MTSAB as u8, 0)));
+		let out = run(&mut test_ee, MMI1, Mmi1Function::QFSRV as u8, rs, rt);
+		assert_eq!(out, (rt >> 24) | (rs << 104));
+
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::RegImm, 4, RegImmFunction::MTSAH as u8, 0x2)));
+		let out = run(&mut test_ee, MMI1, Mmi1Function::QFSRV as u8, rs, rt);
+		assert_eq!(out, (rt >> 16) | (rs << 112));
+	}
+
+	#[test]
+	fn basic_pmaddw() {
+		let mut test_ee = mul_ee(words([0, 0, neg(-1), 0]), words([10, 0, neg(-1), 0]));
+		let out = run(&mut test_ee, MMI2, Mmi2Function::PMAddW as u8, words([3, 0, neg(-2), 0]), words([4, 0, 3, 0]));
+
+		assert_eq!(out, dwords([22, neg_d(-7)]));
+		assert_eq!(test_ee.read_lo_wide(), dwords([22, neg_d(-7)]));
+		assert_eq!(test_ee.read_hi_wide(), dwords([0, !0]));
+	}
+
+	#[test]
+	fn basic_psllvw() {
+		let out = run2(Mmi2Function::PSLLVW, words([4, 0, 8 + 32, 0]), words([0x8000_0001, 0xdead, 0x1234_5678, 0xbeef]));
+		assert_eq!(out, dwords([0x10, 0x3456_7800]));
+	}
+
+	#[test]
+	fn basic_psrlvw() {
+		let out = run2(Mmi2Function::PSRLVW, words([4, 0, 0, 0]), words([0x8000_0000, 0, 0x8000_0000, 0]));
+		assert_eq!(out, dwords([0x0800_0000, 0xffff_ffff_8000_0000]));
+	}
+
+	#[test]
+	fn basic_pmsubw() {
+		let mut test_ee = mul_ee(0, words([10, 0, 0, 0]));
+		let out = run(&mut test_ee, MMI2, Mmi2Function::PMSubW as u8, words([3, 0, 0, 0]), words([4, 0, 0, 0]));
+
+		assert_eq!(out, dwords([neg_d(-2), 0]));
+		assert_eq!(test_ee.read_lo_wide(), dwords([neg_d(-2), 0]));
+		assert_eq!(test_ee.read_hi_wide(), dwords([!0, 0]));
+	}
+
+	#[test]
+	fn basic_pmfhi_pmflo() {
+		let mut test_ee = mul_ee(ramp_words(0x20), ramp_words(0x10));
+
+		assert_eq!(run(&mut test_ee, MMI2, Mmi2Function::PMFHi as u8, 0, 0), ramp_words(0x20));
+		assert_eq!(run(&mut test_ee, MMI2, Mmi2Function::PMFLo as u8, 0, 0), ramp_words(0x10));
+	}
+
+	#[test]
+	fn basic_pinth() {
+		assert_eq!(
+			run2(Mmi2Function::PIntH, ramp_halves(0x10), ramp_halves(0x20)),
+			halves([0x20, 0x14, 0x21, 0x15, 0x22, 0x16, 0x23, 0x17]),
+		);
+	}
+
+	#[test]
+	fn basic_pmultw() {
+		let mut test_ee = EECore::new();
+		let out = run(&mut test_ee, MMI2, Mmi2Function::PMultW as u8, words([3, 0, neg(-2), 0]), words([0x4000_0000, 0, 7, 0]));
+
+		assert_eq!(out, dwords([0xc000_0000, neg_d(-14)]));
+		assert_eq!(test_ee.read_lo_wide(), dwords([0xffff_ffff_c000_0000, neg_d(-14)]));
+		assert_eq!(test_ee.read_hi_wide(), dwords([0, !0]));
+	}
+
+	#[test]
+	fn basic_pdivw() {
+		let mut test_ee = EECore::new();
+		run(&mut test_ee, MMI2, Mmi2Function::PDivW as u8, words([7, 0, neg(-7), 0]), words([2, 0, 2, 0]));
+
+		assert_eq!(test_ee.read_lo_wide(), dwords([3, neg_d(-3)]));
+		assert_eq!(test_ee.read_hi_wide(), dwords([1, neg_d(-1)]));
+	}
+
+	#[test]
+	fn pdivw_zero_divisor_preserves_lane() {
+		let mut test_ee = mul_ee(dwords([0xaa, 0xbb]), dwords([0xcc, 0xdd]));
+		run(&mut test_ee, MMI2, Mmi2Function::PDivW as u8, words([7, 0, 7, 0]), words([0, 0, 2, 0]));
+
+		assert_eq!(test_ee.read_lo_wide(), dwords([0xcc, 3]));
+		assert_eq!(test_ee.read_hi_wide(), dwords([0xaa, 1]));
+	}
+
+	#[test]
+	fn basic_pcpyld() {
+		assert_eq!(run2(Mmi2Function::PCpyLD, dwords([1, 2]), dwords([3, 4])), dwords([3, 1]));
+	}
+
+	#[test]
+	fn basic_pmaddh() {
+		let mut test_ee = mul_ee(words([2; 4]), words([1; 4]));
+		let out = run(
+			&mut test_ee, MMI2, Mmi2Function::PMAddH as u8,
+			halves([1, 2, 3, 4, 5, 6, 7, neg_h(-1)]), halves([10; 8]),
+		);
+
+		assert_eq!(test_ee.read_lo_wide(), words([11, 21, 51, 61]));
+		assert_eq!(test_ee.read_hi_wide(), words([32, 42, 72, neg(-8)]));
+		assert_eq!(out, words([11, 32, 51, 72]));
+	}
+
+	#[test]
+	fn basic_phmadh() {
+		let mut test_ee = mul_ee(words([0xaa; 4]), words([0xbb; 4]));
+		let out = run(
+			&mut test_ee, MMI2, Mmi2Function::PHMAdH as u8,
+			halves([1, 2, 3, 4, 5, 6, 7, neg_h(-1)]), halves([10; 8]),
+		);
+
+		assert_eq!(out, words([30, 70, 110, 60]));
+		assert_eq!(test_ee.read_lo_wide(), words([30, 0xbb, 110, 0xbb]));
+		assert_eq!(test_ee.read_hi_wide(), words([70, 0xaa, 60, 0xaa]));
+	}
+
+	#[test]
+	fn basic_pand() {
+		assert_eq!(run2(Mmi2Function::PAnd, word_a(), word_b()), word_a() & word_b());
+	}
+
+	#[test]
+	fn basic_pxor() {
+		assert_eq!(run2(Mmi2Function::PXor, word_a(), word_b()), word_a() ^ word_b());
+	}
+
+	#[test]
+	fn basic_pmsubh() {
+		let mut test_ee = mul_ee(words([2; 4]), words([1; 4]));
+		let out = run(
+			&mut test_ee, MMI2, Mmi2Function::PMSubH as u8,
+			halves([1, 2, 3, 4, 5, 6, 7, neg_h(-1)]), halves([10; 8]),
+		);
+
+		assert_eq!(test_ee.read_lo_wide(), words([neg(-9), neg(-19), neg(-49), neg(-59)]));
+		assert_eq!(test_ee.read_hi_wide(), words([neg(-28), neg(-38), neg(-68), 12]));
+		assert_eq!(out, words([neg(-9), neg(-28), neg(-49), neg(-68)]));
+	}
+
+	#[test]
+	fn basic_phmsbh() {
+		let out = run2(Mmi2Function::PHMSbH, halves([1, 2, 3, 4, 5, 6, 7, neg_h(-1)]), halves([10; 8]));
+		assert_eq!(out, words([10, 10, 10, neg(-80)]));
+	}
+
+	#[test]
+	fn basic_pexeh() {
+		assert_eq!(run2(Mmi2Function::PExEH, 0, ramp_halves(0)), halves([2, 1, 0, 3, 6, 5, 4, 7]));
+	}
+
+	#[test]
+	fn basic_prevh() {
+		assert_eq!(run2(Mmi2Function::PRevH, 0, ramp_halves(0)), halves([3, 2, 1, 0, 7, 6, 5, 4]));
+	}
+
+	#[test]
+	fn basic_pmulth() {
+		let mut test_ee = EECore::new();
+		let out = run(
+			&mut test_ee, MMI2, Mmi2Function::PMultH as u8,
+			halves([1, 2, 3, 4, 5, 6, 7, neg_h(-1)]), halves([10; 8]),
+		);
+
+		assert_eq!(test_ee.read_lo_wide(), words([10, 20, 50, 60]));
+		assert_eq!(test_ee.read_hi_wide(), words([30, 40, 70, neg(-10)]));
+		assert_eq!(out, words([10, 30, 50, 70]));
+	}
+
+	#[test]
+	fn basic_pdivbw() {
+		let mut test_ee = EECore::new();
+		run(
+			&mut test_ee, MMI2, Mmi2Function::PDivBW as u8,
+			words([100, neg(-100), 7, 0x7fff]), halves([neg_h(-3), 9, 9, 9, 9, 9, 9, 9]),
+		);
+
+		assert_eq!(test_ee.read_lo_wide(), words([neg(-33), 33, neg(-2), neg(-10922)]));
+		assert_eq!(test_ee.read_hi_wide(), words([1, neg(-1), 1, 1]));
+	}
+
+	#[test]
+	fn basic_pexew() {
+		assert_eq!(run2(Mmi2Function::PExEW, 0, ramp_words(0)), words([2, 1, 0, 3]));
+	}
+
+	#[test]
+	fn basic_prot3w() {
+		assert_eq!(run2(Mmi2Function::PRot3W, 0, ramp_words(0)), words([1, 2, 0, 3]));
+	}
+
+	#[test]
+	fn basic_pmadduw() {
+		let mut test_ee = mul_ee(words([1, 0, 0, 0]), words([!0, 0, 5, 0]));
+		let out = run(&mut test_ee, MMI3, Mmi3Function::PMAddUW as u8, words([1, 0, 2, 0]), words([1, 0, 3, 0]));
+
+		assert_eq!(out, dwords([0x2_0000_0000, 11]));
+		assert_eq!(test_ee.read_lo_wide(), dwords([0, 11]));
+		assert_eq!(test_ee.read_hi_wide(), dwords([2, 0]));
+	}
+
+	#[test]
+	fn basic_psravw() {
+		let out = run3(Mmi3Function::PSRAVW, words([4, 0, 4, 0]), words([0x8000_0000, 0, 0x10, 0]));
+		assert_eq!(out, dwords([0xffff_ffff_f800_0000, 1]));
+	}
+
+	#[test]
+	fn basic_pmthi_pmtlo() {
+		let mut test_ee = EECore::new();
+
+		run(&mut test_ee, MMI3, Mmi3Function::PMTHi as u8, ramp_words(0x20), 0);
+		run(&mut test_ee, MMI3, Mmi3Function::PMTLo as u8, ramp_words(0x10), 0);
+
+		assert_eq!(test_ee.read_hi_wide(), ramp_words(0x20));
+		assert_eq!(test_ee.read_lo_wide(), ramp_words(0x10));
+		assert_eq!(test_ee.read_hi1(), 0x23_0000_0022);
+		assert_eq!(test_ee.read_lo1(), 0x13_0000_0012);
+	}
+
+	#[test]
+	fn basic_pinteh() {
+		assert_eq!(
+			run3(Mmi3Function::PIntEH, ramp_halves(0x10), ramp_halves(0x20)),
+			halves([0x20, 0x10, 0x22, 0x12, 0x24, 0x14, 0x26, 0x16]),
+		);
+	}
+
+	#[test]
+	fn basic_pmultuw() {
+		let mut test_ee = EECore::new();
+		let out = run(&mut test_ee, MMI3, Mmi3Function::PMultUW as u8, words([!0, 0, 2, 0]), words([2, 0, 3, 0]));
+
+		assert_eq!(out, dwords([0x1_ffff_fffe, 6]));
+		assert_eq!(test_ee.read_lo_wide(), dwords([neg_d(-2), 6]));
+		assert_eq!(test_ee.read_hi_wide(), dwords([1, 0]));
+	}
+
+	#[test]
+	fn basic_pdivuw() {
+		let mut test_ee = mul_ee(dwords([0xaa, 0xbb]), dwords([0xcc, 0xdd]));
+		run(&mut test_ee, MMI3, Mmi3Function::PDivUW as u8, words([0xffff_fffe, 0, 7, 0]), words([2, 0, 0, 0]));
+
+		assert_eq!(test_ee.read_lo_wide(), dwords([0x7fff_ffff, 0xdd]));
+		assert_eq!(test_ee.read_hi_wide(), dwords([0, 0xbb]));
+	}
+
+	#[test]
+	fn basic_pcpyud() {
+		assert_eq!(run3(Mmi3Function::PCpyUD, dwords([1, 2]), dwords([3, 4])), dwords([2, 4]));
+	}
+
+	#[test]
+	fn basic_por() {
+		assert_eq!(run3(Mmi3Function::POr, word_a(), word_b()), word_a() | word_b());
+	}
+
+	#[test]
+	fn basic_pnor() {
+		assert_eq!(run3(Mmi3Function::PNor, word_a(), word_b()), !(word_a() | word_b()));
+	}
+
+	#[test]
+	fn basic_pexch() {
+		assert_eq!(run3(Mmi3Function::PExcH, 0, ramp_halves(0)), halves([0, 2, 1, 3, 4, 6, 5, 7]));
+	}
+
+	#[test]
+	fn basic_pcpyh() {
+		assert_eq!(run3(Mmi3Function::PCpyH, 0, ramp_halves(1)), halves([1, 1, 1, 1, 5, 5, 5, 5]));
+	}
+
+	#[test]
+	fn basic_pexcw() {
+		assert_eq!(run3(Mmi3Function::PExcW, 0, ramp_words(0)), words([0, 2, 1, 3]));
+	}
+
+	#[test]
+	fn basic_plzcw() {
+		let mut test_ee = EECore::new();
+		test_ee.write_register_wide(3, !0);
+
+		let out = run(&mut test_ee, MmiBaseFunction::PLZCW as u8, 0, dwords([0xffff_0000_0000_1000, 0]), 0);
+
+		// Only the lower doubleword is written.
+		assert_eq!(out, dwords([(15 << 32) | 18, !0]));
+	}
+
+	#[test]
+	fn basic_pmfhl_lw_uw() {
+		let mut test_ee = mul_ee(ramp_words(0x20), ramp_words(0x10));
+
+		let lw = run(&mut test_ee, MmiBaseFunction::PMFHL as u8, PMFHLFunction::LW as u8, 0, 0);
+		let uw = run(&mut test_ee, MmiBaseFunction::PMFHL as u8, PMFHLFunction::UW as u8, 0, 0);
+
+		assert_eq!(lw, words([0x10, 0x20, 0x12, 0x22]));
+		assert_eq!(uw, words([0x11, 0x21, 0x13, 0x23]));
+	}
+
+	#[test]
+	fn basic_pmfhl_slw() {
+		let mut test_ee = mul_ee(words([0, 9, !0, 9]), words([0x8000_0000, 9, 5, 9]));
+		let out = run(&mut test_ee, MmiBaseFunction::PMFHL as u8, PMFHLFunction::SLW as u8, 0, 0);
+
+		assert_eq!(out, dwords([0x7fff_ffff, neg_d(i64::from(i32::MIN))]));
+
+		let mut test_ee = mul_ee(words([!0, 9, 0, 9]), words([neg(-5), 9, 5, 9]));
+		let out = run(&mut test_ee, MmiBaseFunction::PMFHL as u8, PMFHLFunction::SLW as u8, 0, 0);
+
+		assert_eq!(out, dwords([neg_d(-5), 5]));
+	}
+
+	#[test]
+	fn basic_pmfhl_lh() {
+		let mut test_ee = mul_ee(ramp_halves(0x20), ramp_halves(0x10));
+		let out = run(&mut test_ee, MmiBaseFunction::PMFHL as u8, PMFHLFunction::LH as u8, 0, 0);
+
+		assert_eq!(out, halves([0x10, 0x12, 0x20, 0x22, 0x14, 0x16, 0x24, 0x26]));
+	}
+
+	#[test]
+	fn basic_pmfhl_sh() {
+		let mut test_ee = mul_ee(
+			words([neg(-1), 2, 3, 0x8000_0000]),
+			words([1, 0x1_0000, neg(-65536), 0x7fff]),
+		);
+		let out = run(&mut test_ee, MmiBaseFunction::PMFHL as u8, PMFHLFunction::SH as u8, 0, 0);
+
+		assert_eq!(out, halves([1, 0x7fff, neg_h(-1), 2, 0x8000, 0x7fff, 3, 0x8000]));
+	}
+
+	#[test]
+	fn basic_pmthl_lw() {
+		let mut test_ee = mul_ee(ramp_words(0xb0), ramp_words(0xa0));
+		run(&mut test_ee, MmiBaseFunction::PMTHL as u8, PMTHL_LW, words([1, 2, 3, 4]), 0);
+
+		assert_eq!(test_ee.read_lo_wide(), words([1, 0xa1, 3, 0xa3]));
+		assert_eq!(test_ee.read_hi_wide(), words([2, 0xb1, 4, 0xb3]));
+	}
+
+	#[test]
+	fn basic_psllh_psrlh_psrah() {
+		let input = halves([0x1234, 0x8001, 0, 0, 0, 0, 0, 0]);
+
+		assert_eq!(run_base(MmiBaseFunction::PSLLH, 4, 0, input), halves([0x2340, 0x0010, 0, 0, 0, 0, 0, 0]));
+		assert_eq!(run_base(MmiBaseFunction::PSRLH, 4, 0, input), halves([0x0123, 0x0800, 0, 0, 0, 0, 0, 0]));
+		assert_eq!(run_base(MmiBaseFunction::PSRAH, 4, 0, input), halves([0x0123, 0xf800, 0, 0, 0, 0, 0, 0]));
+
+		// Only the lowest 4 bits of `sa` are used.
+		assert_eq!(run_base(MmiBaseFunction::PSLLH, 4 + 16, 0, input), halves([0x2340, 0x0010, 0, 0, 0, 0, 0, 0]));
+	}
+
+	#[test]
+	fn basic_psllw_psrlw_psraw() {
+		let input = words([0x1234_5678, 0x8000_0001, 0, 0]);
+
+		assert_eq!(run_base(MmiBaseFunction::PSLLW, 8, 0, input), words([0x3456_7800, 0x0000_0100, 0, 0]));
+		assert_eq!(run_base(MmiBaseFunction::PSRLW, 8, 0, input), words([0x0012_3456, 0x0080_0000, 0, 0]));
+		assert_eq!(run_base(MmiBaseFunction::PSRAW, 8, 0, input), words([0x0012_3456, 0xff80_0000, 0, 0]));
+	}
+}
@@ -2,17 +2,22 @@ mod arithmetic;
 mod branch;
 mod cop0;
 mod cop1;
+mod cop2;
 mod load;
+mod mmi;
 mod store;
+mod trap;
 
 use crate::{
 	core::{
 		constants::{requirements as req, timings::*},
+		exceptions::L1Exception,
 		pipeline::*,
 		EECore,
 	},
 	isa::mips::{
-		ee::{CacheFunction, Cop0Function, Cop1Function},
+		self,
+		ee::{CacheFunction, Cop0Function, Cop1Function, Cop2Function, MmiFunction},
 		Capability as Cap,
 		Function as MipsFunction,
 		Instruction,
@@ -29,23 +34,54 @@ rs2_macro::mips_ops!([
 			(ADDU, arithmetic::addu, MipsFunction::AddU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(AND, arithmetic::and, MipsFunction::And, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(BREAK, branch::break_i, MipsFunction::Break, INTEGER_BRANCH_JUMP_DELAY, req::ALU, Cap::no_req),
+			(DADD, arithmetic::dadd, MipsFunction::DAdd, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(DADDU, arithmetic::daddu, MipsFunction::DAddU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(DIV, arithmetic::div, MipsFunction::Div, INTEGER_DIV_DELAY, req::MAC0, Cap::mul_div),
 			(DIVU, arithmetic::divu, MipsFunction::DivU, INTEGER_DIV_DELAY, req::MAC0, Cap::mul_div),
+			(DSLL, arithmetic::dsll, MipsFunction::DSLL, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_t),
+			(DSLL32, arithmetic::dsll32, MipsFunction::DSLL32, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_t),
+			(DSLLV, arithmetic::dsllv, MipsFunction::DSLLV, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_ts),
+			(DSRA, arithmetic::dsra, MipsFunction::DSRA, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_t),
+			(DSRA32, arithmetic::dsra32, MipsFunction::DSRA32, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_t),
+			(DSRAV, arithmetic::dsrav, MipsFunction::DSRAV, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_ts),
+			(DSRL, arithmetic::dsrl, MipsFunction::DSRL, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_t),
+			(DSRL32, arithmetic::dsrl32, MipsFunction::DSRL32, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_t),
+			(DSRLV, arithmetic::dsrlv, MipsFunction::DSRLV, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_ts),
+			(DSUB, arithmetic::dsub, MipsFunction::DSub, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
+			(DSUBU, arithmetic::dsubu, MipsFunction::DSubU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(JALR, branch::jalr, MipsFunction::JaLR, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump_link_reg),
 			(JR, branch::jr, MipsFunction::JR, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump_reg),
-			(MFHI, load::mfhi, MipsFunction::MFHi, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC0, Cap::write_d),
-			(MFLO, load::mflo, MipsFunction::MFLo, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC0, Cap::write_d),
+			(MFHI, load::mfhi, MipsFunction::MFHi, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC0, Cap::write_d_read_hi),
+			(MFLO, load::mflo, MipsFunction::MFLo, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC0, Cap::write_d_read_lo),
+			(MFSA, load::mfsa, MipsFunction::MFSA, INTEGER_SUM_LOGIC_DELAY, req::SA, Cap::write_d_read_sa),
 			(MOVN, arithmetic::movn, MipsFunction::MovN, INTEGER_CONDITIONAL_MOVE_DELAY, req::ALU, Cap::write_d_read_ts),
+			(MOVZ, arithmetic::movz, MipsFunction::MovZ, INTEGER_CONDITIONAL_MOVE_DELAY, req::ALU, Cap::write_d_read_ts),
+			(MTHI, store::mthi, MipsFunction::MTHi, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC0, Cap::write_hi_read_s),
+			(MTLO, store::mtlo, MipsFunction::MTLo, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC0, Cap::write_lo_read_s),
+			(MTSA, store::mtsa, MipsFunction::MTSA, INTEGER_SUM_LOGIC_DELAY, req::SA, Cap::write_sa_read_s),
 			(MULT, arithmetic::mult, MipsFunction::Mult, INTEGER_MULT_DELAY, req::MAC0, Cap::mul_div),
+			(MULTU, arithmetic::multu, MipsFunction::MultU, INTEGER_MULT_DELAY, req::MAC0, Cap::mul_div),
+			(NOR, arithmetic::nor, MipsFunction::Nor, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(OR, arithmetic::or, MipsFunction::Or, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(SLL, arithmetic::sll, MipsFunction::SLL, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_t),
+			(SLLV, arithmetic::sllv, MipsFunction::SLLV, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_ts),
 			(SLT, arithmetic::slt, MipsFunction::SLT, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(SLTU, arithmetic::sltu, MipsFunction::SLTU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(SRA, arithmetic::sra, MipsFunction::SRA, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_t),
+			(SRAV, arithmetic::srav, MipsFunction::SRAV, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_ts),
 			(SRL, arithmetic::srl, MipsFunction::SRL, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_t),
+			(SRLV, arithmetic::srlv, MipsFunction::SRLV, INTEGER_SHIFT_LUI_DELAY, req::ALU, Cap::write_d_read_ts),
+			(SUB, arithmetic::sub, MipsFunction::Sub, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(SUBU, arithmetic::subu, MipsFunction::SubU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 			(SYNC, nop, MipsFunction::Sync, INTEGER_SHIFT_LUI_DELAY, req::SYNC, Cap::no_req),
+			(SYSCALL, branch::syscall, MipsFunction::Syscall, INTEGER_BRANCH_JUMP_DELAY, req::ALU, Cap::no_req),
+			(TEQ, trap::teq, MipsFunction::TEq, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_ts),
+			(TGE, trap::tge, MipsFunction::TGE, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_ts),
+			(TGEU, trap::tgeu, MipsFunction::TGEU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_ts),
+			(TLT, trap::tlt, MipsFunction::TLT, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_ts),
+			(TLTU, trap::tltu, MipsFunction::TLTU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_ts),
+			(TNE, trap::tne, MipsFunction::TNE, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_ts),
+			(XOR, arithmetic::xor, MipsFunction::Xor, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_d_read_ts),
 		]),
 		(MipsOpcode::Cache, "CACHE", CacheFunction::decode, [
 			(BFH, nop, CacheFunction::BFH, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::no_req),
@@ -70,19 +106,192 @@ rs2_macro::mips_ops!([
 			(IXSTG, nop, CacheFunction::IXSTG, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::no_req),
 		]),
 		(MipsOpcode::Cop0, "COP0", Cop0Function::decode, [
+			(BC0F, cop0::bc0f, Cop0Function::BC0F, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(BC0FL, cop0::bc0fl, Cop0Function::BC0FL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(BC0T, cop0::bc0t, Cop0Function::BC0T, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(BC0TL, cop0::bc0tl, Cop0Function::BC0TL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(DI, cop0::di, Cop0Function::DI, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::no_req),
+			(EI, cop0::ei, Cop0Function::EI, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::no_req),
+			(ERET, cop0::eret, Cop0Function::Eret, INTEGER_BRANCH_JUMP_DELAY, req::ERET, Cap::jump),
 			(MFBPC, cop0::mfc0, Cop0Function::MFBPC, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::write_t),
 			(MFC0, cop0::mfc0, Cop0Function::MFC0, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::write_t_read_d),
 			(MTBPC, cop0::mtc0, Cop0Function::MTBPC, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::read_t),
 			(MTC0, cop0::mtc0, Cop0Function::MTC0, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::read_td),
+			(TLBP, cop0::tlbp, Cop0Function::TlbP, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::no_req),
+			(TLBR, cop0::tlbr, Cop0Function::TlbR, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::no_req),
 			(TLBWI, cop0::tlbwi, Cop0Function::TlbWI, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::no_req),
 			(TLBWR, cop0::tlbwr, Cop0Function::TlbWR, INTEGER_LOAD_STORE_DELAY, req::COP0, Cap::no_req),
 		]),
 		(MipsOpcode::Cop1, "COP1", Cop1Function::decode, [
-			// N/A
+			(ABS_S, cop1::abs_s, Cop1Function::Abs, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(ADD_S, cop1::add_s, Cop1Function::Add, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(ADDA_S, cop1::adda_s, Cop1Function::AddA, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(BC1F, cop1::bc1f, Cop1Function::BC1F, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(BC1FL, cop1::bc1fl, Cop1Function::BC1FL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(BC1T, cop1::bc1t, Cop1Function::BC1T, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(BC1TL, cop1::bc1tl, Cop1Function::BC1TL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(C_EQ_S, cop1::c_eq_s, Cop1Function::CEq, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(C_F_S, cop1::c_f_s, Cop1Function::CF, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(C_LE_S, cop1::c_le_s, Cop1Function::CLe, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(C_LT_S, cop1::c_lt_s, Cop1Function::CLt, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(CFC1, cop1::cfc1, Cop1Function::CFC1, FLOAT_MFC1_DELAY, req::COP1_MOVE, Cap::write_t),
+			(CTC1, cop1::ctc1, Cop1Function::CTC1, FLOAT_MTC1_DELAY, req::COP1_MOVE, Cap::read_t),
+			(CVT_S_W, cop1::cvt_s_w, Cop1Function::CvtS, FLOAT_CVT_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(CVT_W_S, cop1::cvt_w_s, Cop1Function::CvtW, FLOAT_CVT_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(DIV_S, cop1::div_s, Cop1Function::Div, FLOAT_DIV_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(MADD_S, cop1::madd_s, Cop1Function::MAdd, FLOAT_MADD_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(MADDA_S, cop1::madda_s, Cop1Function::MAddA, FLOAT_MADD_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(MAX_S, cop1::max_s, Cop1Function::Max, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(MFC1, cop1::mfc1, Cop1Function::MFC1, FLOAT_MFC1_DELAY, req::COP1_MOVE, Cap::write_t),
+			(MIN_S, cop1::min_s, Cop1Function::Min, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(MOV_S, cop1::mov_s, Cop1Function::Mov, FLOAT_MOVE_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(MSUB_S, cop1::msub_s, Cop1Function::MSub, FLOAT_MADD_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(MSUBA_S, cop1::msuba_s, Cop1Function::MSubA, FLOAT_MADD_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(MTC1, cop1::mtc1, Cop1Function::MTC1, FLOAT_MTC1_DELAY, req::COP1_MOVE, Cap::read_t),
+			(MUL_S, cop1::mul_s, Cop1Function::Mul, FLOAT_MUL_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(MULA_S, cop1::mula_s, Cop1Function::MulA, FLOAT_MUL_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(NEG_S, cop1::neg_s, Cop1Function::Neg, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(RSQRT_S, cop1::rsqrt_s, Cop1Function::RSqrt, FLOAT_RSQRT_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(SQRT_S, cop1::sqrt_s, Cop1Function::Sqrt, FLOAT_SQRT_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(SUB_S, cop1::sub_s, Cop1Function::Sub, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+			(SUBA_S, cop1::suba_s, Cop1Function::SubA, FLOAT_ADD_NEG_COND_DELAY, req::COP1_OPERATE, Cap::no_req),
+		]),
+		(MipsOpcode::Cop2, "COP2", Cop2Function::decode, [
+			(BC2F, cop2::bc2f, Cop2Function::BC2F, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(BC2FL, cop2::bc2fl, Cop2Function::BC2FL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(BC2T, cop2::bc2t, Cop2Function::BC2T, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(BC2TL, cop2::bc2tl, Cop2Function::BC2TL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
+			(CFC2, cop2::cfc2, Cop2Function::CFC2, VECTOR_MOVE_DELAY, req::COP2_MOVE, Cap::write_t),
+			(CTC2, cop2::ctc2, Cop2Function::CTC2, VECTOR_MOVE_DELAY, req::COP2_MOVE, Cap::read_t),
+			(QMFC2, cop2::qmfc2, Cop2Function::QMFC2, VECTOR_MOVE_DELAY, req::COP2_MOVE, Cap::write_t),
+			(QMTC2, cop2::qmtc2, Cop2Function::QMTC2, VECTOR_MOVE_DELAY, req::COP2_MOVE, Cap::read_t),
+			(VCALLMS, cop2::vcallms, Cop2Function::VCallMs, VECTOR_OPERATE_DELAY, req::COP2_OPERATE, Cap::no_req),
+			(VCALLMSR, cop2::vcallmsr, Cop2Function::VCallMsR, VECTOR_OPERATE_DELAY, req::COP2_OPERATE, Cap::no_req),
+			(VLOWER, cop2::operate, Cop2Function::Lower, VECTOR_OPERATE_DELAY, req::COP2_OPERATE, Cap::no_req),
+			(VUPPER, cop2::operate, Cop2Function::Upper, VECTOR_OPERATE_DELAY, req::COP2_OPERATE, Cap::no_req),
+		]),
+		(MipsOpcode::Mmi, "MMI", MmiFunction::decode, [
+			(DIV1, arithmetic::div1, MmiFunction::Div1, INTEGER_DIV_DELAY, req::MAC1, Cap::mul_div1),
+			(DIVU1, arithmetic::divu1, MmiFunction::DivU1, INTEGER_DIV_DELAY, req::MAC1, Cap::mul_div1),
+			(MADD, arithmetic::madd, MmiFunction::MAdd, INTEGER_MADD_DELAY, req::MAC0, Cap::mul_div),
+			(MADD1, arithmetic::madd1, MmiFunction::MAdd1, INTEGER_MADD_DELAY, req::MAC1, Cap::mul_div1),
+			(MADDU, arithmetic::maddu, MmiFunction::MAddU, INTEGER_MADD_DELAY, req::MAC0, Cap::mul_div),
+			(MADDU1, arithmetic::maddu1, MmiFunction::MAddU1, INTEGER_MADD_DELAY, req::MAC1, Cap::mul_div1),
+			(MFHI1, load::mfhi1, MmiFunction::MFHi1, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC1, Cap::write_d_read_hi1),
+			(MFLO1, load::mflo1, MmiFunction::MFLo1, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC1, Cap::write_d_read_lo1),
+			(MTHI1, store::mthi1, MmiFunction::MTHi1, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC1, Cap::write_hi1_read_s),
+			(MTLO1, store::mtlo1, MmiFunction::MTLo1, INTEGER_HI_LO_TRANSFER_DELAY, req::MAC1, Cap::write_lo1_read_s),
+			(MULT1, arithmetic::mult1, MmiFunction::Mult1, INTEGER_MULT_DELAY, req::MAC1, Cap::mul_div1),
+			(MULTU1, arithmetic::multu1, MmiFunction::MultU1, INTEGER_MULT_DELAY, req::MAC1, Cap::mul_div1),
+			(PABSH, mmi::pabsh, MmiFunction::PAbsH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PABSW, mmi::pabsw, MmiFunction::PAbsW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PADDB, mmi::paddb, MmiFunction::PAddB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PADDH, mmi::paddh, MmiFunction::PAddH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PADDSB, mmi::paddsb, MmiFunction::PAddSB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PADDSH, mmi::paddsh, MmiFunction::PAddSH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PADDSW, mmi::paddsw, MmiFunction::PAddSW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PADDUB, mmi::paddub, MmiFunction::PAddUB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PADDUH, mmi::padduh, MmiFunction::PAddUH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PADDUW, mmi::padduw, MmiFunction::PAddUW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PADDW, mmi::paddw, MmiFunction::PAddW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PADSBH, mmi::padsbh, MmiFunction::PAdSBH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PAND, mmi::pand, MmiFunction::PAnd, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PCEQB, mmi::pceqb, MmiFunction::PCEqB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PCEQH, mmi::pceqh, MmiFunction::PCEqH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PCEQW, mmi::pceqw, MmiFunction::PCEqW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PCGTB, mmi::pcgtb, MmiFunction::PCGTB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PCGTH, mmi::pcgth, MmiFunction::PCGTH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PCGTW, mmi::pcgtw, MmiFunction::PCGTW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PCPYH, mmi::pcpyh, MmiFunction::PCpyH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PCPYLD, mmi::pcpyld, MmiFunction::PCpyLD, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PCPYUD, mmi::pcpyud, MmiFunction::PCpyUD, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PDIVBW, mmi::pdivbw, MmiFunction::PDivBW, INTEGER_DIV_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PDIVUW, mmi::pdivuw, MmiFunction::PDivUW, INTEGER_DIV_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PDIVW, mmi::pdivw, MmiFunction::PDivW, INTEGER_DIV_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PEXCH, mmi::pexch, MmiFunction::PExcH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PEXCW, mmi::pexcw, MmiFunction::PExcW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PEXEH, mmi::pexeh, MmiFunction::PExEH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PEXEW, mmi::pexew, MmiFunction::PExEW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PEXT5, mmi::pext5, MmiFunction::PExt5, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PEXTLB, mmi::pextlb, MmiFunction::PExtLB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PEXTLH, mmi::pextlh, MmiFunction::PExtLH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PEXTLW, mmi::pextlw, MmiFunction::PExtLW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PEXTUB, mmi::pextub, MmiFunction::PExtUB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PEXTUH, mmi::pextuh, MmiFunction::PExtUH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PEXTUW, mmi::pextuw, MmiFunction::PExtUW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PHMADH, mmi::phmadh, MmiFunction::PHMAdH, INTEGER_MADD_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PHMSBH, mmi::phmsbh, MmiFunction::PHMSbH, INTEGER_MADD_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PINTEH, mmi::pinteh, MmiFunction::PIntEH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PINTH, mmi::pinth, MmiFunction::PIntH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PLZCW, mmi::plzcw, MmiFunction::PLZCW, INTEGER_SUM_LOGIC_DELAY, req::LZC, Cap::write_d_read_s),
+			(PMADDH, mmi::pmaddh, MmiFunction::PMAddH, INTEGER_MADD_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PMADDUW, mmi::pmadduw, MmiFunction::PMAddUW, INTEGER_MADD_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PMADDW, mmi::pmaddw, MmiFunction::PMAddW, INTEGER_MADD_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PMAXH, mmi::pmaxh, MmiFunction::PMaxH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PMAXW, mmi::pmaxw, MmiFunction::PMaxW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PMFHI, mmi::pmfhi, MmiFunction::PMFHi, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_d_read_hi_lo),
+			(PMFHL_LH, mmi::pmfhl_lh, MmiFunction::PMFHLLH, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_d_read_hi_lo),
+			(PMFHL_LW, mmi::pmfhl_lw, MmiFunction::PMFHLLW, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_d_read_hi_lo),
+			(PMFHL_SH, mmi::pmfhl_sh, MmiFunction::PMFHLSH, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_d_read_hi_lo),
+			(PMFHL_SLW, mmi::pmfhl_slw, MmiFunction::PMFHLSLW, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_d_read_hi_lo),
+			(PMFHL_UW, mmi::pmfhl_uw, MmiFunction::PMFHLUW, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_d_read_hi_lo),
+			(PMFLO, mmi::pmflo, MmiFunction::PMFLo, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_d_read_hi_lo),
+			(PMINH, mmi::pminh, MmiFunction::PMinH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PMINW, mmi::pminw, MmiFunction::PMinW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PMSUBH, mmi::pmsubh, MmiFunction::PMSubH, INTEGER_MADD_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PMSUBW, mmi::pmsubw, MmiFunction::PMSubW, INTEGER_MADD_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PMTHI, mmi::pmthi, MmiFunction::PMTHi, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_hi_lo_read_s),
+			(PMTHL_LW, mmi::pmthl_lw, MmiFunction::PMTHLLW, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_hi_lo_read_s),
+			(PMTLO, mmi::pmtlo, MmiFunction::PMTLo, INTEGER_HI_LO_TRANSFER_DELAY, req::WIDE_OPERATE, Cap::write_hi_lo_read_s),
+			(PMULTH, mmi::pmulth, MmiFunction::PMultH, INTEGER_MULT_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PMULTUW, mmi::pmultuw, MmiFunction::PMultUW, INTEGER_MULT_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PMULTW, mmi::pmultw, MmiFunction::PMultW, INTEGER_MULT_DELAY, req::WIDE_OPERATE, Cap::wide_mul_div),
+			(PNOR, mmi::pnor, MmiFunction::PNor, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(POR, mmi::por, MmiFunction::POr, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PPAC5, mmi::ppac5, MmiFunction::PPac5, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PPACB, mmi::ppacb, MmiFunction::PPacB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PPACH, mmi::ppach, MmiFunction::PPacH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PPACW, mmi::ppacw, MmiFunction::PPacW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PREVH, mmi::prevh, MmiFunction::PRevH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PROT3W, mmi::prot3w, MmiFunction::PRot3W, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PSLLH, mmi::psllh, MmiFunction::PSLLH, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PSLLVW, mmi::psllvw, MmiFunction::PSLLVW, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSLLW, mmi::psllw, MmiFunction::PSLLW, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PSRAH, mmi::psrah, MmiFunction::PSRAH, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PSRAVW, mmi::psravw, MmiFunction::PSRAVW, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSRAW, mmi::psraw, MmiFunction::PSRAW, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PSRLH, mmi::psrlh, MmiFunction::PSRLH, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PSRLVW, mmi::psrlvw, MmiFunction::PSRLVW, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSRLW, mmi::psrlw, MmiFunction::PSRLW, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::write_d_read_t),
+			(PSUBB, mmi::psubb, MmiFunction::PSubB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSUBH, mmi::psubh, MmiFunction::PSubH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSUBSB, mmi::psubsb, MmiFunction::PSubSB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSUBSH, mmi::psubsh, MmiFunction::PSubSH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSUBSW, mmi::psubsw, MmiFunction::PSubSW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSUBUB, mmi::psubub, MmiFunction::PSubUB, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSUBUH, mmi::psubuh, MmiFunction::PSubUH, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSUBUW, mmi::psubuw, MmiFunction::PSubUW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PSUBW, mmi::psubw, MmiFunction::PSubW, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(PXOR, mmi::pxor, MmiFunction::PXor, INTEGER_SUM_LOGIC_DELAY, req::WIDE_OPERATE, Cap::write_d_read_ts),
+			(QFSRV, mmi::qfsrv, MmiFunction::QFSRV, INTEGER_SHIFT_LUI_DELAY, req::WIDE_OPERATE, Cap::funnel_shift),
 		]),
 		(MipsOpcode::RegImm, "REGIMM", RegImmFunction::decode, [
 			(BGEZ, branch::bgez, RegImmFunction::BGEZ, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_read_s),
+			(BGEZAL, branch::bgezal, RegImmFunction::BGEZAL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_link_read_s),
+			(BGEZALL, branch::bgezall, RegImmFunction::BGEZALL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_link_read_s),
+			(BGEZL, branch::bgezl, RegImmFunction::BGEZL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_read_s),
 			(BLTZ, branch::bltz, RegImmFunction::BLTZ, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_read_s),
+			(BLTZAL, branch::bltzal, RegImmFunction::BLTZAL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_link_read_s),
+			(BLTZALL, branch::bltzall, RegImmFunction::BLTZALL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_link_read_s),
+			(BLTZL, branch::bltzl, RegImmFunction::BLTZL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_read_s),
+			(MTSAB, store::mtsab, RegImmFunction::MTSAB, INTEGER_SUM_LOGIC_DELAY, req::SA, Cap::write_sa_read_s),
+			(MTSAH, store::mtsah, RegImmFunction::MTSAH, INTEGER_SUM_LOGIC_DELAY, req::SA, Cap::write_sa_read_s),
+			(TEQI, trap::teqi, RegImmFunction::TEqI, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_s),
+			(TGEI, trap::tgei, RegImmFunction::TGEI, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_s),
+			(TGEIU, trap::tgeiu, RegImmFunction::TGEIU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_s),
+			(TLTI, trap::tlti, RegImmFunction::TLTI, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_s),
+			(TLTIU, trap::tltiu, RegImmFunction::TLTIU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_s),
+			(TNEI, trap::tnei, RegImmFunction::TNEI, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::read_s),
 		]),
 	],
 	[
@@ -92,24 +301,46 @@ rs2_macro::mips_ops!([
 		(BEQ, branch::beq, MipsOpcode::BEq, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_compare),
 		(BEQL, branch::beql, MipsOpcode::BEqL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_compare),
 		(BGTZ, branch::bgtz, MipsOpcode::BGTZ, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_compare),
+		(BGTZL, branch::bgtzl, MipsOpcode::BGTZL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_compare),
 		(BLEZ, branch::blez, MipsOpcode::BLEZ, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_compare),
+		(BLEZL, branch::blezl, MipsOpcode::BLEZL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_compare),
 		(BNE, branch::bne, MipsOpcode::BNE, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_compare),
 		(BNEL, branch::bnel, MipsOpcode::BNEL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::branch_compare),
+		(DADDI, arithmetic::daddi, MipsOpcode::DAddI, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_t_read_s),
+		(DADDIU, arithmetic::daddiu, MipsOpcode::DAddIU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_t_read_s),
 		(J, branch::j, MipsOpcode::J, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump),
 		(JAL, branch::jal, MipsOpcode::JaL, INTEGER_BRANCH_JUMP_DELAY, req::BRANCH, Cap::jump_link),
 		(LB, load::lb, MipsOpcode::LB, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
 		(LBU, load::lbu, MipsOpcode::LBU, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
 		(LD, load::ld, MipsOpcode::LD, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
+		(LDL, load::ldl, MipsOpcode::LDL, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
+		(LDR, load::ldr, MipsOpcode::LDR, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
+		(LH, load::lh, MipsOpcode::LH, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
 		(LHU, load::lhu, MipsOpcode::LHU, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
+		(LQ, load::lq, MipsOpcode::LQ, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
+		(LQC2, cop2::lqc2, MipsOpcode::LQC2, VECTOR_MOVE_DELAY, req::COP2_MOVE, Cap::read_s),
 		(LUI, load::lui, MipsOpcode::LUI, INTEGER_SHIFT_LUI_DELAY, req::LS, Cap::write_t),
 		(LW, load::lw, MipsOpcode::LW, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
+		(LWC1, cop1::lwc1, MipsOpcode::LWC1, FLOAT_LWC1_DELAY, req::COP1_MOVE, Cap::read_s),
+		(LWL, load::lwl, MipsOpcode::LWL, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
+		(LWR, load::lwr, MipsOpcode::LWR, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
+		(LWU, load::lwu, MipsOpcode::LWU, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::write_t_read_s),
 		(ORI, arithmetic::ori, MipsOpcode::OrI, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_t_read_s),
+		(PREF, nop, MipsOpcode::Pref, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_s),
 		(SB, store::sb, MipsOpcode::SB, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_ts),
 		(SD, store::sd, MipsOpcode::SD, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_ts),
+		(SDL, store::sdl, MipsOpcode::SDL, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_ts),
+		(SDR, store::sdr, MipsOpcode::SDR, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_ts),
+		(SH, store::sh, MipsOpcode::SH, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_ts),
 		(SLTI, arithmetic::slti, MipsOpcode::SLTI, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_t_read_s),
 		(SLTIU, arithmetic::sltiu, MipsOpcode::SLTIU, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_t_read_s),
+		(SQ, store::sq, MipsOpcode::SQ, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_ts),
+		(SQC2, cop2::sqc2, MipsOpcode::SQC2, VECTOR_MOVE_DELAY, req::COP2_MOVE, Cap::read_s),
 		(SW, store::sw, MipsOpcode::SW, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_ts),
 		(SWC1, cop1::swc1, MipsOpcode::SWC1, FLOAT_MFC1_DELAY, req::COP1_MOVE, Cap::read_s),
+		(SWL, store::swl, MipsOpcode::SWL, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_ts),
+		(SWR, store::swr, MipsOpcode::SWR, INTEGER_LOAD_STORE_DELAY, req::LS, Cap::read_ts),
+		(XORI, arithmetic::xori, MipsOpcode::XorI, INTEGER_SUM_LOGIC_DELAY, req::ALU, Cap::write_t_read_s),
 	],
 ]);
 
@@ -117,3 +348,27 @@ pub fn nop(_cpu: &mut EECore, _data: &OpCode) {
 	// No Op.
 	trace!("NOP FIRED");
 }
+
+/// Action for any instruction which could not be decoded.
+///
+/// Raises a Reserved Instruction exception, or Coprocessor Unusable
+/// if the instruction targets a disabled coprocessor. In strict mode,
+/// the instruction is also logged and the core halted.
+pub fn reserved_instruction(cpu: &mut EECore, data: &OpCode) {
+	let exception = match mips::coprocessor_number(data.raw) {
+		Some(cop) if !cpu.coprocessor_usable(cop) => L1Exception::CoprocessorUnusable(cop),
+		_ => L1Exception::ReservedInstruction,
+	};
+
+	if cpu.strict {
+		error!(
+			"Reserved instruction {:08x} at PC {:08x} (guess: {}).",
+			data.raw,
+			cpu.pc_register,
+			mips::mnemonic_guess(data.raw),
+		);
+		cpu.halted = true;
+	}
+
+	cpu.throw_l1_exception(exception);
+}
@@ -1,4 +1,3 @@
-use byteorder::{ByteOrder, LittleEndian};
 use crate::{
 	core::{
 		exceptions::L1Exception,
@@ -8,16 +7,50 @@ use crate::{
 	isa::mips::Instruction,
 	utils::*,
 };
-use std::mem::size_of;
+
+pub fn mthi(cpu: &mut EECore, data: &OpCode) {
+	// GPR[rs] -> HI
+	cpu.write_hi(cpu.read_register(data.ri_get_source()));
+}
+
+pub fn mthi1(cpu: &mut EECore, data: &OpCode) {
+	// GPR[rs] -> HI1
+	cpu.write_hi1(cpu.read_register(data.ri_get_source()));
+}
+
+pub fn mtlo(cpu: &mut EECore, data: &OpCode) {
+	// GPR[rs] -> LO
+	cpu.write_lo(cpu.read_register(data.ri_get_source()));
+}
+
+pub fn mtlo1(cpu: &mut EECore, data: &OpCode) {
+	// GPR[rs] -> LO1
+	cpu.write_lo1(cpu.read_register(data.ri_get_source()));
+}
+
+pub fn mtsa(cpu: &mut EECore, data: &OpCode) {
+	// GPR[rs] -> SA, as previously read by MFSA.
+	cpu.sa_register = cpu.read_register(data.ri_get_source()) as u32 & 0x7f;
+}
+
+pub fn mtsab(cpu: &mut EECore, data: &OpCode) {
+	// SA <- (GPR[rs] ^ imm) as a byte offset, held as a shift in bits.
+	let bytes = (cpu.read_register(data.ri_get_source()) as u32 ^ u32::from(data.i_get_immediate())) & 0b1111;
+	cpu.sa_register = bytes * 8;
+}
+
+pub fn mtsah(cpu: &mut EECore, data: &OpCode) {
+	// SA <- (GPR[rs] ^ imm) as a halfword offset, held as a shift in bits.
+	let halves = (cpu.read_register(data.ri_get_source()) as u32 ^ u32::from(data.i_get_immediate())) & 0b111;
+	cpu.sa_register = halves * 16;
+}
 
 pub fn sb(cpu: &mut EECore, data: &OpCode) {
 	// mem[GPR[rs] + signed(imm)] <- (GPR[rt] as 32)
 	let to_store = cpu.read_register(data.ri_get_target()) as u8;
 	let v_addr = v_addr_with_offset(cpu, data);
 
-	if let Some(loc) = cpu.read_memory_mut(v_addr as u32, size_of::<u32>()) {
-		loc[0] = to_store;
-	}
+	cpu.store(v_addr, to_store);
 }
 
 pub fn sw(cpu: &mut EECore, data: &OpCode) {
@@ -31,9 +64,7 @@ pub fn sw(cpu: &mut EECore, data: &OpCode) {
 		return;
 	}
 
-	if let Some(loc) = cpu.read_memory_mut(v_addr as u32, size_of::<u32>()) {
-		LittleEndian::write_u32(loc, to_store);
-	}
+	cpu.store(v_addr, to_store);
 }
 
 pub fn sd(cpu: &mut EECore, data: &OpCode) {
@@ -47,9 +78,74 @@ pub fn sd(cpu: &mut EECore, data: &OpCode) {
 		return;
 	}
 
-	if let Some(loc) = cpu.read_memory_mut(v_addr as u32, size_of::<u64>()) {
-		LittleEndian::write_u64(loc, to_store);
+	cpu.store(v_addr, to_store);
+}
+
+pub fn sdl(cpu: &mut EECore, data: &OpCode) {
+	// Store the most significant bytes of GPR[rt] from v_addr
+	// down to the enclosing doubleword's boundary.
+	let to_store = cpu.read_register(data.ri_get_target());
+	let v_addr = v_addr_with_offset(cpu, data);
+	let shift = (0b111 - (v_addr & 0b111)) * 8;
+	let keep = !(u64::MAX >> shift);
+
+	cpu.store_merge(v_addr, |old: u64| (old & keep) | (to_store >> shift));
+}
+
+pub fn sdr(cpu: &mut EECore, data: &OpCode) {
+	// Store the least significant bytes of GPR[rt] from v_addr
+	// up to the enclosing doubleword's boundary.
+	let to_store = cpu.read_register(data.ri_get_target());
+	let v_addr = v_addr_with_offset(cpu, data);
+	let shift = (v_addr & 0b111) * 8;
+	let keep = (1u64 << shift) - 1;
+
+	cpu.store_merge(v_addr, |old: u64| (old & keep) | (to_store << shift));
+}
+
+pub fn sh(cpu: &mut EECore, data: &OpCode) {
+	// mem[GPR[rs] + signed(imm)] <- (GPR[rt] as 16)
+	let to_store = cpu.read_register(data.ri_get_target()) as u16;
+	let v_addr = v_addr_with_offset(cpu, data);
+
+	// FIXME: make size info part of address resolution.
+	if v_addr & 0b1 != 0 {
+		cpu.throw_l1_exception(L1Exception::AddressErrorStore(v_addr));
+		return;
 	}
+
+	cpu.store(v_addr, to_store);
+}
+
+pub fn sq(cpu: &mut EECore, data: &OpCode) {
+	// mem[GPR[rs] + signed(imm)] <- GPR[rt]
+	// The lowest 4 bits of the address are ignored: SQ never raises an address error.
+	let to_store = cpu.read_register_wide(data.ri_get_target());
+	let v_addr = v_addr_with_offset(cpu, data) & !0b1111;
+
+	cpu.store(v_addr, to_store);
+}
+
+pub fn swl(cpu: &mut EECore, data: &OpCode) {
+	// Store the most significant bytes of GPR[rt] (as 32) from v_addr
+	// down to the enclosing word's boundary.
+	let to_store = cpu.read_register(data.ri_get_target()) as u32;
+	let v_addr = v_addr_with_offset(cpu, data);
+	let shift = (0b11 - (v_addr & 0b11)) * 8;
+	let keep = !(u32::MAX >> shift);
+
+	cpu.store_merge(v_addr, |old: u32| (old & keep) | (to_store >> shift));
+}
+
+pub fn swr(cpu: &mut EECore, data: &OpCode) {
+	// Store the least significant bytes of GPR[rt] (as 32) from v_addr
+	// up to the enclosing word's boundary.
+	let to_store = cpu.read_register(data.ri_get_target()) as u32;
+	let v_addr = v_addr_with_offset(cpu, data);
+	let shift = (v_addr & 0b11) * 8;
+	let keep = (1u32 << shift) - 1;
+
+	cpu.store_merge(v_addr, |old: u32| (old & keep) | (to_store << shift));
 }
 
 #[cfg(test)]
@@ -60,10 +156,10 @@ mod test {
 		LittleEndian,
 	};
 	use crate::{
-		core::ops,
+		core::{cop0::Register, ops},
 		isa::mips::{
 			self,
-			ee::{CacheFunction, Cop0Function, Cop1Function},
+			ee::{CacheFunction, Cop0Function, Cop1Function, MmiBaseFunction},
 			Function as MipsFunction,
 			Instruction,
 			Opcode as MipsOpcode,
@@ -228,4 +324,191 @@ mod test {
 		assert!(test_ee.in_exception());
 		assert_eq!(test_ee.read_memory(base_pointer, 8).map(|d| LittleEndian::read_u64(d)), Some(0));
 	}
+
+	#[test]
+	fn basic_sq() {
+		let stored_data: u128 = 0x0f1e_2d3c_4b5a_6978_1234_5678_9abc_def0;
+
+		let mut test_ee = EECore::default();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_register_wide(2, stored_data);
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_immediate(MipsOpcode::SQ, 1, 2, 0),
+		]));
+
+		assert_eq!(test_ee.read_memory(KSEG1_START, 16).map(LittleEndian::read_u128), Some(stored_data));
+	}
+
+	#[test]
+	fn sq_ignores_low_address_bits() {
+		let stored_data: u128 = 0x0f1e_2d3c_4b5a_6978_1234_5678_9abc_def0;
+		let base_pointer = KSEG1_START + 132;
+
+		let mut test_ee = EECore::default();
+
+		test_ee.write_register(1, base_pointer.z_ext());
+		test_ee.write_register_wide(2, stored_data);
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_immediate(MipsOpcode::SQ, 1, 2, 0),
+		]));
+
+		assert_eq!(test_ee.read_memory(KSEG1_START + 128, 16).map(LittleEndian::read_u128), Some(stored_data));
+	}
+
+	#[test]
+	fn basic_sh() {
+		let stored_data: u16 = 0xfade;
+
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_register(2, stored_data.s_ext());
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_immediate(MipsOpcode::SH, 1, 2, 2),
+		]));
+
+		assert_eq!(test_ee.read_memory(KSEG1_START + 2, 2).map(LittleEndian::read_u16), Some(stored_data));
+	}
+
+	#[test]
+	fn sh_2_byte_aligned() {
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_register(2, 0xfade);
+
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::SH, 1, 2, 1)));
+
+		assert!(test_ee.in_exception());
+		assert_eq!(test_ee.read_memory(KSEG1_START, 4).map(LittleEndian::read_u32), Some(0));
+	}
+
+	#[test]
+	fn swl_swr_unaligned_word() {
+		let mut test_ee = EECore::default();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_register(2, 0xdead_beef_1122_3344);
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_immediate(MipsOpcode::SWR, 1, 2, 1),
+			mips::build_op_immediate(MipsOpcode::SWL, 1, 2, 4),
+		]));
+
+		assert_eq!(test_ee.read_memory(KSEG1_START, 8).map(LittleEndian::read_u64), Some(0x11_2233_4400));
+	}
+
+	#[test]
+	fn swl_swr_merge_into_memory() {
+		let mut test_ee = EECore::new();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_register(2, 0x1122_3344);
+		test_ee.write_memory(KSEG1_START, &0xaabb_ccddu32.to_le_bytes());
+
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::SWL, 1, 2, 1)));
+		assert_eq!(test_ee.read_memory(KSEG1_START, 4).map(LittleEndian::read_u32), Some(0xaabb_1122));
+
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::SWR, 1, 2, 3)));
+		assert_eq!(test_ee.read_memory(KSEG1_START, 4).map(LittleEndian::read_u32), Some(0x44bb_1122));
+	}
+
+	#[test]
+	fn swl_to_unmapped_page_raises_store_refill() {
+		let mut test_ee = EECore::new();
+		let v_addr = 0x0123_4567;
+
+		test_ee.write_register(1, v_addr.z_ext());
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::SWL, 1, 2, 0)));
+
+		assert!(test_ee.in_exception());
+		assert_eq!(
+			(test_ee.read_cop0(Register::Cause as u8) >> 2) & 0b1_1111,
+			u32::from(u8::from(L1Exception::TlbStoreRefill(v_addr))),
+		);
+		assert_eq!(test_ee.read_cop0_direct(Register::BadVAddr as u8), v_addr);
+	}
+
+	#[test]
+	fn sdl_sdr_unaligned_doubleword() {
+		let mut test_ee = EECore::default();
+
+		test_ee.write_register(1, KSEG1_START.z_ext());
+		test_ee.write_register(2, 0x1122_3344_5566_7788);
+
+		install_and_run_program(&mut test_ee, instructions_to_bytes(&[
+			mips::build_op_immediate(MipsOpcode::SDR, 1, 2, 3),
+			mips::build_op_immediate(MipsOpcode::SDL, 1, 2, 10),
+		]));
+
+		assert_eq!(
+			test_ee.read_memory(KSEG1_START, 16).map(LittleEndian::read_u128),
+			Some(0x1122_3344_5566_7788 << 24),
+		);
+	}
+
+	#[test]
+	fn basic_mthi_mtlo() {
+		let mut test_ee = EECore::new();
+		test_ee.write_register(1, 0x1234_5678_abcd_ef90);
+		test_ee.write_register(2, 0x0fed_cba9_8765_4321);
+
+		test_ee.execute(ops::process_instruction(mips::build_op_register(MipsFunction::MTHi, 1, 0, 0, 0)));
+		test_ee.execute(ops::process_instruction(mips::build_op_register(MipsFunction::MTLo, 2, 0, 0, 0)));
+
+		assert_eq!(test_ee.read_hi(), 0x1234_5678_abcd_ef90);
+		assert_eq!(test_ee.read_lo(), 0x0fed_cba9_8765_4321);
+	}
+
+	#[test]
+	fn basic_mthi1_mtlo1() {
+		let mut test_ee = EECore::new();
+		test_ee.write_register(1, 0x1234_5678_abcd_ef90);
+		test_ee.write_register(2, 0x0fed_cba9_8765_4321);
+
+		test_ee.execute(ops::process_instruction(mips::build_op_register_custom(MipsOpcode::Mmi, MmiBaseFunction::MTHI1 as u8, 1, 0, 0, 0)));
+		test_ee.execute(ops::process_instruction(mips::build_op_register_custom(MipsOpcode::Mmi, MmiBaseFunction::MTLO1 as u8, 2, 0, 0, 0)));
+
+		assert_eq!(test_ee.read_hi1(), 0x1234_5678_abcd_ef90);
+		assert_eq!(test_ee.read_lo1(), 0x0fed_cba9_8765_4321);
+		assert_eq!(test_ee.read_hi(), 0);
+		assert_eq!(test_ee.read_lo(), 0);
+	}
+
+	#[test]
+	fn basic_mtsab_mtsah() {
+		let mut test_ee = EECore::new();
+		test_ee.write_register(1, 0x13);
+
+		// (0x13 ^ 0x1) & 0xf = 2 bytes.
+		let mtsab = mips::build_op_immediate(MipsOpcode::RegImm, 1, RegImmFunction::MTSAB as u8, 1);
+		test_ee.execute(ops::process_instruction(mtsab));
+		assert_eq!(test_ee.sa_register, 2 * 8);
+
+		// (0x13 ^ 0x1) & 0x7 = 2 halfwords.
+		let mtsah = mips::build_op_immediate(MipsOpcode::RegImm, 1, RegImmFunction::MTSAH as u8, 1);
+		test_ee.execute(ops::process_instruction(mtsah));
+		assert_eq!(test_ee.sa_register, 2 * 16);
+	}
+
+	#[test]
+	fn mfsa_mtsa_round_trip() {
+		let mut test_ee = EECore::new();
+		test_ee.write_register(1, 0x5);
+
+		// Save SA as set by MTSAB, clobber it, then restore it.
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::RegImm, 1, RegImmFunction::MTSAB as u8, 0)));
+		test_ee.execute(ops::process_instruction(mips::build_op_register(MipsFunction::MFSA, 0, 0, 2, 0)));
+		assert_eq!(test_ee.read_register(2), 5 * 8);
+
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::RegImm, 0, RegImmFunction::MTSAH as u8, 3)));
+		assert_eq!(test_ee.sa_register, 3 * 16);
+
+		test_ee.execute(ops::process_instruction(mips::build_op_register(MipsFunction::MTSA, 2, 0, 0, 0)));
+		assert_eq!(test_ee.sa_register, 5 * 8);
+	}
 }
\ No newline at end of file
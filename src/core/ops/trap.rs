@@ -0,0 +1,172 @@
+use crate::{
+	core::{
+		exceptions::L1Exception,
+		pipeline::*,
+		EECore,
+	},
+	isa::mips::Instruction,
+	utils::*,
+};
+
+#[inline]
+fn trap_if(cpu: &mut EECore, cond: bool) {
+	if cond {
+		cpu.throw_l1_exception(L1Exception::Trap);
+	}
+}
+
+#[inline]
+fn operands(cpu: &EECore, data: &OpCode) -> (u64, u64) {
+	(
+		cpu.read_register(data.ri_get_source()),
+		cpu.read_register(data.ri_get_target()),
+	)
+}
+
+#[inline]
+fn operands_imm(cpu: &EECore, data: &OpCode) -> (u64, u64) {
+	(
+		cpu.read_register(data.ri_get_source()),
+		data.i_get_immediate_signed().s_ext(),
+	)
+}
+
+pub fn teq(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands(cpu, data);
+	trap_if(cpu, lhs == rhs);
+}
+
+pub fn teqi(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands_imm(cpu, data);
+	trap_if(cpu, lhs == rhs);
+}
+
+pub fn tge(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands(cpu, data);
+	trap_if(cpu, lhs as i64 >= rhs as i64);
+}
+
+pub fn tgei(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands_imm(cpu, data);
+	trap_if(cpu, lhs as i64 >= rhs as i64);
+}
+
+pub fn tgeiu(cpu: &mut EECore, data: &OpCode) {
+	// NOTE: the immediate is sign-extended, then compared as unsigned.
+	let (lhs, rhs) = operands_imm(cpu, data);
+	trap_if(cpu, lhs >= rhs);
+}
+
+pub fn tgeu(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands(cpu, data);
+	trap_if(cpu, lhs >= rhs);
+}
+
+pub fn tlt(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands(cpu, data);
+	trap_if(cpu, (lhs as i64) < rhs as i64);
+}
+
+pub fn tlti(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands_imm(cpu, data);
+	trap_if(cpu, (lhs as i64) < rhs as i64);
+}
+
+pub fn tltiu(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands_imm(cpu, data);
+	trap_if(cpu, lhs < rhs);
+}
+
+pub fn tltu(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands(cpu, data);
+	trap_if(cpu, lhs < rhs);
+}
+
+pub fn tne(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands(cpu, data);
+	trap_if(cpu, lhs != rhs);
+}
+
+pub fn tnei(cpu: &mut EECore, data: &OpCode) {
+	let (lhs, rhs) = operands_imm(cpu, data);
+	trap_if(cpu, lhs != rhs);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::ops,
+		isa::mips::{
+			self,
+			Function as MipsFunction,
+			Opcode as MipsOpcode,
+			RegImmFunction,
+		},
+	};
+
+	fn traps(instruction: u32, rs: i64, rt: i64) -> bool {
+		let mut test_ee = EECore::new();
+		test_ee.write_register(1, rs as u64);
+		test_ee.write_register(2, rt as u64);
+
+		test_ee.execute(ops::process_instruction(instruction));
+
+		test_ee.in_exception()
+	}
+
+	fn register_traps(function: MipsFunction, rs: i64, rt: i64) -> bool {
+		traps(mips::build_op_register(function, 1, 2, 0, 0), rs, rt)
+	}
+
+	fn immediate_traps(function: RegImmFunction, rs: i64, imm: i16) -> bool {
+		traps(mips::build_op_immediate(MipsOpcode::RegImm, 1, function as u8, imm as u16), rs, 0)
+	}
+
+	#[test]
+	fn register_trap_conditions() {
+		use MipsFunction::*;
+
+		assert!(register_traps(TEq, 5, 5));
+		assert!(!register_traps(TEq, 5, 6));
+
+		assert!(register_traps(TNE, 5, 6));
+		assert!(!register_traps(TNE, 5, 5));
+
+		assert!(register_traps(TGE, 5, -6));
+		assert!(!register_traps(TGE, -6, 5));
+
+		assert!(register_traps(TGEU, -6, 5));
+		assert!(!register_traps(TGEU, 5, -6));
+
+		assert!(register_traps(TLT, -6, 5));
+		assert!(!register_traps(TLT, 5, 5));
+
+		assert!(register_traps(TLTU, 5, -6));
+		assert!(!register_traps(TLTU, -6, 5));
+	}
+
+	#[test]
+	fn immediate_trap_conditions() {
+		use RegImmFunction::*;
+
+		assert!(immediate_traps(TEqI, -1, -1));
+		assert!(!immediate_traps(TEqI, 0xffff, -1));
+
+		assert!(immediate_traps(TNEI, 0xffff, -1));
+		assert!(!immediate_traps(TNEI, -1, -1));
+
+		assert!(immediate_traps(TGEI, 0, -1));
+		assert!(!immediate_traps(TGEI, -2, -1));
+
+		// Sign-extended immediate, compared as unsigned.
+		assert!(immediate_traps(TGEIU, -1, -1));
+		assert!(!immediate_traps(TGEIU, 0x7fff_ffff, -1));
+
+		assert!(immediate_traps(TLTI, -2, -1));
+		assert!(!immediate_traps(TLTI, 0, -1));
+
+		assert!(immediate_traps(TLTIU, 0x7fff_ffff, -1));
+		assert!(!immediate_traps(TLTIU, -1, -1));
+	}
+}
@@ -72,7 +72,8 @@ impl Default for OpCode {
 impl std::fmt::Debug for OpCode {
 	fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
 		fmt.debug_struct("OpCode")
-			.field("raw", &format!("{:032b}", self.raw))
+			.field("raw", &format!("{:08x}", self.raw))
+			.field("instruction", &super::ops::disassemble(self.raw, None))
 			.field("delay", &self.delay)
 			.field("action", &"<pointer>")
 			.field("requirements", &self.requirements)
@@ -1,5 +1,6 @@
 use crate::{
 	core::ops,
+	intc::*,
 	isa::mips::{
 		self,
 		Function as MipsFunction,
@@ -51,6 +52,37 @@ fn write_reg_0_nop() {
 	assert_eq!(0, test_ee.read_register(0));
 }
 
+#[test]
+fn wide_register_read_and_write() {
+	let mut test_ee = EECore::new();
+	let wide: u128 = 0x0f1e_2d3c_4b5a_6978_1234_5678_9abc_def0;
+
+	test_ee.write_register_wide(1, wide);
+	assert_eq!(test_ee.read_register_wide(1), wide);
+	assert_eq!(test_ee.read_register(1), wide as u64);
+
+	// 64-bit writes must leave the upper half untouched.
+	test_ee.write_register(1, 0x1111_2222_3333_4444);
+	assert_eq!(test_ee.read_register_wide(1) >> 64, wide >> 64);
+
+	// R0 is still hardwired to zero.
+	test_ee.write_register_wide(0, wide);
+	assert_eq!(test_ee.read_register_wide(0), 0);
+}
+
+#[test]
+fn hi1_lo1_alias_upper_hi_lo() {
+	let mut test_ee = EECore::new();
+
+	test_ee.write_hi(1);
+	test_ee.write_hi1(2);
+	test_ee.write_lo(3);
+	test_ee.write_lo1(4);
+
+	assert_eq!(test_ee.read_hi_wide(), (2 << 64) | 1);
+	assert_eq!(test_ee.read_lo_wide(), (4 << 64) | 3);
+}
+
 #[test]
 fn physical_address_mapped_by_kseg_0_1() {
 	let mut test_ee = EECore::default();
@@ -108,3 +140,426 @@ fn branch_delay_active_with_dual_issue_makes_two_reads() {
 	assert_eq!(format!("{:016x}", test_ee.read_register(untouched_register)), format!("{:016x}", test_ee.read_register(0)));
 	assert_eq!(format!("{:016x}", test_ee.read_register(touched_register)), format!("{:016x}", 0xabcd_0000u32.s_ext()));
 }
+
+fn l1_exception_code(test_ee: &EECore) -> u8 {
+	((test_ee.read_cop0_direct(cop0::Register::Cause as u8) >> 2) & 0b1_1111) as u8
+}
+
+#[test]
+fn unknown_opcode_raises_reserved_instruction() {
+	let mut test_ee = EECore::new();
+
+	// Opcode 0b01_1101 is unassigned on the EE Core.
+	test_ee.execute(ops::process_instruction(0b01_1101 << 26));
+
+	assert!(test_ee.in_exception());
+	assert!(!test_ee.halted);
+	assert_eq!(l1_exception_code(&test_ee), L1Exception::ReservedInstruction.to_exception_code());
+}
+
+#[test]
+fn unknown_function_raises_reserved_instruction() {
+	let mut test_ee = EECore::new();
+
+	// SPECIAL function 0b00_0001 is unassigned, as is COP0 family 0b0_0011.
+	let special = mips::build_op_register_custom(MipsOpcode::Special, 0b00_0001, 1, 2, 3, 0);
+	let cop0 = mips::build_op_register_custom(MipsOpcode::Cop0, 0, 0b0_0011, 2, 3, 0);
+
+	test_ee.execute(ops::process_instruction(special));
+	assert!(test_ee.in_exception());
+	assert_eq!(l1_exception_code(&test_ee), L1Exception::ReservedInstruction.to_exception_code());
+
+	let mut test_ee = EECore::new();
+	test_ee.execute(ops::process_instruction(cop0));
+	assert!(test_ee.in_exception());
+	assert_eq!(l1_exception_code(&test_ee), L1Exception::ReservedInstruction.to_exception_code());
+}
+
+#[test]
+fn unknown_coprocessor_instruction_when_disabled() {
+	let mut test_ee = EECore::new();
+
+	// COP1 is disabled at reset, so this is reported as unusable rather than reserved.
+	let cop1 = mips::build_op_register_custom(MipsOpcode::Cop1, 0b11_1111, 0b1_1111, 2, 3, 0);
+	test_ee.execute(ops::process_instruction(cop1));
+
+	let cause = test_ee.read_cop0_direct(cop0::Register::Cause as u8);
+
+	assert!(test_ee.in_exception());
+	assert_eq!(l1_exception_code(&test_ee), L1Exception::CoprocessorUnusable(1).to_exception_code());
+	assert_eq!((cause >> 28) & 0b11, 1);
+}
+
+#[test]
+fn strict_mode_halts_on_reserved_instruction() {
+	let mut test_ee = EECore::new();
+	test_ee.strict = true;
+
+	test_ee.execute(ops::process_instruction(0b01_1101 << 26));
+
+	assert!(test_ee.halted);
+	assert!(test_ee.in_exception());
+}
+
+#[test]
+fn reserved_instruction_mnemonic_guess() {
+	let mfsa = mips::build_op_register_custom(MipsOpcode::Special, 0b10_1000, 0, 0, 3, 0);
+	let bad_special = mips::build_op_register_custom(MipsOpcode::Special, 0b00_0001, 0, 0, 3, 0);
+	let lqc2 = 0b11_0110 << 26;
+
+	assert_eq!(mips::mnemonic_guess(mfsa), "MFSA");
+	assert_eq!(mips::mnemonic_guess(bad_special), "reserved SPECIAL function 000001");
+	assert_eq!(mips::mnemonic_guess(lqc2), "LQC2");
+	assert_eq!(mips::mnemonic_guess(0b01_1101 << 26), "reserved opcode 011101");
+
+	assert_eq!(mips::coprocessor_number(lqc2), Some(2));
+	assert_eq!(mips::coprocessor_number(mfsa), None);
+}
+
+fn interrupt_ready_ee() -> EECore {
+	let mut test_ee = EECore::new();
+	let status = Status::INTERRUPT_ENABLE | Status::ENABLE_IE | Status::INTERRUPT_MASK;
+	test_ee.write_cop0_direct(cop0::Register::Status as u8, status.bits());
+	test_ee.set_bios(instructions_to_bytes(&[NOP; 8]));
+
+	test_ee
+}
+
+fn raise_vblank(test_ee: &mut EECore) {
+	test_ee.intc.borrow_mut().raise(Interrupt::VBlankStart);
+	test_ee.store::<u32>(KSEG1_START + I_MASK_PHYSICAL, 1 << (Interrupt::VBlankStart as u32));
+}
+
+#[test]
+fn intc_interrupt_taken_at_instruction_boundary() {
+	let mut test_ee = interrupt_ready_ee();
+	raise_vblank(&mut test_ee);
+
+	test_ee.cycle();
+
+	let cause = Cause::from_bits_truncate(test_ee.read_cop0_direct(cop0::Register::Cause as u8));
+
+	assert!(test_ee.in_exception());
+	assert_eq!(l1_exception_code(&test_ee), 0);
+	assert!(cause.contains(Cause::PENDING_INTERRUPT_I0));
+	assert!(!cause.contains(Cause::PENDING_INTERRUPT_I1));
+	assert_eq!(test_ee.read_cop0_direct(cop0::Register::EPC as u8), BIOS_START);
+	assert_eq!(test_ee.pc_register, exceptions::vectors::INTERRUPT);
+}
+
+#[test]
+fn interrupt_masking_combinations() {
+	let blockers = [
+		(Status::INTERRUPT_ENABLE, Status::empty()),
+		(Status::ENABLE_IE, Status::empty()),
+		(Status::INTERRUPT_MASK_2, Status::empty()),
+		(Status::empty(), Status::EXCEPTION_LEVEL),
+		(Status::empty(), Status::ERROR_LEVEL),
+	];
+
+	for (remove, insert) in blockers.iter() {
+		let mut test_ee = interrupt_ready_ee();
+		raise_vblank(&mut test_ee);
+
+		let mut status = Status::from_bits_truncate(test_ee.read_cop0_direct(cop0::Register::Status as u8));
+		status.remove(*remove);
+		status.insert(*insert);
+		test_ee.write_cop0_direct(cop0::Register::Status as u8, status.bits());
+
+		assert_eq!(test_ee.pending_interrupt(), None, "remove {:?}, insert {:?}", remove, insert);
+	}
+
+	// Requests masked in the INTC never reach the core.
+	let mut test_ee = interrupt_ready_ee();
+	test_ee.intc.borrow_mut().raise(Interrupt::VBlankStart);
+	assert_eq!(test_ee.pending_interrupt(), None);
+
+	raise_vblank(&mut test_ee);
+	assert_eq!(test_ee.pending_interrupt(), Some(2));
+}
+
+#[test]
+fn dmac_line_drives_int1() {
+	let mut test_ee = interrupt_ready_ee();
+	test_ee.int1 = true;
+
+	assert_eq!(test_ee.pending_interrupt(), Some(3));
+	let cause = Cause::from_bits_truncate(test_ee.read_cop0_direct(cop0::Register::Cause as u8));
+	assert!(cause.contains(Cause::PENDING_INTERRUPT_I1));
+
+	// Int[0] takes priority when both lines are asserted.
+	raise_vblank(&mut test_ee);
+	assert_eq!(test_ee.pending_interrupt(), Some(2));
+
+	test_ee.int1 = false;
+	test_ee.store(KSEG1_START + I_STAT_PHYSICAL, !0u32);
+	assert_eq!(test_ee.pending_interrupt(), None);
+	assert_eq!(test_ee.read_cop0_direct(cop0::Register::Cause as u8), 0);
+}
+
+#[test]
+fn timer_interrupt_latched_until_compare_write() {
+	let mut test_ee = interrupt_ready_ee();
+	test_ee.write_cop0(cop0::Register::Compare as u8, 2);
+
+	test_ee.cycle();
+	assert!(!test_ee.in_exception());
+
+	test_ee.cycle();
+	assert!(test_ee.in_exception());
+	assert_eq!(l1_exception_code(&test_ee), 0);
+
+	let cause = Cause::from_bits_truncate(test_ee.read_cop0_direct(cop0::Register::Cause as u8));
+	assert!(cause.contains(Cause::PENDING_INTERRUPT_TIMER));
+
+	test_ee.write_cop0(cop0::Register::Compare as u8, 0);
+	let cause = Cause::from_bits_truncate(test_ee.read_cop0_direct(cop0::Register::Cause as u8));
+	assert!(!cause.contains(Cause::PENDING_INTERRUPT_TIMER));
+}
+
+#[test]
+fn interrupts_are_level_triggered() {
+	let eret = mips::build_op_register_custom(MipsOpcode::Cop0, mips::ee::C0Function::Eret as u8, mips::ee::C0, 0, 0, 0);
+
+	let mut test_ee = interrupt_ready_ee();
+	raise_vblank(&mut test_ee);
+
+	test_ee.cycle();
+	assert!(test_ee.in_exception());
+
+	// Returning without acknowledging I_STAT re-enters the handler.
+	test_ee.execute(ops::process_instruction(eret));
+	assert_eq!(test_ee.pc_register, BIOS_START);
+	test_ee.cycle();
+	assert!(test_ee.in_exception());
+	assert_eq!(test_ee.read_cop0_direct(cop0::Register::EPC as u8), BIOS_START);
+
+	// Once acknowledged, execution resumes.
+	test_ee.store::<u32>(KSEG1_START + I_STAT_PHYSICAL, 1 << (Interrupt::VBlankStart as u32));
+	test_ee.execute(ops::process_instruction(eret));
+	test_ee.cycle();
+	assert!(!test_ee.in_exception());
+	assert_eq!(test_ee.pc_register, BIOS_START + 4);
+}
+
+#[test]
+fn interrupt_deferred_past_delay_slot() {
+	let beq = mips::build_op_immediate(MipsOpcode::BEq, 0, 0, 4);
+
+	let mut test_ee = interrupt_ready_ee();
+	test_ee.set_bios(instructions_to_bytes(&[beq, NOP, NOP, NOP, NOP, NOP]));
+
+	test_ee.cycle();
+	raise_vblank(&mut test_ee);
+
+	// The delay slot must run alongside its branch.
+	test_ee.cycle();
+	assert!(!test_ee.in_exception());
+
+	test_ee.cycle();
+	assert!(test_ee.in_exception());
+	assert_eq!(test_ee.read_cop0_direct(cop0::Register::EPC as u8), BIOS_START + 20);
+}
+
+#[test]
+fn loads_and_stores_reach_mapped_devices() {
+	let mut test_ee = EECore::new();
+	test_ee.intc.borrow_mut().raise(Interrupt::Timer0);
+
+	test_ee.write_register(1, u64::from(KSEG1_START + INTC_PHYSICAL));
+	test_ee.write_register(2, 1 << (Interrupt::Timer0 as u32));
+
+	test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::SW, 1, 2, 0x10)));
+	test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::LW, 1, 3, 0x0)));
+
+	assert!(!test_ee.in_exception());
+	assert!(test_ee.intc.borrow().pending());
+	assert_eq!(test_ee.read_register(3), 1 << (Interrupt::Timer0 as u32));
+
+	// Byte writes only acknowledge the bits they cover.
+	test_ee.intc.borrow_mut().raise(Interrupt::Timer3);
+	test_ee.execute(ops::process_instruction(mips::build_op_immediate(MipsOpcode::SB, 1, 2, 0x1)));
+	assert_eq!(test_ee.intc.borrow().stat, (1 << (Interrupt::Timer0 as u32)) | (1 << (Interrupt::Timer3 as u32)));
+}
+
+#[test]
+fn unmapped_load_store_raises_bus_error() {
+	let unmapped = 0x1000_2000;
+
+	for op in [MipsOpcode::LW, MipsOpcode::SW] {
+		let mut test_ee = EECore::new();
+		test_ee.write_register(1, u64::from(KSEG1_START + unmapped));
+
+		test_ee.execute(ops::process_instruction(mips::build_op_immediate(op, 1, 2, 0)));
+
+		assert!(test_ee.in_exception());
+		assert_eq!(l1_exception_code(&test_ee), L1Exception::BusErrorLoadStore(0).to_exception_code());
+		assert_eq!(test_ee.read_cop0_direct(cop0::Register::BadPAddr as u8), unmapped);
+	}
+}
+
+#[test]
+fn unmapped_fetch_raises_bus_error() {
+	let mut test_ee = EECore::new();
+	test_ee.pc_register = KSEG1_START + INTC_PHYSICAL;
+
+	test_ee.cycle();
+
+	assert!(test_ee.in_exception());
+	assert_eq!(l1_exception_code(&test_ee), L1Exception::BusErrorFetch(0).to_exception_code());
+	assert_eq!(test_ee.read_cop0_direct(cop0::Register::BadPAddr as u8), INTC_PHYSICAL);
+	assert_eq!(test_ee.read_cop0_direct(cop0::Register::EPC as u8), KSEG1_START + INTC_PHYSICAL);
+}
+
+#[test]
+fn timer_compare_interrupts_core() {
+	use crate::timer::*;
+
+	let mut test_ee = interrupt_ready_ee();
+	let mode = TimerMode::COUNT_ENABLE | TimerMode::COMPARE_INTERRUPT;
+
+	test_ee.store::<u32>(KSEG1_START + TIMERS_PHYSICAL + COMP_OFFSET, 2);
+	test_ee.store(KSEG1_START + TIMERS_PHYSICAL + MODE_OFFSET, mode.bits());
+	test_ee.store::<u32>(KSEG1_START + I_MASK_PHYSICAL, 1 << (Interrupt::Timer0 as u32));
+
+	// One count per bus cycle: two EE cycles each.
+	for _ in 0..3 {
+		test_ee.cycle();
+	}
+	assert!(!test_ee.in_exception());
+	assert_eq!(test_ee.load::<u32>(KSEG1_START + TIMERS_PHYSICAL + COUNT_OFFSET), Some(1));
+
+	test_ee.cycle();
+	test_ee.cycle();
+	assert_eq!(test_ee.clock, 5);
+	assert!(test_ee.in_exception());
+	assert_eq!(test_ee.intc.borrow().stat, 1 << (Interrupt::Timer0 as u32));
+}
+
+#[test]
+fn gs_drives_timer_hblank() {
+	use crate::gs::{EE_CYCLES_PER_SCANLINE, HBLANK_EE_CYCLES};
+	use crate::timer::*;
+
+	let mut test_ee = EECore::new();
+	let spin = mips::build_op_immediate(MipsOpcode::BEq, 0, 0, 0xffff);
+	test_ee.set_bios(instructions_to_bytes(&[spin, NOP]));
+
+	// T0 counts HBLANKs, and T1 the bus clock outside of HBLANK.
+	let t1 = TIMERS_PHYSICAL + TIMER_STRIDE;
+	let hblanks = TimerMode::CLOCK_SELECT | TimerMode::COUNT_ENABLE;
+	let outside_hblank = TimerMode::GATE_ENABLE | TimerMode::COUNT_ENABLE;
+	test_ee.store(KSEG1_START + TIMERS_PHYSICAL + MODE_OFFSET, hblanks.bits());
+	test_ee.store(KSEG1_START + t1 + MODE_OFFSET, outside_hblank.bits());
+
+	// HBLANK rises as the first scanline begins, and again on the cycle the next one does.
+	while test_ee.clock < EE_CYCLES_PER_SCANLINE - 1 {
+		test_ee.cycle();
+	}
+	assert_eq!(test_ee.load::<u32>(KSEG1_START + TIMERS_PHYSICAL + COUNT_OFFSET), Some(1));
+	assert_eq!(
+		test_ee.load::<u32>(KSEG1_START + t1 + COUNT_OFFSET),
+		Some(((EE_CYCLES_PER_SCANLINE - HBLANK_EE_CYCLES) / 2) as u32),
+	);
+
+	test_ee.cycle();
+	assert_eq!(test_ee.load::<u32>(KSEG1_START + TIMERS_PHYSICAL + COUNT_OFFSET), Some(2));
+}
+
+#[test]
+fn dma_completion_interrupts_core() {
+	use crate::dmac::*;
+
+	let mut test_ee = interrupt_ready_ee();
+	let gif = ChannelId::Gif.base();
+
+	test_ee.store::<u32>(KSEG1_START + D_CTRL_PHYSICAL, DCtrl::DMA_ENABLE.bits());
+	test_ee.store::<u32>(KSEG1_START + D_STAT_PHYSICAL, 1 << (16 + ChannelId::Gif as u32));
+	test_ee.store::<u32>(KSEG1_START + D_PCR_PHYSICAL, 1 << (ChannelId::Gif as u32));
+	test_ee.store::<u32>(KSEG1_START + gif + QWC_OFFSET, 2);
+	test_ee.store(KSEG1_START + gif + CHCR_OFFSET, Chcr::START.bits());
+
+	// Two quadwords take two bus cycles, and completing takes a third.
+	for _ in 0..5 {
+		test_ee.cycle();
+	}
+	assert!(!test_ee.in_exception());
+	assert!(!test_ee.cpcond0);
+
+	test_ee.cycle();
+	assert!(test_ee.in_exception());
+	assert!(test_ee.cpcond0);
+
+	let cause = Cause::from_bits_truncate(test_ee.read_cop0_direct(cop0::Register::Cause as u8));
+	assert!(cause.contains(Cause::PENDING_INTERRUPT_I1));
+	assert_eq!(test_ee.load::<u32>(KSEG1_START + gif + CHCR_OFFSET), Some(0));
+}
+
+#[test]
+fn dma_feeds_gif_path3() {
+	use crate::{
+		dmac::*,
+		gif::*,
+	};
+
+	let mut test_ee = EECore::new();
+	let channel = ChannelId::Gif.base();
+	let packet = [(1 << 60) | (1 << 58) | (1 << 15) | 1, 0x1234];
+
+	let captured = Rc::new(RefCell::new(vec![]));
+	let sink = captured.clone();
+	test_ee.gif.borrow_mut().set_capture(Some(Box::new(move |path, qwords| {
+		sink.borrow_mut().push((path, qwords.to_vec()));
+	})));
+
+	for (i, &qword) in packet.iter().enumerate() {
+		test_ee.store::<u128>(KSEG1_START + 0x1000 + (i as u32) * 16, qword);
+	}
+
+	test_ee.store::<u32>(KSEG1_START + D_CTRL_PHYSICAL, DCtrl::DMA_ENABLE.bits());
+	test_ee.store::<u32>(KSEG1_START + channel + MADR_OFFSET, 0x1000);
+	test_ee.store::<u32>(KSEG1_START + channel + QWC_OFFSET, 2);
+	test_ee.store(KSEG1_START + channel + CHCR_OFFSET, Chcr::START.bits());
+
+	for _ in 0..6 {
+		test_ee.cycle();
+	}
+
+	assert_eq!(*captured.borrow(), vec![(GifPath::Path3, packet.to_vec())]);
+	assert_eq!(test_ee.load::<u32>(KSEG1_START + GIF_STAT_PHYSICAL), Some(0));
+}
+
+#[test]
+fn dma_feeds_vif1_unpack() {
+	use crate::{
+		dmac::*,
+		intc::*,
+	};
+
+	let mut test_ee = EECore::new();
+	let channel = ChannelId::Vif1.base();
+
+	// UNPACK V4-32 of one quadword to VU1 address 2, interrupting when done.
+	let packet: [u128; 2] = [
+		(0x6c01_0002 | (1 << 31)) | (0xa << 32) | (0xb << 64) | (0xc << 96),
+		0xd,
+	];
+
+	for (i, &qword) in packet.iter().enumerate() {
+		test_ee.store::<u128>(KSEG1_START + 0x1000 + (i as u32) * 16, qword);
+	}
+
+	test_ee.store::<u32>(KSEG1_START + D_CTRL_PHYSICAL, DCtrl::DMA_ENABLE.bits());
+	test_ee.store::<u32>(KSEG1_START + channel + MADR_OFFSET, 0x1000);
+	test_ee.store::<u32>(KSEG1_START + channel + QWC_OFFSET, 2);
+	test_ee.store(KSEG1_START + channel + CHCR_OFFSET, (Chcr::START | Chcr::DIRECTION).bits());
+
+	for _ in 0..6 {
+		test_ee.cycle();
+	}
+
+	for (i, &word) in [0xa, 0xb, 0xc, 0xd].iter().enumerate() {
+		assert_eq!(test_ee.load::<u32>(KSEG1_START + VU1_DATA_PHYSICAL + 0x20 + (i as u32) * 4), Some(word));
+	}
+	assert_eq!(test_ee.load::<u32>(KSEG1_START + I_STAT_PHYSICAL), Some(1 << Interrupt::Vif1 as u32));
+}
@@ -0,0 +1,1136 @@
+//! The EE's DMA controller (DMAC).
+//!
+//! Moves quadwords between main memory (or scratchpad) and the EE's
+//! peripherals over ten channels, one quadword per bus cycle.
+//! Channels run in normal, chain (driven by DMAtags) or interleave mode,
+//! and signal completion to the EE Core over `Int[1]` and `CPCOND0`.
+//!
+//! Register layout and behaviour follow https://psi-rockin.github.io/ps2tek/.
+
+use bitflags::bitflags;
+use byteorder::{
+	ByteOrder,
+	LittleEndian,
+};
+use crate::memory::{
+	bus::Device,
+	constants::EE_CYCLES_PER_BUS_CYCLE,
+	mmu::MmuAddress,
+	Memory,
+};
+use enum_primitive::*;
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+/// Physical address of the DMAC's register space (channel 0's registers).
+pub const DMAC_PHYSICAL: u32 = 0x1000_8000;
+
+/// Length of the DMAC's register space, up to and including `D_STADR`.
+pub const DMAC_SIZE: u32 = D_STADR_PHYSICAL + 0x10 - DMAC_PHYSICAL;
+
+pub const D_CTRL_PHYSICAL: u32 = 0x1000_E000;
+pub const D_STAT_PHYSICAL: u32 = 0x1000_E010;
+pub const D_PCR_PHYSICAL: u32 = 0x1000_E020;
+pub const D_SQWC_PHYSICAL: u32 = 0x1000_E030;
+pub const D_RBSR_PHYSICAL: u32 = 0x1000_E040;
+pub const D_RBOR_PHYSICAL: u32 = 0x1000_E050;
+pub const D_STADR_PHYSICAL: u32 = 0x1000_E060;
+
+/// Offset of each register within a channel's block.
+pub const CHCR_OFFSET: u32 = 0x00;
+pub const MADR_OFFSET: u32 = 0x10;
+pub const QWC_OFFSET: u32 = 0x20;
+pub const TADR_OFFSET: u32 = 0x30;
+pub const ASR0_OFFSET: u32 = 0x40;
+pub const ASR1_OFFSET: u32 = 0x50;
+pub const SADR_OFFSET: u32 = 0x80;
+
+/// Length of the register block of each channel.
+pub const CHANNEL_BLOCK_SIZE: u32 = 0x400;
+
+pub const CHANNEL_COUNT: usize = 10;
+
+pub const QWORD_BYTES: u32 = 16;
+
+/// Set in a memory address to select scratchpad rather than main memory.
+pub const SPR_ADDRESS: u32 = 0x8000_0000;
+
+/// Bits of a scratchpad address which are valid for DMA.
+pub const SPR_ADDRESS_MASK: u32 = 0x3ff0;
+
+/// Mask of the channel bits in `D_STAT.CIS`, `D_STAT.CIM`, `D_PCR.CPC` and `D_PCR.CDE`.
+const CHANNEL_BITS: u32 = (1 << CHANNEL_COUNT) - 1;
+
+enum_from_primitive!{
+/// The ten DMA channels, numbered by their bit in `D_STAT`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelId {
+	Vif0 = 0,
+	Vif1,
+	Gif,
+	IpuFrom,
+	IpuTo,
+	Sif0,
+	Sif1,
+	Sif2,
+	SprFrom,
+	SprTo,
+}
+}
+
+/// Direction of data flow through a channel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+	/// Peripheral (or scratchpad) to memory.
+	ToMemory,
+	/// Memory to peripheral (or scratchpad).
+	FromMemory,
+}
+
+impl ChannelId {
+	/// Physical address of this channel's register block.
+	pub fn base(self) -> u32 {
+		use ChannelId::*;
+
+		match self {
+			Vif0 => 0x1000_8000,
+			Vif1 => 0x1000_9000,
+			Gif => 0x1000_A000,
+			IpuFrom => 0x1000_B000,
+			IpuTo => 0x1000_B400,
+			Sif0 => 0x1000_C000,
+			Sif1 => 0x1000_C400,
+			Sif2 => 0x1000_C800,
+			SprFrom => 0x1000_D000,
+			SprTo => 0x1000_D400,
+		}
+	}
+
+	/// Find the channel whose register block contains `p_addr`.
+	pub fn from_address(p_addr: u32) -> Option<Self> {
+		(0..CHANNEL_COUNT)
+			.filter_map(ChannelId::from_usize)
+			.find(|id| id.base() <= p_addr && p_addr < id.base() + CHANNEL_BLOCK_SIZE)
+	}
+
+	/// Direction of transfer: fixed for most channels, but chosen by `CHCR.DIR` for the rest.
+	pub fn direction(self, chcr: Chcr) -> Direction {
+		use ChannelId::*;
+
+		match self {
+			Vif0 | Vif1 | Sif2 => if chcr.contains(Chcr::DIRECTION) {
+				Direction::FromMemory
+			} else {
+				Direction::ToMemory
+			},
+			Gif | IpuTo | Sif1 | SprTo => Direction::FromMemory,
+			IpuFrom | Sif0 | SprFrom => Direction::ToMemory,
+		}
+	}
+
+	/// Whether chain mode reads DMAtags from the transferred data (destination chain),
+	/// rather than from memory at `TADR` (source chain).
+	pub fn destination_chain(self) -> bool {
+		matches!(self, ChannelId::Sif0 | ChannelId::SprFrom)
+	}
+}
+
+bitflags!{
+/// Flags contained within a channel's `CHCR` register.
+pub struct Chcr: u32 {
+	/// Transfer from (`1`) or to (`0`) memory, where not fixed by the channel.
+	const DIRECTION     = 0b0000_0000_0000_0001;
+
+	const MODE_B0       = 0b0000_0000_0000_0100;
+	const MODE_B1       = 0b0000_0000_0000_1000;
+
+	/// 2-bit field selecting the transfer mode:
+	/// * `00` => normal.
+	/// * `01` => chain.
+	/// * `10` => interleave.
+	const MODE = Self::MODE_B0.bits
+		| Self::MODE_B1.bits;
+
+	const ASP_B0        = 0b0000_0000_0001_0000;
+	const ASP_B1        = 0b0000_0000_0010_0000;
+
+	/// 2-bit field counting the addresses pushed by `call` tags.
+	const ADDRESS_STACK_POINTER = Self::ASP_B0.bits
+		| Self::ASP_B1.bits;
+
+	/// Send each DMAtag to the peripheral ahead of its data.
+	const TAG_TRANSFER  = 0b0000_0000_0100_0000;
+
+	/// Stop the transfer after any DMAtag with its `IRQ` bit set.
+	const TAG_INTERRUPT = 0b0000_0000_1000_0000;
+
+	/// Set to start the channel, cleared when it finishes.
+	const START         = 0b0000_0001_0000_0000;
+
+	/// Bits 16--31 of the most recently read DMAtag.
+	const TAG = 0xffff_0000;
+}
+}
+
+/// Transfer modes selectable by [`Chcr::MODE`](struct.Chcr.html#associatedconstant.MODE).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+	Normal,
+	Chain,
+	Interleave,
+}
+
+impl Chcr {
+	#[inline]
+	pub fn mode(self) -> Mode {
+		match (self & Self::MODE).bits() >> 2 {
+			1 => Mode::Chain,
+			2 => Mode::Interleave,
+			_ => Mode::Normal,
+		}
+	}
+
+	#[inline]
+	pub fn address_stack_pointer(self) -> usize {
+		((self & Self::ADDRESS_STACK_POINTER).bits() >> 4) as usize
+	}
+
+	#[inline]
+	pub fn set_address_stack_pointer(&mut self, asp: usize) {
+		self.remove(Self::ADDRESS_STACK_POINTER);
+		self.insert(Self::from_bits_truncate((asp as u32) << 4));
+	}
+
+	/// ID of the most recently read DMAtag.
+	#[inline]
+	pub fn tag_id(self) -> u8 {
+		((self.bits() >> 28) & 0b111) as u8
+	}
+}
+
+bitflags!{
+/// Flags contained within `D_CTRL`.
+pub struct DCtrl: u32 {
+	/// Enable (`1`) all DMA transfers.
+	const DMA_ENABLE    = 0b0000_0000_0001;
+
+	/// Enable cycle stealing.
+	const RELEASE       = 0b0000_0000_0010;
+
+	const MFD_B0        = 0b0000_0000_0100;
+	const MFD_B1        = 0b0000_0000_1000;
+
+	/// 2-bit field selecting the MFIFO drain channel:
+	/// * `00` => MFIFO disabled.
+	/// * `10` => VIF1.
+	/// * `11` => GIF.
+	const MFIFO_DRAIN = Self::MFD_B0.bits
+		| Self::MFD_B1.bits;
+
+	const STS_B0        = 0b0000_0001_0000;
+	const STS_B1        = 0b0000_0010_0000;
+
+	/// 2-bit field selecting the stall control source channel:
+	/// * `00` => none.
+	/// * `01` => SIF0.
+	/// * `10` => fromSPR.
+	/// * `11` => fromIPU.
+	const STALL_SOURCE = Self::STS_B0.bits
+		| Self::STS_B1.bits;
+
+	const STD_B0        = 0b0000_0100_0000;
+	const STD_B1        = 0b0000_1000_0000;
+
+	/// 2-bit field selecting the stall control drain channel:
+	/// * `00` => none.
+	/// * `01` => VIF1.
+	/// * `10` => GIF.
+	/// * `11` => SIF1.
+	const STALL_DRAIN = Self::STD_B0.bits
+		| Self::STD_B1.bits;
+
+	/// 3-bit field setting the release cycle when cycle stealing.
+	const RELEASE_CYCLE = 0b0111_0000_0000;
+}
+}
+
+impl DCtrl {
+	pub fn mfifo_drain(self) -> Option<ChannelId> {
+		match (self & Self::MFIFO_DRAIN).bits() >> 2 {
+			2 => Some(ChannelId::Vif1),
+			3 => Some(ChannelId::Gif),
+			_ => None,
+		}
+	}
+
+	pub fn stall_source(self) -> Option<ChannelId> {
+		match (self & Self::STALL_SOURCE).bits() >> 4 {
+			1 => Some(ChannelId::Sif0),
+			2 => Some(ChannelId::SprFrom),
+			3 => Some(ChannelId::IpuFrom),
+			_ => None,
+		}
+	}
+
+	pub fn stall_drain(self) -> Option<ChannelId> {
+		match (self & Self::STALL_DRAIN).bits() >> 6 {
+			1 => Some(ChannelId::Vif1),
+			2 => Some(ChannelId::Gif),
+			3 => Some(ChannelId::Sif1),
+			_ => None,
+		}
+	}
+}
+
+bitflags!{
+/// Flags contained within `D_STAT`.
+///
+/// Status bits are cleared by writing `1`, and mask bits are toggled by writing `1`.
+pub struct DStat: u32 {
+	/// Per-channel completion flags.
+	const CHANNEL_INTERRUPT      = CHANNEL_BITS;
+
+	/// Set when the stall control drain channel stalls.
+	const STALL_INTERRUPT        = 1 << 13;
+
+	/// Set when the MFIFO drain channel finds the ring buffer empty.
+	const MFIFO_EMPTY_INTERRUPT  = 1 << 14;
+
+	/// Set when a transfer touches an invalid address.
+	const BUS_ERROR_INTERRUPT    = 1 << 15;
+
+	/// Per-channel masks for `CHANNEL_INTERRUPT`.
+	const CHANNEL_MASK           = CHANNEL_BITS << 16;
+
+	const STALL_MASK             = 1 << 29;
+
+	const MFIFO_EMPTY_MASK       = 1 << 30;
+
+	const STATUS = Self::CHANNEL_INTERRUPT.bits
+		| Self::STALL_INTERRUPT.bits
+		| Self::MFIFO_EMPTY_INTERRUPT.bits
+		| Self::BUS_ERROR_INTERRUPT.bits;
+
+	const MASK = Self::CHANNEL_MASK.bits
+		| Self::STALL_MASK.bits
+		| Self::MFIFO_EMPTY_MASK.bits;
+}
+}
+
+/// `D_PCR.PCE`: only run channels enabled by `D_PCR.CDE`.
+pub const PCR_PRIORITY_ENABLE: u32 = 0x8000_0000;
+
+/// A DMAtag, held in the lower 64 bits of a quadword.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DmaTag {
+	pub qwc: u32,
+	pub id: u8,
+	pub irq: bool,
+	/// Address, including [`SPR_ADDRESS`](constant.SPR_ADDRESS.html).
+	pub addr: u32,
+}
+
+impl From<u128> for DmaTag {
+	fn from(qword: u128) -> Self {
+		let tag = qword as u64;
+
+		Self {
+			qwc: (tag & 0xffff) as u32,
+			id: ((tag >> 28) & 0b111) as u8,
+			irq: tag & (1 << 31) != 0,
+			addr: ((tag >> 32) as u32) & !0xf,
+		}
+	}
+}
+
+/// Source chain tag IDs, read from memory at `TADR`.
+pub mod source_tag {
+	pub const REFE: u8 = 0;
+	pub const CNT: u8 = 1;
+	pub const NEXT: u8 = 2;
+	pub const REF: u8 = 3;
+	pub const REFS: u8 = 4;
+	pub const CALL: u8 = 5;
+	pub const RET: u8 = 6;
+	pub const END: u8 = 7;
+}
+
+/// Destination chain tag IDs, read from the transferred data.
+pub mod destination_tag {
+	pub const CNT: u8 = 0;
+	pub const CNTS: u8 = 1;
+	pub const END: u8 = 7;
+}
+
+/// A peripheral at the far end of a channel.
+pub trait DmaPeripheral {
+	/// Accept a quadword read from memory, or `false` if it can't be taken yet.
+	fn dma_write(&mut self, data: u128) -> bool;
+
+	/// Supply a quadword to be written to memory, or `None` if none is ready yet.
+	fn dma_read(&mut self) -> Option<u128>;
+}
+
+/// Handle to a peripheral, shared between the DMAC and its other users.
+pub type SharedPeripheral = Rc<RefCell<dyn DmaPeripheral>>;
+
+/// Outcome of giving a channel one bus cycle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Progress {
+	Moved,
+	Stalled,
+	Finished,
+	BusError,
+}
+
+#[derive(Clone, Default)]
+pub struct Channel {
+	pub chcr: Chcr,
+	pub madr: u32,
+	pub qwc: u32,
+	pub tadr: u32,
+	pub asr: [u32; 2],
+	pub sadr: u32,
+
+	/// The block being transferred is the last of its chain.
+	end_after: bool,
+
+	/// Quadwords moved in the current interleave block.
+	interleave_count: u32,
+
+	peripheral: Option<SharedPeripheral>,
+}
+
+impl Default for Chcr {
+	fn default() -> Self {
+		Self::empty()
+	}
+}
+
+impl Channel {
+	#[inline]
+	pub fn active(&self) -> bool {
+		self.chcr.contains(Chcr::START)
+	}
+
+	fn read(&self, offset: u32) -> u32 {
+		match offset {
+			CHCR_OFFSET => self.chcr.bits(),
+			MADR_OFFSET => self.madr,
+			QWC_OFFSET => self.qwc,
+			TADR_OFFSET => self.tadr,
+			ASR0_OFFSET => self.asr[0],
+			ASR1_OFFSET => self.asr[1],
+			SADR_OFFSET => self.sadr,
+			_ => 0,
+		}
+	}
+
+	fn write(&mut self, offset: u32, value: u32) {
+		match offset {
+			CHCR_OFFSET => {
+				let chcr = Chcr::from_bits_truncate(value);
+
+				if chcr.contains(Chcr::START) && !self.active() {
+					self.end_after = false;
+					self.interleave_count = 0;
+				}
+
+				self.chcr = chcr;
+			},
+			MADR_OFFSET => self.madr = value & !0xf,
+			QWC_OFFSET => self.qwc = value & 0xffff,
+			TADR_OFFSET => self.tadr = value & !0xf,
+			ASR0_OFFSET => self.asr[0] = value & !0xf,
+			ASR1_OFFSET => self.asr[1] = value & !0xf,
+			SADR_OFFSET => self.sadr = value & SPR_ADDRESS_MASK,
+			_ => {},
+		}
+	}
+}
+
+#[derive(Default)]
+pub struct Dmac {
+	pub channels: [Channel; CHANNEL_COUNT],
+
+	pub ctrl: DCtrl,
+	pub stat: DStat,
+	pub pcr: u32,
+	pub sqwc: u32,
+	pub rbsr: u32,
+	pub rbor: u32,
+	pub stadr: u32,
+
+	/// EE Core cycles elapsed towards the next bus cycle.
+	elapsed: u64,
+}
+
+impl Default for DCtrl {
+	fn default() -> Self {
+		Self::empty()
+	}
+}
+
+impl Default for DStat {
+	fn default() -> Self {
+		Self::empty()
+	}
+}
+
+fn dma_address(addr: u32) -> MmuAddress {
+	if addr & SPR_ADDRESS != 0 {
+		MmuAddress::Scratchpad(addr & SPR_ADDRESS_MASK)
+	} else {
+		MmuAddress::Address(addr & !0xf)
+	}
+}
+
+fn read_qword(memory: &Memory, addr: u32) -> Option<u128> {
+	memory.read(dma_address(addr), QWORD_BYTES as usize)
+		.map(LittleEndian::read_u128)
+}
+
+fn write_qword(memory: &mut Memory, addr: u32, data: u128) -> Option<()> {
+	memory.read_mut(dma_address(addr), QWORD_BYTES as usize)
+		.map(|buf| LittleEndian::write_u128(buf, data))
+}
+
+impl Dmac {
+	/// Connect `peripheral` to the far end of `channel`.
+	///
+	/// Data sent over a channel with no peripheral is discarded,
+	/// and channels reading from no peripheral stall.
+	pub fn attach(&mut self, channel: ChannelId, peripheral: SharedPeripheral) {
+		self.channels[channel as usize].peripheral = Some(peripheral);
+	}
+
+	/// Whether the DMAC is asserting `Int[1]`.
+	pub fn interrupt_pending(&self) -> bool {
+		let stat = self.stat.bits();
+
+		(stat & (stat >> 16) & (DStat::CHANNEL_INTERRUPT | DStat::STALL_INTERRUPT | DStat::MFIFO_EMPTY_INTERRUPT).bits()) != 0
+			|| self.stat.contains(DStat::BUS_ERROR_INTERRUPT)
+	}
+
+	/// Level of `CPCOND0`: set once every channel selected by `D_PCR.CPC` has completed.
+	pub fn cpcond0(&self) -> bool {
+		((self.stat.bits() | !self.pcr) & CHANNEL_BITS) == CHANNEL_BITS
+	}
+
+	/// Advance the DMAC by `ee_cycles` cycles of the EE Core clock.
+	pub fn step(&mut self, ee_cycles: u64, memory: &mut Memory) {
+		self.elapsed += ee_cycles;
+
+		while self.elapsed >= EE_CYCLES_PER_BUS_CYCLE {
+			self.elapsed -= EE_CYCLES_PER_BUS_CYCLE;
+
+			if !self.transfer(memory) {
+				self.elapsed = 0;
+				break;
+			}
+		}
+	}
+
+	/// Give one bus cycle to the highest priority channel which can use it.
+	///
+	/// Returns `false` if no channel could make progress.
+	fn transfer(&mut self, memory: &mut Memory) -> bool {
+		if !self.ctrl.contains(DCtrl::DMA_ENABLE) {
+			return false;
+		}
+
+		for index in 0..CHANNEL_COUNT {
+			let enabled = self.pcr & PCR_PRIORITY_ENABLE == 0
+				|| self.pcr & (1 << (index + 16)) != 0;
+
+			if !(enabled && self.channels[index].active()) {
+				continue;
+			}
+
+			let id = ChannelId::from_usize(index).unwrap();
+
+			match self.step_channel(id, memory) {
+				Progress::Moved => {},
+				Progress::Stalled => continue,
+				Progress::Finished => self.finish(id),
+				Progress::BusError => {
+					trace!("DMA bus error on {:?}", id);
+					self.stat.insert(DStat::BUS_ERROR_INTERRUPT);
+					self.channels[index].chcr.remove(Chcr::START);
+				},
+			}
+
+			return true;
+		}
+
+		false
+	}
+
+	fn finish(&mut self, id: ChannelId) {
+		trace!("DMA channel {:?} finished", id);
+
+		let channel = &mut self.channels[id as usize];
+		channel.chcr.remove(Chcr::START);
+		channel.end_after = false;
+
+		self.stat.insert(DStat::from_bits_truncate(1 << (id as u32)));
+	}
+
+	/// Map an address into the MFIFO ring buffer.
+	fn ring(&self, addr: u32) -> u32 {
+		self.rbor | (addr & self.rbsr)
+	}
+
+	/// Whether `addr` lies within the MFIFO ring buffer (or just past its end).
+	fn in_ring(&self, addr: u32) -> bool {
+		addr >= self.rbor && addr <= self.rbor + self.rbsr + QWORD_BYTES
+	}
+
+	fn step_channel(&mut self, id: ChannelId, memory: &mut Memory) -> Progress {
+		let channel = &self.channels[id as usize];
+
+		if channel.qwc == 0 {
+			return match channel.chcr.mode() {
+				Mode::Chain if !channel.end_after => if id.destination_chain() {
+					self.read_destination_tag(id, memory)
+				} else {
+					self.read_source_tag(id, memory)
+				},
+				_ => Progress::Finished,
+			};
+		}
+
+		self.move_qword(id, memory)
+	}
+
+	fn read_source_tag(&mut self, id: ChannelId, memory: &mut Memory) -> Progress {
+		let mfifo = self.ctrl.mfifo_drain() == Some(id);
+		let tadr = if mfifo { self.ring(self.channels[id as usize].tadr) } else { self.channels[id as usize].tadr };
+
+		if mfifo && tadr == self.ring(self.channels[ChannelId::SprFrom as usize].madr) {
+			self.stat.insert(DStat::MFIFO_EMPTY_INTERRUPT);
+			return Progress::Stalled;
+		}
+
+		let qword = match read_qword(memory, tadr) {
+			Some(qword) => qword,
+			None => return Progress::BusError,
+		};
+		let tag = DmaTag::from(qword);
+
+		let channel = &mut self.channels[id as usize];
+
+		if channel.chcr.contains(Chcr::TAG_TRANSFER) {
+			// Only the upper half of the tag quadword reaches the peripheral.
+			let upper = qword & !u128::from(u64::MAX);
+
+			if let Some(peripheral) = &channel.peripheral {
+				if !peripheral.borrow_mut().dma_write(upper) {
+					return Progress::Stalled;
+				}
+			}
+		}
+
+		trace!("{:?} read source tag {:?} at 0x{:08x}", id, tag, tadr);
+
+		channel.chcr = (channel.chcr & !Chcr::TAG) | Chcr::from_bits_truncate((qword as u32) & Chcr::TAG.bits());
+		channel.qwc = tag.qwc;
+
+		let following = tadr + QWORD_BYTES;
+		let after_data = following + tag.qwc * QWORD_BYTES;
+
+		match tag.id {
+			source_tag::REFE => {
+				channel.madr = tag.addr;
+				channel.tadr = following;
+				channel.end_after = true;
+			},
+			source_tag::CNT => {
+				channel.madr = following;
+				channel.tadr = after_data;
+			},
+			source_tag::NEXT => {
+				channel.madr = following;
+				channel.tadr = tag.addr;
+			},
+			source_tag::REF | source_tag::REFS => {
+				channel.madr = tag.addr;
+				channel.tadr = following;
+			},
+			source_tag::CALL => {
+				let asp = channel.chcr.address_stack_pointer();
+				if asp >= channel.asr.len() {
+					return Progress::BusError;
+				}
+
+				channel.asr[asp] = after_data;
+				channel.chcr.set_address_stack_pointer(asp + 1);
+				channel.madr = following;
+				channel.tadr = tag.addr;
+			},
+			source_tag::RET => {
+				channel.madr = following;
+
+				match channel.chcr.address_stack_pointer() {
+					0 => channel.end_after = true,
+					asp => {
+						channel.chcr.set_address_stack_pointer(asp - 1);
+						channel.tadr = channel.asr[asp - 1];
+					},
+				}
+			},
+			_ => {
+				channel.madr = following;
+				channel.end_after = true;
+			},
+		}
+
+		if tag.irq && channel.chcr.contains(Chcr::TAG_INTERRUPT) {
+			channel.end_after = true;
+		}
+
+		Progress::Moved
+	}
+
+	fn read_destination_tag(&mut self, id: ChannelId, memory: &mut Memory) -> Progress {
+		let channel = &mut self.channels[id as usize];
+
+		let qword = if id == ChannelId::SprFrom {
+			let qword = read_qword(memory, SPR_ADDRESS | channel.sadr);
+			channel.sadr = (channel.sadr + QWORD_BYTES) & SPR_ADDRESS_MASK;
+			qword
+		} else {
+			match &channel.peripheral {
+				Some(peripheral) => match peripheral.borrow_mut().dma_read() {
+					Some(qword) => Some(qword),
+					None => return Progress::Stalled,
+				},
+				None => return Progress::Stalled,
+			}
+		};
+
+		let qword = match qword {
+			Some(qword) => qword,
+			None => return Progress::BusError,
+		};
+		let tag = DmaTag::from(qword);
+
+		trace!("{:?} read destination tag {:?}", id, tag);
+
+		channel.chcr = (channel.chcr & !Chcr::TAG) | Chcr::from_bits_truncate((qword as u32) & Chcr::TAG.bits());
+		channel.qwc = tag.qwc;
+		channel.madr = tag.addr;
+
+		if tag.id == destination_tag::END || (tag.irq && channel.chcr.contains(Chcr::TAG_INTERRUPT)) {
+			channel.end_after = true;
+		}
+
+		Progress::Moved
+	}
+
+	fn move_qword(&mut self, id: ChannelId, memory: &mut Memory) -> Progress {
+		let mfifo_source = id == ChannelId::SprFrom && self.ctrl.mfifo_drain().is_some();
+		let mfifo_drain = self.ctrl.mfifo_drain() == Some(id);
+		let stall_drain = self.ctrl.stall_drain() == Some(id);
+		let stall_source = self.ctrl.stall_source() == Some(id);
+
+		let madr = self.channels[id as usize].madr;
+		let madr = if mfifo_source || (mfifo_drain && self.in_ring(madr)) { self.ring(madr) } else { madr };
+
+		// The MFIFO drain may not overtake the data written by fromSPR.
+		if mfifo_drain && self.in_ring(self.channels[id as usize].madr)
+			&& madr == self.ring(self.channels[ChannelId::SprFrom as usize].madr) {
+			self.stat.insert(DStat::MFIFO_EMPTY_INTERRUPT);
+			return Progress::Stalled;
+		}
+
+		let channel = &self.channels[id as usize];
+
+		// Drains under stall control may not overtake their source.
+		let stall_checked = channel.chcr.mode() == Mode::Normal || channel.chcr.tag_id() == source_tag::REFS;
+		if stall_drain && stall_checked && madr + QWORD_BYTES > self.stadr {
+			self.stat.insert(DStat::STALL_INTERRUPT);
+			return Progress::Stalled;
+		}
+
+		let result = match (id, id.direction(channel.chcr)) {
+			(ChannelId::SprFrom, _) => read_qword(memory, SPR_ADDRESS | channel.sadr)
+				.and_then(|data| write_qword(memory, madr, data)),
+			(ChannelId::SprTo, _) => read_qword(memory, madr)
+				.and_then(|data| write_qword(memory, SPR_ADDRESS | channel.sadr, data)),
+			(_, Direction::FromMemory) => match read_qword(memory, madr) {
+				Some(data) => match &channel.peripheral {
+					Some(peripheral) => if peripheral.borrow_mut().dma_write(data) {
+						Some(())
+					} else {
+						return Progress::Stalled;
+					},
+					None => {
+						trace!("{:?} has no peripheral: dropping 0x{:032x}", id, data);
+						Some(())
+					},
+				},
+				None => None,
+			},
+			(_, Direction::ToMemory) => {
+				let data = match &channel.peripheral {
+					Some(peripheral) => peripheral.borrow_mut().dma_read(),
+					None => None,
+				};
+
+				match data {
+					Some(data) => write_qword(memory, madr, data),
+					None => return Progress::Stalled,
+				}
+			},
+		};
+
+		if result.is_none() {
+			return Progress::BusError;
+		}
+
+		let sqwc = self.sqwc;
+		let channel = &mut self.channels[id as usize];
+
+		channel.madr = madr + QWORD_BYTES;
+		channel.qwc -= 1;
+
+		if matches!(id, ChannelId::SprFrom | ChannelId::SprTo) {
+			channel.sadr = (channel.sadr + QWORD_BYTES) & SPR_ADDRESS_MASK;
+		}
+
+		if channel.chcr.mode() == Mode::Interleave {
+			// Move TQWC quadwords, then skip SQWC.
+			channel.interleave_count += 1;
+
+			if channel.interleave_count == (sqwc >> 16) & 0xff {
+				channel.interleave_count = 0;
+				channel.madr += (sqwc & 0xff) * QWORD_BYTES;
+			}
+		}
+
+		if stall_source {
+			self.stadr = self.channels[id as usize].madr;
+		}
+
+		Progress::Moved
+	}
+
+	fn read(&self, p_addr: u32) -> u32 {
+		match p_addr {
+			D_CTRL_PHYSICAL => self.ctrl.bits(),
+			D_STAT_PHYSICAL => self.stat.bits(),
+			D_PCR_PHYSICAL => self.pcr,
+			D_SQWC_PHYSICAL => self.sqwc,
+			D_RBSR_PHYSICAL => self.rbsr,
+			D_RBOR_PHYSICAL => self.rbor,
+			D_STADR_PHYSICAL => self.stadr,
+			_ => ChannelId::from_address(p_addr)
+				.map(|id| self.channels[id as usize].read(p_addr - id.base()))
+				.unwrap_or(0),
+		}
+	}
+
+	fn write(&mut self, p_addr: u32, value: u32) {
+		match p_addr {
+			D_CTRL_PHYSICAL => self.ctrl = DCtrl::from_bits_truncate(value),
+			D_STAT_PHYSICAL => {
+				let written = DStat::from_bits_truncate(value);
+				self.stat.remove(written & DStat::STATUS);
+				self.stat.toggle(written & DStat::MASK);
+			},
+			D_PCR_PHYSICAL => self.pcr = value,
+			D_SQWC_PHYSICAL => self.sqwc = value & 0x00ff_00ff,
+			D_RBSR_PHYSICAL => self.rbsr = value & 0x7fff_fff0,
+			D_RBOR_PHYSICAL => self.rbor = value & 0x7fff_fff0,
+			D_STADR_PHYSICAL => self.stadr = value & 0x7fff_fff0,
+			_ => if let Some(id) = ChannelId::from_address(p_addr) {
+				trace!("Writing 0x{:08x} to {:?} at offset 0x{:02x}", value, id, p_addr - id.base());
+				self.channels[id as usize].write(p_addr - id.base(), value);
+			},
+		}
+	}
+}
+
+impl Device for Dmac {
+	fn read_u32(&mut self, p_addr: u32) -> u32 {
+		self.read(p_addr & !0b11)
+	}
+
+	fn write_u32(&mut self, p_addr: u32, value: u32) {
+		self.write(p_addr & !0b11, value);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::VecDeque;
+
+	#[derive(Default)]
+	struct Fifo {
+		data: VecDeque<u128>,
+		capacity: Option<usize>,
+	}
+
+	impl DmaPeripheral for Fifo {
+		fn dma_write(&mut self, data: u128) -> bool {
+			if self.capacity.map_or(false, |cap| self.data.len() >= cap) {
+				false
+			} else {
+				self.data.push_back(data);
+				true
+			}
+		}
+
+		fn dma_read(&mut self) -> Option<u128> {
+			self.data.pop_front()
+		}
+	}
+
+	fn setup(channel: ChannelId) -> (Dmac, Memory, Rc<RefCell<Fifo>>) {
+		let mut dmac = Dmac::default();
+		let fifo = Rc::new(RefCell::new(Fifo::default()));
+
+		dmac.attach(channel, fifo.clone());
+		dmac.write_u32(D_CTRL_PHYSICAL, DCtrl::DMA_ENABLE.bits());
+
+		(dmac, Memory::new(vec![]), fifo)
+	}
+
+	fn tag(id: u8, qwc: u32, addr: u32, irq: bool) -> u128 {
+		let irq = if irq { 1 << 31 } else { 0 };
+		u128::from(qwc | (u32::from(id) << 28) | irq) | (u128::from(addr) << 32)
+	}
+
+	fn put(memory: &mut Memory, addr: u32, data: &[u128]) {
+		for (i, qword) in data.iter().enumerate() {
+			write_qword(memory, addr + (i as u32) * QWORD_BYTES, *qword).unwrap();
+		}
+	}
+
+	fn start(dmac: &mut Dmac, channel: ChannelId, chcr: Chcr) {
+		dmac.write_u32(channel.base() + CHCR_OFFSET, (chcr | Chcr::START).bits());
+	}
+
+	fn run(dmac: &mut Dmac, memory: &mut Memory) {
+		dmac.step(1 << 12, memory);
+	}
+
+	#[test]
+	fn normal_transfer_to_peripheral() {
+		let (mut dmac, mut memory, fifo) = setup(ChannelId::Gif);
+		put(&mut memory, 0x1000, &[1, 2, 3]);
+
+		dmac.write_u32(ChannelId::Gif.base() + MADR_OFFSET, 0x1000);
+		dmac.write_u32(ChannelId::Gif.base() + QWC_OFFSET, 3);
+		dmac.write_u32(D_STAT_PHYSICAL, DStat::CHANNEL_MASK.bits());
+		start(&mut dmac, ChannelId::Gif, Chcr::empty());
+
+		// One quadword per bus cycle.
+		dmac.step(2 * EE_CYCLES_PER_BUS_CYCLE, &mut memory);
+		assert_eq!(fifo.borrow().data.len(), 2);
+		assert!(!dmac.interrupt_pending());
+
+		run(&mut dmac, &mut memory);
+
+		assert_eq!(fifo.borrow().data, vec![1, 2, 3]);
+		assert!(!dmac.channels[ChannelId::Gif as usize].active());
+		assert_eq!(dmac.read_u32(ChannelId::Gif.base() + MADR_OFFSET), 0x1030);
+		assert_eq!(dmac.read_u32(ChannelId::Gif.base() + QWC_OFFSET), 0);
+		assert!(dmac.stat.contains(DStat::from_bits_truncate(1 << (ChannelId::Gif as u32))));
+		assert!(dmac.interrupt_pending());
+
+		dmac.write_u32(D_STAT_PHYSICAL, 1 << (ChannelId::Gif as u32));
+		assert!(!dmac.interrupt_pending());
+	}
+
+	#[test]
+	fn normal_transfer_to_memory() {
+		let (mut dmac, mut memory, fifo) = setup(ChannelId::Vif0);
+		fifo.borrow_mut().data.extend(&[7, 8]);
+
+		dmac.write_u32(ChannelId::Vif0.base() + MADR_OFFSET, 0x2000);
+		dmac.write_u32(ChannelId::Vif0.base() + QWC_OFFSET, 3);
+		start(&mut dmac, ChannelId::Vif0, Chcr::empty());
+
+		// The peripheral runs dry: the channel waits for more.
+		run(&mut dmac, &mut memory);
+		assert!(dmac.channels[ChannelId::Vif0 as usize].active());
+
+		fifo.borrow_mut().data.push_back(9);
+		run(&mut dmac, &mut memory);
+
+		assert!(!dmac.channels[ChannelId::Vif0 as usize].active());
+		assert_eq!(read_qword(&memory, 0x2000), Some(7));
+		assert_eq!(read_qword(&memory, 0x2010), Some(8));
+		assert_eq!(read_qword(&memory, 0x2020), Some(9));
+	}
+
+	#[test]
+	fn peripheral_back_pressure_stalls() {
+		let (mut dmac, mut memory, fifo) = setup(ChannelId::Gif);
+		fifo.borrow_mut().capacity = Some(1);
+
+		dmac.write_u32(ChannelId::Gif.base() + QWC_OFFSET, 2);
+		start(&mut dmac, ChannelId::Gif, Chcr::empty());
+
+		run(&mut dmac, &mut memory);
+		assert!(dmac.channels[ChannelId::Gif as usize].active());
+		assert_eq!(dmac.channels[ChannelId::Gif as usize].qwc, 1);
+
+		fifo.borrow_mut().data.clear();
+		run(&mut dmac, &mut memory);
+		assert!(!dmac.channels[ChannelId::Gif as usize].active());
+	}
+
+	#[test]
+	fn source_chain_tags() {
+		let (mut dmac, mut memory, fifo) = setup(ChannelId::Gif);
+
+		// cnt (2) -> next -> ref -> call -> [cnt (1) -> ret] -> end (1).
+		put(&mut memory, 0x1000, &[tag(source_tag::CNT, 2, 0, false), 1, 2, tag(source_tag::NEXT, 1, 0x2000, false), 3]);
+		put(&mut memory, 0x2000, &[tag(source_tag::REF, 1, 0x5000, false), tag(source_tag::CALL, 0, 0x3000, false)]);
+		put(&mut memory, 0x2020, &[tag(source_tag::END, 1, 0, false), 7]);
+		put(&mut memory, 0x3000, &[tag(source_tag::CNT, 1, 0, false), 5, tag(source_tag::RET, 1, 0, false), 6]);
+		put(&mut memory, 0x5000, &[4]);
+
+		dmac.write_u32(ChannelId::Gif.base() + TADR_OFFSET, 0x1000);
+		start(&mut dmac, ChannelId::Gif, Chcr::MODE_B0);
+		run(&mut dmac, &mut memory);
+
+		let channel = &dmac.channels[ChannelId::Gif as usize];
+
+		assert_eq!(fifo.borrow().data, vec![1, 2, 3, 4, 5, 6, 7]);
+		assert!(!channel.active());
+		assert_eq!(channel.chcr.address_stack_pointer(), 0);
+		assert_eq!(channel.chcr.tag_id(), source_tag::END);
+	}
+
+	#[test]
+	fn tag_transfer_and_irq() {
+		let (mut dmac, mut memory, fifo) = setup(ChannelId::Vif1);
+		let tag_qword = tag(source_tag::CNT, 1, 0, true) | (0xabcd << 64);
+
+		put(&mut memory, 0, &[tag_qword, 1, tag(source_tag::END, 0, 0, false)]);
+
+		start(&mut dmac, ChannelId::Vif1, Chcr::DIRECTION | Chcr::MODE_B0 | Chcr::TAG_TRANSFER | Chcr::TAG_INTERRUPT);
+		run(&mut dmac, &mut memory);
+
+		// Stops after the IRQ tag's data, before reading the end tag.
+		assert_eq!(fifo.borrow().data, vec![0xabcd << 64, 1]);
+		assert!(!dmac.channels[ChannelId::Vif1 as usize].active());
+		assert_eq!(dmac.channels[ChannelId::Vif1 as usize].tadr, 0x20);
+	}
+
+	#[test]
+	fn refe_ends_chain() {
+		let (mut dmac, mut memory, fifo) = setup(ChannelId::Gif);
+
+		put(&mut memory, 0, &[tag(source_tag::REFE, 2, 0x400, false)]);
+		put(&mut memory, 0x400, &[8, 9]);
+
+		start(&mut dmac, ChannelId::Gif, Chcr::MODE_B0);
+		run(&mut dmac, &mut memory);
+
+		assert_eq!(fifo.borrow().data, vec![8, 9]);
+		assert_eq!(dmac.channels[ChannelId::Gif as usize].tadr, 0x10);
+	}
+
+	#[test]
+	fn scratchpad_interleave() {
+		let (mut dmac, mut memory, _) = setup(ChannelId::SprTo);
+		put(&mut memory, 0x1000, &[1, 2, 0, 3, 4, 0, 5, 6]);
+
+		// Transfer 2, skip 1.
+		dmac.write_u32(D_SQWC_PHYSICAL, (2 << 16) | 1);
+		dmac.write_u32(ChannelId::SprTo.base() + MADR_OFFSET, 0x1000);
+		dmac.write_u32(ChannelId::SprTo.base() + SADR_OFFSET, 0x100);
+		dmac.write_u32(ChannelId::SprTo.base() + QWC_OFFSET, 6);
+		start(&mut dmac, ChannelId::SprTo, Chcr::MODE_B1);
+		run(&mut dmac, &mut memory);
+
+		let spr: Vec<_> = (0..6).map(|i| read_qword(&memory, SPR_ADDRESS | (0x100 + i * 16)).unwrap()).collect();
+		assert_eq!(spr, vec![1, 2, 3, 4, 5, 6]);
+	}
+
+	#[test]
+	fn scratchpad_destination_chain() {
+		let (mut dmac, mut memory, _) = setup(ChannelId::SprFrom);
+		put(&mut memory, SPR_ADDRESS, &[
+			tag(destination_tag::CNT, 1, 0x3000, false), 1,
+			tag(destination_tag::END, 2, 0x4000, false), 2, 3,
+		]);
+
+		start(&mut dmac, ChannelId::SprFrom, Chcr::MODE_B0);
+		run(&mut dmac, &mut memory);
+
+		assert!(!dmac.channels[ChannelId::SprFrom as usize].active());
+		assert_eq!(read_qword(&memory, 0x3000), Some(1));
+		assert_eq!(read_qword(&memory, 0x4000), Some(2));
+		assert_eq!(read_qword(&memory, 0x4010), Some(3));
+		assert_eq!(dmac.channels[ChannelId::SprFrom as usize].sadr, 0x50);
+	}
+
+	#[test]
+	fn mfifo_ring_buffer() {
+		let (mut dmac, mut memory, fifo) = setup(ChannelId::Gif);
+
+		// 4-qword ring at 0x8000, drained by GIF.
+		dmac.write_u32(D_CTRL_PHYSICAL, (DCtrl::DMA_ENABLE | DCtrl::MFD_B1 | DCtrl::MFD_B0).bits());
+		dmac.write_u32(D_RBOR_PHYSICAL, 0x8000);
+		dmac.write_u32(D_RBSR_PHYSICAL, 0x30);
+
+		put(&mut memory, SPR_ADDRESS, &[tag(source_tag::CNT, 1, 0, false), 1, tag(source_tag::END, 1, 0, false), 2]);
+
+		dmac.write_u32(ChannelId::SprFrom.base() + MADR_OFFSET, 0x8020);
+		dmac.write_u32(ChannelId::SprFrom.base() + QWC_OFFSET, 4);
+		start(&mut dmac, ChannelId::SprFrom, Chcr::empty());
+
+		dmac.write_u32(ChannelId::Gif.base() + TADR_OFFSET, 0x8020);
+		start(&mut dmac, ChannelId::Gif, Chcr::MODE_B0);
+		run(&mut dmac, &mut memory);
+
+		// The second tag wrapped around to the start of the ring.
+		assert_eq!(read_qword(&memory, 0x8000), Some(tag(source_tag::END, 1, 0, false)));
+		assert_eq!(fifo.borrow().data, vec![1, 2]);
+		assert!(!dmac.channels[ChannelId::Gif as usize].active());
+	}
+
+	#[test]
+	fn stat_and_cpcond0() {
+		let mut dmac = Dmac::default();
+
+		// No channels selected: CPCOND0 is set.
+		assert!(dmac.cpcond0());
+
+		dmac.write_u32(D_PCR_PHYSICAL, 1 << (ChannelId::Gif as u32));
+		assert!(!dmac.cpcond0());
+
+		dmac.stat.insert(DStat::from_bits_truncate(1 << (ChannelId::Gif as u32)));
+		assert!(dmac.cpcond0());
+
+		// Mask bits toggle, status bits clear.
+		dmac.write_u32(D_STAT_PHYSICAL, (1 << 18) | (1 << (ChannelId::Gif as u32)));
+		assert_eq!(dmac.read_u32(D_STAT_PHYSICAL), 1 << 18);
+		dmac.write_u32(D_STAT_PHYSICAL, (1 << 16) | (1 << 18));
+		assert_eq!(dmac.read_u32(D_STAT_PHYSICAL), 1 << 16);
+		assert!(!dmac.cpcond0());
+	}
+
+	#[test]
+	fn bus_error_stops_channel() {
+		let (mut dmac, mut memory, _) = setup(ChannelId::Gif);
+
+		dmac.write_u32(ChannelId::Gif.base() + MADR_OFFSET, 0x0400_0000);
+		dmac.write_u32(ChannelId::Gif.base() + QWC_OFFSET, 1);
+		start(&mut dmac, ChannelId::Gif, Chcr::empty());
+		run(&mut dmac, &mut memory);
+
+		assert!(!dmac.channels[ChannelId::Gif as usize].active());
+		assert!(dmac.stat.contains(DStat::BUS_ERROR_INTERRUPT));
+		assert!(dmac.interrupt_pending());
+	}
+}
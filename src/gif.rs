@@ -0,0 +1,937 @@
+//! The Graphics Interface (GIF), the EE's only way of reaching the GS.
+//!
+//! Data arrives over three paths:
+//! * PATH1: VU1's `XGKICK`, from VU1 data memory.
+//! * PATH2: VIF1's `DIRECT`/`DIRECTHL` commands.
+//! * PATH3: DMAC channel 2, or stores to the GIF FIFO.
+//!
+//! Each path delivers packets, each headed by a GIFtag describing the data
+//! following it, which the GIF turns into writes to the GS's registers.
+//! Only one path may own the GIF at a time: once a path starts a packet, the
+//! GIF stays with it until a tag with `EOP` set has been consumed. When the
+//! GIF is free, PATH1 has priority over PATH2, which has priority over PATH3.
+//!
+//! In the other direction, local-to-host transfers are read back through the
+//! PATH3 FIFO, either by DMA or by quadword loads.
+//!
+//! Register layout and tag formats follow https://psi-rockin.github.io/ps2tek/.
+
+use bitflags::bitflags;
+use byteorder::{
+	LittleEndian,
+	ReadBytesExt,
+	WriteBytesExt,
+};
+use crate::{
+	dmac::DmaPeripheral,
+	gs::registers::GsRegister,
+	memory::bus::Device,
+};
+use std::{
+	cell::RefCell,
+	collections::VecDeque,
+	io::{
+		self,
+		Read,
+		Write,
+	},
+	mem,
+	rc::Rc,
+};
+
+/// Physical address of the GIF's register block.
+pub const GIF_PHYSICAL: u32 = GIF_CTRL_PHYSICAL;
+
+/// Length of the GIF's register block.
+pub const GIF_SIZE: u32 = 0xb0;
+
+pub const GIF_CTRL_PHYSICAL: u32 = 0x1000_3000;
+pub const GIF_MODE_PHYSICAL: u32 = 0x1000_3010;
+pub const GIF_STAT_PHYSICAL: u32 = 0x1000_3020;
+pub const GIF_TAG0_PHYSICAL: u32 = 0x1000_3040;
+pub const GIF_TAG1_PHYSICAL: u32 = 0x1000_3050;
+pub const GIF_TAG2_PHYSICAL: u32 = 0x1000_3060;
+pub const GIF_TAG3_PHYSICAL: u32 = 0x1000_3070;
+pub const GIF_CNT_PHYSICAL: u32 = 0x1000_3080;
+pub const GIF_P3CNT_PHYSICAL: u32 = 0x1000_3090;
+pub const GIF_P3TAG_PHYSICAL: u32 = 0x1000_30a0;
+
+/// Physical address of the PATH3 FIFO, which only takes quadword stores.
+pub const GIF_FIFO_PHYSICAL: u32 = 0x1000_6000;
+
+/// Length of the PATH3 FIFO's window.
+pub const GIF_FIFO_SIZE: u32 = 0x10;
+
+/// Quadwords the PATH3 FIFO can hold while PATH3 is waiting for the GIF.
+pub const PATH3_FIFO_QWORDS: usize = 16;
+
+bitflags!{
+/// Flags contained within `GIF_CTRL` (write-only).
+pub struct GifCtrl: u32 {
+	/// Reset the GIF, discarding any packets in progress.
+	const RESET = 0b0001;
+
+	/// Pause transfers on all paths.
+	const PAUSE = 0b1000;
+}
+}
+
+bitflags!{
+/// Flags contained within `GIF_MODE` (write-only).
+pub struct GifMode: u32 {
+	/// Mask PATH3 from starting new packets.
+	const MASK_PATH3   = 0b0001;
+
+	/// Intermittent mode: let other paths interrupt PATH3 `IMAGE` transfers.
+	const INTERMITTENT = 0b0100;
+}
+}
+
+bitflags!{
+/// Flags contained within `GIF_STAT` (read-only).
+pub struct GifStat: u32 {
+	/// PATH3 masked by `GIF_MODE`.
+	const M3R      = 0b0000_0000_0001;
+
+	/// PATH3 masked by VIF1's `MSKPATH3`.
+	const M3P      = 0b0000_0000_0010;
+
+	/// Intermittent mode enabled in `GIF_MODE`.
+	const IMT      = 0b0000_0000_0100;
+
+	/// Transfers paused by `GIF_CTRL`.
+	const PSE      = 0b0000_0000_1000;
+
+	/// PATH3 interrupted by intermittent mode.
+	const IP3      = 0b0000_0010_0000;
+
+	/// PATH3 has data waiting for the GIF.
+	const P3Q      = 0b0000_0100_0000;
+
+	/// PATH2 has data waiting for the GIF.
+	const P2Q      = 0b0000_1000_0000;
+
+	/// PATH1 has data waiting for the GIF.
+	const P1Q      = 0b0001_0000_0000;
+
+	/// A packet is being output to the GS.
+	const OPH      = 0b0010_0000_0000;
+
+	const APATH_B0 = 0b0100_0000_0000;
+	const APATH_B1 = 0b1000_0000_0000;
+
+	/// 2-bit field holding the path owning the GIF:
+	/// * `00` => idle.
+	/// * `01` => PATH1.
+	/// * `10` => PATH2.
+	/// * `11` => PATH3.
+	const APATH = Self::APATH_B0.bits
+		| Self::APATH_B1.bits;
+}
+}
+
+impl Default for GifCtrl {
+	fn default() -> Self {
+		Self::empty()
+	}
+}
+
+impl Default for GifMode {
+	fn default() -> Self {
+		Self::empty()
+	}
+}
+
+/// Shift of `GIF_STAT.FQC`, the count of quadwords in the PATH3 FIFO.
+const FQC_SHIFT: u32 = 24;
+
+/// The paths into the GIF, in decreasing order of priority.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GifPath {
+	Path1,
+	Path2,
+	Path3,
+}
+
+const PATHS: [GifPath; 3] = [GifPath::Path1, GifPath::Path2, GifPath::Path3];
+
+impl GifPath {
+	/// The path's number, as reported in `GIF_STAT.APATH`.
+	pub fn number(self) -> u8 {
+		self as u8 + 1
+	}
+
+	pub fn from_number(number: u8) -> Option<Self> {
+		PATHS.get(usize::from(number).wrapping_sub(1)).copied()
+	}
+}
+
+/// How the data following a GIFtag is to be interpreted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataFormat {
+	/// One register per quadword, converted according to its descriptor.
+	Packed,
+
+	/// One register per doubleword, written unconverted.
+	Reglist,
+
+	/// Raw doublewords for `HWREG`.
+	Image,
+}
+
+/// Header of each GIF packet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GifTag {
+	/// Number of times the register list is repeated (or quadwords, for `IMAGE`).
+	pub nloop: u16,
+
+	/// Whether this is the last tag of the packet.
+	pub eop: bool,
+
+	/// Whether `prim` should be written to `PRIM` (`PACKED` only).
+	pub pre: bool,
+
+	pub prim: u16,
+
+	pub format: DataFormat,
+
+	/// Number of register descriptors in `regs` (1--16).
+	pub nreg: u8,
+
+	/// Register descriptors, 4 bits each, used from the lowest.
+	pub regs: u64,
+}
+
+impl GifTag {
+	/// The `index`th register descriptor.
+	#[inline]
+	pub fn descriptor(&self, index: u8) -> u8 {
+		((self.regs >> (u32::from(index) * 4)) & 0xf) as u8
+	}
+}
+
+impl From<u128> for GifTag {
+	fn from(raw: u128) -> Self {
+		let nreg = ((raw >> 60) & 0xf) as u8;
+
+		Self {
+			nloop: (raw & 0x7fff) as u16,
+			eop: (raw >> 15) & 1 != 0,
+			pre: (raw >> 46) & 1 != 0,
+			prim: ((raw >> 47) & 0x7ff) as u16,
+			format: match (raw >> 58) & 0b11 {
+				0 => DataFormat::Packed,
+				1 => DataFormat::Reglist,
+				_ => DataFormat::Image,
+			},
+			nreg: if nreg == 0 { 16 } else { nreg },
+			regs: (raw >> 64) as u64,
+		}
+	}
+}
+
+/// `PACKED` register descriptors which are converted rather than passed through.
+pub mod packed_descriptor {
+	pub const PRIM: u8 = 0x0;
+	pub const RGBAQ: u8 = 0x1;
+	pub const ST: u8 = 0x2;
+	pub const UV: u8 = 0x3;
+	pub const XYZF2: u8 = 0x4;
+	pub const XYZ2: u8 = 0x5;
+	pub const FOG: u8 = 0xa;
+
+	/// Address and data: the register is in bits 64--71.
+	pub const A_D: u8 = 0xe;
+	pub const NOP: u8 = 0xf;
+}
+
+/// Receiver of the GIF's output: the GS.
+pub trait GsSink {
+	/// Write `value` to the GS register at `register`.
+	fn write_register(&mut self, register: u8, value: u64);
+
+	/// Read the next word of a local-to-host transfer, if one is in progress.
+	fn read_transfer(&mut self) -> Option<u64> {
+		None
+	}
+}
+
+pub type SharedGsSink = Rc<RefCell<dyn GsSink>>;
+
+/// Called with each GIFtag and its data once it has been output to the GS.
+pub type CaptureHook = Box<dyn FnMut(GifPath, &[u128])>;
+
+#[derive(Clone, Debug, Default)]
+struct PathState {
+	/// Quadwords waiting for the GIF.
+	queue: VecDeque<u128>,
+
+	/// Tag whose data is being consumed, if any.
+	tag: Option<GifTag>,
+
+	/// The most recent tag read, as written.
+	raw_tag: u128,
+
+	loops_left: u16,
+	reg_index: u8,
+
+	/// Quadwords of the current tag, kept while capturing.
+	captured: Vec<u128>,
+}
+
+#[derive(Default)]
+pub struct Gif {
+	pub ctrl: GifCtrl,
+	pub mode: GifMode,
+
+	/// Set by VIF1's `MSKPATH3` to hold off PATH3.
+	pub path3_masked: bool,
+
+	paths: [PathState; 3],
+
+	/// Path owning the GIF until its packet ends.
+	active: Option<GifPath>,
+
+	/// Path whose tag is shown in `GIF_TAG0`--`GIF_TAG3` and `GIF_CNT`.
+	last_path: Option<GifPath>,
+
+	/// `Q` from the last `PACKED` `ST`, used to fill `RGBAQ`.
+	q: u32,
+
+	gs: Option<SharedGsSink>,
+	capture: Option<CaptureHook>,
+}
+
+impl Gif {
+	/// Send output to `gs`.
+	pub fn connect(&mut self, gs: SharedGsSink) {
+		self.gs = Some(gs);
+	}
+
+	/// Start (or, with `None`, stop) passing each processed tag to `hook`.
+	pub fn set_capture(&mut self, hook: Option<CaptureHook>) {
+		for state in self.paths.iter_mut() {
+			state.captured.clear();
+		}
+
+		self.capture = hook;
+	}
+
+	/// Record each processed tag and its data to `writer`, to be read back with
+	/// [`read_capture`](fn.read_capture.html).
+	pub fn capture_to<W: Write + 'static>(&mut self, mut writer: W) {
+		self.set_capture(Some(Box::new(move |path, qwords| {
+			if let Err(e) = write_capture_record(&mut writer, path, qwords) {
+				warn!("GIF: failed to record capture: {}", e);
+			}
+		})));
+	}
+
+	/// Queue `qword` on `path`, or return `false` if the PATH3 FIFO is full.
+	///
+	/// Queued data is output immediately unless another path owns the GIF,
+	/// or the path is masked or paused.
+	pub fn push(&mut self, path: GifPath, qword: u128) -> bool {
+		let state = &mut self.paths[path as usize];
+
+		if path == GifPath::Path3 && state.queue.len() >= PATH3_FIFO_QWORDS {
+			return false;
+		}
+
+		state.queue.push_back(qword);
+		self.process();
+		true
+	}
+
+	/// Hold off (or release) PATH3, as VIF1's `MSKPATH3` does.
+	pub fn set_path3_masked(&mut self, masked: bool) {
+		self.path3_masked = masked;
+		self.process();
+	}
+
+	/// Read a quadword of a local-to-host transfer from the GS, if one is in progress.
+	///
+	/// A transfer ending halfway through a quadword has its upper half zeroed.
+	pub fn read_fifo(&mut self) -> Option<u128> {
+		let mut gs = self.gs.as_ref()?.borrow_mut();
+		let low = gs.read_transfer()?;
+		let high = gs.read_transfer().unwrap_or(0);
+
+		Some(u128::from(low) | (u128::from(high) << 64))
+	}
+
+	/// Whether `path` has unprocessed data, or is partway through a packet.
+	pub fn busy(&self, path: GifPath) -> bool {
+		self.active == Some(path) || !self.paths[path as usize].queue.is_empty()
+	}
+
+	/// Current value of `GIF_STAT`.
+	pub fn stat(&self) -> u32 {
+		let mut stat = GifStat::empty();
+
+		stat.set(GifStat::M3R, self.mode.contains(GifMode::MASK_PATH3));
+		stat.set(GifStat::M3P, self.path3_masked);
+		stat.set(GifStat::IMT, self.mode.contains(GifMode::INTERMITTENT));
+		stat.set(GifStat::PSE, self.ctrl.contains(GifCtrl::PAUSE));
+		stat.set(GifStat::OPH, self.active.is_some());
+
+		for (&path, &queued) in PATHS.iter().zip(&[GifStat::P1Q, GifStat::P2Q, GifStat::P3Q]) {
+			stat.set(queued, self.active != Some(path) && !self.paths[path as usize].queue.is_empty());
+		}
+
+		let apath = self.active.map_or(0, |path| u32::from(path.number()));
+		let fqc = self.paths[GifPath::Path3 as usize].queue.len() as u32;
+
+		stat.bits() | (apath << 10) | (fqc << FQC_SHIFT)
+	}
+
+	/// Read the register at physical address `p_addr`.
+	pub fn read(&self, p_addr: u32) -> u32 {
+		let shown = &self.paths[self.last_path.map_or(0, |path| path as usize)];
+		let path3 = &self.paths[GifPath::Path3 as usize];
+
+		match p_addr & !0b11 {
+			GIF_STAT_PHYSICAL => self.stat(),
+			GIF_TAG0_PHYSICAL => shown.raw_tag as u32,
+			GIF_TAG1_PHYSICAL => (shown.raw_tag >> 32) as u32,
+			GIF_TAG2_PHYSICAL => (shown.raw_tag >> 64) as u32,
+			GIF_TAG3_PHYSICAL => (shown.raw_tag >> 96) as u32,
+			GIF_CNT_PHYSICAL => u32::from(shown.loops_left) | (u32::from(shown.reg_index) << 16),
+			GIF_P3CNT_PHYSICAL => u32::from(path3.loops_left),
+			GIF_P3TAG_PHYSICAL => path3.raw_tag as u32 & 0xffff,
+			_ => 0,
+		}
+	}
+
+	/// Write `value` to the register at physical address `p_addr`.
+	pub fn write(&mut self, p_addr: u32, value: u32) {
+		match p_addr & !0b11 {
+			GIF_CTRL_PHYSICAL => {
+				let ctrl = GifCtrl::from_bits_truncate(value);
+
+				if ctrl.contains(GifCtrl::RESET) {
+					self.reset();
+				}
+
+				self.ctrl = ctrl - GifCtrl::RESET;
+			},
+			GIF_MODE_PHYSICAL => self.mode = GifMode::from_bits_truncate(value),
+			_ => return,
+		}
+
+		self.process();
+	}
+
+	/// Drop all queued data and packets in progress.
+	fn reset(&mut self) {
+		trace!("GIF: reset");
+
+		for state in self.paths.iter_mut() {
+			*state = PathState::default();
+		}
+
+		self.mode = GifMode::empty();
+		self.active = None;
+		self.last_path = None;
+	}
+
+	fn path3_blocked(&self) -> bool {
+		self.path3_masked || self.mode.contains(GifMode::MASK_PATH3)
+	}
+
+	/// Output queued data for as long as some path may own the GIF.
+	fn process(&mut self) {
+		while !self.ctrl.contains(GifCtrl::PAUSE) {
+			let path = match self.active {
+				Some(path) => path,
+				None => match PATHS.iter()
+					.copied()
+					.filter(|&path| path != GifPath::Path3 || !self.path3_blocked())
+					.find(|&path| !self.paths[path as usize].queue.is_empty()) {
+					Some(path) => path,
+					None => break,
+				},
+			};
+
+			let qword = match self.paths[path as usize].queue.pop_front() {
+				Some(qword) => qword,
+				None => break,
+			};
+
+			self.active = Some(path);
+			self.last_path = Some(path);
+			self.consume(path, qword);
+		}
+	}
+
+	fn consume(&mut self, path: GifPath, qword: u128) {
+		let index = path as usize;
+
+		if self.capture.is_some() {
+			self.paths[index].captured.push(qword);
+		}
+
+		let tag = match self.paths[index].tag {
+			Some(tag) => tag,
+			None => return self.begin_tag(path, qword),
+		};
+
+		match tag.format {
+			DataFormat::Packed => {
+				let descriptor = tag.descriptor(self.paths[index].reg_index);
+				self.write_packed(descriptor, qword);
+				self.next_register(path);
+			},
+			DataFormat::Reglist => {
+				for &data in &[qword as u64, (qword >> 64) as u64] {
+					// An odd number of registers leaves the last quadword half padding.
+					if self.paths[index].tag.is_none() {
+						break;
+					}
+
+					let descriptor = tag.descriptor(self.paths[index].reg_index);
+					if descriptor < packed_descriptor::A_D {
+						self.write_gs(descriptor, data);
+					}
+
+					self.next_register(path);
+				}
+			},
+			DataFormat::Image => {
+				self.write_gs(GsRegister::HwReg as u8, qword as u64);
+				self.write_gs(GsRegister::HwReg as u8, (qword >> 64) as u64);
+				self.next_loop(path);
+			},
+		}
+	}
+
+	fn begin_tag(&mut self, path: GifPath, raw: u128) {
+		let tag = GifTag::from(raw);
+		trace!("GIF: {:?} tag {:?}", path, tag);
+
+		let state = &mut self.paths[path as usize];
+		state.raw_tag = raw;
+		state.loops_left = tag.nloop;
+		state.reg_index = 0;
+
+		// Each tag starts with Q = 1.0.
+		self.q = 1.0f32.to_bits();
+
+		if tag.pre && tag.format == DataFormat::Packed {
+			self.write_gs(GsRegister::Prim as u8, u64::from(tag.prim));
+		}
+
+		if tag.nloop == 0 {
+			self.end_tag(path);
+		} else {
+			self.paths[path as usize].tag = Some(tag);
+		}
+	}
+
+	fn next_register(&mut self, path: GifPath) {
+		let state = &mut self.paths[path as usize];
+		let nreg = state.tag.map_or(1, |tag| tag.nreg);
+
+		state.reg_index += 1;
+		if state.reg_index >= nreg {
+			state.reg_index = 0;
+			self.next_loop(path);
+		}
+	}
+
+	fn next_loop(&mut self, path: GifPath) {
+		let state = &mut self.paths[path as usize];
+
+		state.loops_left -= 1;
+		if state.loops_left == 0 {
+			self.end_tag(path);
+		}
+	}
+
+	fn end_tag(&mut self, path: GifPath) {
+		let state = &mut self.paths[path as usize];
+		state.tag = None;
+
+		if let Some(hook) = &mut self.capture {
+			let qwords = mem::take(&mut state.captured);
+			hook(path, &qwords);
+		}
+
+		if GifTag::from(state.raw_tag).eop {
+			trace!("GIF: {:?} packet done", path);
+			self.active = None;
+		}
+	}
+
+	/// Convert a `PACKED` quadword according to its register descriptor.
+	fn write_packed(&mut self, descriptor: u8, qword: u128) {
+		use packed_descriptor::*;
+
+		let field = |shift: u32, bits: u32| ((qword >> shift) as u64) & ((1 << bits) - 1);
+		let adc = field(111, 1) != 0;
+
+		match descriptor {
+			PRIM => self.write_gs(GsRegister::Prim as u8, field(0, 11)),
+			RGBAQ => {
+				let rgba = field(0, 8) | (field(32, 8) << 8) | (field(64, 8) << 16) | (field(96, 8) << 24);
+				self.write_gs(GsRegister::Rgbaq as u8, rgba | (u64::from(self.q) << 32));
+			},
+			ST => {
+				self.q = field(64, 32) as u32;
+				self.write_gs(GsRegister::St as u8, qword as u64);
+			},
+			UV => self.write_gs(GsRegister::Uv as u8, field(0, 14) | (field(32, 14) << 16)),
+			XYZF2 => {
+				let register = if adc { GsRegister::Xyzf3 } else { GsRegister::Xyzf2 };
+				let xyzf = field(0, 16) | (field(32, 16) << 16) | (field(68, 24) << 32) | (field(100, 8) << 56);
+				self.write_gs(register as u8, xyzf);
+			},
+			XYZ2 => {
+				let register = if adc { GsRegister::Xyz3 } else { GsRegister::Xyz2 };
+				let xyz = field(0, 16) | (field(32, 16) << 16) | (field(64, 32) << 32);
+				self.write_gs(register as u8, xyz);
+			},
+			FOG => self.write_gs(GsRegister::Fog as u8, field(100, 8) << 56),
+			A_D => self.write_gs(field(64, 8) as u8, qword as u64),
+			NOP => {},
+			_ => self.write_gs(descriptor, qword as u64),
+		}
+	}
+
+	fn write_gs(&mut self, register: u8, value: u64) {
+		trace!("GIF: GS register 0x{:02x} <- 0x{:016x}", register, value);
+
+		if let Some(gs) = &self.gs {
+			gs.borrow_mut().write_register(register, value);
+		}
+	}
+}
+
+impl Device for Gif {
+	fn read_u32(&mut self, p_addr: u32) -> u32 {
+		self.read(p_addr)
+	}
+
+	fn write_u32(&mut self, p_addr: u32, value: u32) {
+		self.write(p_addr, value);
+	}
+
+	fn read_u128(&mut self, p_addr: u32) -> u128 {
+		if p_addr & !0xf == GIF_FIFO_PHYSICAL {
+			self.read_fifo().unwrap_or(0)
+		} else {
+			u128::from(self.read_u64(p_addr)) | (u128::from(self.read_u64(p_addr + 8)) << 64)
+		}
+	}
+
+	fn write_u128(&mut self, p_addr: u32, value: u128) {
+		if p_addr & !0xf == GIF_FIFO_PHYSICAL {
+			if !self.push(GifPath::Path3, value) {
+				warn!("GIF: PATH3 FIFO full, dropping 0x{:032x}", value);
+			}
+		} else {
+			for word in 0..4 {
+				self.write(p_addr + word * 4, (value >> (word * 32)) as u32);
+			}
+		}
+	}
+}
+
+impl DmaPeripheral for Gif {
+	fn dma_write(&mut self, data: u128) -> bool {
+		self.push(GifPath::Path3, data)
+	}
+
+	fn dma_read(&mut self) -> Option<u128> {
+		self.read_fifo()
+	}
+}
+
+/// Append one captured tag to `writer`: the path number, a little-endian
+/// quadword count, then the quadwords themselves.
+pub fn write_capture_record<W: Write>(writer: &mut W, path: GifPath, qwords: &[u128]) -> io::Result<()> {
+	writer.write_u8(path.number())?;
+	writer.write_u32::<LittleEndian>(qwords.len() as u32)?;
+
+	for &qword in qwords {
+		writer.write_u128::<LittleEndian>(qword)?;
+	}
+
+	writer.flush()
+}
+
+/// Read back every record written by [`Gif::capture_to`](struct.Gif.html#method.capture_to).
+pub fn read_capture<R: Read>(reader: &mut R) -> io::Result<Vec<(GifPath, Vec<u128>)>> {
+	let mut records = vec![];
+
+	loop {
+		let path = match reader.read_u8() {
+			Ok(number) => GifPath::from_number(number)
+				.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad GIF path"))?,
+			Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(records),
+			Err(e) => return Err(e),
+		};
+
+		let len = reader.read_u32::<LittleEndian>()?;
+		let qwords = (0..len)
+			.map(|_| reader.read_u128::<LittleEndian>())
+			.collect::<io::Result<_>>()?;
+
+		records.push((path, qwords));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Default)]
+	struct Recorder {
+		writes: Vec<(u8, u64)>,
+	}
+
+	impl GsSink for Recorder {
+		fn write_register(&mut self, register: u8, value: u64) {
+			self.writes.push((register, value));
+		}
+	}
+
+	fn connected_gif() -> (Gif, Rc<RefCell<Recorder>>) {
+		let gs = Rc::new(RefCell::new(Recorder::default()));
+		let mut gif = Gif::default();
+		gif.connect(gs.clone());
+		(gif, gs)
+	}
+
+	fn tag(nloop: u16, eop: bool, format: u128, regs: &[u8]) -> u128 {
+		let descriptors = regs.iter()
+			.enumerate()
+			.fold(0u128, |acc, (i, &reg)| acc | (u128::from(reg) << (i * 4)));
+
+		u128::from(nloop)
+			| (u128::from(eop) << 15)
+			| (format << 58)
+			| ((regs.len() as u128 & 0xf) << 60)
+			| (descriptors << 64)
+	}
+
+	#[test]
+	fn tag_fields_parse() {
+		let raw = tag(3, true, 1, &[0x1, 0x5]) | (1 << 46) | (0x6 << 47);
+		let parsed = GifTag::from(raw);
+
+		assert_eq!(parsed.nloop, 3);
+		assert!(parsed.eop);
+		assert!(parsed.pre);
+		assert_eq!(parsed.prim, 0x6);
+		assert_eq!(parsed.format, DataFormat::Reglist);
+		assert_eq!(parsed.nreg, 2);
+		assert_eq!(parsed.descriptor(1), 0x5);
+
+		// NREG of 0 means all 16 descriptors.
+		assert_eq!(GifTag::from(0u128).nreg, 16);
+	}
+
+	#[test]
+	fn packed_conversions() {
+		let (mut gif, gs) = connected_gif();
+		let q = 2.0f32.to_bits();
+
+		gif.push(GifPath::Path3, tag(1, true, 0, &[0x2, 0x1, 0x4, 0x5, 0xe, 0xf]) | (1 << 46) | (0x3 << 47));
+		// ST, saving Q.
+		gif.push(GifPath::Path3, (u128::from(q) << 64) | 0x2222_2222_1111_1111);
+		// RGBAQ.
+		gif.push(GifPath::Path3, (0x44 << 96) | (0x33 << 64) | (0x22 << 32) | 0x11);
+		// XYZF2, with ADC selecting XYZF3.
+		gif.push(GifPath::Path3, (1 << 111) | (0x56 << 100) | (0xab_cdef << 68) | (0x2000 << 32) | 0x1000);
+		// XYZ2.
+		gif.push(GifPath::Path3, (0x1234_5678 << 64) | (0x20 << 32) | 0x10);
+		// A+D.
+		gif.push(GifPath::Path3, (0x4c << 64) | 0xdead_beef);
+		// NOP.
+		gif.push(GifPath::Path3, !0);
+
+		assert_eq!(gs.borrow().writes, vec![
+			(GsRegister::Prim as u8, 0x3),
+			(GsRegister::St as u8, 0x2222_2222_1111_1111),
+			(GsRegister::Rgbaq as u8, (u64::from(q) << 32) | 0x4433_2211),
+			(GsRegister::Xyzf3 as u8, (0x56 << 56) | (0xab_cdef << 32) | 0x2000_1000),
+			(GsRegister::Xyz2 as u8, (0x1234_5678 << 32) | 0x0020_0010),
+			(GsRegister::Frame1 as u8, 0xdead_beef),
+		]);
+		assert_eq!(gif.stat() & GifStat::OPH.bits(), 0);
+	}
+
+	#[test]
+	fn reglist_discards_padding() {
+		let (mut gif, gs) = connected_gif();
+
+		// Three registers over two loops: three quadwords, no padding.
+		gif.push(GifPath::Path2, tag(2, false, 1, &[0x0, 0x1, 0xf]));
+		gif.push(GifPath::Path2, (2 << 64) | 1);
+		gif.push(GifPath::Path2, (4 << 64) | 3);
+		gif.push(GifPath::Path2, (6 << 64) | 5);
+
+		// Three registers once: the last half is padding.
+		gif.push(GifPath::Path2, tag(1, true, 1, &[0x6, 0x7, 0x8]));
+		gif.push(GifPath::Path2, (8 << 64) | 7);
+		gif.push(GifPath::Path2, (0xff << 64) | 9);
+
+		assert_eq!(gs.borrow().writes, vec![
+			(0x0, 1), (0x1, 2), (0x0, 4), (0x1, 5),
+			(0x6, 7), (0x7, 8), (0x8, 9),
+		]);
+		assert!(!gif.busy(GifPath::Path2));
+	}
+
+	#[test]
+	fn image_writes_hwreg() {
+		let (mut gif, gs) = connected_gif();
+
+		gif.push(GifPath::Path3, tag(2, true, 2, &[]));
+		gif.push(GifPath::Path3, (2 << 64) | 1);
+		assert!(gif.busy(GifPath::Path3));
+		gif.push(GifPath::Path3, (4 << 64) | 3);
+
+		let hwreg = GsRegister::HwReg as u8;
+		assert_eq!(gs.borrow().writes, vec![(hwreg, 1), (hwreg, 2), (hwreg, 3), (hwreg, 4)]);
+		assert!(!gif.busy(GifPath::Path3));
+	}
+
+	#[test]
+	fn path_owns_gif_until_eop() {
+		let (mut gif, gs) = connected_gif();
+
+		// PATH3 starts a two-tag packet...
+		gif.push(GifPath::Path3, tag(1, false, 1, &[0x0]));
+		gif.push(GifPath::Path3, 3);
+		assert_eq!(gif.stat() & GifStat::APATH.bits(), 3 << 10);
+
+		// ...which PATH1 must wait for, despite its priority.
+		gif.push(GifPath::Path1, tag(1, true, 1, &[0x0]));
+		gif.push(GifPath::Path1, 1);
+		assert_ne!(gif.stat() & GifStat::P1Q.bits(), 0);
+
+		gif.push(GifPath::Path3, tag(1, true, 1, &[0x0]));
+		gif.push(GifPath::Path3, 33);
+
+		assert_eq!(gs.borrow().writes, vec![(0, 3), (0, 33), (0, 1)]);
+		assert_eq!(gif.stat() & (GifStat::APATH | GifStat::P1Q).bits(), 0);
+	}
+
+	#[test]
+	fn idle_gif_takes_highest_priority_path() {
+		let (mut gif, gs) = connected_gif();
+
+		gif.write(GIF_CTRL_PHYSICAL, GifCtrl::PAUSE.bits());
+		for (path, value) in PATHS.iter().rev().zip(&[3, 2, 1]) {
+			gif.push(*path, tag(1, true, 1, &[0x0]));
+			gif.push(*path, *value);
+		}
+		assert_eq!(gif.stat() >> FQC_SHIFT, 2);
+		assert!(gs.borrow().writes.is_empty());
+
+		gif.write(GIF_CTRL_PHYSICAL, 0);
+		assert_eq!(gs.borrow().writes, vec![(0, 1), (0, 2), (0, 3)]);
+	}
+
+	#[test]
+	fn masked_path3_fills_fifo() {
+		let (mut gif, gs) = connected_gif();
+
+		gif.write(GIF_MODE_PHYSICAL, GifMode::MASK_PATH3.bits());
+		gif.push(GifPath::Path3, tag(PATH3_FIFO_QWORDS as u16, true, 0, &[0x0]));
+		for i in 1..PATH3_FIFO_QWORDS {
+			assert!(gif.dma_write(i as u128));
+		}
+		assert!(!gif.dma_write(0));
+		assert_eq!(gif.stat() & (GifStat::M3R | GifStat::P3Q).bits(), (GifStat::M3R | GifStat::P3Q).bits());
+		assert!(gs.borrow().writes.is_empty());
+
+		gif.write(GIF_MODE_PHYSICAL, 0);
+		assert_eq!(gs.borrow().writes.len(), PATH3_FIFO_QWORDS - 1);
+		assert!(gif.dma_write(0));
+	}
+
+	#[test]
+	fn reset_drops_packets() {
+		let (mut gif, gs) = connected_gif();
+
+		gif.push(GifPath::Path1, tag(2, true, 0, &[0x0]));
+		gif.push(GifPath::Path1, 1);
+		gif.write_u32(GIF_CTRL_PHYSICAL, GifCtrl::RESET.bits());
+		assert_eq!(gif.stat(), 0);
+
+		gif.write_u128(GIF_FIFO_PHYSICAL, tag(1, true, 1, &[0x0]));
+		gif.write_u128(GIF_FIFO_PHYSICAL, 2);
+		assert_eq!(gs.borrow().writes, vec![(0, 1), (0, 2)]);
+	}
+
+	#[test]
+	fn tag_registers_show_last_tag() {
+		let (mut gif, _) = connected_gif();
+		let raw = tag(3, true, 0, &[0x1, 0x2]);
+
+		gif.push(GifPath::Path2, raw);
+		gif.push(GifPath::Path2, 0);
+
+		assert_eq!(gif.read(GIF_TAG0_PHYSICAL), raw as u32);
+		assert_eq!(gif.read(GIF_TAG3_PHYSICAL), (raw >> 96) as u32);
+		assert_eq!(gif.read(GIF_CNT_PHYSICAL), 3 | (1 << 16));
+	}
+
+	#[test]
+	fn capture_round_trips() {
+		let (mut gif, _) = connected_gif();
+		let captured = Rc::new(RefCell::new(vec![]));
+
+		let sink = captured.clone();
+		gif.set_capture(Some(Box::new(move |path, qwords| {
+			let mut record = vec![];
+			write_capture_record(&mut record, path, qwords).unwrap();
+			sink.borrow_mut().extend(record);
+		})));
+
+		let first = [tag(1, false, 0, &[0x0]), 5];
+		let second = [tag(0, true, 0, &[])];
+		for &qword in first.iter().chain(&second) {
+			gif.push(GifPath::Path1, qword);
+		}
+
+		let records = read_capture(&mut captured.borrow().as_slice()).unwrap();
+		assert_eq!(records, vec![
+			(GifPath::Path1, first.to_vec()),
+			(GifPath::Path1, second.to_vec()),
+		]);
+	}
+
+	#[test]
+	fn image_reads_back_through_fifo() {
+		use crate::gs::{
+			memory::Psm,
+			Gs,
+		};
+
+		let gs = Rc::new(RefCell::new(Gs::default()));
+		let mut gif = Gif::default();
+		gif.connect(gs.clone());
+
+		// Upload a 3×1 PSMCT32 rectangle with an IMAGE tag, then start reading it back.
+		let a_d = |register: GsRegister, value: u64| (u128::from(register as u8) << 64) | u128::from(value);
+		gif.push(GifPath::Path3, tag(4, false, 0, &[packed_descriptor::A_D]));
+		gif.push(GifPath::Path3, a_d(GsRegister::BitBltBuf, (1 << 16) | (1 << 48)));
+		gif.push(GifPath::Path3, a_d(GsRegister::TrxPos, 0));
+		gif.push(GifPath::Path3, a_d(GsRegister::TrxReg, 3 | (1 << 32)));
+		gif.push(GifPath::Path3, a_d(GsRegister::TrxDir, 0));
+		gif.push(GifPath::Path3, tag(2, false, 2, &[]));
+		gif.push(GifPath::Path3, 0x4444_4444_3333_3333_2222_2222_1111_1111);
+		gif.push(GifPath::Path3, 0x5555_5555);
+		assert_eq!(gs.borrow().memory.read_pixel(Psm::Ct32, 0, 1, 2, 0), 0x3333_3333);
+
+		gif.push(GifPath::Path3, tag(1, true, 0, &[packed_descriptor::A_D]));
+		gif.push(GifPath::Path3, a_d(GsRegister::TrxDir, 1));
+
+		assert_eq!(gif.dma_read(), Some(0x0000_0000_3333_3333_2222_2222_1111_1111));
+		assert_eq!(gif.dma_read(), None);
+	}
+}
@@ -0,0 +1,710 @@
+//! Rasterization of primitives, and the per-pixel pipeline writing them to local memory.
+//!
+//! Coordinates are kept in the GS's 12.4 fixed point. Pixels are sampled at
+//! their integer coordinates, and triangle edges follow a top-left fill rule so
+//! that strips and fans touch each pixel once.
+//!
+//! Texture filtering is always nearest-neighbour, and dithering and
+//! antialiasing are not emulated.
+
+use super::{
+	memory::{
+		LocalMemory,
+		Psm,
+		BLOCKS_PER_PAGE,
+	},
+	registers::*,
+	texture::{
+		self,
+		Clut,
+	},
+};
+use enum_primitive::*;
+
+/// Alpha test comparisons, as in `TEST.ATST`.
+const ATST_NEVER: u8 = 0;
+const ATST_ALWAYS: u8 = 1;
+const ATST_LESS: u8 = 2;
+const ATST_LEQUAL: u8 = 3;
+const ATST_EQUAL: u8 = 4;
+const ATST_GEQUAL: u8 = 5;
+const ATST_GREATER: u8 = 6;
+
+/// What is still written when the alpha test fails, as in `TEST.AFAIL`.
+const AFAIL_KEEP: u8 = 0;
+const AFAIL_FB_ONLY: u8 = 1;
+const AFAIL_ZB_ONLY: u8 = 2;
+
+/// Depth test comparisons, as in `TEST.ZTST`.
+const ZTST_NEVER: u8 = 0;
+const ZTST_ALWAYS: u8 = 1;
+const ZTST_GEQUAL: u8 = 2;
+
+/// A vertex, as assembled from `RGBAQ`, `ST`, `UV`, `FOG` and `XYZ*`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vertex {
+	/// Position in primitive coordinates, in 12.4 fixed point.
+	pub x: i32,
+	pub y: i32,
+	pub z: u32,
+	pub fog: u8,
+	pub rgba: [u8; 4],
+	pub s: f32,
+	pub t: f32,
+	pub q: f32,
+
+	/// Texel coordinates, in 10.4 fixed point.
+	pub u: u32,
+	pub v: u32,
+}
+
+/// The drawing environment in effect for one primitive.
+pub struct DrawEnv<'a> {
+	pub prim: Prim,
+	pub frame: FrameBuffer,
+	pub zbuf: ZBuffer,
+	pub tex0: Tex0,
+	pub clamp: Clamp,
+	pub texa: Texa,
+	pub test: Test,
+	pub alpha: Alpha,
+	pub scissor: Scissor,
+	pub offset: XyOffset,
+	pub fog_colour: [u8; 3],
+
+	/// Force the alpha MSB of written pixels (`FBA`).
+	pub fba: bool,
+
+	/// Only blend pixels whose alpha MSB is set (`PABE`).
+	pub pabe: bool,
+
+	/// Clamp (rather than wrap) blended colours (`COLCLAMP`).
+	pub colclamp: bool,
+
+	pub clut: &'a Clut,
+}
+
+impl<'a> DrawEnv<'a> {
+	/// Decode the environment selected by `prim`'s context from the general registers.
+	pub fn new(registers: &[u64; GS_REGISTER_COUNT], prim: Prim, clut: &'a Clut) -> Self {
+		// Each `_2` register immediately follows its `_1` counterpart.
+		let context = |register: GsRegister| registers[register as usize + prim.context];
+		let fog_colour = registers[GsRegister::FogCol as usize];
+
+		Self {
+			prim,
+			frame: context(GsRegister::Frame1).into(),
+			zbuf: context(GsRegister::Zbuf1).into(),
+			tex0: context(GsRegister::Tex0_1).into(),
+			clamp: context(GsRegister::Clamp1).into(),
+			texa: registers[GsRegister::Texa as usize].into(),
+			test: context(GsRegister::Test1).into(),
+			alpha: context(GsRegister::Alpha1).into(),
+			scissor: context(GsRegister::Scissor1).into(),
+			offset: context(GsRegister::XyOffset1).into(),
+			fog_colour: [fog_colour as u8, (fog_colour >> 8) as u8, (fog_colour >> 16) as u8],
+			fba: context(GsRegister::Fba1) & 1 != 0,
+			pabe: registers[GsRegister::Pabe as usize] & 1 != 0,
+			colclamp: registers[GsRegister::ColClamp as usize] & 1 != 0,
+			clut,
+		}
+	}
+
+	/// Position of `vertex` in window coordinates, in 12.4 fixed point.
+	fn window(&self, vertex: &Vertex) -> (i64, i64) {
+		(i64::from(vertex.x - self.offset.ofx), i64::from(vertex.y - self.offset.ofy))
+	}
+
+	/// Interpolable attributes of `vertex`.
+	///
+	/// Texture coordinates are kept as homogeneous texel coordinates, so that
+	/// `ST` mapping is perspective-correct once divided through by `Q`.
+	fn attributes(&self, vertex: &Vertex) -> Attributes {
+		let (s, t, q) = if self.prim.uv {
+			(f64::from(vertex.u) / 16.0, f64::from(vertex.v) / 16.0, 1.0)
+		} else {
+			let width = f64::from(1u32 << self.tex0.tw.min(10));
+			let height = f64::from(1u32 << self.tex0.th.min(10));
+			(f64::from(vertex.s) * width, f64::from(vertex.t) * height, f64::from(vertex.q))
+		};
+
+		[
+			f64::from(vertex.z),
+			f64::from(vertex.rgba[0]),
+			f64::from(vertex.rgba[1]),
+			f64::from(vertex.rgba[2]),
+			f64::from(vertex.rgba[3]),
+			f64::from(vertex.fog),
+			s,
+			t,
+			q,
+		]
+	}
+
+	/// Pixel range covered by the half-open span [`start`, `end`) of window coordinates,
+	/// clipped to `min` and `max`.
+	fn pixel_span(start: i64, end: i64, min: i32, max: i32) -> std::ops::Range<i64> {
+		let first = ((start + 15) >> 4).max(i64::from(min));
+		let last = ((end + 15) >> 4).min(i64::from(max) + 1);
+		first..last.max(first)
+	}
+}
+
+const Z: usize = 0;
+const COLOUR: usize = 1;
+const FOG: usize = 5;
+const S: usize = 6;
+const T: usize = 7;
+const Q: usize = 8;
+
+type Attributes = [f64; 9];
+
+/// A pixel produced by rasterization, before texturing and the pixel tests.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Fragment {
+	x: i64,
+	y: i64,
+	z: u32,
+	rgba: [u8; 4],
+	fog: u8,
+	u: f64,
+	v: f64,
+}
+
+impl Fragment {
+	fn new(x: i64, y: i64, attributes: &Attributes) -> Self {
+		let channel = |value: f64| value.clamp(0.0, 255.0) as u8;
+		let q = if attributes[Q] == 0.0 { 1.0 } else { attributes[Q] };
+
+		Self {
+			x,
+			y,
+			z: attributes[Z].max(0.0).min(f64::from(u32::MAX)) as u32,
+			rgba: [
+				channel(attributes[COLOUR]),
+				channel(attributes[COLOUR + 1]),
+				channel(attributes[COLOUR + 2]),
+				channel(attributes[COLOUR + 3]),
+			],
+			fog: channel(attributes[FOG]),
+			u: attributes[S] / q,
+			v: attributes[T] / q,
+		}
+	}
+}
+
+/// Draw the primitive made of `vertices`, which holds as many as `env.prim` needs.
+pub fn draw(memory: &mut LocalMemory, env: &DrawEnv, vertices: &[Vertex]) {
+	trace!("GS: drawing {:?} {:?}", env.prim, vertices);
+
+	match env.prim.kind {
+		PrimitiveKind::Point => point(memory, env, &vertices[0]),
+		PrimitiveKind::Line | PrimitiveKind::LineStrip => line(memory, env, &vertices[0], &vertices[1]),
+		PrimitiveKind::Triangle | PrimitiveKind::TriangleStrip | PrimitiveKind::TriangleFan =>
+			triangle(memory, env, [&vertices[0], &vertices[1], &vertices[2]]),
+		PrimitiveKind::Sprite => sprite(memory, env, &vertices[0], &vertices[1]),
+		PrimitiveKind::Prohibited => {},
+	}
+}
+
+fn point(memory: &mut LocalMemory, env: &DrawEnv, vertex: &Vertex) {
+	let (x, y) = env.window(vertex);
+	shade(memory, env, Fragment::new((x + 8) >> 4, (y + 8) >> 4, &env.attributes(vertex)));
+}
+
+fn line(memory: &mut LocalMemory, env: &DrawEnv, start: &Vertex, end: &Vertex) {
+	let (x0, y0) = env.window(start);
+	let (x1, y1) = env.window(end);
+	let (a0, a1) = (env.attributes(start), env.attributes(end));
+
+	// Step once per pixel along the major axis, leaving the end for the next line of a strip.
+	let steps = ((x1 - x0).abs().max((y1 - y0).abs()) + 8) >> 4;
+
+	for step in 0..steps.max(1) {
+		let fraction = step as f64 / steps.max(1) as f64;
+		let mut attributes = lerp(&a0, &a1, fraction);
+
+		if !env.prim.gouraud {
+			attributes[COLOUR..COLOUR + 4].copy_from_slice(&a1[COLOUR..COLOUR + 4]);
+		}
+
+		let x = x0 + (((x1 - x0) * step) / steps.max(1));
+		let y = y0 + (((y1 - y0) * step) / steps.max(1));
+		shade(memory, env, Fragment::new((x + 8) >> 4, (y + 8) >> 4, &attributes));
+	}
+}
+
+/// Twice the signed area of triangle (`a`, `b`, `p`): positive when `p` is to the right of `a` → `b`.
+#[inline]
+fn edge(a: (i64, i64), b: (i64, i64), p: (i64, i64)) -> i64 {
+	(b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+/// Whether edge `a` → `b` of a clockwise triangle is a top or left edge,
+/// whose pixels are drawn when exactly on it.
+#[inline]
+fn top_left(a: (i64, i64), b: (i64, i64)) -> bool {
+	(a.1 == b.1 && b.0 > a.0) || b.1 < a.1
+}
+
+fn triangle(memory: &mut LocalMemory, env: &DrawEnv, vertices: [&Vertex; 3]) {
+	let mut positions = [env.window(vertices[0]), env.window(vertices[1]), env.window(vertices[2])];
+	let mut attributes = [env.attributes(vertices[0]), env.attributes(vertices[1]), env.attributes(vertices[2])];
+	let flat = attributes[2];
+
+	let mut area = edge(positions[0], positions[1], positions[2]);
+	if area == 0 {
+		return;
+	} else if area < 0 {
+		positions.swap(1, 2);
+		attributes.swap(1, 2);
+		area = -area;
+	}
+
+	let [p0, p1, p2] = positions;
+	let xs = DrawEnv::pixel_span(p0.0.min(p1.0).min(p2.0), p0.0.max(p1.0).max(p2.0) + 1, env.scissor.x0, env.scissor.x1);
+	let ys = DrawEnv::pixel_span(p0.1.min(p1.1).min(p2.1), p0.1.max(p1.1).max(p2.1) + 1, env.scissor.y0, env.scissor.y1);
+
+	let edges = [(p1, p2), (p2, p0), (p0, p1)];
+
+	for y in ys {
+		for x in xs.clone() {
+			let p = (x << 4, y << 4);
+			let mut weights = [0f64; 3];
+			let mut inside = true;
+
+			for (weight, &(a, b)) in weights.iter_mut().zip(&edges) {
+				let w = edge(a, b, p);
+				inside &= w > 0 || (w == 0 && top_left(a, b));
+				*weight = w as f64 / area as f64;
+			}
+
+			if !inside {
+				continue;
+			}
+
+			let mut interpolated = [0f64; 9];
+			for (i, value) in interpolated.iter_mut().enumerate() {
+				*value = weights[0] * attributes[0][i] + weights[1] * attributes[1][i] + weights[2] * attributes[2][i];
+			}
+
+			if !env.prim.gouraud {
+				interpolated[COLOUR..COLOUR + 4].copy_from_slice(&flat[COLOUR..COLOUR + 4]);
+			}
+
+			shade(memory, env, Fragment::new(x, y, &interpolated));
+		}
+	}
+}
+
+/// Sprites are axis-aligned rectangles between two corners, taking everything
+/// but their texture coordinates from the second.
+fn sprite(memory: &mut LocalMemory, env: &DrawEnv, start: &Vertex, end: &Vertex) {
+	let (x0, y0) = env.window(start);
+	let (x1, y1) = env.window(end);
+	let (a0, a1) = (env.attributes(start), env.attributes(end));
+
+	// Texture coordinates are interpolated linearly, once divided through.
+	let texel = |a: &Attributes| {
+		let q = if a[Q] == 0.0 { 1.0 } else { a[Q] };
+		(a[S] / q, a[T] / q)
+	};
+	let (t0, t1) = (texel(&a0), texel(&a1));
+
+	let xs = DrawEnv::pixel_span(x0.min(x1), x0.max(x1), env.scissor.x0, env.scissor.x1);
+	let ys = DrawEnv::pixel_span(y0.min(y1), y0.max(y1), env.scissor.y0, env.scissor.y1);
+
+	for y in ys {
+		let fy = if y1 == y0 { 0.0 } else { ((y << 4) - y0) as f64 / (y1 - y0) as f64 };
+
+		for x in xs.clone() {
+			let fx = if x1 == x0 { 0.0 } else { ((x << 4) - x0) as f64 / (x1 - x0) as f64 };
+
+			let mut attributes = a1;
+			attributes[S] = t0.0 + (t1.0 - t0.0) * fx;
+			attributes[T] = t0.1 + (t1.1 - t0.1) * fy;
+			attributes[Q] = 1.0;
+
+			shade(memory, env, Fragment::new(x, y, &attributes));
+		}
+	}
+}
+
+fn lerp(a: &Attributes, b: &Attributes, fraction: f64) -> Attributes {
+	let mut out = [0f64; 9];
+	for (i, value) in out.iter_mut().enumerate() {
+		*value = a[i] + (b[i] - a[i]) * fraction;
+	}
+	out
+}
+
+fn compare_alpha(atst: u8, alpha: u8, reference: u8) -> bool {
+	match atst {
+		ATST_NEVER => false,
+		ATST_ALWAYS => true,
+		ATST_LESS => alpha < reference,
+		ATST_LEQUAL => alpha <= reference,
+		ATST_EQUAL => alpha == reference,
+		ATST_GEQUAL => alpha >= reference,
+		ATST_GREATER => alpha > reference,
+		_ => alpha != reference,
+	}
+}
+
+#[inline]
+fn unpack(rgba: u32) -> [u8; 4] {
+	rgba.to_le_bytes()
+}
+
+#[inline]
+fn pack(rgba: [u8; 4]) -> u32 {
+	u32::from_le_bytes(rgba)
+}
+
+/// Largest depth storable in `psm`.
+fn max_depth(psm: Psm) -> u32 {
+	match psm {
+		Psm::Z24 => 0xff_ffff,
+		Psm::Z16 | Psm::Z16S => 0xffff,
+		_ => u32::MAX,
+	}
+}
+
+/// Texture, fog, test, blend and write one fragment.
+fn shade(memory: &mut LocalMemory, env: &DrawEnv, fragment: Fragment) {
+	let scissor = &env.scissor;
+	if fragment.x < i64::from(scissor.x0) || fragment.x > i64::from(scissor.x1)
+		|| fragment.y < i64::from(scissor.y0) || fragment.y > i64::from(scissor.y1) {
+		return;
+	}
+
+	let (x, y) = (fragment.x as u32, fragment.y as u32);
+	let mut colour = fragment.rgba;
+
+	if env.prim.textured {
+		let texel = texture::sample(
+			memory,
+			env.clut,
+			&env.tex0,
+			&env.clamp,
+			env.texa,
+			fragment.u.floor() as i32,
+			fragment.v.floor() as i32,
+		);
+		colour = texture::apply_function(&env.tex0, unpack(texel), colour);
+	}
+
+	if env.prim.fogged {
+		let f = u32::from(fragment.fog);
+		for (channel, &fog) in colour.iter_mut().zip(&env.fog_colour) {
+			*channel = ((f * u32::from(*channel) + (0xff - f) * u32::from(fog)) >> 8) as u8;
+		}
+	}
+
+	let mut write_frame = true;
+	let mut write_alpha = true;
+	let mut write_depth = !env.zbuf.zmsk;
+
+	if env.test.alpha_test && !compare_alpha(env.test.atst, colour[3], env.test.aref) {
+		match env.test.afail {
+			AFAIL_KEEP => return,
+			AFAIL_FB_ONLY => write_depth = false,
+			AFAIL_ZB_ONLY => write_frame = false,
+			_ => {
+				write_alpha = false;
+				write_depth = false;
+			},
+		}
+	}
+
+	let frame_psm = Psm::from_u8(env.frame.psm).unwrap_or(Psm::Ct32);
+	let frame_base = env.frame.fbp * BLOCKS_PER_PAGE;
+	let frame_raw = memory.read_pixel(frame_psm, frame_base, env.frame.fbw, x, y);
+	let destination = unpack(texture::frame_to_rgba(frame_psm, frame_raw));
+
+	if env.test.dest_alpha_test && (destination[3] & 0x80 != 0) != env.test.datm {
+		return;
+	}
+
+	let depth_psm = Psm::from_u8(0x30 | env.zbuf.psm).unwrap_or(Psm::Z32);
+	let depth_base = env.zbuf.zbp * BLOCKS_PER_PAGE;
+	let depth = fragment.z.min(max_depth(depth_psm));
+
+	if env.test.depth_test {
+		let current = memory.read_pixel(depth_psm, depth_base, env.frame.fbw, x, y);
+		let pass = match env.test.ztst {
+			ZTST_NEVER => false,
+			ZTST_ALWAYS => true,
+			ZTST_GEQUAL => depth >= current,
+			_ => depth > current,
+		};
+
+		if !pass {
+			return;
+		}
+	}
+
+	if env.prim.blended && (!env.pabe || colour[3] & 0x80 != 0) {
+		colour = blend(env, colour, destination);
+	}
+
+	if write_frame {
+		let mut out = pack(colour);
+		if env.fba {
+			out |= 0x8000_0000;
+		}
+
+		let mut mask = env.frame.fbmsk;
+		if !write_alpha {
+			mask |= 0xff00_0000;
+		}
+
+		let (out, mask) = match frame_psm.bits_per_pixel() {
+			16 => (texture::pack_16(out), texture::pack_16(mask)),
+			_ => (out, mask),
+		};
+
+		memory.write_pixel(frame_psm, frame_base, env.frame.fbw, x, y, (frame_raw & mask) | (out & !mask));
+	}
+
+	if write_depth {
+		memory.write_pixel(depth_psm, depth_base, env.frame.fbw, x, y, depth);
+	}
+}
+
+/// Blend `source` over `destination` by `((A - B) * C >> 7) + D`, keeping the source alpha.
+fn blend(env: &DrawEnv, source: [u8; 4], destination: [u8; 4]) -> [u8; 4] {
+	let alpha = &env.alpha;
+	let select = |selector: u8, channel: usize| match selector {
+		0 => i32::from(source[channel]),
+		1 => i32::from(destination[channel]),
+		_ => 0,
+	};
+	let c = i32::from(match alpha.c {
+		0 => source[3],
+		1 => destination[3],
+		_ => alpha.fix,
+	});
+
+	let mut out = source;
+	for (channel, value) in out.iter_mut().enumerate().take(3) {
+		let blended = (((select(alpha.a, channel) - select(alpha.b, channel)) * c) >> 7) + select(alpha.d, channel);
+
+		*value = if env.colclamp {
+			blended.clamp(0, 0xff) as u8
+		} else {
+			blended as u8
+		};
+	}
+
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Scene {
+		registers: [u64; GS_REGISTER_COUNT],
+		clut: Clut,
+		memory: LocalMemory,
+	}
+
+	impl Scene {
+		/// A 64×64 PSMCT32 frame buffer at page 0 and 32-bit Z buffer at page 16,
+		/// scissored to the whole frame.
+		fn new() -> Self {
+			let mut registers = [0; GS_REGISTER_COUNT];
+			registers[GsRegister::Frame1 as usize] = 1 << 16;
+			registers[GsRegister::Zbuf1 as usize] = 16;
+			registers[GsRegister::Scissor1 as usize] = (63 << 16) | (63 << 48);
+
+			Self {
+				registers,
+				clut: Clut::default(),
+				memory: LocalMemory::default(),
+			}
+		}
+
+		fn draw(&mut self, prim: u64, vertices: &[Vertex]) {
+			let env = DrawEnv::new(&self.registers, Prim::from(prim), &self.clut);
+			draw(&mut self.memory, &env, vertices);
+		}
+
+		fn pixel(&self, x: u32, y: u32) -> u32 {
+			self.memory.read_pixel(Psm::Ct32, 0, 1, x, y)
+		}
+
+		fn count(&self, rgba: u32) -> usize {
+			(0..64).flat_map(|y| (0..64).map(move |x| (x, y)))
+				.filter(|&(x, y)| self.pixel(x, y) == rgba)
+				.count()
+		}
+	}
+
+	fn vertex(x: i32, y: i32, rgba: [u8; 4]) -> Vertex {
+		Vertex {
+			x: x << 4,
+			y: y << 4,
+			rgba,
+			q: 1.0,
+			..Default::default()
+		}
+	}
+
+	const SPRITE: u64 = PrimitiveKind::Sprite as u64;
+	const TRIANGLE: u64 = PrimitiveKind::Triangle as u64;
+	const RED: [u8; 4] = [0xff, 0, 0, 0x80];
+
+	#[test]
+	fn sprite_covers_half_open_rectangle() {
+		let mut scene = Scene::new();
+		scene.draw(SPRITE, &[vertex(4, 4, [0; 4]), vertex(12, 8, RED)]);
+
+		assert_eq!(scene.count(pack(RED)), 8 * 4);
+		assert_eq!(scene.pixel(4, 4), pack(RED));
+		assert_eq!(scene.pixel(12, 4), 0);
+	}
+
+	#[test]
+	fn adjacent_triangles_do_not_overlap() {
+		let mut scene = Scene::new();
+
+		// Additive blending exposes any pixel drawn twice.
+		scene.registers[GsRegister::Alpha1 as usize] = (2 << 2) | (2 << 4) | (1 << 6) | (0x80 << 32);
+		let blend = 1 << 6;
+		let colour = [0x10, 0, 0, 0x80];
+
+		let quad = [vertex(0, 0, colour), vertex(16, 0, colour), vertex(0, 16, colour), vertex(16, 16, colour)];
+		scene.draw(TRIANGLE | blend, &quad[0..3]);
+		scene.draw(TRIANGLE | blend, &quad[1..4]);
+
+		assert_eq!(scene.count(pack(colour)), 16 * 16);
+	}
+
+	#[test]
+	fn gouraud_interpolates_colour() {
+		let mut scene = Scene::new();
+		let gouraud = 1 << 3;
+
+		scene.draw(TRIANGLE | gouraud, &[
+			vertex(0, 0, [0, 0, 0, 0]),
+			vertex(32, 0, [0xff, 0, 0, 0]),
+			vertex(0, 32, [0, 0xff, 0, 0]),
+		]);
+
+		assert_eq!(unpack(scene.pixel(16, 0))[0], 0x7f);
+		assert_eq!(unpack(scene.pixel(0, 16))[1], 0x7f);
+
+		// Flat shading takes the last vertex's colour.
+		scene.draw(TRIANGLE, &[
+			vertex(32, 32, [0, 0, 0, 0]),
+			vertex(48, 32, [0, 0, 0, 0]),
+			vertex(32, 48, RED),
+		]);
+		assert_eq!(scene.pixel(33, 33), pack(RED));
+	}
+
+	#[test]
+	fn depth_test_and_mask() {
+		let mut scene = Scene::new();
+		scene.registers[GsRegister::Test1 as usize] = (1 << 16) | (u64::from(ZTST_GEQUAL) << 17);
+
+		let mut near = vertex(8, 8, RED);
+		near.z = 100;
+		let mut far = vertex(0, 0, [0, 0xff, 0, 0]);
+		far.z = 50;
+
+		scene.draw(SPRITE, &[vertex(0, 0, RED), near]);
+		scene.draw(SPRITE, &[vertex(4, 4, [0; 4]), far]);
+		assert_eq!(scene.pixel(2, 2), pack(RED));
+		assert_eq!(scene.memory.read_pixel(Psm::Z32, 16 * 32, 1, 2, 2), 100);
+
+		far.z = 200;
+		scene.registers[GsRegister::Zbuf1 as usize] |= 1 << 32;
+		scene.draw(SPRITE, &[vertex(4, 4, [0; 4]), far]);
+		assert_eq!(scene.pixel(2, 2), pack([0, 0xff, 0, 0]));
+		assert_eq!(scene.memory.read_pixel(Psm::Z32, 16 * 32, 1, 2, 2), 100);
+	}
+
+	#[test]
+	fn alpha_test_fail_modes() {
+		let mut scene = Scene::new();
+		let test = 1 | (u64::from(ATST_GEQUAL) << 1) | (0x40 << 4);
+		let faint = [0xff, 0xff, 0xff, 0x10];
+
+		scene.registers[GsRegister::Test1 as usize] = test | (u64::from(AFAIL_KEEP) << 12);
+		scene.draw(SPRITE, &[vertex(0, 0, [0; 4]), vertex(4, 4, faint)]);
+		assert_eq!(scene.pixel(0, 0), 0);
+
+		// RGB_ONLY writes all but alpha.
+		scene.registers[GsRegister::Test1 as usize] = test | (3 << 12);
+		scene.draw(SPRITE, &[vertex(0, 0, [0; 4]), vertex(4, 4, faint)]);
+		assert_eq!(scene.pixel(0, 0), 0x00ff_ffff);
+	}
+
+	#[test]
+	fn blending_and_frame_mask() {
+		let mut scene = Scene::new();
+		scene.draw(SPRITE, &[vertex(0, 0, [0; 4]), vertex(4, 4, [0, 0x80, 0xff, 0x80])]);
+
+		// (Cs - Cd) * As + Cd, at half alpha.
+		scene.registers[GsRegister::Alpha1 as usize] = (1 << 2) | (1 << 6);
+		scene.registers[GsRegister::Frame1 as usize] |= 0xff00_0000 << 32;
+		scene.draw(SPRITE | (1 << 6), &[vertex(0, 0, [0; 4]), vertex(4, 4, [0xff, 0, 0xff, 0x40])]);
+
+		assert_eq!(unpack(scene.pixel(0, 0)), [0x7f, 0x40, 0xff, 0x80]);
+	}
+
+	#[test]
+	fn scissor_and_offset() {
+		let mut scene = Scene::new();
+		scene.registers[GsRegister::Scissor1 as usize] = 2 | (5 << 16) | (2 << 32) | (5 << 48);
+		scene.registers[GsRegister::XyOffset1 as usize] = (100 << 4) | ((100 << 4) << 32);
+
+		scene.draw(SPRITE, &[vertex(100, 100, [0; 4]), vertex(110, 110, RED)]);
+		assert_eq!(scene.count(pack(RED)), 4 * 4);
+		assert_eq!(scene.pixel(2, 2), pack(RED));
+	}
+
+	#[test]
+	fn textured_sprite_with_clut() {
+		let mut scene = Scene::new();
+
+		// 4-bit 16×16 texture at block 0x100, with its palette at block 0x200.
+		for y in 0..16 {
+			for x in 0..16 {
+				scene.memory.write_pixel(Psm::T4, 0x100, 2, x, y, x & 1);
+			}
+		}
+		scene.memory.write_pixel(Psm::Ct32, 0x200, 1, 0, 0, 0x8000_00ff);
+		scene.memory.write_pixel(Psm::Ct32, 0x200, 1, 1, 0, 0x8000_ff00);
+
+		let tex0 = Tex0 {
+			tbp0: 0x100,
+			tbw: 2,
+			psm: Psm::T4 as u8,
+			tw: 4,
+			th: 4,
+			has_alpha: true,
+			tfx: texture::TFX_DECAL,
+			cbp: 0x200,
+			..Default::default()
+		};
+		scene.clut.load(&scene.memory, &tex0, TexClut::default());
+		scene.registers[GsRegister::Tex0_1 as usize] = 0x100 | (2 << 14) | (0x14 << 20) | (4 << 26) | (4 << 30)
+			| (1 << 34) | (1 << 35) | (0x200 << 37);
+
+		// UV mapping, from texel 0 to texel 8 across 8 pixels.
+		let mut start = vertex(0, 0, [0; 4]);
+		let mut end = vertex(8, 8, [0; 4]);
+		end.u = 8 << 4;
+		end.v = 8 << 4;
+		start.u = 0;
+
+		scene.draw(SPRITE | (1 << 4) | (1 << 8), &[start, end]);
+		assert_eq!(scene.pixel(0, 0), 0x8000_00ff);
+		assert_eq!(scene.pixel(1, 0), 0x8000_ff00);
+		assert_eq!(scene.pixel(2, 5), 0x8000_00ff);
+	}
+}
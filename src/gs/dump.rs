@@ -0,0 +1,275 @@
+//! Snapshots of local memory and the GS's registers, and their file format.
+//!
+//! The format is rs2's own, read by `rs2 gs-dump`; no other tool reads it.
+//! It is not PCSX2's `.gs`, which holds PCSX2's internal GS state and a log
+//! of GIF packets to replay rather than the registers as the EE sees them.
+//! Any change to the layout must bump [`DUMP_VERSION`](constant.DUMP_VERSION.html).
+//!
+//! A dump file holds, all little-endian:
+//! * The magic `RS2GSDMP` and a `u32` format version.
+//! * The number of general registers as a `u32`, then their values as `u64`s.
+//! * The same for the privileged registers from `PMODE` to `BGCOLOR`.
+//! * `CSR`, `IMR`, `BUSDIR` and `SIGLBLID`, as `u64`s.
+//! * All of local memory, as `u32` words.
+//!
+//! Files of other versions are rejected rather than guessed at.
+
+use byteorder::{
+	LittleEndian,
+	ReadBytesExt,
+	WriteBytesExt,
+};
+use super::{
+	memory::{
+		LocalMemory,
+		Psm,
+	},
+	output::Frame,
+	registers::{
+		TexClut,
+		Tex0,
+		GS_REGISTER_COUNT,
+	},
+	texture::{
+		self,
+		Clut,
+	},
+	DISPLAY_REGISTER_COUNT,
+};
+use std::io::{
+	self,
+	Read,
+	Write,
+};
+
+pub const DUMP_MAGIC: [u8; 8] = *b"RS2GSDMP";
+
+/// Version written by [`GsDump::write`](struct.GsDump.html#method.write), and the only one read.
+pub const DUMP_VERSION: u32 = 1;
+
+/// The state of the GS at one moment.
+#[derive(Clone)]
+pub struct GsDump {
+	pub registers: [u64; GS_REGISTER_COUNT],
+	pub display_registers: [u64; DISPLAY_REGISTER_COUNT],
+	pub csr: u64,
+	pub imr: u64,
+	pub busdir: u64,
+	pub siglblid: u64,
+	pub memory: LocalMemory,
+}
+
+fn invalid(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_registers<R: Read>(reader: &mut R, registers: &mut [u64]) -> io::Result<()> {
+	if reader.read_u32::<LittleEndian>()? as usize != registers.len() {
+		return Err(invalid("unexpected register count"));
+	}
+
+	reader.read_u64_into::<LittleEndian>(registers)
+}
+
+fn write_registers<W: Write>(writer: &mut W, registers: &[u64]) -> io::Result<()> {
+	writer.write_u32::<LittleEndian>(registers.len() as u32)?;
+
+	for &register in registers {
+		writer.write_u64::<LittleEndian>(register)?;
+	}
+
+	Ok(())
+}
+
+impl GsDump {
+	pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		writer.write_all(&DUMP_MAGIC)?;
+		writer.write_u32::<LittleEndian>(DUMP_VERSION)?;
+
+		write_registers(writer, &self.registers)?;
+		write_registers(writer, &self.display_registers)?;
+
+		for &register in &[self.csr, self.imr, self.busdir, self.siglblid] {
+			writer.write_u64::<LittleEndian>(register)?;
+		}
+
+		for &word in self.memory.words() {
+			writer.write_u32::<LittleEndian>(word)?;
+		}
+
+		writer.flush()
+	}
+
+	pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+		let mut magic = [0; 8];
+		reader.read_exact(&mut magic)?;
+		if magic != DUMP_MAGIC {
+			return Err(invalid("not a GS dump"));
+		}
+
+		let version = reader.read_u32::<LittleEndian>()?;
+		if version != DUMP_VERSION {
+			return Err(invalid(&format!("unsupported GS dump version {}", version)));
+		}
+
+		let mut registers = [0; GS_REGISTER_COUNT];
+		read_registers(reader, &mut registers)?;
+		let mut display_registers = [0; DISPLAY_REGISTER_COUNT];
+		read_registers(reader, &mut display_registers)?;
+
+		let csr = reader.read_u64::<LittleEndian>()?;
+		let imr = reader.read_u64::<LittleEndian>()?;
+		let busdir = reader.read_u64::<LittleEndian>()?;
+		let siglblid = reader.read_u64::<LittleEndian>()?;
+
+		let mut memory = LocalMemory::default();
+		reader.read_u32_into::<LittleEndian>(memory.words_mut())?;
+
+		Ok(Self {
+			registers,
+			display_registers,
+			csr,
+			imr,
+			busdir,
+			siglblid,
+			memory,
+		})
+	}
+
+	/// Render `region` of the dumped local memory.
+	pub fn render(&self, region: &Region) -> Frame {
+		render(&self.memory, region)
+	}
+}
+
+/// A rectangle of local memory, and how to interpret it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+	pub psm: Psm,
+
+	/// Base pointer, in blocks.
+	pub base: u32,
+
+	/// Buffer width, in units of 64 pixels.
+	pub width: u32,
+
+	pub x: u32,
+	pub y: u32,
+	pub columns: u32,
+	pub rows: u32,
+
+	/// For indexed formats, the CLUT's base pointer (in blocks), format and
+	/// offset (in units of 16 entries, for 4-bit formats). CLUTs are read in CSM1.
+	pub cbp: u32,
+	pub cpsm: Psm,
+	pub csa: u32,
+}
+
+impl Default for Region {
+	fn default() -> Self {
+		Self {
+			psm: Psm::Ct32,
+			base: 0,
+			width: 1,
+			x: 0,
+			y: 0,
+			columns: 64,
+			rows: 32,
+			cbp: 0,
+			cpsm: Psm::Ct32,
+			csa: 0,
+		}
+	}
+}
+
+/// Render `region` of `memory` as an image.
+pub fn render(memory: &LocalMemory, region: &Region) -> Frame {
+	let mut frame = Frame::new(region.columns, region.rows);
+
+	let tex0 = Tex0 {
+		psm: region.psm as u8,
+		cbp: region.cbp,
+		cpsm: region.cpsm as u8,
+		csa: region.csa,
+		..Default::default()
+	};
+	let mut clut = Clut::default();
+	if region.psm.is_indexed() {
+		clut.load(memory, &tex0, TexClut::default());
+	}
+
+	for y in 0..region.rows {
+		for x in 0..region.columns {
+			let raw = memory.read_pixel(region.psm, region.base, region.width, region.x + x, region.y + y);
+			let rgba = if region.psm.is_indexed() {
+				clut.lookup(&tex0, raw, texture::FRAME_TEXA)
+			} else {
+				texture::frame_to_rgba(region.psm, raw)
+			};
+
+			frame.set(x, y, rgba);
+		}
+	}
+
+	frame
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::gs::registers::GsRegister;
+
+	fn dump() -> GsDump {
+		let mut memory = LocalMemory::default();
+		memory.write_pixel(Psm::Ct32, 0, 1, 1, 0, 0x0011_2233);
+
+		let mut registers = [0; GS_REGISTER_COUNT];
+		registers[GsRegister::Frame1 as usize] = 0xdead_beef;
+
+		GsDump {
+			registers,
+			display_registers: [7; DISPLAY_REGISTER_COUNT],
+			csr: 1,
+			imr: 2,
+			busdir: 3,
+			siglblid: 4,
+			memory,
+		}
+	}
+
+	#[test]
+	fn round_trips() {
+		let mut file = vec![];
+		dump().write(&mut file).unwrap();
+		assert_eq!(&file[..12], b"RS2GSDMP\x01\0\0\0");
+
+		let read = GsDump::read(&mut file.as_slice()).unwrap();
+		assert_eq!(read.registers[GsRegister::Frame1 as usize], 0xdead_beef);
+		assert_eq!(read.display_registers, [7; DISPLAY_REGISTER_COUNT]);
+		assert_eq!((read.csr, read.imr, read.busdir, read.siglblid), (1, 2, 3, 4));
+		assert_eq!(read.memory.words(), dump().memory.words());
+	}
+
+	#[test]
+	fn rejects_other_versions() {
+		let mut file = vec![];
+		dump().write(&mut file).unwrap();
+		file[8] = 2;
+
+		assert_eq!(GsDump::read(&mut file.as_slice()).err().unwrap().kind(), io::ErrorKind::InvalidData);
+	}
+
+	#[test]
+	fn renders_indexed_regions() {
+		let mut dump = dump();
+		dump.memory.write_pixel(Psm::T8, 64, 2, 3, 2, 1);
+
+		// The CLUT's entry 1 is the colour at (1, 0) of the 32-bit buffer at block 0.
+		let region = Region { psm: Psm::T8, base: 64, width: 2, x: 2, y: 2, columns: 2, rows: 1, ..Default::default() };
+		let frame = dump.render(&region);
+
+		assert_eq!((frame.width, frame.height), (2, 1));
+		assert_eq!(frame.get(1, 0), [0x33, 0x22, 0x11]);
+		assert_eq!(frame.get(0, 0), [0, 0, 0]);
+	}
+}
@@ -0,0 +1,345 @@
+//! The GS's 4 MiB local memory, and the swizzled layouts of its pixel storage modes.
+//!
+//! Memory is divided into 8 KiB pages, each of 32 blocks of 256 bytes, each
+//! of 4 columns of 64 bytes. A buffer is a row-major grid of pages, but blocks
+//! within a page, and pixels within a block, are arranged differently for each
+//! storage mode (PSM) so that neighbouring pixels share a DRAM page.
+//!
+//! Layouts follow https://psi-rockin.github.io/ps2tek/ and the GS User's Manual.
+
+use enum_primitive::*;
+
+/// Size of local memory, in bytes.
+pub const LOCAL_MEMORY_BYTES: usize = 4 * 1024 * 1024;
+
+/// Size of local memory, in 32-bit words.
+pub const LOCAL_MEMORY_WORDS: usize = LOCAL_MEMORY_BYTES / 4;
+
+pub const WORDS_PER_BLOCK: u32 = 64;
+pub const BLOCKS_PER_PAGE: u32 = 32;
+
+const BLOCK_COUNT: u32 = (LOCAL_MEMORY_WORDS as u32) / WORDS_PER_BLOCK;
+
+enum_from_primitive!{
+/// Pixel storage modes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Psm {
+	Ct32   = 0x00,
+	Ct24   = 0x01,
+	Ct16   = 0x02,
+	Ct16S  = 0x0a,
+	T8     = 0x13,
+	T4     = 0x14,
+	T8H    = 0x1b,
+	T4HL   = 0x24,
+	T4HH   = 0x2c,
+	Z32    = 0x30,
+	Z24    = 0x31,
+	Z16    = 0x32,
+	Z16S   = 0x3a,
+}
+}
+
+/// Order of the blocks within a page, for 32-bit and 8-bit modes (8 wide, 4 high).
+const BLOCKS_32: [[u32; 8]; 4] = [
+	[ 0,  1,  4,  5, 16, 17, 20, 21],
+	[ 2,  3,  6,  7, 18, 19, 22, 23],
+	[ 8,  9, 12, 13, 24, 25, 28, 29],
+	[10, 11, 14, 15, 26, 27, 30, 31],
+];
+
+/// Order of the blocks within a page, for 16-bit and 4-bit modes (4 wide, 8 high).
+const BLOCKS_16: [[u32; 4]; 8] = [
+	[ 0,  2,  8, 10],
+	[ 1,  3,  9, 11],
+	[ 4,  6, 12, 14],
+	[ 5,  7, 13, 15],
+	[16, 18, 24, 26],
+	[17, 19, 25, 27],
+	[20, 22, 28, 30],
+	[21, 23, 29, 31],
+];
+
+/// Order of the blocks within a page, for `PSMCT16S`.
+const BLOCKS_16S: [[u32; 4]; 8] = [
+	[ 0,  2, 16, 18],
+	[ 1,  3, 17, 19],
+	[ 8, 10, 24, 26],
+	[ 9, 11, 25, 27],
+	[ 4,  6, 20, 22],
+	[ 5,  7, 21, 23],
+	[12, 14, 28, 30],
+	[13, 15, 29, 31],
+];
+
+/// Z formats place blocks as their colour counterparts, with the page's halves swapped.
+const Z_BLOCK_SWAP: u32 = 0b1_1000;
+
+/// Words of a column, for each pair of rows of 8 words.
+const COLUMN_WORDS: [[u32; 8]; 2] = [
+	[0, 1, 4, 5,  8,  9, 12, 13],
+	[2, 3, 6, 7, 10, 11, 14, 15],
+];
+
+impl Psm {
+	/// Parse a format from its name, with or without the `PSM` prefix (e.g., `PSMCT32` or `t4hl`).
+	pub fn from_name(name: &str) -> Option<Self> {
+		let name = name.to_ascii_uppercase();
+
+		let psm = match name.strip_prefix("PSM").unwrap_or(&name) {
+			"CT32" => Psm::Ct32,
+			"CT24" => Psm::Ct24,
+			"CT16" => Psm::Ct16,
+			"CT16S" => Psm::Ct16S,
+			"T8" => Psm::T8,
+			"T4" => Psm::T4,
+			"T8H" => Psm::T8H,
+			"T4HL" => Psm::T4HL,
+			"T4HH" => Psm::T4HH,
+			"Z32" => Psm::Z32,
+			"Z24" => Psm::Z24,
+			"Z16" => Psm::Z16,
+			"Z16S" => Psm::Z16S,
+			_ => return None,
+		};
+
+		Some(psm)
+	}
+
+	/// Bits per pixel, as stored.
+	pub fn bits_per_pixel(self) -> u32 {
+		match self {
+			Psm::Ct32 | Psm::Ct24 | Psm::Z32 | Psm::Z24 => 32,
+			Psm::Ct16 | Psm::Ct16S | Psm::Z16 | Psm::Z16S => 16,
+			Psm::T8 => 8,
+			Psm::T4 => 4,
+			// Stored in the upper bits of 32-bit pixels.
+			Psm::T8H | Psm::T4HL | Psm::T4HH => 32,
+		}
+	}
+
+	/// Bits per pixel in transfers to and from the host, which pack pixels tightly.
+	pub fn transfer_bits(self) -> u32 {
+		match self {
+			Psm::Ct24 | Psm::Z24 => 24,
+			Psm::T8H => 8,
+			Psm::T4HL | Psm::T4HH => 4,
+			_ => self.bits_per_pixel(),
+		}
+	}
+
+	/// Whether pixels are indices into a CLUT.
+	pub fn is_indexed(self) -> bool {
+		matches!(self, Psm::T8 | Psm::T4 | Psm::T8H | Psm::T4HL | Psm::T4HH)
+	}
+
+	/// Width and height of a page, in pixels.
+	pub fn page_size(self) -> (u32, u32) {
+		match self.bits_per_pixel() {
+			32 => (64, 32),
+			16 => (64, 64),
+			8 => (128, 64),
+			_ => (128, 128),
+		}
+	}
+
+	/// Width and height of a block, in pixels.
+	pub fn block_size(self) -> (u32, u32) {
+		let (page_width, page_height) = self.page_size();
+
+		match self.bits_per_pixel() {
+			32 | 8 => (page_width / 8, page_height / 4),
+			_ => (page_width / 4, page_height / 8),
+		}
+	}
+
+	/// Bit position and width of the value within the word found by [`locate`](fn.locate.html).
+	fn field(self) -> (u32, u32) {
+		match self {
+			Psm::Ct24 | Psm::Z24 => (0, 24),
+			Psm::T8H => (24, 8),
+			Psm::T4HL => (24, 4),
+			Psm::T4HH => (28, 4),
+			_ => (0, self.bits_per_pixel()),
+		}
+	}
+
+	fn block_in_page(self, x: u32, y: u32) -> u32 {
+		let (block_width, block_height) = self.block_size();
+		let (bx, by) = ((x / block_width) as usize, (y / block_height) as usize);
+
+		match self {
+			Psm::Ct16 | Psm::T4 => BLOCKS_16[by % 8][bx % 4],
+			Psm::Ct16S => BLOCKS_16S[by % 8][bx % 4],
+			Psm::Z16 => BLOCKS_16[by % 8][bx % 4] ^ Z_BLOCK_SWAP,
+			Psm::Z16S => BLOCKS_16S[by % 8][bx % 4] ^ Z_BLOCK_SWAP,
+			Psm::Z32 | Psm::Z24 => BLOCKS_32[by % 4][bx % 8] ^ Z_BLOCK_SWAP,
+			_ => BLOCKS_32[by % 4][bx % 8],
+		}
+	}
+}
+
+/// Where a pixel lives: a word of local memory, and the bit offset within it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Location {
+	pub word: usize,
+	pub shift: u32,
+}
+
+/// Find pixel (`x`, `y`) of the buffer at block `base`, `width` × 64 pixels wide.
+pub fn locate(psm: Psm, base: u32, width: u32, x: u32, y: u32) -> Location {
+	let (page_width, page_height) = psm.page_size();
+	let pages_per_row = (width * 64 / page_width).max(1);
+	let page = (y / page_height) * pages_per_row + x / page_width;
+	let block = (base + page * BLOCKS_PER_PAGE + psm.block_in_page(x, y)) % BLOCK_COUNT;
+
+	let (block_width, block_height) = psm.block_size();
+	let (bx, by) = (x % block_width, y % block_height);
+
+	let (word, shift) = match psm.bits_per_pixel() {
+		// Each column holds 2 rows of 8 pixels.
+		32 => ((by / 2) * 16 + COLUMN_WORDS[(by % 2) as usize][bx as usize], 0),
+
+		// Each column holds 2 rows of 16 pixels: the right half takes the upper halfwords.
+		16 => ((by / 2) * 16 + COLUMN_WORDS[(by % 2) as usize][(bx % 8) as usize], (bx / 8) * 16),
+
+		// Each column holds 4 rows of 16 (or 32) pixels, packing 4 (or 8) pixels into
+		// each word. Alternate row pairs are rotated by half a column.
+		bits => {
+			let column = by / 4;
+			let row = by % 4;
+			let rotated = ((row / 2) ^ (column % 2)) != 0;
+			let word_x = if rotated { (bx + 4) % 8 } else { bx % 8 };
+			let element = (row / 2) + 2 * (bx / 8);
+
+			(column * 16 + COLUMN_WORDS[(row % 2) as usize][word_x as usize], element * bits)
+		},
+	};
+
+	Location {
+		word: (block * WORDS_PER_BLOCK + word) as usize,
+		shift: shift + psm.field().0,
+	}
+}
+
+/// The GS's local memory.
+#[derive(Clone)]
+pub struct LocalMemory {
+	words: Vec<u32>,
+}
+
+impl Default for LocalMemory {
+	fn default() -> Self {
+		Self {
+			words: vec![0; LOCAL_MEMORY_WORDS],
+		}
+	}
+}
+
+impl LocalMemory {
+	/// All of local memory, as 32-bit words.
+	pub fn words(&self) -> &[u32] {
+		&self.words
+	}
+
+	pub fn words_mut(&mut self) -> &mut [u32] {
+		&mut self.words
+	}
+
+	/// Read pixel (`x`, `y`) of the buffer at block `base`, `width` × 64 pixels wide.
+	pub fn read_pixel(&self, psm: Psm, base: u32, width: u32, x: u32, y: u32) -> u32 {
+		let location = locate(psm, base, width, x, y);
+		let bits = psm.field().1;
+
+		(self.words[location.word] >> location.shift) & mask(bits)
+	}
+
+	/// Write pixel (`x`, `y`) of the buffer at block `base`, `width` × 64 pixels wide.
+	///
+	/// Only the bits used by `psm` are changed.
+	pub fn write_pixel(&mut self, psm: Psm, base: u32, width: u32, x: u32, y: u32, value: u32) {
+		let location = locate(psm, base, width, x, y);
+		let field_mask = mask(psm.field().1) << location.shift;
+		let word = &mut self.words[location.word];
+
+		*word = (*word & !field_mask) | ((value << location.shift) & field_mask);
+	}
+}
+
+#[inline]
+fn mask(bits: u32) -> u32 {
+	if bits >= 32 {
+		!0
+	} else {
+		(1 << bits) - 1
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashSet;
+
+	const ALL_PSMS: [Psm; 13] = [
+		Psm::Ct32, Psm::Ct24, Psm::Ct16, Psm::Ct16S, Psm::T8, Psm::T4, Psm::T8H,
+		Psm::T4HL, Psm::T4HH, Psm::Z32, Psm::Z24, Psm::Z16, Psm::Z16S,
+	];
+
+	#[test]
+	fn each_page_fills_its_own_memory() {
+		for &psm in &ALL_PSMS {
+			let (page_width, page_height) = psm.page_size();
+			let mut seen = HashSet::new();
+
+			for y in 0..page_height {
+				for x in 0..page_width {
+					let location = locate(psm, BLOCKS_PER_PAGE, 1, x, y);
+					assert!(seen.insert((location.word, location.shift)), "{:?} ({}, {}) reused", psm, x, y);
+					assert!((2048..4096).contains(&location.word), "{:?} ({}, {}) outside page", psm, x, y);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn known_addresses() {
+		// Second block of a 32-bit page is to the right of the first.
+		assert_eq!(locate(Psm::Ct32, 0, 1, 8, 0), Location { word: 64, shift: 0 });
+		assert_eq!(locate(Psm::Ct32, 0, 1, 7, 7), Location { word: 63, shift: 0 });
+		assert_eq!(locate(Psm::Ct32, 0, 1, 0, 8), Location { word: 2 * 64, shift: 0 });
+
+		// Z buffers start at the opposite half of the page.
+		assert_eq!(locate(Psm::Z32, 0, 1, 0, 0).word, 24 * 64);
+
+		// 16-bit pixels 8 apart share a word.
+		assert_eq!(locate(Psm::Ct16, 0, 1, 8, 0), Location { word: 0, shift: 16 });
+		assert_eq!(locate(Psm::Ct16, 0, 1, 0, 8).word, 64);
+
+		// The third row of an 8-bit block is rotated by half a column.
+		assert_eq!(locate(Psm::T8, 0, 2, 0, 2), Location { word: 8, shift: 8 });
+		assert_eq!(locate(Psm::T8, 0, 2, 8, 0), Location { word: 0, shift: 16 });
+		assert_eq!(locate(Psm::T4, 0, 2, 0, 2), Location { word: 8, shift: 4 });
+
+		// Pages are laid out left to right, then top to bottom.
+		assert_eq!(locate(Psm::Ct32, 0, 2, 64, 0).word, 2048);
+		assert_eq!(locate(Psm::Ct32, 0, 2, 0, 32).word, 2 * 2048);
+	}
+
+	#[test]
+	fn pixels_only_touch_their_bits() {
+		let mut memory = LocalMemory::default();
+
+		memory.write_pixel(Psm::Ct32, 0, 1, 0, 0, 0x1122_3344);
+		memory.write_pixel(Psm::T8H, 0, 1, 0, 0, 0xff);
+		memory.write_pixel(Psm::Ct24, 0, 1, 0, 0, 0xab_cdef);
+		assert_eq!(memory.read_pixel(Psm::Ct32, 0, 1, 0, 0), 0xffab_cdef);
+
+		memory.write_pixel(Psm::T4HL, 0, 1, 0, 0, 0x3);
+		assert_eq!(memory.read_pixel(Psm::T4HH, 0, 1, 0, 0), 0xf);
+		assert_eq!(memory.read_pixel(Psm::T8H, 0, 1, 0, 0), 0xf3);
+
+		memory.write_pixel(Psm::T4, 32, 2, 1, 0, 0x1f);
+		assert_eq!(memory.read_pixel(Psm::T4, 32, 2, 1, 0), 0xf);
+		assert_eq!(memory.read_pixel(Psm::T4, 32, 2, 0, 0), 0);
+	}
+}
@@ -0,0 +1,794 @@
+//! The Graphics Synthesizer (GS).
+//!
+//! A software implementation: general register writes arrive from the GIF,
+//! primitives are rasterized straight into local memory, and each VSYNC the
+//! displayed frame buffer can be handed to a hook (e.g., to be written to an
+//! image file), with no display or GPU needed.
+//!
+//! Only the privileged registers are mapped into the EE's address space.
+//! Video timing is that of NTSC, counted in EE Core cycles.
+
+pub mod draw;
+pub mod dump;
+pub mod memory;
+pub mod output;
+pub mod registers;
+pub mod texture;
+pub mod transfer;
+
+use bitflags::bitflags;
+use crate::{
+	gif::GsSink,
+	memory::{
+		bus::Device,
+		constants::GS_PRIV_REGISTERS_PHYSICAL,
+	},
+};
+use draw::{
+	DrawEnv,
+	Vertex,
+};
+use dump::GsDump;
+use enum_primitive::*;
+use memory::{
+	LocalMemory,
+	Psm,
+	BLOCKS_PER_PAGE,
+};
+use output::{
+	Frame,
+	ImageFormat,
+};
+use registers::*;
+use transfer::Transfer;
+use std::{
+	fs::File,
+	io::BufWriter,
+	mem,
+	path::PathBuf,
+};
+
+/// Length of the privileged register block.
+pub const GS_PRIV_SIZE: u32 = 0x2000;
+
+/// Number of privileged registers from `PMODE` to `BGCOLOR`.
+pub const DISPLAY_REGISTER_COUNT: usize = 15;
+
+/// EE Core cycles per NTSC scanline.
+pub const EE_CYCLES_PER_SCANLINE: u64 = 18_743;
+
+/// EE Core cycles of HBLANK, which opens each scanline: about 10.9µs of NTSC's 63.6µs.
+pub const HBLANK_EE_CYCLES: u64 = 3_215;
+
+/// Scanlines per NTSC field.
+pub const SCANLINES_PER_FIELD: u64 = 263;
+
+/// Scanline at which VBLANK begins, lasting until the end of the field.
+pub const VBLANK_START_SCANLINE: u64 = 240;
+
+bitflags!{
+/// Flags contained within `CSR`.
+///
+/// The event flags (`SIGNAL` to `EDWINT`) are cleared by writing `1`,
+/// and raise an interrupt when set unless masked by the same bit of `IMR >> 8`.
+pub struct Csr: u64 {
+	const SIGNAL = 0b0000_0000_0000_0001;
+	const FINISH = 0b0000_0000_0000_0010;
+	const HSINT  = 0b0000_0000_0000_0100;
+	const VSINT  = 0b0000_0000_0000_1000;
+	const EDWINT = 0b0000_0000_0001_0000;
+
+	const EVENTS = Self::SIGNAL.bits
+		| Self::FINISH.bits
+		| Self::HSINT.bits
+		| Self::VSINT.bits
+		| Self::EDWINT.bits;
+
+	const FLUSH  = 0b0000_0001_0000_0000;
+	const RESET  = 0b0000_0010_0000_0000;
+
+	/// Set for an odd field when interlaced.
+	const FIELD  = 0b0010_0000_0000_0000;
+
+	/// 2-bit field giving the state of the host FIFO: `01` is empty.
+	const FIFO_EMPTY = 0b0100_0000_0000_0000;
+}
+}
+
+/// Revision and ID reported in the upper half of `CSR`.
+const CSR_REVISION: u64 = 0x551b << 16;
+
+/// `IMR`'s value after reset: all interrupts masked.
+const IMR_DEFAULT: u64 = 0x7f00;
+
+/// Edges of the VBLANK signal, reported by [`Gs::step`](struct.Gs.html#method.step).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VBlank {
+	Start,
+	End,
+}
+
+/// Called with the displayed frame at each VSYNC.
+pub type FrameHook = Box<dyn FnMut(&Frame)>;
+
+pub struct Gs {
+	pub memory: LocalMemory,
+
+	/// General registers, as last written.
+	pub registers: [u64; GS_REGISTER_COUNT],
+
+	/// Privileged registers from `PMODE` to `BGCOLOR`, indexed by offset / 16.
+	pub display_registers: [u64; DISPLAY_REGISTER_COUNT],
+
+	pub csr: Csr,
+	pub imr: u64,
+	pub busdir: u64,
+	pub siglblid: u64,
+
+	clut: texture::Clut,
+
+	/// `CBP0` and `CBP1`, for conditional CLUT loads.
+	clut_bases: [u32; 2],
+
+	/// Vertices of the primitive being assembled.
+	vertices: Vec<Vertex>,
+
+	/// Host transfer started by the last `TRXDIR` write, until complete.
+	transfer: Option<(TransferDirection, Transfer)>,
+
+	interrupt: bool,
+
+	/// VSYNCs since power-on.
+	frames: u64,
+
+	/// Position within the current field.
+	scanline: u64,
+	scanline_cycles: u64,
+
+	frame_hook: Option<FrameHook>,
+}
+
+impl Default for Gs {
+	fn default() -> Self {
+		let mut registers = [0; GS_REGISTER_COUNT];
+		registers[GsRegister::PrModeCont as usize] = 1;
+
+		Self {
+			memory: LocalMemory::default(),
+			registers,
+			display_registers: [0; DISPLAY_REGISTER_COUNT],
+			csr: Csr::empty(),
+			imr: IMR_DEFAULT,
+			busdir: 0,
+			siglblid: 0,
+			clut: texture::Clut::default(),
+			clut_bases: [0; 2],
+			vertices: Vec::with_capacity(3),
+			transfer: None,
+			interrupt: false,
+			frames: 0,
+			scanline: 0,
+			scanline_cycles: 0,
+			frame_hook: None,
+		}
+	}
+}
+
+impl Gs {
+	/// Write `value` to general register `register`, as the GIF does.
+	pub fn write_register(&mut self, register: u8, value: u64) {
+		let index = register as usize;
+		if index >= GS_REGISTER_COUNT {
+			warn!("GS: write to unknown register 0x{:02x}", register);
+			return;
+		}
+
+		let register = match GsRegister::from_u8(register) {
+			Some(register) => register,
+			None => {
+				warn!("GS: write to unknown register 0x{:02x}", register);
+				return;
+			},
+		};
+
+		match register {
+			GsRegister::Tex2_1 | GsRegister::Tex2_2 => {
+				// TEX2 only replaces TEX0's PSM and CLUT fields.
+				self.registers[index] = value;
+				const TEX2_MASK: u64 = (0x3f << 20) | !((1 << 37) - 1);
+				let tex0 = &mut self.registers[index - (GsRegister::Tex2_1 as usize - GsRegister::Tex0_1 as usize)];
+				*tex0 = (*tex0 & !TEX2_MASK) | (value & TEX2_MASK);
+				let tex0 = *tex0;
+				self.load_clut(tex0);
+			},
+			GsRegister::Signal => {
+				let mask = value >> 32;
+				self.siglblid = (self.siglblid & !mask) | (value & mask & 0xffff_ffff);
+				self.raise(Csr::SIGNAL);
+			},
+			GsRegister::Label => {
+				let mask = (value >> 32) << 32;
+				self.siglblid = (self.siglblid & !mask) | ((value << 32) & mask);
+			},
+			GsRegister::Finish => self.raise(Csr::FINISH),
+			_ => self.registers[index] = value,
+		}
+
+		match register {
+			GsRegister::Prim => self.vertices.clear(),
+			GsRegister::Tex0_1 | GsRegister::Tex0_2 => self.load_clut(value),
+			GsRegister::TrxDir => self.start_transfer(value),
+			GsRegister::HwReg => self.write_transfer(value),
+			GsRegister::Xyz2 | GsRegister::Xyz3 | GsRegister::Xyzf2 | GsRegister::Xyzf3 => {
+				let with_fog = matches!(register, GsRegister::Xyzf2 | GsRegister::Xyzf3);
+				let kick = matches!(register, GsRegister::Xyz2 | GsRegister::Xyzf2);
+				self.push_vertex(value, with_fog, kick);
+			},
+			_ => {},
+		}
+	}
+
+	/// The primitive type and attributes in effect.
+	pub fn prim(&self) -> Prim {
+		let prim = Prim::from(self.registers[GsRegister::Prim as usize]);
+
+		if self.registers[GsRegister::PrModeCont as usize] & 1 != 0 {
+			prim
+		} else {
+			prim.with_attributes(self.registers[GsRegister::PrMode as usize])
+		}
+	}
+
+	fn push_vertex(&mut self, xyz: u64, with_fog: bool, kick: bool) {
+		let rgbaq = self.registers[GsRegister::Rgbaq as usize];
+		let st = self.registers[GsRegister::St as usize];
+		let uv = self.registers[GsRegister::Uv as usize];
+
+		let (z, fog) = if with_fog {
+			(field(xyz, 32, 24), field(xyz, 56, 8))
+		} else {
+			(field(xyz, 32, 32), field(self.registers[GsRegister::Fog as usize], 56, 8))
+		};
+
+		self.vertices.push(Vertex {
+			x: field(xyz, 0, 16) as i32,
+			y: field(xyz, 16, 16) as i32,
+			z: z as u32,
+			fog: fog as u8,
+			rgba: (rgbaq as u32).to_le_bytes(),
+			q: f32::from_bits((rgbaq >> 32) as u32),
+			s: f32::from_bits(st as u32),
+			t: f32::from_bits((st >> 32) as u32),
+			u: field(uv, 0, 14) as u32,
+			v: field(uv, 16, 14) as u32,
+		});
+
+		let prim = self.prim();
+		let needed = match prim.kind {
+			PrimitiveKind::Point => 1,
+			PrimitiveKind::Line | PrimitiveKind::LineStrip | PrimitiveKind::Sprite => 2,
+			PrimitiveKind::Triangle | PrimitiveKind::TriangleStrip | PrimitiveKind::TriangleFan => 3,
+			PrimitiveKind::Prohibited => {
+				self.vertices.clear();
+				return;
+			},
+		};
+
+		if self.vertices.len() < needed {
+			return;
+		}
+
+		if kick {
+			let env = DrawEnv::new(&self.registers, prim, &self.clut);
+			draw::draw(&mut self.memory, &env, &self.vertices);
+		}
+
+		match prim.kind {
+			PrimitiveKind::LineStrip | PrimitiveKind::TriangleStrip => {
+				self.vertices.remove(0);
+			},
+			PrimitiveKind::TriangleFan => {
+				self.vertices.remove(1);
+			},
+			_ => self.vertices.clear(),
+		}
+	}
+
+	/// Reload the CLUT as requested by `TEX0.CLD`.
+	fn load_clut(&mut self, raw: u64) {
+		let tex0 = Tex0::from(raw);
+		let indexed = Psm::from_u8(tex0.psm).map_or(false, Psm::is_indexed);
+
+		let load = match tex0.cld {
+			1 => true,
+			2 | 3 => {
+				self.clut_bases[usize::from(tex0.cld - 2)] = tex0.cbp;
+				true
+			},
+			4 | 5 => {
+				let base = &mut self.clut_bases[usize::from(tex0.cld - 4)];
+				let changed = *base != tex0.cbp;
+				*base = tex0.cbp;
+				changed
+			},
+			_ => false,
+		};
+
+		if load && indexed {
+			let texclut = TexClut::from(self.registers[GsRegister::TexClut as usize]);
+			self.clut.load(&self.memory, &tex0, texclut);
+		}
+	}
+
+	/// Start the transfer set up in `BITBLTBUF`, `TRXPOS` and `TRXREG`.
+	fn start_transfer(&mut self, trxdir: u64) {
+		let bitbltbuf = BitBltBuf::from(self.registers[GsRegister::BitBltBuf as usize]);
+		let trxpos = TrxPos::from(self.registers[GsRegister::TrxPos as usize]);
+		let trxreg = TrxReg::from(self.registers[GsRegister::TrxReg as usize]);
+		let direction = TransferDirection::from_u64(trxdir & 0b11).unwrap();
+
+		trace!("GS: {:?} transfer of {}×{} pixels", direction, trxreg.rrw, trxreg.rrh);
+
+		self.transfer = match direction {
+			TransferDirection::HostToLocal => Some((direction, Transfer::to_local(&bitbltbuf, &trxpos, &trxreg))),
+			TransferDirection::LocalToHost => Some((direction, Transfer::from_local(&bitbltbuf, &trxpos, &trxreg))),
+			TransferDirection::LocalToLocal => {
+				transfer::copy(&mut self.memory, &bitbltbuf, &trxpos, &trxreg);
+				None
+			},
+			TransferDirection::Deactivated => None,
+		};
+	}
+
+	/// Write a word of `HWREG` data to the host-to-local transfer in progress.
+	fn write_transfer(&mut self, data: u64) {
+		match &mut self.transfer {
+			Some((TransferDirection::HostToLocal, transfer)) => {
+				transfer.write(&mut self.memory, data);
+
+				if transfer.done() {
+					self.transfer = None;
+				}
+			},
+			_ => warn!("GS: HWREG write 0x{:016x} without a host to local transfer", data),
+		}
+	}
+
+	/// Read the next word of the local-to-host transfer in progress, if any.
+	pub fn read_transfer(&mut self) -> Option<u64> {
+		let word = match &mut self.transfer {
+			Some((TransferDirection::LocalToHost, transfer)) => transfer.read(&self.memory),
+			_ => None,
+		};
+
+		if self.transfer.as_ref().map_or(false, |(_, transfer)| transfer.done()) {
+			self.transfer = None;
+		}
+
+		word
+	}
+
+	/// Set an event flag in `CSR`, interrupting unless masked.
+	fn raise(&mut self, event: Csr) {
+		self.csr |= event;
+
+		if self.imr & (event.bits() << 8) == 0 {
+			self.interrupt = true;
+		}
+	}
+
+	/// Whether an unmasked event has been raised since the last call.
+	pub fn take_interrupt(&mut self) -> bool {
+		mem::take(&mut self.interrupt)
+	}
+
+	/// Read privileged register `offset` (from `GS_PRIV_REGISTERS_PHYSICAL`).
+	pub fn read_privileged(&self, offset: u32) -> u64 {
+		match offset & !0xf {
+			privileged::CSR => self.csr.bits() | Csr::FIFO_EMPTY.bits() | CSR_REVISION,
+			privileged::IMR => self.imr,
+			privileged::BUSDIR => self.busdir,
+			privileged::SIGLBLID => self.siglblid,
+			offset => self.display_registers.get((offset >> 4) as usize).copied().unwrap_or(0),
+		}
+	}
+
+	/// Write privileged register `offset` (from `GS_PRIV_REGISTERS_PHYSICAL`).
+	pub fn write_privileged(&mut self, offset: u32, value: u64) {
+		match offset & !0xf {
+			privileged::CSR => {
+				let written = Csr::from_bits_truncate(value);
+				self.csr.remove(written & Csr::EVENTS);
+
+				if written.contains(Csr::RESET) {
+					self.reset();
+				}
+			},
+			privileged::IMR => self.imr = value & IMR_DEFAULT,
+			privileged::BUSDIR => self.busdir = value & 1,
+			privileged::SIGLBLID => self.siglblid = value,
+			offset => if let Some(register) = self.display_registers.get_mut((offset >> 4) as usize) {
+				*register = value;
+			},
+		}
+	}
+
+	/// Drop any primitive in progress and clear pending events.
+	fn reset(&mut self) {
+		trace!("GS: reset");
+		self.vertices.clear();
+		self.transfer = None;
+		self.csr = Csr::empty();
+		self.interrupt = false;
+	}
+
+	fn display_register(&self, offset: u32) -> u64 {
+		self.display_registers[(offset >> 4) as usize]
+	}
+
+	/// Render read circuit `circuit` (0 or 1) into a frame, if it is enabled.
+	fn read_circuit(&self, circuit: usize) -> Option<Frame> {
+		let pmode = self.display_register(privileged::PMODE);
+		if pmode & (1 << circuit) == 0 {
+			return None;
+		}
+
+		let (dispfb, display) = if circuit == 0 {
+			(privileged::DISPFB1, privileged::DISPLAY1)
+		} else {
+			(privileged::DISPFB2, privileged::DISPLAY2)
+		};
+		let dispfb = DisplayFrameBuffer::from(self.display_register(dispfb));
+		let display = Display::from(self.display_register(display));
+
+		// Interlaced field mode shows every other line of the buffer per field.
+		let smode2 = self.display_register(privileged::SMODE2);
+		let field_mode = smode2 & 0b11 == 0b11;
+
+		let width = (display.dw + 1) / (display.magh + 1);
+		let mut height = (display.dh + 1) / (display.magv + 1);
+		if field_mode {
+			height /= 2;
+		}
+
+		let psm = Psm::from_u8(dispfb.psm).unwrap_or(Psm::Ct32);
+		let mut frame = Frame::new(width, height);
+
+		for y in 0..height {
+			for x in 0..width {
+				let raw = self.memory.read_pixel(
+					psm,
+					dispfb.fbp * BLOCKS_PER_PAGE,
+					dispfb.fbw,
+					dispfb.dbx + x,
+					dispfb.dby + y,
+				);
+				frame.set(x, y, texture::frame_to_rgba(psm, raw));
+			}
+		}
+
+		Some(frame)
+	}
+
+	/// The frame currently being displayed, according to `PMODE`, `DISPFB` and `DISPLAY`.
+	///
+	/// When both read circuits are enabled, circuit 1 is blended over circuit 2,
+	/// by `PMODE.ALP` or by its own alpha (per `PMODE.MMOD`).
+	pub fn display_frame(&self) -> Option<Frame> {
+		match (self.read_circuit(0), self.read_circuit(1)) {
+			(Some(mut top), Some(bottom)) => {
+				let pmode = self.display_register(privileged::PMODE);
+				let fixed = field(pmode, 5, 1) != 0;
+				let alp = field(pmode, 8, 8) as u32;
+
+				top.merge_over(&bottom, |pixel| if fixed {
+					alp
+				} else {
+					((pixel >> 24) * 2).min(0xff)
+				});
+
+				Some(top)
+			},
+			(top, bottom) => top.or(bottom),
+		}
+	}
+
+	/// A snapshot of local memory and all registers.
+	pub fn dump(&self) -> GsDump {
+		GsDump {
+			registers: self.registers,
+			display_registers: self.display_registers,
+			csr: self.read_privileged(privileged::CSR),
+			imr: self.imr,
+			busdir: self.busdir,
+			siglblid: self.siglblid,
+			memory: self.memory.clone(),
+		}
+	}
+
+	/// Number of VSYNCs so far.
+	pub fn frame_count(&self) -> u64 {
+		self.frames
+	}
+
+	/// Pass the displayed frame to `hook` at each VSYNC (or stop, with `None`).
+	pub fn set_frame_hook(&mut self, hook: Option<FrameHook>) {
+		self.frame_hook = hook;
+	}
+
+	/// Write the displayed frame at each VSYNC to a numbered file in `directory`.
+	pub fn dump_frames_to(&mut self, directory: impl Into<PathBuf>, format: ImageFormat) {
+		let directory = directory.into();
+		let mut count = 0u64;
+
+		self.set_frame_hook(Some(Box::new(move |frame| {
+			let path = directory.join(format!("frame_{:06}.{}", count, format.extension()));
+			count += 1;
+
+			let result = File::create(&path)
+				.and_then(|file| frame.write(format, &mut BufWriter::new(file)));
+
+			if let Err(e) = result {
+				warn!("GS: failed to write {}: {}", path.display(), e);
+			}
+		})));
+	}
+
+	/// Start VBLANK: flag VSYNC, flip the field and present the displayed frame.
+	pub fn vsync(&mut self) {
+		self.raise(Csr::VSINT);
+		self.csr.toggle(Csr::FIELD);
+		self.frames += 1;
+
+		if self.frame_hook.is_some() {
+			if let Some(frame) = self.display_frame() {
+				if let Some(hook) = &mut self.frame_hook {
+					hook(&frame);
+				}
+			}
+		}
+	}
+
+	/// Level of the HBLANK signal, as of the last [`step`](#method.step).
+	pub fn hblank(&self) -> bool {
+		self.scanline_cycles < HBLANK_EE_CYCLES
+	}
+
+	/// Advance video timing by `ee_cycles`, returning any edge of VBLANK.
+	pub fn step(&mut self, ee_cycles: u64) -> Option<VBlank> {
+		let mut edge = None;
+		self.scanline_cycles += ee_cycles;
+
+		while self.scanline_cycles >= EE_CYCLES_PER_SCANLINE {
+			self.scanline_cycles -= EE_CYCLES_PER_SCANLINE;
+			self.scanline += 1;
+			self.raise(Csr::HSINT);
+
+			if self.scanline == VBLANK_START_SCANLINE {
+				self.vsync();
+				edge = Some(VBlank::Start);
+			} else if self.scanline == SCANLINES_PER_FIELD {
+				self.scanline = 0;
+				edge = Some(VBlank::End);
+			}
+		}
+
+		edge
+	}
+}
+
+impl GsSink for Gs {
+	fn write_register(&mut self, register: u8, value: u64) {
+		Gs::write_register(self, register, value);
+	}
+
+	fn read_transfer(&mut self) -> Option<u64> {
+		Gs::read_transfer(self)
+	}
+}
+
+impl Device for Gs {
+	fn read_u32(&mut self, p_addr: u32) -> u32 {
+		let offset = p_addr - GS_PRIV_REGISTERS_PHYSICAL;
+		(self.read_privileged(offset) >> ((offset & 0b100) * 8)) as u32
+	}
+
+	fn write_u32(&mut self, p_addr: u32, value: u32) {
+		let offset = p_addr - GS_PRIV_REGISTERS_PHYSICAL;
+
+		// CSR's upper half is read-only, and its lower half acknowledges events.
+		if offset & !0xf == privileged::CSR {
+			if offset & 0b100 == 0 {
+				self.write_privileged(offset, u64::from(value));
+			}
+			return;
+		}
+
+		let shift = (offset & 0b100) * 8;
+		let old = self.read_privileged(offset) & !(0xffff_ffff << shift);
+		self.write_privileged(offset, old | (u64::from(value) << shift));
+	}
+
+	fn read_u64(&mut self, p_addr: u32) -> u64 {
+		self.read_privileged(p_addr - GS_PRIV_REGISTERS_PHYSICAL)
+	}
+
+	fn write_u64(&mut self, p_addr: u32, value: u64) {
+		self.write_privileged(p_addr - GS_PRIV_REGISTERS_PHYSICAL, value);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Set up context 1 to draw to a 64-pixel-wide PSMCT32 buffer at page 0.
+	fn drawing_gs() -> Gs {
+		let mut gs = Gs::default();
+		gs.write_register(GsRegister::Frame1 as u8, 1 << 16);
+		gs.write_register(GsRegister::Scissor1 as u8, (63 << 16) | (63 << 48));
+		gs
+	}
+
+	fn xyz(x: u64, y: u64) -> u64 {
+		(x << 4) | ((y << 4) << 16)
+	}
+
+	fn count(gs: &Gs, rgba: u32) -> usize {
+		(0..64).flat_map(|y| (0..64).map(move |x| (x, y)))
+			.filter(|&(x, y)| gs.memory.read_pixel(Psm::Ct32, 0, 1, x, y) == rgba)
+			.count()
+	}
+
+	#[test]
+	fn strip_and_fan_share_vertices() {
+		let mut gs = drawing_gs();
+		gs.write_register(GsRegister::Rgbaq as u8, 0xff);
+
+		// A strip of 4 vertices draws two triangles making a 16×16 square.
+		gs.write_register(GsRegister::Prim as u8, PrimitiveKind::TriangleStrip as u64);
+		for &(x, y) in &[(0, 0), (16, 0), (0, 16), (16, 16)] {
+			gs.write_register(GsRegister::Xyz2 as u8, xyz(x, y));
+		}
+		assert_eq!(count(&gs, 0xff), 16 * 16);
+
+		// A fan around (32, 32) drawing the same square shape.
+		gs.write_register(GsRegister::Rgbaq as u8, 0xff00);
+		gs.write_register(GsRegister::Prim as u8, PrimitiveKind::TriangleFan as u64);
+		for &(x, y) in &[(32, 32), (48, 32), (48, 48), (32, 48)] {
+			gs.write_register(GsRegister::Xyz2 as u8, xyz(x, y));
+		}
+		assert_eq!(count(&gs, 0xff00), 16 * 16);
+	}
+
+	#[test]
+	fn xyz3_queues_without_drawing() {
+		let mut gs = drawing_gs();
+		gs.write_register(GsRegister::Rgbaq as u8, 0xff);
+		gs.write_register(GsRegister::Prim as u8, PrimitiveKind::TriangleStrip as u64);
+
+		gs.write_register(GsRegister::Xyz2 as u8, xyz(0, 0));
+		gs.write_register(GsRegister::Xyz2 as u8, xyz(16, 0));
+		gs.write_register(GsRegister::Xyz3 as u8, xyz(0, 16));
+		assert_eq!(count(&gs, 0xff), 0);
+
+		// Only the second triangle is drawn, without the diagonal it shares with the first.
+		gs.write_register(GsRegister::Xyz2 as u8, xyz(16, 16));
+		assert_eq!(count(&gs, 0xff), (16 * 16 - 16) / 2);
+	}
+
+	#[test]
+	fn prmode_replaces_attributes() {
+		let mut gs = Gs::default();
+		gs.write_register(GsRegister::Prim as u8, PrimitiveKind::Sprite as u64 | (1 << 4));
+		gs.write_register(GsRegister::PrMode as u8, 1 << 6);
+		assert!(gs.prim().textured);
+
+		gs.write_register(GsRegister::PrModeCont as u8, 0);
+		assert_eq!(gs.prim().kind, PrimitiveKind::Sprite);
+		assert!(!gs.prim().textured);
+		assert!(gs.prim().blended);
+	}
+
+	#[test]
+	fn signal_and_finish_interrupt_unless_masked() {
+		let mut gs = Gs::default();
+
+		gs.write_register(GsRegister::Finish as u8, 0);
+		assert!(gs.csr.contains(Csr::FINISH));
+		assert!(!gs.take_interrupt());
+
+		gs.write_u32(GS_PRIV_REGISTERS_PHYSICAL + privileged::IMR, IMR_DEFAULT as u32 & !(Csr::SIGNAL.bits() << 8) as u32);
+		gs.write_register(GsRegister::Signal as u8, (0xffff << 32) | 0x1234_5678);
+		assert!(gs.take_interrupt());
+		assert_eq!(gs.siglblid, 0x5678);
+
+		gs.write_register(GsRegister::Label as u8, (0xff << 32) | 0xab);
+		assert_eq!(gs.siglblid, 0xab_0000_5678);
+
+		// Writing 1 to CSR acknowledges.
+		gs.write_u32(GS_PRIV_REGISTERS_PHYSICAL + privileged::CSR, Csr::SIGNAL.bits() as u32);
+		let csr = gs.read_u32(GS_PRIV_REGISTERS_PHYSICAL + privileged::CSR);
+		assert_eq!(u64::from(csr) & Csr::EVENTS.bits(), Csr::FINISH.bits());
+		assert_eq!(csr >> 16, 0x551b);
+	}
+
+	#[test]
+	fn conditional_clut_load() {
+		let mut gs = Gs::default();
+		gs.memory.write_pixel(Psm::Ct32, 64, 1, 0, 0, 0xabcd);
+
+		let tex0 = ((Psm::T4 as u64) << 20) | (64 << 37);
+		gs.write_register(GsRegister::Tex0_1 as u8, tex0 | (4 << 61));
+		assert_eq!(gs.clut.lookup(&Tex0::from(tex0), 0, Texa::default()), 0xabcd);
+
+		// CBP0 is unchanged, so no reload.
+		gs.memory.write_pixel(Psm::Ct32, 64, 1, 0, 0, 0x1234);
+		gs.write_register(GsRegister::Tex0_1 as u8, tex0 | (4 << 61));
+		assert_eq!(gs.clut.lookup(&Tex0::from(tex0), 0, Texa::default()), 0xabcd);
+
+		gs.write_register(GsRegister::Tex0_1 as u8, tex0 | (1 << 61));
+		assert_eq!(gs.clut.lookup(&Tex0::from(tex0), 0, Texa::default()), 0x1234);
+	}
+
+	#[test]
+	fn vsync_presents_display_frame() {
+		let mut gs = drawing_gs();
+		gs.write_register(GsRegister::Rgbaq as u8, 0x0040_80ff);
+		gs.write_register(GsRegister::Prim as u8, PrimitiveKind::Sprite as u64);
+		gs.write_register(GsRegister::Xyz2 as u8, xyz(0, 0));
+		gs.write_register(GsRegister::Xyz2 as u8, xyz(8, 4));
+
+		// Circuit 1 shows an 8×4 window of the buffer, magnified 4× horizontally.
+		let dispfb = 1 << 9;
+		let display = ((4 * 8 - 1) << 32) | (3 << 44) | (3 << 23);
+		gs.write_u64(GS_PRIV_REGISTERS_PHYSICAL + privileged::PMODE, 1);
+		gs.write_u64(GS_PRIV_REGISTERS_PHYSICAL + privileged::DISPFB1, dispfb);
+		gs.write_u64(GS_PRIV_REGISTERS_PHYSICAL + privileged::DISPLAY1, display);
+
+		let frames = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+		let sink = frames.clone();
+		gs.set_frame_hook(Some(Box::new(move |frame: &Frame| sink.borrow_mut().push(frame.clone()))));
+
+		let mut edges = vec![];
+		for _ in 0..SCANLINES_PER_FIELD {
+			edges.extend(gs.step(EE_CYCLES_PER_SCANLINE));
+		}
+
+		assert_eq!(edges, vec![VBlank::Start, VBlank::End]);
+		assert!(gs.csr.contains(Csr::VSINT | Csr::FIELD));
+
+		let frames = frames.borrow();
+		assert_eq!(frames.len(), 1);
+		assert_eq!((frames[0].width, frames[0].height), (8, 4));
+		assert_eq!(frames[0].get(7, 3), [0xff, 0x80, 0x40]);
+	}
+
+	#[test]
+	fn hwreg_transfers_both_ways() {
+		let mut gs = Gs::default();
+		let bitbltbuf = ((Psm::Ct16 as u64) << 24) | (32 << 32) | (1 << 48) | ((Psm::Ct16 as u64) << 56);
+
+		gs.write_register(GsRegister::BitBltBuf as u8, bitbltbuf | 32 | (1 << 16));
+		gs.write_register(GsRegister::TrxPos as u8, (8 << 32) | (1 << 48));
+		gs.write_register(GsRegister::TrxReg as u8, 4 | (1 << 32));
+		gs.write_register(GsRegister::TrxDir as u8, TransferDirection::HostToLocal as u64);
+		gs.write_register(GsRegister::HwReg as u8, 0x0004_0003_0002_0001);
+		assert_eq!(gs.memory.read_pixel(Psm::Ct16, 32, 1, 11, 1), 4);
+
+		// The transfer is over, so further data is dropped.
+		gs.write_register(GsRegister::HwReg as u8, !0);
+		assert_eq!(gs.memory.read_pixel(Psm::Ct16, 32, 1, 12, 1), 0);
+
+		// Copy the row down a line, then read both rows back.
+		gs.write_register(GsRegister::TrxPos as u8, 8 | (1 << 16) | (8 << 32) | (2 << 48));
+		gs.write_register(GsRegister::TrxDir as u8, TransferDirection::LocalToLocal as u64);
+		gs.write_register(GsRegister::TrxPos as u8, 8 | (1 << 16));
+		gs.write_register(GsRegister::TrxReg as u8, 4 | (2 << 32));
+		gs.write_register(GsRegister::TrxDir as u8, TransferDirection::LocalToHost as u64);
+
+		assert_eq!(gs.read_transfer(), Some(0x0004_0003_0002_0001));
+		assert_eq!(gs.read_transfer(), Some(0x0004_0003_0002_0001));
+		assert_eq!(gs.read_transfer(), None);
+	}
+}
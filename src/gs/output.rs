@@ -0,0 +1,256 @@
+//! Frames read out of local memory, and their encoding as PNG or PPM images.
+//!
+//! Both encoders are self-contained: PNGs are written with uncompressed
+//! (stored) deflate blocks, which any decoder accepts.
+
+use byteorder::{
+	BigEndian,
+	LittleEndian,
+	WriteBytesExt,
+};
+use std::io::{
+	self,
+	Write,
+};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Largest payload of a stored deflate block.
+const STORED_BLOCK_MAX: usize = 0xffff;
+
+/// Image file formats frames can be written as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+	Png,
+	Ppm,
+}
+
+impl ImageFormat {
+	pub fn extension(self) -> &'static str {
+		match self {
+			ImageFormat::Png => "png",
+			ImageFormat::Ppm => "ppm",
+		}
+	}
+
+	/// Parse a format from its file extension.
+	pub fn from_extension(extension: &str) -> Option<Self> {
+		match extension.to_ascii_lowercase().as_str() {
+			"png" => Some(ImageFormat::Png),
+			"ppm" => Some(ImageFormat::Ppm),
+			_ => None,
+		}
+	}
+}
+
+/// An image in RGBA8888, as produced by the GS's read circuits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+	pub width: u32,
+	pub height: u32,
+
+	/// Pixels in row-major order, with red in the low byte.
+	pub pixels: Vec<u32>,
+}
+
+impl Frame {
+	/// A black frame.
+	pub fn new(width: u32, height: u32) -> Self {
+		Self {
+			width,
+			height,
+			pixels: vec![0; (width * height) as usize],
+		}
+	}
+
+	pub fn set(&mut self, x: u32, y: u32, rgba: u32) {
+		self.pixels[(y * self.width + x) as usize] = rgba;
+	}
+
+	/// Colour of pixel (`x`, `y`), as RGB.
+	pub fn get(&self, x: u32, y: u32) -> [u8; 3] {
+		let [r, g, b, _] = self.pixels[(y * self.width + x) as usize].to_le_bytes();
+		[r, g, b]
+	}
+
+	/// Blend this frame over `other`, weighting each pixel by `alpha(pixel)` out of 255.
+	pub fn merge_over<F: Fn(u32) -> u32>(&mut self, other: &Frame, alpha: F) {
+		for y in 0..self.height.min(other.height) {
+			for x in 0..self.width.min(other.width) {
+				let index = (y * self.width + x) as usize;
+				let top = self.pixels[index].to_le_bytes();
+				let bottom = other.pixels[(y * other.width + x) as usize].to_le_bytes();
+				let weight = alpha(self.pixels[index]).min(0xff);
+
+				let mut out = top;
+				for (channel, value) in out.iter_mut().enumerate().take(3) {
+					*value = ((u32::from(top[channel]) * weight
+						+ u32::from(bottom[channel]) * (0xff - weight)) / 0xff) as u8;
+				}
+
+				self.pixels[index] = u32::from_le_bytes(out);
+			}
+		}
+	}
+
+	/// Pixel data as packed RGB rows.
+	fn rgb_rows(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+		self.pixels
+			.chunks(self.width.max(1) as usize)
+			.map(|row| row.iter().flat_map(|pixel| pixel.to_le_bytes()[..3].to_vec()).collect())
+	}
+
+	pub fn write<W: Write>(&self, format: ImageFormat, writer: &mut W) -> io::Result<()> {
+		match format {
+			ImageFormat::Png => self.write_png(writer),
+			ImageFormat::Ppm => self.write_ppm(writer),
+		}
+	}
+
+	/// Write as a binary (`P6`) PPM.
+	pub fn write_ppm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+
+		for row in self.rgb_rows() {
+			writer.write_all(&row)?;
+		}
+
+		writer.flush()
+	}
+
+	/// Write as an 8-bit RGB PNG.
+	pub fn write_png<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		writer.write_all(&PNG_SIGNATURE)?;
+
+		let mut header = vec![];
+		header.write_u32::<BigEndian>(self.width)?;
+		header.write_u32::<BigEndian>(self.height)?;
+		// 8 bits per channel, RGB, default compression, filter and no interlace.
+		header.extend_from_slice(&[8, 2, 0, 0, 0]);
+		write_chunk(writer, b"IHDR", &header)?;
+
+		// Each scanline is prefixed by its filter type (none).
+		let mut scanlines = Vec::with_capacity(((self.width * 3 + 1) * self.height) as usize);
+		for row in self.rgb_rows() {
+			scanlines.push(0);
+			scanlines.extend(row);
+		}
+
+		write_chunk(writer, b"IDAT", &zlib_stored(&scanlines)?)?;
+		write_chunk(writer, b"IEND", &[])?;
+
+		writer.flush()
+	}
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+	writer.write_u32::<BigEndian>(data.len() as u32)?;
+	writer.write_all(kind)?;
+	writer.write_all(data)?;
+	writer.write_u32::<BigEndian>(crc32(kind.iter().chain(data)))
+}
+
+/// Wrap `data` in a zlib stream of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> io::Result<Vec<u8>> {
+	let mut out = Vec::with_capacity(data.len() + data.len() / STORED_BLOCK_MAX * 5 + 11);
+
+	// Deflate with a 32 KiB window, no preset dictionary.
+	out.extend_from_slice(&[0x78, 0x01]);
+
+	let mut blocks = data.chunks(STORED_BLOCK_MAX).peekable();
+	if blocks.peek().is_none() {
+		out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+	}
+
+	while let Some(block) = blocks.next() {
+		out.push(if blocks.peek().is_none() { 1 } else { 0 });
+		out.write_u16::<LittleEndian>(block.len() as u16)?;
+		out.write_u16::<LittleEndian>(!(block.len() as u16))?;
+		out.extend_from_slice(block);
+	}
+
+	out.write_u32::<BigEndian>(adler32(data))?;
+	Ok(out)
+}
+
+fn crc32<'a, I: IntoIterator<Item = &'a u8>>(bytes: I) -> u32 {
+	let mut table = [0u32; 256];
+	for (n, entry) in table.iter_mut().enumerate() {
+		*entry = (0..8).fold(n as u32, |c, _| if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 });
+	}
+
+	!bytes.into_iter().fold(!0u32, |crc, &byte| table[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8))
+}
+
+fn adler32(data: &[u8]) -> u32 {
+	const MODULUS: u32 = 65521;
+
+	let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+		let a = (a + u32::from(byte)) % MODULUS;
+		(a, (b + a) % MODULUS)
+	});
+
+	(b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn checksums() {
+		assert_eq!(crc32(b"IEND"), 0xae42_6082);
+		assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+		assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+	}
+
+	#[test]
+	fn ppm_layout() {
+		let mut frame = Frame::new(2, 1);
+		frame.set(1, 0, 0xff33_2211);
+
+		let mut out = vec![];
+		frame.write_ppm(&mut out).unwrap();
+		assert_eq!(out, b"P6\n2 1\n255\n\0\0\0\x11\x22\x33".to_vec());
+	}
+
+	#[test]
+	fn png_layout() {
+		let mut frame = Frame::new(1, 1);
+		frame.set(0, 0, 0x0033_2211);
+
+		let mut out = vec![];
+		frame.write_png(&mut out).unwrap();
+
+		assert_eq!(&out[..8], &PNG_SIGNATURE);
+		assert_eq!(&out[12..16], b"IHDR");
+		assert_eq!(&out[16..24], &[0, 0, 0, 1, 0, 0, 0, 1]);
+
+		// IDAT holds the zlib header, one final stored block of the scanline, and its checksum.
+		let idat = &out[33..];
+		assert_eq!(&idat[4..8], b"IDAT");
+		assert_eq!(&idat[8..20], &[0x78, 0x01, 1, 4, 0, 0xfb, 0xff, 0, 0x11, 0x22, 0x33, 0]);
+		assert_eq!(&out[out.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]);
+	}
+
+	#[test]
+	fn large_images_span_stored_blocks() {
+		let data = vec![7u8; STORED_BLOCK_MAX + 1];
+		let stream = zlib_stored(&data).unwrap();
+
+		assert_eq!(stream[2], 0);
+		assert_eq!(stream[3 + 4 + STORED_BLOCK_MAX], 1);
+		assert_eq!(stream.len(), 2 + 2 * 5 + data.len() + 4);
+	}
+
+	#[test]
+	fn merge_weights_top_frame() {
+		let mut top = Frame::new(1, 1);
+		top.set(0, 0, 0xff);
+		let mut bottom = Frame::new(1, 1);
+		bottom.set(0, 0, 0xff00);
+
+		top.merge_over(&bottom, |_| 0x33);
+		assert_eq!(top.get(0, 0), [0x33, 0xcc, 0]);
+	}
+}
@@ -0,0 +1,554 @@
+//! Addresses of the GS's general registers, as written through the GIF.
+
+use enum_primitive::*;
+
+enum_from_primitive!{
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GsRegister {
+	Prim       = 0x00,
+	Rgbaq      = 0x01,
+	St         = 0x02,
+	Uv         = 0x03,
+	Xyzf2      = 0x04,
+	Xyz2       = 0x05,
+	Tex0_1     = 0x06,
+	Tex0_2     = 0x07,
+	Clamp1     = 0x08,
+	Clamp2     = 0x09,
+	Fog        = 0x0a,
+	Xyzf3      = 0x0c,
+	Xyz3       = 0x0d,
+	Tex1_1     = 0x14,
+	Tex1_2     = 0x15,
+	Tex2_1     = 0x16,
+	Tex2_2     = 0x17,
+	XyOffset1  = 0x18,
+	XyOffset2  = 0x19,
+	PrModeCont = 0x1a,
+	PrMode     = 0x1b,
+	TexClut    = 0x1c,
+	ScanMsk    = 0x22,
+	MipTbp1_1  = 0x34,
+	MipTbp1_2  = 0x35,
+	MipTbp2_1  = 0x36,
+	MipTbp2_2  = 0x37,
+	Texa       = 0x3b,
+	FogCol     = 0x3d,
+	TexFlush   = 0x3f,
+	Scissor1   = 0x40,
+	Scissor2   = 0x41,
+	Alpha1     = 0x42,
+	Alpha2     = 0x43,
+	Dimx       = 0x44,
+	Dthe       = 0x45,
+	ColClamp   = 0x46,
+	Test1      = 0x47,
+	Test2      = 0x48,
+	Pabe       = 0x49,
+	Fba1       = 0x4a,
+	Fba2       = 0x4b,
+	Frame1     = 0x4c,
+	Frame2     = 0x4d,
+	Zbuf1      = 0x4e,
+	Zbuf2      = 0x4f,
+	BitBltBuf  = 0x50,
+	TrxPos     = 0x51,
+	TrxReg     = 0x52,
+	TrxDir     = 0x53,
+	HwReg      = 0x54,
+	Signal     = 0x60,
+	Finish     = 0x61,
+	Label      = 0x62,
+}
+}
+
+/// Number of general register addresses.
+pub const GS_REGISTER_COUNT: usize = 0x63;
+
+/// Extract the `width`-bit field starting at bit `shift` of `value`.
+#[inline]
+pub fn field(value: u64, shift: u32, width: u32) -> u64 {
+	(value >> shift) & ((1 << width) - 1)
+}
+
+enum_from_primitive!{
+/// Primitive types selectable in `PRIM`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrimitiveKind {
+	Point = 0,
+	Line,
+	LineStrip,
+	Triangle,
+	TriangleStrip,
+	TriangleFan,
+	Sprite,
+	Prohibited,
+}
+}
+
+/// `PRIM`, or `PRMODE`'s drawing attributes with `PRIM`'s type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Prim {
+	pub kind: PrimitiveKind,
+
+	/// Gouraud shading (`IIP`).
+	pub gouraud: bool,
+
+	/// Texture mapping (`TME`).
+	pub textured: bool,
+
+	/// Fogging (`FGE`).
+	pub fogged: bool,
+
+	/// Alpha blending (`ABE`).
+	pub blended: bool,
+
+	/// Texture coordinates come from `UV` rather than `ST` (`FST`).
+	pub uv: bool,
+
+	/// Which drawing environment (`_1` or `_2` registers) is in use (`CTXT`).
+	pub context: usize,
+}
+
+impl From<u64> for Prim {
+	fn from(raw: u64) -> Self {
+		Self {
+			kind: PrimitiveKind::from_u64(field(raw, 0, 3)).unwrap(),
+			gouraud: field(raw, 3, 1) != 0,
+			textured: field(raw, 4, 1) != 0,
+			fogged: field(raw, 5, 1) != 0,
+			blended: field(raw, 6, 1) != 0,
+			uv: field(raw, 8, 1) != 0,
+			context: field(raw, 9, 1) as usize,
+		}
+	}
+}
+
+impl Prim {
+	/// Take the drawing attributes from `PRMODE` instead (when `PRMODECONT.AC` is clear).
+	pub fn with_attributes(self, prmode: u64) -> Self {
+		Self {
+			kind: self.kind,
+			..Self::from(prmode)
+		}
+	}
+}
+
+/// `TEX0_1`/`TEX0_2`: texture buffer and CLUT settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tex0 {
+	/// Base pointer, in blocks.
+	pub tbp0: u32,
+
+	/// Buffer width, in units of 64 pixels.
+	pub tbw: u32,
+	pub psm: u8,
+
+	/// Texture width and height, as powers of two.
+	pub tw: u32,
+	pub th: u32,
+
+	/// Whether the texture's alpha is used (`TCC`).
+	pub has_alpha: bool,
+	pub tfx: u8,
+
+	/// CLUT base pointer, in blocks.
+	pub cbp: u32,
+	pub cpsm: u8,
+
+	/// CLUT storage mode: CSM1 (`false`) or CSM2 (`true`).
+	pub csm2: bool,
+
+	/// CLUT entry offset, in units of 16 entries.
+	pub csa: u32,
+	pub cld: u8,
+}
+
+impl From<u64> for Tex0 {
+	fn from(raw: u64) -> Self {
+		Self {
+			tbp0: field(raw, 0, 14) as u32,
+			tbw: field(raw, 14, 6) as u32,
+			psm: field(raw, 20, 6) as u8,
+			tw: field(raw, 26, 4) as u32,
+			th: field(raw, 30, 4) as u32,
+			has_alpha: field(raw, 34, 1) != 0,
+			tfx: field(raw, 35, 2) as u8,
+			cbp: field(raw, 37, 14) as u32,
+			cpsm: field(raw, 51, 4) as u8,
+			csm2: field(raw, 55, 1) != 0,
+			csa: field(raw, 56, 5) as u32,
+			cld: field(raw, 61, 3) as u8,
+		}
+	}
+}
+
+/// `CLAMP_1`/`CLAMP_2`: texture wrap modes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Clamp {
+	pub wms: u8,
+	pub wmt: u8,
+	pub minu: u32,
+	pub maxu: u32,
+	pub minv: u32,
+	pub maxv: u32,
+}
+
+impl From<u64> for Clamp {
+	fn from(raw: u64) -> Self {
+		Self {
+			wms: field(raw, 0, 2) as u8,
+			wmt: field(raw, 2, 2) as u8,
+			minu: field(raw, 4, 10) as u32,
+			maxu: field(raw, 14, 10) as u32,
+			minv: field(raw, 24, 10) as u32,
+			maxv: field(raw, 34, 10) as u32,
+		}
+	}
+}
+
+/// `TEXA`: alpha expansion for 24- and 16-bit textures.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Texa {
+	pub ta0: u8,
+
+	/// Treat black texels as transparent (`AEM`).
+	pub aem: bool,
+	pub ta1: u8,
+}
+
+impl From<u64> for Texa {
+	fn from(raw: u64) -> Self {
+		Self {
+			ta0: field(raw, 0, 8) as u8,
+			aem: field(raw, 15, 1) != 0,
+			ta1: field(raw, 32, 8) as u8,
+		}
+	}
+}
+
+/// `TEXCLUT`: CLUT position for CSM2.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TexClut {
+	/// Buffer width, in units of 64 pixels.
+	pub cbw: u32,
+
+	/// Offsets, in units of 16 pixels (`cou`) and lines (`cov`).
+	pub cou: u32,
+	pub cov: u32,
+}
+
+impl From<u64> for TexClut {
+	fn from(raw: u64) -> Self {
+		Self {
+			cbw: field(raw, 0, 6) as u32,
+			cou: field(raw, 6, 6) as u32,
+			cov: field(raw, 12, 10) as u32,
+		}
+	}
+}
+
+/// `SCISSOR_1`/`SCISSOR_2`: inclusive bounds of the drawable window.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Scissor {
+	pub x0: i32,
+	pub x1: i32,
+	pub y0: i32,
+	pub y1: i32,
+}
+
+impl From<u64> for Scissor {
+	fn from(raw: u64) -> Self {
+		Self {
+			x0: field(raw, 0, 11) as i32,
+			x1: field(raw, 16, 11) as i32,
+			y0: field(raw, 32, 11) as i32,
+			y1: field(raw, 48, 11) as i32,
+		}
+	}
+}
+
+/// `ALPHA_1`/`ALPHA_2`: blending equation `((A - B) * C >> 7) + D`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Alpha {
+	/// Selectors for `A`, `B` and `D`: source, destination or zero.
+	pub a: u8,
+	pub b: u8,
+
+	/// Selector for `C`: source alpha, destination alpha or `fix`.
+	pub c: u8,
+	pub d: u8,
+	pub fix: u8,
+}
+
+impl From<u64> for Alpha {
+	fn from(raw: u64) -> Self {
+		Self {
+			a: field(raw, 0, 2) as u8,
+			b: field(raw, 2, 2) as u8,
+			c: field(raw, 4, 2) as u8,
+			d: field(raw, 6, 2) as u8,
+			fix: field(raw, 32, 8) as u8,
+		}
+	}
+}
+
+/// `TEST_1`/`TEST_2`: pixel tests.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Test {
+	pub alpha_test: bool,
+	pub atst: u8,
+	pub aref: u8,
+	pub afail: u8,
+
+	/// Destination alpha test (`DATE`), passing pixels whose alpha MSB is `datm`.
+	pub dest_alpha_test: bool,
+	pub datm: bool,
+	pub depth_test: bool,
+	pub ztst: u8,
+}
+
+impl From<u64> for Test {
+	fn from(raw: u64) -> Self {
+		Self {
+			alpha_test: field(raw, 0, 1) != 0,
+			atst: field(raw, 1, 3) as u8,
+			aref: field(raw, 4, 8) as u8,
+			afail: field(raw, 12, 2) as u8,
+			dest_alpha_test: field(raw, 14, 1) != 0,
+			datm: field(raw, 15, 1) != 0,
+			depth_test: field(raw, 16, 1) != 0,
+			ztst: field(raw, 17, 2) as u8,
+		}
+	}
+}
+
+/// `FRAME_1`/`FRAME_2`: the frame buffer being drawn to.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameBuffer {
+	/// Base pointer, in pages.
+	pub fbp: u32,
+
+	/// Buffer width, in units of 64 pixels.
+	pub fbw: u32,
+	pub psm: u8,
+
+	/// Bits which are not written.
+	pub fbmsk: u32,
+}
+
+impl From<u64> for FrameBuffer {
+	fn from(raw: u64) -> Self {
+		Self {
+			fbp: field(raw, 0, 9) as u32,
+			fbw: field(raw, 16, 6) as u32,
+			psm: field(raw, 24, 6) as u8,
+			fbmsk: field(raw, 32, 32) as u32,
+		}
+	}
+}
+
+/// `ZBUF_1`/`ZBUF_2`: the depth buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ZBuffer {
+	/// Base pointer, in pages.
+	pub zbp: u32,
+
+	/// Storage format, without the `0x30` common to all Z formats.
+	pub psm: u8,
+
+	/// Whether depth writes are disabled.
+	pub zmsk: bool,
+}
+
+impl From<u64> for ZBuffer {
+	fn from(raw: u64) -> Self {
+		Self {
+			zbp: field(raw, 0, 9) as u32,
+			psm: field(raw, 24, 4) as u8,
+			zmsk: field(raw, 32, 1) != 0,
+		}
+	}
+}
+
+/// `XYOFFSET_1`/`XYOFFSET_2`: primitive to window coordinate offset, in 12.4 fixed point.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct XyOffset {
+	pub ofx: i32,
+	pub ofy: i32,
+}
+
+impl From<u64> for XyOffset {
+	fn from(raw: u64) -> Self {
+		Self {
+			ofx: field(raw, 0, 16) as i32,
+			ofy: field(raw, 32, 16) as i32,
+		}
+	}
+}
+
+/// `BITBLTBUF`: the source and destination buffers of a transfer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BitBltBuf {
+	/// Source base pointer, in blocks.
+	pub sbp: u32,
+
+	/// Source buffer width, in units of 64 pixels.
+	pub sbw: u32,
+	pub spsm: u8,
+
+	/// Destination base pointer, in blocks.
+	pub dbp: u32,
+
+	/// Destination buffer width, in units of 64 pixels.
+	pub dbw: u32,
+	pub dpsm: u8,
+}
+
+impl From<u64> for BitBltBuf {
+	fn from(raw: u64) -> Self {
+		Self {
+			sbp: field(raw, 0, 14) as u32,
+			sbw: field(raw, 16, 6) as u32,
+			spsm: field(raw, 24, 6) as u8,
+			dbp: field(raw, 32, 14) as u32,
+			dbw: field(raw, 48, 6) as u32,
+			dpsm: field(raw, 56, 6) as u8,
+		}
+	}
+}
+
+/// `TRXPOS`: the corners of a transfer's source and destination rectangles.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TrxPos {
+	pub ssax: u32,
+	pub ssay: u32,
+	pub dsax: u32,
+	pub dsay: u32,
+
+	/// Order of pixels in local-to-local transfers, to allow overlapping rectangles.
+	pub dir: u8,
+}
+
+impl From<u64> for TrxPos {
+	fn from(raw: u64) -> Self {
+		Self {
+			ssax: field(raw, 0, 11) as u32,
+			ssay: field(raw, 16, 11) as u32,
+			dsax: field(raw, 32, 11) as u32,
+			dsay: field(raw, 48, 11) as u32,
+			dir: field(raw, 59, 2) as u8,
+		}
+	}
+}
+
+/// `TRXREG`: the size of a transfer's rectangles, in pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TrxReg {
+	pub rrw: u32,
+	pub rrh: u32,
+}
+
+impl From<u64> for TrxReg {
+	fn from(raw: u64) -> Self {
+		Self {
+			rrw: field(raw, 0, 12) as u32,
+			rrh: field(raw, 32, 12) as u32,
+		}
+	}
+}
+
+enum_from_primitive!{
+/// Directions selectable in `TRXDIR`, whose write starts a transfer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransferDirection {
+	HostToLocal = 0,
+	LocalToHost,
+	LocalToLocal,
+	Deactivated,
+}
+}
+
+/// Addresses of the privileged registers, relative to `GS_PRIV_REGISTERS_PHYSICAL`.
+pub mod privileged {
+	pub const PMODE: u32 = 0x0000;
+	pub const SMODE1: u32 = 0x0010;
+	pub const SMODE2: u32 = 0x0020;
+	pub const SRFSH: u32 = 0x0030;
+	pub const SYNCH1: u32 = 0x0040;
+	pub const SYNCH2: u32 = 0x0050;
+	pub const SYNCV: u32 = 0x0060;
+	pub const DISPFB1: u32 = 0x0070;
+	pub const DISPLAY1: u32 = 0x0080;
+	pub const DISPFB2: u32 = 0x0090;
+	pub const DISPLAY2: u32 = 0x00a0;
+	pub const EXTBUF: u32 = 0x00b0;
+	pub const EXTDATA: u32 = 0x00c0;
+	pub const EXTWRITE: u32 = 0x00d0;
+	pub const BGCOLOR: u32 = 0x00e0;
+	pub const CSR: u32 = 0x1000;
+	pub const IMR: u32 = 0x1010;
+	pub const BUSDIR: u32 = 0x1040;
+	pub const SIGLBLID: u32 = 0x1080;
+
+	/// Names of the registers from `PMODE` to `BGCOLOR`, in address order.
+	pub const DISPLAY_NAMES: [&str; 15] = [
+		"PMODE", "SMODE1", "SMODE2", "SRFSH", "SYNCH1", "SYNCH2", "SYNCV", "DISPFB1",
+		"DISPLAY1", "DISPFB2", "DISPLAY2", "EXTBUF", "EXTDATA", "EXTWRITE", "BGCOLOR",
+	];
+}
+
+/// `DISPFB1`/`DISPFB2`: the frame buffer read by a read circuit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DisplayFrameBuffer {
+	/// Base pointer, in pages.
+	pub fbp: u32,
+
+	/// Buffer width, in units of 64 pixels.
+	pub fbw: u32,
+	pub psm: u8,
+
+	/// Position of the displayed rectangle within the buffer.
+	pub dbx: u32,
+	pub dby: u32,
+}
+
+impl From<u64> for DisplayFrameBuffer {
+	fn from(raw: u64) -> Self {
+		Self {
+			fbp: field(raw, 0, 9) as u32,
+			fbw: field(raw, 9, 6) as u32,
+			psm: field(raw, 15, 5) as u8,
+			dbx: field(raw, 32, 11) as u32,
+			dby: field(raw, 43, 11) as u32,
+		}
+	}
+}
+
+/// `DISPLAY1`/`DISPLAY2`: placement and size of a read circuit's output.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Display {
+	pub dx: u32,
+	pub dy: u32,
+
+	/// Horizontal and vertical magnification, less one.
+	pub magh: u32,
+	pub magv: u32,
+
+	/// Width (in video clocks) and height (in lines), less one.
+	pub dw: u32,
+	pub dh: u32,
+}
+
+impl From<u64> for Display {
+	fn from(raw: u64) -> Self {
+		Self {
+			dx: field(raw, 0, 12) as u32,
+			dy: field(raw, 12, 11) as u32,
+			magh: field(raw, 23, 4) as u32,
+			magv: field(raw, 27, 2) as u32,
+			dw: field(raw, 32, 12) as u32,
+			dh: field(raw, 44, 11) as u32,
+		}
+	}
+}
@@ -0,0 +1,280 @@
+//! Texel formats, the CLUT buffer and texture wrap modes.
+
+use super::{
+	memory::{
+		LocalMemory,
+		Psm,
+	},
+	registers::{
+		Clamp,
+		Tex0,
+		TexClut,
+		Texa,
+	},
+};
+use enum_primitive::*;
+
+/// Alpha expansion used when reading 16-bit frame buffers: the `A` bit means 1.0.
+pub const FRAME_TEXA: Texa = Texa {
+	ta0: 0,
+	aem: false,
+	ta1: 0x80,
+};
+
+/// Texture wrap modes, as in `CLAMP.WMS`/`CLAMP.WMT`.
+pub const WRAP_REPEAT: u8 = 0;
+pub const WRAP_CLAMP: u8 = 1;
+pub const WRAP_REGION_CLAMP: u8 = 2;
+pub const WRAP_REGION_REPEAT: u8 = 3;
+
+/// Texture functions, as in `TEX0.TFX`.
+pub const TFX_MODULATE: u8 = 0;
+pub const TFX_DECAL: u8 = 1;
+pub const TFX_HIGHLIGHT: u8 = 2;
+pub const TFX_HIGHLIGHT2: u8 = 3;
+
+/// Expand a 16-bit `A1B5G5R5` colour to RGBA8888, taking alpha from `texa`.
+pub fn expand_16(colour: u32, texa: Texa) -> u32 {
+	let rgb = ((colour & 0x1f) << 3)
+		| (((colour >> 5) & 0x1f) << 11)
+		| (((colour >> 10) & 0x1f) << 19);
+
+	let alpha = if colour & 0x8000 != 0 {
+		texa.ta1
+	} else if texa.aem && rgb == 0 {
+		0
+	} else {
+		texa.ta0
+	};
+
+	rgb | (u32::from(alpha) << 24)
+}
+
+/// Expand a 24-bit colour to RGBA8888, taking alpha from `texa`.
+pub fn expand_24(colour: u32, texa: Texa) -> u32 {
+	let rgb = colour & 0xff_ffff;
+	let alpha = if texa.aem && rgb == 0 { 0 } else { texa.ta0 };
+
+	rgb | (u32::from(alpha) << 24)
+}
+
+/// Pack an RGBA8888 colour into `A1B5G5R5`.
+pub fn pack_16(colour: u32) -> u32 {
+	((colour >> 3) & 0x1f)
+		| (((colour >> 11) & 0x1f) << 5)
+		| (((colour >> 19) & 0x1f) << 10)
+		| (((colour >> 31) & 1) << 15)
+}
+
+/// Convert a pixel of a texture or CLUT to RGBA8888.
+pub fn to_rgba(psm: Psm, raw: u32, texa: Texa) -> u32 {
+	match psm.bits_per_pixel() {
+		16 => expand_16(raw, texa),
+		_ if matches!(psm, Psm::Ct24 | Psm::Z24) => expand_24(raw, texa),
+		_ => raw,
+	}
+}
+
+/// Convert a pixel of a frame buffer to RGBA8888.
+///
+/// 24-bit buffers have no alpha, which reads as 1.0 (`0x80`).
+pub fn frame_to_rgba(psm: Psm, raw: u32) -> u32 {
+	match psm {
+		Psm::Ct24 | Psm::Z24 => raw | 0x8000_0000,
+		_ => to_rgba(psm, raw, FRAME_TEXA),
+	}
+}
+
+/// Apply a wrap mode to texel coordinate `coord` of a texture `size` texels across.
+pub fn wrap(coord: i32, mode: u8, size: u32, min: u32, max: u32) -> u32 {
+	match mode {
+		WRAP_REPEAT => (coord as u32) & (size - 1),
+		WRAP_CLAMP => coord.max(0).min(size as i32 - 1) as u32,
+		WRAP_REGION_CLAMP => coord.max(min as i32).min(max as i32) as u32,
+		_ => ((coord as u32) & min) | max,
+	}
+}
+
+/// The GS's CLUT buffer.
+///
+/// Entries are kept as 16-bit halves: 32-bit entries store their low half at
+/// `n` and their high half at `n + 256`, as in the hardware's 1 KiB buffer.
+#[derive(Clone)]
+pub struct Clut {
+	halves: [u16; 512],
+}
+
+impl Default for Clut {
+	fn default() -> Self {
+		Self {
+			halves: [0; 512],
+		}
+	}
+}
+
+impl Clut {
+	/// Whether `tex0`'s format is 8-bit indexed, and so uses 256 entries (else 16).
+	fn full(tex0: &Tex0) -> bool {
+		matches!(Psm::from_u8(tex0.psm), Some(Psm::T8) | Some(Psm::T8H))
+	}
+
+	/// First entry used by `tex0`.
+	fn offset(tex0: &Tex0) -> usize {
+		match (Self::full(tex0), tex0.cpsm) {
+			(true, _) => 0,
+			(false, 0) => (tex0.csa as usize & 0xf) * 16,
+			(false, _) => tex0.csa as usize * 16,
+		}
+	}
+
+	/// Load the palette described by `tex0` from local memory.
+	pub fn load(&mut self, memory: &LocalMemory, tex0: &Tex0, texclut: TexClut) {
+		let cpsm = Psm::from_u8(tex0.cpsm).unwrap_or(Psm::Ct32);
+		let full = Self::full(tex0);
+		let count = if full { 256 } else { 16 };
+		let offset = Self::offset(tex0);
+
+		trace!("GS: loading {} {:?} CLUT entries from block 0x{:x}", count, cpsm, tex0.cbp);
+
+		for i in 0..count {
+			let (x, y, width) = if tex0.csm2 {
+				(texclut.cou * 16 + i, texclut.cov, texclut.cbw)
+			} else if full {
+				// CSM1 stores 8-bit palettes with entries 8--15 and 16--23 of each 32 swapped.
+				let position = (i & !0x18) | ((i & 0x08) << 1) | ((i & 0x10) >> 1);
+				(position % 16, position / 16, 1)
+			} else {
+				(i % 8, i / 8, 1)
+			};
+
+			let psm = if tex0.csm2 { Psm::Ct16 } else { cpsm };
+			let colour = memory.read_pixel(psm, tex0.cbp, width, x, y);
+			let entry = offset + i as usize;
+
+			if psm == Psm::Ct32 {
+				self.halves[entry % 256] = colour as u16;
+				self.halves[entry % 256 + 256] = (colour >> 16) as u16;
+			} else {
+				self.halves[entry % 512] = colour as u16;
+			}
+		}
+	}
+
+	/// Colour of palette entry `index`, as RGBA8888.
+	pub fn lookup(&self, tex0: &Tex0, index: u32, texa: Texa) -> u32 {
+		let entry = Self::offset(tex0) + index as usize;
+
+		if tex0.cpsm == 0 && !tex0.csm2 {
+			u32::from(self.halves[entry % 256]) | (u32::from(self.halves[entry % 256 + 256]) << 16)
+		} else {
+			expand_16(u32::from(self.halves[entry % 512]), texa)
+		}
+	}
+}
+
+/// Fetch texel (`u`, `v`) of the texture described by `tex0`, wrapped by `clamp`.
+pub fn sample(memory: &LocalMemory, clut: &Clut, tex0: &Tex0, clamp: &Clamp, texa: Texa, u: i32, v: i32) -> u32 {
+	let psm = Psm::from_u8(tex0.psm).unwrap_or(Psm::Ct32);
+	let u = wrap(u, clamp.wms, 1 << tex0.tw.min(10), clamp.minu, clamp.maxu);
+	let v = wrap(v, clamp.wmt, 1 << tex0.th.min(10), clamp.minv, clamp.maxv);
+	let raw = memory.read_pixel(psm, tex0.tbp0, tex0.tbw, u, v);
+
+	if psm.is_indexed() {
+		clut.lookup(tex0, raw, texa)
+	} else {
+		to_rgba(psm, raw, texa)
+	}
+}
+
+/// Combine `texel` with the fragment's colour according to `TEX0.TFX`.
+pub fn apply_function(tex0: &Tex0, texel: [u8; 4], fragment: [u8; 4]) -> [u8; 4] {
+	let modulate = |t: u8, f: u8| ((u32::from(t) * u32::from(f)) >> 7).min(0xff) as u8;
+	let mut out = [0u8; 4];
+
+	for i in 0..3 {
+		out[i] = match tex0.tfx {
+			TFX_MODULATE => modulate(texel[i], fragment[i]),
+			TFX_DECAL => texel[i],
+			_ => modulate(texel[i], fragment[i]).saturating_add(fragment[3]),
+		};
+	}
+
+	out[3] = match (tex0.has_alpha, tex0.tfx) {
+		(false, _) => fragment[3],
+		(true, TFX_MODULATE) => modulate(texel[3], fragment[3]),
+		(true, TFX_HIGHLIGHT) => texel[3].saturating_add(fragment[3]),
+		(true, _) => texel[3],
+	};
+
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sixteen_bit_round_trip() {
+		let texa = Texa { ta0: 0x10, aem: true, ta1: 0x70 };
+
+		assert_eq!(expand_16(0x801f, texa), 0x7000_00f8);
+		assert_eq!(expand_16(0x7c00, texa), 0x10f8_0000);
+		assert_eq!(expand_16(0, texa), 0);
+		assert_eq!(pack_16(0x80f8_f8f8), 0xffff);
+		assert_eq!(pack_16(expand_16(0x1234, texa)), 0x1234);
+	}
+
+	#[test]
+	fn wrap_modes() {
+		assert_eq!(wrap(17, WRAP_REPEAT, 16, 0, 0), 1);
+		assert_eq!(wrap(-1, WRAP_REPEAT, 16, 0, 0), 15);
+		assert_eq!(wrap(-5, WRAP_CLAMP, 16, 0, 0), 0);
+		assert_eq!(wrap(40, WRAP_CLAMP, 16, 0, 0), 15);
+		assert_eq!(wrap(1, WRAP_REGION_CLAMP, 16, 4, 8), 4);
+		assert_eq!(wrap(0b1_0111, WRAP_REGION_REPEAT, 16, 0b0011, 0b1000), 0b1011);
+	}
+
+	#[test]
+	fn csm1_clut_swaps_entries() {
+		let mut memory = LocalMemory::default();
+		let mut clut = Clut::default();
+		let tex0 = Tex0 { psm: Psm::T8 as u8, cbp: 64, ..Default::default() };
+
+		// Entry 8 is stored where entry 16 would be, and vice versa.
+		memory.write_pixel(Psm::Ct32, 64, 1, 0, 1, 0x1111_1111);
+		memory.write_pixel(Psm::Ct32, 64, 1, 8, 0, 0x2222_2222);
+		clut.load(&memory, &tex0, TexClut::default());
+
+		assert_eq!(clut.lookup(&tex0, 8, Texa::default()), 0x1111_1111);
+		assert_eq!(clut.lookup(&tex0, 16, Texa::default()), 0x2222_2222);
+	}
+
+	#[test]
+	fn csa_offsets_4_bit_palettes() {
+		let mut memory = LocalMemory::default();
+		let mut clut = Clut::default();
+		let tex0 = Tex0 { psm: Psm::T4 as u8, cpsm: Psm::Ct16 as u8, csa: 20, ..Default::default() };
+
+		memory.write_pixel(Psm::Ct16, 0, 1, 3, 1, 0x801f);
+		clut.load(&memory, &tex0, TexClut::default());
+
+		assert_eq!(clut.lookup(&tex0, 11, FRAME_TEXA), 0x8000_00f8);
+		assert_eq!(clut.halves[20 * 16 + 11], 0x801f);
+	}
+
+	#[test]
+	fn texture_functions() {
+		let texel = [0x80, 0x40, 0xff, 0x40];
+		let fragment = [0x80, 0x80, 0x80, 0x20];
+		let mut tex0 = Tex0 { has_alpha: true, ..Default::default() };
+
+		assert_eq!(apply_function(&tex0, texel, fragment), [0x80, 0x40, 0xff, 0x10]);
+
+		tex0.tfx = TFX_HIGHLIGHT;
+		assert_eq!(apply_function(&tex0, texel, fragment), [0xa0, 0x60, 0xff, 0x60]);
+
+		tex0.tfx = TFX_DECAL;
+		tex0.has_alpha = false;
+		assert_eq!(apply_function(&tex0, texel, fragment), [0x80, 0x40, 0xff, 0x20]);
+	}
+}
@@ -0,0 +1,258 @@
+//! Transfers between the host and local memory, and within local memory.
+//!
+//! A transfer is set up in `BITBLTBUF`, `TRXPOS` and `TRXREG`, and started by
+//! writing `TRXDIR`. Host data is a stream of 64-bit `HWREG` words with pixels
+//! packed tightly, lowest bits first, row by row from the rectangle's top left
+//! corner. Pixels are swizzled into, or out of, local memory as they pass.
+
+use super::{
+	memory::{
+		LocalMemory,
+		Psm,
+	},
+	registers::{
+		BitBltBuf,
+		TrxPos,
+		TrxReg,
+	},
+};
+use enum_primitive::*;
+
+/// Coordinates wrap at the edges of the 2048×2048 transmission area.
+const COORDINATE_MASK: u32 = 0x7ff;
+
+/// A rectangle of a buffer in local memory, walked a pixel at a time.
+#[derive(Clone, Debug)]
+pub struct Transfer {
+	psm: Psm,
+	base: u32,
+	width: u32,
+
+	left: u32,
+	top: u32,
+	columns: u32,
+	rows: u32,
+
+	/// Next pixel, relative to the top left corner.
+	x: u32,
+	y: u32,
+
+	/// Host data not yet written to, or not yet read from, local memory.
+	pending: u128,
+	pending_bits: u32,
+}
+
+impl Transfer {
+	fn new(psm: u8, base: u32, width: u32, left: u32, top: u32, trxreg: &TrxReg) -> Self {
+		Self {
+			psm: Psm::from_u8(psm).unwrap_or(Psm::Ct32),
+			base,
+			width,
+			left,
+			top,
+			columns: trxreg.rrw,
+			rows: if trxreg.rrw == 0 { 0 } else { trxreg.rrh },
+			x: 0,
+			y: 0,
+			pending: 0,
+			pending_bits: 0,
+		}
+	}
+
+	/// A transfer into the destination rectangle.
+	pub fn to_local(bitbltbuf: &BitBltBuf, trxpos: &TrxPos, trxreg: &TrxReg) -> Self {
+		Self::new(bitbltbuf.dpsm, bitbltbuf.dbp, bitbltbuf.dbw, trxpos.dsax, trxpos.dsay, trxreg)
+	}
+
+	/// A transfer out of the source rectangle.
+	pub fn from_local(bitbltbuf: &BitBltBuf, trxpos: &TrxPos, trxreg: &TrxReg) -> Self {
+		Self::new(bitbltbuf.spsm, bitbltbuf.sbp, bitbltbuf.sbw, trxpos.ssax, trxpos.ssay, trxreg)
+	}
+
+	/// Whether every pixel has been visited.
+	fn walked(&self) -> bool {
+		self.y >= self.rows
+	}
+
+	/// Whether every pixel has been written, or read and handed to the host.
+	pub fn done(&self) -> bool {
+		self.walked() && self.pending_bits == 0
+	}
+
+	/// Position of the next pixel in the buffer, moving on to the one after.
+	fn advance(&mut self) -> (u32, u32) {
+		let position = ((self.left + self.x) & COORDINATE_MASK, (self.top + self.y) & COORDINATE_MASK);
+
+		self.x += 1;
+		if self.x >= self.columns {
+			self.x = 0;
+			self.y += 1;
+		}
+
+		position
+	}
+
+	/// Write a word of host data into local memory.
+	///
+	/// Bits left over once the rectangle is full are discarded.
+	pub fn write(&mut self, memory: &mut LocalMemory, data: u64) {
+		let bits = self.psm.transfer_bits();
+
+		self.pending |= u128::from(data) << self.pending_bits;
+		self.pending_bits += 64;
+
+		while self.pending_bits >= bits && !self.walked() {
+			let (x, y) = self.advance();
+			let pixel = (self.pending & ((1 << bits) - 1)) as u32;
+			memory.write_pixel(self.psm, self.base, self.width, x, y, pixel);
+
+			self.pending >>= bits;
+			self.pending_bits -= bits;
+		}
+
+		if self.walked() {
+			self.pending = 0;
+			self.pending_bits = 0;
+		}
+	}
+
+	/// Read the next word of host data out of local memory, or `None` once all has been read.
+	///
+	/// The last word is padded with zeroes.
+	pub fn read(&mut self, memory: &LocalMemory) -> Option<u64> {
+		let bits = self.psm.transfer_bits();
+
+		while self.pending_bits < 64 && !self.walked() {
+			let (x, y) = self.advance();
+			let pixel = memory.read_pixel(self.psm, self.base, self.width, x, y);
+
+			self.pending |= u128::from(pixel) << self.pending_bits;
+			self.pending_bits += bits;
+		}
+
+		if self.pending_bits == 0 {
+			return None;
+		}
+
+		let word = self.pending as u64;
+		self.pending >>= 64;
+		self.pending_bits = self.pending_bits.saturating_sub(64);
+
+		Some(word)
+	}
+}
+
+/// Copy the source rectangle to the destination rectangle, as a local-to-local transfer.
+///
+/// The whole source is read before anything is written, so overlapping
+/// rectangles copy as if `TRXPOS.DIR` had been chosen to suit them.
+pub fn copy(memory: &mut LocalMemory, bitbltbuf: &BitBltBuf, trxpos: &TrxPos, trxreg: &TrxReg) {
+	let mut source = Transfer::from_local(bitbltbuf, trxpos, trxreg);
+	let mut destination = Transfer::to_local(bitbltbuf, trxpos, trxreg);
+
+	let mut pixels = Vec::with_capacity((trxreg.rrw * trxreg.rrh) as usize);
+	while !source.walked() {
+		let (x, y) = source.advance();
+		pixels.push(memory.read_pixel(source.psm, source.base, source.width, x, y));
+	}
+
+	for pixel in pixels {
+		let (x, y) = destination.advance();
+		memory.write_pixel(destination.psm, destination.base, destination.width, x, y, pixel);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const ALL_PSMS: [Psm; 13] = [
+		Psm::Ct32, Psm::Ct24, Psm::Ct16, Psm::Ct16S, Psm::T8, Psm::T4, Psm::T8H,
+		Psm::T4HL, Psm::T4HH, Psm::Z32, Psm::Z24, Psm::Z16, Psm::Z16S,
+	];
+
+	/// Words of arbitrary (xorshift) data.
+	fn pattern(count: usize) -> Vec<u64> {
+		let mut state = 0x2545_f491_4f6c_dd1du64;
+
+		(0..count).map(|_| {
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			state
+		}).collect()
+	}
+
+	#[test]
+	fn every_psm_round_trips() {
+		// An odd width leaves the last word part padding for most formats.
+		let bitbltbuf = BitBltBuf { sbp: 64, sbw: 2, dbp: 64, dbw: 2, ..Default::default() };
+		let trxpos = TrxPos { ssax: 5, ssay: 3, dsax: 5, dsay: 3, dir: 0 };
+		let trxreg = TrxReg { rrw: 23, rrh: 10 };
+
+		for &psm in &ALL_PSMS {
+			let bitbltbuf = BitBltBuf { spsm: psm as u8, dpsm: psm as u8, ..bitbltbuf };
+			let bits = psm.transfer_bits();
+			let total_bits = trxreg.rrw * trxreg.rrh * bits;
+			let mut data = pattern(((total_bits + 63) / 64) as usize);
+			if total_bits % 64 != 0 {
+				*data.last_mut().unwrap() &= (1 << (total_bits % 64)) - 1;
+			}
+
+			let mut memory = LocalMemory::default();
+			let mut upload = Transfer::to_local(&bitbltbuf, &trxpos, &trxreg);
+			for &word in &data {
+				assert!(!upload.done(), "{:?} finished early", psm);
+				upload.write(&mut memory, word);
+			}
+			assert!(upload.done(), "{:?} did not finish", psm);
+
+			// Pixels land where the stream puts them.
+			let stream = |index: u32| {
+				let bit = index * bits;
+				let pair = u128::from(data[(bit / 64) as usize])
+					| (u128::from(data.get((bit / 64 + 1) as usize).copied().unwrap_or(0)) << 64);
+				((pair >> (bit % 64)) & ((1 << bits) - 1)) as u32
+			};
+			for &(x, y) in &[(0, 0), (22, 0), (7, 4), (22, 9)] {
+				assert_eq!(memory.read_pixel(psm, 64, 2, 5 + x, 3 + y), stream(y * trxreg.rrw + x), "{:?} ({}, {})", psm, x, y);
+			}
+
+			let mut download = Transfer::from_local(&bitbltbuf, &trxpos, &trxreg);
+			let read = std::iter::from_fn(|| download.read(&memory)).collect::<Vec<_>>();
+			assert_eq!(read, data, "{:?} read back differently", psm);
+		}
+	}
+
+	#[test]
+	fn coordinates_wrap() {
+		let bitbltbuf = BitBltBuf { dbw: 32, ..Default::default() };
+		let trxpos = TrxPos { dsax: 2047, dsay: 2047, ..Default::default() };
+		let trxreg = TrxReg { rrw: 2, rrh: 2 };
+
+		let mut memory = LocalMemory::default();
+		let mut upload = Transfer::to_local(&bitbltbuf, &trxpos, &trxreg);
+		upload.write(&mut memory, (2 << 32) | 1);
+		upload.write(&mut memory, (4 << 32) | 3);
+
+		assert_eq!(memory.read_pixel(Psm::Ct32, 0, 32, 2047, 2047), 1);
+		assert_eq!(memory.read_pixel(Psm::Ct32, 0, 32, 0, 2047), 2);
+		assert_eq!(memory.read_pixel(Psm::Ct32, 0, 32, 0, 0), 4);
+	}
+
+	#[test]
+	fn overlapping_copy() {
+		let mut memory = LocalMemory::default();
+		for x in 0..8 {
+			memory.write_pixel(Psm::Ct16, 0, 1, x, 0, x + 1);
+		}
+
+		// Shift the row right by two pixels, over itself.
+		let bitbltbuf = BitBltBuf { sbw: 1, spsm: Psm::Ct16 as u8, dbw: 1, dpsm: Psm::Ct16 as u8, ..Default::default() };
+		let trxpos = TrxPos { dsax: 2, ..Default::default() };
+		copy(&mut memory, &bitbltbuf, &trxpos, &TrxReg { rrw: 8, rrh: 1 });
+
+		let row = (0..10).map(|x| memory.read_pixel(Psm::Ct16, 0, 1, x, 0)).collect::<Vec<_>>();
+		assert_eq!(row, vec![1, 2, 1, 2, 3, 4, 5, 6, 7, 8]);
+	}
+}
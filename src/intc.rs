@@ -0,0 +1,155 @@
+//! The EE's interrupt controller (INTC).
+//!
+//! Collects interrupt requests from the other EE-side devices into `I_STAT`,
+//! and drives the EE Core's `INT0` line whenever any request is unmasked
+//! by `I_MASK`.
+//!
+//! Register behaviour is as described at https://psi-rockin.github.io/ps2tek/:
+//! * Writing `1` to a bit of `I_STAT` acknowledges (clears) it.
+//! * Writing `1` to a bit of `I_MASK` toggles it.
+
+use crate::memory::bus::Device;
+use enum_primitive::*;
+
+/// Physical address of the INTC's register block.
+pub const INTC_PHYSICAL: u32 = I_STAT_PHYSICAL;
+
+/// Length of the INTC's register block.
+pub const INTC_SIZE: u32 = 0x20;
+
+/// Physical address of `I_STAT`.
+pub const I_STAT_PHYSICAL: u32 = 0x1000_F000;
+
+/// Physical address of `I_MASK`.
+pub const I_MASK_PHYSICAL: u32 = 0x1000_F010;
+
+/// Bits of `I_STAT`/`I_MASK` which are backed by an interrupt source.
+pub const INTC_REGISTER_MASK: u32 = 0x7fff;
+
+enum_from_primitive!{
+/// Interrupt sources feeding the INTC, numbered by their bit in `I_STAT`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interrupt {
+	Gs = 0,
+	Sbus,
+	VBlankStart,
+	VBlankEnd,
+	Vif0,
+	Vif1,
+	Vu0,
+	Vu1,
+	Ipu,
+	Timer0,
+	Timer1,
+	Timer2,
+	Timer3,
+	Sfifo,
+	Vu0Watchdog,
+}
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Intc {
+	/// `I_STAT`: latched interrupt requests.
+	pub stat: u32,
+
+	/// `I_MASK`: which requests may drive `INT0`.
+	pub mask: u32,
+}
+
+impl Intc {
+	/// Latch a request from `source`, which stays pending until acknowledged.
+	pub fn raise(&mut self, source: Interrupt) {
+		trace!("INTC: {:?} raised", source);
+		self.stat |= 1 << (source as u32);
+	}
+
+	/// Whether the INTC is currently asserting `INT0`.
+	pub fn pending(&self) -> bool {
+		self.stat & self.mask != 0
+	}
+
+	/// Read the register at physical address `p_addr`.
+	pub fn read(&self, p_addr: u32) -> u32 {
+		match p_addr & !0b11 {
+			I_STAT_PHYSICAL => self.stat,
+			I_MASK_PHYSICAL => self.mask,
+			_ => 0,
+		}
+	}
+
+	/// Write `value` to the register at physical address `p_addr`.
+	pub fn write(&mut self, p_addr: u32, value: u32) {
+		let value = value & INTC_REGISTER_MASK;
+
+		match p_addr & !0b11 {
+			I_STAT_PHYSICAL => self.stat &= !value,
+			I_MASK_PHYSICAL => self.mask ^= value,
+			_ => {},
+		}
+	}
+}
+
+impl Device for Intc {
+	fn read_u32(&mut self, p_addr: u32) -> u32 {
+		self.read(p_addr)
+	}
+
+	fn write_u32(&mut self, p_addr: u32, value: u32) {
+		self.write(p_addr, value);
+	}
+
+	// Both registers act on the bits written as `1`, so narrow writes
+	// must not write back the bits around them.
+	fn write_u8(&mut self, p_addr: u32, value: u8) {
+		self.write(p_addr, u32::from(value) << ((p_addr & 0b11) * 8));
+	}
+
+	fn write_u16(&mut self, p_addr: u32, value: u16) {
+		self.write(p_addr, u32::from(value) << ((p_addr & 0b10) * 8));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn stat_write_acknowledges() {
+		let mut intc = Intc::default();
+
+		intc.raise(Interrupt::VBlankStart);
+		intc.raise(Interrupt::Timer1);
+		assert_eq!(intc.read(I_STAT_PHYSICAL), (1 << 2) | (1 << 10));
+
+		intc.write(I_STAT_PHYSICAL, 1 << 2);
+		assert_eq!(intc.read(I_STAT_PHYSICAL), 1 << 10);
+	}
+
+	#[test]
+	fn mask_write_toggles() {
+		let mut intc = Intc::default();
+
+		intc.write(I_MASK_PHYSICAL, 0b1010);
+		intc.write(I_MASK_PHYSICAL, 0b0011);
+		assert_eq!(intc.read(I_MASK_PHYSICAL), 0b1001);
+
+		// Unbacked bits are ignored.
+		intc.write(I_MASK_PHYSICAL, 0xffff_0000);
+		assert_eq!(intc.read(I_MASK_PHYSICAL), 0b1001);
+	}
+
+	#[test]
+	fn pending_needs_unmasked_request() {
+		let mut intc = Intc::default();
+
+		intc.raise(Interrupt::Gs);
+		assert!(!intc.pending());
+
+		intc.write(I_MASK_PHYSICAL, 1 << (Interrupt::Vif0 as u32));
+		assert!(!intc.pending());
+
+		intc.write(I_MASK_PHYSICAL, 1 << (Interrupt::Gs as u32));
+		assert!(intc.pending());
+	}
+}
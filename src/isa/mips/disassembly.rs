@@ -0,0 +1,305 @@
+//! Rendering of EE Core instructions as assembly, for trace logs and
+//! diagnostics.
+//!
+//! Which instruction a word holds is decided by the `mips_ops!` table in
+//! `core::ops`, which generates `disassemble` and calls [`render`] with the
+//! name of each instruction it decodes: this module only knows how each
+//! instruction writes its operands.
+//!
+//! ```text
+//! addiu $sp, $sp, -16
+//! sq $ra, 0($sp)
+//! mfc0 $k0, Status
+//! vmadd.xyz vf1, vf2, vf3
+//! ```
+//!
+//! Mnemonics are the instruction names in lowercase, with `_` written as
+//! `.` (`ADD_S` is `add.s`). Immediates of arithmetic and memory operations
+//! are sign-extended, and those of logical operations are in hex. Branch and
+//! jump targets are absolute addresses when the instruction's address is
+//! known, and otherwise relative to the branch (`.+16`). VU0 registers are
+//! written as `dvp-as` writes them (`vf1`, `vi1`), whichever instruction
+//! names them.
+
+use crate::core::cop0::Register;
+use crate::core::cop2::{ControlRegister, VI_COUNT};
+use crate::isa::vu::{self, Macro};
+use enum_primitive::*;
+use super::{Instruction, ee::INTERLOCK, mnemonic_guess};
+
+/// Conventional names of the general purpose registers.
+const REGISTER_NAMES: [&str; 32] = [
+	"zero", "at", "v0", "v1", "a0", "a1", "a2", "a3",
+	"t0", "t1", "t2", "t3", "t4", "t5", "t6", "t7",
+	"s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7",
+	"t8", "t9", "k0", "k1", "gp", "sp", "fp", "ra",
+];
+
+/// How an operand is written, and the bits it is held in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand {
+	/// General purpose registers.
+	Rd,
+	Rs,
+	Rt,
+	/// Shift amount.
+	Sa,
+	/// Sign-extended 16-bit immediate.
+	Immediate,
+	/// Zero-extended 16-bit immediate.
+	UnsignedImmediate,
+	/// `offset(base)`, the address of a load or store.
+	Offset,
+	/// Branch target, relative to the delay slot.
+	Target,
+	/// Jump target, within the delay slot's 256MB region.
+	JumpTarget,
+	/// `rt`, as a number, for `PREF`'s hint.
+	Hint,
+	/// `SYSCALL` and `BREAK` code, left out if zero.
+	Code,
+	/// FPU registers.
+	Fd,
+	Fs,
+	Ft,
+	/// FPU control register, in `fs`.
+	Fcr,
+	/// COP0 register, in `rd`.
+	Cop0,
+	/// VU0 `VF` register, in `rd` or `rt`.
+	VfD,
+	VfT,
+	/// VU0 `VI` or control register, in `rd`.
+	Cop2Control,
+	/// `VCALLMS`'s microprogram address in bytes.
+	Microprogram,
+	/// `CMSAR0`, which `VCALLMSR` reads.
+	Cmsar0,
+}
+
+use Operand::*;
+
+/// Operands of each instruction, by its name in `mips_ops!`.
+fn syntax(family: &str, name: &str) -> Option<&'static [Operand]> {
+	Some(match name {
+		"ADD" | "ADDU" | "AND" | "DADD" | "DADDU" | "DSUB" | "DSUBU" | "NOR" | "OR" | "SLT" | "SLTU" | "SUB"
+			| "SUBU" | "XOR" | "MOVN" | "MOVZ" => &[Rd, Rs, Rt],
+		"MULT" | "MULTU" | "MULT1" | "MULTU1" | "MADD" | "MADDU" | "MADD1" | "MADDU1" => &[Rd, Rs, Rt],
+		"DIV" | "DIVU" | "DIV1" | "DIVU1" => &[Rs, Rt],
+		"SLL" | "SRL" | "SRA" | "DSLL" | "DSRL" | "DSRA" | "DSLL32" | "DSRL32" | "DSRA32" => &[Rd, Rt, Sa],
+		"SLLV" | "SRLV" | "SRAV" | "DSLLV" | "DSRLV" | "DSRAV" => &[Rd, Rt, Rs],
+		"JR" => &[Rs],
+		"JALR" => &[Rd, Rs],
+		"MFHI" | "MFLO" | "MFSA" | "MFHI1" | "MFLO1" => &[Rd],
+		"MTHI" | "MTLO" | "MTSA" | "MTHI1" | "MTLO1" => &[Rs],
+		"SYSCALL" | "BREAK" => &[Code],
+		"SYNC" => &[],
+		"TEQ" | "TGE" | "TGEU" | "TLT" | "TLTU" | "TNE" => &[Rs, Rt],
+
+		"BGEZ" | "BGEZAL" | "BGEZALL" | "BGEZL" | "BLTZ" | "BLTZAL" | "BLTZALL" | "BLTZL" => &[Rs, Target],
+		"MTSAB" | "MTSAH" | "TEQI" | "TGEI" | "TGEIU" | "TLTI" | "TLTIU" | "TNEI" => &[Rs, Immediate],
+
+		"BC0F" | "BC0FL" | "BC0T" | "BC0TL" | "BC1F" | "BC1FL" | "BC1T" | "BC1TL" | "BC2F" | "BC2FL" | "BC2T"
+			| "BC2TL" => &[Target],
+		"DI" | "EI" | "ERET" | "TLBP" | "TLBR" | "TLBWI" | "TLBWR" => &[],
+		"MFBPC" | "MTBPC" => &[Rt],
+		"MFC0" | "MTC0" => &[Rt, Cop0],
+
+		"ABS_S" | "MOV_S" | "NEG_S" | "CVT_S_W" | "CVT_W_S" => &[Fd, Fs],
+		"ADD_S" | "SUB_S" | "MUL_S" | "DIV_S" | "MAX_S" | "MIN_S" | "MADD_S" | "MSUB_S" | "RSQRT_S" => &[Fd, Fs, Ft],
+		"ADDA_S" | "SUBA_S" | "MULA_S" | "MADDA_S" | "MSUBA_S" | "C_EQ_S" | "C_F_S" | "C_LE_S" | "C_LT_S"
+			=> &[Fs, Ft],
+		"SQRT_S" => &[Fd, Ft],
+		"CFC1" | "CTC1" => &[Rt, Fcr],
+		"MFC1" | "MTC1" => &[Rt, Fs],
+
+		"CFC2" | "CTC2" => &[Rt, Cop2Control],
+		"QMFC2" | "QMTC2" => &[Rt, VfD],
+		"VCALLMS" => &[Microprogram],
+		"VCALLMSR" => &[Cmsar0],
+
+		"PABSH" | "PABSW" | "PCPYH" | "PEXCH" | "PEXCW" | "PEXEH" | "PEXEW" | "PEXT5" | "PPAC5" | "PREVH"
+			| "PROT3W" => &[Rd, Rt],
+		"PLZCW" => &[Rd, Rs],
+		"PSLLH" | "PSLLW" | "PSRAH" | "PSRAW" | "PSRLH" | "PSRLW" => &[Rd, Rt, Sa],
+		"PSLLVW" | "PSRAVW" | "PSRLVW" => &[Rd, Rt, Rs],
+		"PDIVBW" | "PDIVUW" | "PDIVW" => &[Rs, Rt],
+		"PMFHI" | "PMFLO" | "PMFHL_LH" | "PMFHL_LW" | "PMFHL_SH" | "PMFHL_SLW" | "PMFHL_UW" => &[Rd],
+		"PMTHI" | "PMTLO" | "PMTHL_LW" => &[Rs],
+		// Every other MMI instruction works on `rs` and `rt` into `rd`.
+		_ if family == "MMI" => &[Rd, Rs, Rt],
+		// The cache operation is written as the mnemonic's first operand.
+		_ if family == "CACHE" => &[Offset],
+
+		"ADDI" | "ADDIU" | "DADDI" | "DADDIU" | "SLTI" | "SLTIU" => &[Rt, Rs, Immediate],
+		"ANDI" | "ORI" | "XORI" => &[Rt, Rs, UnsignedImmediate],
+		"LUI" => &[Rt, UnsignedImmediate],
+		"BEQ" | "BEQL" | "BNE" | "BNEL" => &[Rs, Rt, Target],
+		"BGTZ" | "BGTZL" | "BLEZ" | "BLEZL" => &[Rs, Target],
+		"J" | "JAL" => &[JumpTarget],
+		"LB" | "LBU" | "LD" | "LDL" | "LDR" | "LH" | "LHU" | "LQ" | "LW" | "LWL" | "LWR" | "LWU" | "SB" | "SD"
+			| "SDL" | "SDR" | "SH" | "SQ" | "SW" | "SWL" | "SWR" => &[Rt, Offset],
+		"LQC2" | "SQC2" => &[VfT, Offset],
+		"LWC1" | "SWC1" => &[Ft, Offset],
+		"PREF" => &[Hint, Offset],
+
+		_ => return None,
+	})
+}
+
+fn register(index: u8) -> String {
+	format!("${}", REGISTER_NAMES[index as usize])
+}
+
+fn format_operand(operand: Operand, instruction: u32, pc: Option<u32>) -> Option<String> {
+	let i = instruction;
+
+	Some(match operand {
+		Rd => register(i.r_get_destination()),
+		Rs => register(i.ri_get_source()),
+		Rt => register(i.ri_get_target()),
+		Sa => i.r_get_shift_amount().to_string(),
+		Immediate => i.i_get_immediate_signed().to_string(),
+		UnsignedImmediate => format!("0x{:x}", i.i_get_immediate()),
+		Offset => format!("{}({})", i.i_get_immediate_signed(), register(i.ri_get_source())),
+		Target => {
+			let offset = 4 + i32::from(i.i_get_immediate_signed()) * 4;
+			match pc {
+				Some(pc) => format!("0x{:08x}", pc.wrapping_add(offset as u32)),
+				None => format!(".{:+}", offset),
+			}
+		},
+		JumpTarget => {
+			let region = pc.map_or(0, |pc| pc.wrapping_add(4) & 0xf000_0000);
+			format!("0x{:08x}", region | (i.j_get_jump() << 2))
+		},
+		Hint => i.ri_get_target().to_string(),
+		Code => match (i >> 6) & 0xf_ffff {
+			0 => return None,
+			code => format!("0x{:x}", code),
+		},
+		Fd => format!("$f{}", i.r_get_shift_amount()),
+		Fs => format!("$f{}", i.r_get_destination()),
+		Ft => format!("$f{}", i.ri_get_target()),
+		Fcr => format!("$fcr{}", i.r_get_destination()),
+		Cop0 => match Register::from_u8(i.r_get_destination()) {
+			Some(register) => format!("{:?}", register),
+			None => format!("${}", i.r_get_destination()),
+		},
+		VfD => format!("vf{}", i.r_get_destination()),
+		VfT => format!("vf{}", i.ri_get_target()),
+		Cop2Control => match i.r_get_destination() {
+			index if index < VI_COUNT => format!("vi{}", index),
+			index => match ControlRegister::from_u8(index) {
+				Some(register) => format!("{:?}", register),
+				None => format!("vi{}", index),
+			},
+		},
+		Microprogram => format!("0x{:x}", ((i >> 6) & 0x7fff) * 8),
+		Cmsar0 => format!("{:?}", ControlRegister::Cmsar0),
+	})
+}
+
+/// Render `instruction`, decoded by `mips_ops!` as `name` from `family`.
+pub fn render(family: &str, name: &str, instruction: u32, pc: Option<u32>) -> String {
+	match name {
+		// Macro instructions are written as micro mode writes them.
+		"VUPPER" | "VLOWER" => return match Macro::decode(instruction) {
+			Some(Macro::Upper(operation)) => format!("v{}", vu::asm::disassemble_upper(operation)),
+			Some(Macro::Lower(operation)) => format!("v{}", vu::asm::disassemble_lower(operation, 0)),
+			None => undecodable(instruction),
+		},
+		_ => (),
+	}
+
+	let mut mnemonic = name.to_lowercase().replace('_', ".");
+	let operands = syntax(family, name).unwrap_or(&[]).iter()
+		.filter_map(|&operand| format_operand(operand, instruction, pc))
+		.collect::<Vec<_>>();
+
+	if family == "CACHE" {
+		return format!("cache {}, {}", mnemonic, operands.join(", "));
+	}
+
+	if matches!(name, "CFC2" | "CTC2" | "QMFC2" | "QMTC2") && instruction & INTERLOCK != 0 {
+		mnemonic += ".i";
+	}
+
+	if operands.is_empty() {
+		mnemonic
+	} else {
+		format!("{} {}", mnemonic, operands.join(", "))
+	}
+}
+
+/// Render a word which `mips_ops!` does not decode.
+pub fn undecodable(instruction: u32) -> String {
+	format!(".word 0x{:08x} ; {}", instruction, mnemonic_guess(instruction))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::core::ops::{disassemble, INSTRUCTION_NAMES};
+	use crate::isa::mips::{self, ee::*, Opcode};
+	use crate::isa::vu::{self, UpperFunction};
+	use super::*;
+
+	#[test]
+	fn every_instruction_has_a_syntax() {
+		for &(family, name) in INSTRUCTION_NAMES {
+			// Macro instructions are written as micro ones.
+			if matches!(name, "VUPPER" | "VLOWER") {
+				continue;
+			}
+
+			assert!(syntax(family, name).is_some(), "{} has no syntax", name);
+		}
+	}
+
+	#[test]
+	fn registers_immediates_and_targets() {
+		let addiu = mips::build_op_immediate(Opcode::AddIU, 29, 29, (-16i16) as u16);
+		assert_eq!(disassemble(addiu, None), "addiu $sp, $sp, -16");
+
+		let ori = mips::build_op_immediate(Opcode::OrI, 4, 2, 0xbeef);
+		assert_eq!(disassemble(ori, None), "ori $v0, $a0, 0xbeef");
+
+		let sq = mips::build_op_immediate(Opcode::SQ, 29, 31, 0x20);
+		assert_eq!(disassemble(sq, None), "sq $ra, 32($sp)");
+
+		let bne = mips::build_op_immediate(Opcode::BNE, 4, 0, (-2i16) as u16);
+		assert_eq!(disassemble(bne, Some(0x8000_1000)), "bne $a0, $zero, 0x80000ffc");
+		assert_eq!(disassemble(bne, None), "bne $a0, $zero, .-4");
+
+		let jal = mips::build_op_jump(Opcode::JaL, 0x10_0040);
+		assert_eq!(disassemble(jal, Some(0x8000_1000)), "jal 0x80400100");
+
+		let sll = mips::build_op_register(mips::Function::SLL, 0, 9, 8, 2);
+		assert_eq!(disassemble(sll, None), "sll $t0, $t1, 2");
+		assert_eq!(disassemble(0, None), "nop");
+	}
+
+	#[test]
+	fn coprocessor_registers() {
+		let mfc0 = mips::build_op_register_custom(Opcode::Cop0, 0, MF0, 26, Register::Status as u8, 0);
+		assert_eq!(disassemble(mfc0, None), "mfc0 $k0, Status");
+
+		let cfc2 = mips::build_op_register_custom(Opcode::Cop2, INTERLOCK as u8, CF2, 8, ControlRegister::Tpc as u8, 0);
+		assert_eq!(disassemble(cfc2, None), "cfc2.i $t0, Tpc");
+
+		let qmfc2 = mips::build_op_register_custom(Opcode::Cop2, 0, QMF2, 8, 1, 0);
+		assert_eq!(disassemble(qmfc2, None), "qmfc2 $t0, vf1");
+
+		let lqc2 = mips::build_op_immediate(Opcode::LQC2, 29, 2, 0x10);
+		assert_eq!(disassemble(lqc2, None), "lqc2 vf2, 16($sp)");
+
+		let add_s = mips::build_op_register_custom(Opcode::Cop1, 0, FMT_S, 3, 2, 1);
+		assert_eq!(disassemble(add_s, None), "add.s $f1, $f2, $f3");
+
+		let vadd = vu::build_macro(vu::build_upper(UpperFunction::Add as u8, 0b1110, 3, 2, 1));
+		assert_eq!(disassemble(vadd, None), "vadd.xyz vf1, vf2, vf3");
+
+		let cache = mips::build_op_immediate(Opcode::Cache, 4, CacheFunction::IXLTG as u8, 0x40);
+		assert_eq!(disassemble(cache, None), "cache ixltg, 64($a0)");
+	}
+}
@@ -1,12 +1,20 @@
-use crate::core::cop0::Register;
+use crate::{
+	core::cop0::Register,
+	isa::vu::Macro,
+};
 use enum_primitive::*;
 use super::instruction::Instruction;
 
 enum_from_primitive!{
 #[derive(Debug, PartialEq)]
 pub enum C0Function {
+	TlbR    = 0b00_0001,
 	TlbWI   = 0b00_0010,
 	TlbWR   = 0b00_0110,
+	TlbP    = 0b00_1000,
+	Eret    = 0b01_1000,
+	EI      = 0b11_1000,
+	DI      = 0b11_1001,
 }
 }
 
@@ -18,12 +26,40 @@ impl C0Function {
 	}
 }
 
+enum_from_primitive!{
+/// COP0 branch conditions, keyed by the `rt` field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BC0Function {
+	BC0F  = 0b0_0000,
+	BC0T  = 0b0_0001,
+	BC0FL = 0b0_0010,
+	BC0TL = 0b0_0011,
+}
+}
+
+impl BC0Function {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.ri_get_target();
+		Self::from_u8(raw_func)
+	}
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Cop0Function {
+	BC0F,
+	BC0FL,
+	BC0T,
+	BC0TL,
+	DI,
+	EI,
+	Eret,
 	MFBPC,
 	MFC0,
 	MTBPC,
 	MTC0,
+	TlbP,
+	TlbR,
 	TlbWI,
 	TlbWR,
 }
@@ -57,14 +93,26 @@ impl Cop0Function {
 				trace!("C0");
 				use C0Function::*;
 				match C0Function::decode(instruction) {
+					Some(TlbR) => Some(Cop0Function::TlbR),
 					Some(TlbWI) => Some(Cop0Function::TlbWI),
 					Some(TlbWR) => Some(Cop0Function::TlbWR),
-					_ => None,
+					Some(TlbP) => Some(Cop0Function::TlbP),
+					Some(Eret) => Some(Cop0Function::Eret),
+					Some(EI) => Some(Cop0Function::EI),
+					Some(DI) => Some(Cop0Function::DI),
+					None => None,
 				}
 			},
 			BC0 => {
 				trace!("BC0");
-				None
+				use BC0Function::*;
+				match BC0Function::decode(instruction) {
+					Some(BC0F) => Some(Cop0Function::BC0F),
+					Some(BC0T) => Some(Cop0Function::BC0T),
+					Some(BC0FL) => Some(Cop0Function::BC0FL),
+					Some(BC0TL) => Some(Cop0Function::BC0TL),
+					None => None,
+				}
 			},
 			MT0 => {
 				trace!("MT0");
@@ -78,21 +126,747 @@ impl Cop0Function {
 				}
 			},
 			_ => {
-				unreachable!();
-			}
+				trace!("Unknown COP0 family {:05b}", family);
+				None
+			},
 		}
 
 	}
 }
 
+enum_from_primitive!{
+/// Single-precision (`fmt = S`) COP1 operations, keyed by function field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum C1SFunction {
+	Add   = 0b00_0000,
+	Sub   = 0b00_0001,
+	Mul   = 0b00_0010,
+	Div   = 0b00_0011,
+	Sqrt  = 0b00_0100,
+	Abs   = 0b00_0101,
+	Mov   = 0b00_0110,
+	Neg   = 0b00_0111,
+	RSqrt = 0b01_0110,
+	AddA  = 0b01_1000,
+	SubA  = 0b01_1001,
+	MulA  = 0b01_1010,
+	MAdd  = 0b01_1100,
+	MSub  = 0b01_1101,
+	MAddA = 0b01_1110,
+	MSubA = 0b01_1111,
+	CvtW  = 0b10_0100,
+	Max   = 0b10_1000,
+	Min   = 0b10_1001,
+	CF    = 0b11_0000,
+	CEq   = 0b11_0010,
+	CLt   = 0b11_0100,
+	CLe   = 0b11_0110,
+}
+}
+
+impl C1SFunction {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.r_get_function();
+		Self::from_u8(raw_func)
+	}
+}
+
+enum_from_primitive!{
+/// COP1 branch conditions, keyed by the `rt` field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BC1Function {
+	BC1F  = 0b0_0000,
+	BC1T  = 0b0_0001,
+	BC1FL = 0b0_0010,
+	BC1TL = 0b0_0011,
+}
+}
+
+impl BC1Function {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.ri_get_target();
+		Self::from_u8(raw_func)
+	}
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Cop1Function {
+	Abs,
+	Add,
+	AddA,
+	BC1F,
+	BC1FL,
+	BC1T,
+	BC1TL,
+	CEq,
+	CF,
+	CFC1,
+	CLe,
+	CLt,
+	CTC1,
+	CvtS,
+	CvtW,
+	Div,
+	MAdd,
+	MAddA,
+	Max,
+	MFC1,
+	Min,
+	Mov,
+	MSub,
+	MSubA,
+	MTC1,
+	Mul,
+	MulA,
+	Neg,
+	RSqrt,
+	Sqrt,
+	Sub,
+	SubA,
 }
 
+pub const MF1:   u8 = 0b0_0000;
+pub const CF1:   u8 = 0b0_0010;
+pub const MT1:   u8 = 0b0_0100;
+pub const CT1:   u8 = 0b0_0110;
+pub const BC1:   u8 = 0b0_1000;
+pub const FMT_S: u8 = 0b1_0000;
+pub const FMT_W: u8 = 0b1_0100;
+
+/// Function code of `CVT.S.W`, the only operation in the `W` format.
+pub const CVT_S: u8 = 0b10_0000;
+
 impl Cop1Function {
 	#[inline(always)]
-	pub fn decode(_instruction: u32) -> Option<Self> {
-		None
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let family = instruction.ri_get_source();
+		match family {
+			MF1 => if instruction & LAST_11 == 0 {
+				Some(Cop1Function::MFC1)
+			} else {
+				None
+			},
+			CF1 => if instruction & LAST_11 == 0 {
+				Some(Cop1Function::CFC1)
+			} else {
+				None
+			},
+			MT1 => if instruction & LAST_11 == 0 {
+				Some(Cop1Function::MTC1)
+			} else {
+				None
+			},
+			CT1 => if instruction & LAST_11 == 0 {
+				Some(Cop1Function::CTC1)
+			} else {
+				None
+			},
+			BC1 => {
+				trace!("BC1");
+				use BC1Function::*;
+				match BC1Function::decode(instruction) {
+					Some(BC1F) => Some(Cop1Function::BC1F),
+					Some(BC1T) => Some(Cop1Function::BC1T),
+					Some(BC1FL) => Some(Cop1Function::BC1FL),
+					Some(BC1TL) => Some(Cop1Function::BC1TL),
+					None => None,
+				}
+			},
+			FMT_S => {
+				trace!("S");
+				use C1SFunction::*;
+				C1SFunction::decode(instruction).map(|f| match f {
+					Add => Cop1Function::Add,
+					Sub => Cop1Function::Sub,
+					Mul => Cop1Function::Mul,
+					Div => Cop1Function::Div,
+					Sqrt => Cop1Function::Sqrt,
+					Abs => Cop1Function::Abs,
+					Mov => Cop1Function::Mov,
+					Neg => Cop1Function::Neg,
+					RSqrt => Cop1Function::RSqrt,
+					AddA => Cop1Function::AddA,
+					SubA => Cop1Function::SubA,
+					MulA => Cop1Function::MulA,
+					MAdd => Cop1Function::MAdd,
+					MSub => Cop1Function::MSub,
+					MAddA => Cop1Function::MAddA,
+					MSubA => Cop1Function::MSubA,
+					CvtW => Cop1Function::CvtW,
+					Max => Cop1Function::Max,
+					Min => Cop1Function::Min,
+					CF => Cop1Function::CF,
+					CEq => Cop1Function::CEq,
+					CLt => Cop1Function::CLt,
+					CLe => Cop1Function::CLe,
+				})
+			},
+			FMT_W => {
+				trace!("W");
+				if instruction.r_get_function() == CVT_S {
+					Some(Cop1Function::CvtS)
+				} else {
+					None
+				}
+			},
+			_ => None,
+		}
+	}
+}
+
+enum_from_primitive!{
+/// COP2 branch conditions, keyed by the `rt` field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BC2Function {
+	BC2F  = 0b0_0000,
+	BC2T  = 0b0_0001,
+	BC2FL = 0b0_0010,
+	BC2TL = 0b0_0011,
+}
+}
+
+impl BC2Function {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.ri_get_target();
+		Self::from_u8(raw_func)
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Cop2Function {
+	BC2F,
+	BC2FL,
+	BC2T,
+	BC2TL,
+	CFC2,
+	CTC2,
+	Lower,
+	QMFC2,
+	QMTC2,
+	Upper,
+	VCallMs,
+	VCallMsR,
+}
+
+pub const QMF2: u8 = 0b0_0001;
+pub const CF2:  u8 = 0b0_0010;
+pub const QMT2: u8 = 0b0_0101;
+pub const CT2:  u8 = 0b0_0110;
+pub const BC2:  u8 = 0b0_1000;
+
+/// Set in the `rs` field of VU0 macro instructions, whose `dest` takes the rest.
+pub const CO2:  u8 = 0b1_0000;
+
+/// Function codes of the macro instructions which are not VU operations.
+pub const VCALLMS:  u8 = 0b11_1000;
+pub const VCALLMSR: u8 = 0b11_1001;
+
+/// Interlock bit of the COP2 moves: the move waits for VU0's microprogram to end.
+pub const INTERLOCK: u32 = 0b1;
+
+impl Cop2Function {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let family = instruction.ri_get_source();
+		match family {
+			QMF2 | CF2 | QMT2 | CT2 => if instruction & LAST_11 & !INTERLOCK == 0 {
+				Some(match family {
+					QMF2 => Cop2Function::QMFC2,
+					CF2 => Cop2Function::CFC2,
+					QMT2 => Cop2Function::QMTC2,
+					_ => Cop2Function::CTC2,
+				})
+			} else {
+				None
+			},
+			BC2 => {
+				trace!("BC2");
+				use BC2Function::*;
+				match BC2Function::decode(instruction) {
+					Some(BC2F) => Some(Cop2Function::BC2F),
+					Some(BC2T) => Some(Cop2Function::BC2T),
+					Some(BC2FL) => Some(Cop2Function::BC2FL),
+					Some(BC2TL) => Some(Cop2Function::BC2TL),
+					None => None,
+				}
+			},
+			_ if family & CO2 != 0 => {
+				trace!("CO2");
+				match instruction.r_get_function() {
+					VCALLMS => Some(Cop2Function::VCallMs),
+					VCALLMSR => Some(Cop2Function::VCallMsR),
+					_ => Macro::decode(instruction).map(|operation| match operation {
+						Macro::Upper(_) => Cop2Function::Upper,
+						Macro::Lower(_) => Cop2Function::Lower,
+					}),
+				}
+			},
+			_ => {
+				trace!("Unknown COP2 family {:05b}", family);
+				None
+			},
+		}
+	}
+}
+
+enum_from_primitive!{
+/// MMI operations keyed directly by the function field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MmiBaseFunction {
+	MADD   = 0b00_0000,
+	MADDU  = 0b00_0001,
+	PLZCW  = 0b00_0100,
+	MFHI1  = 0b01_0000,
+	MTHI1  = 0b01_0001,
+	MFLO1  = 0b01_0010,
+	MTLO1  = 0b01_0011,
+	MULT1  = 0b01_1000,
+	MULTU1 = 0b01_1001,
+	DIV1   = 0b01_1010,
+	DIVU1  = 0b01_1011,
+	MADD1  = 0b10_0000,
+	MADDU1 = 0b10_0001,
+	PMFHL  = 0b11_0000,
+	PMTHL  = 0b11_0001,
+	PSLLH  = 0b11_0100,
+	PSRLH  = 0b11_0110,
+	PSRAH  = 0b11_0111,
+	PSLLW  = 0b11_1100,
+	PSRLW  = 0b11_1110,
+	PSRAW  = 0b11_1111,
+}
+}
+
+impl MmiBaseFunction {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.r_get_function();
+		Self::from_u8(raw_func)
+	}
+}
+
+enum_from_primitive!{
+/// MMI0 operations, keyed by the `sa` field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mmi0Function {
+	PAddW  = 0b0_0000,
+	PSubW  = 0b0_0001,
+	PCGTW  = 0b0_0010,
+	PMaxW  = 0b0_0011,
+	PAddH  = 0b0_0100,
+	PSubH  = 0b0_0101,
+	PCGTH  = 0b0_0110,
+	PMaxH  = 0b0_0111,
+	PAddB  = 0b0_1000,
+	PSubB  = 0b0_1001,
+	PCGTB  = 0b0_1010,
+	PAddSW = 0b1_0000,
+	PSubSW = 0b1_0001,
+	PExtLW = 0b1_0010,
+	PPacW  = 0b1_0011,
+	PAddSH = 0b1_0100,
+	PSubSH = 0b1_0101,
+	PExtLH = 0b1_0110,
+	PPacH  = 0b1_0111,
+	PAddSB = 0b1_1000,
+	PSubSB = 0b1_1001,
+	PExtLB = 0b1_1010,
+	PPacB  = 0b1_1011,
+	PExt5  = 0b1_1110,
+	PPac5  = 0b1_1111,
+}
+}
+
+impl Mmi0Function {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.r_get_shift_amount();
+		Self::from_u8(raw_func)
+	}
+}
+
+enum_from_primitive!{
+/// MMI1 operations, keyed by the `sa` field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mmi1Function {
+	PAbsW  = 0b0_0001,
+	PCEqW  = 0b0_0010,
+	PMinW  = 0b0_0011,
+	PAdSBH = 0b0_0100,
+	PAbsH  = 0b0_0101,
+	PCEqH  = 0b0_0110,
+	PMinH  = 0b0_0111,
+	PCEqB  = 0b0_1010,
+	PAddUW = 0b1_0000,
+	PSubUW = 0b1_0001,
+	PExtUW = 0b1_0010,
+	PAddUH = 0b1_0100,
+	PSubUH = 0b1_0101,
+	PExtUH = 0b1_0110,
+	PAddUB = 0b1_1000,
+	PSubUB = 0b1_1001,
+	PExtUB = 0b1_1010,
+	QFSRV  = 0b1_1011,
+}
+}
+
+impl Mmi1Function {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.r_get_shift_amount();
+		Self::from_u8(raw_func)
+	}
+}
+
+enum_from_primitive!{
+/// MMI2 operations, keyed by the `sa` field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mmi2Function {
+	PMAddW = 0b0_0000,
+	PSLLVW = 0b0_0010,
+	PSRLVW = 0b0_0011,
+	PMSubW = 0b0_0100,
+	PMFHi  = 0b0_1000,
+	PMFLo  = 0b0_1001,
+	PIntH  = 0b0_1010,
+	PMultW = 0b0_1100,
+	PDivW  = 0b0_1101,
+	PCpyLD = 0b0_1110,
+	PMAddH = 0b1_0000,
+	PHMAdH = 0b1_0001,
+	PAnd   = 0b1_0010,
+	PXor   = 0b1_0011,
+	PMSubH = 0b1_0100,
+	PHMSbH = 0b1_0101,
+	PExEH  = 0b1_1010,
+	PRevH  = 0b1_1011,
+	PMultH = 0b1_1100,
+	PDivBW = 0b1_1101,
+	PExEW  = 0b1_1110,
+	PRot3W = 0b1_1111,
+}
+}
+
+impl Mmi2Function {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.r_get_shift_amount();
+		Self::from_u8(raw_func)
+	}
+}
+
+enum_from_primitive!{
+/// MMI3 operations, keyed by the `sa` field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mmi3Function {
+	PMAddUW = 0b0_0000,
+	PSRAVW  = 0b0_0011,
+	PMTHi   = 0b0_1000,
+	PMTLo   = 0b0_1001,
+	PIntEH  = 0b0_1010,
+	PMultUW = 0b0_1100,
+	PDivUW  = 0b0_1101,
+	PCpyUD  = 0b0_1110,
+	POr     = 0b1_0010,
+	PNor    = 0b1_0011,
+	PExcH   = 0b1_1010,
+	PCpyH   = 0b1_1011,
+	PExcW   = 0b1_1110,
+}
+}
+
+impl Mmi3Function {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.r_get_shift_amount();
+		Self::from_u8(raw_func)
+	}
+}
+
+enum_from_primitive!{
+/// `PMFHL` formats, keyed by the `sa` field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PMFHLFunction {
+	LW  = 0b0_0000,
+	UW  = 0b0_0001,
+	SLW = 0b0_0010,
+	LH  = 0b0_0011,
+	SH  = 0b0_0100,
+}
+}
+
+impl PMFHLFunction {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let raw_func = instruction.r_get_shift_amount();
+		Self::from_u8(raw_func)
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MmiFunction {
+	Div1,
+	DivU1,
+	MAdd,
+	MAdd1,
+	MAddU,
+	MAddU1,
+	MFHi1,
+	MFLo1,
+	MTHi1,
+	MTLo1,
+	Mult1,
+	MultU1,
+	PAbsH,
+	PAbsW,
+	PAddB,
+	PAddH,
+	PAddSB,
+	PAddSH,
+	PAddSW,
+	PAddUB,
+	PAddUH,
+	PAddUW,
+	PAddW,
+	PAdSBH,
+	PAnd,
+	PCEqB,
+	PCEqH,
+	PCEqW,
+	PCGTB,
+	PCGTH,
+	PCGTW,
+	PCpyH,
+	PCpyLD,
+	PCpyUD,
+	PDivBW,
+	PDivUW,
+	PDivW,
+	PExcH,
+	PExcW,
+	PExEH,
+	PExEW,
+	PExt5,
+	PExtLB,
+	PExtLH,
+	PExtLW,
+	PExtUB,
+	PExtUH,
+	PExtUW,
+	PHMAdH,
+	PHMSbH,
+	PIntEH,
+	PIntH,
+	PLZCW,
+	PMAddH,
+	PMAddUW,
+	PMAddW,
+	PMaxH,
+	PMaxW,
+	PMFHi,
+	PMFHLLH,
+	PMFHLLW,
+	PMFHLSH,
+	PMFHLSLW,
+	PMFHLUW,
+	PMFLo,
+	PMinH,
+	PMinW,
+	PMSubH,
+	PMSubW,
+	PMTHi,
+	PMTHLLW,
+	PMTLo,
+	PMultH,
+	PMultUW,
+	PMultW,
+	PNor,
+	POr,
+	PPac5,
+	PPacB,
+	PPacH,
+	PPacW,
+	PRevH,
+	PRot3W,
+	PSLLH,
+	PSLLVW,
+	PSLLW,
+	PSRAH,
+	PSRAVW,
+	PSRAW,
+	PSRLH,
+	PSRLVW,
+	PSRLW,
+	PSubB,
+	PSubH,
+	PSubSB,
+	PSubSH,
+	PSubSW,
+	PSubUB,
+	PSubUH,
+	PSubUW,
+	PSubW,
+	PXor,
+	QFSRV,
+}
+
+pub const MMI0: u8 = 0b00_1000;
+pub const MMI1: u8 = 0b10_1000;
+pub const MMI2: u8 = 0b00_1001;
+pub const MMI3: u8 = 0b10_1001;
+
+/// Format (`sa` field) of `PMTHL.LW`, the only valid `PMTHL`.
+pub const PMTHL_LW: u8 = 0b0_0000;
+
+impl MmiFunction {
+	#[inline(always)]
+	pub fn decode(instruction: u32) -> Option<Self> {
+		match instruction.r_get_function() {
+			MMI0 => {
+				trace!("MMI0");
+				use Mmi0Function::*;
+				Mmi0Function::decode(instruction).map(|f| match f {
+					PAddW => MmiFunction::PAddW,
+					PSubW => MmiFunction::PSubW,
+					PCGTW => MmiFunction::PCGTW,
+					PMaxW => MmiFunction::PMaxW,
+					PAddH => MmiFunction::PAddH,
+					PSubH => MmiFunction::PSubH,
+					PCGTH => MmiFunction::PCGTH,
+					PMaxH => MmiFunction::PMaxH,
+					PAddB => MmiFunction::PAddB,
+					PSubB => MmiFunction::PSubB,
+					PCGTB => MmiFunction::PCGTB,
+					PAddSW => MmiFunction::PAddSW,
+					PSubSW => MmiFunction::PSubSW,
+					PExtLW => MmiFunction::PExtLW,
+					PPacW => MmiFunction::PPacW,
+					PAddSH => MmiFunction::PAddSH,
+					PSubSH => MmiFunction::PSubSH,
+					PExtLH => MmiFunction::PExtLH,
+					PPacH => MmiFunction::PPacH,
+					PAddSB => MmiFunction::PAddSB,
+					PSubSB => MmiFunction::PSubSB,
+					PExtLB => MmiFunction::PExtLB,
+					PPacB => MmiFunction::PPacB,
+					PExt5 => MmiFunction::PExt5,
+					PPac5 => MmiFunction::PPac5,
+				})
+			},
+			MMI1 => {
+				trace!("MMI1");
+				use Mmi1Function::*;
+				Mmi1Function::decode(instruction).map(|f| match f {
+					PAbsW => MmiFunction::PAbsW,
+					PCEqW => MmiFunction::PCEqW,
+					PMinW => MmiFunction::PMinW,
+					PAdSBH => MmiFunction::PAdSBH,
+					PAbsH => MmiFunction::PAbsH,
+					PCEqH => MmiFunction::PCEqH,
+					PMinH => MmiFunction::PMinH,
+					PCEqB => MmiFunction::PCEqB,
+					PAddUW => MmiFunction::PAddUW,
+					PSubUW => MmiFunction::PSubUW,
+					PExtUW => MmiFunction::PExtUW,
+					PAddUH => MmiFunction::PAddUH,
+					PSubUH => MmiFunction::PSubUH,
+					PExtUH => MmiFunction::PExtUH,
+					PAddUB => MmiFunction::PAddUB,
+					PSubUB => MmiFunction::PSubUB,
+					PExtUB => MmiFunction::PExtUB,
+					QFSRV => MmiFunction::QFSRV,
+				})
+			},
+			MMI2 => {
+				trace!("MMI2");
+				use Mmi2Function::*;
+				Mmi2Function::decode(instruction).map(|f| match f {
+					PMAddW => MmiFunction::PMAddW,
+					PSLLVW => MmiFunction::PSLLVW,
+					PSRLVW => MmiFunction::PSRLVW,
+					PMSubW => MmiFunction::PMSubW,
+					PMFHi => MmiFunction::PMFHi,
+					PMFLo => MmiFunction::PMFLo,
+					PIntH => MmiFunction::PIntH,
+					PMultW => MmiFunction::PMultW,
+					PDivW => MmiFunction::PDivW,
+					PCpyLD => MmiFunction::PCpyLD,
+					PMAddH => MmiFunction::PMAddH,
+					PHMAdH => MmiFunction::PHMAdH,
+					PAnd => MmiFunction::PAnd,
+					PXor => MmiFunction::PXor,
+					PMSubH => MmiFunction::PMSubH,
+					PHMSbH => MmiFunction::PHMSbH,
+					PExEH => MmiFunction::PExEH,
+					PRevH => MmiFunction::PRevH,
+					PMultH => MmiFunction::PMultH,
+					PDivBW => MmiFunction::PDivBW,
+					PExEW => MmiFunction::PExEW,
+					PRot3W => MmiFunction::PRot3W,
+				})
+			},
+			MMI3 => {
+				trace!("MMI3");
+				use Mmi3Function::*;
+				Mmi3Function::decode(instruction).map(|f| match f {
+					PMAddUW => MmiFunction::PMAddUW,
+					PSRAVW => MmiFunction::PSRAVW,
+					PMTHi => MmiFunction::PMTHi,
+					PMTLo => MmiFunction::PMTLo,
+					PIntEH => MmiFunction::PIntEH,
+					PMultUW => MmiFunction::PMultUW,
+					PDivUW => MmiFunction::PDivUW,
+					PCpyUD => MmiFunction::PCpyUD,
+					POr => MmiFunction::POr,
+					PNor => MmiFunction::PNor,
+					PExcH => MmiFunction::PExcH,
+					PCpyH => MmiFunction::PCpyH,
+					PExcW => MmiFunction::PExcW,
+				})
+			},
+			_ => {
+				use MmiBaseFunction::*;
+				MmiBaseFunction::decode(instruction).and_then(|f| match f {
+					PMFHL => {
+						use PMFHLFunction::*;
+						PMFHLFunction::decode(instruction).map(|g| match g {
+							LW => MmiFunction::PMFHLLW,
+							UW => MmiFunction::PMFHLUW,
+							SLW => MmiFunction::PMFHLSLW,
+							LH => MmiFunction::PMFHLLH,
+							SH => MmiFunction::PMFHLSH,
+						})
+					},
+					PMTHL => if instruction.r_get_shift_amount() == PMTHL_LW {
+						Some(MmiFunction::PMTHLLW)
+					} else {
+						None
+					},
+					MADD => Some(MmiFunction::MAdd),
+					MADDU => Some(MmiFunction::MAddU),
+					MFHI1 => Some(MmiFunction::MFHi1),
+					MTHI1 => Some(MmiFunction::MTHi1),
+					MFLO1 => Some(MmiFunction::MFLo1),
+					MTLO1 => Some(MmiFunction::MTLo1),
+					MULT1 => Some(MmiFunction::Mult1),
+					MULTU1 => Some(MmiFunction::MultU1),
+					DIV1 => Some(MmiFunction::Div1),
+					DIVU1 => Some(MmiFunction::DivU1),
+					MADD1 => Some(MmiFunction::MAdd1),
+					MADDU1 => Some(MmiFunction::MAddU1),
+					PLZCW => Some(MmiFunction::PLZCW),
+					PSLLH => Some(MmiFunction::PSLLH),
+					PSRLH => Some(MmiFunction::PSRLH),
+					PSRAH => Some(MmiFunction::PSRAH),
+					PSLLW => Some(MmiFunction::PSLLW),
+					PSRLW => Some(MmiFunction::PSRLW),
+					PSRAW => Some(MmiFunction::PSRAW),
+				})
+			},
+		}
 	}
 }
 
@@ -1,3 +1,4 @@
+pub mod disassembly;
 pub mod ee;
 mod instruction;
 
@@ -14,6 +15,8 @@ pub enum Opcode {
 	Cache   = 0b10_1111,
 	Cop0    = 0b01_0000,
 	Cop1    = 0b01_0001,
+	Cop2    = 0b01_0010,
+	Mmi     = 0b01_1100,
 	RegImm  = 0b00_0001,
 
 	AddI    = 0b00_1000,
@@ -22,51 +25,104 @@ pub enum Opcode {
 	BEq     = 0b00_0100,
 	BEqL    = 0b01_0100,
 	BGTZ    = 0b00_0111,
+	BGTZL   = 0b01_0111,
 	BLEZ    = 0b00_0110,
+	BLEZL   = 0b01_0110,
 	BNE     = 0b00_0101,
 	BNEL    = 0b01_0101,
+	DAddI   = 0b01_1000,
+	DAddIU  = 0b01_1001,
 	J       = 0b00_0010,
 	JaL     = 0b00_0011,
 	LB      = 0b10_0000,
 	LBU     = 0b10_0100,
 	LD      = 0b11_0111,
+	LDL     = 0b01_1010,
+	LDR     = 0b01_1011,
+	LH      = 0b10_0001,
 	LHU     = 0b10_0101,
+	LQ      = 0b01_1110,
+	LQC2    = 0b11_0110,
 	LUI     = 0b00_1111,
 	LW      = 0b10_0011,
+	LWC1    = 0b11_0001,
+	LWL     = 0b10_0010,
+	LWR     = 0b10_0110,
+	LWU     = 0b10_0111,
 	OrI     = 0b00_1101,
+	Pref    = 0b11_0011,
 	SB      = 0b10_1000,
 	SD      = 0b11_1111,
+	SDL     = 0b10_1100,
+	SDR     = 0b10_1101,
+	SH      = 0b10_1001,
 	SLTI    = 0b00_1010,
 	SLTIU   = 0b00_1011,
+	SQ      = 0b01_1111,
+	SQC2    = 0b11_1110,
 	SW      = 0b10_1011,
 	SWC1    = 0b11_1001,
+	SWL     = 0b10_1010,
+	SWR     = 0b10_1110,
+	XorI    = 0b00_1110,
 }
 }
 
 enum_from_primitive!{
 #[derive(Debug, PartialEq)]
 pub enum Function {
-	Add   = 0b10_0000,
-	AddU  = 0b10_0001,
-	And   = 0b10_0100,
-	Break = 0b00_1101,
-	DAddU = 0b10_1101,
-	Div   = 0b01_1010,
-	DivU  = 0b01_1011,
-	JaLR  = 0b00_1001,
-	JR    = 0b00_1000,
-	MFHi  = 0b01_0000,
-	MFLo  = 0b01_0010,
-	MovN  = 0b00_1011,
-	Mult  = 0b01_1000,
-	Or    = 0b10_0101,
-	SLL   = 0b00_0000,
-	SLT   = 0b10_1010,
-	SLTU  = 0b10_1011,
-	SRA   = 0b00_0011,
-	SRL   = 0b00_0010,
-	SubU  = 0b10_0011,
-	Sync  = 0b00_1111,
+	Add     = 0b10_0000,
+	AddU    = 0b10_0001,
+	And     = 0b10_0100,
+	Break   = 0b00_1101,
+	DAdd    = 0b10_1100,
+	DAddU   = 0b10_1101,
+	Div     = 0b01_1010,
+	DivU    = 0b01_1011,
+	DSLL    = 0b11_1000,
+	DSLL32  = 0b11_1100,
+	DSLLV   = 0b01_0100,
+	DSRA    = 0b11_1011,
+	DSRA32  = 0b11_1111,
+	DSRAV   = 0b01_0111,
+	DSRL    = 0b11_1010,
+	DSRL32  = 0b11_1110,
+	DSRLV   = 0b01_0110,
+	DSub    = 0b10_1110,
+	DSubU   = 0b10_1111,
+	JaLR    = 0b00_1001,
+	JR      = 0b00_1000,
+	MFHi    = 0b01_0000,
+	MFLo    = 0b01_0010,
+	MFSA    = 0b10_1000,
+	MovN    = 0b00_1011,
+	MovZ    = 0b00_1010,
+	MTHi    = 0b01_0001,
+	MTLo    = 0b01_0011,
+	MTSA    = 0b10_1001,
+	Mult    = 0b01_1000,
+	MultU   = 0b01_1001,
+	Nor     = 0b10_0111,
+	Or      = 0b10_0101,
+	SLL     = 0b00_0000,
+	SLLV    = 0b00_0100,
+	SLT     = 0b10_1010,
+	SLTU    = 0b10_1011,
+	SRA     = 0b00_0011,
+	SRAV    = 0b00_0111,
+	SRL     = 0b00_0010,
+	SRLV    = 0b00_0110,
+	Sub     = 0b10_0010,
+	SubU    = 0b10_0011,
+	Sync    = 0b00_1111,
+	Syscall = 0b00_1100,
+	TEq     = 0b11_0100,
+	TGE     = 0b11_0000,
+	TGEU    = 0b11_0001,
+	TLT     = 0b11_0010,
+	TLTU    = 0b11_0011,
+	TNE     = 0b11_0110,
+	Xor     = 0b10_0110,
 }
 }
 
@@ -81,8 +137,22 @@ impl Function {
 enum_from_primitive!{
 #[derive(Debug, PartialEq)]
 pub enum RegImmFunction {
-	BGEZ   = 0b0_0001,
-	BLTZ   = 0b0_0000,
+	BGEZ    = 0b0_0001,
+	BGEZAL  = 0b1_0001,
+	BGEZALL = 0b1_0011,
+	BGEZL   = 0b0_0011,
+	BLTZ    = 0b0_0000,
+	BLTZAL  = 0b1_0000,
+	BLTZALL = 0b1_0010,
+	BLTZL   = 0b0_0010,
+	MTSAB   = 0b1_1000,
+	MTSAH   = 0b1_1001,
+	TEqI    = 0b0_1100,
+	TGEI    = 0b0_1000,
+	TGEIU   = 0b0_1001,
+	TLTI    = 0b0_1010,
+	TLTIU   = 0b0_1011,
+	TNEI    = 0b0_1110,
 }
 }
 
@@ -95,6 +165,78 @@ impl RegImmFunction {
 	}
 }
 
+/// EE Core mnemonics for each primary opcode, or `""` if reserved.
+const PRIMARY_MNEMONICS: [&str; 64] = [
+	"SPECIAL", "REGIMM", "J", "JAL", "BEQ", "BNE", "BLEZ", "BGTZ",
+	"ADDI", "ADDIU", "SLTI", "SLTIU", "ANDI", "ORI", "XORI", "LUI",
+	"COP0", "COP1", "COP2", "", "BEQL", "BNEL", "BLEZL", "BGTZL",
+	"DADDI", "DADDIU", "LDL", "LDR", "MMI", "", "LQ", "SQ",
+	"LB", "LH", "LWL", "LW", "LBU", "LHU", "LWR", "LWU",
+	"SB", "SH", "SWL", "SW", "SDL", "SDR", "SWR", "CACHE",
+	"", "LWC1", "", "PREF", "", "", "LQC2", "LD",
+	"", "SWC1", "", "", "", "", "SQC2", "SD",
+];
+
+/// EE Core mnemonics for each `SPECIAL` function, or `""` if reserved.
+const SPECIAL_MNEMONICS: [&str; 64] = [
+	"SLL", "", "SRL", "SRA", "SLLV", "", "SRLV", "SRAV",
+	"JR", "JALR", "MOVZ", "MOVN", "SYSCALL", "BREAK", "", "SYNC",
+	"MFHI", "MTHI", "MFLO", "MTLO", "DSLLV", "", "DSRLV", "DSRAV",
+	"MULT", "MULTU", "DIV", "DIVU", "", "", "", "",
+	"ADD", "ADDU", "SUB", "SUBU", "AND", "OR", "XOR", "NOR",
+	"MFSA", "MTSA", "SLT", "SLTU", "DADD", "DADDU", "DSUB", "DSUBU",
+	"TGE", "TGEU", "TLT", "TLTU", "TEQ", "", "TNE", "",
+	"DSLL", "", "DSRL", "DSRA", "DSLL32", "", "DSRL32", "DSRA32",
+];
+
+/// EE Core mnemonics for each `REGIMM` function, or `""` if reserved.
+const REGIMM_MNEMONICS: [&str; 32] = [
+	"BLTZ", "BGEZ", "BLTZL", "BGEZL", "", "", "", "",
+	"TGEI", "TGEIU", "TLTI", "TLTIU", "TEQI", "", "TNEI", "",
+	"BLTZAL", "BGEZAL", "BLTZALL", "BGEZALL", "", "", "", "",
+	"MTSAB", "MTSAH", "", "", "", "", "", "",
+];
+
+/// Best-effort description of an instruction which failed to decode,
+/// for use in diagnostics.
+pub fn mnemonic_guess(instruction: u32) -> String {
+	let raw_opcode = instruction.get_opcode();
+	let family = PRIMARY_MNEMONICS[raw_opcode as usize];
+
+	let sub_name = match Opcode::from_u8(raw_opcode) {
+		Some(Opcode::Special) => SPECIAL_MNEMONICS[instruction.r_get_function() as usize],
+		Some(Opcode::RegImm) => REGIMM_MNEMONICS[instruction.ri_get_target() as usize],
+		_ => "",
+	};
+
+	match (family, sub_name) {
+		("", _) => format!("reserved opcode {:06b}", raw_opcode),
+		(_, "") if family == "SPECIAL" => format!("reserved SPECIAL function {:06b}", instruction.r_get_function()),
+		(_, "") if family == "REGIMM" => format!("reserved REGIMM function {:05b}", instruction.ri_get_target()),
+		(_, "") if family.starts_with("COP") || family == "MMI" || family == "CACHE" => format!(
+			"{} (rs {:05b}, rt {:05b}, function {:06b})",
+			family,
+			instruction.ri_get_source(),
+			instruction.ri_get_target(),
+			instruction.r_get_function(),
+		),
+		(_, "") => family.to_string(),
+		(_, name) => name.to_string(),
+	}
+}
+
+/// Coprocessor targeted by an instruction, if any.
+pub fn coprocessor_number(instruction: u32) -> Option<u8> {
+	let raw_opcode = instruction.get_opcode();
+
+	match raw_opcode {
+		// COPz, LWCz, LQC2/LDCz, SWCz, SQC2/SDCz.
+		0b01_0000..=0b01_0011 | 0b11_0000..=0b11_0111 | 0b11_1000..=0b11_1111
+			if !matches!(raw_opcode, 0b11_0011 | 0b11_0111 | 0b11_1111) => Some(raw_opcode & 0b11),
+		_ => None,
+	}
+}
+
 /// Consistent functions shared between a MIPS CPU.
 pub trait Cpu {
 	type Register;
@@ -303,10 +445,122 @@ impl Capability {
 		)
 	}
 
+	pub fn branch_link_read_s(i: u32) -> Self {
+		Self::normalised(
+			Self::REG_PC | (1 << 31),
+			1 << i.ri_get_source(),
+		)
+	}
+
 	pub fn mul_div(i: u32) -> Self {
 		Self::normalised(
-			Self::REG_HI | Self::REG_LO,
-			(1 << i.ri_get_source()) | (1 << i.ri_get_source()),
+			Self::REG_HI | Self::REG_LO | (1 << i.r_get_destination()),
+			(1 << i.ri_get_target()) | (1 << i.ri_get_source()),
+		)
+	}
+
+	pub fn mul_div1(i: u32) -> Self {
+		Self::normalised(
+			Self::REG_HI1 | Self::REG_LO1 | (1 << i.r_get_destination()),
+			(1 << i.ri_get_target()) | (1 << i.ri_get_source()),
+		)
+	}
+
+	pub fn write_d_read_hi(i: u32) -> Self {
+		Self::normalised(
+			1 << i.r_get_destination(),
+			Self::REG_HI,
+		)
+	}
+
+	pub fn write_d_read_lo(i: u32) -> Self {
+		Self::normalised(
+			1 << i.r_get_destination(),
+			Self::REG_LO,
+		)
+	}
+
+	pub fn write_d_read_hi1(i: u32) -> Self {
+		Self::normalised(
+			1 << i.r_get_destination(),
+			Self::REG_HI1,
+		)
+	}
+
+	pub fn write_d_read_lo1(i: u32) -> Self {
+		Self::normalised(
+			1 << i.r_get_destination(),
+			Self::REG_LO1,
+		)
+	}
+
+	pub fn write_hi_read_s(i: u32) -> Self {
+		Self::normalised(
+			Self::REG_HI,
+			1 << i.ri_get_source(),
+		)
+	}
+
+	pub fn write_lo_read_s(i: u32) -> Self {
+		Self::normalised(
+			Self::REG_LO,
+			1 << i.ri_get_source(),
+		)
+	}
+
+	pub fn write_hi1_read_s(i: u32) -> Self {
+		Self::normalised(
+			Self::REG_HI1,
+			1 << i.ri_get_source(),
+		)
+	}
+
+	pub fn write_lo1_read_s(i: u32) -> Self {
+		Self::normalised(
+			Self::REG_LO1,
+			1 << i.ri_get_source(),
+		)
+	}
+
+	pub fn write_d_read_sa(i: u32) -> Self {
+		Self::normalised(
+			1 << i.r_get_destination(),
+			Self::REG_SA,
+		)
+	}
+
+	pub fn write_sa_read_s(i: u32) -> Self {
+		Self::normalised(
+			Self::REG_SA,
+			1 << i.ri_get_source(),
+		)
+	}
+
+	pub fn wide_mul_div(i: u32) -> Self {
+		Self::normalised(
+			Self::REG_HI | Self::REG_LO | Self::REG_HI1 | Self::REG_LO1 | (1 << i.r_get_destination()),
+			(1 << i.ri_get_target()) | (1 << i.ri_get_source()),
+		)
+	}
+
+	pub fn write_d_read_hi_lo(i: u32) -> Self {
+		Self::normalised(
+			1 << i.r_get_destination(),
+			Self::REG_HI | Self::REG_LO | Self::REG_HI1 | Self::REG_LO1,
+		)
+	}
+
+	pub fn write_hi_lo_read_s(i: u32) -> Self {
+		Self::normalised(
+			Self::REG_HI | Self::REG_LO | Self::REG_HI1 | Self::REG_LO1,
+			1 << i.ri_get_source(),
+		)
+	}
+
+	pub fn funnel_shift(i: u32) -> Self {
+		Self::normalised(
+			1 << i.r_get_destination(),
+			Self::REG_SA | (1 << i.ri_get_target()) | (1 << i.ri_get_source()),
 		)
 	}
 
@@ -1 +1,2 @@
 pub mod mips;
+pub mod vu;
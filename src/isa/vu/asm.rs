@@ -0,0 +1,704 @@
+//! Disassembly and assembly of microinstruction pairs, in the syntax of the
+//! SDK's `dvp-as`.
+//!
+//! A pair is written on one line, its upper instruction then its lower one:
+//!
+//! ```text
+//! addy.xyz[e] vf1, vf2, vf3y      lq.w vf4, 2(vi1)
+//! ```
+//!
+//! Mnemonics are the names of the decoded instructions (`UpperFunction` and
+//! so on) in lowercase, with `bc` replaced by the broadcast field, so both
+//! directions go through the interpreter's own decoder. The `E`, `M`, `D`
+//! and `T` bits follow the upper mnemonic in brackets, and a pair with the
+//! `I` bit has `loi` and the value in place of its lower instruction. Branch
+//! targets are byte addresses in micro memory, or labels.
+
+use std::collections::HashMap;
+use super::*;
+
+const FIELD_NAMES: [char; 4] = ['x', 'y', 'z', 'w'];
+
+/// Flag bits of the upper instruction, by their letters.
+const FLAGS: [(char, u32); 4] = [('e', UPPER_E), ('m', UPPER_M), ('d', UPPER_D), ('t', UPPER_T)];
+
+/// Labels, and the byte addresses they mark.
+type Labels = HashMap<String, u32>;
+
+/// How an operand is written, and the bits it is held in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand {
+	/// `vfN`.
+	Fd,
+	Fs,
+	Ft,
+	/// `vfNx`, with the broadcast field.
+	FtBc,
+	/// `vfNx`, with the field in `fsf` or `ftf`.
+	FsF,
+	FtF,
+	/// `viN`.
+	Id,
+	Is,
+	It,
+	/// Registers named by the instruction itself.
+	Acc,
+	Q,
+	I,
+	P,
+	R,
+	Vi1,
+	Imm5,
+	Imm12,
+	Imm15,
+	Imm24,
+	/// A whole 32-bit word, as `loi` and `.word` take.
+	Word,
+	/// `imm11(viN)`.
+	OffsetIs,
+	OffsetIt,
+	/// `(viN)`.
+	IndirectIs,
+	/// `(viN++)`.
+	IncrementIs,
+	IncrementIt,
+	/// `(--viN)`.
+	DecrementIs,
+	DecrementIt,
+	/// Branch target, `imm11` instructions past the delay slot.
+	Target,
+}
+
+use Operand::*;
+
+/// Whether an upper instruction takes a field mask, and its operands.
+fn upper_syntax(upper: Upper) -> (bool, &'static [Operand]) {
+	use UpperFunction as F;
+	use UpperSpecial as S;
+
+	match upper {
+		Upper::Function(function) => match function {
+			F::AddBc | F::SubBc | F::MaddBc | F::MsubBc | F::MaxBc | F::MiniBc | F::MulBc => (true, &[Fd, Fs, FtBc]),
+			F::MulQ | F::AddQ | F::MaddQ | F::SubQ | F::MsubQ => (true, &[Fd, Fs, Q]),
+			F::MaxI | F::MulI | F::MiniI | F::AddI | F::MaddI | F::SubI | F::MsubI => (true, &[Fd, Fs, I]),
+			F::Add | F::Madd | F::Mul | F::Max | F::Sub | F::Msub | F::OpMsub | F::Mini => (true, &[Fd, Fs, Ft]),
+			F::Special => (false, &[]),
+		},
+		Upper::Special(special) => match special {
+			S::AddaBc | S::SubaBc | S::MaddaBc | S::MsubaBc | S::MulaBc => (true, &[Acc, Fs, FtBc]),
+			S::MulaQ | S::AddaQ | S::MaddaQ | S::SubaQ | S::MsubaQ => (true, &[Acc, Fs, Q]),
+			S::MulaI | S::AddaI | S::MaddaI | S::SubaI | S::MsubaI => (true, &[Acc, Fs, I]),
+			S::Adda | S::Madda | S::Mula | S::Suba | S::Msuba | S::OpMula => (true, &[Acc, Fs, Ft]),
+			S::Itof0 | S::Itof4 | S::Itof12 | S::Itof15 | S::Ftoi0 | S::Ftoi4 | S::Ftoi12 | S::Ftoi15 | S::Abs
+				=> (true, &[Ft, Fs]),
+			S::Clip => (true, &[Fs, FtBc]),
+			S::Nop => (false, &[]),
+		},
+	}
+}
+
+/// Whether a lower instruction takes a field mask, and its operands.
+fn lower_syntax(lower: Lower) -> (bool, &'static [Operand]) {
+	use LowerOpcode as O;
+	use LowerFunction as F;
+	use LowerSpecial as S;
+
+	match lower {
+		Lower::Opcode(opcode) => match opcode {
+			O::Lq => (true, &[Ft, OffsetIs]),
+			O::Sq => (true, &[Fs, OffsetIt]),
+			O::Ilw | O::Isw => (true, &[It, OffsetIs]),
+			O::IaddIu | O::IsubIu => (false, &[It, Is, Imm15]),
+			O::FcEq | O::FcAnd | O::FcOr => (false, &[Vi1, Imm24]),
+			O::FcSet => (false, &[Imm24]),
+			O::FsEq | O::FsAnd | O::FsOr => (false, &[It, Imm12]),
+			O::FsSet => (false, &[Imm12]),
+			O::FmEq | O::FmAnd | O::FmOr => (false, &[It, Is]),
+			O::FcGet => (false, &[It]),
+			O::B => (false, &[Target]),
+			O::Bal => (false, &[It, Target]),
+			O::Jr => (false, &[Is]),
+			O::JalR => (false, &[It, Is]),
+			O::IbEq | O::IbNe => (false, &[It, Is, Target]),
+			O::IbLtz | O::IbGtz | O::IbLez | O::IbGez => (false, &[Is, Target]),
+			O::Operate => (false, &[]),
+		},
+		Lower::Function(function) => match function {
+			F::Iadd | F::Isub | F::Iand | F::Ior => (false, &[Id, Is, It]),
+			F::Iaddi => (false, &[It, Is, Imm5]),
+			F::Special => (false, &[]),
+		},
+		Lower::Special(special) => match special {
+			S::Move | S::Mr32 => (true, &[Ft, Fs]),
+			S::Lqi => (true, &[Ft, IncrementIs]),
+			S::Sqi => (true, &[Fs, IncrementIt]),
+			S::Lqd => (true, &[Ft, DecrementIs]),
+			S::Sqd => (true, &[Fs, DecrementIt]),
+			S::Div | S::Rsqrt => (false, &[Q, FsF, FtF]),
+			S::Sqrt => (false, &[Q, FtF]),
+			S::WaitQ | S::WaitP => (false, &[]),
+			S::Mtir => (false, &[It, FsF]),
+			S::Mfir => (true, &[Ft, Is]),
+			S::Ilwr | S::Iswr => (true, &[It, IndirectIs]),
+			S::Rnext | S::Rget => (true, &[Ft, R]),
+			S::Rinit | S::Rxor => (false, &[R, FsF]),
+			S::Mfp => (true, &[Ft, P]),
+			S::Xtop | S::Xitop => (false, &[It]),
+			S::XgKick => (false, &[Is]),
+			S::Esadd | S::Ersadd | S::Eleng | S::Erleng | S::EatanXy | S::EatanXz | S::Esum => (false, &[P, Fs]),
+			S::Esqrt | S::Ersqrt | S::Ercpr | S::Esin | S::Eatan | S::Eexp => (false, &[P, FsF]),
+		},
+	}
+}
+
+fn variant_name(variant: impl std::fmt::Debug) -> String {
+	format!("{:?}", variant).to_lowercase()
+}
+
+fn upper_mnemonic(upper: Upper, instruction: u32) -> String {
+	let name = match upper {
+		Upper::Function(function) => variant_name(function),
+		// Only ever judged against `w`.
+		Upper::Special(UpperSpecial::Clip) => return "clipw".to_string(),
+		Upper::Special(special) => variant_name(special),
+	};
+
+	match name.strip_suffix("bc") {
+		Some(base) => format!("{}{}", base, FIELD_NAMES[instruction.get_bc() as usize]),
+		None => name,
+	}
+}
+
+fn lower_mnemonic(lower: Lower) -> String {
+	match lower {
+		Lower::Opcode(opcode) => variant_name(opcode),
+		Lower::Function(function) => variant_name(function),
+		Lower::Special(special) => variant_name(special),
+	}
+}
+
+/// Every upper encoding with a distinct mnemonic, operands left zero.
+fn upper_candidates() -> impl Iterator<Item = u32> {
+	let functions = (0..UpperFunction::Special as u8).map(|function| build_upper(function, 0, 0, 0, 0));
+	let specials = (0..0x80).map(|special| build_upper_special(special, 0, 0, 0));
+
+	functions.chain(specials).filter(|&instruction| Upper::decode(instruction).is_some())
+}
+
+/// Every lower encoding with a distinct mnemonic, operands left zero.
+fn lower_candidates() -> impl Iterator<Item = u32> {
+	let opcodes = (0..LowerOpcode::Operate as u8).map(|opcode| build_lower(opcode, 0, 0, 0, 0));
+	let functions = (0..LowerFunction::Special as u8).map(|function| build_lower_function(function, 0, 0, 0, 0));
+	let specials = (0..0x80).map(|special| build_lower_special(special, 0, 0, 0));
+
+	opcodes.chain(functions).chain(specials).filter(|&instruction| Lower::decode(instruction).is_some())
+}
+
+fn dest_suffix(dest: u8) -> String {
+	let letters = FIELD_NAMES.iter()
+		.enumerate()
+		.filter(|&(field, _)| dest & (DEST_X >> field) != 0)
+		.map(|(_, &letter)| letter)
+		.collect::<String>();
+
+	if letters.is_empty() { letters } else { format!(".{}", letters) }
+}
+
+fn format_operand(operand: Operand, instruction: u32, address: u32) -> String {
+	let i = instruction;
+
+	match operand {
+		Fd => format!("vf{}", i.get_fd()),
+		Fs => format!("vf{}", i.get_fs()),
+		Ft => format!("vf{}", i.get_ft()),
+		FtBc => format!("vf{}{}", i.get_ft(), FIELD_NAMES[i.get_bc() as usize]),
+		FsF => format!("vf{}{}", i.get_fs(), FIELD_NAMES[i.get_fsf() as usize]),
+		FtF => format!("vf{}{}", i.get_ft(), FIELD_NAMES[i.get_ftf() as usize]),
+		Id => format!("vi{}", i.get_id()),
+		Is => format!("vi{}", i.get_is()),
+		It => format!("vi{}", i.get_it()),
+		Acc => "acc".to_string(),
+		Q => "q".to_string(),
+		I => "i".to_string(),
+		P => "p".to_string(),
+		R => "r".to_string(),
+		Vi1 => "vi1".to_string(),
+		Imm5 => i.get_imm5().to_string(),
+		Imm12 => format!("0x{:x}", i.get_imm12()),
+		Imm15 => format!("0x{:x}", i.get_imm15()),
+		Imm24 => format!("0x{:x}", i.get_imm24()),
+		Word => format!("0x{:08x}", i),
+		OffsetIs => format!("{}(vi{})", i.get_imm11(), i.get_is()),
+		OffsetIt => format!("{}(vi{})", i.get_imm11(), i.get_it()),
+		IndirectIs => format!("(vi{})", i.get_is()),
+		IncrementIs => format!("(vi{}++)", i.get_is()),
+		IncrementIt => format!("(vi{}++)", i.get_it()),
+		DecrementIs => format!("(--vi{})", i.get_is()),
+		DecrementIt => format!("(--vi{})", i.get_it()),
+		Target => format!("0x{:04x}", branch_target(i, address)),
+	}
+}
+
+/// Byte address a branch at `address` goes to.
+fn branch_target(instruction: u32, address: u32) -> u32 {
+	address.wrapping_add(8).wrapping_add((i32::from(instruction.get_imm11()) * 8) as u32)
+}
+
+fn format_instruction(mnemonic: String, operands: &[Operand], instruction: u32, address: u32) -> String {
+	if operands.is_empty() {
+		return mnemonic;
+	}
+
+	let operands = operands.iter()
+		.map(|&operand| format_operand(operand, instruction, address))
+		.collect::<Vec<_>>();
+
+	format!("{} {}", mnemonic, operands.join(", "))
+}
+
+/// Disassemble an upper instruction, with its flag bits save `I`.
+pub fn disassemble_upper(instruction: u32) -> String {
+	let upper = match Upper::decode(instruction) {
+		Some(upper) => upper,
+		None => return format_instruction(".word".to_string(), &[Word], instruction, 0),
+	};
+
+	let (dest, operands) = upper_syntax(upper);
+	let mut mnemonic = upper_mnemonic(upper, instruction);
+	if dest {
+		mnemonic += &dest_suffix(instruction.get_dest());
+	}
+	for &(letter, bit) in FLAGS.iter() {
+		if instruction & bit != 0 {
+			mnemonic += &format!("[{}]", letter);
+		}
+	}
+
+	format_instruction(mnemonic, operands, instruction, 0)
+}
+
+/// Disassemble a lower instruction at byte `address` in micro memory.
+pub fn disassemble_lower(instruction: u32, address: u32) -> String {
+	if instruction == LOWER_NOP {
+		return "nop".to_string();
+	}
+
+	let lower = match Lower::decode(instruction) {
+		Some(lower) => lower,
+		None => return format_instruction(".word".to_string(), &[Word], instruction, address),
+	};
+
+	let (dest, operands) = lower_syntax(lower);
+	let mut mnemonic = lower_mnemonic(lower);
+	if dest {
+		mnemonic += &dest_suffix(instruction.get_dest());
+	}
+
+	format_instruction(mnemonic, operands, instruction, address)
+}
+
+/// Disassemble the instruction pair at byte `address` in micro memory.
+pub fn disassemble(pair: u64, address: u32) -> String {
+	let (upper, lower) = ((pair >> 32) as u32, pair as u32);
+
+	let lower = if upper & UPPER_I != 0 {
+		format_instruction("loi".to_string(), &[Word], lower, address)
+	} else {
+		disassemble_lower(lower, address)
+	};
+
+	format!("{:<31} {}", disassemble_upper(upper & !UPPER_I), lower)
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+	let (negative, magnitude) = match text.strip_prefix('-') {
+		Some(magnitude) => (true, magnitude),
+		None => (false, text),
+	};
+
+	let value = match magnitude.strip_prefix("0x") {
+		Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+		None => magnitude.parse().ok()?,
+	};
+
+	Some(if negative { -value } else { value })
+}
+
+/// Parse a number within `range`.
+fn parse_immediate(text: &str, range: std::ops::RangeInclusive<i64>) -> Result<u32, String> {
+	match parse_number(text) {
+		Some(value) if range.contains(&value) => Ok(value as u32),
+		Some(_) => Err(format!("`{}` is out of range", text)),
+		None => Err(format!("expected a number, not `{}`", text)),
+	}
+}
+
+/// Parse a field mask, such as `xzw`.
+fn parse_dest(text: &str) -> Result<u8, String> {
+	let mut dest = 0;
+	let mut next = 0;
+
+	for letter in text.chars() {
+		match FIELD_NAMES[next.min(4)..].iter().position(|&name| name == letter) {
+			Some(offset) => {
+				dest |= DEST_X >> (next + offset);
+				next += offset + 1;
+			},
+			None => return Err(format!("bad field mask `{}`", text)),
+		}
+	}
+
+	if dest == 0 {
+		return Err(format!("bad field mask `{}`", text));
+	}
+	Ok(dest)
+}
+
+fn parse_field(text: &str, register: &str) -> Result<u32, String> {
+	let mut letters = text.chars();
+
+	match (letters.next().and_then(|letter| FIELD_NAMES.iter().position(|&name| name == letter)), letters.next()) {
+		(Some(field), None) => Ok(field as u32),
+		_ => Err(format!("expected a single field after `{}`", register)),
+	}
+}
+
+/// Parse register `text`, one of `count` with names starting `prefix`,
+/// returning its index and whatever follows the number.
+fn parse_register<'a>(text: &'a str, prefix: &str, count: u32) -> Result<(u32, &'a str), String> {
+	let error = || format!("expected a {} register, not `{}`", prefix, text);
+	let digits = text.strip_prefix(prefix).ok_or_else(error)?;
+	let length = digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+
+	match digits[..length].parse() {
+		Ok(index) if index < count => Ok((index, &digits[length..])),
+		_ => Err(error()),
+	}
+}
+
+fn parse_vi(text: &str) -> Result<u32, String> {
+	match parse_register(text, "vi", 16)? {
+		(index, "") => Ok(index),
+		_ => Err(format!("expected a vi register, not `{}`", text)),
+	}
+}
+
+/// Parse a VF register, which may be followed by a field mask as `dvp-as` allows.
+fn parse_vf(text: &str) -> Result<u32, String> {
+	let (index, rest) = parse_register(text, "vf", 32)?;
+	if !rest.is_empty() {
+		parse_dest(rest)?;
+	}
+	Ok(index)
+}
+
+/// Parse a VF register followed by a single field.
+fn parse_vf_field(text: &str) -> Result<(u32, u32), String> {
+	let (index, rest) = parse_register(text, "vf", 32)?;
+	Ok((index, parse_field(rest, text)?))
+}
+
+/// Parse a VI register written between `prefix` and `suffix`.
+fn parse_wrapped_vi(text: &str, prefix: &str, suffix: &str) -> Result<u32, String> {
+	text.strip_prefix(prefix)
+		.and_then(|text| text.strip_suffix(suffix))
+		.ok_or_else(|| format!("expected `{}viN{}`, not `{}`", prefix, suffix, text))
+		.and_then(parse_vi)
+}
+
+/// Parse `imm11(viN)`, returning the offset's bits and the register.
+fn parse_offset(text: &str) -> Result<(u32, u32), String> {
+	let (offset, register) = text.split_once('(')
+		.ok_or_else(|| format!("expected `offset(viN)`, not `{}`", text))?;
+	let offset = if offset.is_empty() { 0 } else { parse_immediate(offset, -0x400..=0x3ff)? };
+
+	Ok((offset & 0x7ff, parse_wrapped_vi(register, "", ")")?))
+}
+
+fn parse_word(text: &str) -> Result<u32, String> {
+	if text.contains('.') && !text.starts_with("0x") {
+		return text.parse::<f32>()
+			.map(f32::to_bits)
+			.map_err(|_| format!("expected a number, not `{}`", text));
+	}
+
+	parse_immediate(text, i64::from(i32::MIN)..=i64::from(u32::MAX))
+}
+
+/// The bits of `instruction` at byte `address` which encode operand `text`.
+fn encode_operand(operand: Operand, text: &str, instruction: u32, address: u32, labels: &Labels) -> Result<u32, String> {
+	let fixed = |name: &str| if text == name {
+		Ok(0)
+	} else {
+		Err(format!("expected `{}`, not `{}`", name, text))
+	};
+
+	match operand {
+		Fd => Ok(parse_vf(text)? << 6),
+		Fs => Ok(parse_vf(text)? << 11),
+		Ft => Ok(parse_vf(text)? << 16),
+		FtBc => {
+			let (index, field) = parse_vf_field(text)?;
+			if field != u32::from(instruction.get_bc()) {
+				return Err(format!("the field of `{}` does not match the instruction", text));
+			}
+			Ok(index << 16)
+		},
+		FsF => parse_vf_field(text).map(|(index, field)| (field << 21) | (index << 11)),
+		FtF => parse_vf_field(text).map(|(index, field)| (field << 23) | (index << 16)),
+		Id => Ok(parse_vi(text)? << 6),
+		Is => Ok(parse_vi(text)? << 11),
+		It => Ok(parse_vi(text)? << 16),
+		Acc => fixed("acc"),
+		Q => fixed("q"),
+		I => fixed("i"),
+		P => fixed("p"),
+		R => fixed("r"),
+		Vi1 => fixed("vi1"),
+		Imm5 => Ok((parse_immediate(text, -0x10..=0xf)? & 0x1f) << 6),
+		Imm12 => parse_immediate(text, 0..=0xfff).map(|value| ((value >> 11) << 21) | (value & 0x7ff)),
+		Imm15 => parse_immediate(text, 0..=0x7fff).map(|value| ((value >> 11) << 21) | (value & 0x7ff)),
+		Imm24 => parse_immediate(text, 0..=0xff_ffff),
+		Word => parse_word(text),
+		OffsetIs => parse_offset(text).map(|(offset, register)| (register << 11) | offset),
+		OffsetIt => parse_offset(text).map(|(offset, register)| (register << 16) | offset),
+		IndirectIs => Ok(parse_wrapped_vi(text, "(", ")")? << 11),
+		IncrementIs => Ok(parse_wrapped_vi(text, "(", "++)")? << 11),
+		IncrementIt => Ok(parse_wrapped_vi(text, "(", "++)")? << 16),
+		DecrementIs => Ok(parse_wrapped_vi(text, "(--", ")")? << 11),
+		DecrementIt => Ok(parse_wrapped_vi(text, "(--", ")")? << 16),
+		Target => {
+			let target = match labels.get(text) {
+				Some(&target) => i64::from(target),
+				None => parse_number(text).ok_or_else(|| format!("unknown label `{}`", text))?,
+			};
+			let offset = target - (i64::from(address) + 8);
+
+			if offset % 8 != 0 || !(-0x400 * 8..=0x3ff * 8).contains(&offset) {
+				return Err(format!("cannot branch from 0x{:04x} to `{}`", address, text));
+			}
+			Ok((offset / 8) as u32 & 0x7ff)
+		},
+	}
+}
+
+/// Split a mnemonic into its name, field mask and flag bits.
+fn parse_mnemonic(text: &str) -> Result<(&str, Option<u8>, u32), String> {
+	let (text, flags) = text.split_at(text.find('[').unwrap_or(text.len()));
+
+	let mut bits = 0;
+	for letter in flags.chars().filter(|&c| c != '[' && c != ']') {
+		bits |= FLAGS.iter()
+			.find(|&&(flag, _)| flag == letter)
+			.map(|&(_, bit)| bit)
+			.ok_or_else(|| format!("unknown flag `{}`", letter))?;
+	}
+
+	// Directives start with a dot of their own.
+	match text.char_indices().skip(1).find(|&(_, c)| c == '.') {
+		Some((index, _)) => Ok((&text[..index], Some(parse_dest(&text[index + 1..])?), bits)),
+		None => Ok((text, None, bits)),
+	}
+}
+
+/// Encode instruction `base`, given its field mask and operands as written.
+fn encode(
+	base: u32,
+	(takes_dest, operands): (bool, &[Operand]),
+	dest: Option<u8>,
+	text: Option<&str>,
+	address: u32,
+	labels: &Labels,
+) -> Result<u32, String> {
+	let mut instruction = base;
+
+	match dest {
+		Some(dest) if takes_dest => instruction |= u32::from(dest) << 21,
+		Some(_) => return Err("instruction takes no field mask".to_string()),
+		None => {},
+	}
+
+	let texts = text.map(|text| text.split(',').collect::<Vec<_>>()).unwrap_or_default();
+	if texts.len() != operands.len() {
+		return Err(format!("expected {} operands, found {}", operands.len(), texts.len()));
+	}
+
+	for (&operand, text) in operands.iter().zip(texts) {
+		instruction |= encode_operand(operand, text, instruction, address, labels)?;
+	}
+
+	Ok(instruction)
+}
+
+fn assemble_pair_with(text: &str, address: u32, labels: &Labels) -> Result<u64, String> {
+	// Operands are separated by commas alone, leaving spaces between instructions.
+	let text = text.split(',').map(str::trim).collect::<Vec<_>>().join(",");
+	let mut tokens = text.split_whitespace();
+
+	let (name, dest, flags) = parse_mnemonic(tokens.next().ok_or("missing upper instruction")?)?;
+	let (base, syntax) = if name == ".word" {
+		(0, (false, &[Word][..]))
+	} else {
+		let base = upper_candidates()
+			.find(|&instruction| upper_mnemonic(Upper::decode(instruction).unwrap(), instruction) == name)
+			.ok_or_else(|| format!("unknown upper instruction `{}`", name))?;
+		(base, upper_syntax(Upper::decode(base).unwrap()))
+	};
+	let operands = if syntax.1.is_empty() { None } else { tokens.next() };
+	let mut upper = encode(base, syntax, dest, operands, address, labels)? | flags;
+
+	let (name, dest, flags) = parse_mnemonic(tokens.next().ok_or("missing lower instruction")?)?;
+	if flags != 0 {
+		return Err("flags belong to the upper instruction".to_string());
+	}
+	let (base, syntax) = match name {
+		"nop" => (LOWER_NOP, (false, &[][..])),
+		".word" => (0, (false, &[Word][..])),
+		"loi" => {
+			upper |= UPPER_I;
+			(0, (false, &[Word][..]))
+		},
+		name => {
+			let base = lower_candidates()
+				.find(|&instruction| lower_mnemonic(Lower::decode(instruction).unwrap()) == name)
+				.ok_or_else(|| format!("unknown lower instruction `{}`", name))?;
+			(base, lower_syntax(Lower::decode(base).unwrap()))
+		},
+	};
+	let operands = if syntax.1.is_empty() { None } else { tokens.next() };
+	let lower = encode(base, syntax, dest, operands, address, labels)?;
+
+	if let Some(extra) = tokens.next() {
+		return Err(format!("unexpected `{}`", extra));
+	}
+
+	Ok((u64::from(upper) << 32) | u64::from(lower))
+}
+
+/// Assemble one line holding an instruction pair, for byte `address` in micro memory.
+pub fn assemble_pair(text: &str, address: u32) -> Result<u64, String> {
+	assemble_pair_with(&text.to_lowercase(), address, &Labels::new())
+}
+
+fn is_label(text: &str) -> bool {
+	let mut chars = text.chars();
+
+	chars.next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+		&& chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Assemble a microprogram to run from address 0, a pair per line.
+///
+/// Lines may start with `label:`, and `;` starts a comment.
+pub fn assemble(source: &str) -> Result<Vec<u64>, String> {
+	let mut labels = Labels::new();
+	let mut lines = vec![];
+	let mut address = 0;
+
+	for (number, line) in source.lines().enumerate() {
+		let line = line.split(';').next().unwrap_or("").to_lowercase();
+		let mut text = line.trim();
+
+		while let Some((label, rest)) = text.split_once(':') {
+			let label = label.trim();
+			if !is_label(label) || labels.insert(label.to_string(), address).is_some() {
+				return Err(format!("line {}: bad or repeated label `{}`", number + 1, label));
+			}
+			text = rest.trim();
+		}
+
+		if !text.is_empty() {
+			lines.push((number + 1, text.to_string(), address));
+			address += 8;
+		}
+	}
+
+	lines.iter()
+		.map(|(number, text, address)| assemble_pair_with(text, *address, &labels)
+			.map_err(|message| format!("line {}: {}", number, message)))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn disassembles_pairs() {
+		let pair = |upper: u32, lower: u32| (u64::from(upper) << 32) | u64::from(lower);
+
+		let addy = build_upper(UpperFunction::AddBc as u8 | 1, DEST_X | DEST_Y | DEST_Z, 3, 2, 1);
+		let lq = build_lower(LowerOpcode::Lq as u8, DEST_W, 4, 1, 2);
+		assert_eq!(disassemble(pair(addy | UPPER_E, lq), 0), format!("{:<31} lq.w vf4, 2(vi1)", "addy.xyz[e] vf1, vf2, vf3y"));
+
+		let clip = build_upper_special(UpperSpecial::Clip as u8, DEST_X | DEST_Y | DEST_Z, 2, 1);
+		let ibne = build_lower(LowerOpcode::IbNe as u8, 0, 0, 1, (-3i16) as u16);
+		assert_eq!(disassemble_upper(clip), "clipw.xyz vf1, vf2w");
+		assert_eq!(disassemble_lower(ibne, 0x20), "ibne vi0, vi1, 0x0010");
+
+		let div = build_lower_special(LowerSpecial::Div as u8, 0b01_11, 2, 1);
+		assert_eq!(disassemble_lower(div, 0), "div q, vf1w, vf2y");
+		assert_eq!(disassemble_lower(build_lower_special(LowerSpecial::Sqi as u8, DEST_X, 2, 5), 0), "sqi.x vf5, (vi2++)");
+
+		assert_eq!(disassemble(pair(UPPER_NOP | UPPER_I, 0x3f80_0000), 0).split_whitespace().collect::<Vec<_>>(),
+			["nop", "loi", "0x3f800000"]);
+		assert_eq!(disassemble(pair(UPPER_NOP, LOWER_NOP), 0).split_whitespace().collect::<Vec<_>>(), ["nop", "nop"]);
+		assert_eq!(disassemble_lower(0x7e00_0000, 0), ".word 0x7e000000");
+	}
+
+	#[test]
+	fn assembles_programs() {
+		let program = assemble("
+			; Sum VI1 down to 1 into VI2.
+			        NOP                      iaddiu vi1, vi0, 3
+			loop:   mulx.xyzw vf1, vf1, vf0x  iadd vi2, vi2, vi1
+			        nop                      iaddi vi1, vi1, -1
+			        nop                      ibne vi1, vi0, loop
+			        add.xy vf2xy,vf1xy,vf1xy  nop
+			        nop[e]                   loi 1.0
+			        nop                      sq.xyzw vf2, 0x10(vi2)
+		").unwrap();
+
+		assert_eq!(program, [
+			(u64::from(UPPER_NOP) << 32) | u64::from(build_lower(LowerOpcode::IaddIu as u8, 0, 1, 0, 3)),
+			(u64::from(build_upper(UpperFunction::MulBc as u8, 0xf, 0, 1, 1)) << 32)
+				| u64::from(build_lower_function(LowerFunction::Iadd as u8, 0, 1, 2, 2)),
+			(u64::from(UPPER_NOP) << 32) | u64::from(build_lower_function(LowerFunction::Iaddi as u8, 0, 1, 1, 0x1f)),
+			(u64::from(UPPER_NOP) << 32) | u64::from(build_lower(LowerOpcode::IbNe as u8, 0, 1, 0, (-3i16) as u16)),
+			(u64::from(build_upper(UpperFunction::Add as u8, DEST_X | DEST_Y, 1, 1, 2)) << 32) | u64::from(LOWER_NOP),
+			(u64::from(UPPER_NOP | UPPER_E | UPPER_I) << 32) | 0x3f80_0000,
+			(u64::from(UPPER_NOP) << 32) | u64::from(build_lower(LowerOpcode::Sq as u8, 0xf, 2, 2, 0x10)),
+		]);
+	}
+
+	#[test]
+	fn every_instruction_survives_a_round_trip() {
+		let fields = (0b1010 << 21) | (3 << 16) | (5 << 11);
+
+		for upper in upper_candidates() {
+			// Specials keep their function in fd.
+			let upper = upper | fields | if upper.get_upper_function() < 0x3c { 7 << 6 } else { 0 };
+			let text = format!("{} nop", disassemble_upper(upper));
+			let pair = assemble_pair(&text, 0).unwrap_or_else(|e| panic!("{}: {}", text, e));
+			assert_eq!(disassemble_upper((pair >> 32) as u32), disassemble_upper(upper));
+		}
+
+		for lower in lower_candidates() {
+			let lower = lower | fields | if lower.get_lower_opcode() < 0x40 { 0x7f5 } else { 0 };
+			let text = format!("nop {}", disassemble_lower(lower, 0x100));
+			let pair = assemble_pair(&text, 0x100).unwrap_or_else(|e| panic!("{}: {}", text, e));
+			assert_eq!(disassemble_lower(pair as u32, 0x100), disassemble_lower(lower, 0x100));
+		}
+	}
+
+	#[test]
+	fn reports_errors() {
+		assert!(assemble_pair("frob vf1, vf2 nop", 0).unwrap_err().contains("unknown upper"));
+		assert!(assemble_pair("add.xzy vf1, vf2, vf3 nop", 0).unwrap_err().contains("field mask"));
+		assert!(assemble_pair("addx.x vf1, vf2, vf3y nop", 0).unwrap_err().contains("does not match"));
+		assert!(assemble_pair("nop iaddiu vi1, vi0, 0x8000", 0).unwrap_err().contains("out of range"));
+		assert!(assemble_pair("nop b 0x3000", 0).unwrap_err().contains("cannot branch"));
+		assert!(assemble_pair("nop[e] move.x vf1", 0).unwrap_err().contains("operands"));
+		assert!(assemble("nop nop\nnop b nowhere").unwrap_err().starts_with("line 2: unknown label"));
+	}
+}
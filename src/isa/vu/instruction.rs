@@ -0,0 +1,208 @@
+use crate::isa::mips::Opcode;
+
+const FIELD_MASK: u32 = 0b1111;
+const REGISTER_MASK: u32 = 0b1_1111;
+const FUNCTION_MASK: u32 = 0b11_1111;
+const BROADCAST_MASK: u32 = 0b11;
+const IMMEDIATE_11_MASK: u32 = 0x7ff;
+const IMMEDIATE_24_MASK: u32 = 0xff_ffff;
+
+/// Add methods to a 32-bit upper or lower VU instruction to extract its
+/// fields, in the manner of [`mips::Instruction`](../mips/trait.Instruction.html).
+///
+/// VI registers are encoded where the VF ones are: `it` is `ft`, `is` is `fs`
+/// and `id` is `fd`.
+pub trait VuInstruction {
+	/// The field mask: `x` in bit 3, down to `w` in bit 0.
+	fn get_dest(&self) -> u8;
+
+	fn get_ft(&self) -> u8;
+	fn get_fs(&self) -> u8;
+	fn get_fd(&self) -> u8;
+
+	fn get_it(&self) -> u8;
+	fn get_is(&self) -> u8;
+	fn get_id(&self) -> u8;
+
+	/// Broadcast field, `0` for `x` to `3` for `w`.
+	fn get_bc(&self) -> u8;
+
+	/// Single fields of `fs` and `ft`, as used by `DIV` and friends.
+	fn get_fsf(&self) -> u8;
+	fn get_ftf(&self) -> u8;
+
+	fn get_upper_function(&self) -> u8;
+
+	/// The extended function of `0x3c`--`0x3f` instructions, `(fd << 2) | bc`.
+	fn get_special_function(&self) -> u8;
+
+	fn get_lower_opcode(&self) -> u8;
+
+	fn get_imm5(&self) -> i16;
+	fn get_imm11(&self) -> i16;
+	fn get_imm12(&self) -> u16;
+	fn get_imm15(&self) -> u16;
+	fn get_imm24(&self) -> u32;
+}
+
+impl VuInstruction for u32 {
+	#[inline]
+	fn get_dest(&self) -> u8 {
+		((self >> 21) & FIELD_MASK) as u8
+	}
+
+	#[inline]
+	fn get_ft(&self) -> u8 {
+		((self >> 16) & REGISTER_MASK) as u8
+	}
+
+	#[inline]
+	fn get_fs(&self) -> u8 {
+		((self >> 11) & REGISTER_MASK) as u8
+	}
+
+	#[inline]
+	fn get_fd(&self) -> u8 {
+		((self >> 6) & REGISTER_MASK) as u8
+	}
+
+	#[inline]
+	fn get_it(&self) -> u8 {
+		self.get_ft() & 0xf
+	}
+
+	#[inline]
+	fn get_is(&self) -> u8 {
+		self.get_fs() & 0xf
+	}
+
+	#[inline]
+	fn get_id(&self) -> u8 {
+		self.get_fd() & 0xf
+	}
+
+	#[inline]
+	fn get_bc(&self) -> u8 {
+		(self & BROADCAST_MASK) as u8
+	}
+
+	#[inline]
+	fn get_fsf(&self) -> u8 {
+		((self >> 21) & 0b11) as u8
+	}
+
+	#[inline]
+	fn get_ftf(&self) -> u8 {
+		((self >> 23) & 0b11) as u8
+	}
+
+	#[inline]
+	fn get_upper_function(&self) -> u8 {
+		(self & FUNCTION_MASK) as u8
+	}
+
+	#[inline]
+	fn get_special_function(&self) -> u8 {
+		(self.get_fd() << 2) | self.get_bc()
+	}
+
+	#[inline]
+	fn get_lower_opcode(&self) -> u8 {
+		(self >> 25) as u8
+	}
+
+	#[inline]
+	fn get_imm5(&self) -> i16 {
+		((self.get_fd() as i16) << 11) >> 11
+	}
+
+	#[inline]
+	fn get_imm11(&self) -> i16 {
+		(((self & IMMEDIATE_11_MASK) as i16) << 5) >> 5
+	}
+
+	#[inline]
+	fn get_imm12(&self) -> u16 {
+		((((self >> 21) & 1) << 11) | (self & IMMEDIATE_11_MASK)) as u16
+	}
+
+	#[inline]
+	fn get_imm15(&self) -> u16 {
+		((u32::from(self.get_dest()) << 11) | (self & IMMEDIATE_11_MASK)) as u16
+	}
+
+	#[inline]
+	fn get_imm24(&self) -> u32 {
+		self & IMMEDIATE_24_MASK
+	}
+}
+
+/// Build an upper instruction from its fields.
+///
+/// Functions `0x3c`--`0x3f` take the extended function in `fd` and `bc`.
+#[inline]
+pub fn build_upper(function: u8, dest: u8, ft: u8, fs: u8, fd: u8) -> u32 {
+	(u32::from(dest) << 21)
+		| (u32::from(ft) << 16)
+		| (u32::from(fs) << 11)
+		| (u32::from(fd) << 6)
+		| u32::from(function)
+}
+
+/// Build an upper `0x3c`--`0x3f` instruction from its extended function.
+#[inline]
+pub fn build_upper_special(special: u8, dest: u8, ft: u8, fs: u8) -> u32 {
+	build_upper(0x3c | (special & 0b11), dest, ft, fs, special >> 2)
+}
+
+/// Build a lower instruction with a 7-bit opcode, and an 11-bit immediate.
+#[inline]
+pub fn build_lower(opcode: u8, dest: u8, it: u8, is: u8, immediate: u16) -> u32 {
+	(u32::from(opcode) << 25)
+		| (u32::from(dest) << 21)
+		| (u32::from(it) << 16)
+		| (u32::from(is) << 11)
+		| (u32::from(immediate) & IMMEDIATE_11_MASK)
+}
+
+/// Build a lower `0x40` instruction.
+#[inline]
+pub fn build_lower_function(function: u8, dest: u8, it: u8, is: u8, id: u8) -> u32 {
+	(0x40 << 25) | build_upper(function, dest, it, is, id)
+}
+
+/// Build a lower `0x40` instruction from its extended function.
+#[inline]
+pub fn build_lower_special(special: u8, dest: u8, it: u8, is: u8) -> u32 {
+	(0x40 << 25) | build_upper_special(special, dest, it, is)
+}
+
+/// Build a COP2 macro instruction performing the upper or lower `0x40`
+/// instruction `operation`.
+#[inline]
+pub fn build_macro(operation: u32) -> u32 {
+	((Opcode::Cop2 as u32) << 26) | (1 << 25) | (operation & super::MACRO_OPERATION_MASK)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fields() {
+		// IADDIU VI3, VI2, 0x7fff: the top 4 bits of the immediate sit in dest.
+		let iaddiu = build_lower(0x08, 0xf, 3, 2, 0x7ff);
+		assert_eq!((iaddiu.get_it(), iaddiu.get_is(), iaddiu.get_imm15()), (3, 2, 0x7fff));
+
+		let branch = build_lower(0x20, 0, 0, 0, 0x7fe);
+		assert_eq!(branch.get_imm11(), -2);
+
+		// IADDI VI1, VI1, -1.
+		let iaddi = build_lower_function(0x32, 0, 1, 1, 0x1f);
+		assert_eq!(iaddi.get_imm5(), -1);
+
+		// DIV Q, VF1w, VF2y.
+		let div = build_lower_special(0x38, 0b01_11, 2, 1);
+		assert_eq!((div.get_fsf(), div.get_ftf(), div.get_special_function()), (3, 1, 0x38));
+	}
+}
@@ -0,0 +1,476 @@
+//! Vector unit microinstructions.
+//!
+//! Each 64-bit microinstruction pairs an upper (FMAC) instruction, in its
+//! high word, with a lower (integer, load/store, branch, FDIV and EFU)
+//! instruction, in its low word. Both are issued together.
+
+pub mod asm;
+mod instruction;
+
+use enum_primitive::*;
+pub use instruction::*;
+
+/// Flag bits in the upper instruction.
+pub const UPPER_I: u32 = 1 << 31;
+pub const UPPER_E: u32 = 1 << 30;
+pub const UPPER_M: u32 = 1 << 29;
+pub const UPPER_D: u32 = 1 << 28;
+pub const UPPER_T: u32 = 1 << 27;
+
+/// Upper `NOP`, as assemblers emit it.
+pub const UPPER_NOP: u32 = 0x0000_02ff;
+
+/// Lower `NOP`, encoded as a `MOVE` of no fields.
+pub const LOWER_NOP: u32 = 0x8000_033c;
+
+/// Bits of a field mask, `dest`.
+pub const DEST_X: u8 = 0b1000;
+pub const DEST_Y: u8 = 0b0100;
+pub const DEST_Z: u8 = 0b0010;
+pub const DEST_W: u8 = 0b0001;
+
+/// Upper functions whose low two bits select a broadcast field are listed by
+/// their `x` form.
+pub const BROADCAST_FUNCTIONS: u8 = 0x1c;
+
+enum_from_primitive!{
+/// Upper instructions, by their low 6 bits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpperFunction {
+	AddBc   = 0x00,
+	SubBc   = 0x04,
+	MaddBc  = 0x08,
+	MsubBc  = 0x0c,
+	MaxBc   = 0x10,
+	MiniBc  = 0x14,
+	MulBc   = 0x18,
+	MulQ    = 0x1c,
+	MaxI    = 0x1d,
+	MulI    = 0x1e,
+	MiniI   = 0x1f,
+	AddQ    = 0x20,
+	MaddQ   = 0x21,
+	AddI    = 0x22,
+	MaddI   = 0x23,
+	SubQ    = 0x24,
+	MsubQ   = 0x25,
+	SubI    = 0x26,
+	MsubI   = 0x27,
+	Add     = 0x28,
+	Madd    = 0x29,
+	Mul     = 0x2a,
+	Max     = 0x2b,
+	Sub     = 0x2c,
+	Msub    = 0x2d,
+	OpMsub  = 0x2e,
+	Mini    = 0x2f,
+	Special = 0x3c,
+}
+}
+
+enum_from_primitive!{
+/// Upper instructions with functions `0x3c`--`0x3f`, by `(fd << 2) | bc`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpperSpecial {
+	AddaBc  = 0x00,
+	SubaBc  = 0x04,
+	MaddaBc = 0x08,
+	MsubaBc = 0x0c,
+	Itof0   = 0x10,
+	Itof4   = 0x11,
+	Itof12  = 0x12,
+	Itof15  = 0x13,
+	Ftoi0   = 0x14,
+	Ftoi4   = 0x15,
+	Ftoi12  = 0x16,
+	Ftoi15  = 0x17,
+	MulaBc  = 0x18,
+	MulaQ   = 0x1c,
+	Abs     = 0x1d,
+	MulaI   = 0x1e,
+	Clip    = 0x1f,
+	AddaQ   = 0x20,
+	MaddaQ  = 0x21,
+	AddaI   = 0x22,
+	MaddaI  = 0x23,
+	SubaQ   = 0x24,
+	MsubaQ  = 0x25,
+	SubaI   = 0x26,
+	MsubaI  = 0x27,
+	Adda    = 0x28,
+	Madda   = 0x29,
+	Mula    = 0x2a,
+	Suba    = 0x2c,
+	Msuba   = 0x2d,
+	OpMula  = 0x2e,
+	Nop     = 0x2f,
+}
+}
+
+enum_from_primitive!{
+/// Lower instructions, by their top 7 bits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LowerOpcode {
+	Lq      = 0x00,
+	Sq      = 0x01,
+	Ilw     = 0x04,
+	Isw     = 0x05,
+	IaddIu  = 0x08,
+	IsubIu  = 0x09,
+	FcEq    = 0x10,
+	FcSet   = 0x11,
+	FcAnd   = 0x12,
+	FcOr    = 0x13,
+	FsEq    = 0x14,
+	FsSet   = 0x15,
+	FsAnd   = 0x16,
+	FsOr    = 0x17,
+	FmEq    = 0x18,
+	FmAnd   = 0x1a,
+	FmOr    = 0x1b,
+	FcGet   = 0x1c,
+	B       = 0x20,
+	Bal     = 0x21,
+	Jr      = 0x24,
+	JalR    = 0x25,
+	IbEq    = 0x28,
+	IbNe    = 0x29,
+	IbLtz   = 0x2c,
+	IbGtz   = 0x2d,
+	IbLez   = 0x2e,
+	IbGez   = 0x2f,
+	Operate = 0x40,
+}
+}
+
+enum_from_primitive!{
+/// Lower `0x40` instructions, by their low 6 bits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LowerFunction {
+	Iadd    = 0x30,
+	Isub    = 0x31,
+	Iaddi   = 0x32,
+	Iand    = 0x34,
+	Ior     = 0x35,
+	Special = 0x3c,
+}
+}
+
+enum_from_primitive!{
+/// Lower instructions with functions `0x3c`--`0x3f`, by `(id << 2) | (function & 3)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LowerSpecial {
+	Move    = 0x30,
+	Mr32    = 0x31,
+	Lqi     = 0x34,
+	Sqi     = 0x35,
+	Lqd     = 0x36,
+	Sqd     = 0x37,
+	Div     = 0x38,
+	Sqrt    = 0x39,
+	Rsqrt   = 0x3a,
+	WaitQ   = 0x3b,
+	Mtir    = 0x3c,
+	Mfir    = 0x3d,
+	Ilwr    = 0x3e,
+	Iswr    = 0x3f,
+	Rnext   = 0x40,
+	Rget    = 0x41,
+	Rinit   = 0x42,
+	Rxor    = 0x43,
+	Mfp     = 0x64,
+	Xtop    = 0x68,
+	Xitop   = 0x69,
+	XgKick  = 0x6c,
+	Esadd   = 0x70,
+	Ersadd  = 0x71,
+	Eleng   = 0x72,
+	Erleng  = 0x73,
+	EatanXy = 0x74,
+	EatanXz = 0x75,
+	Esum    = 0x76,
+	Esqrt   = 0x78,
+	Ersqrt  = 0x79,
+	Ercpr   = 0x7a,
+	WaitP   = 0x7b,
+	Esin    = 0x7c,
+	Eatan   = 0x7d,
+	Eexp    = 0x7e,
+}
+}
+
+/// A decoded upper instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Upper {
+	Function(UpperFunction),
+	Special(UpperSpecial),
+}
+
+impl Upper {
+	/// Decode `instruction`, ignoring its flag bits.
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let function = instruction.get_upper_function();
+
+		if function >= UpperFunction::Special as u8 {
+			let special = instruction.get_special_function();
+			let special = if special < 0x10 || (0x18..0x1c).contains(&special) { special & !0b11 } else { special };
+
+			UpperSpecial::from_u8(special).map(Upper::Special)
+		} else {
+			let function = if function < BROADCAST_FUNCTIONS { function & !0b11 } else { function };
+
+			UpperFunction::from_u8(function).map(Upper::Function)
+		}
+	}
+}
+
+/// A decoded lower instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lower {
+	Opcode(LowerOpcode),
+	Function(LowerFunction),
+	Special(LowerSpecial),
+}
+
+impl Lower {
+	pub fn decode(instruction: u32) -> Option<Self> {
+		match LowerOpcode::from_u8(instruction.get_lower_opcode())? {
+			LowerOpcode::Operate => {
+				let function = instruction.get_upper_function();
+
+				if function >= LowerFunction::Special as u8 {
+					LowerSpecial::from_u8(instruction.get_special_function()).map(Lower::Special)
+				} else {
+					LowerFunction::from_u8(function).map(Lower::Function)
+				}
+			},
+			opcode => Some(Lower::Opcode(opcode)),
+		}
+	}
+}
+
+/// Bits of a COP2 macro instruction which encode its operation as micro
+/// mode does.
+pub const MACRO_OPERATION_MASK: u32 = 0x01ff_ffff;
+
+/// The operation of a COP2 macro instruction, as the micro-mode instruction
+/// which performs it.
+///
+/// Macro instructions share the upper and lower `0x40` encodings, so that
+/// (save for `VCALLMS` and `VCALLMSR`) they are micro instructions in all but
+/// their top 7 bits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Macro {
+	Upper(u32),
+	Lower(u32),
+}
+
+impl Macro {
+	pub fn decode(instruction: u32) -> Option<Self> {
+		let operation = instruction & MACRO_OPERATION_MASK;
+		let function = operation.get_upper_function();
+
+		// Upper operations come first in both function tables.
+		let upper = if function >= UpperFunction::Special as u8 {
+			operation.get_special_function() < LowerSpecial::Move as u8
+		} else {
+			function < LowerFunction::Iadd as u8
+		};
+
+		if upper {
+			return Upper::decode(operation).map(|_| Macro::Upper(operation));
+		}
+
+		let lower = ((LowerOpcode::Operate as u32) << 25) | operation;
+		match Lower::decode(lower)? {
+			// VU0 has no EFU, and the EE has no use for XTOP or XGKICK.
+			Lower::Special(special) if special as u8 > LowerSpecial::Rxor as u8 => None,
+			_ => Some(Macro::Lower(lower)),
+		}
+	}
+}
+
+/// Resources an instruction reads and writes, as masks of the `REG_` bits.
+///
+/// A register an instruction both reads and writes is in both masks, so that
+/// the instruction waits for the register's earlier results.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Resources {
+	pub write: u64,
+	pub read: u64,
+}
+
+/// Resources tracked for hazards: the VF registers in bits 0--31, then the
+/// VI registers, then the special registers.
+pub const REG_VI_SHIFT: u32 = 32;
+pub const REG_ACC: u64 = 1 << 48;
+pub const REG_Q: u64 = 1 << 49;
+pub const REG_P: u64 = 1 << 50;
+pub const REG_I: u64 = 1 << 51;
+pub const REG_R: u64 = 1 << 52;
+pub const REG_CLIP: u64 = 1 << 53;
+pub const REG_MAC: u64 = 1 << 54;
+pub const REG_STATUS: u64 = 1 << 55;
+
+/// Written by branches and jumps. Nothing reads it.
+pub const REG_BRANCH: u64 = 1 << 56;
+
+/// Flags are read as they stand, never waited for.
+pub const REG_FLAGS: u64 = REG_CLIP | REG_MAC | REG_STATUS;
+
+/// Number of resources tracked.
+pub const RESOURCE_COUNT: usize = 57;
+
+#[inline]
+fn vf(index: u8) -> u64 {
+	// VF0 is constant, so never a hazard.
+	(1 << index) & !1
+}
+
+#[inline]
+fn vi(index: u8) -> u64 {
+	(1 << (REG_VI_SHIFT + u32::from(index & 0xf))) & !(1 << REG_VI_SHIFT)
+}
+
+/// Registers read and written by an upper instruction.
+pub fn upper_resources(instruction: u32) -> Resources {
+	use UpperFunction as F;
+	use UpperSpecial as S;
+
+	let i = instruction;
+	let (fd, fs, ft) = (vf(i.get_fd()), vf(i.get_fs()), vf(i.get_ft()));
+
+	let (write, read) = match Upper::decode(i) {
+		Some(Upper::Function(function)) => match function {
+			F::AddBc | F::SubBc | F::MulBc | F::MaxBc | F::MiniBc | F::Add | F::Sub | F::Mul | F::Max | F::Mini
+				=> (fd | REG_MAC | REG_STATUS, fs | ft),
+			F::MaddBc | F::MsubBc | F::Madd | F::Msub | F::OpMsub => (fd | REG_MAC | REG_STATUS, fs | ft | REG_ACC),
+			F::MulQ | F::AddQ | F::SubQ => (fd | REG_MAC | REG_STATUS, fs | REG_Q),
+			F::MaddQ | F::MsubQ => (fd | REG_MAC | REG_STATUS, fs | REG_Q | REG_ACC),
+			F::MaxI | F::MulI | F::MiniI | F::AddI | F::SubI => (fd | REG_MAC | REG_STATUS, fs | REG_I),
+			F::MaddI | F::MsubI => (fd | REG_MAC | REG_STATUS, fs | REG_I | REG_ACC),
+			F::Special => (0, 0),
+		},
+		Some(Upper::Special(special)) => match special {
+			S::AddaBc | S::SubaBc | S::MulaBc | S::Adda | S::Suba | S::Mula | S::OpMula
+				=> (REG_ACC | REG_MAC | REG_STATUS, fs | ft),
+			S::MaddaBc | S::MsubaBc | S::Madda | S::Msuba => (REG_ACC | REG_MAC | REG_STATUS, fs | ft | REG_ACC),
+			S::MulaQ | S::AddaQ | S::SubaQ => (REG_ACC | REG_MAC | REG_STATUS, fs | REG_Q),
+			S::MaddaQ | S::MsubaQ => (REG_ACC | REG_MAC | REG_STATUS, fs | REG_Q | REG_ACC),
+			S::MulaI | S::AddaI | S::SubaI => (REG_ACC | REG_MAC | REG_STATUS, fs | REG_I),
+			S::MaddaI | S::MsubaI => (REG_ACC | REG_MAC | REG_STATUS, fs | REG_I | REG_ACC),
+			S::Itof0 | S::Itof4 | S::Itof12 | S::Itof15 | S::Ftoi0 | S::Ftoi4 | S::Ftoi12 | S::Ftoi15 | S::Abs
+				=> (ft, fs),
+			S::Clip => (REG_CLIP, fs | ft),
+			S::Nop => (0, 0),
+		},
+		None => (0, 0),
+	};
+
+	Resources { write, read }
+}
+
+/// Registers read and written by a lower instruction.
+pub fn lower_resources(instruction: u32) -> Resources {
+	use LowerOpcode as O;
+	use LowerSpecial as S;
+
+	let i = instruction;
+	let (fs, ft) = (vf(i.get_fs()), vf(i.get_ft()));
+	let (is, it, id) = (vi(i.get_is()), vi(i.get_it()), vi(i.get_id()));
+
+	let (write, read) = match Lower::decode(i) {
+		Some(Lower::Opcode(opcode)) => match opcode {
+			O::Lq => (ft, is),
+			O::Sq => (0, fs | it),
+			O::Ilw => (it, is),
+			O::Isw => (0, is | it),
+			O::IaddIu | O::IsubIu => (it, is),
+			O::FcEq | O::FcAnd | O::FcOr => (vi(1), REG_CLIP),
+			O::FcSet => (REG_CLIP, 0),
+			O::FcGet => (it, REG_CLIP),
+			O::FsEq | O::FsAnd | O::FsOr => (it, REG_STATUS),
+			O::FsSet => (REG_STATUS, 0),
+			O::FmEq | O::FmAnd | O::FmOr => (it, is | REG_MAC),
+			O::B => (REG_BRANCH, 0),
+			O::Bal => (REG_BRANCH | it, 0),
+			O::Jr => (REG_BRANCH, is),
+			O::JalR => (REG_BRANCH | it, is),
+			O::IbEq | O::IbNe => (REG_BRANCH, is | it),
+			O::IbLtz | O::IbGtz | O::IbLez | O::IbGez => (REG_BRANCH, is),
+			O::Operate => (0, 0),
+		},
+		Some(Lower::Function(LowerFunction::Iaddi)) => (it, is),
+		Some(Lower::Function(_)) => (id, is | it),
+		Some(Lower::Special(special)) => match special {
+			S::Move | S::Mr32 => (ft, fs),
+			S::Lqi | S::Lqd => (ft | is, is),
+			S::Sqi | S::Sqd => (it, fs | it),
+			S::Div | S::Rsqrt => (REG_Q | REG_STATUS, fs | ft | REG_Q),
+			S::Sqrt => (REG_Q | REG_STATUS, ft | REG_Q),
+			S::WaitQ => (0, REG_Q),
+			S::Mtir => (it, fs),
+			S::Mfir => (ft, is),
+			S::Ilwr => (it, is),
+			S::Iswr => (0, is | it),
+			S::Rnext => (ft | REG_R, REG_R),
+			S::Rget => (ft, REG_R),
+			S::Rinit | S::Rxor => (REG_R, fs | REG_R),
+			S::Mfp => (ft, 0),
+			S::Xtop | S::Xitop => (it, 0),
+			S::XgKick => (0, is),
+			S::WaitP => (0, REG_P),
+			S::Esadd | S::Ersadd | S::Eleng | S::Erleng | S::EatanXy | S::EatanXz | S::Esum
+				| S::Esqrt | S::Ersqrt | S::Ercpr | S::Esin | S::Eatan | S::Eexp => (REG_P, fs | REG_P),
+		},
+		None => (0, 0),
+	};
+
+	Resources { write, read }
+}
+
+/// Cycles until the results of a lower instruction may be used, if it is
+/// not an ordinary single-cycle integer operation.
+pub fn lower_latency(instruction: u32) -> u64 {
+	use LowerSpecial as S;
+
+	match Lower::decode(instruction) {
+		Some(Lower::Special(special)) => match special {
+			S::Div | S::Sqrt => 7,
+			S::Rsqrt => 13,
+			S::Esadd | S::Ercpr => 11,
+			S::Esum | S::Esqrt => 12,
+			S::Ersadd | S::Eleng | S::Ersqrt => 18,
+			S::Erleng => 24,
+			S::Esin => 29,
+			S::Eexp => 44,
+			S::EatanXy | S::EatanXz | S::Eatan => 54,
+			S::Move | S::Mr32 | S::Lqi | S::Lqd | S::Mfir | S::Rnext | S::Rget | S::Mfp => FMAC_LATENCY,
+			_ => 1,
+		},
+		Some(Lower::Opcode(LowerOpcode::Lq)) => FMAC_LATENCY,
+		_ => 1,
+	}
+}
+
+/// Cycles from an FMAC instruction issuing to its result being usable.
+pub const FMAC_LATENCY: u64 = 4;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn integer_function_resources() {
+		assert_eq!(
+			lower_resources(build_lower_function(LowerFunction::Iadd as u8, 0, 1, 2, 3)),
+			Resources { write: vi(3), read: vi(1) | vi(2) },
+		);
+
+		// IADDI writes VI[it]; its "id" field holds the immediate.
+		assert_eq!(
+			lower_resources(build_lower_function(LowerFunction::Iaddi as u8, 0, 1, 2, 0x1f)),
+			Resources { write: vi(1), read: vi(2) },
+		);
+	}
+}
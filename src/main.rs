@@ -10,16 +10,301 @@ use std::{
 
 pub mod core;
 pub mod debugger;
+pub mod dmac;
+pub mod gif;
+pub mod gs;
+pub mod intc;
 pub mod memory;
+pub mod timer;
 pub mod isa;
 pub mod utils;
+pub mod vif;
+pub mod vu;
 
-use crate::core::*;
+use crate::{
+	core::*,
+	gif::Gif,
+	gs::{
+		dump::{
+			GsDump,
+			Region,
+		},
+		memory::Psm,
+		output::ImageFormat,
+		registers::{
+			privileged,
+			GsRegister,
+		},
+	},
+	vu::VectorUnit,
+};
+use enum_primitive::FromPrimitive;
+use std::{
+	cell::RefCell,
+	io::BufWriter,
+	path::Path,
+	rc::Rc,
+};
+
+/// Value of a `--name=value` argument.
+fn argument(name: &str) -> Option<String> {
+	option(&std::env::args().collect::<Vec<_>>(), name)
+}
+
+/// Value of a `--name=value` option among a subcommand's `args`.
+fn option(args: &[String], name: &str) -> Option<String> {
+	let prefix = format!("--{}=", name);
+	args.iter().find_map(|arg| arg.strip_prefix(&prefix).map(String::from))
+}
+
+/// Parse a decimal or `0x`-prefixed hexadecimal number.
+fn number(text: &str) -> Option<u32> {
+	match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+		Some(hex) => u32::from_str_radix(hex, 16).ok(),
+		None => text.parse().ok(),
+	}
+}
+
+/// Value of a `--name=value` option giving a pixel storage mode, by name or number.
+fn psm_option(args: &[String], name: &str) -> Option<Psm> {
+	option(args, name).and_then(|value| Psm::from_name(&value)
+		.or_else(|| number(&value).and_then(Psm::from_u32)))
+}
+
+fn write_gs_dump(ee_core: &EECore, path: &str) {
+	let result = File::create(path)
+		.and_then(|file| ee_core.gs.borrow().dump().write(&mut BufWriter::new(file)));
+
+	match result {
+		Ok(()) => println!("Wrote GS dump to {}.", path),
+		Err(e) => error!("Cannot write GS dump to {}: {}", path, e),
+	}
+}
+
+/// `gs-dump info <dump>` prints a dump's registers.
+/// `gs-dump render <dump> <image> [--psm= --base= --width= --rect=x,y,w,h --cbp= --cpsm= --csa=]`
+/// renders a region of its local memory, in the format given by the image's extension.
+fn gs_dump_command(args: &[String]) -> Result<(), String> {
+	let read_dump = |path: &String| File::open(path)
+		.and_then(|mut file| GsDump::read(&mut io::BufReader::new(&mut file)))
+		.map_err(|e| format!("Cannot read GS dump {}: {}", path, e));
+
+	match args {
+		[command, dump] if command == "info" => {
+			let dump = read_dump(dump)?;
+
+			for (index, &value) in dump.registers.iter().enumerate() {
+				if let (Some(register), true) = (GsRegister::from_usize(index), value != 0) {
+					println!("{:>10} {:016x}", format!("{:?}", register), value);
+				}
+			}
+
+			for (name, &value) in privileged::DISPLAY_NAMES.iter().zip(&dump.display_registers) {
+				println!("{:>10} {:016x}", name, value);
+			}
+
+			println!("{:>10} {:016x}", "CSR", dump.csr);
+			println!("{:>10} {:016x}", "IMR", dump.imr);
+			println!("{:>10} {:016x}", "BUSDIR", dump.busdir);
+			println!("{:>10} {:016x}", "SIGLBLID", dump.siglblid);
+			Ok(())
+		},
+		[command, dump, image, ..] if command == "render" => {
+			let dump = read_dump(dump)?;
+			let format = Path::new(image).extension()
+				.and_then(|extension| ImageFormat::from_extension(&extension.to_string_lossy()))
+				.ok_or_else(|| format!("Unknown image format for {}", image))?;
+
+			let number_option = |name| option(args, name).as_deref().and_then(number);
+
+			let mut region = Region::default();
+			region.psm = psm_option(args, "psm").unwrap_or(region.psm);
+			region.base = number_option("base").unwrap_or(region.base);
+			region.width = number_option("width").unwrap_or(region.width);
+			region.cbp = number_option("cbp").unwrap_or(region.cbp);
+			region.cpsm = psm_option(args, "cpsm").unwrap_or(region.cpsm);
+			region.csa = number_option("csa").unwrap_or(region.csa);
+
+			if let Some(rect) = option(args, "rect") {
+				match rect.split(',').map(number).collect::<Option<Vec<_>>>().as_deref() {
+					Some(&[x, y, columns, rows]) => {
+						region.x = x;
+						region.y = y;
+						region.columns = columns;
+						region.rows = rows;
+					},
+					_ => return Err(format!("Bad rectangle {}: expected x,y,w,h", rect)),
+				}
+			}
+
+			File::create(image)
+				.and_then(|file| dump.render(&region).write(format, &mut BufWriter::new(file)))
+				.map_err(|e| format!("Cannot write {}: {}", image, e))
+		},
+		_ => Err("Usage: gs-dump info <dump> | gs-dump render <dump> <image> [--psm=] [--base=] \
+			[--width=] [--rect=x,y,w,h] [--cbp=] [--cpsm=] [--csa=]".to_string()),
+	}
+}
+
+/// Read a whole file into the start of `into`, which it must fit.
+fn read_image(path: &str, into: &mut [u8]) -> Result<(), String> {
+	let bytes = std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+
+	if bytes.len() > into.len() {
+		return Err(format!("{} is larger than VU memory ({} bytes)", path, into.len()));
+	}
+
+	into[..bytes.len()].copy_from_slice(&bytes);
+	Ok(())
+}
+
+/// `vu-run <micro> [--data= --start= --max-cycles= --gif-capture= --dump-data=]` runs a VU1
+/// microprogram on its own, printing the registers it leaves behind.
+///
+/// Memory images are raw little-endian files, loaded at address 0.
+fn vu_run_command(args: &[String]) -> Result<(), String> {
+	let micro_path = match args {
+		[path, ..] if !path.starts_with("--") => path,
+		_ => return Err("Usage: vu-run <micro> [--data=] [--start=] [--max-cycles=] \
+			[--gif-capture=] [--dump-data=]".to_string()),
+	};
+
+	let mut vu = VectorUnit::vu1();
+
+	let mut micro = vec![0; vu.micro.len() * 8];
+	read_image(micro_path, &mut micro)?;
+	for (instruction, bytes) in vu.micro.iter_mut().zip(micro.chunks(8)) {
+		*instruction = u64::from_le_bytes(bytes.try_into().unwrap());
+	}
+
+	if let Some(path) = argument("data") {
+		let mut data = vec![0; vu.data.len() * 4];
+		read_image(&path, &mut data)?;
+		for (word, bytes) in vu.data.iter_mut().zip(data.chunks(4)) {
+			*word = u32::from_le_bytes(bytes.try_into().unwrap());
+		}
+	}
+
+	let gif = Rc::new(RefCell::new(Gif::default()));
+	if let Some(path) = argument("gif-capture") {
+		let file = File::create(&path).map_err(|e| format!("Cannot create {}: {}", path, e))?;
+		gif.borrow_mut().capture_to(BufWriter::new(file));
+	}
+	vu.connect(gif.clone());
+
+	let start = argument("start").as_deref().and_then(number).unwrap_or(0);
+	let max_cycles = argument("max-cycles").as_deref().and_then(number).unwrap_or(1_000_000);
+
+	vu.start(start);
+	let ended = vu.run(u64::from(max_cycles));
+	// Flush the capture.
+	gif.borrow_mut().set_capture(None);
+
+	if ended {
+		println!("Ended at PC {:04x} after {} cycles.", vu.pc, vu.cycles);
+	} else {
+		println!("Still running at PC {:04x} after {} cycles.", vu.pc, vu.cycles);
+	}
+
+	for (index, vf) in vu.vf.iter().enumerate().skip(1).filter(|(_, vf)| **vf != [0; 4]) {
+		let floats = vf.iter().map(|&field| f32::from_bits(field)).collect::<Vec<_>>();
+		println!("{:>5} {:08x} {:08x} {:08x} {:08x} {:?}", format!("VF{}", index), vf[0], vf[1], vf[2], vf[3], floats);
+	}
+
+	for (index, &vi) in vu.vi.iter().enumerate().skip(1).filter(|(_, vi)| **vi != 0) {
+		println!("{:>5} {:04x}", format!("VI{}", index), vi);
+	}
+
+	println!("{:>5} {:08x} {:08x} {:08x} {:08x}", "ACC", vu.acc[0], vu.acc[1], vu.acc[2], vu.acc[3]);
+	println!("{:>5} {:08x}  P {:08x}  I {:08x}  R {:08x}", "Q", vu.q, vu.p, vu.i, vu.r);
+	println!("{:>5} {:04x}  status {:04x}  clip {:06x}", "MAC", vu.mac, vu.status, vu.clip);
+
+	if let Some(path) = argument("dump-data") {
+		let bytes = vu.data.iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<_>>();
+		std::fs::write(&path, bytes).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+	}
+
+	Ok(())
+}
+
+/// `vu-as <source> <micro>` assembles a microprogram, written as `dvp-as` does,
+/// into a raw little-endian image that `vu-run` can load.
+fn vu_as_command(args: &[String]) -> Result<(), String> {
+	let (source_path, micro_path) = match args {
+		[source, micro, ..] => (source, micro),
+		_ => return Err("Usage: vu-as <source> <micro>".to_string()),
+	};
+
+	let source = std::fs::read_to_string(source_path)
+		.map_err(|e| format!("Cannot read {}: {}", source_path, e))?;
+	let program = isa::vu::asm::assemble(&source)
+		.map_err(|message| format!("{}: {}", source_path, message))?;
+
+	let bytes = program.iter().flat_map(|pair| pair.to_le_bytes()).collect::<Vec<_>>();
+	std::fs::write(micro_path, bytes).map_err(|e| format!("Cannot write {}: {}", micro_path, e))
+}
+
+/// `vu-dis <micro>` disassembles a raw little-endian microprogram image.
+fn vu_dis_command(args: &[String]) -> Result<(), String> {
+	let micro_path = match args {
+		[path, ..] => path,
+		_ => return Err("Usage: vu-dis <micro>".to_string()),
+	};
+
+	let bytes = std::fs::read(micro_path).map_err(|e| format!("Cannot read {}: {}", micro_path, e))?;
+	for (index, pair) in bytes.chunks_exact(8).enumerate() {
+		let address = index as u32 * 8;
+		let pair = u64::from_le_bytes(pair.try_into().unwrap());
+		println!("{:04x}: {:016x}  {}", address, pair, isa::vu::asm::disassemble(pair, address));
+	}
+
+	Ok(())
+}
+
+/// A subcommand, run in place of the emulator.
+type Command = fn(&[String]) -> Result<(), String>;
 
 fn main() {
 	env_logger::init();
+
+	let args = std::env::args().collect::<Vec<_>>();
+	let command: Option<Command> = match args.get(1).map(String::as_str) {
+		Some("gs-dump") => Some(gs_dump_command),
+		Some("vu-run") => Some(vu_run_command),
+		Some("vu-as") => Some(vu_as_command),
+		Some("vu-dis") => Some(vu_dis_command),
+		_ => None,
+	};
+
+	if let Some(command) = command {
+		if let Err(message) = command(&args[2..]) {
+			eprintln!("{}", message);
+			std::process::exit(1);
+		}
+		return;
+	}
 	
 	let mut ee_core = EECore::default();
+	ee_core.strict = std::env::args().any(|arg| arg == "--strict");
+
+	// Write each displayed frame to an image file, for headless regression runs.
+	if let Some(directory) = argument("dump-frames") {
+		let format = argument("frame-format")
+			.and_then(|extension| ImageFormat::from_extension(&extension))
+			.unwrap_or(ImageFormat::Png);
+
+		if let Err(e) = std::fs::create_dir_all(&directory) {
+			error!("Cannot create frame directory {}: {}", directory, e);
+		}
+
+		ee_core.gs.borrow_mut().dump_frames_to(directory, format);
+	}
+
+	// Write a GS dump once the given number of frames have been displayed.
+	let mut gs_dump = argument("dump-gs").map(|path| {
+		let frame = argument("dump-gs-frame").as_deref().and_then(number).unwrap_or(1);
+		(u64::from(frame), path)
+	});
 
 	// if let Ok(mut f) = File::open("bios/scph39001.bin") {
 	if let Ok(mut f) = File::open("bios/scph10000.bin") {
@@ -34,11 +319,83 @@ fn main() {
 
 			let stdin = io::stdin();
 			let mut s = String::new();
-			println!("Stepped execution: press enter to cycle.");
+			let mut stepping = false;
 			loop {
-				// let _ = stdin.read_line(&mut s);
+				// Strict mode halts on unknown instructions: drop into stepped execution.
+				if ee_core.halted && !stepping {
+					println!("Halted at PC {:08x}.", ee_core.pc_register);
+					println!("Stepped execution: press enter to cycle.");
+					stepping = true;
+				}
+
+				if stepping {
+					let _ = stdin.read_line(&mut s);
+				}
+
 				ee_core.cycle();
+
+				if let Some((frame, path)) = &gs_dump {
+					if ee_core.gs.borrow().frame_count() >= *frame {
+						write_gs_dump(&ee_core, path);
+						gs_dump = None;
+					}
+				}
 			}
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::gs::Gs;
+
+	/// Write a dump of a GS whose local memory holds one colour at (1, 0), returning its path.
+	fn write_dump(name: &str) -> String {
+		let mut gs = Gs::default();
+		gs.memory.write_pixel(Psm::Ct32, 0, 1, 1, 0, 0x0011_2233);
+
+		let path = std::env::temp_dir().join(format!("rs2-{}-{}.gsdump", name, std::process::id()));
+		gs.dump().write(&mut File::create(&path).unwrap()).unwrap();
+		path.to_string_lossy().into_owned()
+	}
+
+	fn args(args: &[&str]) -> Vec<String> {
+		args.iter().map(|&arg| arg.to_string()).collect()
+	}
+
+	#[test]
+	fn numbers_in_either_base() {
+		assert_eq!(number("16"), Some(16));
+		assert_eq!(number("0x10"), Some(16));
+		assert_eq!(number("0X1f"), Some(31));
+		assert_eq!(number("0x"), None);
+		assert_eq!(number("ten"), None);
+	}
+
+	#[test]
+	fn gs_dump_renders_regions() {
+		let dump = write_dump("render");
+		let image = format!("{}.ppm", dump);
+
+		gs_dump_command(&args(&["render", &dump, &image, "--psm=PSMCT32", "--width=0X1", "--rect=0,0,2,1"])).unwrap();
+		assert_eq!(std::fs::read(&image).unwrap(), b"P6\n2 1\n255\n\0\0\0\x33\x22\x11".to_vec());
+
+		gs_dump_command(&args(&["info", &dump])).unwrap();
+
+		let _ = std::fs::remove_file(&dump);
+		let _ = std::fs::remove_file(&image);
+	}
+
+	#[test]
+	fn gs_dump_rejects_bad_arguments() {
+		let dump = write_dump("bad");
+
+		assert!(gs_dump_command(&args(&["render", &dump, "out.bmp"])).is_err());
+		assert!(gs_dump_command(&args(&["render", &dump, "out.ppm", "--rect=1,2"])).is_err());
+		assert!(gs_dump_command(&args(&["info", "/nonexistent/dump"])).is_err());
+		assert!(gs_dump_command(&args(&["show", &dump])).is_err());
+
+		let _ = std::fs::remove_file(&dump);
+	}
+}
@@ -0,0 +1,225 @@
+//! Routing of physical accesses to memory-mapped devices.
+//!
+//! Devices register the physical ranges they respond to, and are handed
+//! typed reads and writes (with whatever side effects they require).
+//! Devices are shared, so that other components (e.g., the EE Core's
+//! interrupt logic) can still reach them directly.
+
+use byteorder::{
+	ByteOrder,
+	LittleEndian,
+};
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+/// A device whose registers are mapped into the physical address space.
+///
+/// Only word accesses must be handled: accesses of other widths are built
+/// from these unless overridden. Narrow writes are read-modify-write by default,
+/// so devices whose registers have write side effects should override them.
+pub trait Device {
+	fn read_u32(&mut self, p_addr: u32) -> u32;
+
+	fn write_u32(&mut self, p_addr: u32, value: u32);
+
+	fn read_u8(&mut self, p_addr: u32) -> u8 {
+		(self.read_u32(p_addr & !0b11) >> ((p_addr & 0b11) * 8)) as u8
+	}
+
+	fn read_u16(&mut self, p_addr: u32) -> u16 {
+		(self.read_u32(p_addr & !0b11) >> ((p_addr & 0b10) * 8)) as u16
+	}
+
+	fn read_u64(&mut self, p_addr: u32) -> u64 {
+		u64::from(self.read_u32(p_addr))
+			| (u64::from(self.read_u32(p_addr + 4)) << 32)
+	}
+
+	fn read_u128(&mut self, p_addr: u32) -> u128 {
+		u128::from(self.read_u64(p_addr))
+			| (u128::from(self.read_u64(p_addr + 8)) << 64)
+	}
+
+	fn write_u8(&mut self, p_addr: u32, value: u8) {
+		let word_addr = p_addr & !0b11;
+		let shift = (p_addr & 0b11) * 8;
+		let old = self.read_u32(word_addr) & !(0xff << shift);
+		self.write_u32(word_addr, old | (u32::from(value) << shift));
+	}
+
+	fn write_u16(&mut self, p_addr: u32, value: u16) {
+		let word_addr = p_addr & !0b11;
+		let shift = (p_addr & 0b10) * 8;
+		let old = self.read_u32(word_addr) & !(0xffff << shift);
+		self.write_u32(word_addr, old | (u32::from(value) << shift));
+	}
+
+	fn write_u64(&mut self, p_addr: u32, value: u64) {
+		self.write_u32(p_addr, value as u32);
+		self.write_u32(p_addr + 4, (value >> 32) as u32);
+	}
+
+	fn write_u128(&mut self, p_addr: u32, value: u128) {
+		self.write_u64(p_addr, value as u64);
+		self.write_u64(p_addr + 8, (value >> 64) as u64);
+	}
+}
+
+/// Handle to a device, shared between the bus and its other users.
+pub type SharedDevice = Rc<RefCell<dyn Device>>;
+
+struct Mapping {
+	name: &'static str,
+	start: u32,
+	end: u32,
+	device: SharedDevice,
+}
+
+/// Table of the physical ranges claimed by each device.
+#[derive(Default)]
+pub struct Bus {
+	mappings: Vec<Mapping>,
+}
+
+impl Bus {
+	/// Route the `len` bytes of physical address space from `start` to `device`.
+	pub fn map(&mut self, name: &'static str, start: u32, len: u32, device: SharedDevice) {
+		let end = start + (len - 1);
+
+		if let Some(clash) = self.mappings.iter().find(|m| m.start <= end && start <= m.end) {
+			panic!("Device {} at 0x{:08x} overlaps {} at 0x{:08x}.", name, start, clash.name, clash.start);
+		}
+
+		trace!("Mapping device {} to 0x{:08x}--0x{:08x}", name, start, end);
+		self.mappings.push(Mapping { name, start, end, device });
+	}
+
+	/// Find the device (if any) responding to physical address `p_addr`.
+	pub fn device_at(&self, p_addr: u32) -> Option<&SharedDevice> {
+		self.mappings.iter()
+			.find(|m| m.start <= p_addr && p_addr <= m.end)
+			.map(|m| &m.device)
+	}
+}
+
+/// Values which may be loaded or stored over the bus.
+pub trait BusAccess: Copy {
+	const SIZE: usize;
+
+	fn from_bytes(buf: &[u8]) -> Self;
+
+	fn to_bytes(self, buf: &mut [u8]);
+
+	fn read_from(device: &mut dyn Device, p_addr: u32) -> Self;
+
+	fn write_to(self, device: &mut dyn Device, p_addr: u32);
+}
+
+impl BusAccess for u8 {
+	const SIZE: usize = 1;
+
+	fn from_bytes(buf: &[u8]) -> Self {
+		buf[0]
+	}
+
+	fn to_bytes(self, buf: &mut [u8]) {
+		buf[0] = self;
+	}
+
+	fn read_from(device: &mut dyn Device, p_addr: u32) -> Self {
+		device.read_u8(p_addr)
+	}
+
+	fn write_to(self, device: &mut dyn Device, p_addr: u32) {
+		device.write_u8(p_addr, self);
+	}
+}
+
+macro_rules! bus_access {
+	($t: ty, $read: ident, $write: ident, $dev_read: ident, $dev_write: ident) => {
+		impl BusAccess for $t {
+			const SIZE: usize = std::mem::size_of::<$t>();
+
+			fn from_bytes(buf: &[u8]) -> Self {
+				LittleEndian::$read(buf)
+			}
+
+			fn to_bytes(self, buf: &mut [u8]) {
+				LittleEndian::$write(buf, self);
+			}
+
+			fn read_from(device: &mut dyn Device, p_addr: u32) -> Self {
+				device.$dev_read(p_addr)
+			}
+
+			fn write_to(self, device: &mut dyn Device, p_addr: u32) {
+				device.$dev_write(p_addr, self);
+			}
+		}
+	};
+}
+
+bus_access!(u16, read_u16, write_u16, read_u16, write_u16);
+bus_access!(u32, read_u32, write_u32, read_u32, write_u32);
+bus_access!(u64, read_u64, write_u64, read_u64, write_u64);
+bus_access!(u128, read_u128, write_u128, read_u128, write_u128);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Default)]
+	struct Latch {
+		words: [u32; 4],
+		writes: usize,
+	}
+
+	impl Device for Latch {
+		fn read_u32(&mut self, p_addr: u32) -> u32 {
+			self.words[((p_addr >> 2) & 0b11) as usize]
+		}
+
+		fn write_u32(&mut self, p_addr: u32, value: u32) {
+			self.writes += 1;
+			self.words[((p_addr >> 2) & 0b11) as usize] = value;
+		}
+	}
+
+	#[test]
+	fn wide_and_narrow_accesses_split_into_words() {
+		let mut latch = Latch::default();
+
+		latch.write_u128(0, 0x4444_4444_3333_3333_2222_2222_1111_1111);
+		assert_eq!(latch.writes, 4);
+		assert_eq!(latch.read_u64(8), 0x4444_4444_3333_3333);
+
+		latch.write_u8(5, 0xab);
+		assert_eq!(latch.read_u32(4), 0x2222_ab22);
+		assert_eq!(latch.read_u16(6), 0x2222);
+		assert_eq!(latch.read_u8(5), 0xab);
+	}
+
+	#[test]
+	fn devices_claim_their_ranges() {
+		let mut bus = Bus::default();
+		let latch = Rc::new(RefCell::new(Latch::default()));
+
+		bus.map("latch", 0x1000_0000, 0x10, latch.clone());
+		bus.device_at(0x1000_000c).unwrap().borrow_mut().write_u32(0x1000_000c, 7);
+
+		assert_eq!(latch.borrow().words[3], 7);
+		assert!(bus.device_at(0x0fff_ffff).is_none());
+		assert!(bus.device_at(0x1000_0010).is_none());
+	}
+
+	#[test]
+	#[should_panic]
+	fn overlapping_devices_rejected() {
+		let mut bus = Bus::default();
+
+		bus.map("a", 0x1000_0000, 0x10, Rc::new(RefCell::new(Latch::default())));
+		bus.map("b", 0x1000_0008, 0x10, Rc::new(RefCell::new(Latch::default())));
+	}
+}
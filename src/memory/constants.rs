@@ -76,4 +76,7 @@ pub const SPRAM_START: u32 = 0x7000_0000;
 pub const SPRAM_SIZE: usize = 16 * (1 << 10);
 
 /// Amount of physical memory in the PS2: 32 MiB.
-pub const PHYSICAL_MEMORY_SIZE: usize = 32 * (1 << (10 * 2));
\ No newline at end of file
+pub const PHYSICAL_MEMORY_SIZE: usize = 32 * (1 << (10 * 2));
+
+/// EE Core cycles per cycle of the (147.456MHz) bus clock.
+pub const EE_CYCLES_PER_BUS_CYCLE: u64 = 2;
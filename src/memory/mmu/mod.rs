@@ -1,8 +1,11 @@
 pub mod tlb;
 
-use crate::core::{
-	cop0::*,
-	exceptions::L1Exception,
+use crate::{
+	core::{
+		cop0::*,
+		exceptions::L1Exception,
+	},
+	memory::constants::SPRAM_START,
 };
 use tlb::Tlb;
 
@@ -69,25 +72,18 @@ pub fn page_mask_size(p_mask: u32) -> &'static str {
 	}
 }
 
-const SPR_SHIFT_AMOUNT: u32 = 12 + 2 + 1;
-
 impl Mmu {
 	pub fn translate_address(&self, v_addr: u32, load: bool) -> MmuAddress {
-		let vpn_shift_amount = page_mask_shift_amount(self.page_mask);
-
-		let vpn = v_addr >> vpn_shift_amount;
-		let vpn2 = (vpn >> 1) << (vpn_shift_amount - 12);
-		let spr_vpn2 = (v_addr >> SPR_SHIFT_AMOUNT) << (SPR_SHIFT_AMOUNT - 12 - 1);
-		let even_page = (vpn & 1) == 0;
+		let vpn2 = v_addr >> 13;
 
-		trace!("Translating {} -- VPN: {}", v_addr, vpn2);
+		trace!("Translating {} -- VPN2: {}", v_addr, vpn2);
 
-		let line = self.tlb.find_match(vpn2, spr_vpn2);
+		let line = self.tlb.find_match(vpn2, self.asid);
 
-		let out = line.and_then(|line| {
-			if !line.global && line.asid != self.asid {
-				return None;
-			}
+		let out = line.map(|line| {
+			// Each line may have its own page size.
+			let vpn_shift_amount = page_mask_shift_amount(line.mask);
+			let even_page = ((v_addr >> vpn_shift_amount) & 1) == 0;
 
 			let indiv_page = if even_page {
 				&line.even
@@ -97,21 +93,22 @@ impl Mmu {
 
 			if !indiv_page.valid {
 				if load {
-					return Some(MmuAddress::Exception(L1Exception::TlbFetchLoadInvalid(v_addr)));
+					return MmuAddress::Exception(L1Exception::TlbFetchLoadInvalid(v_addr));
 				} else {
-					return Some(MmuAddress::Exception(L1Exception::TlbStoreInvalid(v_addr)));
+					return MmuAddress::Exception(L1Exception::TlbStoreInvalid(v_addr));
 				}
 			} else if !indiv_page.dirty && !load {
-				return Some(MmuAddress::Exception(L1Exception::TlbModified(v_addr)));
+				return MmuAddress::Exception(L1Exception::TlbModified(v_addr));
 			}
 
-			Some(if line.scratchpad {
+			if line.scratchpad {
 				let offset = v_addr & (OFFSET_ALWAYS_ACTIVE_BITS | (PAGE_MASK_16KB >> 1));
 				MmuAddress::Scratchpad(offset)
 			} else {
-				let offset = v_addr & (OFFSET_ALWAYS_ACTIVE_BITS | (self.page_mask >> 1));
-				MmuAddress::Address((indiv_page.page_frame_number << vpn_shift_amount) | offset)
-			})
+				let offset_bits = OFFSET_ALWAYS_ACTIVE_BITS | (line.mask >> 1);
+				let frame = (indiv_page.page_frame_number << 12) & !offset_bits;
+				MmuAddress::Address(frame | (v_addr & offset_bits))
+			}
 		}).unwrap_or_else(|| MmuAddress::Exception(if load {
 			L1Exception::TlbFetchLoadRefill(v_addr)
 		} else {
@@ -126,10 +123,18 @@ impl Mmu {
 		out
 	}
 
+	/// Find the TLB line (if any) matching `entry_hi`'s VPN2 and ASID.
+	pub fn probe(&self, entry_hi: u32) -> Option<usize> {
+		self.tlb.probe(EntryHi::get_vpn2(entry_hi), entry_hi.get_asid())
+	}
+
+	/// Update the line at Index, ignoring indices beyond the end of the TLB.
 	pub fn write_index(&mut self, entry_hi: u32, entry_lo0: u32, entry_lo1: u32) {
-		self.tlb.lines[self.index as usize].update(self.page_mask, entry_hi, entry_lo0, entry_lo1);
+		if let Some(line) = self.tlb.lines.get_mut(self.index as usize) {
+			line.update(self.page_mask, entry_hi, entry_lo0, entry_lo1);
 
-		trace!("Put into line {}: {:?}", self.index, self.tlb.lines[self.index as usize]);
+			trace!("Put into line {}: {:?}", self.index, line);
+		}
 	}
 
 	pub fn write_random(&mut self, random_index: u32, entry_hi: u32, entry_lo0: u32, entry_lo1: u32) {
@@ -139,13 +144,34 @@ impl Mmu {
 	}
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MmuAddress {
 	Address(u32),
 	Scratchpad(u32),
 	Exception(L1Exception),
 }
 
+impl MmuAddress {
+	/// The physical address represented, as reported in `BadPAddr`.
+	pub fn raw(self) -> u32 {
+		match self {
+			MmuAddress::Address(a) => a,
+			MmuAddress::Scratchpad(a) => a + SPRAM_START,
+			MmuAddress::Exception(_) => 0,
+		}
+	}
+
+	/// The same address, rounded down to a multiple of `size` bytes.
+	pub fn aligned(self, size: usize) -> Self {
+		let mask = !(size as u32 - 1);
+		match self {
+			MmuAddress::Address(a) => MmuAddress::Address(a & mask),
+			MmuAddress::Scratchpad(a) => MmuAddress::Scratchpad(a & mask),
+			e => e,
+		}
+	}
+}
+
 impl Default for Mmu {
 	fn default() -> Self {
 		Self {
@@ -1,4 +1,5 @@
 use crate::core::cop0::{
+	self,
 	EntryHi,
 	EntryLo,
 };
@@ -41,22 +42,50 @@ pub struct TlbPageInfo {
 }
 
 impl Tlb {
-	pub fn find_match(&self, vpn_2: u32, spr_vpn_2: u32) -> Option<&TlbLine> {
-		let mut out = None;
-
-		for line in &self.lines[..] {
-			if (line.scratchpad && spr_vpn_2 == line.virtual_page_number_half)
-					|| vpn_2 == line.virtual_page_number_half {
-				out = Some(line);
-				break;
-			}
-		}
+	/// Index of the first line mapping `vpn_2` for address space `asid`, as used by TLBP.
+	pub fn probe(&self, vpn_2: u32, asid: u8) -> Option<usize> {
+		self.lines.iter().position(|line| line.matches(vpn_2, asid))
+	}
 
-		out
+	pub fn find_match(&self, vpn_2: u32, asid: u8) -> Option<&TlbLine> {
+		self.probe(vpn_2, asid).map(|i| &self.lines[i])
 	}
 }
 
 impl TlbLine {
+	/// Bits of a VPN2 which fall inside this line's pages, and so are ignored when matching.
+	#[inline]
+	pub fn vpn2_ignore_mask(&self) -> u32 {
+		if self.scratchpad {
+			PAGE_MASK_16KB >> 13
+		} else {
+			self.mask >> 13
+		}
+	}
+
+	#[inline]
+	pub fn matches(&self, vpn_2: u32, asid: u8) -> bool {
+		let keep = !self.vpn2_ignore_mask();
+
+		(vpn_2 & keep) == (self.virtual_page_number_half & keep)
+			&& (self.global || self.asid == asid)
+	}
+
+	/// Reconstructs the EntryHi value this line was written from (as read by TLBR).
+	pub fn entry_hi(&self) -> u32 {
+		cop0::entry_hi_from_parts(self.virtual_page_number_half, self.asid)
+	}
+
+	/// Reconstructs EntryLo0 (as read by TLBR).
+	pub fn entry_lo0(&self) -> u32 {
+		self.even.entry_lo(self.scratchpad, self.global)
+	}
+
+	/// Reconstructs EntryLo1 (as read by TLBR).
+	pub fn entry_lo1(&self) -> u32 {
+		self.odd.entry_lo(false, self.global)
+	}
+
 	pub fn update(&mut self, page_mask: u32, entry_hi: u32, entry_lo0: u32, entry_lo1: u32) {
 		self.mask = page_mask;
 
@@ -97,6 +126,17 @@ impl TlbPageInfo {
 		self.dirty = entry_lo.is_dirty();
 		self.valid = entry_lo.is_valid();
 	}
+
+	pub fn entry_lo(&self, scratchpad: bool, global: bool) -> u32 {
+		cop0::entry_lo_from_parts(
+			scratchpad,
+			self.page_frame_number,
+			self.cache_mode,
+			self.dirty,
+			self.valid,
+			global,
+		)
+	}
 }
 
 #[cfg(test)]
@@ -104,6 +144,7 @@ mod tests {
 	use super::*;
 	use crate::{
 		core::{
+			exceptions::L1Exception,
 			cop0::{
 				self,
 				Register,
@@ -114,6 +155,7 @@ mod tests {
 			constants::*,
 			mmu::{
 				PAGE_MASK_4KB,
+				PAGE_MASK_64KB,
 				MmuAddress,
 			},
 		},
@@ -156,4 +198,72 @@ mod tests {
 		let read_data = test_ee.read_memory(SPRAM_START, data.len());
 		assert_eq!(Some(&data[..]), read_data);
 	}
-}
\ No newline at end of file
+
+	fn map_line(test_ee: &mut EECore, index: u32, page_mask: u32, hi: u32, lo0: u32, lo1: u32) {
+		test_ee.write_cop0(Register::PageMask as u8, page_mask);
+		test_ee.write_cop0(Register::Index as u8, index);
+		test_ee.mmu.write_index(hi, lo0, lo1);
+	}
+
+	#[test]
+	fn mixed_page_sizes() {
+		let mut test_ee = EECore::new();
+
+		map_line(&mut test_ee, 1, PAGE_MASK_4KB,
+			cop0::entry_hi_from_parts(0x0001_0000 >> 13, 0),
+			cop0::entry_lo_from_parts(false, 0x100, 2, true, true, true),
+			cop0::entry_lo_from_parts(false, 0x200, 2, true, true, true),
+		);
+		map_line(&mut test_ee, 2, PAGE_MASK_64KB,
+			cop0::entry_hi_from_parts(0x0040_0000 >> 13, 0),
+			cop0::entry_lo_from_parts(false, 0x1000, 2, true, true, true),
+			cop0::entry_lo_from_parts(false, 0x2000, 2, true, true, true),
+		);
+
+		// Each line is matched and offset using its own mask, not the current PageMask.
+		assert_eq!(test_ee.mmu.translate_address(0x0001_0123, true), MmuAddress::Address(0x0010_0123));
+		assert_eq!(test_ee.mmu.translate_address(0x0001_1456, true), MmuAddress::Address(0x0020_0456));
+		assert_eq!(test_ee.mmu.translate_address(0x0040_1234, true), MmuAddress::Address(0x0100_1234));
+		assert_eq!(test_ee.mmu.translate_address(0x0041_0010, true), MmuAddress::Address(0x0200_0010));
+		assert_eq!(
+			test_ee.mmu.translate_address(0x0042_0000, true),
+			MmuAddress::Exception(L1Exception::TlbFetchLoadRefill(0x0042_0000)),
+		);
+	}
+
+	#[test]
+	fn lines_match_on_asid() {
+		let mut test_ee = EECore::new();
+
+		map_line(&mut test_ee, 3, PAGE_MASK_4KB,
+			cop0::entry_hi_from_parts(0x0080_0000 >> 13, 5),
+			cop0::entry_lo_from_parts(false, 0x300, 2, true, true, false),
+			cop0::entry_lo_from_parts(false, 0x301, 2, true, true, false),
+		);
+
+		test_ee.write_cop0(Register::EntryHi as u8, cop0::entry_hi_from_parts(0, 6));
+		assert_eq!(
+			test_ee.mmu.translate_address(0x0080_0000, false),
+			MmuAddress::Exception(L1Exception::TlbStoreRefill(0x0080_0000)),
+		);
+
+		test_ee.write_cop0(Register::EntryHi as u8, cop0::entry_hi_from_parts(0, 5));
+		assert_eq!(test_ee.mmu.translate_address(0x0080_0000, false), MmuAddress::Address(0x0030_0000));
+	}
+
+	#[test]
+	fn refill_fills_context_entryhi() {
+		let mut test_ee = EECore::new();
+		let v_addr = 0x0123_4567;
+
+		test_ee.write_cop0(Register::Context as u8, cop0::context_from_parts(0x1ab, 0));
+		test_ee.write_cop0(Register::EntryHi as u8, cop0::entry_hi_from_parts(0x7_ffff, 9));
+
+		assert_eq!(test_ee.translate_virtual_address(v_addr, true), None);
+
+		assert!(test_ee.in_exception());
+		assert_eq!(test_ee.read_cop0_direct(Register::BadVAddr as u8), v_addr);
+		assert_eq!(test_ee.read_cop0_direct(Register::Context as u8), cop0::context_from_parts(0x1ab, v_addr >> 13));
+		assert_eq!(test_ee.read_cop0_direct(Register::EntryHi as u8), cop0::entry_hi_from_parts(v_addr >> 13, 9));
+	}
+}
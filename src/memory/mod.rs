@@ -1,6 +1,11 @@
+pub mod bus;
 pub mod constants;
 pub mod mmu;
 
+use bus::{
+	Bus,
+	BusAccess,
+};
 use mmu::MmuAddress;
 
 use constants::*;
@@ -9,6 +14,9 @@ pub struct Memory {
 	bios: Vec<u8>,
 	data: Vec<u8>,
 	scratchpad: Vec<u8>,
+
+	/// Devices mapped into the physical address space.
+	pub bus: Bus,
 }
 
 impl Memory {
@@ -17,6 +25,7 @@ impl Memory {
 			bios,
 			data: vec![0; PHYSICAL_MEMORY_SIZE],
 			scratchpad: vec![0; SPRAM_SIZE],
+			bus: Default::default(),
 		}
 	}
 
@@ -25,52 +34,90 @@ impl Memory {
 	}
 
 	/// Read a slice of the desired size from the specified physical address.
-	pub fn read(&self, addr: MmuAddress, size: usize) -> &[u8] {
+	///
+	/// Only RAM, BIOS and scratchpad are backed by plain memory:
+	/// any other address (including mapped devices) gives `None`.
+	pub fn read(&self, addr: MmuAddress, size: usize) -> Option<&[u8]> {
 		use MmuAddress::*;
 		match addr {
 			Address(a) => {
 				match a {
 					0..=IO_REGISTERS_PHYSICAL => {
 						let u_addr = a as usize;
-						&self.data[u_addr..u_addr+size]
+						self.data.get(u_addr..u_addr + size)
 					},
 					BIOS_PHYSICAL..=0xFFFF_FFFF => {
 						let bios_addr = (a - BIOS_PHYSICAL) as usize;
-						&self.bios[bios_addr..bios_addr + size]
+						self.bios.get(bios_addr..bios_addr + size)
 					}
-					_ => &self.data[..],
+					_ => None,
 				}
 			},
-			Scratchpad(a) => &self.scratchpad[a as usize..a as usize + size],
+			Scratchpad(a) => self.scratchpad.get(a as usize..a as usize + size),
 			_ => unreachable!(),
 		}
 	}
 
 	/// Read a slice of the desired size from the specified physical address.
-	pub fn read_mut(&mut self, addr: MmuAddress, size: usize) -> &mut [u8] {
+	///
+	/// Only RAM, BIOS and scratchpad are backed by plain memory:
+	/// any other address (including mapped devices) gives `None`.
+	pub fn read_mut(&mut self, addr: MmuAddress, size: usize) -> Option<&mut [u8]> {
 		use MmuAddress::*;
 		match addr {
 			Address(a) => {
 				match a {
 					0..=IO_REGISTERS_PHYSICAL => {
 						let u_addr = a as usize;
-						&mut self.data[u_addr..u_addr+size]
+						self.data.get_mut(u_addr..u_addr + size)
 					},
 					BIOS_PHYSICAL..=0xFFFF_FFFF => {
 						let bios_addr = (a - BIOS_PHYSICAL) as usize;
-						&mut self.bios[bios_addr..bios_addr + size]
+						self.bios.get_mut(bios_addr..bios_addr + size)
 					}
-					_ => &mut self.data[..],
+					_ => None,
 				}
 			},
-			Scratchpad(a) => &mut self.scratchpad[a as usize..a as usize + size],
+			Scratchpad(a) => self.scratchpad.get_mut(a as usize..a as usize + size),
 			_ => unreachable!(),
 		}
 	}
 
-	pub fn write(&mut self, addr: MmuAddress, data: &[u8]) {
-		let dest = self.read_mut(addr, data.len());
+	pub fn write(&mut self, addr: MmuAddress, data: &[u8]) -> Option<()> {
+		let dest = self.read_mut(addr, data.len())?;
 		dest.copy_from_slice(data);
+		Some(())
+	}
+
+	/// Load a value from the specified physical address, via any device mapped there.
+	///
+	/// Returns `None` if nothing responds to the address.
+	pub fn load<T: BusAccess>(&mut self, addr: MmuAddress) -> Option<T> {
+		if let Some(buf) = self.read(addr, T::SIZE) {
+			return Some(T::from_bytes(buf));
+		}
+
+		match addr {
+			MmuAddress::Address(a) => self.bus.device_at(a)
+				.map(|device| T::read_from(&mut *device.borrow_mut(), a)),
+			_ => None,
+		}
+	}
+
+	/// Store a value to the specified physical address, via any device mapped there.
+	///
+	/// Returns `None` if nothing responds to the address.
+	pub fn store<T: BusAccess>(&mut self, addr: MmuAddress, value: T) -> Option<()> {
+		if let Some(buf) = self.read_mut(addr, T::SIZE) {
+			value.to_bytes(buf);
+			return Some(());
+		}
+
+		match addr {
+			MmuAddress::Address(a) => self.bus.device_at(a)
+				.map(|device| value.write_to(&mut *device.borrow_mut(), a)),
+			_ => None,
+		}
 	}
 }
 
@@ -87,14 +134,14 @@ mod tests {
 	fn low_physical_address_writes_to_ram() {
 		let mut test_ee = EECore::default();
 
-		let space = test_ee.memory.read_mut(MmuAddress::Address(0), 4);
+		let space = test_ee.memory.read_mut(MmuAddress::Address(0), 4).unwrap();
 		let value = 0xDEAD_BEEF;
 
 		LittleEndian::write_u32(space, value);
 
 		assert_eq!(LittleEndian::read_u32(&test_ee.memory.data[..]), value);
 
-		let space = test_ee.memory.read_mut(MmuAddress::Address(512), 4);
+		let space = test_ee.memory.read_mut(MmuAddress::Address(512), 4).unwrap();
 		let value = 0xDEAD_BEEF;
 
 		LittleEndian::write_u32(space, value);
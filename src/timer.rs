@@ -0,0 +1,533 @@
+//! The EE's four general-purpose timers (T0--T3).
+//!
+//! Each timer is a 16-bit counter driven by the bus clock (half the EE Core clock),
+//! one of its prescaled variants, or HBLANK. Counting can be gated by HBLANK or
+//! VBLANK, and reaching `COMP` or overflowing can raise an interrupt in the INTC.
+//!
+//! Register layout and behaviour follow https://psi-rockin.github.io/ps2tek/.
+
+use bitflags::bitflags;
+use crate::{
+	intc::{
+		Intc,
+		Interrupt,
+	},
+	memory::{
+		bus::Device,
+		constants::EE_CYCLES_PER_BUS_CYCLE,
+	},
+};
+
+/// Physical address of T0's register block.
+pub const TIMERS_PHYSICAL: u32 = 0x1000_0000;
+
+/// Length of the register blocks of all four timers.
+pub const TIMERS_SIZE: u32 = 0x1830;
+
+/// Distance between each timer's register blocks.
+pub const TIMER_STRIDE: u32 = 0x800;
+
+pub const TIMER_COUNT: usize = 4;
+
+/// Offset of each register within a timer's block.
+pub const COUNT_OFFSET: u32 = 0x00;
+pub const MODE_OFFSET: u32 = 0x10;
+pub const COMP_OFFSET: u32 = 0x20;
+pub const HOLD_OFFSET: u32 = 0x30;
+
+bitflags!{
+/// Flags contained within a timer's `MODE` register.
+pub struct TimerMode: u32 {
+	const CLOCK_B0           = 0b0000_0000_0001;
+	const CLOCK_B1           = 0b0000_0000_0010;
+
+	/// 2-bit field selecting the clock source:
+	/// * `00` => bus clock.
+	/// * `01` => bus clock / 16.
+	/// * `10` => bus clock / 256.
+	/// * `11` => HBLANK.
+	const CLOCK_SELECT = Self::CLOCK_B0.bits
+		| Self::CLOCK_B1.bits;
+
+	/// Enable (`1`) gating of the counter.
+	const GATE_ENABLE        = 0b0000_0000_0100;
+
+	/// Gate on HBLANK (`0`) or VBLANK (`1`).
+	const GATE_VBLANK        = 0b0000_0000_1000;
+
+	const GATE_MODE_B0       = 0b0000_0001_0000;
+	const GATE_MODE_B1       = 0b0000_0010_0000;
+
+	/// 2-bit field determining the effect of the gate signal:
+	/// * `00` => count only while the gate signal is low.
+	/// * `01` => reset the counter on the gate's rising edge.
+	/// * `10` => reset the counter on the gate's falling edge.
+	/// * `11` => reset the counter on both edges.
+	const GATE_MODE = Self::GATE_MODE_B0.bits
+		| Self::GATE_MODE_B1.bits;
+
+	/// Clear the counter when it reaches `COMP`.
+	const ZERO_RETURN        = 0b0000_0100_0000;
+
+	/// Enable (`1`) counting.
+	const COUNT_ENABLE       = 0b0000_1000_0000;
+
+	/// Raise an interrupt when the counter reaches `COMP`.
+	const COMPARE_INTERRUPT  = 0b0001_0000_0000;
+
+	/// Raise an interrupt when the counter overflows.
+	const OVERFLOW_INTERRUPT = 0b0010_0000_0000;
+
+	/// Set when the counter reaches `COMP`. Cleared by writing `1`.
+	const COMPARE_FLAG       = 0b0100_0000_0000;
+
+	/// Set when the counter overflows. Cleared by writing `1`.
+	const OVERFLOW_FLAG      = 0b1000_0000_0000;
+
+	const FLAGS = Self::COMPARE_FLAG.bits
+		| Self::OVERFLOW_FLAG.bits;
+}
+}
+
+/// Clock sources selectable by [`TimerMode::CLOCK_SELECT`](struct.TimerMode.html#associatedconstant.CLOCK_SELECT).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClockSource {
+	Bus,
+	Bus16,
+	Bus256,
+	HBlank,
+}
+
+impl TimerMode {
+	#[inline]
+	pub fn clock_source(self) -> ClockSource {
+		match (self & Self::CLOCK_SELECT).bits() {
+			0 => ClockSource::Bus,
+			1 => ClockSource::Bus16,
+			2 => ClockSource::Bus256,
+			_ => ClockSource::HBlank,
+		}
+	}
+
+	#[inline]
+	pub fn gate_mode(self) -> u32 {
+		(self & Self::GATE_MODE).bits() >> 4
+	}
+}
+
+impl ClockSource {
+	/// EE Core cycles per count, or `None` if not driven by the bus clock.
+	pub fn period(self) -> Option<u64> {
+		match self {
+			ClockSource::Bus => Some(EE_CYCLES_PER_BUS_CYCLE),
+			ClockSource::Bus16 => Some(16 * EE_CYCLES_PER_BUS_CYCLE),
+			ClockSource::Bus256 => Some(256 * EE_CYCLES_PER_BUS_CYCLE),
+			ClockSource::HBlank => None,
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Timer {
+	pub count: u16,
+	pub mode: TimerMode,
+	pub comp: u16,
+	pub hold: u16,
+
+	/// EE Core cycles elapsed towards the next count.
+	elapsed: u64,
+
+	interrupt: Interrupt,
+}
+
+impl Timer {
+	fn new(interrupt: Interrupt) -> Self {
+		Self {
+			count: 0,
+			mode: TimerMode::empty(),
+			comp: 0,
+			hold: 0,
+			elapsed: 0,
+			interrupt,
+		}
+	}
+
+	/// Whether the gate currently holds the counter still, given the level of its gate signal.
+	/// The level of whichever blanking signal gates this timer.
+	fn gate_level(&self, hblank: bool, vblank: bool) -> bool {
+		if self.mode.contains(TimerMode::GATE_VBLANK) { vblank } else { hblank }
+	}
+
+	fn gated(&self, gate_level: bool) -> bool {
+		self.mode.contains(TimerMode::GATE_ENABLE)
+			&& self.mode.gate_mode() == 0
+			&& gate_level
+	}
+
+	fn set_flag(&mut self, flag: TimerMode, enable: TimerMode, intc: &mut Intc) {
+		// Interrupts are only raised when the flag goes from `0` to `1`.
+		if !self.mode.contains(flag) && self.mode.contains(enable) {
+			intc.raise(self.interrupt);
+		}
+
+		self.mode.insert(flag);
+	}
+
+	fn increment(&mut self, intc: &mut Intc) {
+		let (count, overflowed) = self.count.overflowing_add(1);
+		self.count = count;
+
+		if self.count == self.comp {
+			self.set_flag(TimerMode::COMPARE_FLAG, TimerMode::COMPARE_INTERRUPT, intc);
+
+			if self.mode.contains(TimerMode::ZERO_RETURN) {
+				self.count = 0;
+			}
+		}
+
+		if overflowed {
+			self.set_flag(TimerMode::OVERFLOW_FLAG, TimerMode::OVERFLOW_INTERRUPT, intc);
+		}
+	}
+
+	fn step(&mut self, ee_cycles: u64, gate_level: bool, intc: &mut Intc) {
+		if !self.mode.contains(TimerMode::COUNT_ENABLE) || self.gated(gate_level) {
+			return;
+		}
+
+		if let Some(period) = self.mode.clock_source().period() {
+			self.elapsed += ee_cycles;
+
+			while self.elapsed >= period {
+				self.elapsed -= period;
+				self.increment(intc);
+			}
+		}
+	}
+
+	fn gate_edge(&mut self, rising: bool) {
+		if !self.mode.contains(TimerMode::GATE_ENABLE) {
+			return;
+		}
+
+		let reset = match self.mode.gate_mode() {
+			1 => rising,
+			2 => !rising,
+			3 => true,
+			_ => false,
+		};
+
+		if reset {
+			self.count = 0;
+			self.elapsed = 0;
+		}
+	}
+
+	fn read(&self, offset: u32) -> u32 {
+		match offset {
+			COUNT_OFFSET => u32::from(self.count),
+			MODE_OFFSET => self.mode.bits(),
+			COMP_OFFSET => u32::from(self.comp),
+			HOLD_OFFSET => u32::from(self.hold),
+			_ => 0,
+		}
+	}
+
+	fn write(&mut self, offset: u32, value: u32) {
+		match offset {
+			COUNT_OFFSET => {
+				self.count = value as u16;
+				self.elapsed = 0;
+			},
+			MODE_OFFSET => {
+				let written = TimerMode::from_bits_truncate(value);
+				let flags = self.mode & TimerMode::FLAGS & !written;
+				self.mode = (written & !TimerMode::FLAGS) | flags;
+			},
+			COMP_OFFSET => self.comp = value as u16,
+			HOLD_OFFSET => self.hold = value as u16,
+			_ => {},
+		}
+	}
+}
+
+/// All four EE timers, which share the bus clock and blanking signals.
+#[derive(Clone, Debug)]
+pub struct Timers {
+	pub timers: [Timer; TIMER_COUNT],
+
+	hblank: bool,
+	vblank: bool,
+}
+
+impl Default for Timers {
+	fn default() -> Self {
+		Self {
+			timers: [
+				Timer::new(Interrupt::Timer0),
+				Timer::new(Interrupt::Timer1),
+				Timer::new(Interrupt::Timer2),
+				Timer::new(Interrupt::Timer3),
+			],
+			hblank: false,
+			vblank: false,
+		}
+	}
+}
+
+impl Timers {
+	/// Advance all timers by `ee_cycles` cycles of the EE Core clock.
+	pub fn step(&mut self, ee_cycles: u64, intc: &mut Intc) {
+		let (hblank, vblank) = (self.hblank, self.vblank);
+
+		for timer in self.timers.iter_mut() {
+			let gate_level = timer.gate_level(hblank, vblank);
+			timer.step(ee_cycles, gate_level, intc);
+		}
+	}
+
+	/// Drive the HBLANK signal, counting any timers clocked by HBLANK on its rising edge.
+	pub fn set_hblank(&mut self, level: bool, intc: &mut Intc) {
+		if level == self.hblank {
+			return;
+		}
+		self.hblank = level;
+		let vblank = self.vblank;
+
+		for timer in self.timers.iter_mut() {
+			if !timer.mode.contains(TimerMode::GATE_VBLANK) {
+				timer.gate_edge(level);
+			}
+
+			let counts = level
+				&& timer.mode.contains(TimerMode::COUNT_ENABLE)
+				&& timer.mode.clock_source() == ClockSource::HBlank
+				&& !timer.gated(timer.gate_level(level, vblank));
+
+			if counts {
+				timer.increment(intc);
+			}
+		}
+	}
+
+	/// Drive the VBLANK signal.
+	pub fn set_vblank(&mut self, level: bool) {
+		if level == self.vblank {
+			return;
+		}
+		self.vblank = level;
+
+		for timer in self.timers.iter_mut() {
+			if timer.mode.contains(TimerMode::GATE_VBLANK) {
+				timer.gate_edge(level);
+			}
+		}
+	}
+
+	/// Latch T0 and T1's counters into their `HOLD` registers, as an SBUS interrupt does.
+	pub fn hold(&mut self) {
+		for timer in self.timers[..2].iter_mut() {
+			timer.hold = timer.count;
+		}
+	}
+
+	fn locate(p_addr: u32) -> (usize, u32) {
+		let offset = p_addr - TIMERS_PHYSICAL;
+		((offset / TIMER_STRIDE) as usize, offset % TIMER_STRIDE)
+	}
+}
+
+impl Device for Timers {
+	fn read_u32(&mut self, p_addr: u32) -> u32 {
+		let (index, offset) = Self::locate(p_addr & !0b11);
+
+		match (index, offset) {
+			(2..=3, HOLD_OFFSET) => 0,
+			_ => self.timers[index].read(offset),
+		}
+	}
+
+	fn write_u32(&mut self, p_addr: u32, value: u32) {
+		let (index, offset) = Self::locate(p_addr & !0b11);
+
+		match (index, offset) {
+			(2..=3, HOLD_OFFSET) => {},
+			_ => self.timers[index].write(offset, value),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn timer_addr(index: u32, offset: u32) -> u32 {
+		TIMERS_PHYSICAL + index * TIMER_STRIDE + offset
+	}
+
+	fn start(timers: &mut Timers, index: u32, mode: TimerMode) {
+		timers.write_u32(timer_addr(index, MODE_OFFSET), (mode | TimerMode::COUNT_ENABLE).bits());
+	}
+
+	#[test]
+	fn bus_clock_prescalers() {
+		let mut timers = Timers::default();
+		let mut intc = Intc::default();
+
+		start(&mut timers, 0, TimerMode::empty());
+		start(&mut timers, 1, TimerMode::CLOCK_B0);
+		start(&mut timers, 2, TimerMode::CLOCK_B1);
+
+		timers.step(1024, &mut intc);
+
+		assert_eq!(timers.read_u32(timer_addr(0, COUNT_OFFSET)), 512);
+		assert_eq!(timers.read_u32(timer_addr(1, COUNT_OFFSET)), 32);
+		assert_eq!(timers.read_u32(timer_addr(2, COUNT_OFFSET)), 2);
+
+		// Partial periods carry over between steps.
+		timers.step(511, &mut intc);
+		assert_eq!(timers.read_u32(timer_addr(2, COUNT_OFFSET)), 2);
+		timers.step(1, &mut intc);
+		assert_eq!(timers.read_u32(timer_addr(2, COUNT_OFFSET)), 3);
+	}
+
+	#[test]
+	fn disabled_timer_holds() {
+		let mut timers = Timers::default();
+		let mut intc = Intc::default();
+
+		timers.write_u32(timer_addr(3, COUNT_OFFSET), 40);
+		timers.step(100, &mut intc);
+
+		assert_eq!(timers.read_u32(timer_addr(3, COUNT_OFFSET)), 40);
+	}
+
+	#[test]
+	fn compare_interrupt_and_zero_return() {
+		let mut timers = Timers::default();
+		let mut intc = Intc::default();
+
+		timers.write_u32(timer_addr(1, COMP_OFFSET), 10);
+		start(&mut timers, 1, TimerMode::COMPARE_INTERRUPT | TimerMode::ZERO_RETURN);
+
+		timers.step(20, &mut intc);
+
+		let mode = TimerMode::from_bits_truncate(timers.read_u32(timer_addr(1, MODE_OFFSET)));
+		assert!(mode.contains(TimerMode::COMPARE_FLAG));
+		assert_eq!(timers.read_u32(timer_addr(1, COUNT_OFFSET)), 0);
+		assert_eq!(intc.stat, 1 << (Interrupt::Timer1 as u32));
+
+		// No new interrupt while the flag is still set.
+		intc.stat = 0;
+		timers.step(20, &mut intc);
+		assert_eq!(intc.stat, 0);
+
+		// Acknowledge, keeping the rest of the mode.
+		timers.write_u32(timer_addr(1, MODE_OFFSET), (mode | TimerMode::COMPARE_FLAG).bits());
+		let mode = TimerMode::from_bits_truncate(timers.read_u32(timer_addr(1, MODE_OFFSET)));
+		assert!(!mode.contains(TimerMode::COMPARE_FLAG));
+		assert!(mode.contains(TimerMode::COUNT_ENABLE));
+
+		timers.step(20, &mut intc);
+		assert_eq!(intc.stat, 1 << (Interrupt::Timer1 as u32));
+	}
+
+	#[test]
+	fn overflow_interrupt() {
+		let mut timers = Timers::default();
+		let mut intc = Intc::default();
+
+		timers.write_u32(timer_addr(2, COMP_OFFSET), 0x1234);
+		timers.write_u32(timer_addr(2, COUNT_OFFSET), 0xfffe);
+		start(&mut timers, 2, TimerMode::OVERFLOW_INTERRUPT);
+
+		timers.step(2, &mut intc);
+		assert_eq!(intc.stat, 0);
+
+		timers.step(2, &mut intc);
+		assert_eq!(timers.read_u32(timer_addr(2, COUNT_OFFSET)), 0);
+		assert_eq!(intc.stat, 1 << (Interrupt::Timer2 as u32));
+
+		let mode = TimerMode::from_bits_truncate(timers.read_u32(timer_addr(2, MODE_OFFSET)));
+		assert!(mode.contains(TimerMode::OVERFLOW_FLAG));
+		assert!(!mode.contains(TimerMode::COMPARE_FLAG));
+	}
+
+	#[test]
+	fn hblank_clock_source() {
+		let mut timers = Timers::default();
+		let mut intc = Intc::default();
+
+		start(&mut timers, 0, TimerMode::CLOCK_SELECT);
+
+		for _ in 0..3 {
+			timers.set_hblank(true, &mut intc);
+			timers.step(1000, &mut intc);
+			timers.set_hblank(false, &mut intc);
+		}
+
+		assert_eq!(timers.read_u32(timer_addr(0, COUNT_OFFSET)), 3);
+	}
+
+	#[test]
+	fn hblank_clock_source_respects_gate() {
+		let mut timers = Timers::default();
+		let mut intc = Intc::default();
+
+		// Count HBLANKs only while VBLANK is low.
+		start(&mut timers, 0, TimerMode::CLOCK_SELECT | TimerMode::GATE_ENABLE | TimerMode::GATE_VBLANK);
+
+		timers.set_hblank(true, &mut intc);
+		timers.set_hblank(false, &mut intc);
+
+		timers.set_vblank(true);
+		for _ in 0..3 {
+			timers.set_hblank(true, &mut intc);
+			timers.set_hblank(false, &mut intc);
+		}
+
+		assert_eq!(timers.read_u32(timer_addr(0, COUNT_OFFSET)), 1);
+	}
+
+	#[test]
+	fn gate_modes() {
+		let mut timers = Timers::default();
+		let mut intc = Intc::default();
+
+		// T0: count while HBLANK is low.
+		start(&mut timers, 0, TimerMode::GATE_ENABLE);
+		// T1: reset on VBLANK rising edge.
+		start(&mut timers, 1, TimerMode::GATE_ENABLE | TimerMode::GATE_VBLANK | TimerMode::GATE_MODE_B0);
+		// T2: reset on VBLANK falling edge.
+		start(&mut timers, 2, TimerMode::GATE_ENABLE | TimerMode::GATE_VBLANK | TimerMode::GATE_MODE_B1);
+
+		timers.step(20, &mut intc);
+		timers.set_hblank(true, &mut intc);
+		timers.set_vblank(true);
+		timers.step(20, &mut intc);
+
+		assert_eq!(timers.read_u32(timer_addr(0, COUNT_OFFSET)), 10);
+		assert_eq!(timers.read_u32(timer_addr(1, COUNT_OFFSET)), 10);
+		assert_eq!(timers.read_u32(timer_addr(2, COUNT_OFFSET)), 20);
+
+		timers.set_hblank(false, &mut intc);
+		timers.set_vblank(false);
+		timers.step(20, &mut intc);
+
+		assert_eq!(timers.read_u32(timer_addr(0, COUNT_OFFSET)), 20);
+		assert_eq!(timers.read_u32(timer_addr(1, COUNT_OFFSET)), 20);
+		assert_eq!(timers.read_u32(timer_addr(2, COUNT_OFFSET)), 10);
+	}
+
+	#[test]
+	fn hold_only_on_t0_t1() {
+		let mut timers = Timers::default();
+
+		timers.write_u32(timer_addr(0, COUNT_OFFSET), 5);
+		timers.write_u32(timer_addr(2, COUNT_OFFSET), 6);
+		timers.hold();
+
+		assert_eq!(timers.read_u32(timer_addr(0, HOLD_OFFSET)), 5);
+		assert_eq!(timers.read_u32(timer_addr(2, HOLD_OFFSET)), 0);
+	}
+}
@@ -0,0 +1,1047 @@
+//! The VIFs (VPU interfaces), which feed the vector units.
+//!
+//! VIF0 and VIF1 are fed by DMAC channels 0 and 1 (or by stores to their
+//! FIFOs) with a stream of 32-bit words: VIFcodes, each followed by any data
+//! it takes. VIFcodes set up the VIF's registers, unpack data into VU data
+//! memory, upload microprograms and start them, and (on VIF1 only) pass
+//! packets to the GIF over PATH2.
+//!
+//! A VIFcode which must wait (e.g., `FLUSHE` while the VU is running) holds
+//! up the rest of the stream, and the FIFO stops accepting DMA once full.
+//!
+//! Register layout and behaviour follow https://psi-rockin.github.io/ps2tek/.
+
+pub mod unpack;
+
+use bitflags::bitflags;
+use crate::{
+	dmac::DmaPeripheral,
+	gif::{
+		Gif,
+		GifPath,
+	},
+	memory::bus::Device,
+	vu::VectorUnit,
+};
+use enum_primitive::*;
+use std::{
+	cell::RefCell,
+	collections::VecDeque,
+	mem,
+	rc::Rc,
+};
+use unpack::*;
+
+pub const VIF0_PHYSICAL: u32 = 0x1000_3800;
+pub const VIF1_PHYSICAL: u32 = 0x1000_3c00;
+
+/// Length of each VIF's register block, up to and including `C3`.
+pub const VIF_SIZE: u32 = 0x180;
+
+/// Physical addresses of the FIFOs, which only take quadword accesses.
+pub const VIF0_FIFO_PHYSICAL: u32 = 0x1000_4000;
+pub const VIF1_FIFO_PHYSICAL: u32 = 0x1000_5000;
+
+/// Length of each FIFO's window.
+pub const VIF_FIFO_SIZE: u32 = 0x10;
+
+/// Offset of each register within a VIF's block.
+pub const STAT_OFFSET: u32 = 0x00;
+pub const FBRST_OFFSET: u32 = 0x10;
+pub const ERR_OFFSET: u32 = 0x20;
+pub const MARK_OFFSET: u32 = 0x30;
+pub const CYCLE_OFFSET: u32 = 0x40;
+pub const MODE_OFFSET: u32 = 0x50;
+pub const NUM_OFFSET: u32 = 0x60;
+pub const MASK_OFFSET: u32 = 0x70;
+pub const CODE_OFFSET: u32 = 0x80;
+pub const ITOPS_OFFSET: u32 = 0x90;
+pub const BASE_OFFSET: u32 = 0xa0;
+pub const OFST_OFFSET: u32 = 0xb0;
+pub const TOPS_OFFSET: u32 = 0xc0;
+pub const ITOP_OFFSET: u32 = 0xd0;
+pub const TOP_OFFSET: u32 = 0xe0;
+pub const R0_OFFSET: u32 = 0x100;
+pub const C0_OFFSET: u32 = 0x140;
+
+/// Capacity of each FIFO, in words.
+const VIF0_FIFO_WORDS: usize = 8 * 4;
+const VIF1_FIFO_WORDS: usize = 16 * 4;
+
+/// Bit of `STAT` at which the FIFO's quadword count starts.
+const FQC_SHIFT: u32 = 24;
+
+/// Mask of the 10-bit addresses used by `BASE`, `OFFSET`, `ITOP` and `UNPACK`.
+const ADDRESS_MASK: u32 = 0x3ff;
+
+bitflags!{
+/// Flags contained within `VIFn_STAT`.
+pub struct VifStat: u32 {
+	const VPS_B0 = 0b0000_0000_0000_0000_0001;
+	const VPS_B1 = 0b0000_0000_0000_0000_0010;
+
+	/// 2-bit field holding what the VIF is doing:
+	/// * `00` => idle.
+	/// * `01` => waiting for data.
+	/// * `10` => decoding a VIFcode.
+	/// * `11` => decoding data.
+	const VPS = Self::VPS_B0.bits
+		| Self::VPS_B1.bits;
+
+	/// The VU is executing a microprogram.
+	const VEW = 0b0000_0000_0000_0000_0100;
+
+	/// Waiting for the GIF (VIF1 only).
+	const VGW = 0b0000_0000_0000_0000_1000;
+
+	/// A `MARK` VIFcode has been processed since `VIFn_MARK` was last written.
+	const MRK = 0b0000_0000_0000_0100_0000;
+
+	/// Double buffer flag: which buffer `TOPS` points at (VIF1 only).
+	const DBF = 0b0000_0000_0000_1000_0000;
+
+	/// Stalled by `FBRST.STP`.
+	const VSS = 0b0000_0000_0001_0000_0000;
+
+	/// Stalled by `FBRST.FBK`.
+	const VFS = 0b0000_0000_0010_0000_0000;
+
+	/// Stalled after a VIFcode with its interrupt bit set.
+	const VIS = 0b0000_0000_0100_0000_0000;
+
+	/// Interrupt raised by a VIFcode.
+	const INT = 0b0000_0000_1000_0000_0000;
+
+	/// DMAtag mismatch error.
+	const ER0 = 0b0000_0001_0000_0000_0000;
+
+	/// Undefined VIFcode error.
+	const ER1 = 0b0000_0010_0000_0000_0000;
+
+	const STALLS = Self::VSS.bits
+		| Self::VFS.bits
+		| Self::VIS.bits
+		| Self::ER0.bits
+		| Self::ER1.bits;
+
+	/// FIFO direction: from the VIF to memory (VIF1 only, writable).
+	const FDR = 0b1000_0000_0000_0000_0000_0000;
+}
+}
+
+bitflags!{
+/// Flags contained within `VIFn_FBRST` (write-only).
+pub struct VifFbrst: u32 {
+	/// Reset the VIF, discarding the FIFO and any VIFcode in progress.
+	const RST = 0b0001;
+
+	/// Force break: stall at once.
+	const FBK = 0b0010;
+
+	/// Stall once the current VIFcode is done.
+	const STP = 0b0100;
+
+	/// Cancel stalls, and clear `INT`, `ER0` and `ER1`.
+	const STC = 0b1000;
+}
+}
+
+bitflags!{
+/// Flags contained within `VIFn_ERR`.
+pub struct VifErr: u32 {
+	/// Mask the interrupt bit of VIFcodes.
+	const MII = 0b001;
+
+	/// Ignore DMAtag mismatch errors.
+	const ME0 = 0b010;
+
+	/// Ignore undefined VIFcodes.
+	const ME1 = 0b100;
+}
+}
+
+impl Default for VifStat {
+	fn default() -> Self {
+		Self::empty()
+	}
+}
+
+impl Default for VifErr {
+	fn default() -> Self {
+		Self::empty()
+	}
+}
+
+enum_from_primitive!{
+/// VIFcode commands, other than `UNPACK` (`0x60`--`0x7f`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VifCommand {
+	Nop      = 0x00,
+	StCycl   = 0x01,
+	Offset   = 0x02,
+	Base     = 0x03,
+	Itop     = 0x04,
+	StMod    = 0x05,
+	MskPath3 = 0x06,
+	Mark     = 0x07,
+	FlushE   = 0x10,
+	Flush    = 0x11,
+	FlushA   = 0x13,
+	MsCal    = 0x14,
+	MsCalF   = 0x15,
+	MsCnt    = 0x17,
+	StMask   = 0x20,
+	StRow    = 0x30,
+	StCol    = 0x31,
+	Mpg      = 0x4a,
+	Direct   = 0x50,
+	DirectHl = 0x51,
+}
+}
+
+/// Set in a command to mark it `UNPACK`.
+const UNPACK_COMMAND: u8 = 0x60;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VifCode {
+	pub immediate: u16,
+	pub num: u8,
+	pub command: u8,
+
+	/// Raise an interrupt, and stall, once done.
+	pub interrupt: bool,
+}
+
+impl From<u32> for VifCode {
+	fn from(raw: u32) -> Self {
+		Self {
+			immediate: raw as u16,
+			num: (raw >> 16) as u8,
+			command: ((raw >> 24) & 0x7f) as u8,
+			interrupt: raw & (1 << 31) != 0,
+		}
+	}
+}
+
+/// What the words following the current VIFcode are for.
+#[derive(Clone, Debug)]
+enum State {
+	/// Waiting for a VIFcode.
+	Idle,
+
+	/// Holding a VIFcode which can't run yet.
+	Waiting(VifCode),
+
+	Mask,
+	Row(usize),
+	Column(usize),
+
+	/// Microinstructions, arriving as the lower word then the upper word.
+	Mpg { address: u32, left: u32, lower: Option<u32> },
+
+	/// Quadwords for PATH2.
+	Direct { left: u32, words: Vec<u32> },
+
+	Unpack(Unpack),
+}
+
+pub struct Vif {
+	/// 0 for VIF0, 1 for VIF1.
+	number: usize,
+
+	pub stat: VifStat,
+	pub err: VifErr,
+	pub mark: u32,
+
+	/// Write cycle: `CL` and `WL`.
+	pub cl: u32,
+	pub wl: u32,
+
+	pub mode: u32,
+	pub num: u32,
+	pub mask: u32,
+
+	/// The last VIFcode read.
+	pub code: u32,
+
+	pub itops: u32,
+	pub itop: u32,
+	pub base: u32,
+	pub ofst: u32,
+	pub tops: u32,
+	pub top: u32,
+
+	/// Row and column registers, filled by `STROW` and `STCOL`.
+	pub row: [u32; 4],
+	pub col: [u32; 4],
+
+	fifo: VecDeque<u32>,
+	state: State,
+
+	/// Stall once the current VIFcode is done.
+	stop_requested: bool,
+
+	interrupt: bool,
+
+	vu: Rc<RefCell<VectorUnit>>,
+
+	/// The GIF, reached over PATH2 (VIF1 only).
+	gif: Option<Rc<RefCell<Gif>>>,
+}
+
+impl Vif {
+	fn new(number: usize, vu: Rc<RefCell<VectorUnit>>, gif: Option<Rc<RefCell<Gif>>>) -> Self {
+		Self {
+			number,
+			stat: VifStat::empty(),
+			err: VifErr::empty(),
+			mark: 0,
+			cl: 0,
+			wl: 0,
+			mode: 0,
+			num: 0,
+			mask: 0,
+			code: 0,
+			itops: 0,
+			itop: 0,
+			base: 0,
+			ofst: 0,
+			tops: 0,
+			top: 0,
+			row: [0; 4],
+			col: [0; 4],
+			fifo: VecDeque::with_capacity(VIF1_FIFO_WORDS),
+			state: State::Idle,
+			stop_requested: false,
+			interrupt: false,
+			vu,
+			gif,
+		}
+	}
+
+	/// VIF0, feeding `vu0`.
+	pub fn vif0(vu0: Rc<RefCell<VectorUnit>>) -> Self {
+		Self::new(0, vu0, None)
+	}
+
+	/// VIF1, feeding `vu1` and PATH2 of `gif`.
+	pub fn vif1(vu1: Rc<RefCell<VectorUnit>>, gif: Rc<RefCell<Gif>>) -> Self {
+		Self::new(1, vu1, Some(gif))
+	}
+
+	fn is_vif1(&self) -> bool {
+		self.number == 1
+	}
+
+	fn physical(&self) -> u32 {
+		if self.is_vif1() { VIF1_PHYSICAL } else { VIF0_PHYSICAL }
+	}
+
+	fn fifo_physical(&self) -> u32 {
+		if self.is_vif1() { VIF1_FIFO_PHYSICAL } else { VIF0_FIFO_PHYSICAL }
+	}
+
+	fn fifo_capacity(&self) -> usize {
+		if self.is_vif1() { VIF1_FIFO_WORDS } else { VIF0_FIFO_WORDS }
+	}
+
+	/// Queue a quadword in the FIFO, or return `false` if it is full.
+	pub fn push(&mut self, qword: u128) -> bool {
+		if self.fifo.len() + 4 > self.fifo_capacity() {
+			return false;
+		}
+
+		for word in 0..4 {
+			self.fifo.push_back((qword >> (word * 32)) as u32);
+		}
+
+		self.process();
+		true
+	}
+
+	/// Whether an interrupt has been raised since the last call.
+	pub fn take_interrupt(&mut self) -> bool {
+		mem::take(&mut self.interrupt)
+	}
+
+	/// Work through the FIFO until it runs dry or the VIF stalls.
+	pub fn process(&mut self) {
+		while !self.stat.intersects(VifStat::STALLS) && self.step() {}
+	}
+
+	/// Leave `state` to be resumed later, making no progress.
+	fn wait(&mut self, state: State) -> bool {
+		self.state = state;
+		false
+	}
+
+	/// Process one word (or one waiting VIFcode), returning whether any progress was made.
+	fn step(&mut self) -> bool {
+		let state = mem::replace(&mut self.state, State::Idle);
+
+		if let State::Waiting(code) = state {
+			return self.execute(code);
+		}
+
+		if let State::Unpack(unpack) = state {
+			return self.unpack(unpack);
+		}
+
+		let word = match self.fifo.pop_front() {
+			Some(word) => word,
+			None => return self.wait(state),
+		};
+
+		match state {
+			State::Idle => {
+				self.code = word;
+				self.execute(VifCode::from(word));
+			},
+			State::Mask => {
+				self.mask = word;
+				self.finish();
+			},
+			State::Row(index) | State::Column(index) => {
+				let is_row = matches!(state, State::Row(_));
+				let registers = if is_row { &mut self.row } else { &mut self.col };
+				registers[index] = word;
+
+				match (index, is_row) {
+					(3, _) => self.finish(),
+					(_, true) => self.state = State::Row(index + 1),
+					(_, false) => self.state = State::Column(index + 1),
+				}
+			},
+			State::Mpg { address, left, lower: None } => {
+				self.state = State::Mpg { address, left, lower: Some(word) };
+			},
+			State::Mpg { address, left, lower: Some(lower) } => {
+				self.vu.borrow_mut().write_micro(address, u64::from(lower) | (u64::from(word) << 32));
+				self.num = left - 1;
+
+				if left == 1 {
+					self.finish();
+				} else {
+					self.state = State::Mpg { address: address + 1, left: left - 1, lower: None };
+				}
+			},
+			State::Direct { left, mut words } => {
+				words.push(word);
+
+				if words.len() < 4 {
+					self.state = State::Direct { left, words };
+					return true;
+				}
+
+				let qword = words.iter().rev().fold(0u128, |qword, &word| (qword << 32) | u128::from(word));
+				if let Some(gif) = &self.gif {
+					gif.borrow_mut().push(GifPath::Path2, qword);
+				}
+
+				if left == 1 {
+					self.finish();
+				} else {
+					words.clear();
+					self.state = State::Direct { left: left - 1, words };
+				}
+			},
+			State::Waiting(_) | State::Unpack(_) => unreachable!(),
+		}
+
+		true
+	}
+
+	/// Whether `command` may run now, or must wait for the VU or GIF.
+	fn ready(&self, command: VifCommand) -> bool {
+		let vu_idle = !self.vu.borrow().running;
+		let paths_idle = |paths: &[GifPath]| self.gif.as_ref()
+			.map_or(true, |gif| paths.iter().all(|&path| !gif.borrow().busy(path)));
+
+		match command {
+			VifCommand::FlushE | VifCommand::MsCal | VifCommand::MsCnt | VifCommand::Mpg => vu_idle,
+			VifCommand::Flush | VifCommand::MsCalF => vu_idle && paths_idle(&[GifPath::Path1, GifPath::Path2]),
+			VifCommand::FlushA => vu_idle && paths_idle(&[GifPath::Path1, GifPath::Path2, GifPath::Path3]),
+			_ => true,
+		}
+	}
+
+	/// Start executing `code`, returning whether it could start.
+	fn execute(&mut self, code: VifCode) -> bool {
+		trace!("VIF{}: {:?}", self.number, code);
+
+		if code.command & UNPACK_COMMAND == UNPACK_COMMAND {
+			let mut address = u32::from(code.immediate) & ADDRESS_MASK;
+			if self.is_vif1() && code.immediate & 0x8000 != 0 {
+				address += self.tops;
+			}
+
+			let total = if code.num == 0 { 256 } else { u32::from(code.num) };
+			let unsigned = code.immediate & 0x4000 != 0;
+
+			self.num = total;
+			self.state = State::Unpack(Unpack::new(code.command, address, unsigned, total));
+			return true;
+		}
+
+		let command = match VifCommand::from_u8(code.command) {
+			Some(command) => command,
+			None => {
+				warn!("VIF{}: undefined VIFcode 0x{:08x}", self.number, self.code);
+
+				if !self.err.contains(VifErr::ME1) {
+					self.stat.insert(VifStat::ER1);
+					self.interrupt = true;
+				}
+				return true;
+			},
+		};
+
+		if !self.ready(command) {
+			return self.wait(State::Waiting(code));
+		}
+
+		let vif1_only = matches!(command,
+			VifCommand::Offset | VifCommand::Base | VifCommand::MskPath3 | VifCommand::Flush
+				| VifCommand::FlushA | VifCommand::Direct | VifCommand::DirectHl);
+		if vif1_only && !self.is_vif1() {
+			warn!("VIF0: {:?} is only supported by VIF1", command);
+			self.finish();
+			return true;
+		}
+
+		let immediate = u32::from(code.immediate);
+
+		match command {
+			VifCommand::Nop | VifCommand::FlushE | VifCommand::Flush | VifCommand::FlushA => {},
+			VifCommand::StCycl => {
+				self.cl = immediate & 0xff;
+				self.wl = immediate >> 8;
+			},
+			VifCommand::Offset => {
+				self.ofst = immediate & ADDRESS_MASK;
+				self.stat.remove(VifStat::DBF);
+				self.tops = self.base;
+			},
+			VifCommand::Base => self.base = immediate & ADDRESS_MASK,
+			VifCommand::Itop => self.itops = immediate & ADDRESS_MASK,
+			VifCommand::StMod => self.mode = immediate & 0b11,
+			VifCommand::MskPath3 => if let Some(gif) = &self.gif {
+				gif.borrow_mut().set_path3_masked(immediate & 0x8000 != 0);
+			},
+			VifCommand::Mark => {
+				self.mark = immediate;
+				self.stat.insert(VifStat::MRK);
+			},
+			VifCommand::MsCal | VifCommand::MsCalF => self.start_microprogram(Some(immediate * 8)),
+			VifCommand::MsCnt => self.start_microprogram(None),
+			VifCommand::StMask => {
+				self.state = State::Mask;
+				return true;
+			},
+			VifCommand::StRow => {
+				self.state = State::Row(0);
+				return true;
+			},
+			VifCommand::StCol => {
+				self.state = State::Column(0);
+				return true;
+			},
+			VifCommand::Mpg => {
+				let left = if code.num == 0 { 256 } else { u32::from(code.num) };
+				self.num = left;
+				self.state = State::Mpg { address: immediate, left, lower: None };
+				return true;
+			},
+			// DIRECTHL would also wait for PATH3 to finish an IMAGE transfer,
+			// but PATH2 data queues behind PATH3 anyway.
+			VifCommand::Direct | VifCommand::DirectHl => {
+				let left = if immediate == 0 { 0x1_0000 } else { immediate };
+				self.state = State::Direct { left, words: Vec::with_capacity(4) };
+				return true;
+			},
+		}
+
+		self.finish();
+		true
+	}
+
+	/// Pass `TOP` and `ITOP` to the VU, and start it at `address` (or where it left off).
+	fn start_microprogram(&mut self, address: Option<u32>) {
+		let mut vu = self.vu.borrow_mut();
+
+		self.itop = self.itops;
+		vu.itop = self.itop;
+
+		if self.is_vif1() {
+			self.top = self.tops;
+			vu.top = self.top;
+
+			self.stat.toggle(VifStat::DBF);
+			self.tops = if self.stat.contains(VifStat::DBF) { self.base + self.ofst } else { self.base };
+		}
+
+		match address {
+			Some(address) => vu.start(address),
+			None => vu.resume(),
+		}
+	}
+
+	/// Write quadwords of `unpack` for as long as there is data.
+	fn unpack(&mut self, mut unpack: Unpack) -> bool {
+		let (cl, wl) = (self.cl.max(1), self.wl.max(1));
+		let mut progressed = false;
+
+		while !unpack.done() {
+			let data = if unpack.next_is_fill(cl, wl) {
+				None
+			} else {
+				while unpack.needs_data() {
+					match self.fifo.pop_front() {
+						Some(word) => {
+							unpack.push(word);
+							progressed = true;
+						},
+						None => {
+							self.state = State::Unpack(unpack);
+							return progressed;
+						},
+					}
+				}
+
+				Some(unpack.take_element())
+			};
+
+			let address = unpack.next_address(cl, wl);
+			let cycle = unpack.written % wl;
+			self.write_unpacked(address, cycle, data, unpack.masked);
+
+			unpack.advance();
+			self.num = unpack.total - unpack.written;
+			progressed = true;
+		}
+
+		self.finish();
+		true
+	}
+
+	/// Write one quadword of unpacked `data` (or filling, when `None`), applying `MASK` and `MODE`.
+	fn write_unpacked(&mut self, address: u32, cycle: u32, data: Option<[u32; 4]>, masked: bool) {
+		let mut vu = self.vu.borrow_mut();
+		let mut words = vu.read_qword(address);
+		let row = cycle.min(3) as usize;
+
+		for (field, word) in words.iter_mut().enumerate() {
+			let mask = if masked { (self.mask >> (row * 8 + field * 2)) & 0b11 } else { MASK_DATA };
+
+			match (mask, data) {
+				(MASK_DATA, Some(data)) => *word = match self.mode {
+					STMOD_OFFSET => data[field].wrapping_add(self.row[field]),
+					STMOD_DIFFERENCE => {
+						self.row[field] = data[field].wrapping_add(self.row[field]);
+						self.row[field]
+					},
+					_ => data[field],
+				},
+				(MASK_DATA, None) | (MASK_ROW, _) => *word = self.row[field],
+				(MASK_COLUMN, _) => *word = self.col[row],
+				_ => {},
+			}
+		}
+
+		vu.write_qword(address, words);
+	}
+
+	/// End the current VIFcode, stalling if it or `FBRST` asked to.
+	fn finish(&mut self) {
+		self.state = State::Idle;
+
+		if VifCode::from(self.code).interrupt && !self.err.contains(VifErr::MII) {
+			trace!("VIF{}: interrupt", self.number);
+			self.stat.insert(VifStat::INT | VifStat::VIS);
+			self.interrupt = true;
+		}
+
+		if mem::take(&mut self.stop_requested) {
+			self.stat.insert(VifStat::VSS);
+		}
+	}
+
+	fn reset(&mut self) {
+		trace!("VIF{}: reset", self.number);
+
+		self.fifo.clear();
+		self.state = State::Idle;
+		self.stat = VifStat::empty();
+		self.num = 0;
+		self.stop_requested = false;
+	}
+
+	/// Current value of `STAT`.
+	pub fn read_stat(&self) -> u32 {
+		let vps = match &self.state {
+			State::Idle => 0b00,
+			State::Waiting(_) => 0b10,
+			_ if self.fifo.is_empty() => 0b01,
+			_ => 0b11,
+		};
+
+		let mut stat = self.stat;
+		stat.set(VifStat::VEW, self.vu.borrow().running);
+		stat.set(VifStat::VGW, matches!(self.state, State::Waiting(code)
+			if matches!(VifCommand::from_u8(code.command), Some(VifCommand::Flush) | Some(VifCommand::FlushA) | Some(VifCommand::MsCalF))));
+
+		let fqc = ((self.fifo.len() + 3) / 4) as u32;
+		stat.bits() | vps | (fqc << FQC_SHIFT)
+	}
+
+	/// Read the register at `offset` within the VIF's block.
+	pub fn read(&self, offset: u32) -> u32 {
+		match offset & !0xf {
+			STAT_OFFSET => self.read_stat(),
+			ERR_OFFSET => self.err.bits(),
+			MARK_OFFSET => self.mark,
+			CYCLE_OFFSET => self.cl | (self.wl << 8),
+			MODE_OFFSET => self.mode,
+			NUM_OFFSET => self.num,
+			MASK_OFFSET => self.mask,
+			CODE_OFFSET => self.code,
+			ITOPS_OFFSET => self.itops,
+			BASE_OFFSET => self.base,
+			OFST_OFFSET => self.ofst,
+			TOPS_OFFSET => self.tops,
+			ITOP_OFFSET => self.itop,
+			TOP_OFFSET => self.top,
+			offset if (R0_OFFSET..C0_OFFSET).contains(&offset) => self.row[((offset - R0_OFFSET) / 0x10) as usize],
+			offset if (C0_OFFSET..VIF_SIZE).contains(&offset) => self.col[((offset - C0_OFFSET) / 0x10) as usize],
+			_ => 0,
+		}
+	}
+
+	/// Write the register at `offset` within the VIF's block.
+	pub fn write(&mut self, offset: u32, value: u32) {
+		match offset & !0xf {
+			STAT_OFFSET => if self.is_vif1() {
+				self.stat.set(VifStat::FDR, value & VifStat::FDR.bits() != 0);
+			},
+			FBRST_OFFSET => {
+				let fbrst = VifFbrst::from_bits_truncate(value);
+
+				if fbrst.contains(VifFbrst::RST) {
+					self.reset();
+				}
+
+				if fbrst.contains(VifFbrst::FBK) {
+					self.stat.insert(VifStat::VFS);
+				}
+
+				if fbrst.contains(VifFbrst::STP) {
+					if matches!(self.state, State::Idle) {
+						self.stat.insert(VifStat::VSS);
+					} else {
+						self.stop_requested = true;
+					}
+				}
+
+				if fbrst.contains(VifFbrst::STC) {
+					self.stat.remove(VifStat::STALLS | VifStat::INT);
+					self.process();
+				}
+			},
+			ERR_OFFSET => self.err = VifErr::from_bits_truncate(value),
+			MARK_OFFSET => {
+				self.mark = value & 0xffff;
+				self.stat.remove(VifStat::MRK);
+			},
+			_ => warn!("VIF{}: write to read-only register 0x{:03x}", self.number, offset),
+		}
+	}
+
+	/// Read a quadword bound for memory: VIF1 passes on transfers from the GS.
+	fn read_fifo(&mut self) -> Option<u128> {
+		if !self.stat.contains(VifStat::FDR) {
+			return None;
+		}
+
+		self.gif.as_ref()?.borrow_mut().read_fifo()
+	}
+}
+
+impl Device for Vif {
+	fn read_u32(&mut self, p_addr: u32) -> u32 {
+		self.read(p_addr.wrapping_sub(self.physical()))
+	}
+
+	fn write_u32(&mut self, p_addr: u32, value: u32) {
+		self.write(p_addr.wrapping_sub(self.physical()), value);
+	}
+
+	fn read_u128(&mut self, p_addr: u32) -> u128 {
+		if p_addr & !0xf == self.fifo_physical() {
+			self.read_fifo().unwrap_or(0)
+		} else {
+			u128::from(self.read_u64(p_addr)) | (u128::from(self.read_u64(p_addr + 8)) << 64)
+		}
+	}
+
+	fn write_u128(&mut self, p_addr: u32, value: u128) {
+		if p_addr & !0xf == self.fifo_physical() {
+			if !self.push(value) {
+				warn!("VIF{}: FIFO full, dropping 0x{:032x}", self.number, value);
+			}
+		} else {
+			for word in 0..4 {
+				self.write_u32(p_addr + word * 4, (value >> (word * 32)) as u32);
+			}
+		}
+	}
+}
+
+impl DmaPeripheral for Vif {
+	fn dma_write(&mut self, data: u128) -> bool {
+		self.push(data)
+	}
+
+	fn dma_read(&mut self) -> Option<u128> {
+		self.read_fifo()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		gif::GsSink,
+		isa::vu::{
+			LOWER_NOP,
+			UPPER_E,
+			UPPER_NOP,
+		},
+	};
+
+	#[derive(Default)]
+	struct Recorder {
+		writes: Vec<(u8, u64)>,
+	}
+
+	impl GsSink for Recorder {
+		fn write_register(&mut self, register: u8, value: u64) {
+			self.writes.push((register, value));
+		}
+	}
+
+	type Shared<T> = Rc<RefCell<T>>;
+
+	fn vif1() -> (Vif, Shared<VectorUnit>, Shared<Gif>, Shared<Recorder>) {
+		let vu = Rc::new(RefCell::new(VectorUnit::vu1()));
+		let gif = Rc::new(RefCell::new(Gif::default()));
+		let gs = Rc::new(RefCell::new(Recorder::default()));
+		gif.borrow_mut().connect(gs.clone());
+
+		(Vif::vif1(vu.clone(), gif.clone()), vu, gif, gs)
+	}
+
+	fn code(command: u8, num: u8, immediate: u16) -> u32 {
+		(u32::from(command) << 24) | (u32::from(num) << 16) | u32::from(immediate)
+	}
+
+	/// Feed `words` to the VIF, padded to whole quadwords with `NOP`s.
+	fn feed(vif: &mut Vif, words: &[u32]) {
+		for chunk in words.chunks(4) {
+			let qword = chunk.iter().rev().fold(0u128, |qword, &word| (qword << 32) | u128::from(word));
+			assert!(vif.push(qword));
+		}
+	}
+
+	#[test]
+	fn unpack_v4_32_and_s_16() {
+		let (mut vif, vu, _, _) = vif1();
+
+		feed(&mut vif, &[
+			code(0x6c, 2, 0x10), 1, 2, 3,
+			4, 5, 6, 7,
+			8, code(0x61, 3, 0x20), 0xffff_0005, 0x0007,
+		]);
+
+		assert_eq!(vu.borrow().read_qword(0x10), [1, 2, 3, 4]);
+		assert_eq!(vu.borrow().read_qword(0x11), [5, 6, 7, 8]);
+		assert_eq!(vu.borrow().read_qword(0x20), [5; 4]);
+		assert_eq!(vu.borrow().read_qword(0x21), [u32::MAX; 4]);
+		assert_eq!(vu.borrow().read_qword(0x22), [7; 4]);
+		assert_eq!(vif.read_stat() & VifStat::VPS.bits(), 0);
+	}
+
+	#[test]
+	fn unpack_waits_for_data() {
+		let (mut vif, vu, _, _) = vif1();
+
+		// V3-8 unsigned, with the TOPS offset (zero here): 5 elements take 15 bytes.
+		feed(&mut vif, &[code(0x6a, 5, 0xc000), 0x0403_0201, 0x0807_0605, 0x0c0b_0a09]);
+		assert_eq!(vif.num, 1);
+		assert_eq!(vif.read_stat() & VifStat::VPS.bits(), 0b01);
+
+		feed(&mut vif, &[0x000f_0e0d, code(0x07, 0, 0x1234)]);
+		assert_eq!(vu.borrow().read_qword(2), [7, 8, 9, 0]);
+		assert_eq!(vu.borrow().read_qword(4), [13, 14, 15, 0]);
+		assert_eq!(vif.mark, 0x1234);
+		assert!(vif.stat.contains(VifStat::MRK));
+	}
+
+	#[test]
+	fn mask_and_modes() {
+		let (mut vif, vu, _, _) = vif1();
+		vu.borrow_mut().write_qword(1, [0xaa; 4]);
+
+		feed(&mut vif, &[
+			code(0x01, 0, 0x0404),
+			code(0x30, 0, 0), 10, 20, 30, 40,
+			code(0x31, 0, 0), 100, 101, 102, 103,
+			// Row 0: x data, y row, z column, w protected. Row 1: all data.
+			code(0x20, 0, 0), 0b11_10_01_00,
+			code(0x05, 0, STMOD_OFFSET as u16),
+			code(0x70, 2, 0), 1, 2,
+		]);
+
+		assert_eq!(vu.borrow().read_qword(0), [11, 20, 100, 0]);
+		assert_eq!(vu.borrow().read_qword(1), [12, 22, 32, 42]);
+
+		// Difference mode accumulates into the row registers.
+		feed(&mut vif, &[code(0x05, 0, STMOD_DIFFERENCE as u16), code(0x60, 2, 4), 1, 1]);
+		assert_eq!(vu.borrow().read_qword(5), [12, 22, 32, 42]);
+		assert_eq!(vif.row, [12, 22, 32, 42]);
+	}
+
+	#[test]
+	fn skip_and_fill_cycles() {
+		let (mut vif, vu, _, _) = vif1();
+
+		// CL = 2, WL = 1: every other quadword is skipped.
+		feed(&mut vif, &[code(0x01, 0, 0x0102), code(0x60, 2, 0), 1, 2]);
+		assert_eq!(vu.borrow().read_qword(0), [1; 4]);
+		assert_eq!(vu.borrow().read_qword(1), [0; 4]);
+		assert_eq!(vu.borrow().read_qword(2), [2; 4]);
+
+		// CL = 1, WL = 2: every other quadword is filled from the row registers.
+		feed(&mut vif, &[
+			code(0x30, 0, 0), 9, 9, 9, 9,
+			code(0x01, 0, 0x0201), code(0x60, 4, 0x10), 3, 4,
+		]);
+		assert_eq!(vu.borrow().read_qword(0x10), [3; 4]);
+		assert_eq!(vu.borrow().read_qword(0x11), [9; 4]);
+		assert_eq!(vu.borrow().read_qword(0x12), [4; 4]);
+		assert_eq!(vu.borrow().read_qword(0x13), [9; 4]);
+	}
+
+	#[test]
+	fn v4_5_unpacks_colours() {
+		let (mut vif, vu, _, _) = vif1();
+
+		feed(&mut vif, &[code(0x6f, 2, 0), 0x0000_801f]);
+		assert_eq!(vu.borrow().read_qword(0), [0xf8, 0, 0, 0x80]);
+		assert_eq!(vu.borrow().read_qword(1), [0; 4]);
+	}
+
+	#[test]
+	fn mpg_and_double_buffering() {
+		let (mut vif, vu, _, _) = vif1();
+		vu.borrow_mut().write_micro(3, (u64::from(UPPER_NOP | UPPER_E) << 32) | u64::from(LOWER_NOP));
+
+		feed(&mut vif, &[
+			code(0x4a, 2, 4), 0x1111, 0x2222, 0x3333, 0x4444,
+			code(0x03, 0, 0x100), code(0x02, 0, 0x80), code(0x04, 0, 0x12), code(0x14, 0, 3),
+		]);
+
+		assert_eq!(vu.borrow().micro[4], 0x2222_0000_1111);
+		assert_eq!(vu.borrow().micro[5], 0x4444_0000_3333);
+		assert_eq!((vu.borrow().top, vu.borrow().itop, vu.borrow().pc), (0x100, 0x12, 24));
+
+		// Each start flips to the other buffer, once the program has ended.
+		assert_eq!(vif.tops, 0x180);
+		assert!(vu.borrow_mut().run(10));
+		feed(&mut vif, &[code(0x17, 0, 0), code(0x60, 1, 0x8000), 5]);
+		assert_eq!(vu.borrow().top, 0x180);
+		assert_eq!(vif.tops, 0x100);
+		assert_eq!(vu.borrow().read_qword(0x100), [5; 4]);
+	}
+
+	#[test]
+	fn direct_feeds_path2() {
+		let (mut vif, _, _, gs) = vif1();
+
+		// A GIFtag for one A+D write of FINISH, then the write itself.
+		let tag = 1u128 | (1 << 15) | (1 << 60) | (0xe << 64);
+		let data = (0x61u128 << 64) | 0x99;
+		let words = |qword: u128| (0..4).map(move |word| (qword >> (word * 32)) as u32);
+
+		let mut stream = vec![0, 0, 0, code(0x50, 0, 2)];
+		stream.extend(words(tag).chain(words(data)));
+		feed(&mut vif, &stream);
+
+		assert_eq!(gs.borrow().writes, vec![(0x61, 0x99)]);
+	}
+
+	#[test]
+	fn flusha_waits_for_path3() {
+		let (mut vif, _, gif, _) = vif1();
+
+		// PATH3 is partway through a two-loop packet.
+		gif.borrow_mut().push(GifPath::Path3, 2 | (1 << 15) | (1 << 60) | (0xf << 64));
+		gif.borrow_mut().push(GifPath::Path3, 0);
+
+		feed(&mut vif, &[code(0x13, 0, 0), code(0x07, 0, 1)]);
+		assert_eq!(vif.read_stat() & VifStat::VPS.bits(), 0b10);
+		assert!(vif.read_stat() & VifStat::VGW.bits() != 0);
+		assert_eq!(vif.mark, 0);
+
+		gif.borrow_mut().push(GifPath::Path3, 0);
+		vif.process();
+		assert_eq!(vif.mark, 1);
+	}
+
+	#[test]
+	fn interrupt_bit_stalls_until_cleared() {
+		let (mut vif, _, _, _) = vif1();
+
+		feed(&mut vif, &[code(0x07, 0, 1) | (1 << 31), code(0x07, 0, 2)]);
+		assert!(vif.take_interrupt());
+		assert!(vif.stat.contains(VifStat::INT | VifStat::VIS));
+		assert_eq!(vif.mark, 1);
+
+		vif.write(FBRST_OFFSET, VifFbrst::STC.bits());
+		assert_eq!(vif.mark, 2);
+		assert!(!vif.stat.contains(VifStat::INT));
+
+		// Masked by ERR.MII.
+		vif.write(ERR_OFFSET, VifErr::MII.bits());
+		feed(&mut vif, &[code(0x07, 0, 3) | (1 << 31)]);
+		assert!(!vif.take_interrupt());
+	}
+
+	#[test]
+	fn undefined_codes_stall() {
+		let (mut vif, _, _, _) = vif1();
+
+		feed(&mut vif, &[code(0x08, 0, 0), code(0x07, 0, 1)]);
+		assert!(vif.stat.contains(VifStat::ER1));
+		assert_eq!(vif.mark, 0);
+
+		vif.write(FBRST_OFFSET, VifFbrst::STC.bits());
+		assert_eq!(vif.mark, 1);
+	}
+
+	#[test]
+	fn full_fifo_refuses_data() {
+		let vu = Rc::new(RefCell::new(VectorUnit::vu0()));
+		let mut vif = Vif::vif0(vu);
+
+		// Stalled, the VIF holds on to what it's given until full.
+		vif.write(FBRST_OFFSET, VifFbrst::FBK.bits());
+		for _ in 0..8 {
+			assert!(vif.push(0));
+		}
+		assert!(!vif.push(0));
+		assert_eq!(vif.read_stat() >> FQC_SHIFT, 8);
+
+		vif.write(FBRST_OFFSET, VifFbrst::RST.bits());
+		assert_eq!(vif.read_stat() >> FQC_SHIFT, 0);
+	}
+}
@@ -0,0 +1,216 @@
+//! Decoding of `UNPACK` data into VU quadwords.
+//!
+//! `UNPACK`'s command byte is `011mvnvl`: `vn` gives the number of components
+//! per element (1 to 4) and `vl` their size (32, 16 or 8 bits, or the packed
+//! 16-bit colour of `V4-5`). Elements are packed tightly, lowest bits first,
+//! and the data as a whole is padded to a word.
+
+/// Mask modes, two bits per field in `MASK`.
+pub const MASK_DATA: u32 = 0;
+pub const MASK_ROW: u32 = 1;
+pub const MASK_COLUMN: u32 = 2;
+pub const MASK_PROTECT: u32 = 3;
+
+/// Addition modes selectable in `MODE`.
+pub const STMOD_NORMAL: u32 = 0;
+pub const STMOD_OFFSET: u32 = 1;
+pub const STMOD_DIFFERENCE: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnpackFormat {
+	/// Components per element, less one.
+	pub vn: u8,
+
+	/// Component size: `0` for 32 bits, `1` for 16, `2` for 8, `3` for `V4-5`.
+	pub vl: u8,
+}
+
+impl UnpackFormat {
+	pub fn from_command(command: u8) -> Self {
+		Self {
+			vn: (command >> 2) & 0b11,
+			vl: command & 0b11,
+		}
+	}
+
+	/// Whether this is `V4-5`, the only valid format with `vl` = 3.
+	pub fn is_v4_5(self) -> bool {
+		self.vl == 3
+	}
+
+	/// Bits of data in each element.
+	pub fn element_bits(self) -> u32 {
+		if self.is_v4_5() {
+			16
+		} else {
+			(32 >> self.vl) * (u32::from(self.vn) + 1)
+		}
+	}
+
+	/// Expand an element (in the low bits of `element`) to four fields.
+	///
+	/// Scalars are copied to every field, and `V2` elements to both halves.
+	/// `V3` leaves `W` undefined on hardware: here it is zero.
+	pub fn expand(self, element: u128, unsigned: bool) -> [u32; 4] {
+		if self.is_v4_5() {
+			let colour = element as u32;
+			return [
+				(colour & 0x1f) << 3,
+				((colour >> 5) & 0x1f) << 3,
+				((colour >> 10) & 0x1f) << 3,
+				((colour >> 15) & 1) << 7,
+			];
+		}
+
+		let bits = 32 >> self.vl;
+		let component = |index: u32| {
+			let raw = (element >> (index * bits)) as u32 & (u32::MAX >> (32 - bits));
+
+			if unsigned || bits == 32 {
+				raw
+			} else {
+				(((raw << (32 - bits)) as i32) >> (32 - bits)) as u32
+			}
+		};
+
+		match self.vn {
+			0 => [component(0); 4],
+			1 => [component(0), component(1), component(0), component(1)],
+			2 => [component(0), component(1), component(2), 0],
+			_ => [component(0), component(1), component(2), component(3)],
+		}
+	}
+}
+
+/// Progress through an `UNPACK` command.
+#[derive(Clone, Debug)]
+pub struct Unpack {
+	pub format: UnpackFormat,
+	pub unsigned: bool,
+	pub masked: bool,
+
+	/// First quadword of VU memory written.
+	pub address: u32,
+
+	/// Quadwords to write, and written so far.
+	pub total: u32,
+	pub written: u32,
+
+	/// Data words received but not yet unpacked.
+	pending: u128,
+	pending_bits: u32,
+}
+
+impl Unpack {
+	pub fn new(command: u8, address: u32, unsigned: bool, total: u32) -> Self {
+		Self {
+			format: UnpackFormat::from_command(command),
+			unsigned,
+			masked: command & 0x10 != 0,
+			address,
+			total,
+			written: 0,
+			pending: 0,
+			pending_bits: 0,
+		}
+	}
+
+	pub fn done(&self) -> bool {
+		self.written >= self.total
+	}
+
+	/// Whether another word is needed before the next element can be unpacked.
+	pub fn needs_data(&self) -> bool {
+		self.pending_bits < self.format.element_bits()
+	}
+
+	pub fn push(&mut self, word: u32) {
+		self.pending |= u128::from(word) << self.pending_bits;
+		self.pending_bits += 32;
+	}
+
+	/// Take the next element, once enough data is pending.
+	pub fn take_element(&mut self) -> [u32; 4] {
+		let bits = self.format.element_bits();
+		let element = self.pending & (u128::MAX >> (128 - bits));
+
+		self.pending = self.pending.checked_shr(bits).unwrap_or(0);
+		self.pending_bits -= bits;
+
+		self.format.expand(element, self.unsigned)
+	}
+
+	/// Move on to the next quadword, discarding the padding after the last element.
+	pub fn advance(&mut self) {
+		self.written += 1;
+
+		if self.done() {
+			self.pending = 0;
+			self.pending_bits = 0;
+		}
+	}
+
+	/// VU address of the next quadword, for a write cycle of `cl` and `wl`.
+	///
+	/// With `cl` ≥ `wl`, each `wl` writes are followed by skipping `cl - wl`
+	/// quadwords. Otherwise writes are consecutive, and the last `wl - cl` of
+	/// each cycle are filling writes which take no data.
+	pub fn next_address(&self, cl: u32, wl: u32) -> u32 {
+		if cl >= wl {
+			self.address + (self.written / wl) * cl + self.written % wl
+		} else {
+			self.address + self.written
+		}
+	}
+
+	/// Whether the next quadword is a filling write.
+	pub fn next_is_fill(&self, cl: u32, wl: u32) -> bool {
+		cl < wl && self.written % wl >= cl
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn format(vn: u8, vl: u8) -> UnpackFormat {
+		UnpackFormat { vn, vl }
+	}
+
+	#[test]
+	fn element_sizes() {
+		assert_eq!(format(0, 0).element_bits(), 32);
+		assert_eq!(format(0, 2).element_bits(), 8);
+		assert_eq!(format(1, 1).element_bits(), 32);
+		assert_eq!(format(2, 1).element_bits(), 48);
+		assert_eq!(format(2, 2).element_bits(), 24);
+		assert_eq!(format(3, 0).element_bits(), 128);
+		assert_eq!(format(3, 3).element_bits(), 16);
+	}
+
+	#[test]
+	fn expansion() {
+		assert_eq!(format(0, 0).expand(7, false), [7; 4]);
+		assert_eq!(format(0, 1).expand(0x8000, false), [0xffff_8000; 4]);
+		assert_eq!(format(0, 1).expand(0x8000, true), [0x8000; 4]);
+		assert_eq!(format(1, 2).expand(0x02_ff, false), [u32::MAX, 2, u32::MAX, 2]);
+		assert_eq!(format(2, 0).expand(0x3_0000_0002_0000_0001, false), [1, 2, 3, 0]);
+		assert_eq!(format(3, 2).expand(0x04_03_02_81, true), [0x81, 2, 3, 4]);
+		assert_eq!(format(3, 3).expand(0b1_00011_00010_00001, false), [8, 16, 24, 0x80]);
+	}
+
+	#[test]
+	fn skipping_and_filling_addresses() {
+		let mut unpack = Unpack::new(0x60, 10, false, 6);
+		let mut addresses = vec![];
+		while !unpack.done() {
+			addresses.push(unpack.next_address(4, 2));
+			unpack.advance();
+		}
+		assert_eq!(addresses, vec![10, 11, 14, 15, 18, 19]);
+
+		let unpack = Unpack::new(0x60, 0, false, 6);
+		let fills = (0..6).map(|written| Unpack { written, ..unpack.clone() }.next_is_fill(2, 3)).collect::<Vec<_>>();
+		assert_eq!(fills, vec![false, false, true, false, false, true]);
+	}
+}
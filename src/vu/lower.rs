@@ -0,0 +1,315 @@
+//! Lower instructions: integer, load/store, flag, branch, FDIV and EFU operations.
+
+use crate::{
+	core::fpu::{
+		self,
+		FpuFlags,
+		FpuResult,
+	},
+	isa::vu::*,
+};
+use super::{
+	pipeline::Write,
+	upper::{
+		field_bit,
+		fields,
+	},
+	status,
+	VectorUnit,
+};
+
+/// Value of a float, for the EFU's transcendental functions.
+fn to_host(value: u32) -> f64 {
+	let value = fpu::flush(value);
+	let exponent = ((value >> 23) & 0xff) as i32;
+	if exponent == 0 {
+		return 0.0;
+	}
+
+	let magnitude = f64::from((value & 0x7f_ffff) | 0x80_0000) * 2f64.powi(exponent - 150);
+	if value & fpu::SIGN_BIT != 0 { -magnitude } else { magnitude }
+}
+
+/// Truncate a result of the EFU to a float, clamping it to ±Fmax.
+fn from_host(value: f64) -> u32 {
+	let sign = if value.is_sign_negative() { fpu::SIGN_BIT } else { 0 };
+	let magnitude = value.abs();
+
+	if magnitude.is_nan() || magnitude >= 2f64.powi(129) {
+		return sign | fpu::FMAX;
+	}
+
+	let bits = magnitude.to_bits();
+	let exponent = ((bits >> 52) & 0x7ff) as i32 - 1023 + 127;
+	if exponent <= 0 {
+		return sign;
+	}
+
+	sign | ((exponent as u32) << 23) | ((bits >> 29) as u32 & 0x7f_ffff)
+}
+
+/// Advance the random number generator, a 23-bit LFSR under an exponent of `1.0`.
+fn advance_random(r: u32) -> u32 {
+	let feedback = ((r >> 4) ^ (r >> 22)) & 1;
+	(((r << 1) | feedback) & 0x7f_ffff) | super::upper::ONE
+}
+
+impl VectorUnit {
+	pub(super) fn set_vi(&mut self, index: u8, value: u16) {
+		if index != 0 {
+			self.vi[index as usize] = value;
+		}
+	}
+
+	/// Write the `dest` fields of `value` to `VF[index]`.
+	pub(super) fn set_vf(&mut self, index: u8, dest: u8, value: [u32; 4]) {
+		if index == 0 {
+			return;
+		}
+
+		for field in fields(dest) {
+			self.vf[index as usize][field] = value[field];
+		}
+	}
+
+	/// Queue `value` for the `dest` fields of `VF[ft]`, after the instruction's latency.
+	fn write_ft(&mut self, instruction: u32, value: [u32; 4]) {
+		self.queue(lower_latency(instruction), Write::Vf {
+			index: instruction.get_ft(),
+			dest: instruction.get_dest(),
+			value,
+		});
+	}
+
+	/// The single field `field` of `VF[index]`.
+	fn vf_field(&self, index: u8, field: u8) -> u32 {
+		self.vf[index as usize][field as usize]
+	}
+
+	/// Quadword address `VI[index] + offset`.
+	fn address(&self, index: u8, offset: i16) -> u32 {
+		u32::from(self.vi[index as usize].wrapping_add(offset as u16))
+	}
+
+	fn load(&mut self, instruction: u32, address: u32) {
+		let value = self.read_qword(address);
+		self.write_ft(instruction, value);
+	}
+
+	fn store(&mut self, instruction: u32, address: u32) {
+		let dest = instruction.get_dest();
+		let mut words = self.read_qword(address);
+		let value = self.vf[instruction.get_fs() as usize];
+
+		for field in fields(dest) {
+			words[field] = value[field];
+		}
+
+		self.write_qword(address, words);
+	}
+
+	/// `ILW` and `ILWR`: load the low 16 bits of a field (the highest set in `dest`).
+	fn load_integer(&mut self, instruction: u32, address: u32) {
+		let dest = instruction.get_dest();
+		let words = self.read_qword(address);
+
+		if let Some(field) = (0..4).find(|&field| dest & field_bit(field) != 0) {
+			self.set_vi(instruction.get_it(), words[field] as u16);
+		}
+	}
+
+	/// `ISW` and `ISWR`: store `VI[it]`, zero-extended, to the `dest` fields.
+	fn store_integer(&mut self, instruction: u32, address: u32) {
+		let dest = instruction.get_dest();
+		let value = u32::from(self.vi[instruction.get_it() as usize]);
+		let mut words = self.read_qword(address);
+
+		for field in fields(dest) {
+			words[field] = value;
+		}
+
+		self.write_qword(address, words);
+	}
+
+	/// Take a branch after the next instruction, to `imm11` instructions past it.
+	fn branch(&mut self, instruction: u32, taken: bool) {
+		if taken {
+			let offset = i32::from(instruction.get_imm11()) * 8;
+			self.branch = Some(self.pc.wrapping_add(8).wrapping_add(offset as u32));
+		}
+	}
+
+	/// Address of the instruction after the delay slot, in doublewords.
+	fn link(&self) -> u16 {
+		((self.pc + 16) / 8) as u16
+	}
+
+	/// Record the outcome of an FDIV operation in Q and the status flags.
+	pub(super) fn divide(&mut self, result: FpuResult) {
+		self.q = result.value;
+
+		let mut flags = 0;
+		if result.flags.contains(FpuFlags::INVALID) {
+			flags |= status::I;
+		}
+		if result.flags.contains(FpuFlags::DIVIDE_BY_ZERO) {
+			flags |= status::D;
+		}
+
+		self.status = (self.status & !(status::I | status::D)) | flags | (flags << status::STICKY_SHIFT);
+	}
+
+	fn fdiv(&mut self, instruction: u32, result: FpuResult) {
+		self.queue(lower_latency(instruction), Write::Q(result));
+	}
+
+	fn efu(&mut self, instruction: u32, value: f64) {
+		self.queue(lower_latency(instruction), Write::P(from_host(value)));
+	}
+
+	pub(super) fn execute_lower(&mut self, instruction: u32) {
+		use LowerOpcode as O;
+		use LowerFunction as F;
+		use LowerSpecial as S;
+
+		let i = instruction;
+		let (it, is, id) = (i.get_it(), i.get_is(), i.get_id());
+		let registers = self.vi;
+		let vi = |index: u8| registers[index as usize];
+		let fs = self.vf[i.get_fs() as usize];
+		let fsf = self.vf_field(i.get_fs(), i.get_fsf());
+
+		match Lower::decode(i) {
+			Some(Lower::Opcode(opcode)) => match opcode {
+				O::Lq => self.load(i, self.address(is, i.get_imm11())),
+				O::Sq => self.store(i, self.address(it, i.get_imm11())),
+				O::Ilw => self.load_integer(i, self.address(is, i.get_imm11())),
+				O::Isw => self.store_integer(i, self.address(is, i.get_imm11())),
+				O::IaddIu => self.set_vi(it, vi(is).wrapping_add(i.get_imm15())),
+				O::IsubIu => self.set_vi(it, vi(is).wrapping_sub(i.get_imm15())),
+				O::FcEq => self.set_vi(1, (self.clip == i.get_imm24()) as u16),
+				O::FcSet => self.clip = i.get_imm24(),
+				O::FcAnd => self.set_vi(1, (self.clip & i.get_imm24() != 0) as u16),
+				O::FcOr => self.set_vi(1, ((self.clip | i.get_imm24()) == 0xff_ffff) as u16),
+				O::FsEq => self.set_vi(it, (self.status & 0xfff == i.get_imm12()) as u16),
+				O::FsSet => self.status = (self.status & 0x3f) | (i.get_imm12() & 0xfc0),
+				O::FsAnd => self.set_vi(it, self.status & i.get_imm12()),
+				O::FsOr => self.set_vi(it, (self.status | i.get_imm12()) & 0xfff),
+				O::FmEq => self.set_vi(it, (self.mac == vi(is)) as u16),
+				O::FmAnd => self.set_vi(it, self.mac & vi(is)),
+				O::FmOr => self.set_vi(it, self.mac | vi(is)),
+				O::FcGet => self.set_vi(it, (self.clip & 0xfff) as u16),
+				O::B => self.branch(i, true),
+				O::Bal => {
+					self.set_vi(it, self.link());
+					self.branch(i, true);
+				},
+				O::Jr => self.branch = Some(u32::from(vi(is)) * 8),
+				O::JalR => {
+					self.branch = Some(u32::from(vi(is)) * 8);
+					self.set_vi(it, self.link());
+				},
+				O::IbEq => self.branch(i, vi(it) == vi(is)),
+				O::IbNe => self.branch(i, vi(it) != vi(is)),
+				O::IbLtz => self.branch(i, (vi(is) as i16) < 0),
+				O::IbGtz => self.branch(i, (vi(is) as i16) > 0),
+				O::IbLez => self.branch(i, (vi(is) as i16) <= 0),
+				O::IbGez => self.branch(i, (vi(is) as i16) >= 0),
+				O::Operate => unreachable!(),
+			},
+			Some(Lower::Function(function)) => match function {
+				F::Iadd => self.set_vi(id, vi(is).wrapping_add(vi(it))),
+				F::Isub => self.set_vi(id, vi(is).wrapping_sub(vi(it))),
+				F::Iaddi => self.set_vi(it, vi(is).wrapping_add(i.get_imm5() as u16)),
+				F::Iand => self.set_vi(id, vi(is) & vi(it)),
+				F::Ior => self.set_vi(id, vi(is) | vi(it)),
+				F::Special => unreachable!(),
+			},
+			Some(Lower::Special(special)) => match special {
+				S::Move => self.write_ft(i, fs),
+				S::Mr32 => self.write_ft(i, [fs[1], fs[2], fs[3], fs[0]]),
+				S::Lqi => {
+					self.load(i, u32::from(vi(is)));
+					self.set_vi(is, vi(is).wrapping_add(1));
+				},
+				S::Sqi => {
+					self.store(i, u32::from(vi(it)));
+					self.set_vi(it, vi(it).wrapping_add(1));
+				},
+				S::Lqd => {
+					self.set_vi(is, vi(is).wrapping_sub(1));
+					self.load(i, u32::from(vi(is).wrapping_sub(1)));
+				},
+				S::Sqd => {
+					self.set_vi(it, vi(it).wrapping_sub(1));
+					self.store(i, u32::from(vi(it).wrapping_sub(1)));
+				},
+				S::Div => self.fdiv(i, fpu::div(fsf, self.vf_field(i.get_ft(), i.get_ftf()))),
+				S::Sqrt => self.fdiv(i, fpu::sqrt(self.vf_field(i.get_ft(), i.get_ftf()))),
+				S::Rsqrt => self.fdiv(i, fpu::rsqrt(fsf, self.vf_field(i.get_ft(), i.get_ftf()))),
+				// Waiting is handled when the instruction issues.
+				S::WaitQ | S::WaitP => {},
+				S::Mtir => self.set_vi(it, fsf as u16),
+				S::Mfir => self.write_ft(i, [i32::from(vi(is) as i16) as u32; 4]),
+				S::Ilwr => self.load_integer(i, u32::from(vi(is))),
+				S::Iswr => self.store_integer(i, u32::from(vi(is))),
+				S::Rnext => {
+					self.r = advance_random(self.r);
+					self.write_ft(i, [self.r; 4]);
+				},
+				S::Rget => self.write_ft(i, [self.r; 4]),
+				S::Rinit => self.r = (fsf & 0x7f_ffff) | super::upper::ONE,
+				S::Rxor => self.r = ((self.r ^ fsf) & 0x7f_ffff) | super::upper::ONE,
+				S::Mfp => self.write_ft(i, [self.p; 4]),
+				S::Xtop => self.set_vi(it, self.top as u16),
+				S::Xitop => self.set_vi(it, self.itop as u16),
+				S::XgKick => self.xgkick(u32::from(vi(is))),
+				S::Esadd | S::Ersadd | S::Eleng | S::Erleng => {
+					let (x, y, z) = (to_host(fs[0]), to_host(fs[1]), to_host(fs[2]));
+					let square = x * x + y * y + z * z;
+
+					self.efu(i, match special {
+						S::Esadd => square,
+						S::Ersadd => 1.0 / square,
+						S::Eleng => square.sqrt(),
+						_ => 1.0 / square.sqrt(),
+					});
+				},
+				S::EatanXy => self.efu(i, (to_host(fs[1]) / to_host(fs[0])).atan()),
+				S::EatanXz => self.efu(i, (to_host(fs[2]) / to_host(fs[0])).atan()),
+				S::Esum => self.efu(i, fs.iter().map(|&value| to_host(value)).sum()),
+				S::Esqrt => self.efu(i, to_host(fsf).abs().sqrt()),
+				S::Ersqrt => self.efu(i, 1.0 / to_host(fsf).abs().sqrt()),
+				S::Ercpr => self.efu(i, 1.0 / to_host(fsf)),
+				S::Esin => self.efu(i, to_host(fsf).sin()),
+				S::Eatan => self.efu(i, to_host(fsf).atan()),
+				S::Eexp => self.efu(i, (-to_host(fsf)).exp()),
+			},
+			None => warn!("VU: undefined lower instruction 0x{:08x}", instruction),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn host_conversions() {
+		assert_eq!(to_host(0xc040_0000), -3.0);
+		assert_eq!(to_host(fpu::FMAX), 2f64.powi(129) * (1.0 - 2f64.powi(-24)));
+		assert_eq!(from_host(0.1), 0x3dcc_cccc);
+		assert_eq!(from_host(-1e300), fpu::SIGN_BIT | fpu::FMAX);
+		assert_eq!(from_host(1e-40), 0);
+	}
+
+	#[test]
+	fn random_numbers_stay_in_one_to_two() {
+		let mut r = 0x3f80_0001;
+		for _ in 0..100 {
+			r = advance_random(r);
+			assert_eq!(r & 0xff80_0000, super::super::upper::ONE);
+		}
+		assert_ne!(r, 0x3f80_0001);
+	}
+}
@@ -0,0 +1,811 @@
+//! The vector units, VU0 and VU1.
+//!
+//! Each VU has its own micro (instruction) memory and data memory, both of
+//! which are mapped into the EE's physical address space: VU0 has 4 KiB of
+//! each, and VU1 16 KiB. Addresses within each memory wrap around, save
+//! that VU0's data memory maps VU1's registers from quadword `0x400`.
+//!
+//! In micro mode, a VU executes a pair of instructions each cycle from micro
+//! memory until one with the `E` bit set (and the pair after it) has run.
+//! Register hazards are tracked with a scoreboard of when each register's
+//! value becomes available, and the VU stalls until the registers an
+//! instruction reads are ready. Results themselves are only written back
+//! once their latency has passed, so anything read without a stall (Q by
+//! FMAC instructions, and the flags) may still hold an older value.
+
+mod lower;
+mod pipeline;
+pub mod upper;
+
+use crate::{
+	core::cop2::{
+		ControlRegister,
+		VI_COUNT,
+	},
+	gif::{
+		DataFormat,
+		Gif,
+		GifPath,
+		GifTag,
+	},
+	isa::vu::*,
+	memory::{
+		bus::Device,
+		constants::{
+			VU0_CODE_PHYSICAL,
+			VU0_DATA_PHYSICAL,
+			VU1_CODE_PHYSICAL,
+			VU1_DATA_PHYSICAL,
+		},
+	},
+};
+use enum_primitive::FromPrimitive;
+use pipeline::{
+	PendingWrite,
+	Write,
+};
+use std::{
+	cell::RefCell,
+	cmp::Reverse,
+	collections::BinaryHeap,
+	mem,
+	rc::Rc,
+};
+use upper::{
+	Target,
+	UpperResult,
+};
+
+/// Size of each of VU0's memories, in bytes.
+pub const VU0_MEMORY_SIZE: u32 = 0x1000;
+
+/// Size of each of VU1's memories, in bytes.
+pub const VU1_MEMORY_SIZE: u32 = 0x4000;
+
+/// Quadword address in VU0's data memory from which VU1's registers are
+/// mapped: VF0--VF31, then the COP2 control registers (in the `x` field).
+pub const VU1_REGISTERS_ADDRESS: u32 = 0x400;
+
+/// Quadwords of VU1's registers mapped: 32 VF, then 32 control registers.
+const VU1_REGISTERS_SIZE: u32 = 0x40;
+
+/// Bits of the status flag register.
+pub mod status {
+	pub const Z: u16 = 1 << 0;
+	pub const S: u16 = 1 << 1;
+	pub const U: u16 = 1 << 2;
+	pub const O: u16 = 1 << 3;
+	pub const I: u16 = 1 << 4;
+	pub const D: u16 = 1 << 5;
+
+	/// Each of the above has a sticky copy this far up.
+	pub const STICKY_SHIFT: u32 = 6;
+}
+
+pub struct VectorUnit {
+	/// Micro memory, as 64-bit instructions.
+	pub micro: Vec<u64>,
+
+	/// Data memory, as 32-bit words (four to a quadword).
+	pub data: Vec<u32>,
+
+	/// Values passed on by the VIF at each microprogram start, read by `XTOP` and `XITOP`.
+	pub top: u32,
+	pub itop: u32,
+
+	/// Floating-point registers, as `x`, `y`, `z`, `w`. VF0 reads `(0, 0, 0, 1)`.
+	pub vf: [[u32; 4]; 32],
+
+	/// Integer registers. VI0 reads `0`.
+	pub vi: [u16; 16],
+
+	pub acc: [u32; 4],
+	pub q: u32,
+	pub p: u32,
+	pub i: u32,
+	pub r: u32,
+
+	pub mac: u16,
+	pub status: u16,
+	pub clip: u32,
+
+	/// Address of the next microinstruction, in bytes.
+	pub pc: u32,
+
+	/// Whether a microprogram is executing.
+	pub running: bool,
+
+	/// Set when the last microprogram was stopped by `FBRST`.
+	pub force_broken: bool,
+
+	/// Cycles executed, including stalls.
+	pub cycles: u64,
+
+	/// Cycles passed by [`cycle`](#method.cycle), which stalls may put `cycles` ahead of.
+	clock: u64,
+
+	/// Target of a branch taken by the previous instruction.
+	branch: Option<u32>,
+
+	/// Set when the previous instruction had the `E` bit.
+	ending: bool,
+
+	/// Cycle at which each resource (see `isa::vu::RESOURCE_COUNT`) is next ready.
+	ready: [u64; RESOURCE_COUNT],
+
+	/// Results not yet written back.
+	pending: BinaryHeap<Reverse<PendingWrite>>,
+
+	/// Number of writes queued so far, to keep those due together in order.
+	queued: u64,
+
+	gif: Option<Rc<RefCell<Gif>>>,
+
+	/// VU1, whose registers VU0 maps into its data memory.
+	vu1: Option<Rc<RefCell<VectorUnit>>>,
+
+	number: usize,
+	code_physical: u32,
+	data_physical: u32,
+}
+
+impl VectorUnit {
+	fn new(number: usize, size: u32, code_physical: u32, data_physical: u32) -> Self {
+		let mut vf = [[0; 4]; 32];
+		vf[0][3] = upper::ONE;
+
+		Self {
+			micro: vec![0; (size / 8) as usize],
+			data: vec![0; (size / 4) as usize],
+			top: 0,
+			itop: 0,
+			vf,
+			vi: [0; 16],
+			acc: [0; 4],
+			q: 0,
+			p: 0,
+			i: 0,
+			r: 0,
+			mac: 0,
+			status: 0,
+			clip: 0,
+			pc: 0,
+			running: false,
+			force_broken: false,
+			cycles: 0,
+			clock: 0,
+			branch: None,
+			ending: false,
+			ready: [0; RESOURCE_COUNT],
+			pending: BinaryHeap::new(),
+			queued: 0,
+			gif: None,
+			vu1: None,
+			number,
+			code_physical,
+			data_physical,
+		}
+	}
+
+	pub fn vu0() -> Self {
+		Self::new(0, VU0_MEMORY_SIZE, VU0_CODE_PHYSICAL, VU0_DATA_PHYSICAL)
+	}
+
+	pub fn vu1() -> Self {
+		Self::new(1, VU1_MEMORY_SIZE, VU1_CODE_PHYSICAL, VU1_DATA_PHYSICAL)
+	}
+
+	/// Send `XGKICK`ed packets to `gif`, over PATH1.
+	pub fn connect(&mut self, gif: Rc<RefCell<Gif>>) {
+		self.gif = Some(gif);
+	}
+
+	/// Map `vu1`'s registers into data memory from
+	/// [`VU1_REGISTERS_ADDRESS`](constant.VU1_REGISTERS_ADDRESS.html), as VU0 does.
+	pub fn map_vu1(&mut self, vu1: Rc<RefCell<VectorUnit>>) {
+		self.vu1 = Some(vu1);
+	}
+
+	/// Size of each memory, in bytes.
+	pub fn memory_size(&self) -> u32 {
+		(self.data.len() * 4) as u32
+	}
+
+	/// Read quadword `address` (in quadwords) of data memory.
+	pub fn read_qword(&self, address: u32) -> [u32; 4] {
+		if let Some((vu1, index)) = self.vu1_register(address) {
+			let vu1 = vu1.borrow();
+
+			return match index.checked_sub(32) {
+				Some(control) => [vu1.read_control(control), 0, 0, 0],
+				None => vu1.vf[index as usize],
+			};
+		}
+
+		let base = self.qword_index(address);
+		let mut words = [0; 4];
+		words.copy_from_slice(&self.data[base..base + 4]);
+		words
+	}
+
+	/// Write quadword `address` (in quadwords) of data memory.
+	pub fn write_qword(&mut self, address: u32, words: [u32; 4]) {
+		if let Some((vu1, index)) = self.vu1_register(address) {
+			let mut vu1 = vu1.borrow_mut();
+
+			match index.checked_sub(32) {
+				Some(control) => vu1.write_control(control, words[0]),
+				None => vu1.set_vf(index, DEST_X | DEST_Y | DEST_Z | DEST_W, words),
+			}
+			return;
+		}
+
+		let base = self.qword_index(address);
+		self.data[base..base + 4].copy_from_slice(&words);
+	}
+
+	/// VU1 and the index of its register mapped at quadword `address`, if any.
+	fn vu1_register(&self, address: u32) -> Option<(&Rc<RefCell<VectorUnit>>, u8)> {
+		let index = address.wrapping_sub(VU1_REGISTERS_ADDRESS);
+
+		match &self.vu1 {
+			Some(vu1) if index < VU1_REGISTERS_SIZE => Some((vu1, index as u8)),
+			_ => None,
+		}
+	}
+
+	fn qword_index(&self, address: u32) -> usize {
+		(address as usize * 4) % self.data.len()
+	}
+
+	/// Write instruction `address` (in doublewords) of micro memory.
+	pub fn write_micro(&mut self, address: u32, instruction: u64) {
+		let index = address as usize % self.micro.len();
+		self.micro[index] = instruction;
+	}
+
+	/// Start the microprogram at `address` (in bytes).
+	pub fn start(&mut self, address: u32) {
+		self.pc = address % self.memory_size();
+		self.resume();
+	}
+
+	/// Continue execution from the current PC.
+	pub fn resume(&mut self) {
+		trace!("VU{}: microprogram at 0x{:04x}", self.number, self.pc);
+
+		self.branch = None;
+		self.ending = false;
+		self.running = true;
+		self.force_broken = false;
+		self.clock = self.cycles;
+	}
+
+	/// Stop the running microprogram, as `FBRST`'s `FB` bit does.
+	pub fn force_break(&mut self) {
+		if self.running {
+			trace!("VU{}: forced break at 0x{:04x}", self.number, self.pc);
+			self.running = false;
+			self.force_broken = true;
+			self.flush();
+		}
+	}
+
+	/// Stop any microprogram and clear the flags, as `FBRST`'s `RS` bit does.
+	pub fn reset(&mut self) {
+		trace!("VU{}: reset", self.number);
+		self.running = false;
+		self.force_broken = false;
+		self.branch = None;
+		self.ending = false;
+		self.pending.clear();
+		self.mac = 0;
+		self.status = 0;
+		self.clip = 0;
+	}
+
+	/// Read VI register or special register `index`, numbered as for `CFC2`.
+	///
+	/// Registers which the VU does not hold read as `0`.
+	pub fn read_control(&self, index: u8) -> u32 {
+		if index < VI_COUNT {
+			return u32::from(self.vi[index as usize]);
+		}
+
+		match ControlRegister::from_u8(index) {
+			Some(ControlRegister::Status) => u32::from(self.status),
+			Some(ControlRegister::Mac) => u32::from(self.mac),
+			Some(ControlRegister::Clipping) => self.clip,
+			Some(ControlRegister::R) => self.r,
+			Some(ControlRegister::I) => self.i,
+			Some(ControlRegister::Q) => self.q,
+			Some(ControlRegister::Tpc) => self.pc / 8,
+			_ => 0,
+		}
+	}
+
+	/// Write VI register or special register `index`, numbered as for `CTC2`.
+	///
+	/// Read-only bits and registers are left untouched.
+	pub fn write_control(&mut self, index: u8, value: u32) {
+		if index < VI_COUNT {
+			self.set_vi(index, value as u16);
+			return;
+		}
+
+		match ControlRegister::from_u8(index) {
+			Some(ControlRegister::Status) => {
+				let sticky = 0x3f << status::STICKY_SHIFT;
+				self.status = (self.status & !sticky) | (value as u16 & sticky);
+			},
+			Some(ControlRegister::Clipping) => self.clip = value & 0xff_ffff,
+			Some(ControlRegister::R) => self.r = (value & 0x7f_ffff) | upper::ONE,
+			Some(ControlRegister::I) => self.i = value,
+			Some(ControlRegister::Q) => self.q = value,
+			_ => {},
+		}
+	}
+
+	/// Advance by one cycle alongside the EE, issuing an instruction unless still stalled.
+	pub fn cycle(&mut self) {
+		if !self.running {
+			return;
+		}
+
+		if self.cycles <= self.clock {
+			self.step();
+		}
+		self.clock += 1;
+	}
+
+	/// Run until the microprogram ends, or for at most `cycles` cycles.
+	///
+	/// Returns whether the microprogram ended.
+	pub fn run(&mut self, cycles: u64) -> bool {
+		let end = self.cycles + cycles;
+
+		while self.running && self.cycles < end {
+			self.step();
+		}
+
+		!self.running
+	}
+
+	/// Issue one instruction pair, stalling first if it reads registers not yet ready.
+	pub fn step(&mut self) {
+		let pair = self.micro[(self.pc / 8) as usize % self.micro.len()];
+		let (upper, lower) = ((pair >> 32) as u32, pair as u32);
+		let loads_i = upper & UPPER_I != 0;
+
+		let upper_resources = upper_resources(upper);
+		let lower_resources = if loads_i { Default::default() } else { lower_resources(lower) };
+
+		// Only FDIV and EFU instructions wait for Q and P: FMAC instructions take them as they stand.
+		let ready = self.ready_at((upper_resources.read & !REG_Q) | lower_resources.read);
+		if ready > self.cycles {
+			trace!("VU{}: stalled {} cycles at 0x{:04x}", self.number, ready - self.cycles, self.pc);
+			self.cycles = ready;
+		}
+		self.retire(self.cycles);
+
+		if upper & (UPPER_D | UPPER_T) != 0 {
+			trace!("VU{}: ignoring debug bits of 0x{:08x}", self.number, upper);
+		}
+
+		// Both halves read the registers as they were at issue.
+		if loads_i {
+			self.i = lower;
+		}
+		let result = upper::execute(self, upper);
+		let branch = self.branch.take();
+		if !loads_i {
+			self.execute_lower(lower);
+		}
+		self.queue(FMAC_LATENCY, Write::Upper(result));
+
+		self.mark_written(upper_resources.write, FMAC_LATENCY);
+		self.mark_written(lower_resources.write, lower_latency(lower));
+
+		self.cycles += 1;
+		self.pc = branch.unwrap_or(self.pc + 8) % self.memory_size();
+
+		// The instruction after one with the E bit is the last.
+		if mem::replace(&mut self.ending, upper & UPPER_E != 0) {
+			trace!("VU{}: microprogram ended after {} cycles", self.number, self.cycles);
+			self.running = false;
+			self.ending = false;
+			self.flush();
+		}
+	}
+
+	/// Perform a COP2 macro instruction's operation at once, as VU0 does for the EE.
+	pub fn execute_macro(&mut self, instruction: u32) {
+		match Macro::decode(instruction) {
+			Some(Macro::Upper(upper)) => {
+				let result = upper::execute(self, upper);
+				self.write_back(result);
+			},
+			Some(Macro::Lower(lower)) => {
+				self.execute_lower(lower);
+				// The EE interlocks on results itself.
+				self.flush();
+			},
+			None => warn!("VU{}: undefined macro instruction 0x{:08x}", self.number, instruction),
+		}
+	}
+
+	/// Queue `write` to become visible `latency` cycles from now.
+	fn queue(&mut self, latency: u64, write: Write) {
+		if let Write::Upper(UpperResult::None) = write {
+			return;
+		}
+
+		self.queued += 1;
+		self.pending.push(Reverse(PendingWrite {
+			time: self.cycles + latency,
+			sequence: self.queued,
+			write,
+		}));
+	}
+
+	/// Write back every result due by cycle `time`.
+	fn retire(&mut self, time: u64) {
+		while self.pending.peek().map_or(false, |Reverse(pending)| pending.time <= time) {
+			let Reverse(pending) = self.pending.pop().unwrap();
+
+			match pending.write {
+				Write::Upper(result) => self.write_back(result),
+				Write::Vf { index, dest, value } => self.set_vf(index, dest, value),
+				Write::Q(result) => self.divide(result),
+				Write::P(value) => self.p = value,
+			}
+		}
+	}
+
+	/// Write back every result still in flight, as the pipeline drains.
+	fn flush(&mut self) {
+		self.retire(u64::MAX);
+	}
+
+	/// The cycle at which every resource in `mask` is ready.
+	fn ready_at(&self, mask: u64) -> u64 {
+		let mask = mask & !REG_FLAGS;
+
+		(0..RESOURCE_COUNT)
+			.filter(|&resource| mask & (1 << resource) != 0)
+			.map(|resource| self.ready[resource])
+			.max()
+			.unwrap_or(0)
+	}
+
+	fn mark_written(&mut self, mask: u64, latency: u64) {
+		for resource in (0..RESOURCE_COUNT).filter(|&resource| mask & (1 << resource) != 0) {
+			self.ready[resource] = self.cycles + latency;
+		}
+	}
+
+	/// Write back an upper instruction's results, after its lower instruction has run.
+	///
+	/// Should both write the same VF register, the upper instruction wins.
+	fn write_back(&mut self, result: UpperResult) {
+		match result {
+			UpperResult::None => {},
+			UpperResult::Vector { target, dest, value, mac } => {
+				match target {
+					Target::Vf(index) => self.set_vf(index, dest, value),
+					Target::Acc => for field in upper::fields(dest) {
+						self.acc[field] = value[field];
+					},
+				}
+
+				if let Some(mac) = mac {
+					self.set_mac(mac);
+				}
+			},
+			UpperResult::Clip(clip) => self.clip = clip,
+		}
+	}
+
+	/// Replace the MAC flags, updating the status flags to match.
+	fn set_mac(&mut self, mac: u16) {
+		self.mac = mac;
+
+		let mut flags = 0;
+		for (nibble, &flag) in [status::Z, status::S, status::U, status::O].iter().enumerate() {
+			if (mac >> (nibble * 4)) & 0xf != 0 {
+				flags |= flag;
+			}
+		}
+
+		let kept = self.status & !(status::Z | status::S | status::U | status::O);
+		self.status = kept | flags | (flags << status::STICKY_SHIFT);
+	}
+
+	/// Send the GIF packet at quadword `address` over PATH1.
+	fn xgkick(&mut self, address: u32) {
+		let gif = match &self.gif {
+			Some(gif) => gif.clone(),
+			None => {
+				warn!("VU{}: XGKICK with no GIF attached", self.number);
+				return;
+			},
+		};
+
+		let qword = |vu: &Self, address: u32| vu.read_qword(address).iter().rev()
+			.fold(0u128, |qword, &word| (qword << 32) | u128::from(word));
+
+		// A packet without an end would otherwise loop forever.
+		let limit = self.memory_size() / 16;
+		let mut address = address;
+		let mut sent = 0;
+
+		while sent < limit {
+			let raw = qword(self, address);
+			let tag = GifTag::from(raw);
+			let registers = u32::from(tag.nloop) * u32::from(tag.nreg);
+			let length = 1 + match tag.format {
+				DataFormat::Packed => registers,
+				DataFormat::Reglist => (registers + 1) / 2,
+				DataFormat::Image => u32::from(tag.nloop),
+			};
+
+			let mut gif = gif.borrow_mut();
+			for offset in 0..length.min(limit - sent) {
+				gif.push(GifPath::Path1, qword(self, address + offset));
+			}
+
+			address += length;
+			sent += length;
+
+			if tag.eop {
+				break;
+			}
+		}
+	}
+}
+
+impl Device for VectorUnit {
+	fn read_u32(&mut self, p_addr: u32) -> u32 {
+		let size = self.memory_size();
+
+		if p_addr.wrapping_sub(self.code_physical) < size {
+			let offset = p_addr - self.code_physical;
+			(self.micro[(offset / 8) as usize] >> ((offset & 0b100) * 8)) as u32
+		} else {
+			self.data[((p_addr - self.data_physical) % size / 4) as usize]
+		}
+	}
+
+	fn write_u32(&mut self, p_addr: u32, value: u32) {
+		let size = self.memory_size();
+
+		if p_addr.wrapping_sub(self.code_physical) < size {
+			let offset = p_addr - self.code_physical;
+			let shift = (offset & 0b100) * 8;
+			let instruction = &mut self.micro[(offset / 8) as usize];
+			*instruction = (*instruction & !(0xffff_ffff << shift)) | (u64::from(value) << shift);
+		} else {
+			self.data[((p_addr - self.data_physical) % size / 4) as usize] = value;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::gif::GsSink;
+
+	const ONE: u32 = 0x3f80_0000;
+	const TWO: u32 = 0x4000_0000;
+
+	/// Load `pairs` of upper and lower instructions at address 0, and run them.
+	fn run(vu: &mut VectorUnit, pairs: &[(u32, u32)]) {
+		for (address, &(upper, lower)) in pairs.iter().enumerate() {
+			vu.write_micro(address as u32, (u64::from(upper) << 32) | u64::from(lower));
+		}
+
+		vu.start(0);
+		assert!(vu.run(1000), "microprogram did not end");
+	}
+
+	fn float(value: f32) -> u32 {
+		value.to_bits()
+	}
+
+	#[test]
+	fn loops_and_ends_after_the_e_bit() {
+		let mut vu = VectorUnit::vu1();
+
+		run(&mut vu, &[
+			(UPPER_NOP, build_lower(LowerOpcode::IaddIu as u8, 0, 1, 0, 3)),
+			(UPPER_NOP, build_lower(LowerOpcode::IaddIu as u8, 0, 2, 0, 0)),
+			// Sum VI1 down to 1 into VI2.
+			(UPPER_NOP, build_lower_function(LowerFunction::Iadd as u8, 0, 1, 2, 2)),
+			(UPPER_NOP, build_lower_function(LowerFunction::Iaddi as u8, 0, 1, 1, 0x1f)),
+			(UPPER_NOP, build_lower(LowerOpcode::IbNe as u8, 0, 0, 1, (-3i16) as u16)),
+			(UPPER_NOP, LOWER_NOP),
+			(UPPER_NOP | UPPER_E, LOWER_NOP),
+			// The delay slot of the E bit runs, but nothing after it.
+			(UPPER_NOP, build_lower(LowerOpcode::IaddIu as u8, 0, 3, 0, 1)),
+			(UPPER_NOP, build_lower(LowerOpcode::IaddIu as u8, 0, 4, 0, 1)),
+		]);
+
+		assert_eq!(&vu.vi[1..5], &[0, 6, 1, 0]);
+		assert!(!vu.running);
+		assert_eq!(vu.pc, 8 * 8);
+	}
+
+	#[test]
+	fn vu0_maps_vu1_registers() {
+		let vu1 = Rc::new(RefCell::new(VectorUnit::vu1()));
+		let mut vu0 = VectorUnit::vu0();
+		vu0.map_vu1(vu1.clone());
+		vu1.borrow_mut().vf[2] = [ONE, TWO, ONE, TWO];
+		vu1.borrow_mut().q = 0x3faa_aaaa;
+
+		run(&mut vu0, &[
+			(UPPER_NOP, build_lower(LowerOpcode::IaddIu as u8, 0, 1, 0, 0x400)),
+			(UPPER_NOP, build_lower(LowerOpcode::IaddIu as u8, 0, 2, 0, 9)),
+			(UPPER_NOP, build_lower(LowerOpcode::Lq as u8, 0xf, 1, 1, 2)),
+			(UPPER_NOP, build_lower(LowerOpcode::Ilw as u8, DEST_X, 3, 1, 0x20 + ControlRegister::Q as u16)),
+			(UPPER_NOP, build_lower(LowerOpcode::Sq as u8, 0xf, 1, 1, 5)),
+			// Past the registers is VU0's own memory, wrapped.
+			(UPPER_NOP, build_lower(LowerOpcode::Sq as u8, 0xf, 1, 1, 0x40)),
+			(UPPER_NOP | UPPER_E, build_lower(LowerOpcode::Isw as u8, DEST_X, 2, 1, 0x23)),
+			(UPPER_NOP, LOWER_NOP),
+		]);
+
+		let vu1 = vu1.borrow();
+		assert_eq!(vu0.vf[1], [ONE, TWO, ONE, TWO]);
+		assert_eq!(vu0.vi[3], 0xaaaa);
+		assert_eq!(vu1.vf[5], [ONE, TWO, ONE, TWO]);
+		assert_eq!(vu1.vi[3], 9);
+		assert_eq!(vu0.read_qword(0x40), [ONE, TWO, ONE, TWO]);
+		assert_eq!(vu0.data.iter().filter(|&&word| word != 0).count(), 4);
+	}
+
+	#[test]
+	fn pairs_read_registers_at_issue_and_stall_on_hazards() {
+		let mut vu = VectorUnit::vu1();
+		vu.vf[1] = [ONE, TWO, float(3.0), float(4.0)];
+		vu.vf[2] = [ONE; 4];
+		vu.write_qword(0, [float(5.0); 4]);
+
+		run(&mut vu, &[
+			// VF1 is loaded while the upper instruction reads its old value.
+			(build_upper(UpperFunction::Add as u8, 0xf, 2, 1, 3), build_lower(LowerOpcode::Lq as u8, 0xf, 1, 0, 0)),
+			// Stalls until VF3 is written back.
+			(build_upper(UpperFunction::Mul as u8, 0xf, 3, 3, 4), LOWER_NOP),
+			(UPPER_NOP | UPPER_E, build_lower_special(LowerSpecial::Div as u8, 0b01_00, 3, 4)),
+			(UPPER_NOP, build_lower_special(LowerSpecial::WaitQ as u8, 0, 0, 0)),
+		]);
+
+		assert_eq!(vu.vf[1], [float(5.0); 4]);
+		assert_eq!(vu.vf[3], [TWO, float(3.0), float(4.0), float(5.0)]);
+		assert_eq!(vu.vf[4], [float(4.0), float(9.0), float(16.0), float(25.0)]);
+		assert_eq!(vu.q, 0x3faa_aaaa);
+		assert_eq!(vu.mac, 0);
+		assert_eq!(vu.cycles, 4 + 4 + 1 + (7 - 1) + 1);
+	}
+
+	#[test]
+	fn instructions_stall_on_their_own_destination() {
+		let mut vu = VectorUnit::vu1();
+		vu.vf[1] = [ONE; 4];
+		vu.vf[2] = [ONE; 4];
+
+		run(&mut vu, &[
+			(build_upper(UpperFunction::Add as u8, 0xf, 2, 1, 3), LOWER_NOP),
+			// Reads and writes VF3, so stalls until the first ADD is written back.
+			(build_upper(UpperFunction::Add as u8, 0xf, 2, 3, 3) | UPPER_E, LOWER_NOP),
+			(UPPER_NOP, LOWER_NOP),
+		]);
+
+		assert_eq!(vu.vf[3], [float(3.0); 4]);
+		assert_eq!(vu.cycles, 4 + 1 + 1);
+	}
+
+	#[test]
+	fn flags_are_read_stale_until_written_back() {
+		let mut vu = VectorUnit::vu1();
+		let fmand = |it, is| build_lower(LowerOpcode::FmAnd as u8, 0, it, is, 0);
+
+		run(&mut vu, &[
+			(UPPER_NOP, build_lower(LowerOpcode::IaddIu as u8, 0, 2, 0, 0xf)),
+			// Every field is zero, but the Z flags only show four cycles later.
+			(build_upper(UpperFunction::Sub as u8, 0xf, 0, 0, 1), fmand(3, 2)),
+			(UPPER_NOP, LOWER_NOP),
+			(UPPER_NOP, LOWER_NOP),
+			(UPPER_NOP, fmand(4, 2)),
+			(UPPER_NOP, fmand(5, 2)),
+			(UPPER_NOP | UPPER_E, LOWER_NOP),
+			(UPPER_NOP, LOWER_NOP),
+		]);
+
+		assert_eq!(&vu.vi[3..6], &[0, 0, 0xf]);
+		assert_eq!(vu.status, status::Z | (status::Z << status::STICKY_SHIFT));
+	}
+
+	#[test]
+	fn fmac_instructions_read_q_without_waiting() {
+		let mut vu = VectorUnit::vu1();
+		vu.q = TWO;
+		let mul_q = |fd| build_upper(UpperFunction::MulQ as u8, DEST_W, 0, 0, fd);
+
+		run(&mut vu, &[
+			(UPPER_NOP, build_lower_special(LowerSpecial::Div as u8, 0b11_11, 0, 0)),
+			(mul_q(2), LOWER_NOP),
+			(mul_q(3), build_lower_special(LowerSpecial::WaitQ as u8, 0, 0, 0)),
+			(UPPER_NOP | UPPER_E, LOWER_NOP),
+			(UPPER_NOP, LOWER_NOP),
+		]);
+
+		assert_eq!(vu.vf[2][3], TWO);
+		assert_eq!(vu.vf[3][3], ONE);
+		assert_eq!(vu.q, ONE);
+	}
+
+	#[test]
+	fn results_still_in_flight_finish_as_the_program_ends() {
+		let mut vu = VectorUnit::vu1();
+		vu.write_qword(0, [ONE; 4]);
+
+		vu.write_micro(0, (u64::from(UPPER_NOP | UPPER_E) << 32) | u64::from(build_lower(LowerOpcode::Lq as u8, 0xf, 1, 0, 0)));
+		vu.write_micro(1, (u64::from(UPPER_NOP) << 32) | u64::from(LOWER_NOP));
+		vu.start(0);
+
+		vu.step();
+		assert_eq!(vu.vf[1], [0; 4]);
+		vu.step();
+		assert!(!vu.running);
+		assert_eq!(vu.vf[1], [ONE; 4]);
+	}
+
+	#[derive(Default)]
+	struct Recorder {
+		writes: Vec<(u8, u64)>,
+	}
+
+	impl GsSink for Recorder {
+		fn write_register(&mut self, register: u8, value: u64) {
+			self.writes.push((register, value));
+		}
+	}
+
+	#[test]
+	fn loads_i_and_kicks_packets_to_the_gif() {
+		let gs = Rc::new(RefCell::new(Recorder::default()));
+		let gif = Rc::new(RefCell::new(Gif::default()));
+		gif.borrow_mut().connect(gs.clone());
+
+		let mut vu = VectorUnit::vu1();
+		vu.connect(gif);
+		vu.vi[5] = 0x10;
+
+		// A PACKED A+D packet writing PRIM.
+		vu.write_qword(0x10, [0x8001, 0x1000_0000, 0xe, 0]);
+		vu.write_qword(0x11, [6, 0, 0, 0]);
+
+		run(&mut vu, &[
+			(build_upper(UpperFunction::AddI as u8, DEST_X, 0, 0, 1) | UPPER_I, TWO),
+			(UPPER_NOP | UPPER_E, build_lower_special(LowerSpecial::XgKick as u8, 0, 0, 5)),
+			(UPPER_NOP, LOWER_NOP),
+		]);
+
+		assert_eq!(vu.vf[1], [TWO, 0, 0, 0]);
+		assert_eq!(vu.i, TWO);
+		assert_eq!(gs.borrow().writes, vec![(0, 6)]);
+	}
+
+	#[test]
+	fn memories_are_mapped_and_wrap() {
+		let mut vu = VectorUnit::vu0();
+
+		vu.write_u32(VU0_CODE_PHYSICAL + 12, 0x1234_5678);
+		assert_eq!(vu.micro[1], 0x1234_5678 << 32);
+
+		vu.write_qword(0x100 + 2, [1, 2, 3, 4]);
+		assert_eq!(vu.read_u32(VU0_DATA_PHYSICAL + 0x28), 3);
+		assert_eq!(vu.read_qword(2), [1, 2, 3, 4]);
+	}
+}
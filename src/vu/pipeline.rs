@@ -0,0 +1,55 @@
+//! Delayed write-back of VU results.
+//!
+//! Each result is queued with the cycle it becomes visible, much as the EE
+//! queues a [`LiveAction`](../../core/pipeline/struct.LiveAction.html), and
+//! is written back once the VU reaches that cycle. Registers an instruction
+//! reads are interlocked by the scoreboard, but Q for FMAC instructions and
+//! the flags are not: reading them early sees their old values.
+
+use crate::core::fpu::FpuResult;
+use std::cmp::Ordering;
+use super::upper::UpperResult;
+
+/// A result waiting to be written back.
+pub enum Write {
+	/// An upper instruction's register and flag results.
+	Upper(UpperResult),
+
+	/// A lower instruction's result, to the `dest` fields of `VF[index]`.
+	Vf { index: u8, dest: u8, value: [u32; 4] },
+
+	/// An FDIV result, to Q and the `I` and `D` status flags.
+	Q(FpuResult),
+
+	/// An EFU result.
+	P(u32),
+}
+
+/// Write paired with the cycle at which it becomes visible.
+///
+/// Writes due in the same cycle are made in the order they were queued.
+pub struct PendingWrite {
+	pub time: u64,
+	pub sequence: u64,
+	pub write: Write,
+}
+
+impl Eq for PendingWrite {}
+
+impl PartialEq for PendingWrite {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl PartialOrd for PendingWrite {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for PendingWrite {
+	fn cmp(&self, other: &Self) -> Ordering {
+		(self.time, self.sequence).cmp(&(other.time, other.sequence))
+	}
+}
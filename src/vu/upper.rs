@@ -0,0 +1,364 @@
+//! Upper (FMAC) instructions.
+//!
+//! Upper instructions are evaluated before the lower instruction they are
+//! paired with, but their results are held in an [`UpperResult`](enum.UpperResult.html)
+//! and only written back afterwards: both halves see the registers as they
+//! were when the pair was issued.
+
+use crate::{
+	core::fpu::{
+		self,
+		FpuFlags,
+		FpuResult,
+	},
+	isa::vu::*,
+};
+use super::VectorUnit;
+
+/// `1.0`, as VF0's `w` field.
+pub const ONE: u32 = 0x3f80_0000;
+
+/// Where an upper instruction writes its vector.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+	Vf(u8),
+	Acc,
+}
+
+/// The effects of an upper instruction, yet to be written back.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpperResult {
+	None,
+
+	/// The `dest` fields of `value` are written to `target`. With `mac`,
+	/// the MAC flags are replaced and the status flags updated.
+	Vector {
+		target: Target,
+		dest: u8,
+		value: [u32; 4],
+		mac: Option<u16>,
+	},
+
+	/// The new clipping flags.
+	Clip(u32),
+}
+
+/// Bit of `field` (`0` for `x`) in a field mask, or in each nibble of the MAC flags.
+#[inline]
+pub fn field_bit(field: usize) -> u8 {
+	DEST_X >> field
+}
+
+/// The fields set in a field mask, from `x` (`0`) to `w` (`3`).
+pub fn fields(dest: u8) -> impl Iterator<Item = usize> {
+	(0..4).filter(move |&field| dest & field_bit(field) != 0)
+}
+
+/// MAC flag bits for one field's result: zero, sign, underflow and overflow.
+fn mac_bits(result: FpuResult, field: usize) -> u16 {
+	let bit = u16::from(field_bit(field));
+	let mut mac = 0;
+
+	if result.value & !fpu::SIGN_BIT == 0 {
+		mac |= bit;
+	}
+	if result.value & fpu::SIGN_BIT != 0 {
+		mac |= bit << 4;
+	}
+	if result.flags.contains(FpuFlags::UNDERFLOW) {
+		mac |= bit << 8;
+	}
+	if result.flags.contains(FpuFlags::OVERFLOW) {
+		mac |= bit << 12;
+	}
+
+	mac
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operation {
+	Add,
+	Sub,
+	Mul,
+	Madd,
+	Msub,
+	Max,
+	Mini,
+}
+
+impl Operation {
+	fn apply(self, acc: u32, fs: u32, ft: u32) -> FpuResult {
+		match self {
+			Operation::Add => fpu::add(fs, ft),
+			Operation::Sub => fpu::sub(fs, ft),
+			Operation::Mul => fpu::mul(fs, ft),
+			Operation::Madd => multiply_accumulate(acc, fs, ft, fpu::add),
+			Operation::Msub => multiply_accumulate(acc, fs, ft, fpu::sub),
+			Operation::Max => fpu::max(fs, ft).into(),
+			Operation::Mini => fpu::min(fs, ft).into(),
+		}
+	}
+
+	fn sets_flags(self) -> bool {
+		!matches!(self, Operation::Max | Operation::Mini)
+	}
+}
+
+/// `acc ± fs × ft`, rounding the product before accumulating it.
+fn multiply_accumulate(acc: u32, fs: u32, ft: u32, accumulate: fn(u32, u32) -> FpuResult) -> FpuResult {
+	let product = fpu::mul(fs, ft);
+	let mut result = accumulate(acc, product.value);
+	result.flags |= product.flags & FpuFlags::OVERFLOW;
+	result
+}
+
+/// Where the second operand of an arithmetic instruction comes from.
+#[derive(Clone, Copy, Debug)]
+enum Operand {
+	Vector,
+	Broadcast,
+	Q,
+	I,
+}
+
+fn operand(vu: &VectorUnit, instruction: u32, operand: Operand) -> [u32; 4] {
+	let ft = vu.vf[instruction.get_ft() as usize];
+
+	match operand {
+		Operand::Vector => ft,
+		Operand::Broadcast => [ft[instruction.get_bc() as usize]; 4],
+		Operand::Q => [vu.q; 4],
+		Operand::I => [vu.i; 4],
+	}
+}
+
+fn arithmetic(vu: &VectorUnit, instruction: u32, operation: Operation, source: Operand, target: Target) -> UpperResult {
+	let dest = instruction.get_dest();
+	let fs = vu.vf[instruction.get_fs() as usize];
+	let ft = operand(vu, instruction, source);
+
+	let mut value = [0; 4];
+	let mut mac = 0;
+	for field in fields(dest) {
+		let result = operation.apply(vu.acc[field], fs[field], ft[field]);
+		value[field] = result.value;
+		mac |= mac_bits(result, field);
+	}
+
+	UpperResult::Vector {
+		target,
+		dest,
+		value,
+		mac: if operation.sets_flags() { Some(mac) } else { None },
+	}
+}
+
+/// `OPMULA` and `OPMSUB`: the outer product's terms, `fs.yzx × ft.zxy`.
+fn outer_product(vu: &VectorUnit, instruction: u32, operation: Operation, target: Target) -> UpperResult {
+	let dest = instruction.get_dest();
+	let fs = vu.vf[instruction.get_fs() as usize];
+	let ft = vu.vf[instruction.get_ft() as usize];
+
+	let mut value = [0; 4];
+	let mut mac = 0;
+	for field in fields(dest & !DEST_W) {
+		let (a, b) = ((field + 1) % 3, (field + 2) % 3);
+		let result = operation.apply(vu.acc[field], fs[a], ft[b]);
+		value[field] = result.value;
+		mac |= mac_bits(result, field);
+	}
+
+	UpperResult::Vector {
+		target,
+		dest,
+		value,
+		mac: Some(mac),
+	}
+}
+
+/// `ITOF`, `FTOI` and `ABS`, which write `ft` without touching the flags.
+fn convert(vu: &VectorUnit, instruction: u32, conversion: impl Fn(u32) -> u32) -> UpperResult {
+	let fs = vu.vf[instruction.get_fs() as usize];
+
+	UpperResult::Vector {
+		target: Target::Vf(instruction.get_ft()),
+		dest: instruction.get_dest(),
+		value: [conversion(fs[0]), conversion(fs[1]), conversion(fs[2]), conversion(fs[3])],
+		mac: None,
+	}
+}
+
+/// `2^power`, as a float.
+fn power_of_two(power: i32) -> u32 {
+	((127 + power) as u32) << 23
+}
+
+fn itof(value: u32, fraction_bits: i32) -> u32 {
+	fpu::mul(fpu::from_i32(value as i32), power_of_two(-fraction_bits)).value
+}
+
+fn ftoi(value: u32, fraction_bits: i32) -> u32 {
+	fpu::to_i32(fpu::mul(value, power_of_two(fraction_bits)).value) as u32
+}
+
+/// `CLIP`: judge `fs.xyz` against `±|ft.w|`, shifting the result into the clipping flags.
+fn clip(vu: &VectorUnit, instruction: u32) -> UpperResult {
+	let fs = vu.vf[instruction.get_fs() as usize];
+	let w = fpu::abs(vu.vf[instruction.get_ft() as usize][3]);
+
+	let mut judgement = 0;
+	for (field, &value) in fs[..3].iter().enumerate() {
+		if fpu::compare(value, w) == std::cmp::Ordering::Greater {
+			judgement |= 1 << (field * 2);
+		}
+		if fpu::compare(value, fpu::neg(w)) == std::cmp::Ordering::Less {
+			judgement |= 2 << (field * 2);
+		}
+	}
+
+	UpperResult::Clip(((vu.clip << 6) | judgement) & 0xff_ffff)
+}
+
+/// Evaluate an upper instruction against the current registers.
+pub fn execute(vu: &VectorUnit, instruction: u32) -> UpperResult {
+	use Operand::*;
+	use Operation::*;
+	use UpperFunction as F;
+	use UpperSpecial as S;
+
+	let fd = Target::Vf(instruction.get_fd());
+
+	match Upper::decode(instruction) {
+		Some(Upper::Function(function)) => match function {
+			F::AddBc => arithmetic(vu, instruction, Add, Broadcast, fd),
+			F::SubBc => arithmetic(vu, instruction, Sub, Broadcast, fd),
+			F::MaddBc => arithmetic(vu, instruction, Madd, Broadcast, fd),
+			F::MsubBc => arithmetic(vu, instruction, Msub, Broadcast, fd),
+			F::MaxBc => arithmetic(vu, instruction, Max, Broadcast, fd),
+			F::MiniBc => arithmetic(vu, instruction, Mini, Broadcast, fd),
+			F::MulBc => arithmetic(vu, instruction, Mul, Broadcast, fd),
+			F::MulQ => arithmetic(vu, instruction, Mul, Q, fd),
+			F::MaxI => arithmetic(vu, instruction, Max, I, fd),
+			F::MulI => arithmetic(vu, instruction, Mul, I, fd),
+			F::MiniI => arithmetic(vu, instruction, Mini, I, fd),
+			F::AddQ => arithmetic(vu, instruction, Add, Q, fd),
+			F::MaddQ => arithmetic(vu, instruction, Madd, Q, fd),
+			F::AddI => arithmetic(vu, instruction, Add, I, fd),
+			F::MaddI => arithmetic(vu, instruction, Madd, I, fd),
+			F::SubQ => arithmetic(vu, instruction, Sub, Q, fd),
+			F::MsubQ => arithmetic(vu, instruction, Msub, Q, fd),
+			F::SubI => arithmetic(vu, instruction, Sub, I, fd),
+			F::MsubI => arithmetic(vu, instruction, Msub, I, fd),
+			F::Add => arithmetic(vu, instruction, Add, Vector, fd),
+			F::Madd => arithmetic(vu, instruction, Madd, Vector, fd),
+			F::Mul => arithmetic(vu, instruction, Mul, Vector, fd),
+			F::Max => arithmetic(vu, instruction, Max, Vector, fd),
+			F::Sub => arithmetic(vu, instruction, Sub, Vector, fd),
+			F::Msub => arithmetic(vu, instruction, Msub, Vector, fd),
+			F::OpMsub => outer_product(vu, instruction, Msub, fd),
+			F::Mini => arithmetic(vu, instruction, Mini, Vector, fd),
+			F::Special => unreachable!(),
+		},
+		Some(Upper::Special(special)) => match special {
+			S::AddaBc => arithmetic(vu, instruction, Add, Broadcast, Target::Acc),
+			S::SubaBc => arithmetic(vu, instruction, Sub, Broadcast, Target::Acc),
+			S::MaddaBc => arithmetic(vu, instruction, Madd, Broadcast, Target::Acc),
+			S::MsubaBc => arithmetic(vu, instruction, Msub, Broadcast, Target::Acc),
+			S::Itof0 => convert(vu, instruction, |value| itof(value, 0)),
+			S::Itof4 => convert(vu, instruction, |value| itof(value, 4)),
+			S::Itof12 => convert(vu, instruction, |value| itof(value, 12)),
+			S::Itof15 => convert(vu, instruction, |value| itof(value, 15)),
+			S::Ftoi0 => convert(vu, instruction, |value| ftoi(value, 0)),
+			S::Ftoi4 => convert(vu, instruction, |value| ftoi(value, 4)),
+			S::Ftoi12 => convert(vu, instruction, |value| ftoi(value, 12)),
+			S::Ftoi15 => convert(vu, instruction, |value| ftoi(value, 15)),
+			S::MulaBc => arithmetic(vu, instruction, Mul, Broadcast, Target::Acc),
+			S::MulaQ => arithmetic(vu, instruction, Mul, Q, Target::Acc),
+			S::Abs => convert(vu, instruction, fpu::abs),
+			S::MulaI => arithmetic(vu, instruction, Mul, I, Target::Acc),
+			S::Clip => clip(vu, instruction),
+			S::AddaQ => arithmetic(vu, instruction, Add, Q, Target::Acc),
+			S::MaddaQ => arithmetic(vu, instruction, Madd, Q, Target::Acc),
+			S::AddaI => arithmetic(vu, instruction, Add, I, Target::Acc),
+			S::MaddaI => arithmetic(vu, instruction, Madd, I, Target::Acc),
+			S::SubaQ => arithmetic(vu, instruction, Sub, Q, Target::Acc),
+			S::MsubaQ => arithmetic(vu, instruction, Msub, Q, Target::Acc),
+			S::SubaI => arithmetic(vu, instruction, Sub, I, Target::Acc),
+			S::MsubaI => arithmetic(vu, instruction, Msub, I, Target::Acc),
+			S::Adda => arithmetic(vu, instruction, Add, Vector, Target::Acc),
+			S::Madda => arithmetic(vu, instruction, Madd, Vector, Target::Acc),
+			S::Mula => arithmetic(vu, instruction, Mul, Vector, Target::Acc),
+			S::Suba => arithmetic(vu, instruction, Sub, Vector, Target::Acc),
+			S::Msuba => arithmetic(vu, instruction, Msub, Vector, Target::Acc),
+			S::OpMula => outer_product(vu, instruction, Mul, Target::Acc),
+			S::Nop => UpperResult::None,
+		},
+		None => {
+			warn!("VU: undefined upper instruction 0x{:08x}", instruction);
+			UpperResult::None
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TWO: u32 = 0x4000_0000;
+	const THREE: u32 = 0x4040_0000;
+	const HALF: u32 = 0x3f00_0000;
+
+	fn vu() -> VectorUnit {
+		let mut vu = VectorUnit::vu1();
+		vu.vf[1] = [ONE, TWO, THREE, HALF];
+		vu.vf[2] = [TWO, TWO, ONE, fpu::neg(THREE)];
+		vu
+	}
+
+	#[test]
+	fn broadcast_and_flags() {
+		let vu = vu();
+
+		// ADDw.xyz VF3, VF1, VF2: adding -3.
+		let result = execute(&vu, build_upper(UpperFunction::AddBc as u8 | 3, 0b1110, 2, 1, 3));
+		assert_eq!(result, UpperResult::Vector {
+			target: Target::Vf(3),
+			dest: 0b1110,
+			value: [0xc000_0000, 0xbf80_0000, 0, 0],
+			// Negative x and y, zero z.
+			mac: Some(0b1100_0000 | 0b0010),
+		});
+	}
+
+	#[test]
+	fn outer_product_and_conversions() {
+		let mut vu = vu();
+		vu.acc = [ONE, ONE, ONE, 0];
+
+		// OPMSUB.xyz VF3, VF1, VF2: ACC - VF1.yzx × VF2.zxy.
+		let result = execute(&vu, build_upper(UpperFunction::OpMsub as u8, 0b1110, 2, 1, 3));
+		match result {
+			UpperResult::Vector { value, .. } => assert_eq!(value[..3], [fpu::neg(ONE), fpu::neg(0x40a0_0000), fpu::neg(ONE)]),
+			_ => panic!("{:?}", result),
+		}
+
+		// FTOI4 then ITOF4 round-trips 0.5.
+		vu.vf[4] = [HALF; 4];
+		let ftoi4 = execute(&vu, build_upper_special(UpperSpecial::Ftoi4 as u8, 0xf, 5, 4));
+		assert_eq!(ftoi4, UpperResult::Vector { target: Target::Vf(5), dest: 0xf, value: [8; 4], mac: None });
+
+		vu.vf[5] = [8; 4];
+		let itof4 = execute(&vu, build_upper_special(UpperSpecial::Itof4 as u8, 0xf, 6, 5));
+		assert_eq!(itof4, UpperResult::Vector { target: Target::Vf(6), dest: 0xf, value: [HALF; 4], mac: None });
+	}
+
+	#[test]
+	fn clipping_judgements_shift_in() {
+		let mut vu = vu();
+		vu.clip = 0b11_1111;
+		vu.vf[3] = [0x4040_0000, fpu::neg(THREE), HALF, TWO];
+
+		// CLIPw.xyz VF3, VF3: x > +2, y < -2, z within.
+		assert_eq!(execute(&vu, build_upper_special(UpperSpecial::Clip as u8, 0b1110, 3, 3)), UpperResult::Clip(0b1111_1100_1001));
+	}
+}